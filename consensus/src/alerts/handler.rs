@@ -0,0 +1,281 @@
+use super::*;
+
+/// How long to wait for a requested alert before allowing it to be requested again.
+const ALERT_REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+/// A timeout requested by the [`AlertHandler`] with [`AlertAction::ScheduleTimeout`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AlertTimeout<H: Hasher> {
+    /// The alert with the given hash was requested and did not necessarily arrive yet.
+    AlertRequest(H::Hash),
+}
+
+/// An input of the [`AlertHandler`].
+pub enum AlertEvent<H: Hasher, D: Data, MK: MultiKeychain> {
+    /// A message received from the network.
+    Message(NetworkMessage<H, D, MK>),
+    /// A fork discovered locally, which we should alert the others about.
+    OwnAlert(Alert<H, D, MK::Signature>),
+    /// The RMC for the alert with the given hash completed.
+    Multisigned(Multisigned<H::Hash, MK>),
+    /// A timeout requested with [`AlertAction::ScheduleTimeout`] expired.
+    Timeout(AlertTimeout<H>),
+}
+
+/// An output of the [`AlertHandler`]. The caller is required to perform all the actions, in
+/// the order in which they were returned.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AlertAction<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
+    /// Send the message to the recipient via the network.
+    Send(AlertMessage<H, D, S, MS>, Recipient),
+    /// Pass the notification to the units.
+    Notify(ForkingNotification<H, D, S>),
+    /// Start an RMC for the alert with the given hash. The resulting multisignature should be
+    /// passed back as [`AlertEvent::Multisigned`].
+    StartRmc(H::Hash),
+    /// Pass the message to the RMC.
+    ForwardToRmc(RmcMessage<H::Hash, S, MS>),
+    /// Pass the timeout back as [`AlertEvent::Timeout`] once the given time passes.
+    ScheduleTimeout(AlertTimeout<H>, time::Duration),
+    /// The incomplete alert with the given hash was dropped because of the [`AlertLimits`].
+    /// Nothing has to be done, this is only reported so that the drops can be monitored.
+    Expired(H::Hash),
+}
+
+type Actions<H, D, MK> = Vec<
+    AlertAction<H, D, <MK as Keychain>::Signature, <MK as MultiKeychain>::PartialMultisignature>,
+>;
+
+/// Fork alert handling as a state machine performing no IO, so that it can be tested
+/// independently of the async plumbing, e.g. by feeding it arbitrary sequences of events.
+///
+/// Every input is passed to [`AlertHandler::handle`] as an [`AlertEvent`], which returns the
+/// [`AlertAction`]s the input requires. The handler only ever waits for the keychain to sign
+/// an alert, in particular it never sleeps -- whenever it has to be woken up later, it asks for
+/// that with [`AlertAction::ScheduleTimeout`].
+pub struct AlertHandler<'a, H: Hasher, D: Data, MK: MultiKeychain> {
+    alerter: Alerter<'a, H, D, MK>,
+    requested_alerts: HashSet<H::Hash>,
+}
+
+impl<'a, H: Hasher, D: Data, MK: MultiKeychain> AlertHandler<'a, H, D, MK> {
+    pub fn new(keychain: &'a MK, session_id: SessionId) -> Self {
+        let config = AlertConfig {
+            n_members: keychain.node_count(),
+            session_id,
+            limits: AlertLimits::default(),
+            key_schedule: KeySchedule::default(),
+        };
+        AlertHandler {
+            alerter: Alerter::new(keychain, config),
+            requested_alerts: HashSet::new(),
+        }
+    }
+
+    /// Changes the bounds on the alerts in progress, the default ones are used otherwise.
+    pub fn with_limits(mut self, limits: AlertLimits) -> Self {
+        self.alerter.limits = limits;
+        self
+    }
+
+    pub fn index(&self) -> NodeIndex {
+        self.alerter.index()
+    }
+
+    /// Processes the event, returning the actions it requires.
+    pub async fn handle(&mut self, event: AlertEvent<H, D, MK>) -> Actions<H, D, MK> {
+        let mut actions = self.handle_event(event).await;
+        actions.extend(
+            self.alerter
+                .take_expired()
+                .into_iter()
+                .map(AlertAction::Expired),
+        );
+        actions
+    }
+
+    async fn handle_event(&mut self, event: AlertEvent<H, D, MK>) -> Actions<H, D, MK> {
+        match event {
+            AlertEvent::Message(message) => self.on_message(message),
+            AlertEvent::OwnAlert(alert) => {
+                let (message, recipient, hash) = self.alerter.on_own_alert(alert).await;
+                vec![
+                    AlertAction::Send(message, recipient),
+                    AlertAction::StartRmc(hash),
+                ]
+            }
+            AlertEvent::Multisigned(multisigned) => self
+                .alerter
+                .alert_confirmed(multisigned)
+                .map(AlertAction::Notify)
+                .into_iter()
+                .collect(),
+            AlertEvent::Timeout(AlertTimeout::AlertRequest(hash)) => {
+                // Further RMC messages about this alert will trigger a new request, possibly to
+                // a different node.
+                self.requested_alerts.remove(&hash);
+                Vec::new()
+            }
+        }
+    }
+
+    fn on_message(&mut self, message: NetworkMessage<H, D, MK>) -> Actions<H, D, MK> {
+        match self.alerter.on_message(message) {
+            Some(AlerterResponse::ForkAlert(alert, recipient)) => {
+                vec![AlertAction::Send(AlertMessage::ForkAlert(alert), recipient)]
+            }
+            Some(AlerterResponse::AlertRequest(hash, recipient)) => {
+                if !self.requested_alerts.insert(hash) {
+                    trace!(target: "AlephBFT-alerter", "{:?} Alert {:?} already requested.", self.index(), hash);
+                    return Vec::new();
+                }
+                vec![
+                    AlertAction::Send(AlertMessage::AlertRequest(self.index(), hash), recipient),
+                    AlertAction::ScheduleTimeout(
+                        AlertTimeout::AlertRequest(hash),
+                        ALERT_REQUEST_TIMEOUT,
+                    ),
+                ]
+            }
+            Some(AlerterResponse::RmcMessage(message)) => vec![AlertAction::ForwardToRmc(message)],
+            Some(AlerterResponse::ForkResponse(notification, hash)) => {
+                let mut actions = vec![AlertAction::StartRmc(hash)];
+                actions.extend(notification.map(AlertAction::Notify));
+                actions
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ALERT_REQUEST_TIMEOUT;
+    use crate::{
+        alerts::{
+            Alert, AlertAction, AlertEvent, AlertHandler, AlertMessage, AlertTimeout,
+            ForkingNotification, RmcMessage,
+        },
+        units::{ControlHash, FullUnit, PreUnit},
+        Recipient, SessionId,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
+    use aleph_bft_types::{Index, NodeCount, NodeIndex, NodeMap, Signable, Signed};
+
+    type TestMessage = AlertMessage<Hasher64, Data, Signature, PartialMultisignature>;
+    type TestAlert = Alert<Hasher64, Data, Signature>;
+
+    async fn signed_alert_hash_message(
+        alerter_keychain: &Keychain,
+        forker_keychain: &Keychain,
+        n_members: NodeCount,
+    ) -> (TestMessage, TestAlert) {
+        let forker = forker_keychain.index();
+        let mut proof = Vec::new();
+        for variant in 0..2 {
+            let unit = FullUnit::new(
+                PreUnit::new(forker, 0, ControlHash::new(&NodeMap::with_size(n_members))),
+                Some(variant),
+                SessionId::from_number(0),
+            );
+            proof.push(Signed::sign(unit, forker_keychain).await.into_unchecked());
+        }
+        let proof = (proof.remove(0), proof.remove(0));
+        let alert = Alert::new(alerter_keychain.index(), proof, vec![]);
+        let signed_hash = Signed::sign_with_index(Signable::hash(&alert), alerter_keychain)
+            .await
+            .into_unchecked();
+        (
+            AlertMessage::RmcMessage(
+                alerter_keychain.index(),
+                RmcMessage::SignedHash(signed_hash),
+            ),
+            alert,
+        )
+    }
+
+    #[tokio::test]
+    async fn requests_unknown_alert_once_per_timeout() {
+        let n_members = NodeCount(4);
+        let own_keychain = Keychain::new(n_members, NodeIndex(0));
+        let alerter_keychain = Keychain::new(n_members, NodeIndex(1));
+        let forker_keychain = Keychain::new(n_members, NodeIndex(3));
+        let mut handler = AlertHandler::new(&own_keychain, SessionId::from_number(0));
+        let (message, alert) =
+            signed_alert_hash_message(&alerter_keychain, &forker_keychain, n_members).await;
+        let hash = Signable::hash(&alert);
+
+        let request = vec![
+            AlertAction::Send(
+                AlertMessage::AlertRequest(NodeIndex(0), hash),
+                Recipient::Node(NodeIndex(1)),
+            ),
+            AlertAction::ScheduleTimeout(AlertTimeout::AlertRequest(hash), ALERT_REQUEST_TIMEOUT),
+        ];
+        assert_eq!(
+            handler.handle(AlertEvent::Message(message.clone())).await,
+            request
+        );
+        assert!(handler
+            .handle(AlertEvent::Message(message.clone()))
+            .await
+            .is_empty());
+        assert!(handler
+            .handle(AlertEvent::Timeout(AlertTimeout::AlertRequest(hash)))
+            .await
+            .is_empty());
+        assert_eq!(handler.handle(AlertEvent::Message(message)).await, request);
+    }
+
+    #[tokio::test]
+    async fn handles_incoming_alert() {
+        let n_members = NodeCount(4);
+        let own_keychain = Keychain::new(n_members, NodeIndex(0));
+        let alerter_keychain = Keychain::new(n_members, NodeIndex(1));
+        let forker_keychain = Keychain::new(n_members, NodeIndex(3));
+        let mut handler = AlertHandler::new(&own_keychain, SessionId::from_number(0));
+        let (message, alert) =
+            signed_alert_hash_message(&alerter_keychain, &forker_keychain, n_members).await;
+        let hash = Signable::hash(&alert);
+        let proof = alert.proof.clone();
+        let signed_alert = Signed::sign(alert, &alerter_keychain)
+            .await
+            .into_unchecked();
+
+        assert_eq!(
+            handler
+                .handle(AlertEvent::Message(AlertMessage::ForkAlert(signed_alert)))
+                .await,
+            vec![
+                AlertAction::StartRmc(hash),
+                AlertAction::Notify(ForkingNotification::Forker(proof)),
+            ]
+        );
+        match &handler.handle(AlertEvent::Message(message)).await[..] {
+            [AlertAction::ForwardToRmc(message)] => assert_eq!(message.hash(), &hash),
+            actions => panic!("unexpected actions {:?}", actions),
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_own_alert() {
+        let n_members = NodeCount(4);
+        let own_keychain = Keychain::new(n_members, NodeIndex(1));
+        let forker_keychain = Keychain::new(n_members, NodeIndex(3));
+        let mut handler = AlertHandler::new(&own_keychain, SessionId::from_number(0));
+        let (_, alert) =
+            signed_alert_hash_message(&own_keychain, &forker_keychain, n_members).await;
+        let hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert.clone(), &own_keychain)
+            .await
+            .into_unchecked();
+
+        assert_eq!(
+            handler.handle(AlertEvent::OwnAlert(alert)).await,
+            vec![
+                AlertAction::Send(AlertMessage::ForkAlert(signed_alert), Recipient::Everyone),
+                AlertAction::StartRmc(hash),
+            ]
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use super::*;
+use futures::future::BoxFuture;
+use futures_timer::Delay;
 
 pub struct IO<'a, H: Hasher, D: Data, MK: MultiKeychain> {
     pub messages_for_network: Sender<(NetworkMessage<H, D, MK>, Recipient)>,
@@ -8,10 +10,39 @@ pub struct IO<'a, H: Hasher, D: Data, MK: MultiKeychain> {
     pub rmc: ReliableMulticast<'a, H::Hash, MK>,
     pub messages_from_rmc: Receiver<RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>>,
     pub messages_for_rmc: Sender<RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>>,
+    pub timeouts: FuturesUnordered<BoxFuture<'static, AlertTimeout<H>>>,
     pub alerter_index: NodeIndex,
 }
 
 impl<'a, H: Hasher, D: Data, MK: MultiKeychain> IO<'a, H, D, MK> {
+    pub async fn perform(
+        &mut self,
+        action: AlertAction<H, D, MK::Signature, MK::PartialMultisignature>,
+        exiting: &mut bool,
+    ) {
+        match action {
+            AlertAction::Send(message, recipient) => {
+                self.send_message_for_network(message, recipient, exiting)
+            }
+            AlertAction::Notify(notification) => {
+                self.send_notification_for_units(notification, exiting)
+            }
+            AlertAction::StartRmc(hash) => self.rmc.start_rmc(hash).await,
+            AlertAction::ForwardToRmc(message) => {
+                if self.messages_for_rmc.unbounded_send(message).is_err() {
+                    warn!(target: "AlephBFT-alerter", "{:?} Channel with messages for rmc should be open", self.alerter_index);
+                    *exiting = true;
+                }
+            }
+            AlertAction::ScheduleTimeout(timeout, delay) => self
+                .timeouts
+                .push(Delay::new(delay).map(move |_| timeout).boxed()),
+            AlertAction::Expired(hash) => {
+                warn!(target: "AlephBFT-alerter", "{:?} Dropped incomplete alert {:?}, too many alerts in progress.", self.alerter_index, hash);
+            }
+        }
+    }
+
     pub fn rmc_message_to_network(
         &mut self,
         message: RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>,
@@ -1,21 +1,27 @@
 use crate::{
-    units::UncheckedSignedUnit, Data, Hasher, Index, Keychain, MultiKeychain, Multisigned,
-    NodeCount, NodeIndex, PartialMultisignature, Receiver, Recipient, Sender, SessionId, Signable,
-    Signature, Signed, Terminator, UncheckedSigned,
+    key_rotation::KeySchedule, units::UncheckedSignedUnit, AlertLimits, Data, Hasher, Health,
+    Index, Keychain, MultiKeychain, Multisigned, NodeCount, NodeIndex, PartialMultisignature,
+    Receiver, Recipient, Sender, SessionId, Signable, Signature, Signed, Subsystem, Terminator,
+    UncheckedSigned,
 };
 use aleph_bft_rmc::{DoublingDelayScheduler, Message as RmcMessage, ReliableMulticast};
 use codec::{Decode, Encode};
 use derivative::Derivative;
-use futures::{channel::mpsc, FutureExt, StreamExt};
+use futures::{channel::mpsc, stream::FuturesUnordered, FutureExt, StreamExt};
 use log::{debug, error, trace, warn};
 use parking_lot::RwLock;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     ops::Deref,
     time,
 };
 
+mod handler;
 mod io;
+mod trace;
+
+pub use handler::{AlertAction, AlertEvent, AlertHandler, AlertTimeout};
+pub use trace::{check_alert_trace, TraceData, TraceError};
 
 pub(crate) type ForkProof<H, D, S> = (UncheckedSignedUnit<H, D, S>, UncheckedSignedUnit<H, D, S>);
 
@@ -168,13 +174,20 @@ struct Alerter<'a, H: Hasher, D: Data, MK: MultiKeychain> {
     known_forkers: HashMap<NodeIndex, ForkProof<H, D, MK::Signature>>,
     known_alerts: KnownAlerts<H, D, MK>,
     known_rmcs: HashMap<(NodeIndex, NodeIndex), H::Hash>,
-    exiting: bool,
+    limits: AlertLimits,
+    key_schedule: KeySchedule,
+    // Alerts of others with incomplete RMCs, oldest first, together with their senders and
+    // forkers.
+    incomplete: VecDeque<(H::Hash, NodeIndex, NodeIndex)>,
+    expired: Vec<H::Hash>,
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub(crate) struct AlertConfig {
     pub n_members: NodeCount,
     pub session_id: SessionId,
+    pub limits: AlertLimits,
+    pub key_schedule: KeySchedule,
 }
 
 type NetworkAlert<H, D, MK> = Option<(
@@ -190,7 +203,10 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
             known_forkers: HashMap::new(),
             known_alerts: HashMap::new(),
             known_rmcs: HashMap::new(),
-            exiting: false,
+            limits: config.limits,
+            key_schedule: config.key_schedule,
+            incomplete: VecDeque::new(),
+            expired: Vec::new(),
         }
     }
 
@@ -206,6 +222,60 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         self.known_forkers.insert(forker, proof);
     }
 
+    /// The hashes of the alerts dropped since the last call, because of the limits.
+    fn take_expired(&mut self) -> Vec<H::Hash> {
+        std::mem::take(&mut self.expired)
+    }
+
+    fn add_alert(&mut self, forker: NodeIndex, alert: Signed<Alert<H, D, MK::Signature>, MK>) {
+        let hash = alert.as_signable().hash();
+        let sender = alert.as_signable().sender;
+        if self.known_alerts.insert(hash, alert).is_some() || sender == self.index() {
+            return;
+        }
+        self.incomplete.push_back((hash, sender, forker));
+        let about_forker = self
+            .incomplete
+            .iter()
+            .filter(|(_, _, alerted)| *alerted == forker)
+            .count();
+        if about_forker > self.limits.per_forker {
+            let oldest = self
+                .incomplete
+                .iter()
+                .position(|(_, _, alerted)| *alerted == forker)
+                .expect("there are alerts about the forker");
+            self.expire(oldest);
+        }
+        if self.incomplete.len() > self.limits.total {
+            // Only from the share of the node that sent the most, so that it cannot push the
+            // alerts of others out.
+            let mut sent = HashMap::new();
+            for (_, sender, _) in &self.incomplete {
+                *sent.entry(*sender).or_insert(0) += 1;
+            }
+            let most = sent.values().copied().max().unwrap_or(0);
+            let oldest = self
+                .incomplete
+                .iter()
+                .position(|(_, sender, _)| sent.get(sender) == Some(&most))
+                .expect("there are incomplete alerts");
+            self.expire(oldest);
+        }
+    }
+
+    fn expire(&mut self, position: usize) {
+        let (hash, sender, forker) = match self.incomplete.remove(position) {
+            Some(alert) => alert,
+            None => return,
+        };
+        self.known_alerts.remove(&hash);
+        if self.known_rmcs.get(&(sender, forker)) == Some(&hash) {
+            self.known_rmcs.remove(&(sender, forker));
+        }
+        self.expired.push(hash);
+    }
+
     // Correctness rules:
     // 1) All units must be created by forker
     // 2) All units must come from different rounds
@@ -219,8 +289,20 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         units: &[UncheckedSignedUnit<H, D, MK::Signature>],
     ) -> bool {
         let mut rounds = HashSet::new();
+        // Units of different key epochs are verified in separate batches, the order of the
+        // units does not matter here.
+        let mut batches = BTreeMap::new();
         for u in units {
-            let u = match u.clone().check(self.keychain) {
+            batches
+                .entry(self.key_schedule.epoch(u.as_signable().round()))
+                .or_insert_with(Vec::new)
+                .push(u.clone());
+        }
+        let checked = batches.into_iter().flat_map(|(epoch, batch)| {
+            UncheckedSigned::check_batch(batch, &self.keychain.at_epoch(epoch))
+        });
+        for u in checked {
+            let u = match u {
                 Ok(u) => u,
                 Err(_) => {
                     warn!(target: "AlephBFT-alerter", "{:?} One of the units is incorrectly signed.", self.index());
@@ -244,8 +326,16 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
     fn who_is_forking(&self, proof: &ForkProof<H, D, MK::Signature>) -> Option<NodeIndex> {
         let (u1, u2) = proof;
         let (u1, u2) = {
-            let u1 = u1.clone().check(self.keychain);
-            let u2 = u2.clone().check(self.keychain);
+            let u1 = u1.clone().check(
+                &self
+                    .key_schedule
+                    .keychain(self.keychain, u1.as_signable().round()),
+            );
+            let u2 = u2.clone().check(
+                &self
+                    .key_schedule
+                    .keychain(self.keychain, u2.as_signable().round()),
+            );
             match (u1, u2) {
                 (Ok(u1), Ok(u2)) => (u1, u2),
                 _ => {
@@ -285,7 +375,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         let hash = alert.as_signable().hash();
         self.known_rmcs
             .insert((alert.as_signable().sender, forker), hash);
-        self.known_alerts.insert(hash, alert);
+        self.add_alert(forker, alert);
         hash
     }
 
@@ -324,8 +414,9 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         let contents = alert.as_signable();
         if let Some(forker) = self.who_is_forking(&contents.proof) {
             if self.known_rmcs.contains_key(&(contents.sender, forker)) {
+                // Only one alert per sender and forker is kept, others can only come from
+                // malicious senders.
                 debug!(target: "AlephBFT-alerter","{:?} We already know about an alert by {:?} about {:?}.", self.index(), alert.as_signable().sender, forker);
-                self.known_alerts.insert(contents.hash(), alert);
                 return None;
             }
             let propagate_alert = if self.is_forker(forker) {
@@ -389,10 +480,14 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         &mut self,
         multisigned: Multisigned<H::Hash, MK>,
     ) -> Option<ForkingNotification<H, D, MK::Signature>> {
-        let alert = match self.known_alerts.get(multisigned.as_signable()) {
+        let hash = multisigned.as_signable();
+        self.incomplete
+            .retain(|(incomplete, _, _)| incomplete != hash);
+        let alert = match self.known_alerts.get(hash) {
             Some(alert) => alert.as_signable(),
             None => {
-                error!(target: "AlephBFT-alerter", "{:?} Completed an RMC for an unknown alert.", self.index());
+                // The RMC keeps running after the alert is dropped because of the limits.
+                warn!(target: "AlephBFT-alerter", "{:?} Completed an RMC for an unknown or dropped alert.", self.index());
                 return None;
             }
         };
@@ -416,12 +511,13 @@ pub(crate) async fn run<H: Hasher, D: Data, MK: MultiKeychain>(
     notifications_for_units: Sender<ForkingNotification<H, D, MK::Signature>>,
     alerts_from_units: Receiver<Alert<H, D, MK::Signature>>,
     config: AlertConfig,
+    health: Health,
     mut terminator: Terminator,
 ) {
     use self::io::IO;
 
     let n_members = config.n_members;
-    let mut alerter = Alerter::new(&keychain, config);
+    let mut handler = AlertHandler::new(&keychain, config.session_id).with_limits(config.limits);
     let (messages_for_rmc, messages_from_us) = mpsc::unbounded();
     let (messages_for_us, messages_from_rmc) = mpsc::unbounded();
     let mut io = IO {
@@ -438,74 +534,54 @@ pub(crate) async fn run<H: Hasher, D: Data, MK: MultiKeychain>(
         ),
         messages_from_rmc,
         messages_for_rmc,
-        alerter_index: alerter.index(),
+        timeouts: FuturesUnordered::new(),
+        alerter_index: handler.index(),
     };
+    let mut exiting = false;
     loop {
-        futures::select! {
+        let event = futures::select! {
             message = io.messages_from_network.next() => match message {
-                Some(message) => {
-                    match alerter.on_message(message) {
-                        Some(AlerterResponse::ForkAlert(alert, recipient)) => {
-                            io.send_message_for_network(
-                                AlertMessage::ForkAlert(alert),
-                                recipient,
-                                &mut alerter.exiting,
-                            );
-                        }
-                        Some(AlerterResponse::AlertRequest(hash, peer)) => {
-                            let message = AlertMessage::AlertRequest(alerter.index(), hash);
-                            io.send_message_for_network(message, peer, &mut alerter.exiting);
-                        }
-                        Some(AlerterResponse::RmcMessage(message)) => {
-                            if io.messages_for_rmc.unbounded_send(message).is_err() {
-                                warn!(target: "AlephBFT-alerter", "{:?} Channel with messages for rmc should be open", alerter.index());
-                                alerter.exiting = true;
-                            }
-                        }
-                        Some(AlerterResponse::ForkResponse(maybe_notification, hash)) => {
-                            io.rmc.start_rmc(hash).await;
-                            if let Some(notification) = maybe_notification {
-                                io.send_notification_for_units(notification, &mut alerter.exiting);
-                            }
-                        }
-                        None => {}
-                    }
-                }
+                Some(message) => Some(AlertEvent::Message(message)),
                 None => {
-                    error!(target: "AlephBFT-alerter", "{:?} Message stream closed.", alerter.index());
+                    error!(target: "AlephBFT-alerter", "{:?} Message stream closed.", handler.index());
                     break;
                 }
             },
             alert = io.alerts_from_units.next() => match alert {
-                Some(alert) => {
-                    let (message, recipient, hash) = alerter.on_own_alert(alert.clone()).await;
-                    io.send_message_for_network(message, recipient, &mut alerter.exiting);
-                    io.rmc.start_rmc(hash).await;
-                }
+                Some(alert) => Some(AlertEvent::OwnAlert(alert)),
                 None => {
-                    error!(target: "AlephBFT-alerter", "{:?} Alert stream closed.", alerter.index());
+                    error!(target: "AlephBFT-alerter", "{:?} Alert stream closed.", handler.index());
                     break;
                 }
             },
             message = io.messages_from_rmc.next() => match message {
-                Some(message) => io.rmc_message_to_network(message, &mut alerter.exiting),
+                Some(message) => {
+                    io.rmc_message_to_network(message, &mut exiting);
+                    None
+                }
                 None => {
-                    error!(target: "AlephBFT-alerter", "{:?} RMC message stream closed.", alerter.index());
+                    error!(target: "AlephBFT-alerter", "{:?} RMC message stream closed.", handler.index());
                     break;
                 }
             },
             multisigned = io.rmc.next_multisigned_hash().fuse() => {
-                if let Some(notification) = alerter.alert_confirmed(multisigned) {
-                    io.send_notification_for_units(notification, &mut alerter.exiting);
-                }
+                Some(AlertEvent::Multisigned(multisigned))
             },
+            timeout = io.timeouts.select_next_some() => Some(AlertEvent::Timeout(timeout)),
             _ = &mut terminator.get_exit() => {
-                debug!(target: "AlephBFT-alerter", "{:?} received exit signal", alerter.index());
-                alerter.exiting = true;
+                debug!(target: "AlephBFT-alerter", "{:?} received exit signal", handler.index());
+                exiting = true;
+                None
             },
+        };
+        if let Some(event) = event {
+            health.on_activity(Subsystem::Alerter);
+            for action in handler.handle(event).await {
+                io.perform(action, &mut exiting).await;
+            }
         }
-        if alerter.exiting {
-            debug!(target: "AlephBFT-alerter", "{:?} Alerter decided to exit.", alerter.index());
+        if exiting {
+            debug!(target: "AlephBFT-alerter", "{:?} Alerter decided to exit.", handler.index());
             terminator.terminate_sync().await;
             break;
         }
@@ -519,11 +595,12 @@ mod tests {
             Alert, AlertConfig, AlertMessage, Alerter, AlerterResponse, ForkProof,
             ForkingNotification, RmcMessage,
         },
+        key_rotation::KeySchedule,
         units::{ControlHash, FullUnit, PreUnit},
-        PartiallyMultisigned, Recipient, Round,
+        AlertLimits, PartiallyMultisigned, Recipient, Round, SessionId,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
-    use aleph_bft_types::{NodeCount, NodeIndex, NodeMap, Signable, Signed};
+    use aleph_bft_types::{Index, NodeCount, NodeIndex, NodeMap, Signable, Signed};
 
     type TestForkProof = ForkProof<Hasher64, Data, Signature>;
 
@@ -540,7 +617,7 @@ mod tests {
                 ControlHash::new(&NodeMap::with_size(n_members)),
             ),
             variant,
-            0,
+            SessionId::from_number(0),
         )
     }
 
@@ -569,7 +646,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members).await;
@@ -599,7 +678,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members).await;
@@ -625,7 +706,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members).await;
@@ -659,7 +742,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let valid_unit = Signed::sign(
@@ -690,7 +775,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let alert = Alert::new(
@@ -729,7 +816,9 @@ mod tests {
             &keychains[own_index.0],
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof =
@@ -816,7 +905,9 @@ mod tests {
             &keychains[own_index.0],
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof =
@@ -887,7 +978,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members).await;
@@ -905,7 +998,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 1,
+                session_id: SessionId::from_number(1),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members).await;
@@ -922,7 +1017,9 @@ mod tests {
             &keychains[0],
             AlertConfig {
                 n_members,
-                session_id: 1,
+                session_id: SessionId::from_number(1),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = {
@@ -946,7 +1043,9 @@ mod tests {
             &own_keychain,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = {
@@ -989,7 +1088,9 @@ mod tests {
             &keychains[own_index.0],
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
         );
         let fork_proof = if good_commitment {
@@ -1044,4 +1145,109 @@ mod tests {
         };
         assert_eq!(this.alert_confirmed(multisigned_alert_hash), expected);
     }
+
+    #[tokio::test]
+    async fn drops_oldest_incomplete_alerts() {
+        let n_members = NodeCount(7);
+        let own_keychain = Keychain::new(n_members, NodeIndex(0));
+        let forker_keychains = [
+            Keychain::new(n_members, NodeIndex(5)),
+            Keychain::new(n_members, NodeIndex(6)),
+        ];
+        let mut this: Alerter<Hasher64, Data, _> = Alerter::new(
+            &own_keychain,
+            AlertConfig {
+                n_members,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits {
+                    per_forker: 2,
+                    total: 2,
+                },
+                key_schedule: KeySchedule::default(),
+            },
+        );
+        let mut hashes = Vec::new();
+        for (sender, forker_keychain) in [(1, 0), (2, 0), (3, 0), (1, 1)] {
+            let forker_keychain = &forker_keychains[forker_keychain];
+            let fork_proof =
+                make_fork_proof(forker_keychain.index(), forker_keychain, 0, n_members).await;
+            let alert = Alert::new(NodeIndex(sender), fork_proof, vec![]);
+            hashes.push(Signable::hash(&alert));
+            let signed_alert = Signed::sign(alert, &Keychain::new(n_members, NodeIndex(sender)))
+                .await
+                .into_unchecked();
+            assert!(this.on_network_alert(signed_alert).is_some());
+        }
+        // The third alert about the first forker crosses the per forker limit, the alert about
+        // the second forker crosses the total one.
+        assert_eq!(this.take_expired(), vec![hashes[0], hashes[1]]);
+        assert!(!this.known_alerts.contains_key(&hashes[0]));
+        assert!(this.known_alerts.contains_key(&hashes[2]));
+        assert!(this.known_alerts.contains_key(&hashes[3]));
+    }
+
+    #[tokio::test]
+    async fn drops_incomplete_alerts_of_the_busiest_sender() {
+        let n_members = NodeCount(7);
+        let own_keychain = Keychain::new(n_members, NodeIndex(0));
+        let forker_keychains = [
+            Keychain::new(n_members, NodeIndex(5)),
+            Keychain::new(n_members, NodeIndex(6)),
+        ];
+        let mut this: Alerter<Hasher64, Data, _> = Alerter::new(
+            &own_keychain,
+            AlertConfig {
+                n_members,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits {
+                    per_forker: 7,
+                    total: 2,
+                },
+                key_schedule: KeySchedule::default(),
+            },
+        );
+        let mut hashes = Vec::new();
+        for (sender, forker_keychain) in [(2, 0), (1, 0), (1, 1)] {
+            let forker_keychain = &forker_keychains[forker_keychain];
+            let fork_proof =
+                make_fork_proof(forker_keychain.index(), forker_keychain, 0, n_members).await;
+            let alert = Alert::new(NodeIndex(sender), fork_proof, vec![]);
+            hashes.push(Signable::hash(&alert));
+            let signed_alert = Signed::sign(alert, &Keychain::new(n_members, NodeIndex(sender)))
+                .await
+                .into_unchecked();
+            assert!(this.on_network_alert(signed_alert).is_some());
+        }
+        assert_eq!(this.take_expired(), vec![hashes[1]]);
+        assert!(this.known_alerts.contains_key(&hashes[0]));
+        assert!(this.known_alerts.contains_key(&hashes[2]));
+    }
+
+    #[tokio::test]
+    async fn keeps_one_alert_per_sender_and_forker() {
+        let n_members = NodeCount(7);
+        let own_keychain = Keychain::new(n_members, NodeIndex(0));
+        let forker_keychain = Keychain::new(n_members, NodeIndex(6));
+        let sender_keychain = Keychain::new(n_members, NodeIndex(1));
+        let mut this: Alerter<Hasher64, Data, _> = Alerter::new(
+            &own_keychain,
+            AlertConfig {
+                n_members,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
+            },
+        );
+        let mut hashes = Vec::new();
+        for round in [0, 1] {
+            let fork_proof =
+                make_fork_proof(forker_keychain.index(), &forker_keychain, round, n_members).await;
+            let alert = Alert::new(NodeIndex(1), fork_proof, vec![]);
+            hashes.push(Signable::hash(&alert));
+            let signed_alert = Signed::sign(alert, &sender_keychain).await.into_unchecked();
+            this.on_network_alert(signed_alert);
+        }
+        assert!(this.known_alerts.contains_key(&hashes[0]));
+        assert!(!this.known_alerts.contains_key(&hashes[1]));
+    }
 }
@@ -0,0 +1,417 @@
+//! Executable traces of the [`AlertHandler`], i.e. sequences of its inputs together with the
+//! actions it is expected to return, so that other implementations of the protocol can be
+//! checked against the same behavior.
+//!
+//! A trace is a text file with one command per line, everything after a `#` is a comment:
+//! - `session <number>` sets the session, [`SessionId::from_number`] of it, the default is 0,
+//! - `node <index>` sets the index of the node running the handler, required before any input,
+//! - `alert <name> sender=<index> forker=<index>` defines an alert raised by the sender about
+//!   a fork of two units of round 0 by the forker, with data 0 and 1 respectively and no
+//!   parents, without committing to any legit units,
+//! - `in <event>` passes an event to the handler,
+//! - `out <action>` is an action expected to be returned for the most recent event, all of
+//!   them have to be listed in order.
+//!
+//! Events are `own_alert <name>`, `multisigned <name>` for a completed RMC, `timeout <name>`
+//! for an expired alert request and `<message>` for a message received from the network.
+//! Actions are `send <message> to=<index|everyone>`, `notify_forker <name>` and
+//! `notify_units <name>` for the respective notifications, `start_rmc <name>`,
+//! `forward rmc_hash <name> from=<index>` and `forward rmc_multisigned <name>` for messages
+//! passed on to the RMC, `schedule_timeout <name> <milliseconds>` and `expired <name>` for an
+//! incomplete alert dropped because of the default [`AlertLimits`](crate::AlertLimits).
+//! Messages are:
+//! - `fork_alert <name>`, the alert signed by its sender,
+//! - `alert_request <name> from=<index>`,
+//! - `rmc_hash <name> from=<index>`, the hash of the alert signed by the given node,
+//! - `rmc_multisigned <name> from=<index>`, the hash of the alert multisigned by the first
+//!   floor(2N/3)+1 nodes, sent by the given node.
+//!
+//! All signatures are made with the keychain of the signing node, so the traces hold for any
+//! keychain with deterministic signatures.
+use crate::{
+    alerts::{
+        Alert, AlertAction, AlertEvent, AlertHandler, AlertMessage, AlertTimeout,
+        ForkingNotification,
+    },
+    units::{ControlHash, FullUnit, PreUnit},
+    Hasher, Keychain, MultiKeychain, Multisigned, NodeCount, NodeIndex, NodeMap,
+    PartiallyMultisigned, Recipient, SessionId, Signable, Signed,
+};
+use aleph_bft_rmc::Message as RmcMessage;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    time::Duration,
+};
+
+/// The data of the units in the alerts of traces.
+pub type TraceData = u32;
+
+/// The reason a trace failed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TraceError {
+    /// The line of the trace could not be understood.
+    Syntax { line: usize, reason: String },
+    /// The event from the given line resulted in different actions than expected.
+    Mismatch {
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            TraceError::Syntax { line, reason } => write!(f, "line {}: {}", line, reason),
+            TraceError::Mismatch {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "line {}: expected actions {}, got {}",
+                line, expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for TraceError {}
+
+type TraceAlert<H, MK> = Alert<H, TraceData, <MK as Keychain>::Signature>;
+type TraceMessage<H, MK> = AlertMessage<
+    H,
+    TraceData,
+    <MK as Keychain>::Signature,
+    <MK as MultiKeychain>::PartialMultisignature,
+>;
+type TraceAction<H, MK> = AlertAction<
+    H,
+    TraceData,
+    <MK as Keychain>::Signature,
+    <MK as MultiKeychain>::PartialMultisignature,
+>;
+
+struct Line<'a> {
+    number: usize,
+    words: Vec<&'a str>,
+}
+
+impl<'a> Line<'a> {
+    fn error<T>(&self, reason: impl Into<String>) -> Result<T, TraceError> {
+        Err(TraceError::Syntax {
+            line: self.number,
+            reason: reason.into(),
+        })
+    }
+
+    fn word(&self, position: usize) -> Result<&'a str, TraceError> {
+        match self.words.get(position) {
+            Some(word) => Ok(word),
+            None => self.error(format!("missing argument {}", position)),
+        }
+    }
+
+    fn named(&self, name: &str) -> Result<&'a str, TraceError> {
+        let prefix = format!("{}=", name);
+        match self
+            .words
+            .iter()
+            .find_map(|word| word.strip_prefix(&prefix))
+        {
+            Some(value) => Ok(value),
+            None => self.error(format!("missing argument {}", name)),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, value: &str) -> Result<T, TraceError> {
+        match value.parse() {
+            Ok(number) => Ok(number),
+            Err(_) => self.error(format!("{} is not a number", value)),
+        }
+    }
+}
+
+struct Tracer<'a, H: Hasher, MK: MultiKeychain> {
+    keychains: &'a [MK],
+    session_id: SessionId,
+    alerts: HashMap<String, TraceAlert<H, MK>>,
+}
+
+impl<'a, H: Hasher, MK: MultiKeychain> Tracer<'a, H, MK> {
+    fn node(&self, line: &Line, value: &str) -> Result<NodeIndex, TraceError> {
+        let index = line.parse(value)?;
+        if index >= self.keychains.len() {
+            return line.error(format!("node {} is not in the committee", index));
+        }
+        Ok(NodeIndex(index))
+    }
+
+    fn alert(&self, line: &Line, position: usize) -> Result<&TraceAlert<H, MK>, TraceError> {
+        let name = line.word(position)?;
+        match self.alerts.get(name) {
+            Some(alert) => Ok(alert),
+            None => line.error(format!("unknown alert {}", name)),
+        }
+    }
+
+    async fn define_alert(&mut self, line: &Line<'_>) -> Result<(), TraceError> {
+        let name = line.word(1)?;
+        let sender = self.node(line, line.named("sender")?)?;
+        let forker = self.node(line, line.named("forker")?)?;
+        let n_members = NodeCount(self.keychains.len());
+        let mut proof = Vec::new();
+        for variant in 0..2 {
+            let unit = FullUnit::new(
+                PreUnit::new(forker, 0, ControlHash::new(&NodeMap::with_size(n_members))),
+                Some(variant),
+                self.session_id,
+            );
+            let keychain = &self.keychains[forker.0];
+            proof.push(Signed::sign(unit, keychain).await.into_unchecked());
+        }
+        let alert = Alert::new(sender, (proof.remove(0), proof.remove(0)), Vec::new());
+        self.alerts.insert(name.to_string(), alert);
+        Ok(())
+    }
+
+    async fn multisigned(
+        &self,
+        line: &Line<'_>,
+        hash: H::Hash,
+    ) -> Result<Multisigned<H::Hash, MK>, TraceError> {
+        let threshold = NodeCount(self.keychains.len()).quorum().0;
+        let first = &self.keychains[0];
+        let mut multisigned = Signed::sign_with_index(hash, first)
+            .await
+            .into_partially_multisigned(first);
+        for keychain in &self.keychains[1..threshold] {
+            let signed = Signed::sign_with_index(hash, keychain).await;
+            multisigned = multisigned.add_signature(signed, first);
+        }
+        match multisigned {
+            PartiallyMultisigned::Complete { multisigned } => Ok(multisigned),
+            PartiallyMultisigned::Incomplete { .. } => {
+                line.error("the keychains do not complete a multisignature with a quorum")
+            }
+        }
+    }
+
+    /// The RMC message described by the line, starting at the given word.
+    async fn rmc_message(
+        &self,
+        line: &Line<'_>,
+        position: usize,
+    ) -> Result<RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>, TraceError> {
+        let hash = Signable::hash(self.alert(line, position + 1)?);
+        match line.word(position)? {
+            "rmc_hash" => {
+                let signer = self.node(line, line.named("from")?)?;
+                let signed = Signed::sign_with_index(hash, &self.keychains[signer.0]).await;
+                Ok(RmcMessage::SignedHash(signed.into_unchecked()))
+            }
+            "rmc_multisigned" => Ok(RmcMessage::MultisignedHash(
+                self.multisigned(line, hash).await?.into_unchecked(),
+            )),
+            other => line.error(format!("unknown RMC message {}", other)),
+        }
+    }
+
+    /// The message described by the line, starting at the given word.
+    async fn message(
+        &self,
+        line: &Line<'_>,
+        position: usize,
+    ) -> Result<TraceMessage<H, MK>, TraceError> {
+        match line.word(position)? {
+            "fork_alert" => {
+                let alert = self.alert(line, position + 1)?;
+                let signed = Signed::sign(alert.clone(), &self.keychains[alert.sender.0]).await;
+                Ok(AlertMessage::ForkAlert(signed.into_unchecked()))
+            }
+            "alert_request" => {
+                let hash = Signable::hash(self.alert(line, position + 1)?);
+                let from = self.node(line, line.named("from")?)?;
+                Ok(AlertMessage::AlertRequest(from, hash))
+            }
+            "rmc_hash" | "rmc_multisigned" => {
+                let from = self.node(line, line.named("from")?)?;
+                Ok(AlertMessage::RmcMessage(
+                    from,
+                    self.rmc_message(line, position).await?,
+                ))
+            }
+            other => line.error(format!("unknown message {}", other)),
+        }
+    }
+
+    async fn event(&self, line: &Line<'_>) -> Result<AlertEvent<H, TraceData, MK>, TraceError> {
+        match line.word(1)? {
+            "own_alert" => Ok(AlertEvent::OwnAlert(self.alert(line, 2)?.clone())),
+            "multisigned" => {
+                let hash = Signable::hash(self.alert(line, 2)?);
+                Ok(AlertEvent::Multisigned(self.multisigned(line, hash).await?))
+            }
+            "timeout" => {
+                let hash = Signable::hash(self.alert(line, 2)?);
+                Ok(AlertEvent::Timeout(AlertTimeout::AlertRequest(hash)))
+            }
+            _ => Ok(AlertEvent::Message(self.message(line, 1).await?)),
+        }
+    }
+
+    async fn action(&self, line: &Line<'_>) -> Result<TraceAction<H, MK>, TraceError> {
+        match line.word(1)? {
+            "send" => {
+                let recipient = match line.named("to")? {
+                    "everyone" => Recipient::Everyone,
+                    node => Recipient::Node(self.node(line, node)?),
+                };
+                Ok(AlertAction::Send(self.message(line, 2).await?, recipient))
+            }
+            "notify_forker" => Ok(AlertAction::Notify(ForkingNotification::Forker(
+                self.alert(line, 2)?.proof.clone(),
+            ))),
+            "notify_units" => Ok(AlertAction::Notify(ForkingNotification::Units(
+                self.alert(line, 2)?.legit_units.clone(),
+            ))),
+            "start_rmc" => Ok(AlertAction::StartRmc(Signable::hash(self.alert(line, 2)?))),
+            "forward" => Ok(AlertAction::ForwardToRmc(self.rmc_message(line, 2).await?)),
+            "schedule_timeout" => {
+                let hash = Signable::hash(self.alert(line, 2)?);
+                let millis = line.parse(line.word(3)?)?;
+                Ok(AlertAction::ScheduleTimeout(
+                    AlertTimeout::AlertRequest(hash),
+                    Duration::from_millis(millis),
+                ))
+            }
+            "expired" => Ok(AlertAction::Expired(Signable::hash(self.alert(line, 2)?))),
+            other => line.error(format!("unknown action {}", other)),
+        }
+    }
+}
+
+fn check_step<T: Debug + PartialEq>(
+    step: Option<(usize, Vec<T>, Vec<T>)>,
+) -> Result<(), TraceError> {
+    match step {
+        Some((line, actual, expected)) if actual != expected => Err(TraceError::Mismatch {
+            line,
+            expected: format!("{:?}", expected),
+            actual: format!("{:?}", actual),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Runs the trace against an [`AlertHandler`], the `keychains` being the keychains of all the
+/// nodes of the committee, in order. See the [module documentation](self) for the format.
+pub async fn check_alert_trace<H: Hasher, MK: MultiKeychain>(
+    trace: &str,
+    keychains: &[MK],
+) -> Result<(), TraceError> {
+    let mut tracer = Tracer::<H, MK> {
+        keychains,
+        session_id: SessionId::default(),
+        alerts: HashMap::new(),
+    };
+    let mut node = None;
+    let mut handler = None;
+    // The line of the latest event, with the actions it resulted in and the ones expected.
+    let mut step = None;
+    for (number, text) in trace.lines().enumerate() {
+        let text = text.split('#').next().unwrap_or("");
+        let line = Line {
+            number: number + 1,
+            words: text.split_whitespace().collect(),
+        };
+        let command = match line.words.first() {
+            Some(command) => *command,
+            None => continue,
+        };
+        match command {
+            "session" => {
+                if handler.is_some() {
+                    return line.error("the session has to be set before any input");
+                }
+                tracer.session_id = SessionId::from_number(line.parse(line.word(1)?)?);
+            }
+            "node" => {
+                if handler.is_some() {
+                    return line.error("the node has to be set before any input");
+                }
+                node = Some(tracer.node(&line, line.word(1)?)?);
+            }
+            "alert" => tracer.define_alert(&line).await?,
+            "in" => {
+                check_step(step.take())?;
+                let node = match node {
+                    Some(node) => node,
+                    None => return line.error("the node has to be set before any input"),
+                };
+                let handler = handler.get_or_insert_with(|| {
+                    AlertHandler::new(&keychains[node.0], tracer.session_id)
+                });
+                let actions = handler.handle(tracer.event(&line).await?).await;
+                step = Some((line.number, actions, Vec::new()));
+            }
+            "out" => match &mut step {
+                Some((_, _, expected)) => expected.push(tracer.action(&line).await?),
+                None => return line.error("an action has to follow an input"),
+            },
+            other => return line.error(format!("unknown command {}", other)),
+        }
+    }
+    check_step(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_alert_trace, TraceError};
+    use crate::{NodeCount, NodeIndex};
+    use aleph_bft_mock::{Hasher64, Keychain};
+
+    const TRACES: [(&str, &str); 4] = [
+        ("network_alert", include_str!("traces/network_alert.trace")),
+        ("own_alert", include_str!("traces/own_alert.trace")),
+        (
+            "request_timeout",
+            include_str!("traces/request_timeout.trace"),
+        ),
+        ("known_forker", include_str!("traces/known_forker.trace")),
+    ];
+
+    fn keychains() -> Vec<Keychain> {
+        let n_members = NodeCount(4);
+        (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn shipped_traces_pass() {
+        let keychains = keychains();
+        for (name, trace) in TRACES {
+            if let Err(e) = check_alert_trace::<Hasher64, _>(trace, &keychains).await {
+                panic!("trace {} failed: {}", name, e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_unexpected_actions() {
+        let keychains = keychains();
+        let trace = "node 0\nalert a sender=1 forker=3\n\nin fork_alert a\nout start_rmc a\n";
+        match check_alert_trace::<Hasher64, _>(trace, &keychains).await {
+            Err(TraceError::Mismatch { line, .. }) => assert_eq!(line, 4),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let trace = "alert a sender=1 forker=3\nin fork_alert a\n";
+        assert!(matches!(
+            check_alert_trace::<Hasher64, _>(trace, &keychains).await,
+            Err(TraceError::Syntax { line: 2, .. })
+        ));
+    }
+}
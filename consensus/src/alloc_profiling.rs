@@ -0,0 +1,204 @@
+//! Counting of the allocations made in the hot paths, enabled with the `alloc_profiling` feature,
+//! for quantifying allocation churn. Without the feature the hot paths run unmeasured.
+
+#[cfg(feature = "alloc_profiling")]
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The paths in which allocations are counted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum HotPath {
+    /// Decoding of received messages, most of them units, by a [`CodecNetwork`].
+    ///
+    /// [`CodecNetwork`]: crate::CodecNetwork
+    UnitDecode,
+    /// Adding a unit to the store.
+    StoreInsertion,
+    /// Ordering a unit in the extender, including producing the batches it completes.
+    ExtenderOrdering,
+}
+
+/// Runs `f`, counting the allocations it makes towards the path.
+pub(crate) fn measure<R>(path: HotPath, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "alloc_profiling")]
+    {
+        let before = thread_allocations();
+        let result = f();
+        let after = thread_allocations();
+        let counters = &PROFILE[path as usize];
+        counters.runs.fetch_add(1, Ordering::Relaxed);
+        counters
+            .allocations
+            .fetch_add(after.0 - before.0, Ordering::Relaxed);
+        counters
+            .bytes
+            .fetch_add(after.1 - before.1, Ordering::Relaxed);
+        result
+    }
+    #[cfg(not(feature = "alloc_profiling"))]
+    {
+        let _ = path;
+        f()
+    }
+}
+
+/// The allocations made in one of the hot paths.
+#[cfg(feature = "alloc_profiling")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AllocationCounts {
+    /// How many times the path ran.
+    pub runs: u64,
+    /// How many allocations, including reallocations, were made in all the runs.
+    pub allocations: u64,
+    /// How many bytes were requested by the allocations.
+    pub bytes: u64,
+}
+
+/// The allocations made in the hot paths by all the sessions in the process, see
+/// [`allocation_profile`].
+#[cfg(feature = "alloc_profiling")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AllocationProfile {
+    pub unit_decode: AllocationCounts,
+    pub store_insertion: AllocationCounts,
+    pub extender_ordering: AllocationCounts,
+}
+
+#[cfg(feature = "alloc_profiling")]
+struct PathCounters {
+    runs: AtomicU64,
+    allocations: AtomicU64,
+    bytes: AtomicU64,
+}
+
+#[cfg(feature = "alloc_profiling")]
+impl PathCounters {
+    const fn new() -> Self {
+        PathCounters {
+            runs: AtomicU64::new(0),
+            allocations: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    fn counts(&self) -> AllocationCounts {
+        AllocationCounts {
+            runs: self.runs.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Indexed by `HotPath`.
+#[cfg(feature = "alloc_profiling")]
+static PROFILE: [PathCounters; 3] = [
+    PathCounters::new(),
+    PathCounters::new(),
+    PathCounters::new(),
+];
+
+/// The allocations counted so far. Only allocations made through a [`CountingAllocator`] are
+/// counted, so it has to be installed as the global allocator of the application.
+#[cfg(feature = "alloc_profiling")]
+pub fn allocation_profile() -> AllocationProfile {
+    AllocationProfile {
+        unit_decode: PROFILE[HotPath::UnitDecode as usize].counts(),
+        store_insertion: PROFILE[HotPath::StoreInsertion as usize].counts(),
+        extender_ordering: PROFILE[HotPath::ExtenderOrdering as usize].counts(),
+    }
+}
+
+#[cfg(feature = "alloc_profiling")]
+thread_local! {
+    // The number of allocations and of allocated bytes of the thread.
+    static THREAD_ALLOCATIONS: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+}
+
+#[cfg(feature = "alloc_profiling")]
+fn thread_allocations() -> (u64, u64) {
+    THREAD_ALLOCATIONS.try_with(Cell::get).unwrap_or_default()
+}
+
+#[cfg(feature = "alloc_profiling")]
+fn record_allocation(bytes: usize) {
+    // The thread local is gone while the thread is being torn down, nothing is measured then.
+    let _ = THREAD_ALLOCATIONS.try_with(|allocations| {
+        let (count, total) = allocations.get();
+        allocations.set((count + 1, total + bytes as u64));
+    });
+}
+
+/// A global allocator counting the allocations made by every thread, needed for
+/// [`allocation_profile`]:
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: aleph_bft::CountingAllocator = aleph_bft::CountingAllocator::system();
+/// ```
+#[cfg(feature = "alloc_profiling")]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+#[cfg(feature = "alloc_profiling")]
+impl CountingAllocator<System> {
+    /// Counts the allocations of the system allocator.
+    pub const fn system() -> Self {
+        CountingAllocator { inner: System }
+    }
+}
+
+#[cfg(feature = "alloc_profiling")]
+impl<A> CountingAllocator<A> {
+    /// Counts the allocations of another allocator.
+    pub const fn new(inner: A) -> Self {
+        CountingAllocator { inner }
+    }
+}
+
+#[cfg(feature = "alloc_profiling")]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation(new_size);
+        self.inner.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+}
+
+#[cfg(all(test, feature = "alloc_profiling"))]
+mod tests {
+    use super::{allocation_profile, measure, CountingAllocator, HotPath};
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+
+    #[test]
+    fn counts_allocations_of_paths() {
+        let before = allocation_profile();
+        let data = measure(HotPath::StoreInsertion, || vec![0u8; 100]);
+        assert_eq!(measure(HotPath::StoreInsertion, || data.len()), 100);
+        let after = allocation_profile();
+
+        // Other tests might be running the paths concurrently.
+        let counts = after.store_insertion;
+        assert!(counts.runs >= before.store_insertion.runs + 2);
+        assert!(counts.allocations > before.store_insertion.allocations);
+        assert!(counts.bytes >= before.store_insertion.bytes + 100);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{Hasher, Keychain, NodeIndex, Sender, SessionId};
+use codec::{Decode, Encode};
+
+// Keeps signatures of digests from being mistaken for signatures of anything else.
+const DIGEST_CONTEXT: &[u8] = b"AlephBFT-finalized-digest";
+
+/// A digest of everything a node finalized in a session so far.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct FinalizedDigest {
+    pub session_id: SessionId,
+    /// The node that finalized the batches.
+    pub node_ix: NodeIndex,
+    /// How many batches were finalized, including empty ones.
+    pub batch_count: u64,
+    /// The hash of the hashes of the units of all the finalized batches, chained batch by batch,
+    /// as bytes returned by `AsRef<[u8]>` of the hashes of the session.
+    pub cumulative_hash: Vec<u8>,
+}
+
+impl FinalizedDigest {
+    /// Whether the digests disagree about the same batches, proving that the output of one of
+    /// the nodes diverged.
+    pub fn conflicts_with(&self, other: &FinalizedDigest) -> bool {
+        self.session_id == other.session_id
+            && self.batch_count == other.batch_count
+            && self.cumulative_hash != other.cumulative_hash
+    }
+
+    fn signed_message(&self) -> Vec<u8> {
+        (DIGEST_CONTEXT, self).encode()
+    }
+}
+
+/// A digest of the finalized output of a node, signed by it, for external auditors. Auditors
+/// collecting acknowledgements of several nodes can detect a node whose output diverged by
+/// comparing their digests, see [`FinalizedDigest::conflicts_with`], without comparing the
+/// finalized data.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct FinalizationAcknowledgement {
+    pub digest: FinalizedDigest,
+    /// The encoded signature of the node of the digest.
+    pub signature: Vec<u8>,
+}
+
+impl FinalizationAcknowledgement {
+    async fn sign<K: Keychain>(digest: FinalizedDigest, keychain: &K) -> Self {
+        let signature = keychain.sign(&digest.signed_message()).await.encode();
+        FinalizationAcknowledgement { digest, signature }
+    }
+
+    /// Whether the digest was signed by its node, according to a keychain of the session.
+    pub fn verify<K: Keychain>(&self, keychain: &K) -> bool {
+        match K::Signature::decode(&mut &self.signature[..]) {
+            Ok(signature) => keychain.verify(
+                &self.digest.signed_message(),
+                &signature,
+                self.digest.node_ix,
+            ),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Keeps the digest of the finalized batches up to date, acknowledging it every `interval`
+/// batches.
+pub(crate) struct Acknowledger<H: Hasher> {
+    session_id: SessionId,
+    node_ix: NodeIndex,
+    interval: u64,
+    batch_count: u64,
+    cumulative_hash: H::Hash,
+    acknowledgements: Sender<FinalizationAcknowledgement>,
+}
+
+impl<H: Hasher> Acknowledger<H> {
+    pub(crate) fn new(
+        session_id: SessionId,
+        node_ix: NodeIndex,
+        interval: usize,
+        acknowledgements: Sender<FinalizationAcknowledgement>,
+    ) -> Self {
+        Acknowledger {
+            session_id,
+            node_ix,
+            interval: interval.max(1) as u64,
+            batch_count: 0,
+            cumulative_hash: H::hash(&session_id.encode()),
+            acknowledgements,
+        }
+    }
+
+    /// Adds the batch with the given unit hashes to the digest, returning the digest if it
+    /// should be acknowledged now.
+    pub(crate) fn on_batch(&mut self, batch: &[H::Hash]) -> Option<FinalizedDigest> {
+        self.cumulative_hash = H::hash(&(self.cumulative_hash, batch).encode());
+        self.batch_count += 1;
+        (self.batch_count % self.interval == 0).then(|| FinalizedDigest {
+            session_id: self.session_id,
+            node_ix: self.node_ix,
+            batch_count: self.batch_count,
+            cumulative_hash: self.cumulative_hash.as_ref().to_vec(),
+        })
+    }
+
+    pub(crate) async fn acknowledge<K: Keychain>(
+        digest: FinalizedDigest,
+        keychain: K,
+    ) -> FinalizationAcknowledgement {
+        FinalizationAcknowledgement::sign(digest, &keychain).await
+    }
+
+    pub(crate) fn publish(&self, acknowledgement: FinalizationAcknowledgement) {
+        // The application might not be interested in the acknowledgements anymore.
+        let _ = self.acknowledgements.unbounded_send(acknowledgement);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Acknowledger;
+    use crate::{Index, NodeCount, NodeIndex, SessionId};
+    use aleph_bft_mock::{Hasher64, Keychain};
+    use futures::channel::mpsc::unbounded;
+
+    #[tokio::test]
+    async fn detects_diverged_output() {
+        let session_id = SessionId::from_number(0);
+        let keychains: Vec<_> = (0..2)
+            .map(|ix| Keychain::new(NodeCount(2), NodeIndex(ix)))
+            .collect();
+        let mut acknowledgers: Vec<_> = keychains
+            .iter()
+            .map(|keychain| {
+                Acknowledger::<Hasher64>::new(session_id, keychain.index(), 2, unbounded().0)
+            })
+            .collect();
+        let first_batch = vec![[0; 8], [1; 8]];
+        for acknowledger in acknowledgers.iter_mut() {
+            assert_eq!(acknowledger.on_batch(&first_batch), None);
+        }
+        let honest = acknowledgers[0]
+            .on_batch(&[[2; 8]])
+            .expect("every second batch is acknowledged");
+        let diverged = acknowledgers[1]
+            .on_batch(&[[3; 8]])
+            .expect("every second batch is acknowledged");
+        assert_eq!(honest.batch_count, 2);
+        assert!(honest.conflicts_with(&diverged));
+        assert!(!honest.conflicts_with(&honest));
+
+        let acknowledgement = Acknowledger::<Hasher64>::acknowledge(diverged, keychains[1]).await;
+        assert!(acknowledgement.verify(&keychains[0]));
+        let mut forged = acknowledgement;
+        forged.digest.node_ix = NodeIndex(0);
+        assert!(!forged.verify(&keychains[0]));
+    }
+}
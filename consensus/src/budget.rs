@@ -0,0 +1,127 @@
+use std::time::{Duration, Instant};
+
+/// A change of state of some resource with respect to its budget.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) enum BudgetEvent {
+    /// The usage went above the limit.
+    Exceeded { used: usize, limit: usize },
+    /// The usage went back below the limit.
+    Recovered { used: usize, limit: usize },
+}
+
+/// Compares the usage of a resource with an optional limit, reporting only the moments when the
+/// limit is crossed in either direction, so that callers do not flood the logs.
+pub(crate) struct BudgetTracker {
+    limit: Option<usize>,
+    exceeded: bool,
+}
+
+impl BudgetTracker {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        BudgetTracker {
+            limit,
+            exceeded: false,
+        }
+    }
+
+    pub(crate) fn update(&mut self, used: usize) -> Option<BudgetEvent> {
+        let limit = self.limit?;
+        match (self.exceeded, used > limit) {
+            (false, true) => {
+                self.exceeded = true;
+                Some(BudgetEvent::Exceeded { used, limit })
+            }
+            (true, false) => {
+                self.exceeded = false;
+                Some(BudgetEvent::Recovered { used, limit })
+            }
+            _ => None,
+        }
+    }
+}
+
+const BANDWIDTH_WINDOW: Duration = Duration::from_secs(1);
+
+/// Tracks the number of bytes used per second against an optional limit.
+pub(crate) struct BandwidthTracker {
+    tracker: BudgetTracker,
+    window_start: Instant,
+    used_in_window: usize,
+}
+
+impl BandwidthTracker {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        BandwidthTracker {
+            tracker: BudgetTracker::new(limit),
+            window_start: Instant::now(),
+            used_in_window: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, bytes: usize) -> Option<BudgetEvent> {
+        self.record_at(bytes, Instant::now())
+    }
+
+    fn record_at(&mut self, bytes: usize, now: Instant) -> Option<BudgetEvent> {
+        if now.saturating_duration_since(self.window_start) >= BANDWIDTH_WINDOW {
+            self.window_start = now;
+            self.used_in_window = 0;
+        }
+        self.used_in_window += bytes;
+        self.tracker.update(self.used_in_window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandwidthTracker, BudgetEvent, BudgetTracker, BANDWIDTH_WINDOW};
+
+    #[test]
+    fn unlimited_budget_never_reports() {
+        let mut tracker = BudgetTracker::new(None);
+        assert_eq!(tracker.update(usize::MAX), None);
+    }
+
+    #[test]
+    fn reports_only_crossings() {
+        let mut tracker = BudgetTracker::new(Some(100));
+        assert_eq!(tracker.update(50), None);
+        assert_eq!(
+            tracker.update(150),
+            Some(BudgetEvent::Exceeded {
+                used: 150,
+                limit: 100
+            })
+        );
+        assert_eq!(tracker.update(200), None);
+        assert_eq!(
+            tracker.update(100),
+            Some(BudgetEvent::Recovered {
+                used: 100,
+                limit: 100
+            })
+        );
+        assert_eq!(tracker.update(20), None);
+    }
+
+    #[test]
+    fn bandwidth_is_counted_per_window() {
+        let mut tracker = BandwidthTracker::new(Some(100));
+        let start = tracker.window_start;
+        assert_eq!(tracker.record_at(60, start), None);
+        assert_eq!(
+            tracker.record_at(60, start),
+            Some(BudgetEvent::Exceeded {
+                used: 120,
+                limit: 100
+            })
+        );
+        assert_eq!(
+            tracker.record_at(60, start + BANDWIDTH_WINDOW),
+            Some(BudgetEvent::Recovered {
+                used: 60,
+                limit: 100
+            })
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{
+    run_session, Config, Data, DataProvider, Error, FinalizationHandler, Hasher, Health, LocalIO,
+    MultiKeychain, Network, NetworkData, OrderingLog, SpawnHandle, Stats, Terminator,
+};
+use async_trait::async_trait;
+use std::{
+    io::{empty, sink, Empty, Read, Sink, Write},
+    marker::PhantomData,
+};
+
+/// Placeholder for the network of a [`MemberBuilder`] before one is given, the member cannot be
+/// built without a network.
+pub struct NoNetwork;
+
+/// The data provider of members not proposing any data, e.g. observers following the ordering.
+pub struct NoData;
+
+#[async_trait]
+impl<D: Data> DataProvider<D> for NoData {
+    async fn get_data(&mut self) -> Option<D> {
+        None
+    }
+}
+
+/// The finalization handler of members not interested in the ordered data.
+pub struct IgnoreFinalized;
+
+impl<D: Data> FinalizationHandler<D> for IgnoreFinalized {
+    fn data_finalized(&mut self, _data: D) {}
+}
+
+type BroadcastBackupIO = (Box<dyn Write + Send>, Box<dyn Read + Send>);
+
+/// Wires the components of a member step by step, as an alternative to calling [`run_session`]
+/// with all of them at once. Only the network is required, the other components default to:
+/// * no data proposed, see [`NoData`],
+/// * finalized data ignored, see [`IgnoreFinalized`],
+/// * no unit backup, i.e. the member starts from scratch after every restart,
+/// * fresh [`Stats`] and [`Health`] handles, i.e. nobody can query them.
+pub struct MemberBuilder<D: Data, MK: MultiKeychain, N, DP, FH, US, UL> {
+    config: Config,
+    keychain: MK,
+    network: N,
+    data_provider: DP,
+    finalization_handler: FH,
+    unit_saver: US,
+    unit_loader: UL,
+    stats: Option<Stats>,
+    health: Option<Health>,
+    ordering_log: Option<OrderingLog>,
+    broadcast_backup: Option<BroadcastBackupIO>,
+    _phantom: PhantomData<D>,
+}
+
+impl<D: Data, MK: MultiKeychain>
+    MemberBuilder<D, MK, NoNetwork, NoData, IgnoreFinalized, Sink, Empty>
+{
+    pub fn new(config: Config, keychain: MK) -> Self {
+        MemberBuilder {
+            config,
+            keychain,
+            network: NoNetwork,
+            data_provider: NoData,
+            finalization_handler: IgnoreFinalized,
+            unit_saver: sink(),
+            unit_loader: empty(),
+            stats: None,
+            health: None,
+            ordering_log: None,
+            broadcast_backup: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<D: Data, MK: MultiKeychain, N, DP, FH, US, UL> MemberBuilder<D, MK, N, DP, FH, US, UL> {
+    pub fn with_network<N2>(self, network: N2) -> MemberBuilder<D, MK, N2, DP, FH, US, UL> {
+        MemberBuilder {
+            config: self.config,
+            keychain: self.keychain,
+            network,
+            data_provider: self.data_provider,
+            finalization_handler: self.finalization_handler,
+            unit_saver: self.unit_saver,
+            unit_loader: self.unit_loader,
+            stats: self.stats,
+            health: self.health,
+            ordering_log: self.ordering_log,
+            broadcast_backup: self.broadcast_backup,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_data_provider<DP2: DataProvider<D>>(
+        self,
+        data_provider: DP2,
+    ) -> MemberBuilder<D, MK, N, DP2, FH, US, UL> {
+        MemberBuilder {
+            config: self.config,
+            keychain: self.keychain,
+            network: self.network,
+            data_provider,
+            finalization_handler: self.finalization_handler,
+            unit_saver: self.unit_saver,
+            unit_loader: self.unit_loader,
+            stats: self.stats,
+            health: self.health,
+            ordering_log: self.ordering_log,
+            broadcast_backup: self.broadcast_backup,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_finalization_handler<FH2: FinalizationHandler<D>>(
+        self,
+        finalization_handler: FH2,
+    ) -> MemberBuilder<D, MK, N, DP, FH2, US, UL> {
+        MemberBuilder {
+            config: self.config,
+            keychain: self.keychain,
+            network: self.network,
+            data_provider: self.data_provider,
+            finalization_handler,
+            unit_saver: self.unit_saver,
+            unit_loader: self.unit_loader,
+            stats: self.stats,
+            health: self.health,
+            ordering_log: self.ordering_log,
+            broadcast_backup: self.broadcast_backup,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The unit backup, see [`LocalIO::new`] for the requirements on the saver and loader.
+    pub fn with_backup<US2: Write, UL2: Read>(
+        self,
+        unit_saver: US2,
+        unit_loader: UL2,
+    ) -> MemberBuilder<D, MK, N, DP, FH, US2, UL2> {
+        MemberBuilder {
+            config: self.config,
+            keychain: self.keychain,
+            network: self.network,
+            data_provider: self.data_provider,
+            finalization_handler: self.finalization_handler,
+            unit_saver,
+            unit_loader,
+            stats: self.stats,
+            health: self.health,
+            ordering_log: self.ordering_log,
+            broadcast_backup: self.broadcast_backup,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Collects the statistics of the session into the given handle.
+    pub fn with_metrics(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Reports the health of the subsystems of the session to the given handle.
+    pub fn with_health(mut self, health: Health) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Explains the ordering of the recently finalized rounds in the given log, see
+    /// [`LocalIO::enable_ordering_log`].
+    pub fn with_ordering_log(mut self, ordering_log: OrderingLog) -> Self {
+        self.ordering_log = Some(ordering_log);
+        self
+    }
+
+    /// See [`LocalIO::enable_broadcast_backup`].
+    pub fn with_broadcast_backup(
+        mut self,
+        saver: impl Write + Send + 'static,
+        loader: impl Read + Send + 'static,
+    ) -> Self {
+        self.broadcast_backup = Some((Box::new(saver), Box::new(loader)));
+        self
+    }
+}
+
+impl<D, MK, N, DP, FH, US, UL> MemberBuilder<D, MK, N, DP, FH, US, UL>
+where
+    D: Data,
+    MK: MultiKeychain,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+    US: Write + Send + Sync + 'static,
+    UL: Read + Send + Sync + 'static,
+{
+    /// The member, ready to run. The hasher is the one used by the network, i.e. usually
+    /// inferred from its type.
+    pub fn build<H: Hasher>(self) -> RunnableMember<H, D, MK, N, DP, FH, US, UL>
+    where
+        N: Network<NetworkData<H, D, MK::Signature, MK::PartialMultisignature>> + 'static,
+    {
+        let mut local_io = LocalIO::new(
+            self.data_provider,
+            self.finalization_handler,
+            self.unit_saver,
+            self.unit_loader,
+        );
+        if let Some(stats) = self.stats {
+            local_io.set_stats(stats);
+        }
+        if let Some(health) = self.health {
+            local_io.set_health(health);
+        }
+        if let Some(ordering_log) = self.ordering_log {
+            local_io.set_ordering_log(ordering_log);
+        }
+        if let Some((saver, loader)) = self.broadcast_backup {
+            local_io.enable_broadcast_backup(saver, loader);
+        }
+        RunnableMember {
+            config: self.config,
+            local_io,
+            network: self.network,
+            keychain: self.keychain,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// A member built by a [`MemberBuilder`].
+pub struct RunnableMember<H, D, MK, N, DP, FH, US, UL>
+where
+    D: Data,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+    US: Write,
+    UL: Read,
+{
+    config: Config,
+    local_io: LocalIO<D, DP, FH, US, UL>,
+    network: N,
+    keychain: MK,
+    _phantom: PhantomData<H>,
+}
+
+impl<H, D, MK, N, DP, FH, US, UL> RunnableMember<H, D, MK, N, DP, FH, US, UL>
+where
+    H: Hasher,
+    D: Data,
+    MK: MultiKeychain,
+    N: Network<NetworkData<H, D, MK::Signature, MK::PartialMultisignature>> + 'static,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+    US: Write + Send + Sync + 'static,
+    UL: Read + Send + Sync + 'static,
+{
+    /// Runs the session, exactly as [`run_session`] would.
+    pub async fn run(
+        self,
+        spawn_handle: impl SpawnHandle,
+        terminator: Terminator,
+    ) -> Result<(), Error> {
+        run_session(
+            self.config,
+            self.local_io,
+            self.network,
+            self.keychain,
+            spawn_handle,
+            terminator,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemberBuilder;
+    use crate::{testing::gen_config, NodeCount, NodeIndex, Stats, Terminator};
+    use aleph_bft_mock::{
+        DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, Router, Saver, Spawner,
+    };
+    use futures::{channel::oneshot, StreamExt};
+
+    #[tokio::test]
+    async fn built_members_reach_finalization() {
+        let n_members = NodeCount(4);
+        let (router, networks) = Router::new(n_members, 1.0);
+        tokio::spawn(router);
+        let mut exits = Vec::new();
+        let mut handles = Vec::new();
+        let mut finalized = Vec::new();
+        let stats = Stats::new();
+        for (network, _) in networks {
+            let index = network.index();
+            let (finalization_handler, finalized_rx) = FinalizationHandler::new();
+            finalized.push(finalized_rx);
+            let mut builder = MemberBuilder::new(
+                gen_config(index, n_members),
+                Keychain::new(n_members, index),
+            )
+            .with_network(network)
+            .with_finalization_handler(finalization_handler)
+            .with_backup(Saver::new(), Loader::new(Vec::new()));
+            if index == NodeIndex(0) {
+                builder = builder.with_metrics(stats.clone());
+            }
+            let (exit_tx, exit_rx) = oneshot::channel();
+            exits.push(exit_tx);
+            let terminator = Terminator::create_root(exit_rx, "AlephBFT-member");
+            // Only some members propose data, the others keep the default.
+            handles.push(match index.0 % 2 {
+                0 => tokio::spawn(
+                    builder
+                        .with_data_provider(DataProvider::new())
+                        .build::<Hasher64>()
+                        .run(Spawner {}, terminator),
+                ),
+                _ => tokio::spawn(builder.build::<Hasher64>().run(Spawner {}, terminator)),
+            });
+        }
+
+        for finalized_rx in &mut finalized {
+            finalized_rx.next().await.expect("data should be finalized");
+        }
+        assert!(stats.snapshot().average_units_per_batch.is_some());
+
+        for exit in exits {
+            let _ = exit.send(());
+        }
+        for handle in handles {
+            handle
+                .await
+                .expect("member should finish")
+                .expect("session should end cleanly");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    AggregatingMultiKeychain, Hasher, MultiKeychain, Round, SessionId, Signable, Signature,
+    UncheckedSigned, Vote, VoteCertificate, VoteCollector,
+};
+use codec::{Decode, Encode};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher as StdHasher},
+};
+
+/// A point of the session the committee agreed on: the hash of the head of the batch of the
+/// given round. Members certify checkpoints by voting for them with a [`VoteCollector`] created
+/// using [`checkpoint_voting`], the resulting [`CheckpointCertificate`] can be handed to nodes
+/// joining later, e.g. observers, as a trust anchor checked with [`verify_checkpoint`].
+///
+/// The Dag still has to be built from the start of the session, a certified checkpoint only lets
+/// the joining node confirm that the history it obtained is the one the committee agreed on.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct Checkpoint<H: Hasher> {
+    round: Round,
+    head: H::Hash,
+}
+
+// Derived implementation would require the hasher to implement `Hash`.
+impl<H: Hasher> Hash for Checkpoint<H> {
+    fn hash<S: StdHasher>(&self, state: &mut S) {
+        self.round.hash(state);
+        self.head.hash(state);
+    }
+}
+
+impl<H: Hasher> Checkpoint<H> {
+    pub fn new(round: Round, head: H::Hash) -> Self {
+        Checkpoint { round, head }
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn head(&self) -> H::Hash {
+        self.head
+    }
+}
+
+/// A checkpoint multisigned by enough members.
+pub type CheckpointCertificate<H, MK> = VoteCertificate<Checkpoint<H>, MK>;
+
+/// The collector of votes for the checkpoint of the given round.
+pub fn checkpoint_voting<H: Hasher, MK: MultiKeychain>(
+    keychain: MK,
+    session_id: SessionId,
+    round: Round,
+) -> VoteCollector<Checkpoint<H>, MK> {
+    VoteCollector::new(keychain, session_id, round.into())
+}
+
+/// Why a checkpoint certificate was rejected by [`verify_checkpoint`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheckpointError {
+    /// The certificate does not have enough valid signatures.
+    WrongSignatures,
+    /// The certificate belongs to a different session.
+    WrongSession,
+    /// The round of the voting differs from the round of the checkpoint.
+    WrongRound,
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CheckpointError::WrongSignatures => write!(f, "not enough valid signatures"),
+            CheckpointError::WrongSession => write!(f, "certificate of a different session"),
+            CheckpointError::WrongRound => {
+                write!(f, "voting round differs from the checkpoint round")
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+/// Checks the certificate of a checkpoint, e.g. configured at startup by the operator, returning
+/// the certified checkpoint.
+pub fn verify_checkpoint<H: Hasher, MK: MultiKeychain>(
+    certificate: UncheckedSigned<Vote<Checkpoint<H>>, MK::PartialMultisignature>,
+    keychain: &MK,
+    session_id: SessionId,
+) -> Result<Checkpoint<H>, CheckpointError> {
+    let certificate = certificate
+        .check_multi(keychain)
+        .map_err(|_| CheckpointError::WrongSignatures)?;
+    let vote = certificate.as_signable();
+    if vote.session_id() != session_id {
+        return Err(CheckpointError::WrongSession);
+    }
+    if vote.round() != vote.value().round().into() {
+        return Err(CheckpointError::WrongRound);
+    }
+    Ok(vote.value().clone())
+}
+
+/// Checkpoints of many rounds certified by a single aggregated multisignature, e.g. for bridges
+/// syncing many rounds at once, which verify one aggregate with [`verify_aggregated_checkpoints`]
+/// instead of every certificate. Created with [`aggregate_checkpoints`].
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct AggregatedCheckpoints<H: Hasher, A: Signature> {
+    session_id: SessionId,
+    checkpoints: Vec<Checkpoint<H>>,
+    multisignature: A,
+}
+
+impl<H: Hasher, A: Signature> AggregatedCheckpoints<H, A> {
+    pub fn checkpoints(&self) -> &[Checkpoint<H>] {
+        &self.checkpoints
+    }
+}
+
+/// Aggregates the certificates of a session into one, if the multisignature scheme supports
+/// that. `None` if there are no certificates, they belong to different sessions, or the keychain
+/// could not aggregate their multisignatures.
+pub fn aggregate_checkpoints<H: Hasher, MK: AggregatingMultiKeychain>(
+    certificates: &[CheckpointCertificate<H, MK>],
+    keychain: &MK,
+) -> Option<AggregatedCheckpoints<H, MK::AggregatedMultisignature>> {
+    let session_id = certificates.first()?.as_signable().session_id();
+    let mut checkpoints = Vec::with_capacity(certificates.len());
+    let mut multisignatures = Vec::with_capacity(certificates.len());
+    for certificate in certificates {
+        let unchecked = certificate.clone().into_unchecked();
+        if unchecked.as_signable().session_id() != session_id {
+            return None;
+        }
+        checkpoints.push(unchecked.as_signable().value().clone());
+        multisignatures.push(unchecked.signature());
+    }
+    let multisignature = keychain.aggregate(&multisignatures.iter().collect::<Vec<_>>())?;
+    Some(AggregatedCheckpoints {
+        session_id,
+        checkpoints,
+        multisignature,
+    })
+}
+
+/// Checks the aggregated certificates, returning the certified checkpoints.
+pub fn verify_aggregated_checkpoints<H: Hasher, MK: AggregatingMultiKeychain>(
+    aggregated: AggregatedCheckpoints<H, MK::AggregatedMultisignature>,
+    keychain: &MK,
+    session_id: SessionId,
+) -> Result<Vec<Checkpoint<H>>, CheckpointError> {
+    if aggregated.session_id != session_id {
+        return Err(CheckpointError::WrongSession);
+    }
+    // The votes are rebuilt from the checkpoints, so their rounds match.
+    let msgs: Vec<_> = aggregated
+        .checkpoints
+        .iter()
+        .map(|checkpoint| {
+            Vote::new(session_id, checkpoint.round().into(), checkpoint.clone()).hash()
+        })
+        .collect();
+    let msgs: Vec<&[u8]> = msgs.iter().map(|msg| msg.as_ref()).collect();
+    if !keychain.verify_aggregated(&msgs, &aggregated.multisignature) {
+        return Err(CheckpointError::WrongSignatures);
+    }
+    Ok(aggregated.checkpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        checkpoint_voting, verify_checkpoint, Checkpoint, CheckpointCertificate, CheckpointError,
+    };
+    use crate::{Hasher, MultiKeychain, NodeCount, NodeIndex, SessionId};
+    use aleph_bft_mock::{Hasher64, Keychain};
+
+    async fn certify<MK: MultiKeychain>(
+        keychains: &[MK],
+        session_id: SessionId,
+        checkpoint: &Checkpoint<Hasher64>,
+    ) -> CheckpointCertificate<Hasher64, MK> {
+        let mut collectors: Vec<_> = keychains
+            .iter()
+            .map(|keychain| checkpoint_voting(keychain.clone(), session_id, checkpoint.round()))
+            .collect();
+        let mut votes = Vec::new();
+        for collector in &collectors {
+            votes.push(collector.vote(checkpoint.clone()).await);
+        }
+        let mut certificate = None;
+        for vote in votes {
+            if let Some(complete) = collectors[0].add_vote(vote).expect("votes are correct") {
+                certificate = Some(complete);
+            }
+        }
+        certificate.expect("everyone voted for the checkpoint")
+    }
+
+    #[tokio::test]
+    async fn certified_checkpoints_verify() {
+        let n_members = NodeCount(4);
+        let session_id = SessionId::from_number(3);
+        let checkpoint = Checkpoint::<Hasher64>::new(12, Hasher64::hash(b"head"));
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let certificate = certify(&keychains, session_id, &checkpoint)
+            .await
+            .into_unchecked();
+
+        let observer = Keychain::new(n_members, NodeIndex(3));
+        assert_eq!(
+            verify_checkpoint(certificate.clone(), &observer, session_id),
+            Ok(checkpoint)
+        );
+        assert_eq!(
+            verify_checkpoint(certificate, &observer, SessionId::from_number(4)),
+            Err(CheckpointError::WrongSession)
+        );
+    }
+
+    #[cfg(feature = "bls")]
+    #[tokio::test]
+    async fn aggregated_checkpoints_verify() {
+        use super::{aggregate_checkpoints, verify_aggregated_checkpoints};
+        use crate::bls::generate_keychains;
+
+        let keychains = generate_keychains(NodeCount(4));
+        let session_id = SessionId::from_number(3);
+        let mut checkpoints = Vec::new();
+        let mut certificates = Vec::new();
+        for round in [4, 8, 12] {
+            let checkpoint = Checkpoint::<Hasher64>::new(round, Hasher64::hash(&[round as u8]));
+            certificates.push(certify(&keychains, session_id, &checkpoint).await);
+            checkpoints.push(checkpoint);
+        }
+
+        let aggregated = aggregate_checkpoints(&certificates, &keychains[0])
+            .expect("bls multisignatures aggregate");
+        assert_eq!(aggregated.checkpoints(), &checkpoints[..]);
+        assert_eq!(
+            verify_aggregated_checkpoints(aggregated.clone(), &keychains[1], session_id),
+            Ok(checkpoints)
+        );
+        assert_eq!(
+            verify_aggregated_checkpoints(aggregated, &keychains[1], SessionId::from_number(4)),
+            Err(CheckpointError::WrongSession)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{Receiver, Sender};
+use futures::channel::{mpsc::unbounded, oneshot};
+use std::time::Duration;
+
+/// What pruning the unit store achieved, see [`Compaction`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CompactionReport {
+    /// How many finalized units were pruned from the store.
+    pub pruned_units: usize,
+    /// How many bytes less the units in the store take afterwards.
+    pub reclaimed_bytes: usize,
+    /// How long the pruning took.
+    pub duration: Duration,
+}
+
+/// A handle for compacting the unit store of a running session on demand, e.g. to schedule the
+/// pruning off-peak instead of only when the store exceeds [`ResourceBudget::memory_cap`]. All
+/// clones compact the store of the same session.
+///
+/// [`ResourceBudget::memory_cap`]: crate::ResourceBudget::memory_cap
+#[derive(Clone)]
+pub struct Compaction {
+    requests: Sender<oneshot::Sender<CompactionReport>>,
+}
+
+impl Compaction {
+    /// Prunes all the finalized units that can be pruned from the store, i.e. all but the top
+    /// units of their creators and the units of forkers. `None` if the session ended.
+    pub async fn compact(&self) -> Option<CompactionReport> {
+        let (response, report) = oneshot::channel();
+        self.requests.unbounded_send(response).ok()?;
+        report.await.ok()
+    }
+}
+
+pub(crate) fn compaction() -> (Compaction, Receiver<oneshot::Sender<CompactionReport>>) {
+    let (requests, requests_rx) = unbounded();
+    (Compaction { requests }, requests_rx)
+}
@@ -1,6 +1,11 @@
-use crate::{NodeCount, NodeIndex, Round, SessionId};
+use crate::{
+    DomainKeychain, Keychain, MultiKeychain, NodeCount, NodeIndex, Round, SessionId,
+    WeightedMultiKeychain,
+};
 use std::{
+    collections::HashMap,
     fmt::{Debug, Formatter},
+    net::SocketAddr,
     sync::Arc,
     time::Duration,
 };
@@ -38,6 +43,13 @@ pub struct DelayConfig {
     /// newest_request_delay(k) represents the delay between the kth and (k+1)st try when sending
     /// a broadcast request for newest units
     pub newest_request_delay: DelaySchedule,
+    /// If the tick of the Member arrives later than expected by more than this, we assume the
+    /// process was suspended (e.g. OS sleep or a long pause) and immediately rebroadcast our top
+    /// units and retry all pending requests, instead of waiting for their scheduled time.
+    pub clock_jump_threshold: Duration,
+    /// How long a component waits for its subcomponents to stop when shutting down, before
+    /// aborting the ones that are stuck.
+    pub shutdown_timeout: Duration,
 }
 
 impl Debug for DelayConfig {
@@ -52,10 +64,243 @@ impl Debug for DelayConfig {
                 "max unit rebroadcast interval",
                 &self.unit_rebroadcast_interval_max,
             )
+            .field("clock jump threshold", &self.clock_jump_threshold)
+            .field("shutdown timeout", &self.shutdown_timeout)
             .finish()
     }
 }
 
+/// Policy deciding which parents to wait for when creating a unit.
+///
+/// A unit can always be created with floor(2N/3)+1 parents including our own previous unit, the
+/// policy can only make the creator wait for more. If the preferred parents do not arrive within
+/// `timeout` after the creation delay passes, the unit is created with whatever is available.
+/// The default policy does not wait at all.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParentPolicy {
+    /// Number of parents to wait for, values below the minimum are ignored.
+    pub preferred_parents: NodeCount,
+    /// Nodes whose units should be included as parents, nodes outside the committee are ignored.
+    pub must_include: Vec<NodeIndex>,
+    /// How long to wait for the preferred parents before falling back to the minimum.
+    pub timeout: Duration,
+}
+
+impl ParentPolicy {
+    /// Whether the policy ever makes the creator wait for more than the minimal quorum.
+    pub fn is_trivial(&self) -> bool {
+        self.timeout.is_zero() || (self.preferred_parents.0 == 0 && self.must_include.is_empty())
+    }
+}
+
+/// Limits on the resources a single session is expected to use. Crossing a limit does not stop
+/// the session, but results in warnings, so that hosts running several committees can notice
+/// which of them misbehaves.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResourceBudget {
+    /// Approximate number of bytes the units held in memory may take, `None` means no limit.
+    pub memory: Option<usize>,
+    /// Hard cap on the approximate number of bytes the units held in memory take, `None` means
+    /// no cap. Unlike the other limits, crossing it prunes finalized units of the lowest rounds
+    /// until the units fit again. Pruned units are no longer sent to peers requesting them,
+    /// and received again they are ignored, so the cap should leave room for many rounds.
+    pub memory_cap: Option<usize>,
+    /// Number of bytes per second handed to the network, `None` means no limit. A message
+    /// addressed to everyone is counted once.
+    pub bandwidth: Option<usize>,
+}
+
+/// Bounds on the number of alerts kept while their commitments are being multisigned. Once a
+/// bound is crossed the oldest incomplete alert about the forker, or of the node that sent the
+/// most incomplete alerts, is dropped, so that a malicious node sending many alerts cannot
+/// exhaust our memory nor push out the alerts of others. Only one alert of every node about
+/// every forker is kept, and alerts raised by ourselves are never dropped.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AlertLimits {
+    /// Maximal number of incomplete alerts about a single forker. Every node might alert about
+    /// every forker, so this should be at least the size of the committee.
+    pub per_forker: usize,
+    /// Maximal number of incomplete alerts in total.
+    pub total: usize,
+}
+
+impl Default for AlertLimits {
+    fn default() -> Self {
+        AlertLimits {
+            per_forker: 128,
+            total: 1024,
+        }
+    }
+}
+
+/// Bounds on the number of requests for missing units awaiting a response at the same time. The
+/// actual number is adjusted between 1 and `max`, growing while responses arrive quickly and
+/// shrinking on timeouts or when responses slow down.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestConcurrency {
+    /// Number of outstanding requests allowed at the start of the session.
+    pub initial: usize,
+    /// Maximal number of outstanding requests.
+    pub max: usize,
+}
+
+impl Default for RequestConcurrency {
+    fn default() -> Self {
+        RequestConcurrency {
+            initial: 16,
+            max: 512,
+        }
+    }
+}
+
+/// Peers to ask first for missing units, e.g. dedicated archive nodes, so that nodes catching up
+/// put the load of their requests on these rather than on the active validators. The archive
+/// nodes still have to be members of the committee, as only members answer requests.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CatchUpSources {
+    /// The preferred peers, our own index is ignored.
+    pub preferred: Vec<NodeIndex>,
+    /// Number of tries of a request only asking the preferred peers, after which any peers are
+    /// asked, in case the preferred ones are down or lag behind us.
+    pub fallback_after: usize,
+}
+
+impl Default for CatchUpSources {
+    fn default() -> Self {
+        CatchUpSources {
+            preferred: Vec::new(),
+            fallback_after: 3,
+        }
+    }
+}
+
+/// Hints about where the peers are, so that missing units are requested from peers behind cheap
+/// links more often than from remote ones, e.g. to cut the inter-region traffic of a
+/// geo-distributed committee. Remote peers are still asked, only less often, in case the nearby
+/// ones are down. Broadcasts reach everyone regardless.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TopologyHints {
+    /// The peers in the same datacenter as us.
+    pub same_datacenter: Vec<NodeIndex>,
+    /// Region tags of the nodes, including ours. Peers tagged like us are in our region.
+    pub regions: HashMap<NodeIndex, String>,
+    /// How many times more likely a peer in our datacenter is asked than a remote one.
+    pub datacenter_weight: u32,
+    /// How many times more likely a peer in our region is asked than a remote one.
+    pub region_weight: u32,
+}
+
+impl TopologyHints {
+    /// The relative likelihood of asking the peer, at least one.
+    pub fn weight(&self, own: NodeIndex, peer: NodeIndex) -> u32 {
+        let same_region = match (self.regions.get(&own), self.regions.get(&peer)) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => false,
+        };
+        let weight = match (self.same_datacenter.contains(&peer), same_region) {
+            (true, _) => self.datacenter_weight,
+            (false, true) => self.region_weight,
+            (false, false) => 1,
+        };
+        weight.max(1)
+    }
+}
+
+impl Default for TopologyHints {
+    fn default() -> Self {
+        TopologyHints {
+            same_datacenter: Vec::new(),
+            regions: HashMap::new(),
+            datacenter_weight: 16,
+            region_weight: 4,
+        }
+    }
+}
+
+/// How thoroughly units received from other nodes are validated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ValidationProfile {
+    /// Checks the signature, the structure of the unit and, if configured, its payload.
+    Strict,
+    /// Checks the signature and the structure of the unit, but neither its payload nor its VRF
+    /// proof, which only has to be present. Suitable only for replaying units of a trusted
+    /// committee, e.g. by an observer, as a signing node can make up its priorities.
+    Light,
+    /// Everything checked by `Strict`, and additionally invariants that are guaranteed by
+    /// honest implementations, also for units we created ourselves. Slower, meant for testing
+    /// and for investigating misbehaving deployments.
+    Audit,
+}
+
+impl Default for ValidationProfile {
+    fn default() -> Self {
+        ValidationProfile::Strict
+    }
+}
+
+/// What to do with the data of units created by nodes known to have forked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ForkerDataPolicy {
+    /// Deliver the data of all ordered units, including the legal units of forkers.
+    Deliver,
+    /// Deliver the units of forkers without their data. Only units ordered after we learn about
+    /// the fork are affected, and nodes learn about it at slightly different times, so the data
+    /// delivered around that point might differ between nodes. Applications that need identical
+    /// outputs on all nodes should filter the data themselves.
+    Exclude,
+}
+
+impl Default for ForkerDataPolicy {
+    fn default() -> Self {
+        ForkerDataPolicy::Deliver
+    }
+}
+
+/// How to respond to units we already have, e.g. rebroadcast by a creator that lost track of
+/// our acknowledgements.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DuplicateUnitPolicy {
+    /// Acknowledge the newest unit of the creator, and stay silent about older units.
+    Ignore,
+    /// Additionally tell the creator the highest rounds we have of every creator, so that it can
+    /// stop rebroadcasting everything we already have, at most once per the interval for every
+    /// creator. Every such hint is a full round map, so in adversarial settings a node sending
+    /// us duplicates could otherwise make us amplify its traffic.
+    Hint { interval: Duration },
+}
+
+impl Default for DuplicateUnitPolicy {
+    fn default() -> Self {
+        DuplicateUnitPolicy::Ignore
+    }
+}
+
+/// What to do when an internal invariant of the consensus is violated, see
+/// [`InvariantViolation`]. Deployments differ in whether they prefer safety or liveness then.
+///
+/// [`InvariantViolation`]: crate::InvariantViolation
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum InvariantPolicy {
+    /// Only log the violation and carry on as if nothing happened, the default, as before the
+    /// policy could be configured.
+    Log,
+    /// Panic, stopping the whole process.
+    Panic,
+    /// End the session, reporting the violation in the [`HealthReport`].
+    ///
+    /// [`HealthReport`]: crate::HealthReport
+    Halt,
+    /// Keep following the ordering as an observer, without adding units we create to the Dag,
+    /// so that a corrupted node does not influence others.
+    Observe,
+}
+
+impl Default for InvariantPolicy {
+    fn default() -> Self {
+        InvariantPolicy::Log
+    }
+}
+
 /// Main configuration of the consensus. We refer to [the documentation](https://cardinal-cryptography.github.io/AlephBFT/aleph_bft_api.html#34-alephbft-sessions)
 /// Section 3.4 for a discussion of some of these parameters and their significance.
 #[derive(Clone, Debug)]
@@ -70,6 +315,109 @@ pub struct Config {
     pub delay_config: DelayConfig,
     /// Maximum allowable round of a unit.
     pub max_round: Round,
+    /// Resources the session is expected to fit in.
+    pub resource_budget: ResourceBudget,
+    /// Which parents to wait for when creating units.
+    pub parent_policy: ParentPolicy,
+    /// Whether to alert other nodes about forks. Without alerts the first variant of a unit
+    /// seen is kept and all other variants are ignored, so a single forker can stall the
+    /// session -- this only makes sense for trusted deployments, e.g. in a lab.
+    pub fork_alerts: bool,
+    /// Bounds on the memory used by alerts in progress.
+    pub alert_limits: AlertLimits,
+    /// How many requests for missing units may await a response at the same time.
+    pub request_concurrency: RequestConcurrency,
+    /// Which peers to ask for missing units.
+    pub catch_up_sources: CatchUpSources,
+    /// Where the peers are, biasing whom missing units are requested from.
+    pub topology: TopologyHints,
+    /// If set, requests of a node for units more than this many rounds below the newest unit of
+    /// the node we know are dropped without answering. Such requests were usually queued for a
+    /// long time, e.g. during a partition, and the node already caught up in the meantime -- if
+    /// it still needs the units it repeats the request.
+    pub request_ttl: Option<Round>,
+    /// If set, our units more than this many rounds below the highest round we created a unit
+    /// of are not served to peers requesting them, so that serving the history of a long session
+    /// does not take unbounded bandwidth. Such requests are answered with the lowest round we
+    /// still serve, the requesters then ask other peers, like the [`CatchUpSources`], e.g.
+    /// archive nodes, or confirm the history they obtained with a certified [`Checkpoint`].
+    ///
+    /// [`Checkpoint`]: crate::Checkpoint
+    pub history_limit: Option<Round>,
+    /// If set, our units more than this many rounds behind the round reached by a quorum of
+    /// nodes are not broadcast. Such units are too stale to become parents of units of others,
+    /// so they are only needed by peers validating our later units, who request them then.
+    /// They are created nevertheless, as by the growth rule each of our units needs our unit of
+    /// the previous round as a parent, so only the broadcast is suppressed.
+    pub lag_suppression: Option<Round>,
+    /// How thoroughly units are validated.
+    pub validation: ValidationProfile,
+    /// Whether the data of units created by known forkers ends up in ordered batches.
+    pub forker_data: ForkerDataPolicy,
+    /// How to respond to units we already have.
+    pub duplicate_units: DuplicateUnitPolicy,
+    /// How many units are kept when their signatures cannot be verified yet, because the
+    /// keychain does not know the keys of their creators, see [`Keychain::has_key`]. They are
+    /// verified again once it does. Zero rejects such units right away.
+    ///
+    /// [`Keychain::has_key`]: crate::Keychain::has_key
+    pub quarantine_capacity: usize,
+    /// How many signatures of correctly signed units are remembered, so that units received
+    /// again, e.g. rebroadcast or sent in response to a request, are not verified again. Zero
+    /// disables the cache.
+    pub verification_cache_capacity: usize,
+    /// How many threads verify the signatures of batches of received units, e.g. when catching
+    /// up. Single units, and batches if this is at most one, are verified by the member itself.
+    pub verification_threads: usize,
+    /// What to do when an internal invariant is violated, only logging it by default.
+    pub invariant_policy: InvariantPolicy,
+    /// The weights, e.g. stakes, of the nodes in stake-weighted deployments, the node with index
+    /// `i` carries `weights[i]`. Multisignatures respect them only when made with the keychain
+    /// returned by [`Config::weighted_keychain`], without weights every node carries one.
+    pub weights: Option<Vec<u64>>,
+    /// Separates the signatures of the session from signatures made with the same keys for
+    /// anything else. With a purpose, every message is signed prefixed with the session id and
+    /// the purpose byte, see [`Config::signing_domain`]. All the members have to agree on it,
+    /// and versions without it only accept signatures of sessions without a purpose.
+    pub signing_purpose: Option<u8>,
+    /// Where the nodes listen for connections, the node with index `i` at `addresses[i]`, used
+    /// by the built-in TCP transport of the `tcp` feature, see `tcp::TcpNetwork`. Empty when the
+    /// application brings its own network.
+    pub addresses: Vec<SocketAddr>,
+    /// How many units the terminal and the extender handle in a row before letting the other
+    /// tasks of the executor run. Without that a huge batch of units, e.g. received while
+    /// catching up, keeps them busy for long, starving the timers of requests sharing their
+    /// thread, which then time out spuriously. Zero never yields.
+    pub yield_interval: usize,
+}
+
+impl Config {
+    /// The keychain weighing the signers of multisignatures with the [`Config::weights`], to be
+    /// passed to [`run_session`] instead of the keychain itself.
+    ///
+    /// [`run_session`]: crate::run_session
+    pub fn weighted_keychain<MK: MultiKeychain>(&self, keychain: MK) -> WeightedMultiKeychain<MK> {
+        let weights = self
+            .weights
+            .clone()
+            .unwrap_or_else(|| vec![1; self.n_members.0]);
+        WeightedMultiKeychain::new(keychain, weights)
+    }
+
+    /// The tag prefixing the messages signed in the session, the session id followed by the
+    /// [`Config::signing_purpose`], empty without a purpose.
+    pub fn signing_domain(&self) -> Vec<u8> {
+        self.session_id.signing_domain(self.signing_purpose)
+    }
+
+    /// The keychain signing in the [`Config::signing_domain`], as the session does, e.g. for
+    /// checking certificates made by the session outside of it. [`run_session`] wraps its
+    /// keychain in it by itself.
+    ///
+    /// [`run_session`]: crate::run_session
+    pub fn domain_keychain<K: Keychain>(&self, keychain: K) -> DomainKeychain<K> {
+        DomainKeychain::new(keychain, self.signing_domain())
+    }
 }
 
 pub fn exponential_slowdown(
@@ -108,8 +456,31 @@ pub fn default_config(n_members: NodeCount, node_ix: NodeIndex, session_id: Sess
             parent_request_delay: Arc::new(|_| Duration::from_millis(3000)),
             parent_request_recipients: Arc::new(|_| 1),
             newest_request_delay: Arc::new(|_| Duration::from_millis(3000)),
+            clock_jump_threshold: Duration::from_millis(5000),
+            shutdown_timeout: Duration::from_secs(10),
         },
         max_round: 5000,
+        resource_budget: ResourceBudget::default(),
+        parent_policy: ParentPolicy::default(),
+        fork_alerts: true,
+        alert_limits: AlertLimits::default(),
+        request_concurrency: RequestConcurrency::default(),
+        catch_up_sources: CatchUpSources::default(),
+        topology: TopologyHints::default(),
+        request_ttl: None,
+        history_limit: None,
+        lag_suppression: None,
+        validation: ValidationProfile::default(),
+        forker_data: ForkerDataPolicy::default(),
+        duplicate_units: DuplicateUnitPolicy::default(),
+        quarantine_capacity: 1000,
+        verification_cache_capacity: 10000,
+        verification_threads: 1,
+        invariant_policy: InvariantPolicy::default(),
+        weights: None,
+        signing_purpose: None,
+        addresses: Vec::new(),
+        yield_interval: 100,
     }
 }
 
@@ -11,7 +11,7 @@ use crate::{
     handle_task_termination,
     runway::{NotificationIn, NotificationOut},
     terminal::Terminal,
-    Hasher, Receiver, Round, Sender, SpawnHandle, Terminator,
+    Hasher, Health, OrderingLog, Receiver, Round, Sender, SpawnHandle, Terminator,
 };
 
 pub(crate) async fn run<H: Hasher + 'static>(
@@ -21,36 +21,56 @@ pub(crate) async fn run<H: Hasher + 'static>(
     ordered_batch_tx: Sender<Vec<H::Hash>>,
     spawn_handle: impl SpawnHandle,
     starting_round: oneshot::Receiver<Option<Round>>,
+    health: Health,
+    ordering_log: Option<OrderingLog>,
     mut terminator: Terminator,
 ) {
     debug!(target: "AlephBFT", "{:?} Starting all services...", conf.node_ix);
 
     let n_members = conf.n_members;
     let index = conf.node_ix;
+    let yield_interval = conf.yield_interval;
 
     let (electors_tx, electors_rx) = mpsc::unbounded();
-    let mut extender = Extender::<H>::new(index, n_members, electors_rx, ordered_batch_tx);
-    let extender_terminator = terminator.add_offspring_connection("AlephBFT-extender");
-    let mut extender_handle = spawn_handle
-        .spawn_essential("consensus/extender", async move {
+    let mut extender = Extender::<H>::new(
+        index,
+        n_members,
+        electors_rx,
+        ordered_batch_tx,
+        health.clone(),
+        ordering_log,
+    )
+    .with_yield_interval(yield_interval);
+    let extender_task = terminator
+        .add_offspring_task("AlephBFT-extender", |extender_terminator| async move {
             extender.extend(extender_terminator).await
-        })
+        });
+    let mut extender_handle = spawn_handle
+        .spawn_essential("consensus/extender", extender_task)
         .fuse();
 
     let (parents_for_creator, parents_from_terminal) = mpsc::unbounded();
 
-    let creator_terminator = terminator.add_offspring_connection("creator");
     let io = creation::IO {
         outgoing_units: outgoing_notifications.clone(),
         incoming_parents: parents_from_terminal,
+        health: health.clone(),
     };
+    let creator_task = terminator.add_offspring_task("creator", |creator_terminator| async move {
+        creation::run(conf.clone().into(), io, starting_round, creator_terminator).await;
+    });
     let mut creator_handle = spawn_handle
-        .spawn_essential("consensus/creation", async move {
-            creation::run(conf.clone().into(), io, starting_round, creator_terminator).await;
-        })
+        .spawn_essential("consensus/creation", creator_task)
         .fuse();
 
-    let mut terminal = Terminal::new(index, incoming_notifications, outgoing_notifications);
+    let mut terminal = Terminal::new(
+        index,
+        n_members,
+        incoming_notifications,
+        outgoing_notifications,
+        health,
+    )
+    .with_yield_interval(yield_interval);
 
     // send a new parent candidate to the creator
     terminal.register_post_insert_hook(Box::new(move |u| {
@@ -65,11 +85,12 @@ pub(crate) async fn run<H: Hasher + 'static>(
             .expect("Channel to extender should be open.")
     }));
 
-    let terminal_terminator = terminator.add_offspring_connection("terminal");
-    let mut terminal_handle = spawn_handle
-        .spawn_essential("consensus/terminal", async move {
+    let terminal_task = terminator
+        .add_offspring_task("terminal", |terminal_terminator| async move {
             terminal.run(terminal_terminator).await
-        })
+        });
+    let mut terminal_handle = spawn_handle
+        .spawn_essential("consensus/terminal", terminal_task)
         .fuse();
     debug!(target: "AlephBFT", "{:?} All services started.", index);
 
@@ -0,0 +1,81 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+// Returns pending once, waking itself right away, so that the executor gets to run other tasks
+// before polling it again.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Makes a task yield to the other tasks of the executor every so many steps of a long loop.
+/// Tasks reading from channels that are always ready, e.g. while catching up, are never
+/// suspended otherwise, and can starve timers sharing their thread.
+pub(crate) struct Cooperation {
+    interval: usize,
+    steps: usize,
+}
+
+impl Cooperation {
+    /// Yields every `interval` steps, never if it is zero.
+    pub(crate) fn new(interval: usize) -> Self {
+        Cooperation { interval, steps: 0 }
+    }
+
+    /// Registers a step, yielding if it is the time to.
+    pub(crate) async fn step(&mut self) {
+        if self.interval == 0 {
+            return;
+        }
+        self.steps += 1;
+        if self.steps >= self.interval {
+            self.steps = 0;
+            YieldNow { yielded: false }.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cooperation;
+    use futures::{pin_mut, poll, task::Poll};
+
+    async fn polls_of_step(cooperation: &mut Cooperation) -> usize {
+        let step = cooperation.step();
+        pin_mut!(step);
+        let mut polls = 1;
+        while poll!(step.as_mut()) == Poll::Pending {
+            polls += 1;
+        }
+        polls
+    }
+
+    #[tokio::test]
+    async fn yields_every_interval() {
+        let mut cooperation = Cooperation::new(2);
+        assert_eq!(polls_of_step(&mut cooperation).await, 1);
+        assert_eq!(polls_of_step(&mut cooperation).await, 2);
+        assert_eq!(polls_of_step(&mut cooperation).await, 1);
+        assert_eq!(polls_of_step(&mut cooperation).await, 2);
+
+        let mut never = Cooperation::new(0);
+        for _ in 0..10 {
+            assert_eq!(polls_of_step(&mut never).await, 1);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    config::ParentPolicy,
     units::{ControlHash, PreUnit, Unit},
     Hasher, NodeCount, NodeIndex, NodeMap, Round,
 };
@@ -41,7 +42,7 @@ impl<H: Hasher> UnitsCollector<H> {
         &self,
         node_id: NodeIndex,
     ) -> Result<&NodeMap<H::Hash>, ConstraintError> {
-        let threshold = (self.candidates.size() * 2) / 3 + NodeCount(1);
+        let threshold = self.candidates.size().quorum();
 
         if self.n_candidates < threshold {
             return Err(ConstraintError::NotEnoughParents);
@@ -51,6 +52,16 @@ impl<H: Hasher> UnitsCollector<H> {
         }
         Ok(&self.candidates)
     }
+
+    pub fn satisfies(&self, policy: &ParentPolicy) -> bool {
+        let preferred = std::cmp::min(policy.preferred_parents, self.candidates.size());
+        self.n_candidates >= preferred
+            && policy
+                .must_include
+                .iter()
+                .filter(|node_id| node_id.0 < self.candidates.size().0)
+                .all(|node_id| self.candidates.get(*node_id).is_some())
+    }
 }
 
 fn create_unit<H: Hasher>(
@@ -113,6 +124,18 @@ impl<H: Hasher> Creator<H> {
         Ok(create_unit(self.node_id, parents.clone(), round))
     }
 
+    /// Whether the parents available for a unit of the given round satisfy the given policy,
+    /// on top of the minimal requirements checked by `create_unit`.
+    pub fn has_preferred_parents(&self, round: Round, policy: &ParentPolicy) -> bool {
+        if round == 0 {
+            return true;
+        }
+        self.round_collectors
+            .get(usize::from(round - 1))
+            .map(|collector| collector.satisfies(policy))
+            .unwrap_or(false)
+    }
+
     pub fn add_unit(&mut self, unit: &Unit<H>) {
         self.get_or_initialize_collector_for_round(unit.round())
             .add_unit(unit);
@@ -123,12 +146,13 @@ impl<H: Hasher> Creator<H> {
 mod tests {
     use super::{Creator as GenericCreator, UnitsCollector};
     use crate::{
+        config::ParentPolicy,
         creation::creator::ConstraintError,
         units::{create_units, creator_set, preunit_to_unit},
-        NodeCount, NodeIndex,
+        NodeCount, NodeIndex, SessionId,
     };
     use aleph_bft_mock::Hasher64;
-    use std::collections::HashSet;
+    use std::{collections::HashSet, time::Duration};
 
     type Creator = GenericCreator<Hasher64>;
 
@@ -152,7 +176,7 @@ mod tests {
         let new_units = create_units(creators.iter(), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
         let expected_hashes: Vec<_> = new_units.iter().map(|u| u.hash()).collect();
         let creator = &mut creators[0];
@@ -167,12 +191,12 @@ mod tests {
     }
 
     fn create_unit_with_minimal_parents(n_members: NodeCount) {
-        let n_parents = (n_members.0 * 2) / 3 + 1;
+        let n_parents = n_members.quorum().0;
         let mut creators = creator_set(n_members);
         let new_units = create_units(creators.iter().take(n_parents), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
         let expected_hashes: Vec<_> = new_units.iter().map(|u| u.hash()).collect();
         let creator = &mut creators[0];
@@ -186,6 +210,16 @@ mod tests {
         assert_eq!(parent_hashes, expected_hashes);
     }
 
+    #[test]
+    fn creates_unit_with_minimal_parents_1() {
+        create_unit_with_minimal_parents(NodeCount(1));
+    }
+
+    #[test]
+    fn creates_unit_with_minimal_parents_2() {
+        create_unit_with_minimal_parents(NodeCount(2));
+    }
+
     #[test]
     fn creates_unit_with_minimal_parents_4() {
         create_unit_with_minimal_parents(NodeCount(4));
@@ -207,12 +241,12 @@ mod tests {
     }
 
     fn dont_create_unit_below_parents_threshold(n_members: NodeCount) {
-        let n_parents = (n_members.0 * 2) / 3;
+        let n_parents = n_members.quorum().0 - 1;
         let mut creators = creator_set(n_members);
         let new_units = create_units(creators.iter().take(n_parents), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
         let creator = &mut creators[0];
         creator.add_units(&new_units);
@@ -221,6 +255,16 @@ mod tests {
         assert!(creator.create_unit(round).is_err())
     }
 
+    #[test]
+    fn cannot_create_unit_below_parents_threshold_1() {
+        dont_create_unit_below_parents_threshold(NodeCount(1));
+    }
+
+    #[test]
+    fn cannot_create_unit_below_parents_threshold_2() {
+        dont_create_unit_below_parents_threshold(NodeCount(2));
+    }
+
     #[test]
     fn cannot_create_unit_below_parents_threshold_4() {
         dont_create_unit_below_parents_threshold(NodeCount(4));
@@ -250,7 +294,7 @@ mod tests {
             let new_units = create_units(creators.iter().skip(1), round);
             let new_units: Vec<_> = new_units
                 .into_iter()
-                .map(|(pu, _)| preunit_to_unit(pu, 0))
+                .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
                 .collect();
             let expected_hashes: HashSet<_> = new_units.iter().map(|u| u.hash()).collect();
             for creator in creators.iter_mut() {
@@ -272,7 +316,7 @@ mod tests {
                     expected_hashes_per_round[(round - 1) as usize]
                 );
             }
-            let unit = preunit_to_unit(preunit, 0);
+            let unit = preunit_to_unit(preunit, SessionId::from_number(0));
             creator.add_unit(&unit);
             if round < 2 {
                 expected_hashes_per_round[round as usize].insert(unit.hash());
@@ -287,7 +331,7 @@ mod tests {
         let new_units = create_units(creators.iter().skip(1), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
         let creator = &mut creators[0];
         creator.add_units(&new_units);
@@ -302,7 +346,7 @@ mod tests {
         let new_units = create_units(creators.iter(), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
 
         let mut units_collector = UnitsCollector::new(n_members);
@@ -327,7 +371,7 @@ mod tests {
         let new_units = create_units(creators.iter().take(2), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
 
         let mut units_collector = UnitsCollector::new(n_members);
@@ -349,7 +393,7 @@ mod tests {
         let new_units = create_units(creators.iter().take(3), 0);
         let new_units: Vec<_> = new_units
             .into_iter()
-            .map(|(pu, _)| preunit_to_unit(pu, 0))
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
             .collect();
 
         let mut units_collector = UnitsCollector::new(n_members);
@@ -363,4 +407,29 @@ mod tests {
             ConstraintError::MissingOwnParent
         );
     }
+
+    #[test]
+    fn checks_preferred_parents() {
+        let n_members = NodeCount(7);
+        let mut creators = creator_set(n_members);
+        let new_units = create_units(creators.iter().take(5), 0);
+        let new_units: Vec<_> = new_units
+            .into_iter()
+            .map(|(pu, _)| preunit_to_unit(pu, SessionId::from_number(0)))
+            .collect();
+        let creator = &mut creators[0];
+        creator.add_units(&new_units);
+        let policy = |preferred_parents, must_include| ParentPolicy {
+            preferred_parents: NodeCount(preferred_parents),
+            must_include,
+            timeout: Duration::from_secs(1),
+        };
+        assert!(creator.create_unit(1).is_ok());
+        assert!(creator.has_preferred_parents(1, &policy(5, vec![])));
+        assert!(!creator.has_preferred_parents(1, &policy(6, vec![])));
+        assert!(creator.has_preferred_parents(1, &policy(0, vec![NodeIndex(4)])));
+        assert!(!creator.has_preferred_parents(1, &policy(0, vec![NodeIndex(5)])));
+        assert!(creator.has_preferred_parents(1, &policy(0, vec![NodeIndex(7)])));
+        assert!(!creator.has_preferred_parents(2, &policy(5, vec![])));
+    }
 }
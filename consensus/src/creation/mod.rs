@@ -1,8 +1,8 @@
 use crate::{
-    config::{Config as GeneralConfig, DelaySchedule},
+    config::{Config as GeneralConfig, DelaySchedule, ParentPolicy},
     runway::NotificationOut,
     units::{PreUnit, Unit},
-    Hasher, NodeCount, NodeIndex, Receiver, Round, Sender, Terminator,
+    Hasher, Health, NodeCount, NodeIndex, Receiver, Round, Sender, Subsystem, Terminator,
 };
 use futures::{
     channel::{
@@ -26,6 +26,7 @@ pub struct Config {
     n_members: NodeCount,
     create_lag: DelaySchedule,
     max_round: Round,
+    parent_policy: ParentPolicy,
 }
 
 impl Debug for Config {
@@ -34,6 +35,7 @@ impl Debug for Config {
             .field("node id", &self.node_id)
             .field("member count", &self.n_members)
             .field("max round", &self.max_round)
+            .field("parent policy", &self.parent_policy)
             .finish()
     }
 }
@@ -45,6 +47,7 @@ impl From<GeneralConfig> for Config {
             n_members: conf.n_members,
             create_lag: conf.delay_config.unit_creation_delay,
             max_round: conf.max_round,
+            parent_policy: conf.parent_policy,
         }
     }
 }
@@ -63,6 +66,7 @@ impl<T> From<TrySendError<T>> for CreatorError {
 pub struct IO<H: Hasher> {
     pub(crate) incoming_parents: Receiver<Unit<H>>,
     pub(crate) outgoing_units: Sender<NotificationOut<H>>,
+    pub(crate) health: Health,
 }
 
 async fn create_unit<H: Hasher>(
@@ -81,6 +85,27 @@ async fn create_unit<H: Hasher>(
     }
 }
 
+/// Keeps processing units until the parents for a unit of the given round satisfy the policy, or
+/// the policy timeout passes.
+async fn wait_for_preferred_parents<H: Hasher>(
+    round: Round,
+    creator: &mut Creator<H>,
+    incoming_parents: &mut Receiver<Unit<H>>,
+    policy: &ParentPolicy,
+) -> Result<(), CreatorError> {
+    let mut timeout = Delay::new(policy.timeout).fuse();
+    while !creator.has_preferred_parents(round, policy) {
+        futures::select! {
+            result = process_unit(creator, incoming_parents).fuse() => result?,
+            _ = &mut timeout => {
+                debug!(target: "AlephBFT-creator", "Preferred parents for round {} did not arrive in time.", round);
+                break;
+            },
+        }
+    }
+    Ok(())
+}
+
 /// Tries to process a single parent from given `incoming_parents` receiver.
 /// Returns error when `incoming_parents` channel is closed.
 async fn process_unit<H: Hasher>(
@@ -129,7 +154,9 @@ async fn keep_processing_units_until<H: Hasher>(
 /// - U has > floor(2*N/3) parents.
 /// - U will appear in the channel only if all U's parents appeared there before
 /// The currently implemented strategy creates the unit U according to a delay schedule and when enough
-/// candidates for parents are available for all the above constraints to be satisfied.
+/// candidates for parents are available for all the above constraints to be satisfied. If the
+/// configured [`ParentPolicy`] asks for more parents, the creator additionally waits for them,
+/// but no longer than the policy timeout.
 ///
 /// We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/internals.html
 /// Section 5.1 for a discussion of this component.
@@ -189,10 +216,12 @@ async fn run_creator<H: Hasher>(
         n_members,
         create_lag,
         max_round,
+        parent_policy,
     } = conf;
     let mut creator = Creator::new(node_id, n_members);
     let incoming_parents = &mut io.incoming_parents;
     let outgoing_units = &io.outgoing_units;
+    let health = &io.health;
 
     debug!(target: "AlephBFT-creator", "Creator starting from round {}", starting_round);
     for round in starting_round..max_round {
@@ -204,6 +233,11 @@ async fn run_creator<H: Hasher>(
             let lag = Delay::new(create_lag(round.into()));
 
             keep_processing_units_until(&mut creator, incoming_parents, lag).await?;
+
+            if !parent_policy.is_trivial() {
+                wait_for_preferred_parents(round, &mut creator, incoming_parents, &parent_policy)
+                    .await?;
+            }
         }
 
         let (unit, parent_hashes) = create_unit(round, &mut creator, incoming_parents).await?;
@@ -211,6 +245,7 @@ async fn run_creator<H: Hasher>(
         trace!(target: "AlephBFT-creator", "Created a new unit {:?} at round {:?}.", unit, round);
 
         outgoing_units.unbounded_send(NotificationOut::CreatedPreUnit(unit, parent_hashes))?;
+        health.on_activity(Subsystem::Creator);
     }
 
     warn!(target: "AlephBFT-creator", "Maximum round reached. Not creating another unit.");
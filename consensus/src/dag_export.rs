@@ -0,0 +1,103 @@
+use crate::{units::UnitCoord, Hasher, Receiver, Sender};
+use futures::channel::{mpsc::unbounded, oneshot};
+use std::{collections::HashSet, fmt::Write};
+
+/// A handle for exporting the Dag of a running session in the DOT format of Graphviz, e.g. to
+/// visualize it when explaining a stall. All clones export the Dag of the same session.
+#[derive(Clone)]
+pub struct DagExport {
+    requests: Sender<oneshot::Sender<String>>,
+}
+
+impl DagExport {
+    /// The units currently in the Dag, labeled with their creators and rounds, with edges
+    /// pointing at their parents and the heads of finalized rounds highlighted. `None` if the
+    /// session ended.
+    pub async fn dot(&self) -> Option<String> {
+        let (response, dot) = oneshot::channel();
+        self.requests.unbounded_send(response).ok()?;
+        dot.await.ok()
+    }
+}
+
+pub(crate) fn dag_export() -> (DagExport, Receiver<oneshot::Sender<String>>) {
+    let (requests, requests_rx) = unbounded();
+    (DagExport { requests }, requests_rx)
+}
+
+fn node_id<H: Hasher>(hash: &H::Hash) -> String {
+    hash.as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Renders the units, given with their parents if these are known already. The output is
+/// sorted by rounds and creators, so that exports of the same Dag are equal.
+pub(crate) fn render_dot<'a, H: Hasher>(
+    units: impl Iterator<Item = (UnitCoord, H::Hash, Option<&'a Vec<H::Hash>>)>,
+    heads: &HashSet<H::Hash>,
+) -> String {
+    let mut units: Vec<_> = units.collect();
+    units.sort_by_key(|(coord, hash, _)| (coord.round(), coord.creator(), *hash));
+    let mut dot = String::from("digraph dag {\n    rankdir=BT;\n    node [shape=box];\n");
+    for (coord, hash, _) in &units {
+        let highlight = match heads.contains(hash) {
+            true => ", style=filled, fillcolor=gold",
+            false => "",
+        };
+        writeln!(
+            dot,
+            "    \"{}\" [label=\"{}/{}\"{}];",
+            node_id::<H>(hash),
+            coord.creator().0,
+            coord.round(),
+            highlight
+        )
+        .expect("writing to a string works");
+    }
+    for (_, hash, parents) in &units {
+        for parent in parents.into_iter().flatten() {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\";",
+                node_id::<H>(hash),
+                node_id::<H>(parent)
+            )
+            .expect("writing to a string works");
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_dot;
+    use crate::{units::UnitCoord, NodeIndex};
+    use aleph_bft_mock::Hasher64;
+    use std::collections::HashSet;
+
+    #[test]
+    fn renders_units_edges_and_heads() {
+        let parent = [0; 8];
+        let child = [1, 0, 0, 0, 0, 0, 0, 0];
+        let parents = vec![parent];
+        let units = vec![
+            (UnitCoord::new(1, NodeIndex(2)), child, Some(&parents)),
+            (UnitCoord::new(0, NodeIndex(1)), parent, None),
+        ];
+        let heads = HashSet::from([parent]);
+        let dot = render_dot::<Hasher64>(units.into_iter(), &heads);
+
+        let parent_id = "0000000000000000";
+        let child_id = "0100000000000000";
+        assert_eq!(
+            dot,
+            format!(
+                "digraph dag {{\n    rankdir=BT;\n    node [shape=box];\n    \"{}\" [label=\"1/0\", style=filled, fillcolor=gold];\n    \"{}\" [label=\"2/1\"];\n    \"{}\" -> \"{}\";\n}}\n",
+                parent_id, child_id, child_id, parent_id
+            )
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use crate::{
+    Data, DataProvider, FinalizationHandler, NodeIndex, NodeMap, Receiver, Sender,
+    ThresholdEncryption,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, StreamExt};
+use log::{debug, warn};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    marker::PhantomData,
+};
+
+// Shares of data not finalized locally yet are kept for at most this many positions ahead,
+// so that others cannot make us store shares without limits.
+const MAX_POSITIONS_AHEAD: u64 = 1024;
+
+/// Encrypts the data of the wrapped provider to the committee, so that the creator of a unit
+/// cannot censor data based on its contents, and nobody learns the data before it is ordered.
+/// The session should be run with the ciphertexts as its data, and decrypted using a
+/// [`Decryptor`].
+pub struct EncryptingDataProvider<D: Data, DP: DataProvider<D>, TE: ThresholdEncryption> {
+    data_provider: DP,
+    encryption: TE,
+    _phantom: PhantomData<D>,
+}
+
+impl<D: Data, DP: DataProvider<D>, TE: ThresholdEncryption> EncryptingDataProvider<D, DP, TE> {
+    pub fn new(data_provider: DP, encryption: TE) -> Self {
+        EncryptingDataProvider {
+            data_provider,
+            encryption,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<D: Data, DP: DataProvider<D>, TE: ThresholdEncryption> DataProvider<TE::Ciphertext>
+    for EncryptingDataProvider<D, DP, TE>
+{
+    async fn get_data(&mut self) -> Option<TE::Ciphertext> {
+        let data = self.data_provider.get_data().await?;
+        Some(self.encryption.encrypt(&data.encode()))
+    }
+}
+
+/// Passes the finalized ciphertexts of a session to its [`Decryptor`].
+pub struct CiphertextHandler<C> {
+    ciphertexts: Sender<C>,
+}
+
+impl<C: Data> FinalizationHandler<C> for CiphertextHandler<C> {
+    fn data_finalized(&mut self, ciphertext: C) {
+        if self.ciphertexts.unbounded_send(ciphertext).is_err() {
+            debug!(target: "AlephBFT-decryptor", "Decryptor dropped, ignoring finalized data.");
+        }
+    }
+}
+
+/// The share of a node of the decryption of the ciphertext at the given position in the order
+/// of finalized data of the session, to be sent to all the other nodes.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct DecryptionShareMessage<S> {
+    pub position: u64,
+    pub index: NodeIndex,
+    pub share: S,
+}
+
+/// The reason a share was rejected by a [`Decryptor`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DecryptionError {
+    /// The share is not valid for the ciphertext of its position.
+    WrongShare(NodeIndex),
+    /// The share is of a node outside of the committee.
+    UnknownNode(NodeIndex),
+    /// The share is for data too far ahead of what is finalized locally.
+    TooFarAhead(u64),
+    /// The share is of another node than the one that sent it.
+    WrongSender(NodeIndex),
+}
+
+impl Display for DecryptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            DecryptionError::WrongShare(index) => {
+                write!(f, "wrong decryption share of {:?}", index)
+            }
+            DecryptionError::UnknownNode(index) => {
+                write!(f, "decryption share of unknown node {:?}", index)
+            }
+            DecryptionError::TooFarAhead(position) => {
+                write!(
+                    f,
+                    "decryption share for position {} too far ahead",
+                    position
+                )
+            }
+            DecryptionError::WrongSender(index) => {
+                write!(f, "decryption share sent by {:?} for another node", index)
+            }
+        }
+    }
+}
+
+impl Error for DecryptionError {}
+
+struct Pending<C, S> {
+    ciphertext: C,
+    shares: NodeMap<S>,
+}
+
+/// Decrypts the finalized ciphertexts of a session encrypting its data with an
+/// [`EncryptingDataProvider`], passing the data to the wrapped handler in the order of
+/// finalization. Our share for every ciphertext is made only once the ciphertext is finalized,
+/// and is returned by [`Decryptor::next_share`] to be sent to all the other nodes. The shares
+/// of others should be passed to [`Decryptor::add_share`] together with their senders, data is
+/// decrypted as soon as a threshold of shares is known. Data that does not decrypt or decode is skipped, as it could
+/// only be put in a unit by a malicious creator.
+pub struct Decryptor<D: Data, FH: FinalizationHandler<D>, TE: ThresholdEncryption> {
+    encryption: TE,
+    finalization_handler: FH,
+    ciphertexts: Receiver<TE::Ciphertext>,
+    // The position of the first pending ciphertext.
+    decrypted: u64,
+    pending: VecDeque<Pending<TE::Ciphertext, TE::DecryptionShare>>,
+    // Shares for positions whose ciphertexts were not finalized locally yet, not verified, but
+    // every one from its own node.
+    early: BTreeMap<u64, NodeMap<TE::DecryptionShare>>,
+    _phantom: PhantomData<D>,
+}
+
+/// Returns the handler to run the session with and the [`Decryptor`] passing the decrypted data
+/// to the given handler.
+pub fn threshold_decryption<D: Data, FH: FinalizationHandler<D>, TE: ThresholdEncryption>(
+    encryption: TE,
+    finalization_handler: FH,
+) -> (CiphertextHandler<TE::Ciphertext>, Decryptor<D, FH, TE>) {
+    let (ciphertexts_tx, ciphertexts) = mpsc::unbounded();
+    (
+        CiphertextHandler {
+            ciphertexts: ciphertexts_tx,
+        },
+        Decryptor {
+            encryption,
+            finalization_handler,
+            ciphertexts,
+            decrypted: 0,
+            pending: VecDeque::new(),
+            early: BTreeMap::new(),
+            _phantom: PhantomData,
+        },
+    )
+}
+
+impl<D: Data, FH: FinalizationHandler<D>, TE: ThresholdEncryption> Decryptor<D, FH, TE> {
+    fn finalized(&self) -> u64 {
+        self.decrypted + self.pending.len() as u64
+    }
+
+    /// Waits for the next finalized ciphertext and returns our share of its decryption, to be
+    /// sent to all the other nodes. Returns `None` once the session ended.
+    pub async fn next_share(&mut self) -> Option<DecryptionShareMessage<TE::DecryptionShare>> {
+        let ciphertext = self.ciphertexts.next().await?;
+        let position = self.finalized();
+        let share = self.encryption.decryption_share(&ciphertext).await;
+        let mut shares = NodeMap::with_size(self.encryption.node_count());
+        // The early shares could not be verified before.
+        if let Some(early) = self.early.remove(&position) {
+            for (index, share) in early.iter() {
+                match self.encryption.verify_share(&ciphertext, share, index) {
+                    true => shares.insert(index, share.clone()),
+                    false => {
+                        warn!(target: "AlephBFT-decryptor", "Wrong early decryption share of {:?} for position {}.", index, position)
+                    }
+                }
+            }
+        }
+        let index = self.encryption.index();
+        shares.insert(index, share.clone());
+        self.pending.push_back(Pending { ciphertext, shares });
+        self.decrypt_ready();
+        Some(DecryptionShareMessage {
+            position,
+            index,
+            share,
+        })
+    }
+
+    /// Adds the share of another node, decrypting all the data that became decryptable. The
+    /// `sender` is the node the share came from, as authenticated by the network, only its own
+    /// share is accepted. Early shares cannot be verified before their ciphertexts are
+    /// finalized, so otherwise anyone could replace the shares of others with wrong ones.
+    pub fn add_share(
+        &mut self,
+        sender: NodeIndex,
+        message: DecryptionShareMessage<TE::DecryptionShare>,
+    ) -> Result<(), DecryptionError> {
+        let DecryptionShareMessage {
+            position,
+            index,
+            share,
+        } = message;
+        if index.0 >= self.encryption.node_count().0 {
+            return Err(DecryptionError::UnknownNode(index));
+        }
+        if index != sender {
+            return Err(DecryptionError::WrongSender(sender));
+        }
+        if position < self.decrypted {
+            // Already decrypted, the share is not needed anymore.
+            return Ok(());
+        }
+        let finalized = self.finalized();
+        if position >= finalized {
+            if position >= finalized + MAX_POSITIONS_AHEAD {
+                return Err(DecryptionError::TooFarAhead(position));
+            }
+            let node_count = self.encryption.node_count();
+            self.early
+                .entry(position)
+                .or_insert_with(|| NodeMap::with_size(node_count))
+                .insert(index, share);
+            return Ok(());
+        }
+        let pending = &mut self.pending[(position - self.decrypted) as usize];
+        if !self
+            .encryption
+            .verify_share(&pending.ciphertext, &share, index)
+        {
+            return Err(DecryptionError::WrongShare(index));
+        }
+        pending.shares.insert(index, share);
+        self.decrypt_ready();
+        Ok(())
+    }
+
+    fn decrypt_ready(&mut self) {
+        let threshold = self.encryption.threshold();
+        while let Some(pending) = self.pending.front() {
+            if pending.shares.item_count() < threshold.0 {
+                break;
+            }
+            let pending = self
+                .pending
+                .pop_front()
+                .expect("there is a pending ciphertext");
+            let position = self.decrypted;
+            self.decrypted += 1;
+            let data = self
+                .encryption
+                .combine(&pending.ciphertext, &pending.shares)
+                .and_then(|plaintext| D::decode(&mut &plaintext[..]).ok());
+            match data {
+                Some(data) => self.finalization_handler.data_finalized(data),
+                None => {
+                    warn!(target: "AlephBFT-decryptor", "Skipping data at position {} that does not decrypt.", position)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{threshold_decryption, DecryptionError, EncryptingDataProvider};
+    use crate::{DataProvider, FinalizationHandler, NodeCount, NodeIndex};
+    use aleph_bft_mock::{DataProvider as MockDataProvider, Keychain};
+    use futures::channel::mpsc;
+
+    struct Handler {
+        data: mpsc::UnboundedSender<u32>,
+    }
+
+    impl FinalizationHandler<u32> for Handler {
+        fn data_finalized(&mut self, data: u32) {
+            self.data
+                .unbounded_send(data)
+                .expect("the receiver should be open");
+        }
+    }
+
+    #[tokio::test]
+    async fn decrypts_finalized_data_with_enough_shares() {
+        let n_members = NodeCount(4);
+        let keychains = Keychain::new_vec(n_members);
+        let mut provider = EncryptingDataProvider::new(MockDataProvider::new(), keychains[0]);
+        let first = provider.get_data().await.expect("there is data");
+        let second = provider.get_data().await.expect("there is data");
+
+        let mut decryptors = Vec::new();
+        let mut outputs = Vec::new();
+        for keychain in &keychains[..2] {
+            let (data_tx, data_rx) = mpsc::unbounded();
+            let (mut ciphertexts, decryptor) =
+                threshold_decryption(*keychain, Handler { data: data_tx });
+            ciphertexts.data_finalized(first.clone());
+            ciphertexts.data_finalized(second.clone());
+            decryptors.push(decryptor);
+            outputs.push(data_rx);
+        }
+        let mut shares = Vec::new();
+        for decryptor in decryptors.iter_mut() {
+            shares.push(decryptor.next_share().await.expect("data was finalized"));
+        }
+        // A single share is not enough for the first ciphertext.
+        assert!(outputs[0].try_next().is_err());
+        let second_share = decryptors[1]
+            .next_share()
+            .await
+            .expect("data was finalized");
+        assert_eq!(second_share.position, 1);
+        // Shares ahead of what is finalized are kept until the ciphertext is.
+        let mut forged = second_share.clone();
+        forged.share = shares[0].share.clone();
+        assert_eq!(
+            decryptors[0].add_share(NodeIndex(2), forged),
+            Err(DecryptionError::WrongSender(NodeIndex(2)))
+        );
+        assert_eq!(decryptors[0].add_share(NodeIndex(1), second_share), Ok(()));
+        assert_eq!(
+            decryptors[0].add_share(NodeIndex(1), shares[1].clone()),
+            Ok(())
+        );
+        assert_eq!(
+            outputs[0].try_next().expect("the data is decrypted"),
+            Some(1)
+        );
+        decryptors[0]
+            .next_share()
+            .await
+            .expect("data was finalized");
+        assert_eq!(
+            outputs[0].try_next().expect("the data is decrypted"),
+            Some(2)
+        );
+
+        let mut wrong_share = shares[1].clone();
+        wrong_share.position = 0;
+        wrong_share.index = NodeIndex(2);
+        assert_eq!(
+            decryptors[1].add_share(NodeIndex(2), wrong_share),
+            Err(DecryptionError::WrongShare(NodeIndex(2)))
+        );
+        let mut unknown = shares[0].clone();
+        unknown.index = NodeIndex(7);
+        assert_eq!(
+            decryptors[1].add_share(NodeIndex(7), unknown),
+            Err(DecryptionError::UnknownNode(NodeIndex(7)))
+        );
+        let mut far = shares[0].clone();
+        far.position = 5000;
+        assert_eq!(
+            decryptors[1].add_share(NodeIndex(0), far),
+            Err(DecryptionError::TooFarAhead(5000))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{LoaderError, NodeIndex, SessionId, Signable, Signature, SignatureError};
+use codec::Error as CodecError;
+use thiserror::Error;
+
+/// The errors returned by the entry points of AlephBFT, e.g. [`run_session`].
+///
+/// [`run_session`]: crate::run_session
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The signatures of the keychain do not verify as signatures of its index, so its index
+    /// does not match the position of its key in the committee.
+    #[error("the keychain of node {0:?} does not sign as that node")]
+    KeychainMismatch(NodeIndex),
+    /// A signature did not verify, the signer is `None` for multisignatures.
+    #[error("invalid signature of {signer:?}")]
+    InvalidSignature { signer: Option<NodeIndex> },
+    /// Something belonging to a different session was received.
+    #[error("expected session {expected}, got session {received}")]
+    WrongSession {
+        expected: SessionId,
+        received: SessionId,
+    },
+    /// Some bytes could not be decoded.
+    #[error("decoding failed: {0}")]
+    Decoding(CodecError),
+    /// The backup could not be loaded.
+    #[error("loading the backup failed: {0}")]
+    Backup(#[source] LoaderError),
+    /// A subsystem of the session stopped before the session was ended.
+    #[error("{0} stopped before the session ended")]
+    SubsystemStopped(&'static str),
+}
+
+impl From<CodecError> for Error {
+    fn from(error: CodecError) -> Self {
+        Error::Decoding(error)
+    }
+}
+
+impl From<LoaderError> for Error {
+    fn from(error: LoaderError) -> Self {
+        match error {
+            LoaderError::Codec(error) => Error::Decoding(error),
+            LoaderError::WrongSession(_, expected, received) => {
+                Error::WrongSession { expected, received }
+            }
+            error => Error::Backup(error),
+        }
+    }
+}
+
+impl<T: Signable, S: Signature> From<SignatureError<T, S>> for Error {
+    fn from(error: SignatureError<T, S>) -> Self {
+        Error::InvalidSignature {
+            signer: error.signer,
+        }
+    }
+}
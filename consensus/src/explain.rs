@@ -0,0 +1,108 @@
+use crate::{NodeCount, NodeIndex, Round};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+
+/// How the election of a single head candidate ended. Hashes are given as bytes, as returned by
+/// `AsRef<[u8]>` of the hashes of the session.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CandidateDecision {
+    /// The hash of the candidate unit.
+    pub candidate: Vec<u8>,
+    /// The creator of the candidate unit.
+    pub creator: NodeIndex,
+    /// Whether the candidate was elected head.
+    pub elected: bool,
+    /// The hash of the unit whose vote decided the election.
+    pub decided_by: Vec<u8>,
+    /// The round of the deciding unit, the common vote of the election depends on its distance
+    /// from the round of the candidate.
+    pub decided_at: Round,
+    /// How many parents of the deciding unit voted for the candidate.
+    pub votes_for: NodeCount,
+    /// How many parents of the deciding unit voted against the candidate.
+    pub votes_against: NodeCount,
+}
+
+/// Why the batch of a round looks the way it does. The candidates are all units of the round
+/// known when its election started, considered one after another in the order of their hashes --
+/// the ordering uses no randomness besides that. The first candidate elected becomes the head.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundExplanation {
+    /// The finalized round.
+    pub round: Round,
+    /// The hash of the head of the round.
+    pub head: Vec<u8>,
+    /// The decisions about the candidates, in the order they were considered.
+    pub candidates: Vec<CandidateDecision>,
+    /// The hashes of the units of the batch, in the order they were output, ending with the head.
+    pub order: Vec<Vec<u8>>,
+}
+
+/// A handle for querying how the recently finalized rounds of a session were ordered, for
+/// researchers and auditors verifying the decisions of the ordering. All clones refer to the
+/// same log, so the application should keep a clone of the handle passed to the session.
+#[derive(Clone)]
+pub struct OrderingLog {
+    explanations: Arc<Mutex<VecDeque<RoundExplanation>>>,
+    retained_rounds: usize,
+}
+
+impl OrderingLog {
+    /// A log keeping the explanations of the given number of most recently finalized rounds.
+    pub fn new(retained_rounds: usize) -> Self {
+        OrderingLog {
+            explanations: Arc::new(Mutex::new(VecDeque::new())),
+            retained_rounds,
+        }
+    }
+
+    /// The explanation of the given round, `None` if the round is not finalized yet or was
+    /// finalized too long ago to be retained.
+    pub fn explain(&self, round: Round) -> Option<RoundExplanation> {
+        self.explanations
+            .lock()
+            .iter()
+            .find(|explanation| explanation.round == round)
+            .cloned()
+    }
+
+    /// The explanation of the most recently finalized round.
+    pub fn latest(&self) -> Option<RoundExplanation> {
+        self.explanations.lock().back().cloned()
+    }
+
+    pub(crate) fn record(&self, explanation: RoundExplanation) {
+        let mut explanations = self.explanations.lock();
+        explanations.push_back(explanation);
+        while explanations.len() > self.retained_rounds {
+            explanations.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrderingLog, RoundExplanation};
+
+    fn explanation(round: u16) -> RoundExplanation {
+        RoundExplanation {
+            round,
+            head: vec![round as u8],
+            candidates: Vec::new(),
+            order: vec![vec![round as u8]],
+        }
+    }
+
+    #[test]
+    fn retains_recent_rounds() {
+        let log = OrderingLog::new(2);
+        assert_eq!(log.latest(), None);
+        for round in 0..3 {
+            log.record(explanation(round));
+        }
+        assert_eq!(log.explain(0), None);
+        assert_eq!(log.explain(1), Some(explanation(1)));
+        assert_eq!(log.latest(), Some(explanation(2)));
+        assert_eq!(log.explain(3), None);
+    }
+}
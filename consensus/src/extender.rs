@@ -1,33 +1,60 @@
-use futures::StreamExt;
-use std::collections::{HashMap, VecDeque};
+use futures::{future::ready, stream, Stream, StreamExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
 
 use log::{debug, warn};
 
-use crate::{Hasher, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Terminator};
+use crate::{
+    alloc_profiling::{measure, HotPath},
+    cooperation::Cooperation,
+    explain::{CandidateDecision, OrderingLog, RoundExplanation},
+    terminal::ValidatedUnit,
+    Hasher, Health, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Subsystem, Terminator,
+};
 
+/// A unit being ordered. Outside of this module it can only be made out of a [`ValidatedUnit`],
+/// so only units added to the Dag get ordered.
 pub(crate) struct ExtenderUnit<H: Hasher> {
     creator: NodeIndex,
     round: Round,
     parents: NodeMap<H::Hash>,
     hash: H::Hash,
+    priority: Option<H::Hash>,
     vote: bool,
 }
 
 impl<H: Hasher> ExtenderUnit<H> {
-    pub(crate) fn new(
-        creator: NodeIndex,
-        round: Round,
-        hash: H::Hash,
-        parents: NodeMap<H::Hash>,
-    ) -> Self {
+    fn new(creator: NodeIndex, round: Round, hash: H::Hash, parents: NodeMap<H::Hash>) -> Self {
         ExtenderUnit {
             creator,
             round,
             hash,
             parents,
+            priority: None,
             vote: false,
         }
     }
+
+    fn with_priority(self, priority: Option<H::Hash>) -> Self {
+        ExtenderUnit { priority, ..self }
+    }
+
+    // Candidates are considered in the order of their VRF outputs, or of their hashes if the
+    // session does not use a VRF.
+    fn candidate_key(&self) -> (H::Hash, H::Hash) {
+        (self.priority.unwrap_or(self.hash), self.hash)
+    }
+}
+
+impl<H: Hasher> From<ValidatedUnit<H>> for ExtenderUnit<H> {
+    fn from(u: ValidatedUnit<H>) -> ExtenderUnit<H> {
+        let (unit, parents) = u.into_parts();
+        ExtenderUnit::new(unit.creator(), unit.round(), unit.hash(), parents)
+            .with_priority(unit.priority())
+    }
 }
 
 struct CacheState {
@@ -58,6 +85,10 @@ impl CacheState {
 /// units that should be finalized, unwraps them (leaving only a block hash per unit) and pushes
 /// such a batch to a channel via the finalizer_tx endpoint.
 ///
+/// The extender runs as a separate task and keeps its own copy of the Dag, containing only what
+/// head election needs, so it never shares data structures with the terminal and computing
+/// heads never blocks adding units.
+///
 /// We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/internals.html
 /// Section 5.4 for a discussion of this component.
 
@@ -71,6 +102,11 @@ pub(crate) struct Extender<H: Hasher> {
     candidates: Vec<H::Hash>,
     finalizer_tx: Sender<Vec<H::Hash>>,
     exiting: bool,
+    health: Health,
+    ordering_log: Option<OrderingLog>,
+    // The decisions about the candidates of the current round, only kept for the ordering log.
+    decisions: Vec<CandidateDecision>,
+    cooperation: Cooperation,
 }
 
 impl<H: Hasher> Extender<H> {
@@ -79,6 +115,8 @@ impl<H: Hasher> Extender<H> {
         n_members: NodeCount,
         electors: Receiver<ExtenderUnit<H>>,
         finalizer_tx: Sender<Vec<H::Hash>>,
+        health: Health,
+        ordering_log: Option<OrderingLog>,
     ) -> Self {
         Extender {
             node_id,
@@ -90,9 +128,19 @@ impl<H: Hasher> Extender<H> {
             n_members,
             candidates: vec![],
             exiting: false,
+            health,
+            ordering_log,
+            decisions: vec![],
+            cooperation: Cooperation::new(0),
         }
     }
 
+    /// Makes the extender yield to other tasks after ordering so many units in a row.
+    pub(crate) fn with_yield_interval(mut self, interval: usize) -> Self {
+        self.cooperation = Cooperation::new(interval);
+        self
+    }
+
     fn add_unit(&mut self, u: ExtenderUnit<H>) {
         debug!(target: "AlephBFT-extender", "{:?} New unit in Extender round {:?} creator {:?} hash {:?}.", self.node_id, u.round, u.creator, u.hash);
         let round = u.round;
@@ -118,7 +166,9 @@ impl<H: Hasher> Extender<H> {
         // go back and never update this list. From math it follows that each unit that is added to the Dag later
         // then the moment of round initialization will be decided as false, hence they can be ignored.
         self.candidates = self.units_by_round[round as usize].clone();
-        self.candidates.sort();
+        let units = &self.units;
+        self.candidates
+            .sort_by_key(|hash| units.get(hash).map(ExtenderUnit::candidate_key));
     }
 
     fn common_vote(&self, relative_round: Round) -> bool {
@@ -149,6 +199,14 @@ impl<H: Hasher> Extender<H> {
 
         // We reverse for the batch to start with least recent units.
         batch.reverse();
+        if let Some(ordering_log) = &self.ordering_log {
+            ordering_log.record(RoundExplanation {
+                round,
+                head: head.as_ref().to_vec(),
+                candidates: std::mem::take(&mut self.decisions),
+                order: batch.iter().map(|hash| hash.as_ref().to_vec()).collect(),
+            });
+        }
         if self.finalizer_tx.unbounded_send(batch).is_err() {
             warn!(target: "AlephBFT-extender", "{:?} Channel for batches should be open", self.node_id);
             self.exiting = true;
@@ -192,7 +250,7 @@ impl<H: Hasher> Extender<H> {
         }
         let cv = self.common_vote(relative_round);
         let mut decision = None;
-        let threshold = (self.n_members * 2) / 3 + NodeCount(1);
+        let threshold = self.n_members.quorum();
         assert!(n_votes_true + n_votes_false >= threshold);
 
         if relative_round >= 3
@@ -210,25 +268,55 @@ impl<H: Hasher> Extender<H> {
         (vote, decision)
     }
 
+    // Returns the decision together with the unit that made it.
     fn recompute_votes(
         &mut self,
         candidate_hash: H::Hash,
         candidate_creator: NodeIndex,
         curr_round: Round,
         voters_round: Round,
-    ) -> Option<bool> {
+    ) -> Option<(bool, H::Hash)> {
         for u_hash in self.units_by_round[voters_round as usize].iter() {
             let (vote, u_decision) =
                 self.vote_and_decision(&candidate_hash, u_hash, candidate_creator, curr_round);
             // We update the vote.
             self.units.get_mut(u_hash).unwrap().vote = vote;
-            if u_decision.is_some() {
-                return u_decision;
+            if let Some(decision) = u_decision {
+                return Some((decision, *u_hash));
             }
         }
         None
     }
 
+    fn record_decision(
+        &mut self,
+        candidate_hash: &H::Hash,
+        candidate_creator: NodeIndex,
+        elected: bool,
+        voter_hash: &H::Hash,
+    ) {
+        if self.ordering_log.is_none() {
+            return;
+        }
+        // The votes of the parents are still the ones for this candidate.
+        let voter = self.units.get(voter_hash).unwrap();
+        let votes_for = voter
+            .parents
+            .values()
+            .filter(|p_hash| self.units.get(*p_hash).unwrap().vote)
+            .count();
+        let votes_against = voter.parents.item_count() - votes_for;
+        self.decisions.push(CandidateDecision {
+            candidate: candidate_hash.as_ref().to_vec(),
+            creator: candidate_creator,
+            elected,
+            decided_by: voter_hash.as_ref().to_vec(),
+            decided_at: voter.round,
+            votes_for: NodeCount(votes_for),
+            votes_against: NodeCount(votes_against),
+        });
+    }
+
     // Tries to make progress in extending the partial order after adding a new unit to the Dag.
     fn progress(&mut self, u_new_hash: H::Hash) {
         loop {
@@ -244,7 +332,7 @@ impl<H: Hasher> Extender<H> {
                 }
             }
 
-            let mut decision: Option<bool> = None;
+            let mut decision: Option<(bool, H::Hash)> = None;
             let curr_round = self.state.current_round;
             let candidate_hash = self.candidates[self.state.pending_cand_id];
             let candidate_creator = self.units.get(&candidate_hash).unwrap().creator;
@@ -272,10 +360,13 @@ impl<H: Hasher> Extender<H> {
                     curr_round,
                 );
                 self.units.get_mut(&u_new_hash).unwrap().vote = vote;
-                decision = u_decision;
+                decision = u_decision.map(|decision| (decision, u_new_hash));
             }
 
-            match decision {
+            if let Some((elected, voter_hash)) = decision {
+                self.record_decision(&candidate_hash, candidate_creator, elected, &voter_hash);
+            }
+            match decision.map(|(elected, _)| elected) {
                 Some(true) => {
                     self.finalize_round(self.state.current_round, &candidate_hash);
                     self.state.current_round += 1;
@@ -294,14 +385,24 @@ impl<H: Hasher> Extender<H> {
         }
     }
 
+    fn order_unit(&mut self, u: ExtenderUnit<H>) {
+        let u_hash = u.hash;
+        measure(HotPath::ExtenderOrdering, || {
+            self.add_unit(u);
+            self.progress(u_hash);
+        });
+    }
+
     pub(crate) async fn extend(&mut self, mut terminator: Terminator) {
         loop {
             futures::select! {
                 v = self.electors.next() => {
                     if let Some(v) = v {
-                        let v_hash = v.hash;
-                        self.add_unit(v);
-                        self.progress(v_hash)
+                        self.order_unit(v);
+                        self.health.on_activity(Subsystem::Extender);
+                        // The units that are not ordered yet.
+                        self.health.set_queue_depth(Subsystem::Extender, self.units.len());
+                        self.cooperation.step().await;
                     }
                 }
                 _ = &mut terminator.get_exit() => {
@@ -318,10 +419,161 @@ impl<H: Hasher> Extender<H> {
     }
 }
 
+/// A unit of a Dag ordered by a [`StandaloneExtender`], reduced to what ordering needs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DagUnit<H: Hasher> {
+    pub creator: NodeIndex,
+    pub round: Round,
+    pub hash: H::Hash,
+    /// The hashes of the parents by their creators, empty in round zero.
+    pub parents: NodeMap<H::Hash>,
+}
+
+/// Why a [`StandaloneExtender`] rejected a unit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DagUnitError<H: Hasher> {
+    /// The parents are for a different number of members.
+    WrongNumberOfMembers(H::Hash),
+    /// The creator is not a member.
+    CreatorOutOfRange(H::Hash),
+    /// A unit of round zero with parents, or a unit of a later round with parents of fewer than
+    /// two thirds of the members.
+    WrongParents(H::Hash),
+    /// A parent was not added yet.
+    UnknownParent(H::Hash),
+    /// The unit was added already.
+    Duplicate(H::Hash),
+}
+
+impl<H: Hasher> Display for DagUnitError<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            DagUnitError::WrongNumberOfMembers(hash) => {
+                write!(f, "unit {:?} has parents for a different committee", hash)
+            }
+            DagUnitError::CreatorOutOfRange(hash) => {
+                write!(f, "unit {:?} created by a non-member", hash)
+            }
+            DagUnitError::WrongParents(hash) => {
+                write!(f, "unit {:?} has a wrong number of parents", hash)
+            }
+            DagUnitError::UnknownParent(hash) => {
+                write!(f, "unit {:?} has a parent that was not added yet", hash)
+            }
+            DagUnitError::Duplicate(hash) => write!(f, "unit {:?} was added already", hash),
+        }
+    }
+}
+
+impl<H: Hasher> Error for DagUnitError<H> {}
+
+/// The ordering of AlephBFT on its own, for Dags built elsewhere, e.g. replayed from an archive
+/// or produced by a different implementation, without creating units or networking.
+///
+/// The units have to be valid units of a Dag and added after their parents, like the ones
+/// AlephBFT adds to its own Dag. For the same Dag, the batches are the same as the ones AlephBFT
+/// finalizes, each a list of unit hashes.
+pub struct StandaloneExtender<H: Hasher> {
+    extender: Extender<H>,
+    batches: Receiver<Vec<H::Hash>>,
+    n_members: NodeCount,
+}
+
+impl<H: Hasher> StandaloneExtender<H> {
+    pub fn new(n_members: NodeCount) -> Self {
+        Self::with_ordering_log(n_members, None)
+    }
+
+    /// Like [`StandaloneExtender::new`], explaining the ordering in the log.
+    pub fn with_ordering_log(n_members: NodeCount, ordering_log: Option<OrderingLog>) -> Self {
+        let (_, electors) = futures::channel::mpsc::unbounded();
+        let (batches_for_us, batches) = futures::channel::mpsc::unbounded();
+        let extender = Extender::new(
+            NodeIndex(0),
+            n_members,
+            electors,
+            batches_for_us,
+            Health::new(),
+            ordering_log,
+        );
+        StandaloneExtender {
+            extender,
+            batches,
+            n_members,
+        }
+    }
+
+    fn check(&self, unit: &DagUnit<H>) -> Result<(), DagUnitError<H>> {
+        let hash = unit.hash;
+        if unit.parents.size() != self.n_members {
+            return Err(DagUnitError::WrongNumberOfMembers(hash));
+        }
+        if unit.creator.0 >= self.n_members.0 {
+            return Err(DagUnitError::CreatorOutOfRange(hash));
+        }
+        let n_parents = NodeCount(unit.parents.item_count());
+        let wrong_parents = match unit.round {
+            0 => n_parents > NodeCount(0),
+            _ => n_parents < self.n_members.quorum(),
+        };
+        if wrong_parents {
+            return Err(DagUnitError::WrongParents(hash));
+        }
+        if self.extender.units.contains_key(&hash) {
+            return Err(DagUnitError::Duplicate(hash));
+        }
+        // Units of finalized rounds are forgotten, and not needed for ordering anymore.
+        if unit.round > self.extender.state.current_round
+            && unit
+                .parents
+                .values()
+                .any(|parent| !self.extender.units.contains_key(parent))
+        {
+            return Err(DagUnitError::UnknownParent(hash));
+        }
+        Ok(())
+    }
+
+    /// Adds the unit to the Dag, returning the batches it allowed to finalize, oldest first.
+    pub fn add_unit(&mut self, unit: DagUnit<H>) -> Result<Vec<Vec<H::Hash>>, DagUnitError<H>> {
+        self.check(&unit)?;
+        let DagUnit {
+            creator,
+            round,
+            hash,
+            parents,
+        } = unit;
+        self.extender
+            .order_unit(ExtenderUnit::new(creator, round, hash, parents));
+        let mut batches = Vec::new();
+        while let Ok(Some(batch)) = self.batches.try_next() {
+            batches.push(batch);
+        }
+        Ok(batches)
+    }
+
+    /// Orders a whole stream of units, skipping the ones rejected by
+    /// [`StandaloneExtender::add_unit`].
+    pub fn order_stream(
+        self,
+        units: impl Stream<Item = DagUnit<H>>,
+    ) -> impl Stream<Item = Vec<H::Hash>> {
+        units
+            .scan(self, |extender, unit| {
+                let batches = extender.add_unit(unit).unwrap_or_else(|e| {
+                    warn!(target: "AlephBFT-extender", "Skipping a unit: {}.", e);
+                    Vec::new()
+                });
+                ready(Some(stream::iter(batches)))
+            })
+            .flatten()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::NodeCount;
+    use crate::{Health, NodeCount};
     use aleph_bft_mock::Hasher64;
     use futures::channel::{mpsc, oneshot};
 
@@ -355,7 +607,14 @@ mod tests {
         let rounds = 6;
         let (batch_tx, mut batch_rx) = mpsc::unbounded();
         let (electors_tx, electors_rx) = mpsc::unbounded();
-        let mut extender = Extender::<Hasher64>::new(0.into(), n_members, electors_rx, batch_tx);
+        let mut extender = Extender::<Hasher64>::new(
+            0.into(),
+            n_members,
+            electors_rx,
+            batch_tx,
+            Health::new(),
+            None,
+        );
         let (exit_tx, exit_rx) = oneshot::channel();
         let extender_handle = tokio::spawn(async move {
             extender
@@ -379,4 +638,147 @@ mod tests {
         let _ = exit_tx.send(());
         let _ = extender_handle.await;
     }
+
+    #[test]
+    fn elects_heads_by_priority() {
+        let n_members = NodeCount(4);
+        let (batch_tx, mut batch_rx) = mpsc::unbounded();
+        let (_electors_tx, electors_rx) = mpsc::unbounded();
+        let mut extender = Extender::<Hasher64>::new(
+            0.into(),
+            n_members,
+            electors_rx,
+            batch_tx,
+            Health::new(),
+            None,
+        );
+        for round in 0..6 {
+            for creator in n_members.into_iterator() {
+                // The priorities reverse the order of the hashes.
+                let priority =
+                    (u64::MAX - coord_to_number(creator, round, n_members)).to_be_bytes();
+                extender.order_unit(
+                    construct_unit(creator, round, n_members).with_priority(Some(priority)),
+                );
+            }
+        }
+        let batch = batch_rx.try_next().unwrap().expect("round 0 is finalized");
+        let last_creator = NodeIndex(n_members.0 - 1);
+        assert_eq!(
+            batch,
+            vec![coord_to_number(last_creator, 0, n_members).to_ne_bytes()]
+        );
+    }
+
+    fn dag_unit(creator: NodeIndex, round: Round, n_members: NodeCount) -> DagUnit<Hasher64> {
+        let unit = construct_unit(creator, round, n_members);
+        DagUnit {
+            creator: unit.creator,
+            round: unit.round,
+            hash: unit.hash,
+            parents: unit.parents,
+        }
+    }
+
+    #[tokio::test]
+    async fn orders_external_dag() {
+        let n_members = NodeCount(4);
+        let units: Vec<_> = (0..6)
+            .flat_map(|round| {
+                n_members
+                    .into_iterator()
+                    .map(move |creator| dag_unit(creator, round, n_members))
+            })
+            .collect();
+
+        let mut extender = StandaloneExtender::<Hasher64>::new(n_members);
+        assert_eq!(
+            extender.add_unit(units[4].clone()),
+            Err(DagUnitError::UnknownParent(units[4].hash))
+        );
+        let mut batches = Vec::new();
+        for unit in units.iter().cloned() {
+            batches.extend(extender.add_unit(unit).expect("the units are valid"));
+        }
+        assert_eq!(
+            extender.add_unit(units[23].clone()),
+            Err(DagUnitError::Duplicate(units[23].hash))
+        );
+        assert!(batches.len() >= 2);
+        // Round zero units have no parents, so the first batch is just the head.
+        assert_eq!(batches[0].len(), 1);
+
+        let streamed: Vec<_> = StandaloneExtender::<Hasher64>::new(n_members)
+            .order_stream(stream::iter(units))
+            .collect()
+            .await;
+        assert_eq!(streamed, batches);
+    }
+
+    #[test]
+    fn orders_tiny_committees() {
+        for n_members in [NodeCount(1), NodeCount(2)] {
+            let mut extender = StandaloneExtender::<Hasher64>::new(n_members);
+            let mut batches = Vec::new();
+            for round in 0..8 {
+                for creator in n_members.into_iterator() {
+                    batches.extend(
+                        extender
+                            .add_unit(dag_unit(creator, round, n_members))
+                            .expect("the units are valid"),
+                    );
+                }
+            }
+            // With unanimous votes every round gets decided by the units four rounds above it.
+            assert_eq!(batches.len(), 4);
+            // The head of round 3 and all the units below it.
+            let ordered: usize = batches.iter().map(Vec::len).sum();
+            assert_eq!(ordered, 3 * n_members.0 + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn explains_finalized_rounds() {
+        let n_members = NodeCount(4);
+        let (batch_tx, mut batch_rx) = mpsc::unbounded();
+        let (electors_tx, electors_rx) = mpsc::unbounded();
+        let ordering_log = OrderingLog::new(10);
+        let mut extender = Extender::<Hasher64>::new(
+            0.into(),
+            n_members,
+            electors_rx,
+            batch_tx,
+            Health::new(),
+            Some(ordering_log.clone()),
+        );
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let extender_handle = tokio::spawn(async move {
+            extender
+                .extend(Terminator::create_root(exit_rx, "AlephBFT-extender"))
+                .await
+        });
+
+        for round in 0..6 {
+            for creator in n_members.into_iterator() {
+                electors_tx
+                    .unbounded_send(construct_unit(creator, round, n_members))
+                    .expect("Channel should be open");
+            }
+        }
+        let batch = batch_rx.next().await.unwrap();
+        let explanation = ordering_log.explain(0).expect("round 0 is finalized");
+        assert_eq!(explanation.round, 0);
+        let order: Vec<_> = batch.iter().map(|hash| hash.to_vec()).collect();
+        assert_eq!(explanation.order, order);
+        assert_eq!(Some(&explanation.head), order.last());
+        let elected = explanation
+            .candidates
+            .last()
+            .expect("some candidate was elected");
+        assert!(elected.elected);
+        assert_eq!(elected.candidate, explanation.head);
+        assert!(elected.votes_for > elected.votes_against);
+        let _ = exit_tx.send(());
+        let _ = extender_handle.await;
+    }
 }
@@ -0,0 +1,180 @@
+use crate::{Data, FinalizationHandler};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use log::debug;
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// A finalized batch together with its index, counting from 0 at the start of the session.
+pub type IndexedBatch<D> = (usize, Vec<D>);
+
+/// Why subscribing to a [`FinalizationArchive`] failed.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum ResumeError {
+    /// The requested batch is no longer retained, the oldest one still available is given.
+    Pruned { oldest: usize },
+    /// The requested batch was not finalized yet, and neither were the ones before it, the
+    /// index of the next batch to be finalized is given.
+    NotFinalized { next: usize },
+}
+
+impl Display for ResumeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ResumeError::Pruned { oldest } => write!(
+                f,
+                "the batch is no longer retained, the oldest retained batch is {}",
+                oldest
+            ),
+            ResumeError::NotFinalized { next } => write!(
+                f,
+                "the batch is not finalized, the next batch to be finalized is {}",
+                next
+            ),
+        }
+    }
+}
+
+impl Error for ResumeError {}
+
+struct Archive<D: Data> {
+    retention: usize,
+    oldest: usize,
+    batches: VecDeque<Vec<D>>,
+    subscriber: Option<UnboundedSender<IndexedBatch<D>>>,
+}
+
+impl<D: Data> Archive<D> {
+    fn next(&self) -> usize {
+        self.oldest + self.batches.len()
+    }
+
+    fn push(&mut self, batch: Vec<D>) {
+        let index = self.next();
+        if let Some(subscriber) = &self.subscriber {
+            if subscriber.unbounded_send((index, batch.clone())).is_err() {
+                debug!(target: "AlephBFT-finalization", "Subscriber of finalized batches disconnected at batch {}.", index);
+                self.subscriber = None;
+            }
+        }
+        self.batches.push_back(batch);
+        while self.batches.len() > self.retention {
+            self.batches.pop_front();
+            self.oldest += 1;
+        }
+    }
+}
+
+/// A [`FinalizationHandler`] retaining the most recent finalized batches, so that a consumer
+/// that has to reconnect, e.g. after crashing on the other side of an IPC bridge, can resume
+/// from the first batch it did not process instead of losing whatever was in flight.
+///
+/// The consumer calls [`FinalizationArchive::subscribe`] on a clone of the archive, receiving
+/// the retained batches starting from the requested index and then every newly finalized batch.
+/// There is at most one subscriber at a time, subscribing again replaces the previous one.
+#[derive(Clone)]
+pub struct FinalizationArchive<D: Data> {
+    archive: Arc<Mutex<Archive<D>>>,
+}
+
+impl<D: Data> FinalizationArchive<D> {
+    /// An archive retaining at most `retention` most recent batches.
+    pub fn new(retention: usize) -> Self {
+        FinalizationArchive {
+            archive: Arc::new(Mutex::new(Archive {
+                retention,
+                oldest: 0,
+                batches: VecDeque::new(),
+                subscriber: None,
+            })),
+        }
+    }
+
+    /// The index of the next batch to be finalized.
+    pub fn next_index(&self) -> usize {
+        self.archive.lock().next()
+    }
+
+    /// Subscribes to finalized batches, starting with the batch of the given index.
+    pub fn subscribe(
+        &self,
+        from: usize,
+    ) -> Result<UnboundedReceiver<IndexedBatch<D>>, ResumeError> {
+        let mut archive = self.archive.lock();
+        if from < archive.oldest {
+            return Err(ResumeError::Pruned {
+                oldest: archive.oldest,
+            });
+        }
+        if from > archive.next() {
+            return Err(ResumeError::NotFinalized {
+                next: archive.next(),
+            });
+        }
+        let (subscriber, batches) = unbounded();
+        let oldest = archive.oldest;
+        for (index, batch) in archive.batches.iter().enumerate().skip(from - oldest) {
+            subscriber
+                .unbounded_send((oldest + index, batch.clone()))
+                .expect("we own the receiver");
+        }
+        archive.subscriber = Some(subscriber);
+        Ok(batches)
+    }
+}
+
+impl<D: Data> FinalizationHandler<D> for FinalizationArchive<D> {
+    fn data_finalized(&mut self, data: D) {
+        self.batch_finalized(vec![data]);
+    }
+
+    fn batch_finalized(&mut self, batch: Vec<D>) {
+        self.archive.lock().push(batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FinalizationArchive, ResumeError};
+    use crate::FinalizationHandler;
+
+    #[test]
+    fn resumes_from_retained_batch() {
+        let archive = FinalizationArchive::new(2);
+        let mut handler = archive.clone();
+        let mut first = archive.subscribe(0).expect("nothing was finalized yet");
+        for batch in [vec![0u32], vec![], vec![2, 3]] {
+            handler.batch_finalized(batch);
+        }
+        assert_eq!(first.try_next().unwrap(), Some((0, vec![0])));
+        assert_eq!(archive.next_index(), 3);
+
+        let mut second = archive.subscribe(1).expect("batch is retained");
+        handler.batch_finalized(vec![4]);
+        assert_eq!(second.try_next().unwrap(), Some((1, vec![])));
+        assert_eq!(second.try_next().unwrap(), Some((2, vec![2, 3])));
+        assert_eq!(second.try_next().unwrap(), Some((3, vec![4])));
+        assert!(second.try_next().is_err());
+    }
+
+    #[test]
+    fn rejects_unavailable_batches() {
+        let archive = FinalizationArchive::new(1);
+        let mut handler = archive.clone();
+        handler.batch_finalized(vec![0u32]);
+        handler.batch_finalized(vec![1]);
+        assert_eq!(
+            archive.subscribe(0).err(),
+            Some(ResumeError::Pruned { oldest: 1 })
+        );
+        assert_eq!(
+            archive.subscribe(3).err(),
+            Some(ResumeError::NotFinalized { next: 2 })
+        );
+        assert!(archive.subscribe(2).is_ok());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{
+    default_config, Config, Index, Indexed, MultiKeychain, NodeCount, NodeIndex, NodeMap, Round,
+    SessionId, Signable, Signed, UncheckedSigned,
+};
+use codec::{Decode, Encode};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+// Prepended to the encoding of the genesis before signing, so that the signature of a genesis
+// can never be mistaken for a signature of anything else.
+const GENESIS_CONTEXT: &[u8] = b"AlephBFT genesis";
+
+/// A member of a new committee, with its public key and network address in the encodings of the
+/// application.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct GenesisMember {
+    pub index: NodeIndex,
+    pub public_key: Vec<u8>,
+    pub address: Vec<u8>,
+}
+
+/// The parameters of the [`Config`] the committee agrees on, together with the parameters of the
+/// application, e.g. its genesis block, in its own encoding.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct GenesisParameters {
+    pub session_id: SessionId,
+    pub max_round: Round,
+    pub application: Vec<u8>,
+}
+
+/// What a member sends to all the others in the first phase of a [`GenesisCeremony`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct GenesisAnnouncement {
+    pub member: GenesisMember,
+    pub parameters: GenesisParameters,
+}
+
+/// The configuration a new committee starts with, agreed on in a [`GenesisCeremony`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct Genesis {
+    parameters: GenesisParameters,
+    members: Vec<GenesisMember>,
+}
+
+impl Genesis {
+    pub fn parameters(&self) -> &GenesisParameters {
+        &self.parameters
+    }
+
+    /// All the members, ordered by their indices.
+    pub fn members(&self) -> &[GenesisMember] {
+        &self.members
+    }
+
+    pub fn n_members(&self) -> NodeCount {
+        NodeCount(self.members.len())
+    }
+
+    /// The default configuration of the member with the agreed on parameters.
+    pub fn config(&self, node_ix: NodeIndex) -> Config {
+        let mut config = default_config(self.n_members(), node_ix, self.parameters.session_id);
+        config.max_round = self.parameters.max_round;
+        config
+    }
+}
+
+impl Signable for Genesis {
+    type Hash = Vec<u8>;
+
+    fn hash(&self) -> Self::Hash {
+        (GENESIS_CONTEXT, self).encode()
+    }
+}
+
+/// What went wrong during a [`GenesisCeremony`] or when loading a genesis with [`load_genesis`].
+/// Most of these mean that some members made typos and have to fix them before the ceremony can
+/// finish.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GenesisError {
+    /// The index of the member is outside of the committee.
+    WrongIndex(NodeIndex),
+    /// The member announced itself differently before.
+    ConflictingMember(NodeIndex),
+    /// The member proposed different parameters than ours.
+    ParametersMismatch(NodeIndex),
+    /// The member signed a different genesis than ours.
+    DifferentGenesis(NodeIndex),
+    /// The signature of the member is wrong.
+    WrongSignature(NodeIndex),
+    /// The keychain is of a committee of a different size.
+    WrongCommittee,
+    /// The blob does not decode as a signed genesis.
+    Malformed,
+    /// The genesis is not signed by all the members.
+    NotSignedByAll,
+}
+
+impl Display for GenesisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            GenesisError::WrongIndex(index) => {
+                write!(f, "{:?} is outside of the committee", index)
+            }
+            GenesisError::ConflictingMember(index) => {
+                write!(f, "{:?} announced itself differently before", index)
+            }
+            GenesisError::ParametersMismatch(index) => {
+                write!(f, "{:?} proposed different parameters", index)
+            }
+            GenesisError::DifferentGenesis(index) => {
+                write!(f, "{:?} signed a different genesis", index)
+            }
+            GenesisError::WrongSignature(index) => {
+                write!(f, "wrong signature of {:?}", index)
+            }
+            GenesisError::WrongCommittee => {
+                write!(f, "keychain of a committee of a different size")
+            }
+            GenesisError::Malformed => write!(f, "malformed genesis blob"),
+            GenesisError::NotSignedByAll => {
+                write!(f, "genesis not signed by all the members")
+            }
+        }
+    }
+}
+
+impl Error for GenesisError {}
+
+/// Helps a new committee agree on its genesis. The ceremony does not talk to the network, the
+/// application passes the messages between the members however it can, e.g. by sharing files.
+///
+/// In the first phase every member sends its [`GenesisAnnouncement`] to all the others and adds
+/// theirs with [`GenesisCeremony::on_announcement`]. Once all the members announced themselves,
+/// the application builds its keychain from their public keys and starts the second phase with
+/// [`GenesisCeremony::into_signing`].
+pub struct GenesisCeremony {
+    announcement: GenesisAnnouncement,
+    members: NodeMap<GenesisMember>,
+}
+
+impl GenesisCeremony {
+    /// Starts the ceremony of a committee of the given size, proposing the parameters.
+    pub fn new(
+        n_members: NodeCount,
+        member: GenesisMember,
+        parameters: GenesisParameters,
+    ) -> Result<Self, GenesisError> {
+        if member.index.0 >= n_members.0 {
+            return Err(GenesisError::WrongIndex(member.index));
+        }
+        let mut members = NodeMap::with_size(n_members);
+        members.insert(member.index, member.clone());
+        Ok(GenesisCeremony {
+            announcement: GenesisAnnouncement { member, parameters },
+            members,
+        })
+    }
+
+    /// Our announcement, to be sent to all the other members.
+    pub fn announcement(&self) -> &GenesisAnnouncement {
+        &self.announcement
+    }
+
+    /// Adds the announcement of a member. Repeated announcements are accepted, as long as they
+    /// do not differ.
+    pub fn on_announcement(
+        &mut self,
+        announcement: GenesisAnnouncement,
+    ) -> Result<(), GenesisError> {
+        let GenesisAnnouncement { member, parameters } = announcement;
+        let index = member.index;
+        if index.0 >= self.members.size().0 {
+            return Err(GenesisError::WrongIndex(index));
+        }
+        if parameters != self.announcement.parameters {
+            return Err(GenesisError::ParametersMismatch(index));
+        }
+        match self.members.get(index) {
+            Some(known) if known != &member => Err(GenesisError::ConflictingMember(index)),
+            Some(_) => Ok(()),
+            None => {
+                self.members.insert(index, member);
+                Ok(())
+            }
+        }
+    }
+
+    /// The members that did not announce themselves yet.
+    pub fn missing(&self) -> Vec<NodeIndex> {
+        self.members
+            .size()
+            .into_iterator()
+            .filter(|index| self.members.get(*index).is_none())
+            .collect()
+    }
+
+    /// The genesis, once all the members announced themselves.
+    pub fn genesis(&self) -> Option<Genesis> {
+        let members = self.members.values().cloned().collect::<Vec<_>>();
+        if members.len() != self.members.size().0 {
+            return None;
+        }
+        Some(Genesis {
+            parameters: self.announcement.parameters.clone(),
+            members,
+        })
+    }
+
+    /// Signs the genesis, starting the second phase, `None` if some members did not announce
+    /// themselves yet. The keychain has to be built from the public keys of the genesis.
+    pub async fn into_signing<MK: MultiKeychain>(
+        self,
+        keychain: MK,
+    ) -> Option<Result<GenesisSigning<MK>, GenesisError>> {
+        let genesis = self.genesis()?;
+        Some(GenesisSigning::new(genesis, keychain).await)
+    }
+}
+
+/// The second phase of a [`GenesisCeremony`], in which every member signs the genesis, sends its
+/// signature to all the others and adds theirs with [`GenesisSigning::on_signature`]. Only a
+/// genesis signed by all the members is accepted, so that every member confirms that its key
+/// and address are what the others think they are.
+pub struct GenesisSigning<MK: MultiKeychain> {
+    keychain: MK,
+    signature: UncheckedSigned<Indexed<Genesis>, MK::Signature>,
+    signatures: NodeMap<Signed<Indexed<Genesis>, MK>>,
+}
+
+impl<MK: MultiKeychain> GenesisSigning<MK> {
+    /// Signs the genesis with the keychain.
+    pub async fn new(genesis: Genesis, keychain: MK) -> Result<Self, GenesisError> {
+        let n_members = genesis.n_members();
+        if keychain.node_count() != n_members {
+            return Err(GenesisError::WrongCommittee);
+        }
+        let signed = Signed::sign_with_index(genesis, &keychain).await;
+        let mut signatures = NodeMap::with_size(n_members);
+        signatures.insert(keychain.index(), signed.clone());
+        Ok(GenesisSigning {
+            keychain,
+            signature: signed.into_unchecked(),
+            signatures,
+        })
+    }
+
+    pub fn genesis(&self) -> &Genesis {
+        self.signature.as_signable_strip_index()
+    }
+
+    /// Our signature, to be sent to all the other members.
+    pub fn signature(&self) -> &UncheckedSigned<Indexed<Genesis>, MK::Signature> {
+        &self.signature
+    }
+
+    /// Adds the signature of a member.
+    pub fn on_signature(
+        &mut self,
+        signature: UncheckedSigned<Indexed<Genesis>, MK::Signature>,
+    ) -> Result<(), GenesisError> {
+        let index = signature.index();
+        if index.0 >= self.signatures.size().0 {
+            return Err(GenesisError::WrongIndex(index));
+        }
+        if signature.as_signable_strip_index() != self.genesis() {
+            return Err(GenesisError::DifferentGenesis(index));
+        }
+        let signed = signature
+            .check(&self.keychain)
+            .map_err(|_| GenesisError::WrongSignature(index))?;
+        self.signatures.insert(index, signed);
+        Ok(())
+    }
+
+    /// The members that did not sign the genesis yet.
+    pub fn missing(&self) -> Vec<NodeIndex> {
+        self.signatures
+            .size()
+            .into_iterator()
+            .filter(|index| self.signatures.get(*index).is_none())
+            .collect()
+    }
+
+    /// The genesis signed by all the members, to be stored by every member and loaded with
+    /// [`load_genesis`]. `None` until all the members signed it.
+    pub fn blob(&self) -> Option<Vec<u8>> {
+        if !self.missing().is_empty() {
+            return None;
+        }
+        let mut signatures = self.signatures.values().cloned();
+        let first = signatures
+            .next()?
+            .into_partially_multisigned(&self.keychain);
+        let multisigned = signatures.fold(first, |multisigned, signed| {
+            multisigned.add_signature(signed, &self.keychain)
+        });
+        Some(multisigned.into_unchecked().encode())
+    }
+}
+
+/// Loads the genesis from a blob created by a [`GenesisSigning`], checking that all the members
+/// signed it.
+pub fn load_genesis<MK: MultiKeychain>(
+    blob: &[u8],
+    keychain: &MK,
+) -> Result<Genesis, GenesisError> {
+    let signed = UncheckedSigned::<Genesis, MK::PartialMultisignature>::decode(&mut &blob[..])
+        .map_err(|_| GenesisError::Malformed)?;
+    let n_members = signed.as_signable().n_members();
+    if keychain.node_count() != n_members {
+        return Err(GenesisError::WrongCommittee);
+    }
+    if !keychain.is_complete_with_threshold(
+        signed.as_signable().hash().as_ref(),
+        &signed.signature(),
+        n_members,
+    ) {
+        return Err(GenesisError::NotSignedByAll);
+    }
+    Ok(signed.into_signable())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        load_genesis, GenesisCeremony, GenesisError, GenesisMember, GenesisParameters,
+        GenesisSigning,
+    };
+    use crate::{NodeCount, NodeIndex, SessionId};
+    use aleph_bft_mock::Keychain;
+
+    fn member(ix: usize) -> GenesisMember {
+        GenesisMember {
+            index: NodeIndex(ix),
+            public_key: vec![ix as u8; 4],
+            address: format!("10.0.0.{}:3000", ix).into_bytes(),
+        }
+    }
+
+    fn parameters() -> GenesisParameters {
+        GenesisParameters {
+            session_id: SessionId::from_number(3),
+            max_round: 1000,
+            application: b"genesis block".to_vec(),
+        }
+    }
+
+    fn ceremonies(n_members: usize) -> Vec<GenesisCeremony> {
+        (0..n_members)
+            .map(|ix| {
+                GenesisCeremony::new(NodeCount(n_members), member(ix), parameters())
+                    .expect("the member is in the committee")
+            })
+            .collect()
+    }
+
+    async fn signings(n_members: usize) -> Vec<GenesisSigning<Keychain>> {
+        let mut ceremonies = ceremonies(n_members);
+        let announcements: Vec<_> = ceremonies
+            .iter()
+            .map(|ceremony| ceremony.announcement().clone())
+            .collect();
+        for ceremony in &mut ceremonies {
+            for announcement in &announcements {
+                assert_eq!(ceremony.on_announcement(announcement.clone()), Ok(()));
+            }
+            assert!(ceremony.missing().is_empty());
+        }
+        let mut signings = Vec::new();
+        for (ix, ceremony) in ceremonies.into_iter().enumerate() {
+            let keychain = Keychain::new(NodeCount(n_members), NodeIndex(ix));
+            let signing = ceremony
+                .into_signing(keychain)
+                .await
+                .expect("all the members announced themselves")
+                .expect("the keychain is of the committee");
+            signings.push(signing);
+        }
+        signings
+    }
+
+    #[tokio::test]
+    async fn all_members_sign_the_genesis() {
+        let mut signings = signings(4).await;
+        let signatures: Vec<_> = signings
+            .iter()
+            .map(|signing| signing.signature().clone())
+            .collect();
+        let signing = &mut signings[0];
+        // Enough members for other multisignatures are not enough for the genesis.
+        for signature in &signatures[1..3] {
+            assert_eq!(signing.on_signature(signature.clone()), Ok(()));
+        }
+        assert_eq!(signing.missing(), vec![NodeIndex(3)]);
+        assert_eq!(signing.blob(), None);
+        assert_eq!(signing.on_signature(signatures[3].clone()), Ok(()));
+        let blob = signing.blob().expect("all the members signed");
+
+        let keychain = Keychain::new(NodeCount(4), NodeIndex(2));
+        let genesis = load_genesis(&blob, &keychain).expect("the blob is correct");
+        assert_eq!(&genesis, signing.genesis());
+        assert_eq!(genesis.members()[3], member(3));
+        let config = genesis.config(NodeIndex(2));
+        assert_eq!(config.n_members, NodeCount(4));
+        assert_eq!(config.max_round, 1000);
+        assert_eq!(config.session_id, SessionId::from_number(3));
+        assert_eq!(
+            load_genesis(&blob, &Keychain::new(NodeCount(5), NodeIndex(2))),
+            Err(GenesisError::WrongCommittee)
+        );
+        assert_eq!(
+            load_genesis(&blob[..blob.len() - 1], &keychain),
+            Err(GenesisError::Malformed)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_typos() {
+        let mut ceremonies = ceremonies(4);
+        let mut announcement = ceremonies[1].announcement().clone();
+        announcement.parameters.max_round = 100;
+        assert_eq!(
+            ceremonies[0].on_announcement(announcement),
+            Err(GenesisError::ParametersMismatch(NodeIndex(1)))
+        );
+        let mut announcement = ceremonies[1].announcement().clone();
+        assert_eq!(ceremonies[0].on_announcement(announcement.clone()), Ok(()));
+        announcement.member.address = b"10.0.0.1:300".to_vec();
+        assert_eq!(
+            ceremonies[0].on_announcement(announcement),
+            Err(GenesisError::ConflictingMember(NodeIndex(1)))
+        );
+        assert_eq!(ceremonies[0].missing(), vec![NodeIndex(2), NodeIndex(3)]);
+        assert_eq!(ceremonies[0].genesis(), None);
+
+        // A member that got a wrong address of another signs a different genesis.
+        let mut signings = signings(4).await;
+        let mut genesis = signings[1].genesis().clone();
+        genesis.members[2].address = b"10.0.0.3:3000".to_vec();
+        let confused = GenesisSigning::new(genesis, Keychain::new(NodeCount(4), NodeIndex(1)))
+            .await
+            .expect("the keychain is of the committee");
+        assert_eq!(
+            signings[0].on_signature(confused.signature().clone()),
+            Err(GenesisError::DifferentGenesis(NodeIndex(1)))
+        );
+        assert_eq!(signings[0].missing().len(), 3);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::Round;
+use parking_lot::Mutex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A part of a running session reporting its health.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Subsystem {
+    /// Creates our units.
+    Creator,
+    /// Adds units to the Dag once their parents are there.
+    Terminal,
+    /// Orders the units of the Dag.
+    Extender,
+    /// Requests missing units and responds to requests of others.
+    Syncer,
+    /// Handles alerts about forks.
+    Alerter,
+    /// Passes messages between the network and the other subsystems.
+    Network,
+}
+
+/// The health of a single [`Subsystem`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SubsystemHealth {
+    /// When the subsystem last processed an event, `None` if it did not yet.
+    pub last_activity: Option<Instant>,
+    /// How many items await processing by the subsystem, e.g. units waiting for parents in the
+    /// terminal or scheduled requests in the syncer. Subsystems without a queue of their own
+    /// report zero.
+    pub queue_depth: usize,
+    /// How many errors the subsystem handled so far, e.g. rejected units.
+    pub errors: usize,
+}
+
+/// An internal invariant of the consensus that was violated, indicating a bug or a corrupted
+/// state rather than misbehavior of other nodes. See [`InvariantPolicy`] for what happens then.
+///
+/// [`InvariantPolicy`]: crate::InvariantPolicy
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum InvariantViolation {
+    /// A batch was ordered with a head from a round not above the round of the previous head,
+    /// e.g. two heads were elected for the same round.
+    HeadRoundNotIncreasing { previous: Round, head: Round },
+    /// A unit we created failed validation.
+    InvalidOwnUnit,
+    /// A unit the consensus knows about is missing from the store.
+    UnitNotInStore,
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            InvariantViolation::HeadRoundNotIncreasing { previous, head } => write!(
+                f,
+                "ordered a head of round {} after a head of round {}",
+                head, previous
+            ),
+            InvariantViolation::InvalidOwnUnit => write!(f, "created a unit failing validation"),
+            InvariantViolation::UnitNotInStore => {
+                write!(f, "a unit known to the consensus is not in the store")
+            }
+        }
+    }
+}
+
+/// The health of all the subsystems of a session.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HealthReport {
+    pub creator: SubsystemHealth,
+    pub terminal: SubsystemHealth,
+    pub extender: SubsystemHealth,
+    pub syncer: SubsystemHealth,
+    pub alerter: SubsystemHealth,
+    pub network: SubsystemHealth,
+    /// The violation the session was halted because of, if any.
+    pub halted: Option<InvariantViolation>,
+}
+
+impl HealthReport {
+    pub fn subsystem(&self, subsystem: Subsystem) -> &SubsystemHealth {
+        match subsystem {
+            Subsystem::Creator => &self.creator,
+            Subsystem::Terminal => &self.terminal,
+            Subsystem::Extender => &self.extender,
+            Subsystem::Syncer => &self.syncer,
+            Subsystem::Alerter => &self.alerter,
+            Subsystem::Network => &self.network,
+        }
+    }
+
+    fn subsystem_mut(&mut self, subsystem: Subsystem) -> &mut SubsystemHealth {
+        match subsystem {
+            Subsystem::Creator => &mut self.creator,
+            Subsystem::Terminal => &mut self.terminal,
+            Subsystem::Extender => &mut self.extender,
+            Subsystem::Syncer => &mut self.syncer,
+            Subsystem::Alerter => &mut self.alerter,
+            Subsystem::Network => &mut self.network,
+        }
+    }
+
+    // The alerter only has work to do when someone forks, so its inactivity says nothing.
+    fn busy_subsystems(&self) -> [&SubsystemHealth; 5] {
+        [
+            &self.creator,
+            &self.terminal,
+            &self.extender,
+            &self.syncer,
+            &self.network,
+        ]
+    }
+
+    /// Whether the session started working, i.e. all subsystems that are always busy, so all
+    /// but the alerter, processed at least one event. Suitable for a readiness probe.
+    pub fn is_ready(&self) -> bool {
+        self.busy_subsystems()
+            .iter()
+            .all(|health| health.last_activity.is_some())
+    }
+
+    /// Whether all subsystems that are always busy were active within `max_idle` before `now`,
+    /// and the session was not halted. Suitable for a liveness probe, `max_idle` should be a few
+    /// times the unit creation delay.
+    pub fn is_live(&self, now: Instant, max_idle: Duration) -> bool {
+        self.halted.is_none()
+            && self.busy_subsystems().iter().all(|health| {
+                health
+                    .last_activity
+                    .map(|last_activity| now.saturating_duration_since(last_activity) <= max_idle)
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// A handle for querying the health of a session. All clones refer to the same report, so the
+/// application should keep a clone of the handle passed to the session.
+#[derive(Clone, Default)]
+pub struct Health {
+    report: Arc<Mutex<HealthReport>>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current health of all subsystems.
+    pub fn report(&self) -> HealthReport {
+        self.report.lock().clone()
+    }
+
+    pub(crate) fn on_activity(&self, subsystem: Subsystem) {
+        self.report.lock().subsystem_mut(subsystem).last_activity = Some(Instant::now());
+    }
+
+    pub(crate) fn on_error(&self, subsystem: Subsystem) {
+        self.report.lock().subsystem_mut(subsystem).errors += 1;
+    }
+
+    pub(crate) fn set_queue_depth(&self, subsystem: Subsystem, queue_depth: usize) {
+        self.report.lock().subsystem_mut(subsystem).queue_depth = queue_depth;
+    }
+
+    pub(crate) fn on_halted(&self, violation: InvariantViolation) {
+        self.report.lock().halted = Some(violation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Health, InvariantViolation, Subsystem};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn reports_liveness_of_busy_subsystems() {
+        let health = Health::new();
+        assert!(!health.report().is_ready());
+        for subsystem in [
+            Subsystem::Creator,
+            Subsystem::Terminal,
+            Subsystem::Extender,
+            Subsystem::Syncer,
+        ] {
+            health.on_activity(subsystem);
+        }
+        assert!(!health.report().is_ready());
+        health.on_activity(Subsystem::Network);
+        health.on_error(Subsystem::Network);
+        health.set_queue_depth(Subsystem::Terminal, 3);
+
+        let report = health.report();
+        assert!(report.is_ready());
+        assert!(report.is_live(Instant::now(), Duration::from_secs(60)));
+        assert!(!report.is_live(
+            Instant::now() + Duration::from_secs(120),
+            Duration::from_secs(60)
+        ));
+        assert_eq!(report.subsystem(Subsystem::Network).errors, 1);
+        assert_eq!(report.terminal.queue_depth, 3);
+        assert_eq!(report.alerter.last_activity, None);
+
+        health.on_halted(InvariantViolation::UnitNotInStore);
+        assert!(!health
+            .report()
+            .is_live(Instant::now(), Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{KeyEpoch, Keychain, Round};
+use codec::{Decode, Encode};
+use log::warn;
+use parking_lot::Mutex;
+use std::{
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+/// Why a rotation was rejected by [`KeyRotation::rotate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RotationError {
+    /// The rotation does not start after the round of the previous one.
+    RoundNotIncreasing { previous: Round, round: Round },
+    /// The epoch is not newer than the epoch of the previous rotation.
+    EpochNotIncreasing { previous: KeyEpoch, epoch: KeyEpoch },
+    /// The rotation could not be saved to the backup of the schedule, so it was not scheduled.
+    Backup(io::ErrorKind),
+}
+
+impl Display for RotationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RotationError::RoundNotIncreasing { previous, round } => write!(
+                f,
+                "rotation at round {} does not follow the rotation at round {}",
+                round, previous
+            ),
+            RotationError::EpochNotIncreasing { previous, epoch } => write!(
+                f,
+                "rotation to epoch {} does not follow the rotation to epoch {}",
+                epoch, previous
+            ),
+            RotationError::Backup(kind) => write!(f, "failed to save the rotation: {}", kind),
+        }
+    }
+}
+
+impl Error for RotationError {}
+
+/// The key epochs of the rounds of a session, shared by everything signing or verifying units.
+#[derive(Clone, Default)]
+pub(crate) struct KeySchedule {
+    rotations: Arc<Mutex<Vec<(Round, KeyEpoch)>>>,
+    saver: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+}
+
+impl Debug for KeySchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("KeySchedule")
+            .field("rotations", &*self.rotations.lock())
+            .finish()
+    }
+}
+
+// Schedules are equal if they are clones of each other.
+impl PartialEq for KeySchedule {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.rotations, &other.rotations)
+    }
+}
+
+impl Eq for KeySchedule {}
+
+impl Hash for KeySchedule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.rotations).hash(state);
+    }
+}
+
+impl KeySchedule {
+    /// The epoch of the keys of units of the round, zero until the first rotation.
+    pub(crate) fn epoch(&self, round: Round) -> KeyEpoch {
+        self.rotations
+            .lock()
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= round)
+            .map(|(_, epoch)| *epoch)
+            .unwrap_or(0)
+    }
+
+    /// The keychain for units of the round.
+    pub(crate) fn keychain<K: Keychain>(&self, keychain: &K, round: Round) -> K {
+        keychain.at_epoch(self.epoch(round))
+    }
+
+    /// Schedules the rotations saved by `saver` in an earlier run, read from `loader`, and saves
+    /// every further rotation with `saver`. A record that cannot be decoded, e.g. because we
+    /// crashed while writing it, ends the backup.
+    pub(crate) fn enable_backup(
+        &self,
+        saver: impl Write + Send + 'static,
+        mut loader: impl Read,
+    ) -> io::Result<()> {
+        let mut buf = Vec::new();
+        loader.read_to_end(&mut buf)?;
+        let input = &mut &buf[..];
+        let mut rotations = self.rotations.lock();
+        while !input.is_empty() {
+            match <(Round, KeyEpoch)>::decode(input) {
+                Ok(rotation) => rotations.push(rotation),
+                Err(e) => {
+                    warn!(target: "AlephBFT-key-rotation", "key schedule backup ends with a broken record: {}", e);
+                    break;
+                }
+            }
+        }
+        *self.saver.lock() = Some(Box::new(saver));
+        Ok(())
+    }
+}
+
+/// A handle for rotating the keys of a running session. The keychain of the session has to know
+/// the keys of the new epoch, see [`Keychain::at_epoch`] and [`EpochKeychain`], and all members
+/// have to schedule the same rotations, e.g. agreed on in a vote, see [`VoteCollector`].
+///
+/// A rotation created with [`KeyRotation::default`] is not used by any session, it is meant for
+/// verifying units outside of one, e.g. with [`migrate_backup`].
+///
+/// [`EpochKeychain`]: crate::EpochKeychain
+/// [`VoteCollector`]: crate::VoteCollector
+/// [`migrate_backup`]: crate::migrate_backup
+#[derive(Clone, Default)]
+pub struct KeyRotation {
+    schedule: KeySchedule,
+}
+
+impl KeyRotation {
+    pub(crate) fn new(schedule: KeySchedule) -> Self {
+        KeyRotation { schedule }
+    }
+
+    pub(crate) fn schedule(&self) -> &KeySchedule {
+        &self.schedule
+    }
+
+    /// Makes units of rounds from `round` on signed and verified with the keys of `epoch`. The
+    /// rounds and the epochs have to increase with every rotation. The round should be far
+    /// enough ahead for the rotation to be scheduled before any node gets there. Rotations loaded
+    /// from the backup of the schedule must not be scheduled again.
+    pub fn rotate(&self, round: Round, epoch: KeyEpoch) -> Result<(), RotationError> {
+        let mut rotations = self.schedule.rotations.lock();
+        if let Some((previous_round, previous_epoch)) = rotations.last().copied() {
+            if round <= previous_round {
+                return Err(RotationError::RoundNotIncreasing {
+                    previous: previous_round,
+                    round,
+                });
+            }
+            if epoch <= previous_epoch {
+                return Err(RotationError::EpochNotIncreasing {
+                    previous: previous_epoch,
+                    epoch,
+                });
+            }
+        }
+        if let Some(saver) = self.schedule.saver.lock().as_mut() {
+            saver
+                .write_all(&(round, epoch).encode())
+                .and_then(|_| saver.flush())
+                .map_err(|e| RotationError::Backup(e.kind()))?;
+        }
+        rotations.push((round, epoch));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyRotation, KeySchedule, RotationError};
+    use aleph_bft_mock::{Loader, Saver};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[test]
+    fn selects_epochs_of_rounds() {
+        let schedule = KeySchedule::default();
+        let rotation = KeyRotation::new(schedule.clone());
+        assert_eq!(schedule.epoch(100), 0);
+
+        assert_eq!(rotation.rotate(10, 1), Ok(()));
+        assert_eq!(rotation.rotate(20, 3), Ok(()));
+        assert_eq!(
+            rotation.rotate(20, 4),
+            Err(RotationError::RoundNotIncreasing {
+                previous: 20,
+                round: 20
+            })
+        );
+        assert_eq!(
+            rotation.rotate(30, 3),
+            Err(RotationError::EpochNotIncreasing {
+                previous: 3,
+                epoch: 3
+            })
+        );
+        let epochs: Vec<_> = [0, 9, 10, 19, 20, 100]
+            .into_iter()
+            .map(|round| schedule.epoch(round))
+            .collect();
+        assert_eq!(epochs, vec![0, 0, 1, 1, 3, 3]);
+    }
+
+    #[test]
+    fn restores_saved_rotations() {
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let schedule = KeySchedule::default();
+        schedule
+            .enable_backup(Saver::from(saved.clone()), Loader::new(Vec::new()))
+            .expect("loads");
+        let rotation = KeyRotation::new(schedule);
+        assert_eq!(rotation.rotate(10, 1), Ok(()));
+        assert_eq!(rotation.rotate(20, 2), Ok(()));
+
+        let restored = KeySchedule::default();
+        restored
+            .enable_backup(Saver::new(), Loader::new(saved.lock().clone()))
+            .expect("loads");
+        let epochs: Vec<_> = [9, 10, 20]
+            .into_iter()
+            .map(|round| restored.epoch(round))
+            .collect();
+        assert_eq!(epochs, vec![0, 1, 2]);
+        assert_eq!(
+            KeyRotation::new(restored).rotate(20, 3),
+            Err(RotationError::RoundNotIncreasing {
+                previous: 20,
+                round: 20
+            })
+        );
+    }
+}
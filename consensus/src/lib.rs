@@ -3,31 +3,127 @@
 //! gives appropriate access to the set of available data that we need to make consensus on.
 
 mod alerts;
+mod alloc_profiling;
+mod audit;
+mod budget;
+mod builder;
+mod checkpoint;
+mod compaction;
 mod config;
 mod consensus;
+mod cooperation;
 mod creation;
+mod dag_export;
+mod encryption;
+mod error;
+mod explain;
 mod extender;
+mod finalization;
+mod genesis;
+mod health;
+mod key_rotation;
 mod member;
+mod metadata;
+mod multi_data;
 mod network;
+mod partial_order;
+mod priority;
+mod request_window;
 mod runway;
+mod stats;
 mod terminal;
 mod terminator;
+mod tracking;
 mod units;
+mod vote;
+mod withholding;
 
 mod task_queue;
 #[cfg(test)]
 mod testing;
+// Our own tests have their own testing module, the public one is for tests of applications.
+#[cfg(all(feature = "testing", not(test)))]
+pub use aleph_bft_types::testing;
 
+#[cfg(feature = "bls")]
+pub use aleph_bft_types::bls;
+#[cfg(feature = "ed25519")]
+pub use aleph_bft_types::ed25519;
 pub use aleph_bft_types::{
-    Data, DataProvider, FinalizationHandler, Hasher, IncompleteMultisignatureError, Index, Indexed,
-    Keychain, MultiKeychain, Multisigned, Network, NodeCount, NodeIndex, NodeMap, NodeSubset,
-    PartialMultisignature, PartiallyMultisigned, Recipient, Round, SessionId, Signable, Signature,
-    SignatureError, SignatureSet, Signed, SpawnHandle, TaskHandle, UncheckedSigned,
+    check_keybox_conformance, signable_by_encoding, verify_certificate, AddSignatureError,
+    AddressedRawNetwork, AggregatingMultiKeychain, CachingMultiKeychain, Certificate,
+    CertificateError, CompactEncoding, CompactMultisignature, ConformanceError, Data, DataProvider,
+    DomainKeychain, DualKeychain, DualScheme, DualSignature, EpochKeychain, FinalizationHandler,
+    Hasher, IncompleteMultisignatureError, Index, Indexed, KeyEpoch, Keychain, MultiKeychain,
+    Multisigned, Network, NodeCount, NodeIndex, NodeMap, NodeSubset, PartialMultisignature,
+    PartiallyMultisigned, PublicVerification, Quarantine, RawNetwork, Recipient, Round, SessionId,
+    Signable, Signature, SignatureError, SignatureSet, Signed, SpawnHandle, StreamingVerification,
+    TaskHandle, ThresholdEncryption, UncheckedSigned, VerificationCache, VerificationStatus, Vrf,
+    WeightedMultiKeychain,
 };
-pub use config::{default_config, exponential_slowdown, Config, DelayConfig};
+pub use alerts::{
+    check_alert_trace, Alert, AlertAction, AlertEvent, AlertHandler, AlertMessage, AlertTimeout,
+    ForkingNotification, TraceData, TraceError,
+};
+#[cfg(feature = "alloc_profiling")]
+pub use alloc_profiling::{
+    allocation_profile, AllocationCounts, AllocationProfile, CountingAllocator,
+};
+pub use audit::{FinalizationAcknowledgement, FinalizedDigest};
+pub use builder::{IgnoreFinalized, MemberBuilder, NoData, NoNetwork, RunnableMember};
+pub use checkpoint::{
+    aggregate_checkpoints, checkpoint_voting, verify_aggregated_checkpoints, verify_checkpoint,
+    AggregatedCheckpoints, Checkpoint, CheckpointCertificate, CheckpointError,
+};
+pub use compaction::{Compaction, CompactionReport};
+pub use config::{
+    default_config, exponential_slowdown, AlertLimits, CatchUpSources, Config, DelayConfig,
+    DuplicateUnitPolicy, ForkerDataPolicy, InvariantPolicy, ParentPolicy, RequestConcurrency,
+    ResourceBudget, TopologyHints, ValidationProfile,
+};
+pub use dag_export::DagExport;
+pub use encryption::{
+    threshold_decryption, CiphertextHandler, DecryptionError, DecryptionShareMessage, Decryptor,
+    EncryptingDataProvider,
+};
+pub use error::Error;
+pub use explain::{CandidateDecision, OrderingLog, RoundExplanation};
+pub use extender::{DagUnit, DagUnitError, StandaloneExtender};
+pub use finalization::{FinalizationArchive, IndexedBatch, ResumeError};
+pub use genesis::{
+    load_genesis, Genesis, GenesisAnnouncement, GenesisCeremony, GenesisError, GenesisMember,
+    GenesisParameters, GenesisSigning,
+};
+pub use health::{Health, HealthReport, InvariantViolation, Subsystem, SubsystemHealth};
+pub use key_rotation::{KeyRotation, RotationError};
 pub use member::{run_session, LocalIO};
-pub use network::NetworkData;
+pub use metadata::{
+    MetadataFinalizationHandler, MetadataHandle, MetadataProvider, WithMetadata, MAX_METADATA_SIZE,
+};
+pub use multi_data::{
+    FlatteningFinalizationHandler, MultiDataProvider, PayloadFeedback, PayloadLimits,
+};
+#[cfg(feature = "quic")]
+pub use network::quic;
+#[cfg(feature = "tcp")]
+pub use network::tcp;
+pub use network::{
+    ChaosCommandError, ChaosControl, ChaosNetwork, ChaosSettings, CodecNetwork, CodecPool,
+    DedupNetwork, FilteredNetwork, NetworkData, NetworkSwitch, PeerFilter, SessionMultiplexer,
+    SessionNetwork, SessionOpener, SwitchableNetwork, FRAME_VERSION,
+};
+pub use partial_order::{BatchDependencies, OrderedUnit};
+pub use priority::{PriorityDataProvider, PriorityHandle};
+pub use runway::{
+    migrate_backup, CreatorInclusion, FaultAlarm, GrowthRuleEvidence, LoaderError, PayloadCheck,
+};
+pub use stats::{SessionSummary, Stats, StatsSnapshot};
+pub use terminal::GrowthRuleViolation;
 pub use terminator::{handle_task_termination, Terminator};
+pub use tracking::{DataEvent, TrackingDataProvider, TrackingFinalizationHandler};
+pub use units::{RejectionReason, UnitRejection};
+pub use vote::{SignedVote, Vote, VoteCertificate, VoteCollector, VoteError};
+pub use withholding::WithholdingSuspicion;
 
 type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
 type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
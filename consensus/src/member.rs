@@ -1,50 +1,207 @@
 use crate::{
+    compaction::compaction,
+    dag_export::dag_export,
     handle_task_termination,
+    key_rotation::KeySchedule,
     member::Task::{CoordRequest, ParentsRequest, RequestNewest, UnitBroadcast},
     network,
+    request_window::RequestWindow,
     runway::{
-        self, NetworkIO, NewestUnitResponse, Request, Response, RunwayIO, RunwayNotificationIn,
-        RunwayNotificationOut,
+        self, BroadcastBackup, BroadcastStatus, FaultAlarm, NetworkIO, NewestUnitResponse,
+        PayloadCheck, Request, Response, RunwayIO, RunwayNotificationIn, RunwayNotificationOut,
     },
+    stats::SummaryOutput,
     task_queue::TaskQueue,
-    units::{UncheckedSignedUnit, UnitCoord},
-    Config, Data, DataProvider, FinalizationHandler, Hasher, MultiKeychain, Network, NodeIndex,
-    Receiver, Recipient, Round, Sender, Signature, SpawnHandle, Terminator, UncheckedSigned,
+    units::{UncheckedSignedUnit, UnitCoord, UnitRejection, UnitVrf},
+    withholding::{WithholdingDetector, REPORT_INTERVAL},
+    BatchDependencies, Compaction, CompactionReport, Config, DagExport, Data, DataProvider,
+    DuplicateUnitPolicy, Error, FinalizationAcknowledgement, FinalizationHandler, Hasher, Health,
+    Index, KeyRotation, Keychain, MultiKeychain, Network, NodeIndex, OrderingLog, Receiver,
+    Recipient, Round, Sender, SessionSummary, Signable, Signature, Signed, SpawnHandle, Stats,
+    Subsystem, Terminator, UncheckedSigned, Vrf, WithholdingSuspicion,
 };
 use aleph_bft_types::NodeMap;
 use codec::{Decode, Encode};
-use futures::{channel::mpsc, pin_mut, FutureExt, StreamExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    pin_mut, FutureExt, StreamExt,
+};
 use futures_timer::Delay;
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
 use network::NetworkData;
 use rand::{prelude::SliceRandom, Rng};
 use std::{
-    collections::HashSet,
+    collections::{HashSet, VecDeque},
     convert::TryInto,
     fmt::{self, Debug},
     io::{Read, Write},
     marker::PhantomData,
-    time::Duration,
+    path::PathBuf,
+    time::{Duration, Instant},
 };
 
 /// A message concerning units, either about new units or some requests for them.
-#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+#[derive(Clone, Eq, PartialEq, Debug, Encode, Decode)]
 pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
     /// For disseminating newly created units.
     NewUnit(UncheckedSignedUnit<H, D, S>),
     /// Request for a unit by its coord.
     RequestCoord(NodeIndex, UnitCoord),
-    /// Response to a request by coord.
-    ResponseCoord(UncheckedSignedUnit<H, D, S>),
+    /// Response to a request by coord, possibly with the responder's newest unit piggybacked.
+    ResponseCoord(
+        UncheckedSignedUnit<H, D, S>,
+        Option<UncheckedSignedUnit<H, D, S>>,
+    ),
     /// Request for the full list of parents of a unit.
     RequestParents(NodeIndex, H::Hash),
-    /// Response to a request for a full list of parents.
-    ResponseParents(H::Hash, Vec<UncheckedSignedUnit<H, D, S>>),
+    /// Response to a request for a full list of parents, possibly with the responder's newest unit
+    /// piggybacked.
+    ResponseParents(
+        H::Hash,
+        Vec<UncheckedSignedUnit<H, D, S>>,
+        Option<UncheckedSignedUnit<H, D, S>>,
+    ),
     /// Request by a node for the newest unit created by them, together with a u64 salt
     RequestNewest(NodeIndex, u64),
     /// Response to RequestNewest: (our index, maybe unit, salt) signed by us
     ResponseNewest(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
+    /// Acknowledgement by a node that it received a unit, sent to its creator.
+    UnitAck(UncheckedSigned<UnitAcknowledgement<H>, S>),
+    /// The highest rounds of every creator a node has, gossiped for detecting withholding.
+    TopRounds(UncheckedSigned<TopRoundsReport<H>, S>),
+    /// Response of a node to a unit it already had, sent to the creator of the unit.
+    DuplicateHint(UncheckedSigned<DuplicateUnitHint<H>, S>),
+    /// Response of a node to a request for units it does not serve anymore, because they are
+    /// beyond its history limit, with the lowest round it still serves.
+    HistoryPruned(UncheckedSigned<HistoryFloor<H>, S>),
+}
+
+/// An acknowledgement by a node that it received the unit with the given hash. It is signed, as
+/// it lets the creator stop broadcasting the unit to the node.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode, Decode)]
+pub(crate) struct UnitAcknowledgement<H: Hasher> {
+    node: NodeIndex,
+    hash: H::Hash,
+}
+
+impl<H: Hasher> Signable for UnitAcknowledgement<H> {
+    type Hash = H::Hash;
+
+    fn hash(&self) -> Self::Hash {
+        H::hash(&self.encode())
+    }
+}
+
+impl<H: Hasher> Index for UnitAcknowledgement<H> {
+    fn index(&self) -> NodeIndex {
+        self.node
+    }
+}
+
+impl<H: Hasher> UnitAcknowledgement<H> {
+    pub(crate) fn new(node: NodeIndex, hash: H::Hash) -> Self {
+        UnitAcknowledgement { node, hash }
+    }
+}
+
+/// A hint of a node that it already had the unit with the given hash, with the highest rounds of
+/// every creator it has. It is signed, as it acknowledges units of the creator.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode, Decode)]
+pub(crate) struct DuplicateUnitHint<H: Hasher> {
+    node: NodeIndex,
+    hash: H::Hash,
+    top_rounds: NodeMap<Round>,
+}
+
+impl<H: Hasher> Signable for DuplicateUnitHint<H> {
+    type Hash = H::Hash;
+
+    fn hash(&self) -> Self::Hash {
+        H::hash(&self.encode())
+    }
+}
+
+impl<H: Hasher> Index for DuplicateUnitHint<H> {
+    fn index(&self) -> NodeIndex {
+        self.node
+    }
+}
+
+impl<H: Hasher> DuplicateUnitHint<H> {
+    pub(crate) fn new(node: NodeIndex, hash: H::Hash, top_rounds: NodeMap<Round>) -> Self {
+        DuplicateUnitHint {
+            node,
+            hash,
+            top_rounds,
+        }
+    }
+}
+
+/// The highest rounds of every creator a node has. It is signed, so that a node cannot make
+/// others look deprived of units by reporting in their names.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode, Decode)]
+pub(crate) struct TopRoundsReport<H: Hasher> {
+    node: NodeIndex,
+    top_rounds: NodeMap<Round>,
+    _phantom: PhantomData<H>,
+}
+
+impl<H: Hasher> Signable for TopRoundsReport<H> {
+    type Hash = H::Hash;
+
+    fn hash(&self) -> Self::Hash {
+        H::hash(&self.encode())
+    }
+}
+
+impl<H: Hasher> Index for TopRoundsReport<H> {
+    fn index(&self) -> NodeIndex {
+        self.node
+    }
+}
+
+impl<H: Hasher> TopRoundsReport<H> {
+    pub(crate) fn new(node: NodeIndex, top_rounds: NodeMap<Round>) -> Self {
+        TopRoundsReport {
+            node,
+            top_rounds,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The lowest round a node still serves units of. It is signed, so that a node cannot make others
+/// stop asking a peer for units by answering in its name.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode, Decode)]
+pub(crate) struct HistoryFloor<H: Hasher> {
+    node: NodeIndex,
+    floor: Round,
+    _phantom: PhantomData<H>,
+}
+
+impl<H: Hasher> Signable for HistoryFloor<H> {
+    type Hash = H::Hash;
+
+    fn hash(&self) -> Self::Hash {
+        H::hash(&self.encode())
+    }
+}
+
+impl<H: Hasher> Index for HistoryFloor<H> {
+    fn index(&self) -> NodeIndex {
+        self.node
+    }
+}
+
+impl<H: Hasher> HistoryFloor<H> {
+    pub(crate) fn new(node: NodeIndex, floor: Round) -> Self {
+        HistoryFloor {
+            node,
+            floor,
+            _phantom: PhantomData,
+        }
+    }
 }
 
 impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
@@ -52,14 +209,22 @@ impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
         match self {
             Self::NewUnit(uu) => uu.as_signable().included_data(),
             Self::RequestCoord(_, _) => Vec::new(),
-            Self::ResponseCoord(uu) => uu.as_signable().included_data(),
+            Self::ResponseCoord(uu, frontier) => std::iter::once(uu)
+                .chain(frontier)
+                .flat_map(|uu| uu.as_signable().included_data())
+                .collect(),
             Self::RequestParents(_, _) => Vec::new(),
-            Self::ResponseParents(_, units) => units
+            Self::ResponseParents(_, units, frontier) => units
                 .iter()
+                .chain(frontier)
                 .flat_map(|uu| uu.as_signable().included_data())
                 .collect(),
             UnitMessage::RequestNewest(_, _) => Vec::new(),
             UnitMessage::ResponseNewest(response) => response.as_signable().included_data(),
+            UnitMessage::UnitAck(_) => Vec::new(),
+            UnitMessage::TopRounds(_) => Vec::new(),
+            UnitMessage::DuplicateHint(_) => Vec::new(),
+            UnitMessage::HistoryPruned(_) => Vec::new(),
         }
     }
 }
@@ -70,12 +235,30 @@ enum Task<H: Hasher, D: Data, S: Signature> {
     CoordRequest(UnitCoord),
     // Request parents of the unit with the given hash and Recipient.
     ParentsRequest(H::Hash),
-    // Rebroadcast a given unit periodically (cancelled after a more recent unit by the same creator is received)
+    // Rebroadcast a given unit periodically (cancelled after a more recent unit by the same creator is received,
+    // or, for our own units, once all peers acknowledged it)
     UnitBroadcast(UncheckedSignedUnit<H, D, S>),
     // Request the newest unit created by node itself.
     RequestNewest(u64),
 }
 
+// Identifies a request for units, for the purpose of limiting how many are outstanding.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+enum RequestKey<T> {
+    Coord(UnitCoord),
+    Parents(T),
+}
+
+impl<H: Hasher> RequestKey<H::Hash> {
+    fn of<D: Data, S: Signature>(task: &Task<H, D, S>) -> Option<Self> {
+        match task {
+            CoordRequest(coord) => Some(RequestKey::Coord(*coord)),
+            ParentsRequest(hash) => Some(RequestKey::Parents(*hash)),
+            UnitBroadcast(_) | RequestNewest(_) => None,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Debug)]
 struct RepeatableTask<H: Hasher, D: Data, S: Signature> {
     task: Task<H, D, S>,
@@ -113,6 +296,21 @@ pub struct LocalIO<D: Data, DP: DataProvider<D>, FH: FinalizationHandler<D>, US:
     finalization_handler: FH,
     unit_saver: US,
     unit_loader: UL,
+    stats: Stats,
+    health: Health,
+    ordering_log: Option<OrderingLog>,
+    payload_checks: Option<Sender<PayloadCheck<D>>>,
+    dag_export_requests: Option<Receiver<oneshot::Sender<String>>>,
+    compaction_requests: Option<Receiver<oneshot::Sender<CompactionReport>>>,
+    acknowledgements: Option<(usize, Sender<FinalizationAcknowledgement>)>,
+    broadcast_backup: Option<BroadcastBackup>,
+    withholding_suspicions: Option<(Round, Sender<WithholdingSuspicion>)>,
+    batch_dependencies: Option<Sender<BatchDependencies>>,
+    session_summary: SummaryOutput,
+    key_schedule: KeySchedule,
+    vrf: Option<UnitVrf>,
+    rejections: Option<Sender<UnitRejection>>,
+    fault_alarms: Option<Sender<FaultAlarm>>,
     _phantom: PhantomData<D>,
 }
 
@@ -130,9 +328,196 @@ impl<D: Data, DP: DataProvider<D>, FH: FinalizationHandler<D>, US: Write, UL: Re
             finalization_handler,
             unit_saver,
             unit_loader,
+            stats: Stats::new(),
+            health: Health::new(),
+            ordering_log: None,
+            payload_checks: None,
+            dag_export_requests: None,
+            compaction_requests: None,
+            acknowledgements: None,
+            broadcast_backup: None,
+            withholding_suspicions: None,
+            batch_dependencies: None,
+            session_summary: SummaryOutput::default(),
+            key_schedule: KeySchedule::default(),
+            vrf: None,
+            rejections: None,
+            fault_alarms: None,
             _phantom: PhantomData,
         }
     }
+
+    /// A handle for querying the statistics of the session run with this IO.
+    pub fn stats(&self) -> Stats {
+        self.stats.clone()
+    }
+
+    /// A handle for querying the health of the subsystems of the session run with this IO, e.g.
+    /// for liveness and readiness probes.
+    pub fn health(&self) -> Health {
+        self.health.clone()
+    }
+
+    /// A handle for rotating the keys of the session run with this IO, see [`KeyRotation`].
+    pub fn key_rotation(&self) -> KeyRotation {
+        KeyRotation::new(self.key_schedule.clone())
+    }
+
+    /// Makes the session persist the rotations of its keys, so that after a restart it verifies
+    /// units of earlier epochs, e.g. the ones from the unit backup, with the right keys. The
+    /// rotations saved by an earlier run are scheduled right away, and must not be scheduled
+    /// again. The `saver` and `loader` should refer to the same storage, which is reset together
+    /// with the unit backup. Fails only if reading the `loader` fails.
+    pub fn enable_key_schedule_backup(
+        &mut self,
+        saver: impl Write + Send + 'static,
+        loader: impl Read,
+    ) -> std::io::Result<()> {
+        self.key_schedule.enable_backup(saver, loader)
+    }
+
+    pub(crate) fn set_stats(&mut self, stats: Stats) {
+        self.stats = stats;
+    }
+
+    pub(crate) fn set_health(&mut self, health: Health) {
+        self.health = health;
+    }
+
+    /// Makes the session explain how it ordered the given number of most recently finalized
+    /// rounds, queryable through the returned handle. Without calling this, nothing is recorded.
+    pub fn enable_ordering_log(&mut self, retained_rounds: usize) -> OrderingLog {
+        let ordering_log = OrderingLog::new(retained_rounds);
+        self.ordering_log = Some(ordering_log.clone());
+        ordering_log
+    }
+
+    pub(crate) fn set_ordering_log(&mut self, ordering_log: OrderingLog) {
+        self.ordering_log = Some(ordering_log);
+    }
+
+    /// Makes the session send the data of every unit received from other nodes to the returned
+    /// channel for validation, see [`PayloadCheck`]. Without calling this, all data is accepted.
+    pub fn enable_payload_checks(&mut self) -> mpsc::UnboundedReceiver<PayloadCheck<D>> {
+        let (payload_checks, payload_checks_rx) = mpsc::unbounded();
+        self.payload_checks = Some(payload_checks);
+        payload_checks_rx
+    }
+
+    /// Lets the returned handle export the Dag of the session for visualization, see
+    /// [`DagExport`].
+    pub fn enable_dag_export(&mut self) -> DagExport {
+        let (dag_export, requests) = dag_export();
+        self.dag_export_requests = Some(requests);
+        dag_export
+    }
+
+    /// Lets the returned handle prune the unit store of the session on demand, see
+    /// [`Compaction`]. How much pruning achieved is published in the [`StatsSnapshot`] either way.
+    ///
+    /// [`StatsSnapshot`]: crate::StatsSnapshot
+    pub fn enable_compaction(&mut self) -> Compaction {
+        let (compaction, requests) = compaction();
+        self.compaction_requests = Some(requests);
+        compaction
+    }
+
+    /// Makes the session sign a digest of everything it finalized every `interval` batches and
+    /// send it to the returned channel, to be published for external auditors, see
+    /// [`FinalizationAcknowledgement`].
+    pub fn enable_finalization_acknowledgements(
+        &mut self,
+        interval: usize,
+    ) -> mpsc::UnboundedReceiver<FinalizationAcknowledgement> {
+        let (acknowledgements, acknowledgements_rx) = mpsc::unbounded();
+        self.acknowledgements = Some((interval, acknowledgements));
+        acknowledgements_rx
+    }
+
+    /// Makes the session persist which peers acknowledged receiving our newest unit, so that
+    /// after a restart it resends the unit only to the peers that did not. Without calling this,
+    /// a restarted session rebroadcasts its newest unit to everyone. The `saver` and `loader`
+    /// should refer to the same storage, which is separate from, but reset together with, the
+    /// unit backup. A status saved in a different session, or while we had a different index,
+    /// is ignored.
+    pub fn enable_broadcast_backup(
+        &mut self,
+        saver: impl Write + Send + 'static,
+        loader: impl Read + Send + 'static,
+    ) {
+        self.broadcast_backup = Some(BroadcastBackup::new(saver, loader));
+    }
+
+    /// Makes the session gossip the highest rounds it has of every creator and compare them with
+    /// the rounds gossiped by others, sending a [`WithholdingSuspicion`] to the returned channel
+    /// when a node keeps lacking at least `lag_threshold` more rounds of one creator than it
+    /// lacks of the others. Only nodes that called this gossip their rounds, so the more nodes
+    /// do, the more withholding is detected. Without calling this, nothing is gossiped.
+    pub fn enable_withholding_detection(
+        &mut self,
+        lag_threshold: Round,
+    ) -> mpsc::UnboundedReceiver<WithholdingSuspicion> {
+        let (suspicions, suspicions_rx) = mpsc::unbounded();
+        self.withholding_suspicions = Some((lag_threshold, suspicions));
+        suspicions_rx
+    }
+
+    /// Makes the session send the causal order among the units of every finalized batch to the
+    /// returned channel, right after their data is passed to the finalization handler, see
+    /// [`BatchDependencies`].
+    pub fn enable_batch_dependencies(&mut self) -> mpsc::UnboundedReceiver<BatchDependencies> {
+        let (batch_dependencies, batch_dependencies_rx) = mpsc::unbounded();
+        self.batch_dependencies = Some(batch_dependencies);
+        batch_dependencies_rx
+    }
+
+    /// Makes the session send a [`SessionSummary`] to the returned channel when it ends. The
+    /// summary is logged regardless.
+    pub fn enable_session_summary(&mut self) -> oneshot::Receiver<SessionSummary> {
+        let (session_summary, session_summary_rx) = oneshot::channel();
+        self.session_summary.channel = Some(session_summary);
+        session_summary_rx
+    }
+
+    /// Makes the session elect heads of rounds in the order of priorities proven with the VRF,
+    /// so that nobody can predict the order before the units of the round are out, see [`Vrf`].
+    /// Without calling this, heads are elected in the order of the hashes of units. All the
+    /// members of a session have to agree on whether to use a VRF, as units with proofs are
+    /// rejected by members without one, and the other way round.
+    pub fn enable_vrf(&mut self, vrf: impl Vrf + 'static) {
+        self.vrf = Some(UnitVrf::new(vrf));
+    }
+
+    /// Makes the session send a [`UnitRejection`] to the returned channel for every unit received
+    /// from the network that fails validation, e.g. for scoring peers. Units breaking the growth
+    /// rule come with signed evidence against their creator. The same rejections are counted in
+    /// the [`StatsSnapshot`] regardless.
+    ///
+    /// [`StatsSnapshot`]: crate::StatsSnapshot
+    pub fn enable_rejection_reports(&mut self) -> mpsc::UnboundedReceiver<UnitRejection> {
+        let (rejections, rejections_rx) = mpsc::unbounded();
+        self.rejections = Some(rejections);
+        rejections_rx
+    }
+
+    /// Makes the session send a [`FaultAlarm`] to the returned channel when the number of nodes
+    /// that stopped contributing units reaches the number of faults the session tolerates, so
+    /// that operators can step in before the session stalls. The alarm is raised again only
+    /// after enough of the nodes came back. The silent nodes are in the [`StatsSnapshot`]
+    /// regardless.
+    ///
+    /// [`StatsSnapshot`]: crate::StatsSnapshot
+    pub fn enable_fault_alarms(&mut self) -> mpsc::UnboundedReceiver<FaultAlarm> {
+        let (fault_alarms, fault_alarms_rx) = mpsc::unbounded();
+        self.fault_alarms = Some(fault_alarms);
+        fault_alarms_rx
+    }
+
+    /// Makes the session write its [`SessionSummary`] to the file at the path when it ends,
+    /// replacing the contents of the file.
+    pub fn enable_session_summary_file(&mut self, path: impl Into<PathBuf>) {
+        self.session_summary.file = Some(path.into());
+    }
 }
 
 struct MemberStatus<'a, H: Hasher, D: Data, S: Signature> {
@@ -219,55 +604,109 @@ where
     }
 }
 
-struct Member<H, D, S>
+struct Member<H, D, K>
 where
     H: Hasher,
     D: Data,
-    S: Signature,
+    K: Keychain,
 {
     config: Config,
-    task_queue: TaskQueue<RepeatableTask<H, D, S>>,
+    keychain: K,
+    task_queue: TaskQueue<RepeatableTask<H, D, K::Signature>>,
     not_resolved_parents: HashSet<H::Hash>,
     not_resolved_coords: HashSet<UnitCoord>,
+    request_window: RequestWindow<RequestKey<H::Hash>>,
+    waiting_requests: VecDeque<RepeatableTask<H, D, K::Signature>>,
     newest_unit_resolved: bool,
-    peers: Vec<Recipient>,
-    unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
-    unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
-    notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
-    notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
+    peers: Vec<(Recipient, u32)>,
+    preferred_peers: Vec<Recipient>,
+    unit_messages_for_network: Sender<(UnitMessage<H, D, K::Signature>, Recipient)>,
+    unit_messages_from_network: Receiver<UnitMessage<H, D, K::Signature>>,
+    notifications_for_runway: Sender<RunwayNotificationIn<H, D, K::Signature>>,
+    notifications_from_runway: Receiver<RunwayNotificationOut<H, D, K::Signature>>,
     resolved_requests: Receiver<Request<H>>,
     exiting: bool,
     top_units: NodeMap<Round>,
+    last_hints: NodeMap<Instant>,
+    // The lowest rounds the peers still serve units of, for those that told us.
+    history_floors: NodeMap<Round>,
+    own_broadcast: Option<BroadcastStatus<H>>,
+    broadcast_backup: Option<BroadcastBackup>,
+    withholding_detector: Option<WithholdingDetector>,
+    health: Health,
 }
 
-impl<H, D, S> Member<H, D, S>
+impl<H, D, K> Member<H, D, K>
 where
     H: Hasher,
     D: Data,
-    S: Signature,
+    K: Keychain,
 {
     fn new(
         config: Config,
-        unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
-        unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
-        notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
-        notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
+        keychain: K,
+        unit_messages_for_network: Sender<(UnitMessage<H, D, K::Signature>, Recipient)>,
+        unit_messages_from_network: Receiver<UnitMessage<H, D, K::Signature>>,
+        notifications_for_runway: Sender<RunwayNotificationIn<H, D, K::Signature>>,
+        notifications_from_runway: Receiver<RunwayNotificationOut<H, D, K::Signature>>,
         resolved_requests: Receiver<Request<H>>,
+        broadcast_backup: Option<BroadcastBackup>,
+        withholding_suspicions: Option<(Round, Sender<WithholdingSuspicion>)>,
+        health: Health,
     ) -> Self {
         let n_members = config.n_members;
+        let own_broadcast = match broadcast_backup.as_ref().map(|backup| backup.load()) {
+            Some(Ok(Some(status)))
+                if !status.belongs_to(config.session_id, n_members, config.node_ix) =>
+            {
+                info!(target: "AlephBFT-member", "{:?} Ignoring the broadcast backup of a different session or index.", config.node_ix);
+                None
+            }
+            Some(Ok(status)) => status,
+            Some(Err(e)) => {
+                warn!(target: "AlephBFT-member", "{:?} Unable to load the broadcast backup: {}", config.node_ix, e);
+                None
+            }
+            None => None,
+        };
         let peers = (0..n_members.0)
             .map(NodeIndex)
             .filter(|x| *x != config.node_ix)
+            .map(|x| {
+                (
+                    Recipient::Node(x),
+                    config.topology.weight(config.node_ix, x),
+                )
+            })
+            .collect();
+        let preferred_peers = config
+            .catch_up_sources
+            .preferred
+            .iter()
+            .filter(|x| **x != config.node_ix && x.0 < n_members.0)
+            .unique()
+            .cloned()
             .map(Recipient::Node)
             .collect();
+        let withholding_detector = withholding_suspicions.map(|(lag_threshold, suspicions)| {
+            WithholdingDetector::new(n_members, lag_threshold, suspicions)
+        });
+        let request_window = RequestWindow::new(
+            config.request_concurrency.initial,
+            config.request_concurrency.max,
+        );
 
         Self {
             config,
+            keychain,
             task_queue: TaskQueue::new(),
             not_resolved_parents: HashSet::new(),
             not_resolved_coords: HashSet::new(),
+            request_window,
+            waiting_requests: VecDeque::new(),
             newest_unit_resolved: false,
             peers,
+            preferred_peers,
             unit_messages_for_network,
             unit_messages_from_network,
             notifications_for_runway,
@@ -275,16 +714,279 @@ where
             resolved_requests,
             exiting: false,
             top_units: NodeMap::with_size(n_members),
+            last_hints: NodeMap::with_size(n_members),
+            history_floors: NodeMap::with_size(n_members),
+            own_broadcast,
+            broadcast_backup,
+            withholding_detector,
+            health,
+        }
+    }
+
+    fn on_create(&mut self, u: UncheckedSignedUnit<H, D, K::Signature>) {
+        if self.is_stale(u.as_signable().round()) {
+            trace!(target: "AlephBFT-member", "{:?} Not broadcasting a stale unit of round {:?}.", self.index(), u.as_signable().round());
+            return;
+        }
+        let recipients = match &self.own_broadcast {
+            Some(status) if status.hash() == u.as_signable().hash() => {
+                self.broadcast_recipients(status)
+            }
+            Some(status) if status.round() > u.as_signable().round() => {
+                // An older unit loaded from the backup, the peers already have it or will
+                // request it when they see its successors.
+                trace!(target: "AlephBFT-member", "{:?} Not broadcasting an old unit of round {:?}.", self.index(), u.as_signable().round());
+                return;
+            }
+            _ => vec![Recipient::Everyone],
+        };
+        for recipient in recipients {
+            self.send_unit_message(UnitMessage::NewUnit(u.clone()), recipient);
+        }
+    }
+
+    /// The highest round reached by at least floor(2N/3)+1 nodes, as far as we know.
+    fn quorum_round(&self) -> Round {
+        let mut rounds: Vec<_> = (0..self.config.n_members.0)
+            .map(|node| self.top_units.get(NodeIndex(node)).copied().unwrap_or(0))
+            .collect();
+        rounds.sort_unstable_by(|a, b| b.cmp(a));
+        let quorum = self.config.n_members.quorum();
+        rounds.get(quorum.0 - 1).copied().unwrap_or(0)
+    }
+
+    /// Whether our unit of the given round lags too much to be worth broadcasting. Such units
+    /// are still created rather than suppressed: by the growth rule every unit of ours needs our
+    /// unit of the previous round as a parent, so skipping a round would leave us unable to
+    /// create units once we caught up.
+    fn is_stale(&self, round: Round) -> bool {
+        match self.config.lag_suppression {
+            Some(lag) => self.quorum_round() > round.saturating_add(lag),
+            None => false,
         }
     }
 
-    fn on_create(&mut self, u: UncheckedSignedUnit<H, D, S>) {
-        self.send_unit_message(UnitMessage::NewUnit(u), Recipient::Everyone);
+    /// Everyone, unless some peers acknowledged the unit already, in which case only the others.
+    fn broadcast_recipients(&self, status: &BroadcastStatus<H>) -> Vec<Recipient> {
+        match status.is_fresh() {
+            true => vec![Recipient::Everyone],
+            false => status.pending().map(Recipient::Node).collect(),
+        }
+    }
+
+    fn on_own_unit_discovered(&mut self, unit: &UncheckedSignedUnit<H, D, K::Signature>) {
+        let round = unit.as_signable().round();
+        if let Some(status) = &self.own_broadcast {
+            // Either the status was loaded from the backup, or this is an older unit loaded
+            // from the unit backup.
+            if status.round() >= round {
+                return;
+            }
+        }
+        let status = BroadcastStatus::new(
+            unit.as_signable().hash(),
+            round,
+            self.config.session_id,
+            self.config.n_members,
+            self.index(),
+        );
+        self.save_broadcast_status(&status);
+        self.own_broadcast = Some(status);
+    }
+
+    fn save_broadcast_status(&mut self, status: &BroadcastStatus<H>) {
+        if let Some(backup) = &self.broadcast_backup {
+            if let Err(e) = backup.save(status) {
+                error!(target: "AlephBFT-member", "{:?} Unable to save the broadcast status: {}", self.index(), e);
+                self.exiting = true;
+            }
+        }
+    }
+
+    fn on_signed_unit_ack(&mut self, ack: UncheckedSigned<UnitAcknowledgement<H>, K::Signature>) {
+        match ack.check(&self.keychain) {
+            Ok(ack) => {
+                let ack = ack.into_signable();
+                self.on_unit_ack(ack.node, ack.hash);
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-member", "{:?} Received a wrongly signed acknowledgement: {:?}.", self.index(), e);
+            }
+        }
+    }
+
+    fn on_unit_ack(&mut self, node: NodeIndex, hash: H::Hash) {
+        let mut status = match self.own_broadcast.take() {
+            Some(status) => status,
+            None => return,
+        };
+        if status.hash() == hash && status.ack(node) {
+            trace!(target: "AlephBFT-member", "{:?} Node {:?} acknowledged unit {:?}.", self.index(), node, hash);
+            self.save_broadcast_status(&status);
+        }
+        self.own_broadcast = Some(status);
+    }
+
+    fn on_signed_duplicate_hint(
+        &mut self,
+        hint: UncheckedSigned<DuplicateUnitHint<H>, K::Signature>,
+    ) {
+        match hint.check(&self.keychain) {
+            Ok(hint) => {
+                let hint = hint.into_signable();
+                self.on_duplicate_hint(hint.node, hint.hash, hint.top_rounds);
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-member", "{:?} Received a wrongly signed duplicate hint: {:?}.", self.index(), e);
+            }
+        }
+    }
+
+    /// A hint that a node already has the unit, and how far it got with every creator. Besides
+    /// acknowledging the unit itself, this acknowledges our newest unit if the node has a unit of
+    /// ours at least as high, which lets us catch up after losing its acknowledgements.
+    fn on_duplicate_hint(&mut self, node: NodeIndex, hash: H::Hash, top_rounds: NodeMap<Round>) {
+        if top_rounds.size() != self.config.n_members {
+            return;
+        }
+        self.on_unit_ack(node, hash);
+        let newest = self.own_broadcast.as_ref().and_then(|status| {
+            top_rounds
+                .get(self.index())
+                .filter(|round| **round >= status.round())
+                .map(|_| status.hash())
+        });
+        if let Some(newest) = newest {
+            self.on_unit_ack(node, newest);
+        }
+        self.on_top_rounds(node, top_rounds);
+    }
+
+    /// Lets the creator know we received the unit, unless it is older than what we already have
+    /// from them. The unit is not verified yet, but an acknowledgement of a unit the creator did
+    /// not create matches nothing on their side.
+    async fn acknowledge(&mut self, unit: &UncheckedSignedUnit<H, D, K::Signature>) {
+        let creator = unit.as_signable().creator();
+        let round = unit.as_signable().round();
+        if creator == self.index() || creator.0 >= self.config.n_members.0 {
+            return;
+        }
+        let top_round = self.top_units.get(creator).copied();
+        if top_round
+            .map(|top_round| top_round >= round)
+            .unwrap_or(false)
+            && self
+                .hint_duplicate(creator, unit.as_signable().hash())
+                .await
+        {
+            return;
+        }
+        if top_round
+            .map(|top_round| top_round > round)
+            .unwrap_or(false)
+        {
+            return;
+        }
+        let ack = UnitAcknowledgement::new(self.index(), unit.as_signable().hash());
+        let ack = Signed::sign(ack, &self.keychain).await.into_unchecked();
+        let message = UnitMessage::UnitAck(ack);
+        self.send_unit_message(message, Recipient::Node(creator));
+    }
+
+    /// Tells the creator of a unit we already have how far we got with every creator, if the
+    /// policy allows a hint to them right now. Returns whether the hint was sent.
+    async fn hint_duplicate(&mut self, creator: NodeIndex, hash: H::Hash) -> bool {
+        let interval = match self.config.duplicate_units {
+            DuplicateUnitPolicy::Ignore => return false,
+            DuplicateUnitPolicy::Hint { interval } => interval,
+        };
+        let now = Instant::now();
+        if self
+            .last_hints
+            .get(creator)
+            .map(|last| now.duration_since(*last) < interval)
+            .unwrap_or(false)
+        {
+            return false;
+        }
+        self.last_hints.insert(creator, now);
+        trace!(target: "AlephBFT-member", "{:?} Hinting node {:?} that we already have unit {:?}.", self.index(), creator, hash);
+        let hint = DuplicateUnitHint::new(self.index(), hash, self.top_units.clone());
+        let hint = Signed::sign(hint, &self.keychain).await.into_unchecked();
+        let message = UnitMessage::DuplicateHint(hint);
+        self.send_unit_message(message, Recipient::Node(creator));
+        true
     }
 
-    fn on_unit_discovered(&mut self, new_unit: UncheckedSignedUnit<H, D, S>) {
+    /// Gossips the highest rounds we have of every creator, if we detect withholding.
+    async fn report_top_rounds(&mut self) {
+        if self.withholding_detector.is_none() {
+            return;
+        }
+        let report = TopRoundsReport::new(self.index(), self.top_units.clone());
+        let report = Signed::sign(report, &self.keychain).await.into_unchecked();
+        let message = UnitMessage::TopRounds(report);
+        self.send_unit_message(message, Recipient::Everyone);
+        self.on_top_rounds(self.index(), self.top_units.clone());
+    }
+
+    fn on_signed_top_rounds(&mut self, report: UncheckedSigned<TopRoundsReport<H>, K::Signature>) {
+        match report.check(&self.keychain) {
+            Ok(report) => {
+                let report = report.into_signable();
+                self.on_top_rounds(report.node, report.top_rounds);
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-member", "{:?} Received wrongly signed top rounds: {:?}.", self.index(), e);
+            }
+        }
+    }
+
+    fn on_top_rounds(&mut self, node: NodeIndex, top_rounds: NodeMap<Round>) {
+        if let Some(detector) = &mut self.withholding_detector {
+            detector.on_report(node, top_rounds);
+        }
+    }
+
+    fn on_signed_history_floor(&mut self, floor: UncheckedSigned<HistoryFloor<H>, K::Signature>) {
+        match floor.check(&self.keychain) {
+            Ok(floor) => {
+                let floor = floor.into_signable();
+                self.on_history_pruned(floor.node, floor.floor);
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-member", "{:?} Received a wrongly signed history floor: {:?}.", self.index(), e);
+            }
+        }
+    }
+
+    /// A node does not serve units below the round anymore, so we ask others for them.
+    fn on_history_pruned(&mut self, node: NodeIndex, floor: Round) {
+        if node == self.index() || node.0 >= self.config.n_members.0 {
+            return;
+        }
+        debug!(target: "AlephBFT-member", "{:?} Node {:?} only serves units from round {:?} on.", self.index(), node, floor);
+        self.history_floors.insert(node, floor);
+    }
+
+    /// Whether the peer may still serve units of the round, as far as we know.
+    fn serves(&self, peer: &Recipient, round: Option<Round>) -> bool {
+        match (peer, round) {
+            (Recipient::Node(node), Some(round)) => self
+                .history_floors
+                .get(*node)
+                .map(|floor| *floor <= round)
+                .unwrap_or(true),
+            _ => true,
+        }
+    }
+
+    fn on_unit_discovered(&mut self, new_unit: UncheckedSignedUnit<H, D, K::Signature>) {
         let unit_creator = new_unit.as_signable().creator();
         let unit_round = new_unit.as_signable().round();
+        if unit_creator == self.index() {
+            self.on_own_unit_discovered(&new_unit);
+        }
         if self
             .top_units
             .get(unit_creator)
@@ -292,6 +994,9 @@ where
             .unwrap_or(true)
         {
             self.top_units.insert(unit_creator, unit_round);
+            if unit_creator == self.index() && self.is_stale(unit_round) {
+                return;
+            }
             let task = RepeatableTask::new(UnitBroadcast(new_unit));
             let delay = self.delay(&task.task, task.counter);
             self.task_queue.schedule_in(task, delay)
@@ -326,7 +1031,13 @@ where
     }
 
     fn trigger_tasks(&mut self) {
+        self.release_waiting_requests();
         while let Some(mut task) = self.task_queue.pop_due_task() {
+            if !self.admit(&task) {
+                trace!(target: "AlephBFT-member", "{:?} Postponing {}, too many requests outstanding.", self.index(), task);
+                self.waiting_requests.push_back(task);
+                continue;
+            }
             match self.task_details(&task.task, task.counter) {
                 TaskDetails::Cancel => (),
                 TaskDetails::Perform {
@@ -345,8 +1056,75 @@ where
         }
     }
 
-    fn random_peers(&self, n: usize) -> Vec<Recipient> {
-        self.peers
+    /// Whether the task can be performed now, which is not the case for new requests that do not
+    /// fit into the window of outstanding requests.
+    fn admit(&mut self, task: &RepeatableTask<H, D, K::Signature>) -> bool {
+        let key = match RequestKey::of(&task.task) {
+            Some(key) => key,
+            None => return true,
+        };
+        if !self.still_valid(&task.task) {
+            // Will get cancelled.
+            return true;
+        }
+        let now = Instant::now();
+        match task.counter {
+            0 => self.request_window.try_start(key, now),
+            _ => {
+                self.request_window.on_timeout(&key, now);
+                true
+            }
+        }
+    }
+
+    fn on_request_resolved(&mut self, key: RequestKey<H::Hash>) {
+        self.request_window.on_resolved(&key, Instant::now());
+        self.trigger_tasks();
+    }
+
+    /// Moves as many postponed requests as fit into the window back to the task queue, dropping
+    /// the ones that are no longer needed.
+    fn release_waiting_requests(&mut self) {
+        let mut room = self.request_window.room();
+        while room > 0 {
+            let task = match self.waiting_requests.pop_front() {
+                Some(task) => task,
+                None => break,
+            };
+            if self.still_valid(&task.task) {
+                self.task_queue.schedule_now(task);
+                room -= 1;
+            }
+        }
+    }
+
+    /// Random peers, preferring the ones behind cheap links according to the topology hints.
+    /// Peers that told us they do not serve units of the round are skipped, unless none of them
+    /// serves them.
+    fn random_peers(&self, n: usize, round: Option<Round>) -> Vec<Recipient> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(peer, _)| self.serves(peer, round))
+            .collect();
+        if peers.is_empty() {
+            peers = self.peers.iter().collect();
+        }
+        peers
+            .choose_multiple_weighted(&mut rand::thread_rng(), n, |(_, weight)| *weight as f64)
+            .expect("the weights are positive")
+            .map(|(peer, _)| peer.clone())
+            .collect()
+    }
+
+    /// Recipients of the kth try of a request for missing units: only the preferred catch-up
+    /// sources at first, any peers once the tries reach the fallback threshold.
+    fn request_recipients(&self, n: usize, counter: usize, round: Option<Round>) -> Vec<Recipient> {
+        if self.preferred_peers.is_empty() || counter >= self.config.catch_up_sources.fallback_after
+        {
+            return self.random_peers(n, round);
+        }
+        self.preferred_peers
             .choose_multiple(&mut rand::thread_rng(), n)
             .cloned()
             .collect()
@@ -356,7 +1134,11 @@ where
         self.config.node_ix
     }
 
-    fn send_unit_message(&mut self, message: UnitMessage<H, D, S>, recipient: Recipient) {
+    fn send_unit_message(
+        &mut self,
+        message: UnitMessage<H, D, K::Signature>,
+        recipient: Recipient,
+    ) {
         if self
             .unit_messages_for_network
             .unbounded_send((message, recipient))
@@ -371,7 +1153,11 @@ where
     /// `Delay(Duration)` if the task is active, but cannot be performed right now, and
     /// `Perform { message, recipient, reschedule }` if the task is to send `message` to `recipient` and it should
     /// be rescheduled after `reschedule`.
-    fn task_details(&mut self, task: &Task<H, D, S>, counter: usize) -> TaskDetails<H, D, S> {
+    fn task_details(
+        &mut self,
+        task: &Task<H, D, K::Signature>,
+        counter: usize,
+    ) -> TaskDetails<H, D, K::Signature> {
         match self.still_valid(task) {
             false => TaskDetails::Cancel,
             true => TaskDetails::Perform {
@@ -382,7 +1168,7 @@ where
         }
     }
 
-    fn message(&self, task: &Task<H, D, S>) -> UnitMessage<H, D, S> {
+    fn message(&self, task: &Task<H, D, K::Signature>) -> UnitMessage<H, D, K::Signature> {
         match task {
             CoordRequest(coord) => UnitMessage::RequestCoord(self.index(), *coord),
             ParentsRequest(hash) => UnitMessage::RequestParents(self.index(), *hash),
@@ -391,29 +1177,43 @@ where
         }
     }
 
-    fn recipients(&self, task: &Task<H, D, S>, counter: usize) -> Vec<Recipient> {
+    fn recipients(&self, task: &Task<H, D, K::Signature>, counter: usize) -> Vec<Recipient> {
         match task {
-            CoordRequest(_) => {
-                self.random_peers((self.config.delay_config.coord_request_recipients)(counter))
-            }
-            ParentsRequest(_) => {
-                self.random_peers((self.config.delay_config.parent_request_recipients)(
-                    counter,
-                ))
-            }
-            UnitBroadcast(_) => vec![Recipient::Everyone],
+            CoordRequest(coord) => self.request_recipients(
+                (self.config.delay_config.coord_request_recipients)(counter),
+                counter,
+                Some(coord.round()),
+            ),
+            ParentsRequest(_) => self.request_recipients(
+                (self.config.delay_config.parent_request_recipients)(counter),
+                counter,
+                None,
+            ),
+            UnitBroadcast(unit) => match &self.own_broadcast {
+                Some(status) if status.hash() == unit.as_signable().hash() => {
+                    self.broadcast_recipients(status)
+                }
+                _ => vec![Recipient::Everyone],
+            },
             RequestNewest(_) => vec![Recipient::Everyone],
         }
     }
 
-    fn still_valid(&self, task: &Task<H, D, S>) -> bool {
+    fn still_valid(&self, task: &Task<H, D, K::Signature>) -> bool {
         match task {
             CoordRequest(coord) => self.not_resolved_coords.contains(coord),
             ParentsRequest(hash) => self.not_resolved_parents.contains(hash),
             RequestNewest(_) => !self.newest_unit_resolved,
             UnitBroadcast(unit) => {
+                let acknowledged_by_all = match &self.own_broadcast {
+                    Some(status) => {
+                        status.hash() == unit.as_signable().hash() && status.is_complete()
+                    }
+                    None => false,
+                };
                 Some(&unit.as_signable().round())
                     == self.top_units.get(unit.as_signable().creator())
+                    && !acknowledged_by_all
             }
         }
     }
@@ -425,7 +1225,7 @@ where
     ///
     /// The other exception is [Task::CoordRequest] - this one uses the configurable
     /// `coord_request_delay` schedule.
-    fn delay(&self, task: &Task<H, D, S>, counter: usize) -> Duration {
+    fn delay(&self, task: &Task<H, D, K::Signature>, counter: usize) -> Duration {
         match task {
             UnitBroadcast(_) => {
                 let low = self.config.delay_config.unit_rebroadcast_interval_min;
@@ -439,7 +1239,10 @@ where
         }
     }
 
-    fn on_unit_message_from_units(&mut self, message: RunwayNotificationOut<H, D, S>) {
+    async fn on_unit_message_from_units(
+        &mut self,
+        message: RunwayNotificationOut<H, D, K::Signature>,
+    ) {
         match message {
             RunwayNotificationOut::NewSelfUnit(u) => self.on_create(u),
             RunwayNotificationOut::NewAnyUnit(u) => self.on_unit_discovered(u),
@@ -449,12 +1252,12 @@ where
                 Request::NewestUnit(salt) => self.on_request_newest(salt),
             },
             RunwayNotificationOut::Response(response, recipient) => match response {
-                Response::Coord(u) => {
-                    let message = UnitMessage::ResponseCoord(u);
+                Response::Coord(u, frontier) => {
+                    let message = UnitMessage::ResponseCoord(u, frontier);
                     self.send_unit_message(message, Recipient::Node(recipient))
                 }
-                Response::Parents(u_hash, parents) => {
-                    let message = UnitMessage::ResponseParents(u_hash, parents);
+                Response::Parents(u_hash, parents, frontier) => {
+                    let message = UnitMessage::ResponseParents(u_hash, parents, frontier);
                     self.send_unit_message(message, Recipient::Node(recipient))
                 }
                 Response::NewestUnit(response) => {
@@ -463,6 +1266,24 @@ where
                     self.send_unit_message(message, Recipient::Node(requester))
                 }
             },
+            RunwayNotificationOut::HistoryPruned(floor, recipient) => {
+                let floor = HistoryFloor::new(self.index(), floor);
+                let floor = Signed::sign(floor, &self.keychain).await.into_unchecked();
+                let message = UnitMessage::HistoryPruned(floor);
+                self.send_unit_message(message, Recipient::Node(recipient))
+            }
+        }
+    }
+
+    /// Called when the ticker fired significantly later than it should have, which means the
+    /// process was most likely suspended. Peers probably moved on in the meantime, so instead of
+    /// waiting for the scheduled retries we rebroadcast our top units and repeat all pending
+    /// requests right away, starting their schedules from the beginning.
+    fn on_clock_jump(&mut self, gap: Duration) {
+        info!(target: "AlephBFT-member", "{:?} Detected a pause of {:?}, resynchronizing with peers.", self.index(), gap);
+        let tasks: Vec<_> = self.task_queue.drain().collect();
+        for task in tasks {
+            self.task_queue.schedule_now(RepeatableTask::new(task.task));
         }
     }
 
@@ -478,14 +1299,17 @@ where
     async fn run(mut self, mut terminator: Terminator) {
         let ticker_delay = self.config.delay_config.tick_interval;
         let mut ticker = Delay::new(ticker_delay).fuse();
+        let mut last_tick = Instant::now();
+        let clock_jump_threshold = ticker_delay + self.config.delay_config.clock_jump_threshold;
         let status_ticker_delay = Duration::from_secs(10);
         let mut status_ticker = Delay::new(status_ticker_delay).fuse();
+        let mut report_ticker = Delay::new(REPORT_INTERVAL).fuse();
 
         loop {
             futures::select! {
                 event = self.notifications_from_runway.next() => match event {
                     Some(message) => {
-                        self.on_unit_message_from_units(message);
+                        self.on_unit_message_from_units(message).await;
                     },
                     None => {
                         error!(target: "AlephBFT-member", "{:?} Unit message stream from Runway closed.", self.index());
@@ -497,9 +1321,11 @@ where
                     Some(request) => match request {
                         Request::Coord(coord) => {
                             self.not_resolved_coords.remove(&coord);
+                            self.on_request_resolved(RequestKey::Coord(coord));
                         },
                         Request::Parents(u_hash) => {
                             self.not_resolved_parents.remove(&u_hash);
+                            self.on_request_resolved(RequestKey::Parents(u_hash));
                         },
                         Request::NewestUnit(_) => {
                             self.newest_unit_resolved = true;
@@ -512,11 +1338,23 @@ where
                 },
 
                 event = self.unit_messages_from_network.next() => match event {
-                    Some(message) => match message.try_into() {
-                        Ok(notification) => {
-                            self.send_notification_to_runway(notification)
-                        },
-                        Err(_) => error!(target: "AlephBFT-member", "{:?} Unable to convert a UnitMessage into an instance of RunwayNotificationIn.", self.index()),
+                    Some(UnitMessage::UnitAck(ack)) => self.on_signed_unit_ack(ack),
+                    Some(UnitMessage::TopRounds(report)) => self.on_signed_top_rounds(report),
+                    Some(UnitMessage::DuplicateHint(hint)) => self.on_signed_duplicate_hint(hint),
+                    Some(UnitMessage::HistoryPruned(floor)) => self.on_signed_history_floor(floor),
+                    Some(message) => {
+                        if let UnitMessage::NewUnit(unit) = &message {
+                            self.acknowledge(unit).await;
+                        }
+                        match message.try_into() {
+                            Ok(notification) => {
+                                self.send_notification_to_runway(notification)
+                            },
+                            Err(_) => {
+                                self.health.on_error(Subsystem::Syncer);
+                                error!(target: "AlephBFT-member", "{:?} Unable to convert a UnitMessage into an instance of RunwayNotificationIn.", self.index());
+                            },
+                        }
                     },
                     None => {
                         error!(target: "AlephBFT-member", "{:?} Unit message stream from network closed.", self.index());
@@ -525,7 +1363,15 @@ where
                 },
 
                 _ = &mut ticker => {
+                    let now = Instant::now();
+                    let gap = now.saturating_duration_since(last_tick);
+                    if gap > clock_jump_threshold {
+                        self.on_clock_jump(gap);
+                    }
+                    last_tick = now;
                     self.trigger_tasks();
+                    self.health.on_activity(Subsystem::Syncer);
+                    self.health.set_queue_depth(Subsystem::Syncer, self.task_queue.iter().count() + self.waiting_requests.len());
                     ticker = Delay::new(ticker_delay).fuse();
                 },
 
@@ -534,6 +1380,11 @@ where
                     status_ticker = Delay::new(status_ticker_delay).fuse();
                 },
 
+                _ = &mut report_ticker => {
+                    self.report_top_rounds().await;
+                    report_ticker = Delay::new(REPORT_INTERVAL).fuse();
+                },
+
                 _ = &mut terminator.get_exit() => {
                     debug!(target: "AlephBFT-member", "{:?} received exit signal", self.index());
                     self.exiting = true;
@@ -549,7 +1400,10 @@ where
         debug!(target: "AlephBFT-member", "{:?} Member stopped.", self.index());
     }
 
-    fn send_notification_to_runway(&mut self, notification: RunwayNotificationIn<H, D, S>) {
+    fn send_notification_to_runway(
+        &mut self,
+        notification: RunwayNotificationIn<H, D, K::Signature>,
+    ) {
         if self
             .notifications_for_runway
             .unbounded_send(notification)
@@ -566,6 +1420,8 @@ where
 /// For a detailed description of the consensus implemented by `run_session` see
 /// [docs for devs](https://cardinal-cryptography.github.io/AlephBFT/index.html)
 /// or the [original paper](https://arxiv.org/abs/1908.05156).
+///
+/// Returns an error if the session could not start or one of its subsystems stopped early.
 pub async fn run_session<
     H: Hasher,
     D: Data,
@@ -583,9 +1439,14 @@ pub async fn run_session<
     keychain: MK,
     spawn_handle: SH,
     mut terminator: Terminator,
-) {
+) -> Result<(), Error> {
     let index = config.node_ix;
     info!(target: "AlephBFT-member", "{:?} Starting a new session.", index);
+    let keychain = config.domain_keychain(keychain);
+    if !keychain.verify_own_index().await {
+        error!(target: "AlephBFT-member", "{:?} Own signatures of the keychain do not verify as ours, its index does not match the position of its key in the committee.", index);
+        return Err(Error::KeychainMismatch(index));
+    }
     debug!(target: "AlephBFT-member", "{:?} Spawning party for a session.", index);
 
     let (alert_messages_for_alerter, alert_messages_from_network) = mpsc::unbounded();
@@ -596,27 +1457,35 @@ pub async fn run_session<
     let (runway_messages_for_network, runway_messages_from_runway) = mpsc::unbounded();
     let (resolved_requests_tx, resolved_requests_rx) = mpsc::unbounded();
 
+    terminator.set_termination_timeout(config.delay_config.shutdown_timeout);
+
     debug!(target: "AlephBFT-member", "{:?} Spawning network.", index);
-    let network_terminator = terminator.add_offspring_connection("AlephBFT-network");
+    let bandwidth_budget = config.resource_budget.bandwidth;
+    let network_health = local_io.health.clone();
+    let network_stats = local_io.stats.clone();
 
-    let network_handle = spawn_handle
-        .spawn_essential("member/network", async move {
+    let network_task =
+        terminator.add_offspring_task("AlephBFT-network", |network_terminator| async move {
             network::run(
                 network,
                 unit_messages_from_units,
                 unit_messages_for_units,
                 alert_messages_from_alerter,
                 alert_messages_for_alerter,
+                bandwidth_budget,
+                network_health,
+                network_stats,
                 network_terminator,
             )
             .await
-        })
+        });
+    let network_handle = spawn_handle
+        .spawn_essential("member/network", network_task)
         .fuse();
     pin_mut!(network_handle);
     debug!(target: "AlephBFT-member", "{:?} Network spawned.", index);
 
     debug!(target: "AlephBFT-member", "{:?} Initializing Runway.", index);
-    let runway_terminator = terminator.add_offspring_connection("AlephBFT-runway");
     let network_io = NetworkIO {
         alert_messages_for_network,
         alert_messages_from_network,
@@ -629,11 +1498,25 @@ pub async fn run_session<
         local_io.finalization_handler,
         local_io.unit_saver,
         local_io.unit_loader,
+        local_io.stats,
+        local_io.health.clone(),
+        local_io.ordering_log,
+        local_io.payload_checks,
+        local_io.dag_export_requests,
+        local_io.compaction_requests,
+        local_io.acknowledgements,
+        local_io.batch_dependencies,
+        local_io.session_summary,
+        local_io.key_schedule,
+        local_io.vrf,
+        local_io.rejections,
+        local_io.fault_alarms,
     );
     let spawn_copy = spawn_handle.clone();
     let config_copy = config.clone();
-    let runway_handle = spawn_handle
-        .spawn_essential("member/runway", async move {
+    let member_keychain = keychain.clone();
+    let runway_task =
+        terminator.add_offspring_task("AlephBFT-runway", |runway_terminator| async move {
             runway::run(
                 config_copy,
                 runway_io,
@@ -643,7 +1526,9 @@ pub async fn run_session<
                 runway_terminator,
             )
             .await
-        })
+        });
+    let runway_handle = spawn_handle
+        .spawn_essential("member/runway", runway_task)
         .fuse();
     pin_mut!(runway_handle);
     debug!(target: "AlephBFT-member", "{:?} Runway spawned.", index);
@@ -651,38 +1536,45 @@ pub async fn run_session<
     debug!(target: "AlephBFT-member", "{:?} Initializing Member.", index);
     let member = Member::new(
         config,
+        member_keychain,
         unit_messages_for_network,
         unit_messages_from_network,
         runway_messages_for_runway,
         runway_messages_from_runway,
         resolved_requests_rx,
+        local_io.broadcast_backup,
+        local_io.withholding_suspicions,
+        local_io.health,
     );
-    let member_terminator = terminator.add_offspring_connection("AlephBFT-member");
-    let member_handle = spawn_handle
-        .spawn_essential("member", async move {
+    let member_task =
+        terminator.add_offspring_task("AlephBFT-member", |member_terminator| async move {
             member.run(member_terminator).await;
-        })
-        .fuse();
+        });
+    let member_handle = spawn_handle.spawn_essential("member", member_task).fuse();
     pin_mut!(member_handle);
     debug!(target: "AlephBFT-member", "{:?} Member initialized.", index);
 
-    futures::select! {
+    let result = futures::select! {
         _ = network_handle => {
             error!(target: "AlephBFT-member", "{:?} Network-hub terminated early.", index);
+            Err(Error::SubsystemStopped("network"))
         },
 
         _ = runway_handle => {
             error!(target: "AlephBFT-member", "{:?} Runway terminated early.", index);
+            Err(Error::SubsystemStopped("runway"))
         },
 
         _ = member_handle => {
             error!(target: "AlephBFT-member", "{:?} Member terminated early.", index);
+            Err(Error::SubsystemStopped("member"))
         },
 
         _ = &mut terminator.get_exit() => {
             debug!(target: "AlephBFT-member", "{:?} exit channel was called.", index);
+            Ok(())
         },
-    }
+    };
 
     debug!(target: "AlephBFT-member", "{:?} Run ending.", index);
 
@@ -693,33 +1585,53 @@ pub async fn run_session<
     handle_task_termination(member_handle, "AlephBFT-member", "Member", index).await;
 
     info!(target: "AlephBFT-member", "{:?} Session ended.", index);
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testing::gen_config;
-    use aleph_bft_mock::{Hasher64, Signature};
+    use crate::{
+        testing::gen_config,
+        units::{create_units, creator_set, preunit_to_unchecked_signed_unit},
+        CatchUpSources, RequestConcurrency, SessionId, TopologyHints,
+    };
+    use aleph_bft_mock::{Hasher64, Keychain, Loader, Saver, Signature};
     use aleph_bft_types::NodeCount;
     use futures::channel::mpsc::unbounded;
     use itertools::Itertools;
     use std::sync::Arc;
 
-    fn mock_member(node_ix: NodeIndex, node_count: NodeCount) -> Member<Hasher64, u32, Signature> {
-        let config = gen_config(node_ix, node_count);
+    fn mock_member(node_ix: NodeIndex, node_count: NodeCount) -> Member<Hasher64, u32, Keychain> {
+        mock_member_with_config(gen_config(node_ix, node_count))
+    }
+
+    fn mock_member_with_config(config: Config) -> Member<Hasher64, u32, Keychain> {
+        mock_member_with_backup(config, None)
+    }
+
+    fn mock_member_with_backup(
+        config: Config,
+        broadcast_backup: Option<BroadcastBackup>,
+    ) -> Member<Hasher64, u32, Keychain> {
         let (unit_messages_for_network_sx, _) = unbounded();
         let (_, unit_messages_from_network_rx) = unbounded();
         let (notifications_for_runway_sx, _) = unbounded();
         let (_, notifications_from_runway_rx) = unbounded();
         let (_, resolved_requests_rx) = unbounded();
 
+        let keychain = Keychain::new(config.n_members, config.node_ix);
         Member::new(
             config,
+            keychain,
             unit_messages_for_network_sx,
             unit_messages_from_network_rx,
             notifications_for_runway_sx,
             notifications_from_runway_rx,
             resolved_requests_rx,
+            broadcast_backup,
+            None,
+            Health::new(),
         )
     }
 
@@ -790,6 +1702,82 @@ mod tests {
         assert!(!recipients.contains(&Recipient::Node(node_ix)));
     }
 
+    #[test]
+    fn requests_skip_peers_with_pruned_history() {
+        let node_ix = NodeIndex(0);
+        let mut member = mock_member(node_ix, NodeCount(4));
+        member.config.delay_config.coord_request_recipients = Arc::new(|_| 3);
+        member.on_history_pruned(NodeIndex(1), 10);
+        member.on_history_pruned(NodeIndex(2), 10);
+
+        let old = CoordRequest(UnitCoord::new(5, NodeIndex(3)));
+        assert_eq!(
+            member.recipients(&old, 0),
+            vec![Recipient::Node(NodeIndex(3))]
+        );
+        let recent = CoordRequest(UnitCoord::new(10, NodeIndex(3)));
+        assert_eq!(member.recipients(&recent, 0).len(), 3);
+
+        // Without peers serving the units, everyone is asked anyway.
+        member.on_history_pruned(NodeIndex(3), 10);
+        assert_eq!(member.recipients(&old, 0).len(), 3);
+    }
+
+    #[test]
+    fn requests_prefer_catch_up_sources() {
+        let node_ix = NodeIndex(7);
+        let mut config = gen_config(node_ix, NodeCount(20));
+        config.catch_up_sources = CatchUpSources {
+            preferred: vec![NodeIndex(2), NodeIndex(7), NodeIndex(11)],
+            fallback_after: 2,
+        };
+        let mut member = mock_member_with_config(config);
+        member.config.delay_config.coord_request_recipients = Arc::new(|_| 5);
+        let request = CoordRequest(UnitCoord::new(1, NodeIndex(3)));
+
+        for counter in 0..2 {
+            let recipients = member.recipients(&request, counter);
+            assert_eq!(recipients.len(), 2);
+            assert!(recipients.contains(&Recipient::Node(NodeIndex(2))));
+            assert!(recipients.contains(&Recipient::Node(NodeIndex(11))));
+        }
+        let recipients = member.recipients(&request, 2);
+        assert_eq!(recipients.len(), 5);
+        assert!(!recipients.contains(&Recipient::Node(node_ix)));
+    }
+
+    #[test]
+    fn requests_prefer_nearby_peers() {
+        let node_ix = NodeIndex(7);
+        let mut config = gen_config(node_ix, NodeCount(20));
+        config.topology = TopologyHints {
+            same_datacenter: vec![NodeIndex(3)],
+            regions: (0..20)
+                .map(|i| (NodeIndex(i), format!("region-{}", i % 2)))
+                .collect(),
+            datacenter_weight: 100,
+            region_weight: 10,
+        };
+        let mut member = mock_member_with_config(config);
+        member.config.delay_config.coord_request_recipients = Arc::new(|_| 1);
+        let request = CoordRequest(UnitCoord::new(1, NodeIndex(3)));
+
+        let mut asked = NodeMap::with_size(NodeCount(20));
+        for _ in 0..1000 {
+            for recipient in member.recipients(&request, 5) {
+                if let Recipient::Node(peer) = recipient {
+                    asked.insert(peer, asked.get(peer).unwrap_or(&0) + 1);
+                }
+            }
+        }
+        let count = |peer| *asked.get(NodeIndex(peer)).unwrap_or(&0);
+        assert!(count(3) > count(9));
+        let same_region: usize = (1..20).step_by(2).filter(|i| *i != 7).map(count).sum();
+        let remote: usize = (0..20).step_by(2).map(count).sum();
+        assert!(same_region > remote);
+        assert!(remote > 0);
+    }
+
     #[test]
     fn at_most_n_members_recipients_for_coord_request() {
         let mut member = mock_member(NodeIndex(7), NodeCount(20));
@@ -811,4 +1799,293 @@ mod tests {
 
         assert_eq!(recipients, vec![]);
     }
+
+    #[test]
+    fn clock_jump_reschedules_pending_tasks() {
+        let mut member = mock_member(NodeIndex(7), NodeCount(20));
+        let coord = UnitCoord::new(1, NodeIndex(3));
+        member.task_queue.schedule_in(
+            RepeatableTask {
+                task: CoordRequest(coord),
+                counter: 5,
+            },
+            Duration::from_secs(60),
+        );
+        assert_eq!(member.task_queue.pop_due_task(), None);
+
+        member.on_clock_jump(Duration::from_secs(30));
+
+        assert_eq!(
+            member.task_queue.pop_due_task(),
+            Some(RepeatableTask::new(CoordRequest(coord)))
+        );
+        assert_eq!(member.task_queue.pop_due_task(), None);
+    }
+
+    async fn own_unit(
+        node_ix: NodeIndex,
+        node_count: NodeCount,
+    ) -> UncheckedSignedUnit<Hasher64, u32, Signature> {
+        let creators = creator_set(node_count);
+        let (pu, _) = create_units(creators.iter().skip(node_ix.0).take(1), 0)
+            .pop()
+            .expect("one unit was created");
+        preunit_to_unchecked_signed_unit(
+            pu,
+            SessionId::from_number(0),
+            &Keychain::new(node_count, node_ix),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn broadcasts_own_unit_until_acknowledged() {
+        let node_count = NodeCount(3);
+        let mut member = mock_member(NodeIndex(0), node_count);
+        let unit = own_unit(NodeIndex(0), node_count).await;
+        let hash = unit.as_signable().hash();
+        member.on_unit_discovered(unit.clone());
+
+        let task = UnitBroadcast(unit);
+        assert_eq!(member.recipients(&task, 0), vec![Recipient::Everyone]);
+        member.on_unit_ack(NodeIndex(2), hash);
+        member.on_unit_ack(NodeIndex(1), Hasher64::hash(&[0x0]));
+        assert_eq!(
+            member.recipients(&task, 1),
+            vec![Recipient::Node(NodeIndex(1))]
+        );
+        assert!(member.still_valid(&task));
+        member.on_unit_ack(NodeIndex(1), hash);
+        assert!(!member.still_valid(&task));
+    }
+
+    #[tokio::test]
+    async fn ignores_forged_acknowledgements() {
+        let node_count = NodeCount(3);
+        let mut member = mock_member(NodeIndex(0), node_count);
+        let unit = own_unit(NodeIndex(0), node_count).await;
+        let hash = unit.as_signable().hash();
+        member.on_unit_discovered(unit.clone());
+        let task = UnitBroadcast(unit);
+
+        // Node 2 claiming that node 1 acknowledged the unit.
+        let forged = UnitAcknowledgement::new(NodeIndex(1), hash);
+        let forged = Signed::sign(forged, &Keychain::new(node_count, NodeIndex(2)))
+            .await
+            .into_unchecked();
+        member.on_signed_unit_ack(forged);
+        assert_eq!(member.recipients(&task, 1), vec![Recipient::Everyone]);
+
+        let ack = UnitAcknowledgement::new(NodeIndex(2), hash);
+        let ack = Signed::sign(ack, &Keychain::new(node_count, NodeIndex(2)))
+            .await
+            .into_unchecked();
+        member.on_signed_unit_ack(ack);
+        assert_eq!(
+            member.recipients(&task, 1),
+            vec![Recipient::Node(NodeIndex(1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_hints_acknowledge_own_unit() {
+        let node_count = NodeCount(3);
+        let mut config = gen_config(NodeIndex(0), node_count);
+        config.duplicate_units = DuplicateUnitPolicy::Hint {
+            interval: Duration::from_secs(3600),
+        };
+        let mut member = mock_member_with_config(config);
+        let unit = own_unit(NodeIndex(0), node_count).await;
+        member.on_unit_discovered(unit.clone());
+        let task = UnitBroadcast(unit);
+
+        let mut top_rounds = NodeMap::with_size(node_count);
+        member.on_duplicate_hint(NodeIndex(1), Hasher64::hash(&[0x0]), top_rounds.clone());
+        top_rounds.insert(NodeIndex(0), 0);
+        member.on_duplicate_hint(
+            NodeIndex(2),
+            Hasher64::hash(&[0x0]),
+            NodeMap::with_size(NodeCount(2)),
+        );
+        member.on_duplicate_hint(NodeIndex(1), Hasher64::hash(&[0x0]), top_rounds);
+        assert_eq!(
+            member.recipients(&task, 1),
+            vec![Recipient::Node(NodeIndex(2))]
+        );
+
+        assert!(
+            member
+                .hint_duplicate(NodeIndex(1), Hasher64::hash(&[0x1]))
+                .await
+        );
+        assert!(
+            !member
+                .hint_duplicate(NodeIndex(1), Hasher64::hash(&[0x1]))
+                .await
+        );
+        assert!(
+            member
+                .hint_duplicate(NodeIndex(2), Hasher64::hash(&[0x1]))
+                .await
+        );
+        member.config.duplicate_units = DuplicateUnitPolicy::Ignore;
+        assert!(
+            !member
+                .hint_duplicate(NodeIndex(1), Hasher64::hash(&[0x1]))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_forged_duplicate_hints() {
+        let node_count = NodeCount(3);
+        let mut member = mock_member(NodeIndex(0), node_count);
+        let unit = own_unit(NodeIndex(0), node_count).await;
+        let hash = unit.as_signable().hash();
+        member.on_unit_discovered(unit.clone());
+        let task = UnitBroadcast(unit);
+        let top_rounds = NodeMap::with_size(node_count);
+
+        // Node 2 claiming that node 1 already had the unit.
+        let forged = DuplicateUnitHint::new(NodeIndex(1), hash, top_rounds.clone());
+        let forged = Signed::sign(forged, &Keychain::new(node_count, NodeIndex(2)))
+            .await
+            .into_unchecked();
+        member.on_signed_duplicate_hint(forged);
+        assert_eq!(member.recipients(&task, 1), vec![Recipient::Everyone]);
+
+        let hint = DuplicateUnitHint::new(NodeIndex(2), hash, top_rounds);
+        let hint = Signed::sign(hint, &Keychain::new(node_count, NodeIndex(2)))
+            .await
+            .into_unchecked();
+        member.on_signed_duplicate_hint(hint);
+        assert_eq!(
+            member.recipients(&task, 1),
+            vec![Recipient::Node(NodeIndex(1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_forged_history_floors() {
+        let node_count = NodeCount(4);
+        let mut member = mock_member(NodeIndex(0), node_count);
+        member.config.delay_config.coord_request_recipients = Arc::new(|_| 3);
+        let old = CoordRequest(UnitCoord::new(5, NodeIndex(3)));
+
+        // Node 2 claiming that node 1 pruned its history.
+        let forged = HistoryFloor::new(NodeIndex(1), 10);
+        let forged = Signed::sign(forged, &Keychain::new(node_count, NodeIndex(2)))
+            .await
+            .into_unchecked();
+        member.on_signed_history_floor(forged);
+        assert_eq!(member.recipients(&old, 0).len(), 3);
+
+        let floor = HistoryFloor::new(NodeIndex(1), 10);
+        let floor = Signed::sign(floor, &Keychain::new(node_count, NodeIndex(1)))
+            .await
+            .into_unchecked();
+        member.on_signed_history_floor(floor);
+        assert!(!member
+            .recipients(&old, 0)
+            .contains(&Recipient::Node(NodeIndex(1))));
+    }
+
+    #[test]
+    fn postpones_requests_beyond_window() {
+        let mut config = gen_config(NodeIndex(0), NodeCount(4));
+        config.request_concurrency = RequestConcurrency { initial: 1, max: 1 };
+        let mut member = mock_member_with_config(config);
+        let first = UnitCoord::new(1, NodeIndex(3));
+        let second = UnitCoord::new(1, NodeIndex(2));
+        member.on_request_coord(first);
+        member.on_request_coord(second);
+        assert_eq!(member.waiting_requests.len(), 1);
+
+        member.not_resolved_coords.remove(&first);
+        member.on_request_resolved(RequestKey::Coord(first));
+        assert!(member.waiting_requests.is_empty());
+        assert!(member
+            .task_queue
+            .iter()
+            .any(|task| task.task == CoordRequest(second)));
+    }
+
+    #[tokio::test]
+    async fn keeps_restored_broadcast_status() {
+        let node_count = NodeCount(3);
+        let mut member = mock_member(NodeIndex(0), node_count);
+        let unit = own_unit(NodeIndex(0), node_count).await;
+        let mut status = BroadcastStatus::new(
+            unit.as_signable().hash(),
+            unit.as_signable().round(),
+            gen_config(NodeIndex(0), node_count).session_id,
+            node_count,
+            NodeIndex(0),
+        );
+        status.ack(NodeIndex(1));
+        member.own_broadcast = Some(status.clone());
+
+        member.on_unit_discovered(unit.clone());
+
+        assert_eq!(member.own_broadcast, Some(status));
+        assert_eq!(
+            member.recipients(&UnitBroadcast(unit), 0),
+            vec![Recipient::Node(NodeIndex(2))]
+        );
+    }
+
+    #[test]
+    fn ignores_broadcast_status_of_other_sessions() {
+        let node_count = NodeCount(4);
+        let config = gen_config(NodeIndex(1), node_count);
+        let backup = |session_id, index| {
+            let status = BroadcastStatus::<Hasher64>::new(
+                Hasher64::hash(b"unit"),
+                5,
+                session_id,
+                node_count,
+                index,
+            );
+            BroadcastBackup::new(Saver::new(), Loader::new(status.encode()))
+        };
+
+        let member = mock_member_with_backup(
+            config.clone(),
+            Some(backup(config.session_id, NodeIndex(1))),
+        );
+        assert!(member.own_broadcast.is_some());
+        let member = mock_member_with_backup(
+            config.clone(),
+            Some(backup(SessionId::from_number(7), NodeIndex(1))),
+        );
+        assert!(member.own_broadcast.is_none());
+        let member = mock_member_with_backup(
+            config.clone(),
+            Some(backup(config.session_id, NodeIndex(2))),
+        );
+        assert!(member.own_broadcast.is_none());
+    }
+
+    #[tokio::test]
+    async fn does_not_broadcast_stale_units() {
+        let node_count = NodeCount(4);
+        let mut config = gen_config(NodeIndex(0), node_count);
+        config.lag_suppression = Some(2);
+        let mut member = mock_member_with_config(config);
+        member.top_units.insert(NodeIndex(1), 5);
+        member.top_units.insert(NodeIndex(2), 3);
+        assert_eq!(member.quorum_round(), 0);
+        assert!(!member.is_stale(0));
+
+        member.top_units.insert(NodeIndex(3), 4);
+        assert_eq!(member.quorum_round(), 3);
+        assert!(member.is_stale(0));
+        assert!(!member.is_stale(1));
+
+        member.on_unit_discovered(own_unit(NodeIndex(0), node_count).await);
+        assert!(!member
+            .task_queue
+            .iter()
+            .any(|task| matches!(task.task, UnitBroadcast(_))));
+    }
 }
@@ -0,0 +1,235 @@
+use crate::{Data, DataProvider, FinalizationHandler, NodeIndex, Sender};
+use async_trait::async_trait;
+use codec::{Decode, Encode, Error, Input};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use log::error;
+use parking_lot::Mutex;
+use std::{marker::PhantomData, sync::Arc};
+
+/// Maximal number of bytes the encoded metadata of a unit may take. Units with larger metadata
+/// fail to decode, so they are dropped by all honest nodes.
+pub const MAX_METADATA_SIZE: usize = 256;
+
+/// Data of a unit accompanied by metadata of the application, e.g. its software version or
+/// epoch. As the unit is signed by its creator, the metadata is authenticated, which makes it
+/// suitable for signaling coordinated upgrades through the consensus itself.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode)]
+pub struct WithMetadata<M: Data, D: Data> {
+    metadata: M,
+    data: Option<D>,
+}
+
+impl<M: Data, D: Data> WithMetadata<M, D> {
+    pub fn metadata(&self) -> &M {
+        &self.metadata
+    }
+
+    pub fn data(&self) -> Option<&D> {
+        self.data.as_ref()
+    }
+}
+
+impl<M: Data, D: Data> Decode for WithMetadata<M, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let metadata = M::decode(input)?;
+        if metadata.encoded_size() > MAX_METADATA_SIZE {
+            return Err("Unit metadata too large".into());
+        }
+        let data = Option::<D>::decode(input)?;
+        Ok(WithMetadata { metadata, data })
+    }
+}
+
+/// Changes the metadata placed into the units created from now on.
+#[derive(Clone)]
+pub struct MetadataHandle<M: Data> {
+    metadata: Arc<Mutex<M>>,
+}
+
+impl<M: Data> MetadataHandle<M> {
+    pub fn set(&self, metadata: M) {
+        *self.metadata.lock() = metadata;
+    }
+}
+
+/// Attaches the current metadata to every unit, together with the data of the wrapped provider,
+/// if any. The session should be run with [`WithMetadata`] as its data, and together with a
+/// [`MetadataFinalizationHandler`].
+pub struct MetadataProvider<M: Data, D: Data, DP: DataProvider<D>> {
+    data_provider: DP,
+    metadata: Arc<Mutex<M>>,
+    _phantom: PhantomData<D>,
+}
+
+impl<M: Data, D: Data, DP: DataProvider<D>> MetadataProvider<M, D, DP> {
+    /// The `metadata` should take at most [`MAX_METADATA_SIZE`] bytes when encoded.
+    pub fn new(data_provider: DP, metadata: M) -> Self {
+        MetadataProvider {
+            data_provider,
+            metadata: Arc::new(Mutex::new(metadata)),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// A handle for changing the metadata during the session.
+    pub fn handle(&self) -> MetadataHandle<M> {
+        MetadataHandle {
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Data, D: Data, DP: DataProvider<D>> DataProvider<WithMetadata<M, D>>
+    for MetadataProvider<M, D, DP>
+{
+    async fn get_data(&mut self) -> Option<WithMetadata<M, D>> {
+        let metadata = self.metadata.lock().clone();
+        if metadata.encoded_size() > MAX_METADATA_SIZE {
+            // Other nodes would drop the unit, so we create an empty one instead.
+            error!(target: "AlephBFT-metadata", "Metadata takes {} bytes, more than the allowed {}, creating a unit without data.", metadata.encoded_size(), MAX_METADATA_SIZE);
+            return None;
+        }
+        let data = self.data_provider.get_data().await;
+        Some(WithMetadata { metadata, data })
+    }
+}
+
+/// Passes the data of units created by a [`MetadataProvider`] to the wrapped handler, and the
+/// metadata of every ordered unit, in order and together with its creator, to the returned
+/// channel.
+pub struct MetadataFinalizationHandler<M: Data, D: Data, FH: FinalizationHandler<D>> {
+    finalization_handler: FH,
+    metadata: Sender<(NodeIndex, M)>,
+    _phantom: PhantomData<D>,
+}
+
+impl<M: Data, D: Data, FH: FinalizationHandler<D>> MetadataFinalizationHandler<M, D, FH> {
+    pub fn new(finalization_handler: FH) -> (Self, UnboundedReceiver<(NodeIndex, M)>) {
+        let (metadata, metadata_rx) = unbounded();
+        (
+            MetadataFinalizationHandler {
+                finalization_handler,
+                metadata,
+                _phantom: PhantomData,
+            },
+            metadata_rx,
+        )
+    }
+}
+
+impl<M: Data, D: Data, FH: FinalizationHandler<D>> FinalizationHandler<WithMetadata<M, D>>
+    for MetadataFinalizationHandler<M, D, FH>
+{
+    fn data_finalized(&mut self, data: WithMetadata<M, D>) {
+        if let Some(data) = data.data {
+            self.finalization_handler.data_finalized(data);
+        }
+    }
+
+    fn units_finalized(&mut self, units: Vec<(NodeIndex, Option<WithMetadata<M, D>>)>) {
+        let mut data = Vec::with_capacity(units.len());
+        for (creator, unit_data) in units {
+            match unit_data {
+                Some(WithMetadata {
+                    metadata,
+                    data: unit_data,
+                }) => {
+                    // The receiver might not be interested in metadata anymore.
+                    let _ = self.metadata.unbounded_send((creator, metadata));
+                    data.push((creator, unit_data));
+                }
+                None => data.push((creator, None)),
+            }
+        }
+        self.finalization_handler.units_finalized(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetadataFinalizationHandler, MetadataProvider, WithMetadata, MAX_METADATA_SIZE};
+    use crate::{DataProvider, FinalizationHandler, NodeIndex};
+    use aleph_bft_mock::DataProvider as MockDataProvider;
+    use codec::{Decode, Encode};
+
+    #[tokio::test]
+    async fn attaches_current_metadata() {
+        let mut provider = MetadataProvider::new(MockDataProvider::new(), 1u32);
+        let handle = provider.handle();
+        let first = provider.get_data().await.expect("metadata is small");
+        assert_eq!(first.metadata(), &1);
+        assert!(first.data().is_some());
+        handle.set(2);
+        let second = provider.get_data().await.expect("metadata is small");
+        assert_eq!(second.metadata(), &2);
+
+        let mut provider = MetadataProvider::new(MockDataProvider::new(), vec![0u8; 300]);
+        assert_eq!(provider.get_data().await, None);
+    }
+
+    #[test]
+    fn rejects_large_metadata() {
+        let small = WithMetadata {
+            metadata: vec![7u8; MAX_METADATA_SIZE - 2],
+            data: Some(3u32),
+        };
+        let encoded = small.encode();
+        assert_eq!(
+            WithMetadata::<Vec<u8>, u32>::decode(&mut &encoded[..]).ok(),
+            Some(small)
+        );
+        let large = WithMetadata {
+            metadata: vec![7u8; MAX_METADATA_SIZE],
+            data: Some(3u32),
+        };
+        assert!(WithMetadata::<Vec<u8>, u32>::decode(&mut &large.encode()[..]).is_err());
+    }
+
+    struct CollectingHandler {
+        units: Vec<(NodeIndex, Option<u32>)>,
+    }
+
+    impl FinalizationHandler<u32> for CollectingHandler {
+        fn data_finalized(&mut self, data: u32) {
+            self.units.push((NodeIndex(0), Some(data)));
+        }
+
+        fn units_finalized(&mut self, units: Vec<(NodeIndex, Option<u32>)>) {
+            self.units.extend(units);
+        }
+    }
+
+    #[test]
+    fn exposes_metadata_of_ordered_units() {
+        let (mut handler, mut metadata) =
+            MetadataFinalizationHandler::new(CollectingHandler { units: Vec::new() });
+        handler.units_finalized(vec![
+            (
+                NodeIndex(1),
+                Some(WithMetadata {
+                    metadata: 5u8,
+                    data: Some(7u32),
+                }),
+            ),
+            (NodeIndex(2), None),
+            (
+                NodeIndex(3),
+                Some(WithMetadata {
+                    metadata: 6,
+                    data: None,
+                }),
+            ),
+        ]);
+        assert_eq!(
+            handler.finalization_handler.units,
+            vec![
+                (NodeIndex(1), Some(7)),
+                (NodeIndex(2), None),
+                (NodeIndex(3), None)
+            ]
+        );
+        assert_eq!(metadata.try_next().unwrap(), Some((NodeIndex(1), 5)));
+        assert_eq!(metadata.try_next().unwrap(), Some((NodeIndex(3), 6)));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{Data, DataProvider, FinalizationHandler, Stats, StatsSnapshot};
+use async_trait::async_trait;
+use std::{marker::PhantomData, time::Duration};
+
+/// Limits on the data packed into a single unit by a [`MultiDataProvider`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PayloadLimits {
+    /// Maximal number of data items in a unit.
+    pub max_items: usize,
+    /// Maximal number of bytes the encoded items of a unit may take, a single item is allowed
+    /// to cross it.
+    pub max_bytes: usize,
+}
+
+/// How payloads shrink when the session struggles. When rounds take longer than
+/// `target_round_duration`, the network is saturated, and when batches take longer than
+/// `target_latency` to finalize, consensus is falling behind, so the maximal number of bytes is
+/// scaled down by the worse of the two ratios, not going below `min_bytes`. Once the session
+/// recovers, payloads grow back to the configured limits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PayloadFeedback {
+    /// The round duration expected of a healthy session, e.g. twice the unit creation delay.
+    pub target_round_duration: Duration,
+    /// The finalization latency expected of a healthy session.
+    pub target_latency: Duration,
+    /// The bytes a unit may always take, so that the session can still make progress.
+    pub min_bytes: usize,
+}
+
+impl PayloadFeedback {
+    /// The limits suggested by the statistics of the session. Applications not packing items
+    /// using a [`MultiDataProvider`] can use this on a [`Stats`] snapshot to size their data.
+    pub fn suggested_limits(
+        &self,
+        snapshot: &StatsSnapshot,
+        limits: PayloadLimits,
+    ) -> PayloadLimits {
+        let ratio = |target: Duration, observed: Option<Duration>| match observed {
+            Some(observed) if observed > target => target.as_secs_f64() / observed.as_secs_f64(),
+            _ => 1.0,
+        };
+        let scale = ratio(self.target_round_duration, snapshot.average_round_duration)
+            .min(ratio(self.target_latency, snapshot.finalization_latency));
+        PayloadLimits {
+            max_items: limits.max_items,
+            max_bytes: ((limits.max_bytes as f64 * scale) as usize)
+                .max(self.min_bytes)
+                .min(limits.max_bytes),
+        }
+    }
+}
+
+/// Lets a unit contain several data items, so that throughput can grow without creating units
+/// more often. The session should be run with a vector of items as its data, the items are
+/// obtained from the wrapped provider using [`DataProvider::get_data_items`]. Should be used
+/// together with [`FlatteningFinalizationHandler`].
+pub struct MultiDataProvider<D: Data, DP: DataProvider<D>> {
+    data_provider: DP,
+    limits: PayloadLimits,
+    feedback: Option<(Stats, PayloadFeedback)>,
+    _phantom: PhantomData<D>,
+}
+
+impl<D: Data, DP: DataProvider<D>> MultiDataProvider<D, DP> {
+    pub fn new(data_provider: DP, limits: PayloadLimits) -> Self {
+        MultiDataProvider {
+            data_provider,
+            limits,
+            feedback: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Shrinks the payloads requested from the wrapped provider according to the statistics of
+    /// the session, see [`PayloadFeedback`]. The handle should be the one of the session, set
+    /// e.g. with [`crate::MemberBuilder::with_metrics`].
+    pub fn with_feedback(mut self, stats: Stats, feedback: PayloadFeedback) -> Self {
+        self.feedback = Some((stats, feedback));
+        self
+    }
+
+    fn current_limits(&self) -> PayloadLimits {
+        match &self.feedback {
+            Some((stats, feedback)) => feedback.suggested_limits(&stats.snapshot(), self.limits),
+            None => self.limits,
+        }
+    }
+}
+
+#[async_trait]
+impl<D: Data, DP: DataProvider<D>> DataProvider<Vec<D>> for MultiDataProvider<D, DP> {
+    async fn get_data(&mut self) -> Option<Vec<D>> {
+        let limits = self.current_limits();
+        let mut items = self
+            .data_provider
+            .get_data_items(limits.max_items, limits.max_bytes)
+            .await;
+        // Do not trust the provider with the count, as it is cheap to enforce.
+        items.truncate(limits.max_items);
+        match items.is_empty() {
+            true => None,
+            false => Some(items),
+        }
+    }
+}
+
+/// Passes the items of units created by a [`MultiDataProvider`] to the wrapped handler, in the
+/// order in which they were placed in the units.
+pub struct FlatteningFinalizationHandler<D: Data, FH: FinalizationHandler<D>> {
+    finalization_handler: FH,
+    _phantom: PhantomData<D>,
+}
+
+impl<D: Data, FH: FinalizationHandler<D>> FlatteningFinalizationHandler<D, FH> {
+    pub fn new(finalization_handler: FH) -> Self {
+        FlatteningFinalizationHandler {
+            finalization_handler,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<D: Data, FH: FinalizationHandler<D>> FinalizationHandler<Vec<D>>
+    for FlatteningFinalizationHandler<D, FH>
+{
+    fn data_finalized(&mut self, items: Vec<D>) {
+        for item in items {
+            self.finalization_handler.data_finalized(item);
+        }
+    }
+
+    fn batch_finalized(&mut self, batch: Vec<Vec<D>>) {
+        self.finalization_handler
+            .batch_finalized(batch.into_iter().flatten().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FlatteningFinalizationHandler, MultiDataProvider, PayloadFeedback, PayloadLimits};
+    use crate::{DataProvider, FinalizationHandler, StatsSnapshot};
+    use async_trait::async_trait;
+    use codec::Encode;
+    use std::time::Duration;
+
+    struct CountingProvider {
+        next: u32,
+    }
+
+    #[async_trait]
+    impl DataProvider<u32> for CountingProvider {
+        async fn get_data(&mut self) -> Option<u32> {
+            self.next += 1;
+            Some(self.next)
+        }
+
+        async fn get_data_items(&mut self, max_items: usize, max_bytes: usize) -> Vec<u32> {
+            let mut items = Vec::new();
+            while items.len() < max_items && (items.len() + 1) * 0u32.encoded_size() <= max_bytes {
+                items.push(self.get_data().await.expect("there is always data"));
+            }
+            items
+        }
+    }
+
+    struct CollectingHandler {
+        batches: Vec<Vec<u32>>,
+    }
+
+    impl FinalizationHandler<u32> for CollectingHandler {
+        fn data_finalized(&mut self, data: u32) {
+            self.batches.push(vec![data]);
+        }
+
+        fn batch_finalized(&mut self, batch: Vec<u32>) {
+            self.batches.push(batch);
+        }
+    }
+
+    #[tokio::test]
+    async fn packs_items_within_limits() {
+        let limits = PayloadLimits {
+            max_items: 3,
+            max_bytes: 8,
+        };
+        let mut provider = MultiDataProvider::new(CountingProvider { next: 0 }, limits);
+        assert_eq!(provider.get_data().await, Some(vec![1, 2]));
+
+        let limits = PayloadLimits {
+            max_items: 3,
+            max_bytes: 100,
+        };
+        let mut provider = MultiDataProvider::new(CountingProvider { next: 0 }, limits);
+        assert_eq!(provider.get_data().await, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn shrinks_payloads_when_struggling() {
+        let feedback = PayloadFeedback {
+            target_round_duration: Duration::from_millis(100),
+            target_latency: Duration::from_millis(500),
+            min_bytes: 100,
+        };
+        let limits = PayloadLimits {
+            max_items: 10,
+            max_bytes: 1000,
+        };
+        let snapshot = |round_duration, latency| StatsSnapshot {
+            average_round_duration: Some(Duration::from_millis(round_duration)),
+            finalization_latency: Some(Duration::from_millis(latency)),
+            ..StatsSnapshot::default()
+        };
+        let max_bytes = |snapshot| feedback.suggested_limits(&snapshot, limits).max_bytes;
+
+        assert_eq!(max_bytes(StatsSnapshot::default()), 1000);
+        assert_eq!(max_bytes(snapshot(80, 400)), 1000);
+        assert_eq!(max_bytes(snapshot(200, 400)), 500);
+        assert_eq!(max_bytes(snapshot(200, 2000)), 250);
+        assert_eq!(max_bytes(snapshot(5000, 400)), 100);
+        assert_eq!(
+            feedback
+                .suggested_limits(&snapshot(200, 400), limits)
+                .max_items,
+            10
+        );
+    }
+
+    #[test]
+    fn flattens_items_in_order() {
+        let mut handler = FlatteningFinalizationHandler::new(CollectingHandler {
+            batches: Vec::new(),
+        });
+        handler.batch_finalized(vec![vec![1, 2], vec![], vec![3]]);
+        handler.data_finalized(vec![4, 5]);
+        assert_eq!(
+            handler.finalization_handler.batches,
+            vec![vec![1, 2, 3], vec![4], vec![5]]
+        );
+    }
+}
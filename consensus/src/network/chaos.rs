@@ -0,0 +1,239 @@
+use crate::{RawNetwork, Recipient};
+use futures::{
+    future::{select, BoxFuture, Either},
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
+use futures_timer::Delay;
+use log::{info, trace};
+use parking_lot::RwLock;
+use rand::Rng;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::Duration,
+};
+
+/// The faults injected by a [`ChaosNetwork`] into the frames it receives.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChaosSettings {
+    /// Delay added to every frame.
+    pub delay: Duration,
+    /// Random delay added to every frame on top of `delay`, between zero and this.
+    pub jitter: Duration,
+    /// Fraction of frames dropped, between 0 and 1.
+    pub drop_rate: f64,
+}
+
+/// Why a command passed to [`ChaosControl::apply`] was rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChaosCommandError {
+    UnknownCommand(String),
+    InvalidValue(String),
+}
+
+impl Display for ChaosCommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ChaosCommandError::UnknownCommand(command) => {
+                write!(f, "unknown command {:?}", command)
+            }
+            ChaosCommandError::InvalidValue(value) => write!(f, "invalid value {:?}", value),
+        }
+    }
+}
+
+impl Error for ChaosCommandError {}
+
+/// Changes the faults injected by a [`ChaosNetwork`] while it runs. All clones control the same
+/// network.
+///
+/// Besides the setters, the control understands single line text commands, so that the
+/// application can expose it on a control socket of a staging deployment and chaos experiments
+/// can be scripted against the real binary:
+/// * `delay <ms>` and `jitter <ms>` set the delays,
+/// * `drop <rate>` sets the fraction of dropped frames,
+/// * `reset` stops injecting faults.
+#[derive(Clone, Default)]
+pub struct ChaosControl {
+    settings: Arc<RwLock<ChaosSettings>>,
+}
+
+impl ChaosControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn settings(&self) -> ChaosSettings {
+        self.settings.read().clone()
+    }
+
+    pub fn set(&self, settings: ChaosSettings) {
+        info!(target: "AlephBFT-network-chaos", "Injecting faults: {:?}.", settings);
+        *self.settings.write() = settings;
+    }
+
+    /// Applies a text command, see the description of [`ChaosControl`].
+    pub fn apply(&self, command: &str) -> Result<(), ChaosCommandError> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let value = words.next();
+        let millis = |value: Option<&str>| {
+            value
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_millis)
+                .ok_or_else(|| ChaosCommandError::InvalidValue(value.unwrap_or_default().into()))
+        };
+        let mut settings = self.settings();
+        match name {
+            "delay" => settings.delay = millis(value)?,
+            "jitter" => settings.jitter = millis(value)?,
+            "drop" => {
+                settings.drop_rate = value
+                    .and_then(|value| value.parse().ok())
+                    .filter(|rate| (0.0..=1.0).contains(rate))
+                    .ok_or_else(|| {
+                        ChaosCommandError::InvalidValue(value.unwrap_or_default().into())
+                    })?
+            }
+            "reset" => settings = ChaosSettings::default(),
+            _ => return Err(ChaosCommandError::UnknownCommand(name.into())),
+        }
+        self.set(settings);
+        Ok(())
+    }
+}
+
+/// Wraps a [`RawNetwork`] delaying and dropping the received frames as set through its
+/// [`ChaosControl`], for chaos testing in staging environments. Frames are sent unchanged, so
+/// wrapping the networks of all nodes affects every link in both directions. Without faults
+/// set, frames pass through immediately and in order. While frames are delayed, waiting for the
+/// next frame of the wrapped network is cancelled whenever a delayed frame is due, so its
+/// `next_event` has to be cancel safe, as it is when reading from a channel.
+pub struct ChaosNetwork<N: RawNetwork> {
+    network: N,
+    control: ChaosControl,
+    delayed: FuturesUnordered<BoxFuture<'static, Vec<u8>>>,
+}
+
+impl<N: RawNetwork> ChaosNetwork<N> {
+    pub fn new(network: N) -> (Self, ChaosControl) {
+        let control = ChaosControl::new();
+        (
+            ChaosNetwork {
+                network,
+                control: control.clone(),
+                delayed: FuturesUnordered::new(),
+            },
+            control,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<N: RawNetwork> RawNetwork for ChaosNetwork<N> {
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        self.network.send(frame, recipient);
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let frame = match self.delayed.is_empty() {
+                true => self.network.next_event().await?,
+                false => match select(self.network.next_event(), self.delayed.next()).await {
+                    Either::Left((Some(frame), _)) => frame,
+                    // Deliver the delayed frames even if the network ended.
+                    Either::Left((None, delayed)) => return delayed.await,
+                    Either::Right((frame, _)) => return frame,
+                },
+            };
+            let settings = self.control.settings();
+            let mut rng = rand::thread_rng();
+            if settings.drop_rate > 0.0 && rng.gen_bool(settings.drop_rate.min(1.0)) {
+                trace!(target: "AlephBFT-network-chaos", "Dropped a frame.");
+                continue;
+            }
+            let jitter = match settings.jitter.is_zero() {
+                true => Duration::ZERO,
+                false => rng.gen_range(Duration::ZERO..=settings.jitter),
+            };
+            let delay = settings.delay + jitter;
+            if delay.is_zero() && self.delayed.is_empty() {
+                return Some(frame);
+            }
+            self.delayed
+                .push(Delay::new(delay).map(move |_| frame).boxed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChaosCommandError, ChaosNetwork, ChaosSettings};
+    use crate::{RawNetwork, Recipient};
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver},
+        StreamExt,
+    };
+    use std::time::{Duration, Instant};
+
+    struct TestNetwork {
+        rx: UnboundedReceiver<Vec<u8>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RawNetwork for TestNetwork {
+        fn send(&self, _frame: Vec<u8>, _recipient: Recipient) {}
+
+        async fn next_event(&mut self) -> Option<Vec<u8>> {
+            self.rx.next().await
+        }
+    }
+
+    #[test]
+    fn applies_commands() {
+        let (_, control) = ChaosNetwork::new(TestNetwork { rx: unbounded().1 });
+        control.apply("delay 200").expect("the command is correct");
+        control.apply("jitter 50").expect("the command is correct");
+        control.apply("drop 0.25").expect("the command is correct");
+        assert_eq!(
+            control.settings(),
+            ChaosSettings {
+                delay: Duration::from_millis(200),
+                jitter: Duration::from_millis(50),
+                drop_rate: 0.25,
+            }
+        );
+        assert_eq!(
+            control.apply("drop 2"),
+            Err(ChaosCommandError::InvalidValue("2".into()))
+        );
+        assert_eq!(
+            control.apply("partition"),
+            Err(ChaosCommandError::UnknownCommand("partition".into()))
+        );
+        control.apply("reset").expect("the command is correct");
+        assert_eq!(control.settings(), ChaosSettings::default());
+    }
+
+    #[tokio::test]
+    async fn delays_and_drops_frames() {
+        let (tx, rx) = unbounded();
+        let (mut network, control) = ChaosNetwork::new(TestNetwork { rx });
+        tx.unbounded_send(vec![1]).expect("the receiver is alive");
+        assert_eq!(network.next_event().await, Some(vec![1]));
+
+        control.apply("delay 100").expect("the command is correct");
+        tx.unbounded_send(vec![2]).expect("the receiver is alive");
+        let start = Instant::now();
+        assert_eq!(network.next_event().await, Some(vec![2]));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        control.apply("reset").expect("the command is correct");
+        control.apply("drop 1").expect("the command is correct");
+        tx.unbounded_send(vec![3]).expect("the receiver is alive");
+        drop(tx);
+        assert_eq!(network.next_event().await, None);
+    }
+}
@@ -0,0 +1,71 @@
+use log::error;
+use parking_lot::Mutex;
+use std::{
+    io::Result as IoResult,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of threads dedicated to encoding and decoding large messages, so that they do not
+/// block the threads of the async executor. The threads stop once all clones of the pool are
+/// dropped and the remaining jobs are done.
+#[derive(Clone)]
+pub struct CodecPool {
+    jobs: Arc<Mutex<Sender<Job>>>,
+}
+
+impl CodecPool {
+    /// Starts a pool with the given number of threads, at least one.
+    pub fn new(threads: usize) -> IoResult<Self> {
+        let (jobs, jobs_rx) = channel::<Job>();
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+        for index in 0..threads.max(1) {
+            let jobs_rx = jobs_rx.clone();
+            thread::Builder::new()
+                .name(format!("aleph-bft-codec-{}", index))
+                .spawn(move || loop {
+                    let job = jobs_rx.lock().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })?;
+        }
+        Ok(CodecPool {
+            jobs: Arc::new(Mutex::new(jobs)),
+        })
+    }
+
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        if self.jobs.lock().send(Box::new(job)).is_err() {
+            error!(target: "AlephBFT-network-codec", "All threads of the codec pool stopped.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodecPool;
+    use futures::channel::oneshot;
+
+    #[tokio::test]
+    async fn runs_jobs() {
+        let pool = CodecPool::new(2).expect("threads should start");
+        let mut results = Vec::new();
+        for job in 0..10 {
+            let (tx, rx) = oneshot::channel();
+            pool.execute(move || {
+                let _ = tx.send(job * 2);
+            });
+            results.push(rx);
+        }
+        for (job, result) in results.into_iter().enumerate() {
+            assert_eq!(result.await, Ok(job * 2));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{RawNetwork, Recipient};
+use log::trace;
+use std::{
+    collections::{hash_map::RandomState, HashSet, VecDeque},
+    hash::{BuildHasher, Hash, Hasher},
+    time::{Duration, Instant},
+};
+
+/// Wraps a [`RawNetwork`] dropping frames identical to ones received within the last `window`,
+/// so that when the embedder delivers messages over several redundant transports, e.g. gossip
+/// and direct links, every copy after the first is discarded before decoding and validation.
+///
+/// The window should cover the difference in delivery times between the transports, but stay
+/// well below the retry intervals of [`crate::DelayConfig`], since a repeated request is
+/// indistinguishable from a duplicate.
+pub struct DedupNetwork<N: RawNetwork> {
+    network: N,
+    window: Duration,
+    hasher: RandomState,
+    seen: HashSet<u64>,
+    arrivals: VecDeque<(Instant, u64)>,
+}
+
+impl<N: RawNetwork> DedupNetwork<N> {
+    pub fn new(network: N, window: Duration) -> Self {
+        DedupNetwork {
+            network,
+            window,
+            hasher: RandomState::new(),
+            seen: HashSet::new(),
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    fn digest(&self, frame: &[u8]) -> u64 {
+        // The keys of the hasher are random, so others cannot craft colliding frames.
+        let mut hasher = self.hasher.build_hasher();
+        frame.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while let Some((arrival, digest)) = self.arrivals.front() {
+            if now.saturating_duration_since(*arrival) < self.window {
+                break;
+            }
+            self.seen.remove(digest);
+            self.arrivals.pop_front();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<N: RawNetwork> RawNetwork for DedupNetwork<N> {
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        self.network.send(frame, recipient);
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let frame = self.network.next_event().await?;
+            let now = Instant::now();
+            self.forget_old(now);
+            let digest = self.digest(&frame);
+            if self.seen.insert(digest) {
+                self.arrivals.push_back((now, digest));
+                return Some(frame);
+            }
+            trace!(target: "AlephBFT-network-dedup", "Dropped a duplicate frame of {} bytes.", frame.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DedupNetwork;
+    use crate::{RawNetwork, Recipient};
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver},
+        StreamExt,
+    };
+    use std::time::Duration;
+
+    struct TestNetwork {
+        rx: UnboundedReceiver<Vec<u8>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RawNetwork for TestNetwork {
+        fn send(&self, _frame: Vec<u8>, _recipient: Recipient) {}
+
+        async fn next_event(&mut self) -> Option<Vec<u8>> {
+            self.rx.next().await
+        }
+    }
+
+    fn received(window: Duration, frames: Vec<Vec<u8>>) -> DedupNetwork<TestNetwork> {
+        let (tx, rx) = unbounded();
+        for frame in frames {
+            tx.unbounded_send(frame).expect("channel should be open");
+        }
+        DedupNetwork::new(TestNetwork { rx }, window)
+    }
+
+    #[tokio::test]
+    async fn drops_duplicates_within_window() {
+        let frames = vec![vec![1], vec![2], vec![1], vec![2, 1], vec![2]];
+        let mut network = received(Duration::from_secs(60), frames);
+        assert_eq!(network.next_event().await, Some(vec![1]));
+        assert_eq!(network.next_event().await, Some(vec![2]));
+        assert_eq!(network.next_event().await, Some(vec![2, 1]));
+    }
+
+    #[tokio::test]
+    async fn passes_repeats_after_window() {
+        let mut network = received(Duration::ZERO, vec![vec![1], vec![1]]);
+        assert_eq!(network.next_event().await, Some(vec![1]));
+        assert_eq!(network.next_event().await, Some(vec![1]));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{AddressedRawNetwork, RawNetwork, Recipient};
+use log::trace;
+use parking_lot::RwLock;
+use std::{collections::HashSet, fmt::Debug, hash::Hash, sync::Arc};
+
+/// Decides which transport addresses are allowed to send us traffic.
+///
+/// An address is allowed if it is not blacklisted and, when a whitelist was configured, is
+/// on the whitelist. The whitelist is meant to contain the addresses of the committee and is
+/// fixed, while the blacklist is managed by the operator and can be changed at any time -- all
+/// clones of the filter refer to the same blacklist, so changes are visible without restarting.
+///
+/// The transports shipped with AlephBFT already pin the keys of the committee, authenticating
+/// every peer with its key from the keychain. Other transports authenticating their peers, e.g.
+/// with a Noise or TLS handshake, can use the public key proven in the handshake as the address.
+/// Whitelisting the transport keys from the committee configuration then pins them, so taking
+/// over the IP address or DNS entry of a member is not enough to send us traffic in its name.
+#[derive(Clone)]
+pub struct PeerFilter<A: Eq + Hash> {
+    whitelist: Option<Arc<HashSet<A>>>,
+    blacklist: Arc<RwLock<HashSet<A>>>,
+}
+
+impl<A: Eq + Hash> Default for PeerFilter<A> {
+    fn default() -> Self {
+        PeerFilter {
+            whitelist: None,
+            blacklist: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+}
+
+impl<A: Eq + Hash> PeerFilter<A> {
+    /// A filter allowing all addresses, except the blacklisted ones.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A filter allowing only the given addresses, except the blacklisted ones.
+    pub fn with_whitelist(whitelist: impl IntoIterator<Item = A>) -> Self {
+        PeerFilter {
+            whitelist: Some(Arc::new(whitelist.into_iter().collect())),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_allowed(&self, address: &A) -> bool {
+        let whitelisted = match &self.whitelist {
+            Some(whitelist) => whitelist.contains(address),
+            None => true,
+        };
+        whitelisted && !self.blacklist.read().contains(address)
+    }
+
+    /// Adds the address to the blacklist.
+    pub fn block(&self, address: A) {
+        self.blacklist.write().insert(address);
+    }
+
+    /// Removes the address from the blacklist.
+    pub fn unblock(&self, address: &A) {
+        self.blacklist.write().remove(address);
+    }
+
+    /// Replaces the whole blacklist, e.g. after the operator's configuration was reloaded.
+    pub fn set_blacklist(&self, blacklist: impl IntoIterator<Item = A>) {
+        *self.blacklist.write() = blacklist.into_iter().collect();
+    }
+}
+
+/// Wraps an [`AddressedRawNetwork`] into a [`RawNetwork`] dropping all frames coming from
+/// addresses not allowed by the [`PeerFilter`], so they never reach message decoding.
+pub struct FilteredNetwork<A: Eq + Hash, N: AddressedRawNetwork<A>> {
+    network: N,
+    filter: PeerFilter<A>,
+}
+
+impl<A: Eq + Hash, N: AddressedRawNetwork<A>> FilteredNetwork<A, N> {
+    pub fn new(network: N, filter: PeerFilter<A>) -> Self {
+        FilteredNetwork { network, filter }
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: Eq + Hash + Debug + Send + Sync, N: AddressedRawNetwork<A>> RawNetwork
+    for FilteredNetwork<A, N>
+{
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        self.network.send(frame, recipient);
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let (address, frame) = self.network.next_event().await?;
+            if self.filter.is_allowed(&address) {
+                return Some(frame);
+            }
+            trace!(target: "AlephBFT-network-filter", "Dropped a frame from {:?}, which is not allowed.", address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilteredNetwork, PeerFilter};
+    use crate::{AddressedRawNetwork, RawNetwork, Recipient};
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver},
+        StreamExt,
+    };
+
+    struct TestNetwork {
+        rx: UnboundedReceiver<(u16, Vec<u8>)>,
+    }
+
+    #[async_trait::async_trait]
+    impl AddressedRawNetwork<u16> for TestNetwork {
+        fn send(&self, _frame: Vec<u8>, _recipient: Recipient) {}
+
+        async fn next_event(&mut self) -> Option<(u16, Vec<u8>)> {
+            self.rx.next().await
+        }
+    }
+
+    #[test]
+    fn filters_addresses() {
+        let filter = PeerFilter::with_whitelist([1, 2, 3]);
+        assert!(filter.is_allowed(&1));
+        assert!(!filter.is_allowed(&4));
+        filter.clone().block(2);
+        assert!(!filter.is_allowed(&2));
+        filter.unblock(&2);
+        assert!(filter.is_allowed(&2));
+        filter.set_blacklist([1, 4]);
+        assert!(!filter.is_allowed(&1));
+        assert!(filter.is_allowed(&2));
+
+        let filter = PeerFilter::new();
+        assert!(filter.is_allowed(&4));
+        filter.block(4);
+        assert!(!filter.is_allowed(&4));
+    }
+
+    #[tokio::test]
+    async fn drops_frames_from_disallowed_addresses() {
+        let (tx, rx) = unbounded();
+        let filter = PeerFilter::with_whitelist([1, 2]);
+        filter.block(2);
+        let mut network = FilteredNetwork::new(TestNetwork { rx }, filter);
+        for (address, frame) in [(3, vec![3]), (2, vec![2]), (1, vec![1])] {
+            tx.unbounded_send((address, frame))
+                .expect("channel should be open");
+        }
+        drop(tx);
+        assert_eq!(network.next_event().await, Some(vec![1]));
+        assert_eq!(network.next_event().await, None);
+    }
+}
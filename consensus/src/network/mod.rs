@@ -1,12 +1,39 @@
 use crate::{
-    alerts::AlertMessage, member::UnitMessage, Data, Hasher, Network, PartialMultisignature,
-    Receiver, Recipient, Sender, Signature, Terminator,
+    alerts::AlertMessage,
+    budget::{BandwidthTracker, BudgetEvent},
+    member::UnitMessage,
+    Data, Hasher, Health, Network, PartialMultisignature, Receiver, Recipient, Sender, Signature,
+    Stats, Subsystem, Terminator,
 };
 use codec::{Decode, Encode};
 use futures::{FutureExt, StreamExt};
-use log::{debug, error, warn};
+use log::{debug, error, info, warn};
 use std::fmt::Debug;
 
+mod chaos;
+mod codec_pool;
+mod dedup;
+mod filter;
+mod multiplex;
+#[cfg(feature = "quic")]
+pub mod quic;
+mod raw;
+#[cfg(any(feature = "tcp", feature = "quic"))]
+mod stream;
+mod switch;
+#[cfg(feature = "tcp")]
+pub mod tcp;
+#[cfg(any(feature = "tcp", feature = "quic"))]
+mod tls;
+
+pub use chaos::{ChaosCommandError, ChaosControl, ChaosNetwork, ChaosSettings};
+pub use codec_pool::CodecPool;
+pub use dedup::DedupNetwork;
+pub use filter::{FilteredNetwork, PeerFilter};
+pub use multiplex::{SessionMultiplexer, SessionNetwork, SessionOpener};
+pub use raw::{CodecNetwork, FRAME_VERSION};
+pub use switch::{NetworkSwitch, SwitchableNetwork};
+
 #[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
 pub(crate) enum NetworkDataInner<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
     Units(UnitMessage<H, D, S>),
@@ -50,6 +77,9 @@ struct NetworkHub<
     units_received: Sender<UnitMessage<H, D, S>>,
     alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
     alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    bandwidth_budget: BandwidthTracker,
+    health: Health,
+    stats: Stats,
 }
 
 impl<
@@ -66,6 +96,9 @@ impl<
         units_received: Sender<UnitMessage<H, D, S>>,
         alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
         alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+        bandwidth_budget: Option<usize>,
+        health: Health,
+        stats: Stats,
     ) -> Self {
         NetworkHub {
             network,
@@ -73,25 +106,43 @@ impl<
             units_received,
             alerts_to_send,
             alerts_received,
+            bandwidth_budget: BandwidthTracker::new(bandwidth_budget),
+            health,
+            stats,
         }
     }
 
-    fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient) {
+    fn send(&mut self, data: NetworkData<H, D, S, MS>, recipient: Recipient) {
+        let size = data.encoded_size();
+        self.stats.on_sent(size);
+        match self.bandwidth_budget.record(size) {
+            Some(BudgetEvent::Exceeded { used, limit }) => {
+                warn!(target: "AlephBFT-network-hub", "Sent {} bytes within a second, exceeding the bandwidth budget of {} bytes.", used, limit);
+            }
+            Some(BudgetEvent::Recovered { used, limit }) => {
+                info!(target: "AlephBFT-network-hub", "Sent {} bytes within a second, back within the bandwidth budget of {} bytes.", used, limit);
+            }
+            None => {}
+        }
         self.network.send(data, recipient);
     }
 
     fn handle_incoming(&self, network_data: NetworkData<H, D, S, MS>) {
+        self.health.on_activity(Subsystem::Network);
+        self.stats.on_received(network_data.encoded_size());
         let NetworkData(network_data) = network_data;
         use NetworkDataInner::*;
         match network_data {
             Units(unit_message) => {
                 if let Err(e) = self.units_received.unbounded_send(unit_message) {
+                    self.health.on_error(Subsystem::Network);
                     warn!(target: "AlephBFT-network-hub", "Error when sending units to consensus {:?}", e);
                 }
             }
 
             Alert(alert_message) => {
                 if let Err(e) = self.alerts_received.unbounded_send(alert_message) {
+                    self.health.on_error(Subsystem::Network);
                     warn!(target: "AlephBFT-network-hub", "Error when sending alerts to consensus {:?}", e);
                 }
             }
@@ -146,6 +197,9 @@ pub(crate) async fn run<
     units_received: Sender<UnitMessage<H, D, S>>,
     alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
     alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    bandwidth_budget: Option<usize>,
+    health: Health,
+    stats: Stats,
     terminator: Terminator,
 ) {
     NetworkHub::new(
@@ -154,6 +208,9 @@ pub(crate) async fn run<
         units_received,
         alerts_to_send,
         alerts_received,
+        bandwidth_budget,
+        health,
+        stats,
     )
     .run(terminator)
     .await
@@ -163,10 +220,10 @@ pub(crate) async fn run<
 mod tests {
     use crate::{
         alerts::AlertMessage,
-        member::UnitMessage,
+        member::{UnitAcknowledgement, UnitMessage},
         network::NetworkDataInner::{Alert, Units},
         units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, UnitCoord},
-        Hasher, NodeIndex, NodeSubset, Round, Signed,
+        Hasher, NodeIndex, NodeSubset, Round, SessionId, Signed,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
     use codec::{Decode, Encode};
@@ -181,7 +238,7 @@ mod tests {
             combined_hash: 0.using_encoded(Hasher64::hash),
         };
         let pu = PreUnit::new(creator, round, control_hash);
-        let signable = FullUnit::new(pu, Some(data), 0);
+        let signable = FullUnit::new(pu, Some(data), SessionId::from_number(0));
         Signed::sign(signable, &Keychain::new(0.into(), creator))
             .await
             .into_unchecked()
@@ -249,7 +306,12 @@ mod tests {
 
         let uu = test_unchecked_unit(5.into(), 43, 1729).await;
         let included_data = uu.as_signable().included_data();
-        let nd = TestNetworkData::new(Units(ResponseCoord(uu.clone())));
+        let frontier = test_unchecked_unit(6.into(), 44, 1730).await;
+        let included_data: Vec<Data> = included_data
+            .into_iter()
+            .chain(frontier.as_signable().included_data().into_iter())
+            .collect();
+        let nd = TestNetworkData::new(Units(ResponseCoord(uu.clone(), Some(frontier.clone()))));
         let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
         assert!(decoded.is_ok(), "Bug in encode/decode for ResponseCoord");
         let decoded = decoded.unwrap();
@@ -258,12 +320,17 @@ mod tests {
             included_data,
             "data decoded incorrectly"
         );
-        if let Units(ResponseCoord(decoded_unchecked)) = decoded.0 {
+        if let Units(ResponseCoord(decoded_unchecked, decoded_frontier)) = decoded.0 {
             assert_eq!(
                 uu.as_signable(),
                 decoded_unchecked.as_signable(),
                 "decoded should equal encoded"
             );
+            assert_eq!(
+                Some(frontier.as_signable()),
+                decoded_frontier.as_ref().map(|uu| uu.as_signable()),
+                "decoded should equal encoded"
+            );
         } else {
             panic!("Decoded ResponseCoord as something else");
         }
@@ -290,6 +357,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn decoding_network_data_units_unit_ack() {
+        use UnitMessage::UnitAck;
+
+        let ni = 7.into();
+        let h = 43.using_encoded(Hasher64::hash);
+        let ack = UnitAcknowledgement::new(ni, h);
+        let ack = Signed::sign(ack, &Keychain::new(0.into(), ni))
+            .await
+            .into_unchecked();
+        let nd = TestNetworkData::new(Units(UnitAck(ack.clone())));
+        let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
+        assert!(decoded.is_ok(), "Bug in encode/decode for UnitAck");
+        let decoded = decoded.unwrap();
+        assert!(
+            decoded.included_data().is_empty(),
+            "data returned from an acknowledgement"
+        );
+        if let Units(UnitAck(decoded_ack)) = decoded.0 {
+            assert!(ack == decoded_ack, "decoded should equal encoded");
+        } else {
+            panic!("Decoded UnitAck as something else");
+        }
+    }
+
     #[tokio::test]
     async fn decoding_network_data_units_response_parents() {
         use UnitMessage::ResponseParents;
@@ -307,7 +399,7 @@ mod tests {
             .collect();
         let parents = vec![p1, p2, p3];
 
-        let nd = TestNetworkData::new(Units(ResponseParents(h, parents.clone())));
+        let nd = TestNetworkData::new(Units(ResponseParents(h, parents.clone(), None)));
         let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
         assert!(decoded.is_ok(), "Bug in encode/decode for ResponseParents");
         let decoded = decoded.unwrap();
@@ -316,8 +408,9 @@ mod tests {
             included_data,
             "data decoded incorrectly"
         );
-        if let Units(ResponseParents(dh, dparents)) = decoded.0 {
+        if let Units(ResponseParents(dh, dparents, dfrontier)) = decoded.0 {
             assert_eq!(h, dh, "decoded should equal encoded");
+            assert!(dfrontier.is_none(), "decoded should equal encoded");
             assert_eq!(
                 parents.len(),
                 dparents.len(),
@@ -0,0 +1,208 @@
+use super::raw::frame_header;
+use crate::{RawNetwork, Receiver, Recipient, Sender, SessionId};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+
+type Sessions = Arc<Mutex<HashMap<SessionId, Sender<Vec<u8>>>>>;
+
+/// Multiplexes the traffic of concurrent sessions over a single [`RawNetwork`], so that
+/// overlapping sessions share one connection per peer instead of each opening their own.
+///
+/// Frames produced by [`CodecNetwork`](crate::CodecNetwork) are tagged with the session id, which
+/// is used to route incoming frames to the right session. Frames for sessions that are not open
+/// are dropped. The multiplexer does its work in [`SessionMultiplexer::run`], which should be
+/// spawned, and sessions are opened using [`SessionOpener`].
+pub struct SessionMultiplexer<N: RawNetwork> {
+    raw: N,
+    sessions: Sessions,
+    outgoing: Receiver<(Vec<u8>, Recipient)>,
+}
+
+/// Opens sessions multiplexed by a [`SessionMultiplexer`].
+#[derive(Clone)]
+pub struct SessionOpener {
+    sessions: Sessions,
+    outgoing: Sender<(Vec<u8>, Recipient)>,
+}
+
+/// The [`RawNetwork`] of a single multiplexed session. It should be wrapped in a
+/// [`CodecNetwork`](crate::CodecNetwork) with the same session id. The session is closed on drop.
+pub struct SessionNetwork {
+    session_id: SessionId,
+    sessions: Sessions,
+    outgoing: Sender<(Vec<u8>, Recipient)>,
+    incoming: Receiver<Vec<u8>>,
+}
+
+impl<N: RawNetwork> SessionMultiplexer<N> {
+    pub fn new(raw: N) -> (Self, SessionOpener) {
+        let sessions = Arc::new(Mutex::new(HashMap::new()));
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded();
+        let multiplexer = SessionMultiplexer {
+            raw,
+            sessions: sessions.clone(),
+            outgoing: outgoing_rx,
+        };
+        let opener = SessionOpener {
+            sessions,
+            outgoing: outgoing_tx,
+        };
+        (multiplexer, opener)
+    }
+
+    fn route(&self, frame: Vec<u8>) {
+        let session_id = match frame_header(&frame) {
+            Some((_, session_id)) => session_id,
+            None => {
+                warn!(target: "AlephBFT-network-multiplexer", "Received a malformed frame.");
+                return;
+            }
+        };
+        let mut sessions = self.sessions.lock();
+        match sessions.get(&session_id) {
+            Some(session) => {
+                if session.unbounded_send(frame).is_err() {
+                    debug!(target: "AlephBFT-network-multiplexer", "Session {:?} stopped receiving.", session_id);
+                    sessions.remove(&session_id);
+                }
+            }
+            None => {
+                debug!(target: "AlephBFT-network-multiplexer", "Received a frame for session {:?}, which is not open.", session_id);
+            }
+        }
+    }
+
+    /// Moves frames between the raw network and the sessions. Finishes when the raw network
+    /// stops working, or when all sessions and openers are dropped.
+    pub async fn run(mut self) {
+        loop {
+            futures::select! {
+                outgoing = self.outgoing.next() => match outgoing {
+                    Some((frame, recipient)) => self.raw.send(frame, recipient),
+                    None => {
+                        debug!(target: "AlephBFT-network-multiplexer", "All sessions closed.");
+                        break;
+                    }
+                },
+                incoming = self.raw.next_event().fuse() => match incoming {
+                    Some(frame) => self.route(frame),
+                    None => {
+                        warn!(target: "AlephBFT-network-multiplexer", "Raw network stopped working.");
+                        break;
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl SessionOpener {
+    /// Opens the session with the given id, unless it is already open.
+    pub fn open(&self, session_id: SessionId) -> Option<SessionNetwork> {
+        let mut sessions = self.sessions.lock();
+        if sessions.contains_key(&session_id) {
+            return None;
+        }
+        let (incoming_tx, incoming_rx) = mpsc::unbounded();
+        sessions.insert(session_id, incoming_tx);
+        Some(SessionNetwork {
+            session_id,
+            sessions: self.sessions.clone(),
+            outgoing: self.outgoing.clone(),
+            incoming: incoming_rx,
+        })
+    }
+}
+
+impl Drop for SessionNetwork {
+    fn drop(&mut self) {
+        self.sessions.lock().remove(&self.session_id);
+    }
+}
+
+#[async_trait::async_trait]
+impl RawNetwork for SessionNetwork {
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        if self.outgoing.unbounded_send((frame, recipient)).is_err() {
+            warn!(target: "AlephBFT-network-multiplexer", "Session {:?} sending after the multiplexer stopped.", self.session_id);
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        self.incoming.next().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionMultiplexer;
+    use crate::{CodecNetwork, Network, RawNetwork, Recipient, SessionId};
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+        StreamExt,
+    };
+
+    struct LoopbackRawNetwork {
+        tx: UnboundedSender<Vec<u8>>,
+        rx: UnboundedReceiver<Vec<u8>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RawNetwork for LoopbackRawNetwork {
+        fn send(&self, frame: Vec<u8>, _recipient: Recipient) {
+            self.tx
+                .unbounded_send(frame)
+                .expect("channel should be open");
+        }
+
+        async fn next_event(&mut self) -> Option<Vec<u8>> {
+            self.rx.next().await
+        }
+    }
+
+    fn multiplexer() -> (SessionMultiplexer<LoopbackRawNetwork>, super::SessionOpener) {
+        let (tx, rx) = unbounded();
+        SessionMultiplexer::new(LoopbackRawNetwork { tx, rx })
+    }
+
+    #[tokio::test]
+    async fn routes_frames_to_sessions() {
+        let (multiplexer, opener) = multiplexer();
+        let handle = tokio::spawn(multiplexer.run());
+
+        let mut first = CodecNetwork::<u32, _>::new(
+            opener
+                .open(SessionId::from_number(1))
+                .expect("not open yet"),
+            SessionId::from_number(1),
+        );
+        let mut second = CodecNetwork::<u32, _>::new(
+            opener
+                .open(SessionId::from_number(2))
+                .expect("not open yet"),
+            SessionId::from_number(2),
+        );
+        first.send(43, Recipient::Everyone);
+        second.send(1729, Recipient::Everyone);
+
+        assert_eq!(second.next_event().await, Some(1729));
+        assert_eq!(first.next_event().await, Some(43));
+
+        drop(first);
+        drop(second);
+        drop(opener);
+        handle.await.expect("multiplexer should finish");
+    }
+
+    #[test]
+    fn opens_session_once() {
+        let (_multiplexer, opener) = multiplexer();
+        let session = opener.open(SessionId::from_number(1));
+        assert!(session.is_some());
+        assert!(opener.open(SessionId::from_number(1)).is_none());
+        drop(session);
+        assert!(opener.open(SessionId::from_number(1)).is_some());
+    }
+}
@@ -0,0 +1,620 @@
+//! A transport over QUIC, for deployments over wide area networks.
+//!
+//! [`QuicNetwork`] is a [`RawNetwork`] working like the [TCP transport](crate::tcp): nodes are at
+//! their addresses from [`Config::addresses`], dial each other once there is something to send,
+//! and both nodes prove their identity when connecting.
+//! Unlike over TCP, the frames of every logical channel of the session, i.e. units, requests and
+//! alerts, are sent over a separate stream of the connection, so a packet lost on a congested
+//! link only holds up the frames of its own channel, rather than all the frames sent after it.
+use super::{
+    stream::{
+        authenticate, read_frame, write_frame, HANDSHAKE_TIMEOUT, INITIAL_BACKOFF, MAX_BACKOFF,
+        MAX_QUEUED_FRAMES,
+    },
+    tls::{certificate, client_config, BINDING_SIZE, EXPORTER_LABEL, SERVER_NAME},
+};
+use crate::{Config, Keychain, NodeIndex, RawNetwork, Recipient, SessionId};
+use codec::{Compact, Decode};
+use futures::{channel::oneshot, future::Shared, FutureExt};
+use log::{debug, info, warn};
+use quinn::{
+    ClientConfig, Connecting, Connection, Endpoint, EndpointConfig, RecvStream, ServerConfig,
+    TokioRuntime, TransportConfig, VarInt,
+};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    time::{sleep, timeout},
+};
+
+pub use super::stream::MAX_FRAME_SIZE;
+
+// Keeps idle connections open, so that they do not have to be made again with every burst.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+// The variants of messages, as encoded at the start of the payload of a frame made by a
+// `CodecNetwork` out of `NetworkData`, see `NetworkDataInner` and `UnitMessage`. The tests fail
+// once they get out of date.
+const UNITS: u8 = 0;
+const ALERT: u8 = 1;
+const REQUEST_COORD: u8 = 1;
+const REQUEST_PARENTS: u8 = 3;
+const REQUEST_NEWEST: u8 = 5;
+
+/// The logical channels of a session, each sent over its own stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Channel {
+    Units,
+    Requests,
+    Alerts,
+}
+
+const CHANNEL_COUNT: usize = 3;
+
+impl Channel {
+    // Frames that are not made out of `NetworkData` all go over the units channel.
+    fn of_frame(frame: &[u8]) -> Channel {
+        let mut payload = frame;
+        if <(u16, SessionId, Compact<u32>)>::decode(&mut payload).is_err() {
+            return Channel::Units;
+        }
+        match payload {
+            [ALERT, ..] => Channel::Alerts,
+            [UNITS, REQUEST_COORD | REQUEST_PARENTS | REQUEST_NEWEST, ..] => Channel::Requests,
+            _ => Channel::Units,
+        }
+    }
+}
+
+/// Reasons the QUIC transport could not be started.
+#[derive(Debug)]
+pub enum QuicError {
+    /// The [`Config::addresses`] do not contain exactly one address per member, but this many.
+    WrongAddressCount(usize),
+    /// We could not listen at our address.
+    Bind(io::Error),
+    /// We could not make the TLS configuration of the connections.
+    Tls(String),
+}
+
+impl Display for QuicError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            QuicError::WrongAddressCount(count) => {
+                write!(f, "expected an address of every member, got {}", count)
+            }
+            QuicError::Bind(e) => write!(f, "failed to listen at our address: {}", e),
+            QuicError::Tls(e) => write!(f, "failed to configure TLS: {}", e),
+        }
+    }
+}
+
+impl Error for QuicError {}
+
+fn tls_error<E: Display>(e: E) -> QuicError {
+    QuicError::Tls(e.to_string())
+}
+
+fn endpoint_configs() -> Result<(ServerConfig, ClientConfig), QuicError> {
+    let (certificate, key) = certificate().map_err(QuicError::Tls)?;
+    let mut server_config =
+        ServerConfig::with_single_cert(vec![certificate], key).map_err(tls_error)?;
+    let mut server_transport = TransportConfig::default();
+    // The listening node opens the stream of the handshake, the dialing one a stream per channel.
+    server_transport.max_concurrent_bidi_streams(VarInt::from_u32(0));
+    server_transport.max_concurrent_uni_streams(VarInt::from_u32(CHANNEL_COUNT as u32));
+    server_config.transport_config(Arc::new(server_transport));
+
+    let crypto = client_config().map_err(QuicError::Tls)?;
+    let mut client_config = ClientConfig::new(Arc::new(crypto));
+    let mut client_transport = TransportConfig::default();
+    client_transport.max_concurrent_bidi_streams(VarInt::from_u32(1));
+    client_transport.max_concurrent_uni_streams(VarInt::from_u32(0));
+    client_transport.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    client_config.transport_config(Arc::new(client_transport));
+    Ok((server_config, client_config))
+}
+
+fn other_error<E: Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+// Keying material of the TLS session of the connection, binding the handshake to it.
+fn binding(connection: &Connection) -> io::Result<[u8; BINDING_SIZE]> {
+    let mut binding = [0; BINDING_SIZE];
+    connection
+        .export_keying_material(&mut binding, EXPORTER_LABEL, &[])
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to export keying material"))?;
+    Ok(binding)
+}
+
+// Connects to the peer, and authenticates both of us.
+async fn dial<K: Keychain>(
+    endpoint: &Endpoint,
+    address: SocketAddr,
+    peer: NodeIndex,
+    keychain: &K,
+    session_id: SessionId,
+) -> io::Result<Connection> {
+    let connection = endpoint
+        .connect(address, SERVER_NAME)
+        .map_err(other_error)?
+        .await
+        .map_err(other_error)?;
+    let binding = binding(&connection)?;
+    let (mut writer, mut reader) = connection.accept_bi().await.map_err(other_error)?;
+    let authenticated = authenticate(
+        &mut reader,
+        &mut writer,
+        keychain,
+        session_id,
+        &binding,
+        Some(peer),
+    )
+    .await?;
+    match authenticated {
+        Some(_) => Ok(connection),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the listening node failed to authenticate",
+        )),
+    }
+}
+
+// Authenticates both of us, returns `None` if the dialing node fails to.
+async fn accept<K: Keychain>(
+    connection: &Connection,
+    keychain: &K,
+    session_id: SessionId,
+) -> io::Result<Option<NodeIndex>> {
+    let binding = binding(connection)?;
+    let (mut writer, mut reader) = connection.open_bi().await.map_err(other_error)?;
+    authenticate(
+        &mut reader,
+        &mut writer,
+        keychain,
+        session_id,
+        &binding,
+        None,
+    )
+    .await
+}
+
+async fn receive_channel(
+    peer: NodeIndex,
+    connection: Connection,
+    mut stream: RecvStream,
+    frames: Sender<Vec<u8>>,
+    exit: Shared<oneshot::Receiver<()>>,
+) {
+    loop {
+        tokio::select! {
+            _ = exit.clone() => return,
+            frame = read_frame(&mut stream, MAX_FRAME_SIZE) => match frame {
+                Ok(frame) => {
+                    if frames.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    info!(target: "AlephBFT-network-quic", "Connection from {:?} closed: {}.", peer, e);
+                    connection.close(VarInt::from_u32(0), b"");
+                    return;
+                }
+            },
+        }
+    }
+}
+
+async fn receive<K: Keychain>(
+    connecting: Connecting,
+    keychain: K,
+    session_id: SessionId,
+    frames: Sender<Vec<u8>>,
+    exit: Shared<oneshot::Receiver<()>>,
+) {
+    let address = connecting.remote_address();
+    let connection = match connecting.await {
+        Ok(connection) => connection,
+        Err(e) => {
+            debug!(target: "AlephBFT-network-quic", "Failed to accept a connection from {}: {}.", address, e);
+            return;
+        }
+    };
+    let peer = match timeout(
+        HANDSHAKE_TIMEOUT,
+        accept(&connection, &keychain, session_id),
+    )
+    .await
+    {
+        Ok(Ok(Some(peer))) => peer,
+        Ok(Ok(None)) => {
+            warn!(target: "AlephBFT-network-quic", "A connection from {} failed to authenticate.", address);
+            return;
+        }
+        Ok(Err(e)) => {
+            debug!(target: "AlephBFT-network-quic", "Handshake with a dialing node failed: {}.", e);
+            return;
+        }
+        Err(_) => {
+            debug!(target: "AlephBFT-network-quic", "Handshake with a dialing node timed out.");
+            return;
+        }
+    };
+    info!(target: "AlephBFT-network-quic", "Accepted a connection from {:?}.", peer);
+    loop {
+        tokio::select! {
+            _ = exit.clone() => return,
+            stream = connection.accept_uni() => match stream {
+                Ok(stream) => {
+                    tokio::spawn(receive_channel(
+                        peer,
+                        connection.clone(),
+                        stream,
+                        frames.clone(),
+                        exit.clone(),
+                    ));
+                }
+                Err(e) => {
+                    info!(target: "AlephBFT-network-quic", "Connection from {:?} closed: {}.", peer, e);
+                    return;
+                }
+            },
+        }
+    }
+}
+
+async fn listen<K: Keychain>(
+    endpoint: Endpoint,
+    keychain: K,
+    session_id: SessionId,
+    frames: Sender<Vec<u8>>,
+    exit: Shared<oneshot::Receiver<()>>,
+) {
+    loop {
+        tokio::select! {
+            _ = exit.clone() => return,
+            connecting = endpoint.accept() => match connecting {
+                Some(connecting) => {
+                    tokio::spawn(receive(
+                        connecting,
+                        keychain.clone(),
+                        session_id,
+                        frames.clone(),
+                        exit.clone(),
+                    ));
+                }
+                None => return,
+            },
+        }
+    }
+}
+
+// Ends once the connection breaks, dropping the frames sent afterwards.
+async fn send_channel(peer: NodeIndex, connection: Connection, mut frames: Receiver<Vec<u8>>) {
+    let mut stream = match connection.open_uni().await {
+        Ok(stream) => stream,
+        Err(e) => {
+            debug!(target: "AlephBFT-network-quic", "Failed to open a stream to {:?}: {}.", peer, e);
+            return;
+        }
+    };
+    while let Some(frame) = frames.recv().await {
+        if let Err(e) = write_frame(&mut stream, &frame).await {
+            info!(target: "AlephBFT-network-quic", "Connection to {:?} lost: {}.", peer, e);
+            return;
+        }
+    }
+}
+
+async fn send_to<K: Keychain>(
+    peer: NodeIndex,
+    address: SocketAddr,
+    endpoint: Endpoint,
+    keychain: K,
+    session_id: SessionId,
+    mut frames: Receiver<Vec<u8>>,
+) {
+    // The connection together with the frames for each of its channels.
+    let mut connection: Option<(Connection, Vec<Sender<Vec<u8>>>)> = None;
+    let mut backoff = INITIAL_BACKOFF;
+    while let Some(frame) = frames.recv().await {
+        if connection
+            .as_ref()
+            .map_or(true, |(connection, _)| connection.close_reason().is_some())
+        {
+            let dialed = timeout(
+                HANDSHAKE_TIMEOUT,
+                dial(&endpoint, address, peer, &keychain, session_id),
+            )
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()));
+            match dialed {
+                Ok(dialed) => {
+                    info!(target: "AlephBFT-network-quic", "Connected to {:?}.", peer);
+                    backoff = INITIAL_BACKOFF;
+                    let channels = (0..CHANNEL_COUNT)
+                        .map(|_| {
+                            let (channel_tx, channel_rx) = mpsc::channel(MAX_QUEUED_FRAMES);
+                            tokio::spawn(send_channel(peer, dialed.clone(), channel_rx));
+                            channel_tx
+                        })
+                        .collect();
+                    connection = Some((dialed, channels));
+                }
+                Err(e) => {
+                    debug!(target: "AlephBFT-network-quic", "Failed to connect to {:?}: {}.", peer, e);
+                    connection = None;
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    // Frames queued in the meantime are lost, like the one we failed to send.
+                    while frames.try_recv().is_ok() {}
+                    continue;
+                }
+            }
+        }
+        let (_, channels) = connection.as_ref().expect("we are connected");
+        // If the connection broke the frame is lost, like the ones we drop here.
+        if let Err(TrySendError::Full(_)) =
+            channels[Channel::of_frame(&frame) as usize].try_send(frame)
+        {
+            debug!(target: "AlephBFT-network-quic", "Dropping a frame to {:?}, too many are queued.", peer);
+        }
+    }
+}
+
+fn queue(peer: NodeIndex, frames: &Sender<Vec<u8>>, frame: Vec<u8>) {
+    // The connections end only together with the network.
+    if let Err(TrySendError::Full(_)) = frames.try_send(frame) {
+        debug!(target: "AlephBFT-network-quic", "Dropping a frame to {:?}, too many are queued.", peer);
+    }
+}
+
+/// A [`RawNetwork`] over QUIC connections between all the members, see the [module
+/// documentation](self). Has to be created within a Tokio runtime, which runs the connections
+/// until it is dropped.
+pub struct QuicNetwork {
+    // The frames for every peer, `None` for ourselves.
+    peers: Vec<Option<Sender<Vec<u8>>>>,
+    frames: Receiver<Vec<u8>>,
+    _exit: oneshot::Sender<()>,
+}
+
+impl QuicNetwork {
+    /// Listens at our address from [`Config::addresses`], signing the handshakes with the
+    /// keychain.
+    pub fn new<K: Keychain>(config: &Config, keychain: K) -> Result<Self, QuicError> {
+        if config.addresses.len() != config.n_members.0 {
+            return Err(QuicError::WrongAddressCount(config.addresses.len()));
+        }
+        let socket =
+            UdpSocket::bind(config.addresses[config.node_ix.0]).map_err(QuicError::Bind)?;
+        Self::with_socket(config, keychain, socket)
+    }
+
+    /// Uses the socket rather than one bound to our address, e.g. when the address the other
+    /// nodes know differs from the local one, behind a NAT.
+    pub fn with_socket<K: Keychain>(
+        config: &Config,
+        keychain: K,
+        socket: UdpSocket,
+    ) -> Result<Self, QuicError> {
+        if config.addresses.len() != config.n_members.0 {
+            return Err(QuicError::WrongAddressCount(config.addresses.len()));
+        }
+        let (server_config, client_config) = endpoint_configs()?;
+        let mut endpoint = Endpoint::new(
+            EndpointConfig::default(),
+            Some(server_config),
+            socket,
+            Arc::new(TokioRuntime),
+        )
+        .map_err(QuicError::Bind)?;
+        endpoint.set_default_client_config(client_config);
+        let session_id = config.session_id;
+        let (exit_tx, exit) = oneshot::channel();
+        let exit = exit.shared();
+        let (frames_tx, frames) = mpsc::channel(MAX_QUEUED_FRAMES);
+        tokio::spawn(listen(
+            endpoint.clone(),
+            keychain.clone(),
+            session_id,
+            frames_tx,
+            exit,
+        ));
+        let peers = config
+            .addresses
+            .iter()
+            .enumerate()
+            .map(|(index, address)| {
+                let peer = NodeIndex(index);
+                if peer == keychain.index() {
+                    return None;
+                }
+                let (peer_tx, peer_rx) = mpsc::channel(MAX_QUEUED_FRAMES);
+                tokio::spawn(send_to(
+                    peer,
+                    *address,
+                    endpoint.clone(),
+                    keychain.clone(),
+                    session_id,
+                    peer_rx,
+                ));
+                Some(peer_tx)
+            })
+            .collect();
+        Ok(QuicNetwork {
+            peers,
+            frames,
+            _exit: exit_tx,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl RawNetwork for QuicNetwork {
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        if frame.len() > MAX_FRAME_SIZE {
+            debug!(target: "AlephBFT-network-quic", "Not sending a frame of {} bytes, too large.", frame.len());
+            return;
+        }
+        match recipient {
+            Recipient::Everyone => {
+                for (index, peer) in self.peers.iter().enumerate() {
+                    if let Some(peer) = peer {
+                        queue(NodeIndex(index), peer, frame.clone());
+                    }
+                }
+            }
+            Recipient::Node(node) => match self.peers.get(node.0) {
+                Some(Some(peer)) => queue(node, peer, frame),
+                _ => {
+                    debug!(target: "AlephBFT-network-quic", "Not sending a frame to {:?}, not a peer.", node)
+                }
+            },
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        self.frames.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, QuicError, QuicNetwork};
+    use crate::{
+        alerts::AlertMessage,
+        member::{
+            DuplicateUnitHint, HistoryFloor, TopRoundsReport, UnitAcknowledgement, UnitMessage,
+        },
+        network::{NetworkData, NetworkDataInner},
+        runway::NewestUnitResponse,
+        testing::gen_config,
+        units::{preunit_to_unchecked_signed_unit, ControlHash, PreUnit, UnitCoord},
+        Hasher, NodeCount, NodeIndex, NodeMap, NodeSubset, RawNetwork, Recipient, SessionId,
+        Signed, FRAME_VERSION,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
+    use codec::Encode;
+    use std::net::UdpSocket;
+
+    type TestNetworkData = NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+
+    fn frame(data: NetworkDataInner<Hasher64, Data, Signature, PartialMultisignature>) -> Vec<u8> {
+        let data: TestNetworkData = NetworkData(data);
+        (FRAME_VERSION, SessionId::from_number(0), data.encode()).encode()
+    }
+
+    // Fails to compile once a variant is added, so that its channel gets decided here.
+    fn channel_of(message: &UnitMessage<Hasher64, Data, Signature>) -> Channel {
+        use UnitMessage::*;
+        match message {
+            RequestCoord(..) | RequestParents(..) | RequestNewest(..) => Channel::Requests,
+            NewUnit(..) | ResponseCoord(..) | ResponseParents(..) | ResponseNewest(..)
+            | UnitAck(..) | TopRounds(..) | DuplicateHint(..) | HistoryPruned(..) => Channel::Units,
+        }
+    }
+
+    #[tokio::test]
+    async fn sorts_frames_into_channels() {
+        use UnitMessage::*;
+
+        let n_members = NodeCount(4);
+        let session_id = SessionId::from_number(0);
+        let keychain = Keychain::new(n_members, NodeIndex(1));
+        let control_hash = ControlHash {
+            parents_mask: NodeSubset::with_size(n_members),
+            combined_hash: 0.using_encoded(Hasher64::hash),
+        };
+        let preunit = PreUnit::new(NodeIndex(1), 0, control_hash);
+        let unit = preunit_to_unchecked_signed_unit(preunit, session_id, &keychain).await;
+        let response = NewestUnitResponse::new(NodeIndex(0), NodeIndex(1), Some(unit.clone()), 7);
+        let response = Signed::sign(response, &keychain).await.into_unchecked();
+        let hash = Hasher64::hash(&[7]);
+        let ack = UnitAcknowledgement::new(NodeIndex(1), hash);
+        let ack = Signed::sign(ack, &keychain).await.into_unchecked();
+        let rounds = NodeMap::with_size(n_members);
+        let hint = DuplicateUnitHint::new(NodeIndex(1), hash, rounds.clone());
+        let hint = Signed::sign(hint, &keychain).await.into_unchecked();
+        let report = TopRoundsReport::new(NodeIndex(1), rounds);
+        let report = Signed::sign(report, &keychain).await.into_unchecked();
+        let floor = HistoryFloor::new(NodeIndex(1), 5);
+        let floor = Signed::sign(floor, &keychain).await.into_unchecked();
+        // Every variant, so that reordering them fails the test.
+        let messages = vec![
+            NewUnit(unit.clone()),
+            RequestCoord(NodeIndex(0), UnitCoord::new(3, NodeIndex(1))),
+            ResponseCoord(unit.clone(), None),
+            RequestParents(NodeIndex(0), hash),
+            ResponseParents(hash, vec![unit.clone()], Some(unit)),
+            RequestNewest(NodeIndex(0), 43),
+            ResponseNewest(response),
+            UnitAck(ack),
+            TopRounds(report),
+            DuplicateHint(hint),
+            HistoryPruned(floor),
+        ];
+        for message in messages {
+            let expected = channel_of(&message);
+            assert_eq!(
+                Channel::of_frame(&frame(NetworkDataInner::Units(message))),
+                expected
+            );
+        }
+        let alert = AlertMessage::AlertRequest(NodeIndex(0), hash);
+        assert_eq!(
+            Channel::of_frame(&frame(NetworkDataInner::Alert(alert))),
+            Channel::Alerts
+        );
+        assert_eq!(Channel::of_frame(&[1, 2, 3]), Channel::Units);
+    }
+
+    #[tokio::test]
+    async fn requires_an_address_of_every_member() {
+        let n_members = NodeCount(3);
+        let config = gen_config(NodeIndex(0), n_members);
+        let keychain = Keychain::new(n_members, NodeIndex(0));
+        assert!(matches!(
+            QuicNetwork::new(&config, keychain),
+            Err(QuicError::WrongAddressCount(0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delivers_frames() {
+        let n_members = NodeCount(3);
+        let mut sockets = Vec::new();
+        let mut addresses = Vec::new();
+        for _ in 0..n_members.0 {
+            let socket = UdpSocket::bind("127.0.0.1:0").expect("binds a local port");
+            addresses.push(socket.local_addr().expect("is bound"));
+            sockets.push(socket);
+        }
+        let mut networks = Vec::new();
+        for (index, socket) in sockets.into_iter().enumerate() {
+            let mut config = gen_config(NodeIndex(index), n_members);
+            config.addresses = addresses.clone();
+            let keychain = Keychain::new(n_members, NodeIndex(index));
+            networks.push(
+                QuicNetwork::with_socket(&config, keychain, socket)
+                    .expect("addresses are complete"),
+            );
+        }
+
+        networks[0].send(vec![7], Recipient::Everyone);
+        assert_eq!(networks[1].next_event().await, Some(vec![7]));
+        assert_eq!(networks[2].next_event().await, Some(vec![7]));
+        networks[1].send(vec![8], Recipient::Node(NodeIndex(2)));
+        networks[2].send(vec![9], Recipient::Node(NodeIndex(0)));
+        assert_eq!(networks[2].next_event().await, Some(vec![8]));
+        assert_eq!(networks[0].next_event().await, Some(vec![9]));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{
+    alloc_profiling::{measure, HotPath},
+    network::CodecPool,
+    Network, RawNetwork, Receiver, Recipient, Sender, SessionId,
+};
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use log::{debug, warn};
+use std::marker::PhantomData;
+
+/// Version of the frame format produced by [`CodecNetwork`], bumped whenever the encoding of the
+/// frames or of the messages in them changes. Frames with a different version are dropped on
+/// receipt.
+pub const FRAME_VERSION: u16 = 1;
+
+/// A single message as it is sent over a [`RawNetwork`]. The payload is length-prefixed, hence
+/// frames can also be safely concatenated when the underlying transport is a byte stream.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+struct Frame {
+    version: u16,
+    session_id: SessionId,
+    payload: Vec<u8>,
+}
+
+/// Reads the version and session id of a frame without decoding its payload.
+pub(super) fn frame_header(bytes: &[u8]) -> Option<(u16, SessionId)> {
+    <(u16, SessionId)>::decode(&mut &bytes[..]).ok()
+}
+
+fn encode_frame<D: Encode>(session_id: SessionId, data: &D) -> Vec<u8> {
+    Frame {
+        version: FRAME_VERSION,
+        session_id,
+        payload: data.encode(),
+    }
+    .encode()
+}
+
+fn decode_frame<D: Decode>(session_id: SessionId, bytes: &[u8]) -> Option<D> {
+    let frame = match Frame::decode(&mut &bytes[..]) {
+        Ok(frame) => frame,
+        Err(e) => {
+            warn!(target: "AlephBFT-network-codec", "Received a malformed frame: {:?}.", e);
+            return None;
+        }
+    };
+    if frame.version != FRAME_VERSION {
+        warn!(target: "AlephBFT-network-codec", "Received a frame with unsupported version {:?}.", frame.version);
+        return None;
+    }
+    if frame.session_id != session_id {
+        // Frames from neighbouring sessions are expected around session boundaries.
+        debug!(target: "AlephBFT-network-codec", "Received a frame for session {:?}, while in session {:?}.", frame.session_id, session_id);
+        return None;
+    }
+    match measure(HotPath::UnitDecode, || D::decode(&mut &frame.payload[..])) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            warn!(target: "AlephBFT-network-codec", "Received a frame with malformed payload: {:?}.", e);
+            None
+        }
+    }
+}
+
+struct Offload {
+    pool: CodecPool,
+    threshold: usize,
+}
+
+/// Wraps a [`RawNetwork`] into a [`Network`] for any encodable message type.
+///
+/// Outgoing messages are encoded, tagged with [`FRAME_VERSION`] and the session id, and sent as
+/// a single frame. Incoming frames that cannot be decoded, have an unknown version or belong to a
+/// different session are dropped.
+pub struct CodecNetwork<D, N: RawNetwork> {
+    raw: N,
+    session_id: SessionId,
+    offload: Option<Offload>,
+    encoded_tx: Sender<(Vec<u8>, Recipient)>,
+    encoded_rx: Receiver<(Vec<u8>, Recipient)>,
+    decoded_tx: Sender<D>,
+    decoded_rx: Receiver<D>,
+    _phantom: PhantomData<fn() -> D>,
+}
+
+enum Event<D> {
+    Frame(Option<Vec<u8>>),
+    Encoded((Vec<u8>, Recipient)),
+    Decoded(D),
+}
+
+impl<D: Encode + Decode + Send + 'static, N: RawNetwork> CodecNetwork<D, N> {
+    pub fn new(raw: N, session_id: SessionId) -> Self {
+        let (encoded_tx, encoded_rx) = mpsc::unbounded();
+        let (decoded_tx, decoded_rx) = mpsc::unbounded();
+        CodecNetwork {
+            raw,
+            session_id,
+            offload: None,
+            encoded_tx,
+            encoded_rx,
+            decoded_tx,
+            decoded_rx,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Makes messages whose encoding takes more than `threshold` bytes get encoded and decoded
+    /// on the threads of the `pool` rather than the ones of the async executor. Such messages
+    /// might be delivered out of order with respect to smaller ones, which the protocol
+    /// tolerates.
+    ///
+    /// The raw network cannot be shared with the pool, so the encoded frames are only handed to
+    /// it while [`Network::next_event`] is being polled. The wrapper has to be polled
+    /// continuously, as the member does, otherwise large messages are not sent at all.
+    pub fn enable_codec_pool(&mut self, pool: CodecPool, threshold: usize) {
+        self.offload = Some(Offload { pool, threshold });
+    }
+
+    /// Returns the wrapped raw network.
+    pub fn into_inner(self) -> N {
+        self.raw
+    }
+
+    fn on_frame(&self, bytes: Vec<u8>) -> Option<D> {
+        let session_id = self.session_id;
+        match &self.offload {
+            Some(Offload { pool, threshold }) if bytes.len() > *threshold => {
+                let decoded_tx = self.decoded_tx.clone();
+                pool.execute(move || {
+                    if let Some(data) = decode_frame(session_id, &bytes) {
+                        // We might have been dropped in the meantime.
+                        let _ = decoded_tx.unbounded_send(data);
+                    }
+                });
+                None
+            }
+            _ => decode_frame(session_id, &bytes),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Encode + Decode + Send + 'static, N: RawNetwork> Network<D> for CodecNetwork<D, N> {
+    fn send(&self, data: D, recipient: Recipient) {
+        let session_id = self.session_id;
+        match &self.offload {
+            Some(Offload { pool, threshold }) if data.encoded_size() > *threshold => {
+                let encoded_tx = self.encoded_tx.clone();
+                pool.execute(move || {
+                    let frame = encode_frame(session_id, &data);
+                    // We might have been dropped in the meantime.
+                    let _ = encoded_tx.unbounded_send((frame, recipient));
+                });
+            }
+            _ => self.raw.send(encode_frame(session_id, &data), recipient),
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<D> {
+        loop {
+            // The results of the pool are kept in channels, so they survive this future being
+            // dropped.
+            let event = futures::select! {
+                bytes = self.raw.next_event().fuse() => Event::Frame(bytes),
+                encoded = self.encoded_rx.select_next_some() => Event::Encoded(encoded),
+                decoded = self.decoded_rx.select_next_some() => Event::Decoded(decoded),
+            };
+            match event {
+                Event::Frame(bytes) => {
+                    if let Some(data) = self.on_frame(bytes?) {
+                        return Some(data);
+                    }
+                }
+                Event::Encoded((frame, recipient)) => self.raw.send(frame, recipient),
+                Event::Decoded(data) => return Some(data),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frame_header, CodecNetwork, Frame, FRAME_VERSION};
+    use crate::{network::CodecPool, Network, RawNetwork, Recipient, SessionId};
+    use codec::Encode;
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+        StreamExt,
+    };
+
+    struct LoopbackRawNetwork {
+        tx: UnboundedSender<Vec<u8>>,
+        rx: UnboundedReceiver<Vec<u8>>,
+    }
+
+    impl LoopbackRawNetwork {
+        fn new() -> Self {
+            let (tx, rx) = unbounded();
+            LoopbackRawNetwork { tx, rx }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RawNetwork for LoopbackRawNetwork {
+        fn send(&self, frame: Vec<u8>, _recipient: Recipient) {
+            self.tx
+                .unbounded_send(frame)
+                .expect("channel should be open");
+        }
+
+        async fn next_event(&mut self) -> Option<Vec<u8>> {
+            self.rx.next().await
+        }
+    }
+
+    type TestNetwork = CodecNetwork<(u32, Vec<u8>), LoopbackRawNetwork>;
+
+    #[tokio::test]
+    async fn delivers_encoded_messages() {
+        let mut network = TestNetwork::new(LoopbackRawNetwork::new(), SessionId::from_number(7));
+        network.send((43, vec![1, 7, 29]), Recipient::Everyone);
+        assert_eq!(network.next_event().await, Some((43, vec![1, 7, 29])));
+    }
+
+    #[tokio::test]
+    async fn delivers_offloaded_messages() {
+        let mut network = TestNetwork::new(LoopbackRawNetwork::new(), SessionId::from_number(7));
+        network.enable_codec_pool(CodecPool::new(1).expect("threads should start"), 16);
+        network.send((1, vec![7; 100]), Recipient::Everyone);
+        network.send((2, vec![7]), Recipient::Everyone);
+        // The small message is not delayed by the pool.
+        assert_eq!(network.next_event().await, Some((2, vec![7])));
+        assert_eq!(network.next_event().await, Some((1, vec![7; 100])));
+    }
+
+    #[tokio::test]
+    async fn drops_invalid_frames() {
+        let mut network = TestNetwork::new(LoopbackRawNetwork::new(), SessionId::from_number(7));
+        let raw = &network.raw;
+        raw.send(vec![0, 1, 2], Recipient::Everyone);
+        let wrong_version = Frame {
+            version: FRAME_VERSION + 1,
+            session_id: SessionId::from_number(7),
+            payload: (1u32, Vec::<u8>::new()).encode(),
+        };
+        raw.send(wrong_version.encode(), Recipient::Everyone);
+        let wrong_session = Frame {
+            version: FRAME_VERSION,
+            session_id: SessionId::from_number(8),
+            payload: (2u32, Vec::<u8>::new()).encode(),
+        };
+        raw.send(wrong_session.encode(), Recipient::Everyone);
+        let mut other_chain = *SessionId::from_number(7).as_bytes();
+        other_chain[31] = 1;
+        let other_chain = Frame {
+            version: FRAME_VERSION,
+            session_id: SessionId::new(other_chain),
+            payload: (2u32, Vec::<u8>::new()).encode(),
+        };
+        raw.send(other_chain.encode(), Recipient::Everyone);
+        let wrong_payload = Frame {
+            version: FRAME_VERSION,
+            session_id: SessionId::from_number(7),
+            payload: vec![3],
+        };
+        raw.send(wrong_payload.encode(), Recipient::Everyone);
+        network.send((4, vec![]), Recipient::Everyone);
+        assert_eq!(network.next_event().await, Some((4, vec![])));
+    }
+
+    #[test]
+    fn reads_frame_header() {
+        let frame = Frame {
+            version: FRAME_VERSION,
+            session_id: SessionId::from_number(7),
+            payload: vec![1, 2, 3],
+        };
+        assert_eq!(
+            frame_header(&frame.encode()),
+            Some((FRAME_VERSION, SessionId::from_number(7)))
+        );
+        assert_eq!(frame_header(&[0, 1, 2]), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Framing and authentication shared by the transports carrying frames over byte streams.
+//!
+//! Both ends of a connection introduce themselves with their index and a random nonce, then sign
+//! the two introductions together with keying material exported from the TLS session of the
+//! connection. A signature is thus only valid for the one TLS session both nodes share, so a
+//! node relaying the handshake between two others, with a session of its own to each of them,
+//! cannot reuse their signatures to pass for either.
+use crate::{Keychain, NodeIndex, SessionId};
+use codec::{Decode, Encode};
+use std::{io, time::Duration};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The largest frame sent or received. Larger frames are not sent, and a peer sending one gets
+/// disconnected.
+pub const MAX_FRAME_SIZE: usize = 32 * 1024 * 1024;
+
+// The handshake happens before the peer is authenticated, so it gets much less room.
+const MAX_HANDSHAKE_SIZE: usize = 4 * 1024;
+const HANDSHAKE_DOMAIN: &[u8] = b"AlephBFT-handshake";
+pub(super) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+pub(super) const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+pub(super) const MAX_BACKOFF: Duration = Duration::from_secs(10);
+/// The frames waiting to be sent to a peer, further ones are dropped until the connection
+/// catches up. Received frames are queued likewise, but reading stops while the queue is full.
+pub(super) const MAX_QUEUED_FRAMES: usize = 1024;
+
+#[derive(Encode, Decode)]
+struct Hello {
+    index: NodeIndex,
+    nonce: [u8; 32],
+}
+
+// Keeps a signature of the listening node from passing for one of the dialing node.
+#[derive(Clone, Copy, Encode)]
+enum Role {
+    Dialer,
+    Listener,
+}
+
+fn transcript(
+    session_id: SessionId,
+    binding: &[u8],
+    role: Role,
+    dialer: &Hello,
+    listener: &Hello,
+) -> Vec<u8> {
+    (
+        HANDSHAKE_DOMAIN,
+        session_id,
+        binding,
+        role,
+        dialer,
+        listener,
+    )
+        .encode()
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+pub(super) async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    frame: &[u8],
+) -> io::Result<()> {
+    writer
+        .write_all(&(frame.len() as u32).to_be_bytes())
+        .await?;
+    writer.write_all(frame).await?;
+    writer.flush().await
+}
+
+pub(super) async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_size: usize,
+) -> io::Result<Vec<u8>> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length).await?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > max_size {
+        return Err(invalid_data("frame too large"));
+    }
+    let mut frame = vec![0; length];
+    reader.read_exact(&mut frame).await?;
+    Ok(frame)
+}
+
+/// Authenticates the peer and proves our identity to it. The `binding` is the keying material
+/// exported from the TLS session of the connection, and `dialed` the peer we dialed, `None` if we
+/// accepted the connection. Returns the authenticated peer, `None` if it failed to authenticate.
+pub(super) async fn authenticate<K, R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    keychain: &K,
+    session_id: SessionId,
+    binding: &[u8],
+    dialed: Option<NodeIndex>,
+) -> io::Result<Option<NodeIndex>>
+where
+    K: Keychain,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let ours = Hello {
+        index: keychain.index(),
+        nonce: rand::random(),
+    };
+    write_frame(writer, &ours.encode()).await?;
+    let theirs = read_frame(reader, MAX_HANDSHAKE_SIZE).await?;
+    let theirs = match Hello::decode(&mut &theirs[..]) {
+        Ok(theirs) => theirs,
+        Err(_) => return Ok(None),
+    };
+    let expected = match dialed {
+        Some(peer) => theirs.index == peer,
+        None => theirs.index != keychain.index() && theirs.index.0 < keychain.node_count().0,
+    };
+    if !expected {
+        return Ok(None);
+    }
+    let (our_role, their_role, dialer, listener) = match dialed {
+        Some(_) => (Role::Dialer, Role::Listener, &ours, &theirs),
+        None => (Role::Listener, Role::Dialer, &theirs, &ours),
+    };
+    let signature = keychain
+        .sign(&transcript(session_id, binding, our_role, dialer, listener))
+        .await;
+    write_frame(writer, &signature.encode()).await?;
+    let signature = read_frame(reader, MAX_HANDSHAKE_SIZE).await?;
+    let signature = match K::Signature::decode(&mut &signature[..]) {
+        Ok(signature) => signature,
+        Err(_) => return Ok(None),
+    };
+    let message = transcript(session_id, binding, their_role, dialer, listener);
+    match keychain.verify(&message, &signature, theirs.index) {
+        true => Ok(Some(theirs.index)),
+        false => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, read_frame, write_frame, MAX_FRAME_SIZE};
+    use crate::{NodeCount, NodeIndex, SessionId};
+    use aleph_bft_mock::Keychain;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn frames_are_length_prefixed() {
+        let (mut writer, mut reader) = tokio::io::duplex(1024);
+        write_frame(&mut writer, &[1, 2, 3]).await.expect("writes");
+        write_frame(&mut writer, &[]).await.expect("writes");
+        assert_eq!(
+            read_frame(&mut reader, 3).await.expect("reads"),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            read_frame(&mut reader, 3).await.expect("reads"),
+            Vec::<u8>::new()
+        );
+
+        writer
+            .write_all(&(MAX_FRAME_SIZE as u32 + 1).to_be_bytes())
+            .await
+            .expect("writes");
+        assert!(read_frame(&mut reader, MAX_FRAME_SIZE).await.is_err());
+    }
+
+    async fn handshake(
+        listener: NodeIndex,
+        dialer: NodeIndex,
+        dialed: NodeIndex,
+        listener_binding: &[u8],
+        dialer_binding: &[u8],
+    ) -> (Option<NodeIndex>, Option<NodeIndex>) {
+        let n_members = NodeCount(3);
+        let session_id = SessionId::from_number(0);
+        let listener = Keychain::new(n_members, listener);
+        let dialer = Keychain::new(n_members, dialer);
+        let (listener_end, dialer_end) = tokio::io::duplex(1024);
+        // Every end is dropped once done, like a connection that failed to authenticate.
+        let accepting = async move {
+            let (mut reader, mut writer) = tokio::io::split(listener_end);
+            authenticate(
+                &mut reader,
+                &mut writer,
+                &listener,
+                session_id,
+                listener_binding,
+                None,
+            )
+            .await
+        };
+        let dialing = async move {
+            let (mut reader, mut writer) = tokio::io::split(dialer_end);
+            authenticate(
+                &mut reader,
+                &mut writer,
+                &dialer,
+                session_id,
+                dialer_binding,
+                Some(dialed),
+            )
+            .await
+        };
+        let (accepted, dialed) = futures::join!(accepting, dialing);
+        (accepted.unwrap_or(None), dialed.unwrap_or(None))
+    }
+
+    #[tokio::test]
+    async fn authenticates_both_nodes() {
+        assert_eq!(
+            handshake(NodeIndex(0), NodeIndex(1), NodeIndex(0), &[7], &[7]).await,
+            (Some(NodeIndex(1)), Some(NodeIndex(0)))
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_handshakes_of_other_sessions() {
+        // E.g. relayed by a node having a TLS session of its own with each end.
+        assert_eq!(
+            handshake(NodeIndex(0), NodeIndex(1), NodeIndex(0), &[7], &[8]).await,
+            (None, None)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_peers() {
+        assert_eq!(
+            handshake(NodeIndex(0), NodeIndex(1), NodeIndex(2), &[7], &[7]).await,
+            (None, None)
+        );
+        assert_eq!(
+            handshake(NodeIndex(0), NodeIndex(3), NodeIndex(0), &[7], &[7]).await,
+            (None, None)
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{Network, Recipient};
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    future::{select, Either},
+    StreamExt,
+};
+use log::info;
+use parking_lot::Mutex;
+use std::marker::PhantomData;
+
+/// Replaces the network wrapped by a [`SwitchableNetwork`] while the member using it runs. All
+/// clones switch the same network.
+pub struct NetworkSwitch<N> {
+    replacements: UnboundedSender<N>,
+}
+
+impl<N> Clone for NetworkSwitch<N> {
+    fn clone(&self) -> Self {
+        NetworkSwitch {
+            replacements: self.replacements.clone(),
+        }
+    }
+}
+
+impl<N> NetworkSwitch<N> {
+    /// Makes the switchable network use the given network from now on, dropping the previous
+    /// one. Returns the network back if the switchable network is gone, e.g. because the session
+    /// ended.
+    pub fn switch(&self, network: N) -> Result<(), N> {
+        self.replacements
+            .unbounded_send(network)
+            .map_err(|e| e.into_inner())
+    }
+}
+
+/// Wraps a [`Network`] so that it can be replaced through its [`NetworkSwitch`] without
+/// restarting the member, e.g. after the credentials of the transport rotated or the swarm was
+/// rebuilt. Everything sent after [`NetworkSwitch::switch`] returns goes through the new network.
+/// Messages the previous network received but did not deliver yet are dropped with it, to be
+/// resent or requested again like any lost messages.
+///
+/// When the wrapped network ends, the switchable network waits for a replacement, and ends only
+/// once all the switches are dropped too. Waiting for the next message of the wrapped network is
+/// cancelled when it gets replaced, so its `next_event` has to be cancel safe, as it is when
+/// reading from a channel.
+pub struct SwitchableNetwork<D, N: Network<D>> {
+    network: Mutex<N>,
+    replacements: Mutex<UnboundedReceiver<N>>,
+    _phantom: PhantomData<D>,
+}
+
+impl<D, N: Network<D>> SwitchableNetwork<D, N> {
+    pub fn new(network: N) -> (Self, NetworkSwitch<N>) {
+        let (replacements_tx, replacements) = unbounded();
+        (
+            SwitchableNetwork {
+                network: Mutex::new(network),
+                replacements: Mutex::new(replacements),
+                _phantom: PhantomData,
+            },
+            NetworkSwitch {
+                replacements: replacements_tx,
+            },
+        )
+    }
+
+    fn switch_to(network: &mut N, replacement: N) {
+        info!(target: "AlephBFT-network-switch", "Switched to a new network.");
+        *network = replacement;
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Send, N: Network<D>> Network<D> for SwitchableNetwork<D, N> {
+    fn send(&self, data: D, recipient: Recipient) {
+        let mut network = self.network.lock();
+        while let Ok(Some(replacement)) = self.replacements.lock().try_next() {
+            Self::switch_to(&mut network, replacement);
+        }
+        network.send(data, recipient);
+    }
+
+    async fn next_event(&mut self) -> Option<D> {
+        let network = self.network.get_mut();
+        let replacements = self.replacements.get_mut();
+        loop {
+            let event = match select(network.next_event(), replacements.next()).await {
+                Either::Left((data, _)) => Either::Left(data),
+                Either::Right((replacement, _)) => Either::Right(replacement),
+            };
+            match event {
+                Either::Left(Some(data)) => return Some(data),
+                Either::Left(None) => match replacements.next().await {
+                    Some(replacement) => Self::switch_to(network, replacement),
+                    None => return None,
+                },
+                Either::Right(Some(replacement)) => Self::switch_to(network, replacement),
+                // Nobody can switch the network anymore.
+                Either::Right(None) => return network.next_event().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchableNetwork;
+    use crate::{Network, Recipient};
+    use futures::{
+        channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+        StreamExt,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    struct TestNetwork {
+        rx: UnboundedReceiver<u32>,
+        sent: Arc<Mutex<Vec<u32>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Network<u32> for TestNetwork {
+        fn send(&self, data: u32, _recipient: Recipient) {
+            self.sent.lock().push(data);
+        }
+
+        async fn next_event(&mut self) -> Option<u32> {
+            self.rx.next().await
+        }
+    }
+
+    fn network() -> (TestNetwork, UnboundedSender<u32>, Arc<Mutex<Vec<u32>>>) {
+        let (tx, rx) = unbounded();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        (
+            TestNetwork {
+                rx,
+                sent: sent.clone(),
+            },
+            tx,
+            sent,
+        )
+    }
+
+    #[tokio::test]
+    async fn switches_networks() {
+        let (first, first_tx, first_sent) = network();
+        let (second, second_tx, second_sent) = network();
+        let (mut network, switch) = SwitchableNetwork::new(first);
+        first_tx.unbounded_send(1).expect("channel should be open");
+        assert_eq!(network.next_event().await, Some(1));
+        network.send(1, Recipient::Everyone);
+
+        assert!(switch.switch(second).is_ok());
+        network.send(2, Recipient::Everyone);
+        // The previous network is dropped.
+        assert!(first_tx.unbounded_send(3).is_err());
+        second_tx.unbounded_send(4).expect("channel should be open");
+        assert_eq!(network.next_event().await, Some(4));
+        assert_eq!(*first_sent.lock(), vec![1]);
+        assert_eq!(*second_sent.lock(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn waits_for_replacement_of_ended_network() {
+        let (first, first_tx, _) = network();
+        let (second, second_tx, _) = network();
+        let (mut network, switch) = SwitchableNetwork::new(first);
+        drop(first_tx);
+        second_tx.unbounded_send(1).expect("channel should be open");
+        let replaced = tokio::spawn(async move {
+            let data = network.next_event().await;
+            (network, data)
+        });
+        assert!(switch.switch(second).is_ok());
+        let (mut network, data) = replaced.await.expect("the task should not panic");
+        assert_eq!(data, Some(1));
+
+        drop(switch);
+        drop(second_tx);
+        assert_eq!(network.next_event().await, None);
+    }
+
+    #[tokio::test]
+    async fn returns_networks_after_ending() {
+        let (first, _first_tx, _) = network();
+        let (second, _second_tx, _) = network();
+        let (network, switch) = SwitchableNetwork::new(first);
+        drop(network);
+        assert!(switch.switch(second).is_err());
+    }
+}
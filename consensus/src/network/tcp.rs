@@ -0,0 +1,463 @@
+//! A transport over TCP, for applications without a network of their own.
+//!
+//! [`TcpNetwork`] is a [`RawNetwork`], so it carries the frames of a [`CodecNetwork`], which
+//! in turn is the [`Network`] passed to the session.
+//!
+//! Every node listens at its address from [`Config::addresses`] and dials every other node at its
+//! own, so that every pair of nodes is connected twice, each connection carrying frames one way.
+//! Connections are encrypted with TLS, and both nodes prove their identity by signing random
+//! nonces of both together with keying material of the TLS session, so only members of the
+//! committee can make us receive anything, and we send only to the members we meant to. Frames
+//! are prefixed with their length, a big-endian `u32`.
+//!
+//! Connections are made once there is something to send, and made again after they break, with
+//! exponentially growing delays between failed attempts. Frames sent while a peer is unreachable,
+//! or while too many frames for it are queued, are dropped, the protocol resends or requests them
+//! again as needed.
+//!
+//! [`CodecNetwork`]: crate::CodecNetwork
+//! [`Network`]: crate::Network
+use super::{
+    stream::{
+        authenticate, read_frame, write_frame, HANDSHAKE_TIMEOUT, INITIAL_BACKOFF, MAX_BACKOFF,
+        MAX_QUEUED_FRAMES,
+    },
+    tls::{client_config, server_config, BINDING_SIZE, EXPORTER_LABEL, SERVER_NAME},
+};
+use crate::{Config, Keychain, NodeIndex, RawNetwork, Recipient, SessionId};
+use futures::{channel::oneshot, future::Shared, FutureExt};
+use log::{debug, info, warn};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+    net::SocketAddr,
+    sync::Arc,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    time::{sleep, timeout},
+};
+use tokio_rustls::{client, server, TlsAcceptor, TlsConnector};
+
+pub use super::stream::MAX_FRAME_SIZE;
+
+/// Reasons the TCP transport could not be started.
+#[derive(Debug)]
+pub enum TcpError {
+    /// The [`Config::addresses`] do not contain exactly one address per member, but this many.
+    WrongAddressCount(usize),
+    /// We could not listen at our address.
+    Bind(io::Error),
+    /// We could not make the TLS configuration of the connections.
+    Tls(String),
+}
+
+impl Display for TcpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            TcpError::WrongAddressCount(count) => {
+                write!(f, "expected an address of every member, got {}", count)
+            }
+            TcpError::Bind(e) => write!(f, "failed to listen at our address: {}", e),
+            TcpError::Tls(e) => write!(f, "failed to configure TLS: {}", e),
+        }
+    }
+}
+
+impl Error for TcpError {}
+
+fn other_error<E: Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+// Connects to the peer, and authenticates both of us.
+async fn dial<K: Keychain>(
+    connector: &TlsConnector,
+    address: SocketAddr,
+    peer: NodeIndex,
+    keychain: &K,
+    session_id: SessionId,
+) -> io::Result<client::TlsStream<TcpStream>> {
+    let stream = TcpStream::connect(address).await?;
+    stream.set_nodelay(true)?;
+    let server_name = rustls::ServerName::try_from(SERVER_NAME).map_err(other_error)?;
+    let mut stream = connector.connect(server_name, stream).await?;
+    let mut binding = [0; BINDING_SIZE];
+    stream
+        .get_ref()
+        .1
+        .export_keying_material(&mut binding[..], EXPORTER_LABEL, None)
+        .map_err(other_error)?;
+    let authenticated = {
+        let (mut reader, mut writer) = tokio::io::split(&mut stream);
+        authenticate(
+            &mut reader,
+            &mut writer,
+            keychain,
+            session_id,
+            &binding,
+            Some(peer),
+        )
+        .await?
+    };
+    match authenticated {
+        Some(_) => Ok(stream),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the listening node failed to authenticate",
+        )),
+    }
+}
+
+// Authenticates both of us, returns `None` if the dialing node fails to.
+async fn accept<K: Keychain>(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    keychain: &K,
+    session_id: SessionId,
+) -> io::Result<(server::TlsStream<TcpStream>, Option<NodeIndex>)> {
+    let mut stream = acceptor.accept(stream).await?;
+    let mut binding = [0; BINDING_SIZE];
+    stream
+        .get_ref()
+        .1
+        .export_keying_material(&mut binding[..], EXPORTER_LABEL, None)
+        .map_err(other_error)?;
+    let peer = {
+        let (mut reader, mut writer) = tokio::io::split(&mut stream);
+        authenticate(
+            &mut reader,
+            &mut writer,
+            keychain,
+            session_id,
+            &binding,
+            None,
+        )
+        .await?
+    };
+    Ok((stream, peer))
+}
+
+async fn receive<K: Keychain>(
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+    address: SocketAddr,
+    keychain: K,
+    session_id: SessionId,
+    frames: Sender<Vec<u8>>,
+    exit: Shared<oneshot::Receiver<()>>,
+) {
+    let (mut stream, peer) = match timeout(
+        HANDSHAKE_TIMEOUT,
+        accept(&acceptor, stream, &keychain, session_id),
+    )
+    .await
+    {
+        Ok(Ok((stream, Some(peer)))) => (stream, peer),
+        Ok(Ok((_, None))) => {
+            warn!(target: "AlephBFT-network-tcp", "A connection from {} failed to authenticate.", address);
+            return;
+        }
+        Ok(Err(e)) => {
+            debug!(target: "AlephBFT-network-tcp", "Handshake with a dialing node failed: {}.", e);
+            return;
+        }
+        Err(_) => {
+            debug!(target: "AlephBFT-network-tcp", "Handshake with a dialing node timed out.");
+            return;
+        }
+    };
+    info!(target: "AlephBFT-network-tcp", "Accepted a connection from {:?}.", peer);
+    loop {
+        tokio::select! {
+            _ = exit.clone() => return,
+            frame = read_frame(&mut stream, MAX_FRAME_SIZE) => match frame {
+                Ok(frame) => {
+                    if frames.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    info!(target: "AlephBFT-network-tcp", "Connection from {:?} closed: {}.", peer, e);
+                    return;
+                }
+            },
+        }
+    }
+}
+
+async fn listen<K: Keychain>(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    keychain: K,
+    session_id: SessionId,
+    frames: Sender<Vec<u8>>,
+    exit: Shared<oneshot::Receiver<()>>,
+) {
+    loop {
+        tokio::select! {
+            _ = exit.clone() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, address)) => {
+                    tokio::spawn(receive(
+                        acceptor.clone(),
+                        stream,
+                        address,
+                        keychain.clone(),
+                        session_id,
+                        frames.clone(),
+                        exit.clone(),
+                    ));
+                }
+                Err(e) => {
+                    debug!(target: "AlephBFT-network-tcp", "Failed to accept a connection: {}.", e);
+                    // E.g. too many open files, trying again right away would not help.
+                    sleep(INITIAL_BACKOFF).await;
+                }
+            },
+        }
+    }
+}
+
+async fn send_to<K: Keychain>(
+    peer: NodeIndex,
+    address: SocketAddr,
+    connector: TlsConnector,
+    keychain: K,
+    session_id: SessionId,
+    mut frames: Receiver<Vec<u8>>,
+) {
+    let mut connection = None;
+    let mut backoff = INITIAL_BACKOFF;
+    while let Some(frame) = frames.recv().await {
+        if connection.is_none() {
+            let dialed = timeout(
+                HANDSHAKE_TIMEOUT,
+                dial(&connector, address, peer, &keychain, session_id),
+            )
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()));
+            match dialed {
+                Ok(stream) => {
+                    info!(target: "AlephBFT-network-tcp", "Connected to {:?}.", peer);
+                    backoff = INITIAL_BACKOFF;
+                    connection = Some(stream);
+                }
+                Err(e) => {
+                    debug!(target: "AlephBFT-network-tcp", "Failed to connect to {:?}: {}.", peer, e);
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    // Frames queued in the meantime are lost, like the one we failed to send.
+                    while frames.try_recv().is_ok() {}
+                    continue;
+                }
+            }
+        }
+        let stream = connection.as_mut().expect("we are connected");
+        if let Err(e) = write_frame(stream, &frame).await {
+            info!(target: "AlephBFT-network-tcp", "Connection to {:?} lost: {}.", peer, e);
+            connection = None;
+        }
+    }
+}
+
+fn queue(peer: NodeIndex, frames: &Sender<Vec<u8>>, frame: Vec<u8>) {
+    // The connections end only together with the network.
+    if let Err(TrySendError::Full(_)) = frames.try_send(frame) {
+        debug!(target: "AlephBFT-network-tcp", "Dropping a frame to {:?}, too many are queued.", peer);
+    }
+}
+
+/// A [`RawNetwork`] over TCP connections between all the members, see the [module
+/// documentation](self). Has to be created within a Tokio runtime, which runs the connections
+/// until it is dropped.
+pub struct TcpNetwork {
+    // The frames for every peer, `None` for ourselves.
+    peers: Vec<Option<Sender<Vec<u8>>>>,
+    frames: Receiver<Vec<u8>>,
+    _exit: oneshot::Sender<()>,
+}
+
+impl TcpNetwork {
+    /// Listens at our address from [`Config::addresses`], signing the handshakes with the
+    /// keychain.
+    pub async fn new<K: Keychain>(config: &Config, keychain: K) -> Result<Self, TcpError> {
+        if config.addresses.len() != config.n_members.0 {
+            return Err(TcpError::WrongAddressCount(config.addresses.len()));
+        }
+        let listener = TcpListener::bind(config.addresses[config.node_ix.0])
+            .await
+            .map_err(TcpError::Bind)?;
+        Self::with_listener(config, keychain, listener)
+    }
+
+    /// Accepts connections from the listener rather than listening at our address, e.g. when
+    /// the address the other nodes know differs from the local one, behind a NAT.
+    pub fn with_listener<K: Keychain>(
+        config: &Config,
+        keychain: K,
+        listener: TcpListener,
+    ) -> Result<Self, TcpError> {
+        if config.addresses.len() != config.n_members.0 {
+            return Err(TcpError::WrongAddressCount(config.addresses.len()));
+        }
+        let acceptor = TlsAcceptor::from(Arc::new(server_config().map_err(TcpError::Tls)?));
+        let connector = TlsConnector::from(Arc::new(client_config().map_err(TcpError::Tls)?));
+        let session_id = config.session_id;
+        let (exit_tx, exit) = oneshot::channel();
+        let exit = exit.shared();
+        let (frames_tx, frames) = mpsc::channel(MAX_QUEUED_FRAMES);
+        tokio::spawn(listen(
+            listener,
+            acceptor,
+            keychain.clone(),
+            session_id,
+            frames_tx,
+            exit,
+        ));
+        let peers = config
+            .addresses
+            .iter()
+            .enumerate()
+            .map(|(index, address)| {
+                let peer = NodeIndex(index);
+                if peer == keychain.index() {
+                    return None;
+                }
+                let (peer_tx, peer_rx) = mpsc::channel(MAX_QUEUED_FRAMES);
+                tokio::spawn(send_to(
+                    peer,
+                    *address,
+                    connector.clone(),
+                    keychain.clone(),
+                    session_id,
+                    peer_rx,
+                ));
+                Some(peer_tx)
+            })
+            .collect();
+        Ok(TcpNetwork {
+            peers,
+            frames,
+            _exit: exit_tx,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl RawNetwork for TcpNetwork {
+    fn send(&self, frame: Vec<u8>, recipient: Recipient) {
+        if frame.len() > MAX_FRAME_SIZE {
+            debug!(target: "AlephBFT-network-tcp", "Not sending a frame of {} bytes, too large.", frame.len());
+            return;
+        }
+        match recipient {
+            Recipient::Everyone => {
+                for (index, peer) in self.peers.iter().enumerate() {
+                    if let Some(peer) = peer {
+                        queue(NodeIndex(index), peer, frame.clone());
+                    }
+                }
+            }
+            Recipient::Node(node) => match self.peers.get(node.0) {
+                Some(Some(peer)) => queue(node, peer, frame),
+                _ => {
+                    debug!(target: "AlephBFT-network-tcp", "Not sending a frame to {:?}, not a peer.", node)
+                }
+            },
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<Vec<u8>> {
+        self.frames.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TcpError, TcpNetwork};
+    use crate::{testing::gen_config, NodeCount, NodeIndex, RawNetwork, Recipient};
+    use aleph_bft_mock::Keychain;
+    use std::time::Duration;
+    use tokio::{net::TcpListener, time::timeout};
+
+    #[tokio::test]
+    async fn requires_an_address_of_every_member() {
+        let n_members = NodeCount(3);
+        let config = gen_config(NodeIndex(0), n_members);
+        let keychain = Keychain::new(n_members, NodeIndex(0));
+        assert!(matches!(
+            TcpNetwork::new(&config, keychain).await,
+            Err(TcpError::WrongAddressCount(0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delivers_frames() {
+        let n_members = NodeCount(3);
+        let mut listeners = Vec::new();
+        let mut addresses = Vec::new();
+        for _ in 0..n_members.0 {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("binds a local port");
+            addresses.push(listener.local_addr().expect("is bound"));
+            listeners.push(listener);
+        }
+        let mut networks = Vec::new();
+        for (index, listener) in listeners.into_iter().enumerate() {
+            let mut config = gen_config(NodeIndex(index), n_members);
+            config.addresses = addresses.clone();
+            let keychain = Keychain::new(n_members, NodeIndex(index));
+            networks.push(
+                TcpNetwork::with_listener(&config, keychain, listener)
+                    .expect("addresses are complete"),
+            );
+        }
+
+        networks[0].send(vec![7], Recipient::Everyone);
+        assert_eq!(networks[1].next_event().await, Some(vec![7]));
+        assert_eq!(networks[2].next_event().await, Some(vec![7]));
+        networks[1].send(vec![8], Recipient::Node(NodeIndex(2)));
+        networks[2].send(vec![9], Recipient::Node(NodeIndex(0)));
+        assert_eq!(networks[2].next_event().await, Some(vec![8]));
+        assert_eq!(networks[0].next_event().await, Some(vec![9]));
+    }
+
+    #[tokio::test]
+    async fn refuses_impostors() {
+        let n_members = NodeCount(3);
+        let mut listeners = Vec::new();
+        let mut addresses = Vec::new();
+        for _ in 0..n_members.0 {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("binds a local port");
+            addresses.push(listener.local_addr().expect("is bound"));
+            listeners.push(listener);
+        }
+        let mut networks = Vec::new();
+        // The last node took over the address of the third member, but has the keys of the
+        // second one.
+        for (index, listener) in listeners.into_iter().enumerate() {
+            let mut config = gen_config(NodeIndex(index), n_members);
+            config.addresses = addresses.clone();
+            let keychain = Keychain::new(n_members, NodeIndex(index.min(1)));
+            networks.push(
+                TcpNetwork::with_listener(&config, keychain, listener)
+                    .expect("addresses are complete"),
+            );
+        }
+
+        networks[0].send(vec![7], Recipient::Node(NodeIndex(2)));
+        networks[0].send(vec![8], Recipient::Node(NodeIndex(1)));
+        assert_eq!(networks[1].next_event().await, Some(vec![8]));
+        assert!(
+            timeout(Duration::from_millis(500), networks[2].next_event())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! TLS shared by the transports, encrypting the connections.
+//!
+//! The certificates are self-signed and not verified, the nodes authenticate each other with
+//! their keychains instead, signing keying material exported from the TLS session, see the
+//! handshake in `stream`.
+use std::{sync::Arc, time::SystemTime};
+
+// The name the certificates are made out to, it is not verified anyway.
+pub(super) const SERVER_NAME: &str = "aleph-bft";
+/// The label of the keying material exported from TLS sessions for the handshake.
+pub(super) const EXPORTER_LABEL: &[u8] = b"EXPORTER-AlephBFT-handshake";
+/// The number of bytes of keying material exported for the handshake.
+pub(super) const BINDING_SIZE: usize = 32;
+
+struct SkipServerVerification;
+
+impl rustls::client::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// A fresh self-signed certificate together with its key.
+pub(super) fn certificate() -> Result<(rustls::Certificate, rustls::PrivateKey), String> {
+    let certificate = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+        .map_err(|e| e.to_string())?;
+    let key = rustls::PrivateKey(certificate.serialize_private_key_der());
+    let certificate = rustls::Certificate(certificate.serialize_der().map_err(|e| e.to_string())?);
+    Ok((certificate, key))
+}
+
+/// The configuration of the dialing nodes, accepting any certificate. Only TLS 1.3 is
+/// supported, as QUIC requires.
+pub(super) fn client_config() -> Result<rustls::ClientConfig, String> {
+    Ok(rustls::ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| e.to_string())?
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+        .with_no_client_auth())
+}
+
+/// The configuration of the listening nodes, with a fresh self-signed certificate.
+#[cfg(feature = "tcp")]
+pub(super) fn server_config() -> Result<rustls::ServerConfig, String> {
+    let (certificate, key) = certificate()?;
+    rustls::ServerConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(vec![certificate], key)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,115 @@
+use crate::{Hasher, NodeIndex, Round};
+use std::collections::HashMap;
+
+/// A unit of a finalized batch, see [`BatchDependencies`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct OrderedUnit {
+    pub creator: NodeIndex,
+    pub round: Round,
+    /// The positions in the batch of the parents of the unit finalized in the same batch, in
+    /// increasing order. The other parents were finalized in earlier batches.
+    pub parents: Vec<usize>,
+}
+
+/// The causal order among the units of a finalized batch, see
+/// [`LocalIO::enable_batch_dependencies`]. The units are in the order in which their data was
+/// passed to the [`FinalizationHandler`], which always puts parents before their children.
+///
+/// The data of a unit was created knowing the data of all its ancestors. The data of two units
+/// neither of which is an ancestor of the other was created independently, so an execution
+/// engine can execute it in parallel, as long as the transactions do not conflict otherwise.
+///
+/// [`LocalIO::enable_batch_dependencies`]: crate::LocalIO::enable_batch_dependencies
+/// [`FinalizationHandler`]: crate::FinalizationHandler
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BatchDependencies {
+    pub units: Vec<OrderedUnit>,
+}
+
+impl BatchDependencies {
+    /// Dependencies of the batch of units, given in the order of finalization with their
+    /// hashes and the hashes of their parents.
+    pub(crate) fn new<H: Hasher>(units: Vec<(H::Hash, NodeIndex, Round, &[H::Hash])>) -> Self {
+        let positions: HashMap<_, _> = units
+            .iter()
+            .enumerate()
+            .map(|(position, (hash, ..))| (*hash, position))
+            .collect();
+        let units = units
+            .into_iter()
+            .map(|(_, creator, round, parents)| {
+                let mut parents: Vec<_> = parents
+                    .iter()
+                    .filter_map(|parent| positions.get(parent).copied())
+                    .collect();
+                parents.sort_unstable();
+                OrderedUnit {
+                    creator,
+                    round,
+                    parents,
+                }
+            })
+            .collect();
+        BatchDependencies { units }
+    }
+
+    /// Whether the unit at position `ancestor` in the batch is an ancestor of the unit at
+    /// position `descendant`. No unit is its own ancestor.
+    pub fn is_ancestor(&self, ancestor: usize, descendant: usize) -> bool {
+        if ancestor >= descendant || descendant >= self.units.len() {
+            return false;
+        }
+        let mut visited = vec![false; descendant + 1];
+        let mut pending = vec![descendant];
+        while let Some(position) = pending.pop() {
+            for parent in &self.units[position].parents {
+                if *parent == ancestor {
+                    return true;
+                }
+                // Parents come before their children, so units before the ancestor cannot lead
+                // to it.
+                if *parent > ancestor && !visited[*parent] {
+                    visited[*parent] = true;
+                    pending.push(*parent);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchDependencies;
+    use crate::{Hasher, NodeIndex};
+    use aleph_bft_mock::Hasher64;
+
+    #[test]
+    fn finds_dependencies_within_batch() {
+        let hashes: Vec<_> = (0..5u8).map(|i| Hasher64::hash(&[i])).collect();
+        // Unit 0 is from an earlier batch, the head 4 has parents 2 and 3, and 2 has parent 1.
+        let parents = vec![
+            vec![],
+            vec![hashes[0]],
+            vec![hashes[1]],
+            vec![],
+            vec![hashes[3], hashes[2]],
+        ];
+        let batch = (1..5)
+            .map(|i| (hashes[i], NodeIndex(i), 1, &parents[i][..]))
+            .collect();
+        let dependencies = BatchDependencies::new::<Hasher64>(batch);
+
+        let parents: Vec<_> = dependencies
+            .units
+            .iter()
+            .map(|unit| unit.parents.clone())
+            .collect();
+        assert_eq!(parents, vec![vec![], vec![0], vec![], vec![1, 2]]);
+        assert!(dependencies.is_ancestor(0, 3));
+        assert!(dependencies.is_ancestor(0, 1));
+        assert!(!dependencies.is_ancestor(0, 2));
+        assert!(!dependencies.is_ancestor(3, 3));
+        assert!(!dependencies.is_ancestor(2, 1));
+    }
+}
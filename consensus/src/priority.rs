@@ -0,0 +1,127 @@
+use crate::{Data, DataProvider, Receiver, Sender};
+use async_trait::async_trait;
+use codec::Encode;
+use futures::channel::mpsc::unbounded;
+
+/// A handle for submitting data to be placed in our next unit, ahead of the data of the
+/// provider wrapped in the corresponding [`PriorityDataProvider`]. All clones submit to the same
+/// provider.
+#[derive(Clone)]
+pub struct PriorityHandle<D: Data> {
+    data: Sender<D>,
+}
+
+impl<D: Data> PriorityHandle<D> {
+    /// Submits the data, returning it back if the provider is gone, e.g. the session ended.
+    pub fn submit(&self, data: D) -> Result<(), D> {
+        self.data.unbounded_send(data).map_err(|e| e.into_inner())
+    }
+}
+
+/// Outputs the data submitted through its [`PriorityHandle`]s before any data of the wrapped
+/// provider, in the order of submission. The data is placed in the next unit we create, unless
+/// more was submitted than fits in one, e.g. with a [`crate::MultiDataProvider`] wrapping this
+/// provider, in which case the rest goes to the following units.
+pub struct PriorityDataProvider<D: Data, DP: DataProvider<D>> {
+    data_provider: DP,
+    priority_data: Receiver<D>,
+}
+
+impl<D: Data, DP: DataProvider<D>> PriorityDataProvider<D, DP> {
+    pub fn new(data_provider: DP) -> (Self, PriorityHandle<D>) {
+        let (data, priority_data) = unbounded();
+        (
+            PriorityDataProvider {
+                data_provider,
+                priority_data,
+            },
+            PriorityHandle { data },
+        )
+    }
+
+    fn next_priority(&mut self) -> Option<D> {
+        self.priority_data.try_next().ok().flatten()
+    }
+}
+
+#[async_trait]
+impl<D: Data, DP: DataProvider<D>> DataProvider<D> for PriorityDataProvider<D, DP> {
+    async fn get_data(&mut self) -> Option<D> {
+        match self.next_priority() {
+            Some(data) => Some(data),
+            None => self.data_provider.get_data().await,
+        }
+    }
+
+    async fn get_data_items(&mut self, max_items: usize, max_bytes: usize) -> Vec<D> {
+        let mut items = Vec::new();
+        let mut bytes = 0;
+        while items.len() < max_items {
+            let data = match self.next_priority() {
+                Some(data) => data,
+                None => break,
+            };
+            bytes += data.encoded_size();
+            items.push(data);
+            if bytes >= max_bytes {
+                return items;
+            }
+        }
+        if items.len() < max_items {
+            let rest = self
+                .data_provider
+                .get_data_items(max_items - items.len(), max_bytes - bytes)
+                .await;
+            items.extend(rest);
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PriorityDataProvider;
+    use crate::DataProvider;
+    use async_trait::async_trait;
+
+    struct CountingProvider {
+        next: u32,
+    }
+
+    #[async_trait]
+    impl DataProvider<u32> for CountingProvider {
+        async fn get_data(&mut self) -> Option<u32> {
+            self.next += 1;
+            Some(self.next)
+        }
+
+        async fn get_data_items(&mut self, max_items: usize, _max_bytes: usize) -> Vec<u32> {
+            let mut items = Vec::new();
+            while items.len() < max_items {
+                items.push(self.get_data().await.expect("there is always data"));
+            }
+            items
+        }
+    }
+
+    #[tokio::test]
+    async fn outputs_priority_data_first() {
+        let (mut provider, handle) = PriorityDataProvider::new(CountingProvider { next: 0 });
+        assert_eq!(provider.get_data().await, Some(1));
+
+        handle.submit(100).expect("the provider is alive");
+        handle.submit(200).expect("the provider is alive");
+        assert_eq!(provider.get_data().await, Some(100));
+        assert_eq!(provider.get_data_items(3, 1000).await, vec![200, 2, 3]);
+
+        for data in 300..305 {
+            handle.submit(data).expect("the provider is alive");
+        }
+        assert_eq!(provider.get_data_items(3, 1000).await, vec![300, 301, 302]);
+        assert_eq!(provider.get_data_items(3, 4).await, vec![303]);
+        assert_eq!(provider.get_data().await, Some(304));
+
+        drop(provider);
+        assert_eq!(handle.submit(400), Err(400));
+    }
+}
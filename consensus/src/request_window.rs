@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// How many times slower than the fastest observed response the typical response may get before
+/// we consider the peers or the links congested.
+const LATENCY_TOLERANCE: u32 = 3;
+/// The factor by which the window shrinks on congestion.
+const BACKOFF: f64 = 0.5;
+/// The weight of a new sample in the smoothed round trip time.
+const SMOOTHING: f64 = 0.125;
+
+/// Limits the number of outstanding requests, adjusting the limit to the observed responses in
+/// the AIMD fashion: every timely response grows the window by about one per window's worth of
+/// responses, while a timeout, or responses getting much slower than the fastest observed, halve
+/// it, at most once per round trip. This way catching up is fast on low latency links yet does
+/// not flood slow ones.
+///
+/// A request occupies a slot only until its first timeout, requests that are being retried do
+/// not block new ones, so requests that can never be answered do not stall the window.
+pub struct RequestWindow<K: Eq + Hash> {
+    limit: f64,
+    max: f64,
+    outstanding: HashMap<K, Instant>,
+    min_rtt: Option<Duration>,
+    smoothed_rtt: Option<Duration>,
+    last_backoff: Option<Instant>,
+}
+
+impl<K: Eq + Hash> RequestWindow<K> {
+    /// A window starting at `initial` outstanding requests and never exceeding `max`, the
+    /// window always allows at least one request.
+    pub fn new(initial: usize, max: usize) -> Self {
+        let max = max.max(1);
+        RequestWindow {
+            limit: initial.clamp(1, max) as f64,
+            max: max as f64,
+            outstanding: HashMap::new(),
+            min_rtt: None,
+            smoothed_rtt: None,
+            last_backoff: None,
+        }
+    }
+
+    /// The current number of requests allowed to be outstanding.
+    pub fn limit(&self) -> usize {
+        self.limit as usize
+    }
+
+    /// How many more requests can be started right now.
+    pub fn room(&self) -> usize {
+        self.limit().saturating_sub(self.outstanding.len())
+    }
+
+    /// Registers a new request, unless the window is full. Requests that are outstanding
+    /// already are always allowed.
+    pub fn try_start(&mut self, key: K, now: Instant) -> bool {
+        if self.outstanding.contains_key(&key) {
+            return true;
+        }
+        if self.room() == 0 {
+            return false;
+        }
+        self.outstanding.insert(key, now);
+        true
+    }
+
+    /// The request is being retried, as it was not answered in time.
+    pub fn on_timeout(&mut self, key: &K, now: Instant) {
+        if self.outstanding.remove(key).is_some() {
+            self.back_off(now);
+        }
+    }
+
+    /// The request was answered. Answers to requests that timed out are not taken into
+    /// account, as it is unknown which try they answer.
+    pub fn on_resolved(&mut self, key: &K, now: Instant) {
+        let started = match self.outstanding.remove(key) {
+            Some(started) => started,
+            None => return,
+        };
+        let rtt = now.saturating_duration_since(started);
+        let min_rtt = self.min_rtt.map_or(rtt, |min_rtt| min_rtt.min(rtt));
+        self.min_rtt = Some(min_rtt);
+        let smoothed_rtt = match self.smoothed_rtt {
+            Some(smoothed_rtt) => smoothed_rtt.mul_f64(1.0 - SMOOTHING) + rtt.mul_f64(SMOOTHING),
+            None => rtt,
+        };
+        self.smoothed_rtt = Some(smoothed_rtt);
+        if smoothed_rtt > min_rtt * LATENCY_TOLERANCE {
+            self.back_off(now);
+        } else {
+            self.limit = (self.limit + 1.0 / self.limit).min(self.max);
+        }
+    }
+
+    fn back_off(&mut self, now: Instant) {
+        // A single congestion event affects many requests at once, so it should only be
+        // penalized once.
+        let interval = self.smoothed_rtt.unwrap_or_default();
+        if let Some(last_backoff) = self.last_backoff {
+            if now.saturating_duration_since(last_backoff) < interval {
+                return;
+            }
+        }
+        self.limit = (self.limit * BACKOFF).max(1.0);
+        self.last_backoff = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequestWindow;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn grows_on_timely_responses() {
+        let mut window = RequestWindow::new(2, 4);
+        let start = Instant::now();
+        assert!(window.try_start(0, start));
+        assert!(window.try_start(1, start));
+        assert!(window.try_start(1, start));
+        assert!(!window.try_start(2, start));
+        let mut key = 2;
+        for _ in 0..20 {
+            window.on_resolved(&(key - 2), start + Duration::from_millis(10));
+            while window.try_start(key, start) {
+                key += 1;
+            }
+        }
+        assert_eq!(window.limit(), 4);
+    }
+
+    #[test]
+    fn shrinks_once_per_round_trip_on_timeouts() {
+        let mut window = RequestWindow::new(8, 8);
+        let start = Instant::now();
+        for key in 0..3 {
+            assert!(window.try_start(key, start));
+        }
+        window.on_resolved(&0, start + Duration::from_millis(100));
+        let timeout = start + Duration::from_secs(1);
+        window.on_timeout(&1, timeout);
+        window.on_timeout(&2, timeout + Duration::from_millis(10));
+        assert_eq!(window.limit(), 4);
+        assert_eq!(window.room(), 4);
+
+        assert!(window.try_start(3, timeout));
+        window.on_timeout(&3, timeout + Duration::from_millis(200));
+        assert_eq!(window.limit(), 2);
+        window.on_resolved(&1, timeout + Duration::from_millis(300));
+        assert_eq!(window.limit(), 2);
+    }
+
+    #[test]
+    fn shrinks_when_responses_slow_down() {
+        let mut window = RequestWindow::new(4, 8);
+        let start = Instant::now();
+        assert!(window.try_start(0, start));
+        window.on_resolved(&0, start + Duration::from_millis(10));
+        let limit = window.limit();
+        for key in 1..20 {
+            let now = start + Duration::from_secs(key);
+            assert!(window.try_start(key, now));
+            window.on_resolved(&key, now + Duration::from_millis(500));
+        }
+        assert!(window.limit() < limit);
+        assert!(window.limit() >= 1);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::{units::UncheckedSignedUnit, Data, Hasher, NodeIndex, Round, SessionId, Signature};
+use crate::{
+    units::UncheckedSignedUnit, Data, Hasher, Index, KeyRotation, Keychain, NodeCount, NodeIndex,
+    NodeSubset, Round, SessionId, Signature,
+};
 use codec::{Decode, Encode, Error as CodecError};
 use futures::channel::oneshot;
 use log::{error, info, warn};
+use parking_lot::Mutex;
 use std::{
     fmt,
     io::{Read, Write},
     marker::PhantomData,
+    sync::Arc,
 };
 
 /// Backup load error. Could be either caused by io error from Reader, or by decoding.
@@ -16,6 +21,7 @@ pub enum LoaderError {
     RoundMissmatch(Round, Round),
     WrongCreator(Round, NodeIndex, NodeIndex),
     WrongSession(Round, SessionId, SessionId),
+    WrongSignature(Round),
 }
 
 impl fmt::Display for LoaderError {
@@ -52,6 +58,20 @@ impl fmt::Display for LoaderError {
                     round, expected, session
                 )
             }
+
+            LoaderError::WrongSignature(round) => {
+                write!(f, "Wrong signature for unit round {:?}", round)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // Codec errors only implement `Error` with the `std` feature of the codec.
+        match self {
+            LoaderError::IO(err) => Some(err),
+            _ => None,
         }
     }
 }
@@ -115,6 +135,130 @@ impl<R: Read, H: Hasher, D: Data, S: Signature> UnitLoader<R, H, D, S> {
     }
 }
 
+/// Which peers acknowledged receiving our newest unit.
+#[derive(Clone, Eq, PartialEq, Debug, Encode, Decode)]
+pub struct BroadcastStatus<H: Hasher> {
+    hash: H::Hash,
+    round: Round,
+    session_id: SessionId,
+    creator: NodeIndex,
+    acked: NodeSubset,
+}
+
+impl<H: Hasher> BroadcastStatus<H> {
+    /// The status of a unit we just created, acknowledged only by ourselves.
+    pub fn new(
+        hash: H::Hash,
+        round: Round,
+        session_id: SessionId,
+        n_members: NodeCount,
+        index: NodeIndex,
+    ) -> Self {
+        let mut acked = NodeSubset::with_size(n_members);
+        acked.insert(index);
+        BroadcastStatus {
+            hash,
+            round,
+            session_id,
+            creator: index,
+            acked,
+        }
+    }
+
+    /// Whether the status was saved in this session by a node with the same index in a
+    /// committee of the same size. The storage of the backup might be reused between sessions,
+    /// in which our index can change, and the acknowledgements then refer to different nodes.
+    pub fn belongs_to(
+        &self,
+        session_id: SessionId,
+        n_members: NodeCount,
+        index: NodeIndex,
+    ) -> bool {
+        self.session_id == session_id && self.creator == index && self.acked.size() == n_members.0
+    }
+
+    pub fn hash(&self) -> H::Hash {
+        self.hash
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// Marks the unit as received by the node, returns whether this changed anything.
+    pub fn ack(&mut self, node: NodeIndex) -> bool {
+        if node.0 >= self.acked.size() || self.acked[node] {
+            return false;
+        }
+        self.acked.insert(node);
+        true
+    }
+
+    /// Whether no node other than ourselves acknowledged the unit yet.
+    pub fn is_fresh(&self) -> bool {
+        self.acked.len() <= 1
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.acked.len() == self.acked.size()
+    }
+
+    /// The nodes that did not acknowledge the unit yet.
+    pub fn pending(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        (0..self.acked.size())
+            .map(NodeIndex)
+            .filter(|node| !self.acked[*node])
+    }
+}
+
+/// Persists the [`BroadcastStatus`] of our newest unit next to the unit backup, as a sequence
+/// of records out of which the last one is current.
+#[derive(Clone)]
+pub struct BroadcastBackup {
+    saver: Arc<Mutex<Box<dyn Write + Send>>>,
+    loader: Arc<Mutex<Option<Box<dyn Read + Send>>>>,
+}
+
+impl BroadcastBackup {
+    pub fn new(saver: impl Write + Send + 'static, loader: impl Read + Send + 'static) -> Self {
+        BroadcastBackup {
+            saver: Arc::new(Mutex::new(Box::new(saver))),
+            loader: Arc::new(Mutex::new(Some(Box::new(loader)))),
+        }
+    }
+
+    pub fn save<H: Hasher>(&self, status: &BroadcastStatus<H>) -> Result<(), std::io::Error> {
+        let mut saver = self.saver.lock();
+        saver.write_all(&status.encode())?;
+        saver.flush()?;
+        Ok(())
+    }
+
+    /// Loads the most recently saved status. Can only be done once, afterwards there is nothing
+    /// more to load. A record that cannot be decoded, e.g. because we crashed while writing it,
+    /// ends the backup.
+    pub fn load<H: Hasher>(&self) -> Result<Option<BroadcastStatus<H>>, LoaderError> {
+        let mut loader = match self.loader.lock().take() {
+            Some(loader) => loader,
+            None => return Ok(None),
+        };
+        let mut buf = Vec::new();
+        loader.read_to_end(&mut buf)?;
+        let input = &mut &buf[..];
+        let mut status = None;
+        while !input.is_empty() {
+            match BroadcastStatus::decode(input) {
+                Ok(loaded) => status = Some(loaded),
+                Err(e) => {
+                    warn!(target: "AlephBFT-unit-backup", "broadcast backup ends with a broken record: {}", e);
+                    break;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
 fn load_backup<H: Hasher, D: Data, S: Signature, R: Read>(
     unit_loader: UnitLoader<R, H, D, S>,
     index: NodeIndex,
@@ -148,6 +292,43 @@ fn load_backup<H: Hasher, D: Data, S: Signature, R: Read>(
     Ok(units)
 }
 
+/// Copies the backup of units of the owner of the `keychain` in the session `session_id` from
+/// `source` to `target`, e.g. when changing the storage of backups. Before anything is written,
+/// the whole backup is verified -- it has to contain consecutive units of the owner of the
+/// keychain from the given session, all correctly signed with the keys of the epochs scheduled
+/// by the `key_rotation`, as in the session. Returns the number of copied units.
+pub fn migrate_backup<H, D, K, R, W>(
+    source: R,
+    target: W,
+    keychain: &K,
+    key_rotation: &KeyRotation,
+    session_id: SessionId,
+) -> Result<usize, LoaderError>
+where
+    H: Hasher,
+    D: Data,
+    K: Keychain,
+    R: Read,
+    W: Write,
+{
+    let units: Vec<UncheckedSignedUnit<H, D, K::Signature>> =
+        load_backup(UnitLoader::new(source), keychain.index(), session_id)?;
+    for u in &units {
+        let keychain = key_rotation
+            .schedule()
+            .keychain(keychain, u.as_signable().round());
+        if u.clone().check(&keychain).is_err() {
+            return Err(LoaderError::WrongSignature(u.as_signable().round()));
+        }
+    }
+    let mut unit_saver = UnitSaver::new(target);
+    for u in &units {
+        unit_saver.save(u.clone())?;
+    }
+    info!(target: "AlephBFT-unit-backup", "migrated {:?} units of the backup", units.len());
+    Ok(units.len())
+}
+
 fn on_shutdown(starting_round_tx: oneshot::Sender<Option<Round>>) {
     if starting_round_tx.send(None).is_err() {
         warn!(target: "AlephBFT-unit-backup", "Could not send `None` starting round.");
@@ -211,21 +392,26 @@ pub async fn run_loading_mechanism<'a, H: Hasher, D: Data, S: Signature, R: Read
 
 #[cfg(test)]
 mod tests {
-    use super::{run_loading_mechanism, UnitLoader};
+    use super::{
+        migrate_backup, run_loading_mechanism, BroadcastBackup, BroadcastStatus, LoaderError,
+        UnitLoader,
+    };
     use crate::{
         units::{
             create_units, creator_set, preunit_to_unchecked_signed_unit, preunit_to_unit,
             UncheckedSignedUnit as GenericUncheckedSignedUnit,
         },
-        NodeCount, NodeIndex, Round, SessionId,
+        Hasher, KeyRotation, NodeCount, NodeIndex, Round, SessionId,
     };
-    use aleph_bft_mock::{Data, Hasher64, Keychain, Loader, Signature};
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Loader, Saver, Signature};
     use codec::Encode;
     use futures::channel::oneshot::{self, Receiver, Sender};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
 
     type UncheckedSignedUnit = GenericUncheckedSignedUnit<Hasher64, Data, Signature>;
 
-    const SESSION_ID: SessionId = 43;
+    const SESSION_ID: SessionId = SessionId::from_number(43);
     const NODE_ID: NodeIndex = NodeIndex(0);
     const N_MEMBERS: NodeCount = NodeCount(4);
 
@@ -249,15 +435,7 @@ mod tests {
         }
     }
 
-    async fn prepare_test<'a>(
-        units: Vec<Unit>,
-    ) -> (
-        impl futures::Future,
-        Receiver<Vec<UncheckedSignedUnit>>,
-        Sender<Round>,
-        Receiver<Option<Round>>,
-        Vec<UncheckedSignedUnit>,
-    ) {
+    async fn encode_units(units: Vec<Unit>) -> (Vec<u8>, Vec<UncheckedSignedUnit>) {
         let mut encoded_data = Vec::new();
         let mut data = Vec::new();
 
@@ -300,6 +478,19 @@ mod tests {
                 creator.add_units(&new_units);
             }
         }
+        (encoded_data, data)
+    }
+
+    async fn prepare_test<'a>(
+        units: Vec<Unit>,
+    ) -> (
+        impl futures::Future,
+        Receiver<Vec<UncheckedSignedUnit>>,
+        Sender<Round>,
+        Receiver<Option<Round>>,
+        Vec<UncheckedSignedUnit>,
+    ) {
+        let (encoded_data, data) = encode_units(units).await;
         let unit_loader = UnitLoader::new(Loader::new(encoded_data));
         let (loaded_unit_tx, loaded_unit_rx) = oneshot::channel();
         let (starting_round_tx, starting_round_rx) = oneshot::channel();
@@ -520,7 +711,9 @@ mod tests {
     #[tokio::test]
     async fn corrupted_backup_wrong_session() {
         let mut units: Vec<_> = (0..5).map(Unit::new_correct).collect();
-        units.iter_mut().for_each(|u| u.session_id += 1);
+        units
+            .iter_mut()
+            .for_each(|u| u.session_id = SessionId::from_number(44));
         let (task, loaded_unit_rx, highest_response_tx, starting_round_rx, _) =
             prepare_test(units).await;
 
@@ -535,4 +728,74 @@ mod tests {
         assert_eq!(starting_round_rx.await, Ok(None));
         assert!(loaded_unit_rx.await.is_err());
     }
+
+    #[tokio::test]
+    async fn migrates_correct_backup() {
+        let (encoded_data, _) = encode_units((0..5).map(Unit::new_correct).collect()).await;
+        let target = Arc::new(Mutex::new(Vec::new()));
+        let keychain = Keychain::new(N_MEMBERS, NODE_ID);
+
+        let migrated = migrate_backup::<Hasher64, Data, _, _, _>(
+            Loader::new(encoded_data.clone()),
+            Saver::from(target.clone()),
+            &keychain,
+            &KeyRotation::default(),
+            SESSION_ID,
+        );
+
+        assert!(matches!(migrated, Ok(5)));
+        assert_eq!(*target.lock(), encoded_data);
+    }
+
+    #[tokio::test]
+    async fn does_not_migrate_backup_of_other_node() {
+        let (encoded_data, _) = encode_units((0..5).map(Unit::new_correct).collect()).await;
+        let target = Arc::new(Mutex::new(Vec::new()));
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(1));
+
+        let migrated = migrate_backup::<Hasher64, Data, _, _, _>(
+            Loader::new(encoded_data),
+            Saver::from(target.clone()),
+            &keychain,
+            &KeyRotation::default(),
+            SESSION_ID,
+        );
+
+        assert!(matches!(migrated, Err(LoaderError::WrongCreator(0, _, _))));
+        assert!(target.lock().is_empty());
+    }
+
+    #[test]
+    fn loads_last_broadcast_status() {
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let backup = BroadcastBackup::new(Saver::from(saved.clone()), Loader::new(vec![]));
+        let mut status = BroadcastStatus::<Hasher64>::new(
+            Hasher64::hash(&[1]),
+            3,
+            SESSION_ID,
+            N_MEMBERS,
+            NODE_ID,
+        );
+        backup.save(&status).expect("saving should work");
+        assert!(status.ack(NodeIndex(2)));
+        assert!(!status.ack(NodeIndex(2)));
+        assert!(!status.ack(NodeIndex(7)));
+        backup.save(&status).expect("saving should work");
+
+        let mut encoded = saved.lock().clone();
+        encoded.extend_from_slice(&status.encode()[..5]);
+        let backup = BroadcastBackup::new(Saver::new(), Loader::new(encoded));
+        let loaded = backup
+            .load::<Hasher64>()
+            .expect("loading should work")
+            .expect("the status was saved");
+        assert_eq!(loaded, status);
+        assert!(loaded.belongs_to(SESSION_ID, N_MEMBERS, NODE_ID));
+        assert!(!loaded.belongs_to(SESSION_ID, N_MEMBERS, NodeIndex(1)));
+        assert_eq!(
+            loaded.pending().collect::<Vec<_>>(),
+            vec![NodeIndex(1), NodeIndex(3)]
+        );
+        assert!(matches!(backup.load::<Hasher64>(), Ok(None)));
+    }
 }
@@ -35,10 +35,10 @@ pub struct NewestUnitResponse<H: Hasher, D: Data, S: Signature> {
 }
 
 impl<H: Hasher, D: Data, S: Signature> Signable for NewestUnitResponse<H, D, S> {
-    type Hash = Vec<u8>;
+    type Hash = H::Hash;
 
     fn hash(&self) -> Self::Hash {
-        self.encode()
+        H::hash(&self.encode())
     }
 }
 
@@ -267,6 +267,12 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
         let status_ticker_delay = Duration::from_secs(10);
         let mut status_ticker = Delay::new(status_ticker_delay).fuse();
 
+        // There might be nobody to wait for, e.g. in a committee of one.
+        if let Finished(round) = self.collection.status() {
+            self.finish(round);
+            return;
+        }
+
         loop {
             futures::select! {
                 response = self.responses_from_network.next() => {
@@ -315,7 +321,7 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
 mod tests {
     use super::{
         Collection as GenericCollection, Error, NewestUnitResponse as GenericNewestUnitResponse,
-        Salt, Status::*,
+        Salt, Status::*, IO,
     };
     use crate::{
         creation::Creator as GenericCreator,
@@ -326,7 +332,12 @@ mod tests {
         Index, NodeCount, NodeIndex, SessionId, Signed, UncheckedSigned,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
-    use std::iter::{once, repeat};
+    use futures::channel::{mpsc, oneshot};
+    use std::{
+        iter::{once, repeat},
+        time::Duration,
+    };
+    use tokio::time::timeout;
 
     type Collection<'a> = GenericCollection<'a, Keychain>;
     type Validator = GenericValidator<Keychain>;
@@ -371,12 +382,35 @@ mod tests {
         signed_unit.into()
     }
 
+    #[tokio::test]
+    async fn finishes_right_away_without_others() {
+        let n_members = NodeCount(1);
+        let threshold = NodeCount(1);
+        let creator_id = NodeIndex(0);
+        let session_id = SessionId::from_number(0);
+        let max_round = 2;
+        let keychain = Keychain::new(n_members, creator_id);
+        let validator = Validator::new(session_id, keychain, max_round, threshold);
+        let (collection, _) = Collection::new(&keychain, &validator, threshold);
+        assert_eq!(collection.status(), Finished(0));
+
+        let (round_tx, round_rx) = oneshot::channel();
+        let (_responses_tx, responses_rx) = mpsc::unbounded();
+        let (resolved_tx, _resolved_rx) = mpsc::unbounded();
+        let io = IO::new(round_tx, responses_rx, resolved_tx, collection);
+        // Well before the catch up delay.
+        timeout(Duration::from_secs(1), io.run())
+            .await
+            .expect("the collection finishes right away");
+        assert_eq!(round_rx.await, Ok(0));
+    }
+
     #[test]
     fn pending_with_no_messages() {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
         let validator = Validator::new(session_id, keychain, max_round, threshold);
@@ -389,7 +423,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -412,7 +446,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -435,7 +469,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -462,7 +496,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -501,7 +535,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -528,8 +562,8 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
-        let wrong_session_id = 43;
+        let session_id = SessionId::from_number(0);
+        let wrong_session_id = SessionId::from_number(43);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
@@ -559,7 +593,7 @@ mod tests {
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
         let other_creator_id = NodeIndex(1);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
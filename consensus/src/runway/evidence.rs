@@ -0,0 +1,213 @@
+use crate::{
+    terminal::GrowthRuleViolation,
+    units::{ControlHash, UncheckedSignedUnit},
+    Data, Hasher, Keychain, NodeCount, NodeIndex, NodeMap, Signature,
+};
+use codec::{Decode, Encode};
+
+/// Evidence that the creator of a unit broke the growth rule: the unit signed by its creator, the
+/// hashes of its parents and, if the violation concerns one of them, that parent signed by its
+/// own creator. Anybody knowing the public keys of the committee can verify it, see
+/// [`GrowthRuleEvidence::check`]. It is sent, encoded, with the [`UnitRejection`] of the unit.
+///
+/// [`UnitRejection`]: crate::UnitRejection
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct GrowthRuleEvidence<H: Hasher, D: Data, S: Signature> {
+    unit: UncheckedSignedUnit<H, D, S>,
+    parents: NodeMap<H::Hash>,
+    parent: Option<UncheckedSignedUnit<H, D, S>>,
+    violation: GrowthRuleViolation,
+}
+
+impl<H: Hasher, D: Data, S: Signature> GrowthRuleEvidence<H, D, S> {
+    pub(crate) fn new(
+        unit: UncheckedSignedUnit<H, D, S>,
+        parents: NodeMap<H::Hash>,
+        parent: Option<UncheckedSignedUnit<H, D, S>>,
+        violation: GrowthRuleViolation,
+    ) -> Self {
+        GrowthRuleEvidence {
+            unit,
+            parents,
+            parent,
+            violation,
+        }
+    }
+
+    /// The node that broke the growth rule.
+    pub fn creator(&self) -> NodeIndex {
+        self.unit.as_signable().creator()
+    }
+
+    pub fn violation(&self) -> GrowthRuleViolation {
+        self.violation
+    }
+
+    /// Whether the evidence proves the violation, i.e. the units in it are signed by their
+    /// creators, the parents match the control hash of the unit and break the growth rule in
+    /// the claimed way.
+    pub fn check<K: Keychain<Signature = S>>(&self, keychain: &K) -> bool {
+        use GrowthRuleViolation::*;
+        let unit = match self.unit.clone().check(keychain) {
+            Ok(unit) => unit.into_signable(),
+            Err(_) => return false,
+        };
+        let control_hash = unit.control_hash();
+        let n_members = control_hash.n_members();
+        if unit.round() == 0
+            || self.parents.size() != n_members
+            || ControlHash::<H>::combine_hashes(&self.parents) != control_hash.combined_hash
+        {
+            return false;
+        }
+        let parent = match self.violation {
+            MissingOwnParent => return self.parents.get(unit.creator()).is_none(),
+            NotEnoughParents { parents, threshold } => {
+                return parents == NodeCount(self.parents.item_count())
+                    && threshold == n_members.quorum()
+                    && parents < threshold;
+            }
+            ParentFromWrongRound { parent, .. } | ParentFromWrongCreator { parent, .. } => parent,
+        };
+        if parent.0 >= n_members.0 {
+            return false;
+        }
+        let parent_unit = match self.parent.clone().map(|parent| parent.check(keychain)) {
+            Some(Ok(parent_unit)) => parent_unit.into_signable(),
+            _ => return false,
+        };
+        if self.parents.get(parent) != Some(&parent_unit.hash()) {
+            return false;
+        }
+        match self.violation {
+            ParentFromWrongRound { round, .. } => {
+                parent_unit.round() == round && round + 1 != unit.round()
+            }
+            ParentFromWrongCreator { creator, .. } => {
+                parent_unit.creator() == creator && creator != parent
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GrowthRuleEvidence as GenericGrowthRuleEvidence;
+    use crate::{
+        terminal::GrowthRuleViolation,
+        units::{preunit_to_unchecked_signed_unit, ControlHash, PreUnit, UncheckedSignedUnit},
+        NodeCount, NodeIndex, NodeMap, Round, SessionId,
+    };
+    use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, Signature};
+
+    type GrowthRuleEvidence = GenericGrowthRuleEvidence<Hasher64, Data, Signature>;
+    type SignedUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+
+    async fn unit(creator: usize, round: Round, parents: &NodeMap<Hash64>) -> SignedUnit {
+        let preunit = PreUnit::new(NodeIndex(creator), round, ControlHash::new(parents));
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(creator));
+        preunit_to_unchecked_signed_unit(preunit, SessionId::from_number(0), &keychain).await
+    }
+
+    async fn round_zero() -> Vec<SignedUnit> {
+        let mut units = Vec::new();
+        for creator in 0..N_MEMBERS.0 {
+            units.push(unit(creator, 0, &NodeMap::with_size(N_MEMBERS)).await);
+        }
+        units
+    }
+
+    fn parent_map(parents: &[(usize, &SignedUnit)]) -> NodeMap<Hash64> {
+        let mut map = NodeMap::with_size(N_MEMBERS);
+        for (index, parent) in parents {
+            map.insert(NodeIndex(*index), parent.as_signable().hash());
+        }
+        map
+    }
+
+    #[tokio::test]
+    async fn proves_missing_own_parent() {
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(3));
+        let units = round_zero().await;
+        let parents = parent_map(&[(1, &units[1]), (2, &units[2]), (3, &units[3])]);
+        let offending = unit(0, 1, &parents).await;
+
+        let evidence = GrowthRuleEvidence::new(
+            offending.clone(),
+            parents.clone(),
+            None,
+            GrowthRuleViolation::MissingOwnParent,
+        );
+        assert_eq!(evidence.creator(), NodeIndex(0));
+        assert!(evidence.check(&keychain));
+
+        // The parents are enough, so claiming otherwise proves nothing.
+        let evidence = GrowthRuleEvidence::new(
+            offending.clone(),
+            parents,
+            None,
+            GrowthRuleViolation::NotEnoughParents {
+                parents: NodeCount(3),
+                threshold: NodeCount(3),
+            },
+        );
+        assert!(!evidence.check(&keychain));
+
+        // Parents not matching the control hash prove nothing either.
+        let evidence = GrowthRuleEvidence::new(
+            offending,
+            parent_map(&[(1, &units[1]), (2, &units[2])]),
+            None,
+            GrowthRuleViolation::MissingOwnParent,
+        );
+        assert!(!evidence.check(&keychain));
+    }
+
+    #[tokio::test]
+    async fn proves_parent_from_wrong_round() {
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(3));
+        let units = round_zero().await;
+        let all_parents = parent_map(&[
+            (0, &units[0]),
+            (1, &units[1]),
+            (2, &units[2]),
+            (3, &units[3]),
+        ]);
+        let mut round_one = Vec::new();
+        for creator in 0..N_MEMBERS.0 {
+            round_one.push(unit(creator, 1, &all_parents).await);
+        }
+        // The parent of node 1 is its unit of round 0 instead of round 1.
+        let parents = parent_map(&[
+            (0, &round_one[0]),
+            (1, &units[1]),
+            (2, &round_one[2]),
+            (3, &round_one[3]),
+        ]);
+        let offending = unit(0, 2, &parents).await;
+        let violation = GrowthRuleViolation::ParentFromWrongRound {
+            parent: NodeIndex(1),
+            round: 0,
+        };
+
+        let evidence = GrowthRuleEvidence::new(
+            offending.clone(),
+            parents.clone(),
+            Some(units[1].clone()),
+            violation,
+        );
+        assert!(evidence.check(&keychain));
+
+        // Without the parent the claim cannot be checked.
+        let evidence = GrowthRuleEvidence::new(offending.clone(), parents.clone(), None, violation);
+        assert!(!evidence.check(&keychain));
+
+        // Neither with a parent that is not the one in the parents.
+        let evidence =
+            GrowthRuleEvidence::new(offending, parents, Some(round_one[1].clone()), violation);
+        assert!(!evidence.check(&keychain));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{Data, Hasher, NodeCount, NodeIndex, NodeMap, Round};
+use codec::Encode;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+};
+
+// For how many rounds the data of ordered units is remembered to recognize duplicates.
+const DATA_RETENTION_ROUNDS: Round = 100;
+
+/// How a single creator's units contributed to the finalized output.
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Hash)]
+pub struct CreatorInclusion {
+    /// Number of units of this creator ordered in finalized batches.
+    pub ordered_units: usize,
+    /// Number of ordered units that contributed data not finalized before.
+    pub new_data: usize,
+    /// Number of ordered units with data that was already finalized before.
+    pub duplicated_data: usize,
+    /// Number of ordered units without any data.
+    pub empty: usize,
+}
+
+impl fmt::Display for CreatorInclusion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}/{}",
+            self.ordered_units, self.new_data, self.duplicated_data, self.empty
+        )
+    }
+}
+
+/// Keeps track of how often units of each creator end up contributing data to finalized
+/// batches, so that systematic unfairness in ordering can be detected.
+///
+/// Data is remembered for [`DATA_RETENTION_ROUNDS`] rounds after it was first finalized, data
+/// repeated after a longer time counts as new.
+pub(crate) struct InclusionAudit<H: Hasher> {
+    per_creator: NodeMap<CreatorInclusion>,
+    // The hashes of finalized data together with the round of the unit that first contained it.
+    finalized_data: HashMap<H::Hash, Round>,
+    pruned_below: Round,
+}
+
+impl<H: Hasher> InclusionAudit<H> {
+    pub(crate) fn new(n_members: NodeCount) -> Self {
+        let mut per_creator = NodeMap::with_size(n_members);
+        for creator in n_members.into_iterator() {
+            per_creator.insert(creator, CreatorInclusion::default());
+        }
+        InclusionAudit {
+            per_creator,
+            finalized_data: HashMap::new(),
+            pruned_below: 0,
+        }
+    }
+
+    /// Registers an ordered unit of `creator` of the given round with the given data.
+    pub(crate) fn on_ordered_unit<D: Data>(
+        &mut self,
+        creator: NodeIndex,
+        round: Round,
+        data: Option<&D>,
+    ) {
+        if creator.0 >= self.per_creator.size().0 {
+            return;
+        }
+        let mut inclusion = *self
+            .per_creator
+            .get(creator)
+            .expect("all creators are present");
+        inclusion.ordered_units += 1;
+        match data {
+            None => inclusion.empty += 1,
+            Some(data) => match self.finalized_data.entry(data.using_encoded(H::hash)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(round);
+                    inclusion.new_data += 1;
+                }
+                Entry::Occupied(_) => inclusion.duplicated_data += 1,
+            },
+        }
+        self.per_creator.insert(creator, inclusion);
+        self.prune(round);
+    }
+
+    // Forgets data finalized more than DATA_RETENTION_ROUNDS rounds ago, every so many rounds
+    // so that it does not take time proportional to the remembered data for every unit.
+    fn prune(&mut self, round: Round) {
+        let threshold = round.saturating_sub(DATA_RETENTION_ROUNDS);
+        if threshold < self.pruned_below + DATA_RETENTION_ROUNDS {
+            return;
+        }
+        self.finalized_data
+            .retain(|_, finalized_at| *finalized_at >= threshold);
+        self.pruned_below = threshold;
+    }
+
+    pub(crate) fn inclusion(&self) -> Vec<CreatorInclusion> {
+        self.per_creator.values().copied().collect()
+    }
+
+    /// Creators whose units were ordered less than half as often as those of the best creator.
+    pub(crate) fn lagging_creators(&self) -> Vec<NodeIndex> {
+        let max_ordered = self
+            .per_creator
+            .iter()
+            .map(|inclusion| inclusion.ordered_units)
+            .max()
+            .unwrap_or(0);
+        self.per_creator
+            .iter()
+            .filter(|(_, inclusion)| 2 * inclusion.ordered_units < max_ordered)
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+impl<H: Hasher> fmt::Display for InclusionAudit<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "inclusion (ordered/new data/duplicated data/empty) - [")?;
+        let mut it = self.per_creator.iter().peekable();
+        while let Some((id, inclusion)) = it.next() {
+            write!(f, "({}, {})", id.0, inclusion)?;
+            if it.peek().is_some() {
+                write!(f, ", ")?;
+            }
+        }
+        write!(f, "]")?;
+        let lagging = self.lagging_creators();
+        if !lagging.is_empty() {
+            write!(f, "; lagging creators - {:?}", lagging)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CreatorInclusion, InclusionAudit, DATA_RETENTION_ROUNDS};
+    use crate::{NodeCount, NodeIndex};
+    use aleph_bft_mock::{Data, Hasher64};
+
+    #[test]
+    fn counts_inclusion_per_creator() {
+        let mut audit = InclusionAudit::<Hasher64>::new(NodeCount(3));
+        audit.on_ordered_unit::<Data>(NodeIndex(0), 0, Some(&1));
+        audit.on_ordered_unit::<Data>(NodeIndex(1), 0, Some(&1));
+        audit.on_ordered_unit::<Data>(NodeIndex(1), 1, None);
+        audit.on_ordered_unit::<Data>(NodeIndex(0), 1, Some(&2));
+        audit.on_ordered_unit::<Data>(NodeIndex(7), 1, Some(&3));
+
+        assert_eq!(
+            audit.inclusion(),
+            vec![
+                CreatorInclusion {
+                    ordered_units: 2,
+                    new_data: 2,
+                    duplicated_data: 0,
+                    empty: 0,
+                },
+                CreatorInclusion {
+                    ordered_units: 2,
+                    new_data: 0,
+                    duplicated_data: 1,
+                    empty: 1,
+                },
+                CreatorInclusion::default(),
+            ]
+        );
+        assert_eq!(audit.lagging_creators(), vec![NodeIndex(2)]);
+    }
+
+    #[test]
+    fn forgets_old_data() {
+        let mut audit = InclusionAudit::<Hasher64>::new(NodeCount(2));
+        audit.on_ordered_unit::<Data>(NodeIndex(0), 0, Some(&1));
+        audit.on_ordered_unit::<Data>(NodeIndex(0), DATA_RETENTION_ROUNDS + 1, Some(&2));
+        audit.on_ordered_unit::<Data>(NodeIndex(1), 2 * DATA_RETENTION_ROUNDS, None);
+        assert_eq!(audit.finalized_data.len(), 1);
+        audit.on_ordered_unit::<Data>(NodeIndex(1), 2 * DATA_RETENTION_ROUNDS, Some(&1));
+        audit.on_ordered_unit::<Data>(NodeIndex(1), 2 * DATA_RETENTION_ROUNDS, Some(&2));
+        assert_eq!(audit.inclusion()[1].new_data, 1);
+        assert_eq!(audit.inclusion()[1].duplicated_data, 1);
+    }
+}
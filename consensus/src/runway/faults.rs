@@ -0,0 +1,115 @@
+use crate::{NodeCount, NodeIndex, NodeMap, Round};
+
+/// How many rounds the units of a node may lag behind the Dag before the node counts as not
+/// contributing. Honest nodes on slow links usually lag a round or two.
+const SILENCE_ROUNDS: Round = 5;
+
+/// A warning that so many nodes stopped contributing units that a single further fault would
+/// make the session lose liveness, see [`LocalIO::enable_fault_alarms`].
+///
+/// [`LocalIO::enable_fault_alarms`]: crate::LocalIO::enable_fault_alarms
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FaultAlarm {
+    /// The nodes whose units lag far behind the Dag.
+    pub silent: Vec<NodeIndex>,
+    /// How many faulty nodes the session tolerates.
+    pub tolerated: NodeCount,
+}
+
+/// Estimates how many nodes are faulty, i.e. do not contribute units anymore, from the highest
+/// rounds of their units in the Dag, raising an alarm once the estimate reaches the number of
+/// tolerated faults.
+pub(crate) struct FaultEstimator {
+    n_members: NodeCount,
+    top_rounds: NodeMap<Round>,
+    silent: Vec<NodeIndex>,
+    alarmed: bool,
+}
+
+impl FaultEstimator {
+    pub(crate) fn new(n_members: NodeCount) -> Self {
+        FaultEstimator {
+            n_members,
+            top_rounds: NodeMap::with_size(n_members),
+            silent: Vec::new(),
+            alarmed: false,
+        }
+    }
+
+    /// Registers a unit of the creator added to the Dag.
+    pub(crate) fn on_unit_in_dag(&mut self, creator: NodeIndex, round: Round) {
+        if self
+            .top_rounds
+            .get(creator)
+            .map_or(true, |top| *top < round)
+        {
+            self.top_rounds.insert(creator, round);
+        }
+    }
+
+    /// Estimates the faults once the Dag reached the round, returning an alarm if the estimate
+    /// has just reached the number of tolerated faults.
+    pub(crate) fn on_round_reached(&mut self, round: Round) -> Option<FaultAlarm> {
+        let silent_below = round.checked_sub(SILENCE_ROUNDS)?;
+        self.silent = (0..self.n_members.0)
+            .map(NodeIndex)
+            .filter(|node| {
+                self.top_rounds
+                    .get(*node)
+                    .map_or(true, |top| *top < silent_below)
+            })
+            .collect();
+        let tolerated = self.n_members.max_faulty();
+        let alarming = !self.silent.is_empty() && self.silent.len() >= tolerated.0;
+        let alarm = (alarming && !self.alarmed).then(|| FaultAlarm {
+            silent: self.silent.clone(),
+            tolerated,
+        });
+        self.alarmed = alarming;
+        alarm
+    }
+
+    /// The nodes estimated as faulty as of the last round reached.
+    pub(crate) fn silent(&self) -> &[NodeIndex] {
+        &self.silent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FaultAlarm, FaultEstimator};
+    use crate::{NodeCount, NodeIndex};
+
+    #[test]
+    fn alarms_once_tolerance_is_reached() {
+        let mut estimator = FaultEstimator::new(NodeCount(7));
+        // Node 6 stops creating units after round 2, node 5 after round 6.
+        for round in 0..=12 {
+            let creators = match round {
+                0..=2 => 7,
+                3..=6 => 6,
+                _ => 5,
+            };
+            for creator in 0..creators {
+                estimator.on_unit_in_dag(NodeIndex(creator), round);
+            }
+        }
+        assert_eq!(estimator.on_round_reached(7), None);
+        assert!(estimator.silent().is_empty());
+        assert_eq!(estimator.on_round_reached(8), None);
+        assert_eq!(estimator.silent(), &[NodeIndex(6)]);
+        assert_eq!(
+            estimator.on_round_reached(12),
+            Some(FaultAlarm {
+                silent: vec![NodeIndex(5), NodeIndex(6)],
+                tolerated: NodeCount(2),
+            })
+        );
+        // The alarm is raised again only after the estimate dropped below the tolerance.
+        assert_eq!(estimator.on_round_reached(12), None);
+        estimator.on_unit_in_dag(NodeIndex(5), 12);
+        assert_eq!(estimator.on_round_reached(12), None);
+        assert_eq!(estimator.silent(), &[NodeIndex(6)]);
+        assert!(estimator.on_round_reached(20).is_some());
+    }
+}
@@ -1,40 +1,69 @@
 use crate::{
     alerts::{self, Alert, AlertConfig, ForkProof, ForkingNotification, NetworkMessage},
-    consensus, handle_task_termination,
+    audit::Acknowledger,
+    budget::{BudgetEvent, BudgetTracker},
+    compaction::CompactionReport,
+    consensus,
+    dag_export::render_dot,
+    handle_task_termination,
+    key_rotation::KeySchedule,
     member::UnitMessage,
+    stats::{ConfigRecommendation, Stats, StatsCollector, SummaryOutput},
+    terminal::GrowthRuleViolation,
     units::{
-        ControlHash, PreUnit, SignedUnit, UncheckedSignedUnit, Unit, UnitCoord, UnitStore,
-        UnitStoreStatus, Validator,
+        ControlHash, FullUnit, PreUnit, RejectionReason, SignedUnit, UncheckedSignedUnit, Unit,
+        UnitCoord, UnitRejection, UnitStore, UnitStoreStatus, UnitVrf, ValidationError, Validator,
     },
-    Config, Data, DataProvider, FinalizationHandler, Hasher, Index, Keychain, MultiKeychain,
-    NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Signature, Signed, SpawnHandle,
-    Terminator, UncheckedSigned,
+    BatchDependencies, Config, Data, DataProvider, DelayConfig, FinalizationAcknowledgement,
+    FinalizationHandler, ForkerDataPolicy, Hasher, Health, Index, InvariantPolicy,
+    InvariantViolation, Keychain, MultiKeychain, NodeCount, NodeIndex, NodeMap, OrderingLog,
+    Quarantine, Receiver, Round, Sender, Signature, SignatureError, Signed, SpawnHandle,
+    Terminator, UncheckedSigned, ValidationProfile,
 };
 use aleph_bft_types::Recipient;
+use codec::Encode;
 use futures::{
     channel::{mpsc, oneshot},
-    pin_mut, Future, FutureExt, StreamExt,
+    future::BoxFuture,
+    pin_mut,
+    stream::FuturesUnordered,
+    Future, FutureExt, StreamExt,
 };
 use futures_timer::Delay;
 use log::{debug, error, info, trace, warn};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     fmt,
     io::{Read, Write},
     marker::PhantomData,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 mod backup;
 mod collection;
+mod evidence;
+mod fairness;
+mod faults;
 mod packer;
+mod payload_check;
 
+pub use backup::{migrate_backup, LoaderError};
+pub(crate) use backup::{BroadcastBackup, BroadcastStatus};
 use backup::{UnitLoader, UnitSaver};
 #[cfg(feature = "initial_unit_collection")]
 use collection::{Collection, IO as CollectionIO};
 pub use collection::{NewestUnitResponse, Salt};
+pub use evidence::GrowthRuleEvidence;
+pub use fairness::CreatorInclusion;
+use fairness::InclusionAudit;
+pub use faults::FaultAlarm;
+use faults::FaultEstimator;
 use packer::Packer;
+pub use payload_check::PayloadCheck;
+
+/// The maximal encoded size of our newest unit for it to be piggybacked on sync responses.
+const MAX_FRONTIER_UNIT_SIZE: usize = 1024;
 
 /// Type for incoming notifications: Runway to Consensus.
 #[derive(Clone, Eq, PartialEq)]
@@ -44,6 +73,11 @@ pub(crate) enum NotificationIn<H: Hasher> {
     NewUnits(Vec<Unit<H>>),
     /// Response to a request to decode parents when the control hash is wrong.
     UnitParents(H::Hash, Vec<H::Hash>),
+    /// The payloads of the units with the given hashes are being checked. Always sent before
+    /// the units themselves.
+    PayloadsPending(Vec<H::Hash>),
+    /// The result of checking the payload of the unit with the given hash.
+    PayloadChecked(H::Hash, bool),
 }
 
 /// Type for outgoing notifications: Consensus to Runway.
@@ -59,6 +93,8 @@ pub(crate) enum NotificationOut<H: Hasher> {
     WrongControlHash(H::Hash),
     /// Notification that a new unit has been added to the DAG, list of decoded parents provided
     AddedToDag(H::Hash, Vec<H::Hash>),
+    /// Notification that a unit was rejected, because its parents violate the growth rule.
+    GrowthRuleViolated(H::Hash, NodeMap<H::Hash>, GrowthRuleViolation),
 }
 
 /// Possible requests for information from other nodes.
@@ -68,9 +104,18 @@ pub enum Request<H: Hasher> {
     NewestUnit(Salt),
 }
 
+/// Sync responses carry an optional frontier unit -- the newest unit of the responder, so that
+/// lagging nodes learn about it without additional requests.
 pub(crate) enum Response<H: Hasher, D: Data, S: Signature> {
-    Coord(UncheckedSignedUnit<H, D, S>),
-    Parents(H::Hash, Vec<UncheckedSignedUnit<H, D, S>>),
+    Coord(
+        UncheckedSignedUnit<H, D, S>,
+        Option<UncheckedSignedUnit<H, D, S>>,
+    ),
+    Parents(
+        H::Hash,
+        Vec<UncheckedSignedUnit<H, D, S>>,
+        Option<UncheckedSignedUnit<H, D, S>>,
+    ),
     NewestUnit(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
 }
 
@@ -81,6 +126,8 @@ pub(crate) enum RunwayNotificationOut<H: Hasher, D: Data, S: Signature> {
     NewAnyUnit(UncheckedSignedUnit<H, D, S>),
     Request(Request<H>),
     Response(Response<H, D, S>, NodeIndex),
+    /// Notification for the node that we do not serve units below the round anymore.
+    HistoryPruned(Round, NodeIndex),
 }
 
 pub(crate) enum RunwayNotificationIn<H: Hasher, D: Data, S: Signature> {
@@ -103,9 +150,11 @@ impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
             UnitMessage::RequestParents(node_id, u_hash) => {
                 RunwayNotificationIn::Request(Request::Parents(u_hash), node_id)
             }
-            UnitMessage::ResponseCoord(u) => RunwayNotificationIn::Response(Response::Coord(u)),
-            UnitMessage::ResponseParents(u_hash, parents) => {
-                RunwayNotificationIn::Response(Response::Parents(u_hash, parents))
+            UnitMessage::ResponseCoord(u, frontier) => {
+                RunwayNotificationIn::Response(Response::Coord(u, frontier))
+            }
+            UnitMessage::ResponseParents(u_hash, parents, frontier) => {
+                RunwayNotificationIn::Response(Response::Parents(u_hash, parents, frontier))
             }
             UnitMessage::RequestNewest(node_id, salt) => {
                 RunwayNotificationIn::Request(Request::NewestUnit(salt), node_id)
@@ -113,6 +162,10 @@ impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
             UnitMessage::ResponseNewest(response) => {
                 RunwayNotificationIn::Response(Response::NewestUnit(response))
             }
+            UnitMessage::UnitAck(_)
+            | UnitMessage::TopRounds(_)
+            | UnitMessage::DuplicateHint(_)
+            | UnitMessage::HistoryPruned(_) => return Err(()),
         };
         Ok(result)
     }
@@ -136,7 +189,7 @@ where
     store: UnitStore<H, D, MK>,
     keychain: MK,
     validator: Validator<MK>,
-    alerts_for_alerter: Sender<Alert<H, D, MK::Signature>>,
+    alerts_for_alerter: Option<Sender<Alert<H, D, MK::Signature>>>,
     notifications_from_alerter: Receiver<ForkingNotification<H, D, MK::Signature>>,
     unit_messages_from_network: Receiver<RunwayNotificationIn<H, D, MK::Signature>>,
     unit_messages_for_network: Sender<RunwayNotificationOut<H, D, MK::Signature>>,
@@ -149,6 +202,35 @@ where
     unit_saver: UnitSaver<US, H, D, MK::Signature>,
     preunits_for_packer: Sender<PreUnit<H>>,
     signed_units_from_packer: Receiver<SignedUnit<H, D, MK>>,
+    memory_budget: BudgetTracker,
+    memory_cap: Option<usize>,
+    inclusion_audit: InclusionAudit<H>,
+    fault_estimator: FaultEstimator,
+    fault_alarms: Option<Sender<FaultAlarm>>,
+    stats: StatsCollector,
+    delay_config: DelayConfig,
+    // The first evidence of breaking the growth rule of every creator.
+    growth_rule_evidence: NodeMap<GrowthRuleEvidence<H, D, MK::Signature>>,
+    payload_checks: Option<Sender<PayloadCheck<D>>>,
+    payload_verdicts: FuturesUnordered<BoxFuture<'static, (H::Hash, bool)>>,
+    forker_data: ForkerDataPolicy,
+    request_ttl: Option<Round>,
+    history_limit: Option<Round>,
+    dag_export_requests: Receiver<oneshot::Sender<String>>,
+    compaction_requests: Receiver<oneshot::Sender<CompactionReport>>,
+    // The heads of the finalized rounds, only kept when the Dag can be exported.
+    heads: Option<HashSet<H::Hash>>,
+    quarantine: Quarantine<FullUnit<H, D>, MK::Signature>,
+    acknowledger: Option<Acknowledger<H>>,
+    pending_acknowledgements: FuturesUnordered<BoxFuture<'static, FinalizationAcknowledgement>>,
+    batch_dependencies: Option<Sender<BatchDependencies>>,
+    rejections: Option<Sender<UnitRejection>>,
+    session_summary: SummaryOutput,
+    health: Health,
+    invariant_policy: InvariantPolicy,
+    last_head_round: Option<Round>,
+    // Set after an invariant violation with the observe policy.
+    observing: bool,
     exiting: bool,
 }
 
@@ -156,6 +238,8 @@ struct RunwayStatus<'a, H: Hasher> {
     status: UnitStoreStatus<'a>,
     missing_coords: &'a HashSet<UnitCoord>,
     missing_parents: &'a HashSet<H::Hash>,
+    inclusion_audit: &'a InclusionAudit<H>,
+    growth_rule_violators: Vec<NodeIndex>,
 }
 
 impl<'a, H: Hasher> RunwayStatus<'a, H> {
@@ -163,11 +247,15 @@ impl<'a, H: Hasher> RunwayStatus<'a, H> {
         status: UnitStoreStatus<'a>,
         missing_coords: &'a HashSet<UnitCoord>,
         missing_parents: &'a HashSet<H::Hash>,
+        inclusion_audit: &'a InclusionAudit<H>,
+        growth_rule_violators: Vec<NodeIndex>,
     ) -> Self {
         Self {
             status,
             missing_coords,
             missing_parents,
+            inclusion_audit,
+            growth_rule_violators,
         }
     }
 }
@@ -188,6 +276,14 @@ impl<'a, H: Hasher> fmt::Display for RunwayStatus<'a, H> {
         if !self.missing_parents.is_empty() {
             write!(f, "; missing parents - {:?}", self.missing_parents)?;
         }
+        if !self.growth_rule_violators.is_empty() {
+            write!(
+                f,
+                "; growth rule violators - {:?}",
+                self.growth_rule_violators
+            )?;
+        }
+        write!(f, "; {}", self.inclusion_audit)?;
         write!(f, ".")?;
         Ok(())
     }
@@ -197,7 +293,7 @@ struct RunwayConfig<H: Hasher, D: Data, US: Write, FH: FinalizationHandler<D>, M
     max_round: Round,
     finalization_handler: FH,
     unit_saver: UnitSaver<US, H, D, MK::Signature>,
-    alerts_for_alerter: Sender<Alert<H, D, MK::Signature>>,
+    alerts_for_alerter: Option<Sender<Alert<H, D, MK::Signature>>>,
     notifications_from_alerter: Receiver<ForkingNotification<H, D, MK::Signature>>,
     tx_consensus: Sender<NotificationIn<H>>,
     rx_consensus: Receiver<NotificationOut<H>>,
@@ -208,6 +304,24 @@ struct RunwayConfig<H: Hasher, D: Data, US: Write, FH: FinalizationHandler<D>, M
     resolved_requests: Sender<Request<H>>,
     preunits_for_packer: Sender<PreUnit<H>>,
     signed_units_from_packer: Receiver<SignedUnit<H, D, MK>>,
+    memory_budget: Option<usize>,
+    memory_cap: Option<usize>,
+    stats: Stats,
+    delay_config: DelayConfig,
+    payload_checks: Option<Sender<PayloadCheck<D>>>,
+    forker_data: ForkerDataPolicy,
+    request_ttl: Option<Round>,
+    history_limit: Option<Round>,
+    dag_export_requests: Option<Receiver<oneshot::Sender<String>>>,
+    compaction_requests: Option<Receiver<oneshot::Sender<CompactionReport>>>,
+    quarantine_capacity: usize,
+    acknowledger: Option<Acknowledger<H>>,
+    batch_dependencies: Option<Sender<BatchDependencies>>,
+    rejections: Option<Sender<UnitRejection>>,
+    fault_alarms: Option<Sender<FaultAlarm>>,
+    session_summary: SummaryOutput,
+    health: Health,
+    invariant_policy: InvariantPolicy,
 }
 
 impl<H, D, US, FH, MK> Runway<H, D, US, FH, MK>
@@ -235,8 +349,30 @@ where
             resolved_requests,
             preunits_for_packer,
             signed_units_from_packer,
+            memory_budget,
+            memory_cap,
+            stats,
+            delay_config,
+            payload_checks,
+            forker_data,
+            request_ttl,
+            history_limit,
+            dag_export_requests,
+            compaction_requests,
+            quarantine_capacity,
+            acknowledger,
+            batch_dependencies,
+            rejections,
+            fault_alarms,
+            session_summary,
+            health,
+            invariant_policy,
         } = config;
         let store = UnitStore::new(n_members, max_round);
+        let heads = dag_export_requests.as_ref().map(|_| HashSet::new());
+        // Without exports requested the channel is closed right away.
+        let dag_export_requests = dag_export_requests.unwrap_or_else(|| mpsc::unbounded().1);
+        let compaction_requests = compaction_requests.unwrap_or_else(|| mpsc::unbounded().1);
 
         Runway {
             store,
@@ -257,6 +393,32 @@ where
             responses_for_collection,
             preunits_for_packer,
             signed_units_from_packer,
+            memory_budget: BudgetTracker::new(memory_budget),
+            memory_cap,
+            inclusion_audit: InclusionAudit::new(n_members),
+            fault_estimator: FaultEstimator::new(n_members),
+            fault_alarms,
+            stats: StatsCollector::new(stats),
+            delay_config,
+            growth_rule_evidence: NodeMap::with_size(n_members),
+            payload_checks,
+            payload_verdicts: FuturesUnordered::new(),
+            forker_data,
+            request_ttl,
+            history_limit,
+            dag_export_requests,
+            compaction_requests,
+            heads,
+            quarantine: Quarantine::new(quarantine_capacity),
+            acknowledger,
+            pending_acknowledgements: FuturesUnordered::new(),
+            batch_dependencies,
+            rejections,
+            session_summary,
+            health,
+            invariant_policy,
+            last_head_round: None,
+            observing: false,
             exiting: false,
         }
     }
@@ -292,13 +454,15 @@ where
             },
 
             RunwayNotificationIn::Response(res) => match res {
-                Response::Coord(u) => {
+                Response::Coord(u, frontier) => {
                     trace!(target: "AlephBFT-runway", "{:?} Fetch response received {:?}.", self.index(), &u);
-                    self.on_unit_received(u, false)
+                    self.on_unit_received(u, false);
+                    self.on_frontier_unit(frontier);
                 }
-                Response::Parents(u_hash, parents) => {
+                Response::Parents(u_hash, parents, frontier) => {
                     trace!(target: "AlephBFT-runway", "{:?} Response parents received {:?}.", self.index(), u_hash);
-                    self.on_parents_response(u_hash, parents)
+                    self.on_parents_response(u_hash, parents);
+                    self.on_frontier_unit(frontier);
                 }
                 Response::NewestUnit(response) => {
                     trace!(target: "AlephBFT-runway", "{:?} Response newest unit received from {:?}.", self.index(), response.index());
@@ -311,8 +475,50 @@ where
         }
     }
 
+    fn on_frontier_unit(&mut self, frontier: Option<UncheckedSignedUnit<H, D, MK::Signature>>) {
+        if let Some(uu) = frontier {
+            trace!(target: "AlephBFT-runway", "{:?} Frontier unit received {:?}.", self.index(), &uu);
+            self.on_unit_received(uu, false);
+        }
+    }
+
     fn on_unit_received(&mut self, uu: UncheckedSignedUnit<H, D, MK::Signature>, alert: bool) {
-        match self.validator.validate_unit(uu) {
+        let hash = uu.as_signable().hash();
+        let result = self.validator.validate_unit(uu);
+        self.on_validated_unit(hash, result, alert);
+    }
+
+    // Verifies the signatures of all the units in one batch, which is much faster for many
+    // units, e.g. when catching up.
+    fn on_units_received(
+        &mut self,
+        uus: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+        alert: bool,
+    ) {
+        let hashes: Vec<_> = uus.iter().map(|uu| uu.as_signable().hash()).collect();
+        let results = self.validator.validate_units(uus);
+        for (hash, result) in hashes.into_iter().zip(results) {
+            self.on_validated_unit(hash, result, alert);
+        }
+    }
+
+    fn on_validated_unit(
+        &mut self,
+        hash: H::Hash,
+        result: Result<SignedUnit<H, D, MK>, ValidationError<H, D, MK::Signature>>,
+        alert: bool,
+    ) {
+        let duplicate = self.store.unit_by_hash(&hash).is_some();
+        self.stats.on_unit_received(duplicate);
+        self.add_validated_unit(result, alert);
+    }
+
+    fn add_validated_unit(
+        &mut self,
+        result: Result<SignedUnit<H, D, MK>, ValidationError<H, D, MK::Signature>>,
+        alert: bool,
+    ) {
+        match result {
             Ok(su) => {
                 self.resolve_missing_coord(&su.as_signable().coord());
                 if alert {
@@ -322,7 +528,42 @@ where
                     self.add_unit_to_store_unless_fork(su);
                 }
             }
-            Err(e) => warn!(target: "AlephBFT-member", "Received unit failing validation: {}", e),
+            // Units from alerts were already verified by the alerter.
+            Err(ValidationError::WrongSignature(unchecked)) if !alert => {
+                let error = SignatureError {
+                    signer: Some(unchecked.index()),
+                    unchecked,
+                };
+                match self.quarantine.insert(error, &self.keychain) {
+                    Ok(()) => {
+                        debug!(target: "AlephBFT-member", "{:?} Quarantined a unit signed with an unknown key.", self.index())
+                    }
+                    Err(e) => self.on_rejected_unit(ValidationError::from(e)),
+                }
+            }
+            Err(e) => self.on_rejected_unit(e),
+        }
+    }
+
+    fn on_rejected_unit(&mut self, error: ValidationError<H, D, MK::Signature>) {
+        warn!(target: "AlephBFT-member", "Received unit failing validation: {}", error);
+        let rejection = error.rejection();
+        self.stats.on_unit_rejected(rejection.reason);
+        if let Some(rejections) = &self.rejections {
+            // The application might not be interested in the reports anymore.
+            let _ = rejections.unbounded_send(rejection);
+        }
+    }
+
+    fn recheck_quarantine(&mut self) {
+        if self.quarantine.is_empty() {
+            return;
+        }
+        for result in self.quarantine.recheck(&self.keychain) {
+            let result = result
+                .map_err(ValidationError::from)
+                .and_then(|su| self.validator.validate_signed_unit(su));
+            self.add_validated_unit(result, false);
         }
     }
 
@@ -342,6 +583,11 @@ where
 
         if let Some(sv) = self.store.is_new_fork(full_unit) {
             let creator = full_unit.creator();
+            if self.alerts_for_alerter.is_none() {
+                // Without alerts the variant we saw first is the canonical one.
+                warn!(target: "AlephBFT-runway", "{:?} Ignoring a fork by {:?}, alerts are disabled.", self.index(), creator);
+                return;
+            }
             if !self.store.is_forker(creator) {
                 // We need to mark the forker if it is not known yet.
                 let proof = (su.into(), sv.into());
@@ -356,9 +602,14 @@ where
     }
 
     fn on_new_forker_detected(&mut self, forker: NodeIndex, proof: ForkProof<H, D, MK::Signature>) {
+        self.stats.on_fork_detected();
         let alerted_units = self.store.mark_forker(forker);
         let alert = self.form_alert(proof, alerted_units);
-        if self.alerts_for_alerter.unbounded_send(alert).is_err() {
+        let alerts_for_alerter = match &self.alerts_for_alerter {
+            Some(alerts_for_alerter) => alerts_for_alerter,
+            None => return,
+        };
+        if alerts_for_alerter.unbounded_send(alert).is_err() {
             warn!(target: "AlephBFT-runway", "{:?} Channel to alerter should be open", self.index());
             self.exiting = true;
         }
@@ -376,14 +627,65 @@ where
         )
     }
 
+    // Our newest unit to be piggybacked on a sync response, unless it is too big or it is the
+    // unit the response is about anyway.
+    fn frontier_unit(
+        &self,
+        responding_about: &H::Hash,
+    ) -> Option<UncheckedSignedUnit<H, D, MK::Signature>> {
+        self.store
+            .newest_unit(self.index())
+            .filter(|uu| uu.as_signable().hash() != *responding_about)
+            .filter(|uu| uu.encoded_size() <= MAX_FRONTIER_UNIT_SIZE)
+    }
+
+    /// Whether a request of the node concerning a unit of the given round is past its TTL.
+    fn is_obsolete_request(&self, requester: NodeIndex, round: Round) -> bool {
+        match (self.request_ttl, self.store.top_round(requester)) {
+            (Some(ttl), Some(top_round)) => top_round > round.saturating_add(ttl),
+            _ => false,
+        }
+    }
+
+    /// The lowest round of units we serve to peers, if it is above zero, see
+    /// [`Config::history_limit`].
+    ///
+    /// [`Config::history_limit`]: crate::Config::history_limit
+    fn history_floor(&self) -> Option<Round> {
+        let limit = self.history_limit?;
+        let top_round = self.store.top_round(self.index())?;
+        Some(top_round.saturating_sub(limit)).filter(|floor| *floor > 0)
+    }
+
+    /// Tells the node we do not serve units of the round, if we do not, because they are beyond
+    /// the history limit.
+    fn refuse_pruned(&mut self, node_id: NodeIndex, round: Round) -> bool {
+        match self.history_floor() {
+            Some(floor) if round < floor => {
+                trace!(target: "AlephBFT-runway", "{:?} Not serving units of round {:?} to {:?}, beyond the history limit.", self.index(), round, node_id);
+                self.send_message_for_network(RunwayNotificationOut::HistoryPruned(floor, node_id));
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn on_request_coord(&mut self, node_id: NodeIndex, coord: UnitCoord) {
         debug!(target: "AlephBFT-runway", "{:?} Received fetch request for coord {:?} from {:?}.", self.index(), coord, node_id);
+        if self.is_obsolete_request(node_id, coord.round()) {
+            trace!(target: "AlephBFT-runway", "{:?} Dropping an obsolete fetch request for coord {:?} from {:?}.", self.index(), coord, node_id);
+            return;
+        }
+        if self.refuse_pruned(node_id, coord.round()) {
+            return;
+        }
         let maybe_su = (self.store.unit_by_coord(coord)).cloned();
 
         if let Some(su) = maybe_su {
             trace!(target: "AlephBFT-runway", "{:?} Answering fetch request for coord {:?} from {:?}.", self.index(), coord, node_id);
+            let frontier = self.frontier_unit(&su.as_signable().hash());
             self.send_message_for_network(RunwayNotificationOut::Response(
-                Response::Coord(su.into()),
+                Response::Coord(su.into(), frontier),
                 node_id,
             ));
         } else {
@@ -393,6 +695,19 @@ where
 
     fn on_request_parents(&mut self, node_id: NodeIndex, u_hash: H::Hash) {
         debug!(target: "AlephBFT-runway", "{:?} Received parents request for hash {:?} from {:?}.", self.index(), u_hash, node_id);
+        if let Some(round) = self
+            .store
+            .unit_by_hash(&u_hash)
+            .map(|su| su.as_signable().round())
+        {
+            if self.is_obsolete_request(node_id, round) {
+                trace!(target: "AlephBFT-runway", "{:?} Dropping an obsolete parents request for hash {:?} from {:?}.", self.index(), u_hash, node_id);
+                return;
+            }
+            if self.refuse_pruned(node_id, round.saturating_sub(1)) {
+                return;
+            }
+        }
 
         if let Some(p_hashes) = self.store.get_parents(u_hash) {
             let p_hashes = p_hashes.clone();
@@ -411,8 +726,9 @@ where
                     return;
                 }
             }
+            let frontier = self.frontier_unit(&u_hash);
             self.send_message_for_network(RunwayNotificationOut::Response(
-                Response::Parents(u_hash, full_units),
+                Response::Parents(u_hash, full_units, frontier),
                 node_id,
             ));
         } else {
@@ -470,8 +786,13 @@ where
         }
 
         let mut p_hashes_node_map = NodeMap::with_size(self.node_count());
-        for (i, uu) in parents.into_iter().enumerate() {
-            let su = match self.validator.validate_unit(uu) {
+        for (i, result) in self
+            .validator
+            .validate_units(parents)
+            .into_iter()
+            .enumerate()
+        {
+            let su = match result {
                 Ok(su) => su,
                 Err(e) => {
                     warn!(target: "AlephBFT-runway", "{:?} In received parent response received a unit that does not pass validation: {}", self.index(), e);
@@ -524,6 +845,16 @@ where
 
     fn on_packed(&mut self, signed_unit: SignedUnit<H, D, MK>) {
         debug!(target: "AlephBFT-runway", "{:?} On create notification.", self.index());
+        if self.validator.profile() == ValidationProfile::Audit {
+            if let Err(e) = self.validator.validate_unit(signed_unit.clone().into()) {
+                error!(target: "AlephBFT-runway", "{:?} Created a unit failing validation: {}", self.index(), e);
+                self.on_invariant_violation(InvariantViolation::InvalidOwnUnit);
+            }
+        }
+        if self.observing {
+            debug!(target: "AlephBFT-runway", "{:?} Dropping a unit we created, as we are only observing.", self.index());
+            return;
+        }
         self.save_unit(signed_unit.clone().into());
         self.store.add_unit(signed_unit, false);
     }
@@ -538,11 +869,7 @@ where
                 }
             }
 
-            Units(units) => {
-                for uu in units {
-                    self.on_unit_received(uu, true);
-                }
-            }
+            Units(units) => self.on_units_received(units, true),
         }
     }
 
@@ -564,6 +891,12 @@ where
                 self.store.add_parents(h, p_hashes);
                 self.resolve_missing_parents(&h);
                 if let Some(su) = self.store.unit_by_hash(&h).cloned() {
+                    let (creator, round) = (su.as_signable().creator(), su.as_signable().round());
+                    self.fault_estimator.on_unit_in_dag(creator, round);
+                    if self.stats.on_unit_in_dag(creator, round) {
+                        self.estimate_faults(round);
+                        self.publish_stats();
+                    }
                     self.send_message_for_network(RunwayNotificationOut::NewAnyUnit(
                         su.clone().into(),
                     ));
@@ -576,8 +909,52 @@ where
                     }
                 } else {
                     error!(target: "AlephBFT-runway", "{:?} A unit already added to DAG is not in our store: {:?}.", self.index(), h);
+                    self.on_invariant_violation(InvariantViolation::UnitNotInStore);
                 }
             }
+            NotificationOut::GrowthRuleViolated(h, parents, violation) => {
+                self.on_growth_rule_violation(h, parents, violation);
+            }
+        }
+    }
+
+    fn on_growth_rule_violation(
+        &mut self,
+        u_hash: H::Hash,
+        parents: NodeMap<H::Hash>,
+        violation: GrowthRuleViolation,
+    ) {
+        use GrowthRuleViolation::*;
+        let unit = match self.store.unit_by_hash(&u_hash) {
+            Some(su) => su.clone().into_unchecked(),
+            None => {
+                error!(target: "AlephBFT-runway", "{:?} A unit rejected by the terminal is not in our store: {:?}.", self.index(), u_hash);
+                self.on_invariant_violation(InvariantViolation::UnitNotInStore);
+                return;
+            }
+        };
+        let (round, creator) = (unit.as_signable().round(), unit.as_signable().creator());
+        debug!(target: "AlephBFT-runway", "{:?} Unit of round {:?} created by {:?} violates the growth rule: {}.", self.index(), round, creator, violation);
+        let parent = match violation {
+            ParentFromWrongRound { parent, .. } | ParentFromWrongCreator { parent, .. } => parents
+                .get(parent)
+                .and_then(|p_hash| self.store.unit_by_hash(p_hash))
+                .map(|su| su.clone().into_unchecked()),
+            MissingOwnParent | NotEnoughParents { .. } => None,
+        };
+        let evidence = GrowthRuleEvidence::new(unit, parents, parent, violation);
+        let rejection = UnitRejection {
+            culprit: Some(creator),
+            reason: RejectionReason::WrongParents,
+            evidence: Some(evidence.encode()),
+        };
+        self.stats.on_unit_rejected(rejection.reason);
+        if let Some(rejections) = &self.rejections {
+            // The application might not be interested in the reports anymore.
+            let _ = rejections.unbounded_send(rejection);
+        }
+        if self.growth_rule_evidence.get(creator).is_none() {
+            self.growth_rule_evidence.insert(creator, evidence);
         }
     }
 
@@ -608,23 +985,135 @@ where
     }
 
     fn on_ordered_batch(&mut self, batch: Vec<H::Hash>) {
-        let data_iter: Vec<_> = batch
+        if let (Some(heads), Some(head)) = (&mut self.heads, batch.last()) {
+            heads.insert(*head);
+        }
+        self.check_head_round(batch.last());
+        if let Some(digest) = self
+            .acknowledger
+            .as_mut()
+            .and_then(|acknowledger| acknowledger.on_batch(&batch))
+        {
+            let keychain = self.keychain.clone();
+            self.pending_acknowledgements
+                .push(Acknowledger::<H>::acknowledge(digest, keychain).boxed());
+        }
+        let units: Vec<_> = batch
             .iter()
-            .filter_map(|h| {
-                self.store
+            .map(|h| {
+                let full_unit = self
+                    .store
                     .unit_by_hash(h)
                     .expect("Ordered units must be in store")
-                    .as_signable()
-                    .data()
-                    .clone()
+                    .as_signable();
+                self.inclusion_audit
+                    .on_ordered_unit(
+                        full_unit.creator(),
+                        full_unit.round(),
+                        full_unit.data().as_ref(),
+                    );
+                let creator = full_unit.creator();
+                if self.forker_data == ForkerDataPolicy::Exclude && self.store.is_forker(creator) {
+                    trace!(target: "AlephBFT-runway", "{:?} Excluding data of an ordered unit of forker {:?}.", self.keychain.index(), creator);
+                    return (creator, None);
+                }
+                (creator, full_unit.data().clone())
             })
             .collect();
 
-        for d in data_iter {
-            self.finalization_handler.data_finalized(d);
+        self.stats.on_ordered_batch(batch.len());
+        self.publish_stats();
+
+        self.finalization_handler.units_finalized(units);
+        self.send_batch_dependencies(&batch);
+        self.store.on_ordered(&batch);
+    }
+
+    fn send_batch_dependencies(&self, batch: &[H::Hash]) {
+        let batch_dependencies = match &self.batch_dependencies {
+            Some(batch_dependencies) => batch_dependencies,
+            None => return,
+        };
+        let units = batch
+            .iter()
+            .map(|hash| {
+                let full_unit = self
+                    .store
+                    .unit_by_hash(hash)
+                    .expect("Ordered units must be in store")
+                    .as_signable();
+                // Ordered units were added to the Dag, so their parents are known.
+                let parents = self.store.parents(hash).map_or(&[][..], |p| &p[..]);
+                (*hash, full_unit.creator(), full_unit.round(), parents)
+            })
+            .collect();
+        // The application might not be interested in the dependencies anymore.
+        let _ = batch_dependencies.unbounded_send(BatchDependencies::new::<H>(units));
+    }
+
+    fn check_head_round(&mut self, head: Option<&H::Hash>) {
+        let head = match head.and_then(|head| self.store.unit_by_hash(head)) {
+            Some(head) => head.as_signable().round(),
+            None => return,
+        };
+        match self.last_head_round.replace(head) {
+            Some(previous) if previous >= head => {
+                self.on_invariant_violation(InvariantViolation::HeadRoundNotIncreasing {
+                    previous,
+                    head,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn on_invariant_violation(&mut self, violation: InvariantViolation) {
+        error!(target: "AlephBFT-runway", "{:?} Internal invariant violated: {}.", self.index(), violation);
+        match self.invariant_policy {
+            InvariantPolicy::Log => {}
+            InvariantPolicy::Panic => panic!("internal invariant violated: {}", violation),
+            InvariantPolicy::Halt => {
+                self.health.on_halted(violation);
+                self.exiting = true;
+            }
+            InvariantPolicy::Observe => {
+                if !self.observing {
+                    info!(target: "AlephBFT-runway", "{:?} Continuing only as an observer.", self.index());
+                }
+                self.observing = true;
+            }
+        }
+    }
+
+    fn export_dag(&self, response: oneshot::Sender<String>) {
+        let heads = self.heads.clone().unwrap_or_default();
+        let units = self.store.units().map(|unit| {
+            let unit = unit.as_signable();
+            (unit.coord(), unit.hash(), self.store.parents(&unit.hash()))
+        });
+        // The requester might have given up waiting already.
+        let _ = response.send(render_dot::<H>(units, &heads));
+    }
+
+    fn estimate_faults(&mut self, round: Round) {
+        let alarm = self.fault_estimator.on_round_reached(round);
+        self.stats.on_silent_nodes(self.fault_estimator.silent());
+        let alarm = match alarm {
+            Some(alarm) => alarm,
+            None => return,
+        };
+        warn!(target: "AlephBFT-runway", "{:?} Nodes {:?} stopped contributing units, the session tolerates {} faulty nodes.", self.index(), alarm.silent, alarm.tolerated.0);
+        if let Some(fault_alarms) = &self.fault_alarms {
+            // The application might not be interested in the alarms anymore.
+            let _ = fault_alarms.unbounded_send(alarm);
         }
     }
 
+    fn publish_stats(&self) {
+        self.stats
+            .publish(self.store.byte_size(), &self.inclusion_audit.inclusion());
+    }
+
     fn send_message_for_network(
         &mut self,
         notification: RunwayNotificationOut<H, D, MK::Signature>,
@@ -654,22 +1143,117 @@ where
     }
 
     fn move_units_to_consensus(&mut self) {
-        let units_to_move = self
-            .store
-            .yield_buffer_units()
-            .into_iter()
-            .map(|su| su.as_signable().unit())
-            .collect();
+        let units = self.store.yield_buffer_units();
+        let pending = self.request_payload_checks(&units);
+        if !pending.is_empty() {
+            self.send_consensus_notification(NotificationIn::PayloadsPending(pending));
+        }
+        let units_to_move = units.iter().map(Unit::from).collect();
         self.send_consensus_notification(NotificationIn::NewUnits(units_to_move))
     }
 
+    // Sends the data of units created by other nodes to the application for checking, returns
+    // the hashes of the units that have to wait for the verdict.
+    fn request_payload_checks(&mut self, units: &[SignedUnit<H, D, MK>]) -> Vec<H::Hash> {
+        let mut pending = Vec::new();
+        if self.validator.profile() == ValidationProfile::Light {
+            return pending;
+        }
+        let payload_checks = match &self.payload_checks {
+            Some(payload_checks) => payload_checks,
+            None => return pending,
+        };
+        for su in units {
+            let full_unit = su.as_signable();
+            let data = match full_unit.data() {
+                Some(data) if full_unit.creator() != self.keychain.index() => data.clone(),
+                _ => continue,
+            };
+            let hash = full_unit.hash();
+            let (check, verdict) = PayloadCheck::new(data);
+            if payload_checks.unbounded_send(check).is_err() {
+                debug!(target: "AlephBFT-runway", "{:?} Payload checks are no longer received, accepting all payloads.", self.index());
+                self.payload_checks = None;
+                return Vec::new();
+            }
+            self.payload_verdicts
+                .push(verdict.map(move |valid| (hash, valid)).boxed());
+            pending.push(hash);
+        }
+        pending
+    }
+
+    /// Prunes the store to at most `cap` bytes, as far as possible.
+    fn compact(&mut self, cap: usize) -> CompactionReport {
+        let started = Instant::now();
+        let byte_size = self.store.byte_size();
+        let pruned_units = self.store.prune_to(cap);
+        let report = CompactionReport {
+            pruned_units,
+            reclaimed_bytes: byte_size.saturating_sub(self.store.byte_size()),
+            duration: started.elapsed(),
+        };
+        if pruned_units > 0 {
+            debug!(target: "AlephBFT-runway", "{:?} Pruned {} finalized units from the store, which now takes {} bytes.", self.index(), pruned_units, self.store.byte_size());
+        }
+        self.stats.on_compaction(&report);
+        report
+    }
+
+    fn on_compaction_request(&mut self, response: oneshot::Sender<CompactionReport>) {
+        let report = self.compact(0);
+        self.check_memory_budget();
+        // The requester might have given up waiting already.
+        let _ = response.send(report);
+    }
+
+    fn check_memory_budget(&mut self) {
+        if let Some(cap) = self.memory_cap {
+            if self.store.byte_size() > cap {
+                self.compact(cap);
+            }
+        }
+        match self.memory_budget.update(self.store.byte_size()) {
+            Some(BudgetEvent::Exceeded { used, limit }) => {
+                warn!(target: "AlephBFT-runway", "{:?} Units in store take {} bytes, exceeding the memory budget of {} bytes.", self.index(), used, limit);
+            }
+            Some(BudgetEvent::Recovered { used, limit }) => {
+                info!(target: "AlephBFT-runway", "{:?} Units in store take {} bytes, back within the memory budget of {} bytes.", self.index(), used, limit);
+            }
+            None => {}
+        }
+    }
+
+    fn growth_rule_violators(&self) -> Vec<NodeIndex> {
+        self.growth_rule_evidence
+            .iter()
+            .map(|(creator, _)| creator)
+            .collect()
+    }
+
     fn status_report(&self) {
         let runway_status: RunwayStatus<H> = RunwayStatus::new(
             self.store.get_status(),
             &self.missing_coords,
             &self.missing_parents,
+            &self.inclusion_audit,
+            self.growth_rule_violators(),
         );
         info!(target: "AlephBFT-runway", "{}", runway_status);
+        self.recommend_config();
+    }
+
+    fn recommend_config(&self) {
+        let snapshot = self.stats.snapshot();
+        let recommendation = match ConfigRecommendation::new(&snapshot, &self.delay_config) {
+            Some(recommendation) => recommendation,
+            None => return,
+        };
+        let round_duration = snapshot.average_round_duration.unwrap_or_default();
+        let throughput = snapshot
+            .average_bytes_per_round
+            .map(|bytes| bytes as f64 / round_duration.as_secs_f64().max(f64::EPSILON));
+        info!(target: "AlephBFT-runway", "{:?} Observed round duration {:?}, duplicate rate {:.2} and unit throughput {:.0} B/s, consider setting {}.", self.index(), round_duration, snapshot.duplicate_rate.unwrap_or_default(), throughput.unwrap_or_default(), recommendation);
     }
 
     async fn run(
@@ -722,17 +1306,28 @@ where
                 },
 
                 message = units_from_backup => match message {
-                    Ok(units) => {
-                        for u in units {
-                            self.on_unit_received(u, false);
-                        }
-                    },
+                    Ok(units) => self.on_units_received(units, false),
                     Err(e) => {
                         error!(target: "AlephBFT-runway", "{:?} Units message from backup channel closed: {:?}", index, e);
                         break;
                     },
                 },
 
+                response = self.dag_export_requests.select_next_some() => self.export_dag(response),
+
+                response = self.compaction_requests.select_next_some() => self.on_compaction_request(response),
+
+                acknowledgement = self.pending_acknowledgements.select_next_some() => {
+                    if let Some(acknowledger) = &self.acknowledger {
+                        acknowledger.publish(acknowledgement);
+                    }
+                },
+
+                verdict = self.payload_verdicts.select_next_some() => {
+                    let (hash, valid) = verdict;
+                    self.send_consensus_notification(NotificationIn::PayloadChecked(hash, valid));
+                },
+
                 batch = self.ordered_batch_rx.next() => match batch {
                     Some(batch) => self.on_ordered_batch(batch),
                     None => {
@@ -751,7 +1346,9 @@ where
                     self.exiting = true;
                 }
             };
+            self.recheck_quarantine();
             self.move_units_to_consensus();
+            self.check_memory_budget();
 
             if self.exiting {
                 debug!(target: "AlephBFT-runway", "{:?} Runway decided to exit.", index);
@@ -760,6 +1357,8 @@ where
             }
         }
 
+        let summary = self.stats.summary(self.node_count());
+        std::mem::take(&mut self.session_summary).emit(index, summary);
         debug!(target: "AlephBFT-runway", "{:?} Run ended.", index);
     }
 }
@@ -823,6 +1422,19 @@ pub struct RunwayIO<
     pub finalization_handler: FH,
     pub unit_saver: UnitSaver<US, H, D, S>,
     pub unit_loader: UnitLoader<UL, H, D, S>,
+    pub stats: Stats,
+    pub health: Health,
+    pub ordering_log: Option<OrderingLog>,
+    pub payload_checks: Option<Sender<PayloadCheck<D>>>,
+    pub dag_export_requests: Option<Receiver<oneshot::Sender<String>>>,
+    pub compaction_requests: Option<Receiver<oneshot::Sender<CompactionReport>>>,
+    pub acknowledgements: Option<(usize, Sender<FinalizationAcknowledgement>)>,
+    pub batch_dependencies: Option<Sender<BatchDependencies>>,
+    pub session_summary: SummaryOutput,
+    pub key_schedule: KeySchedule,
+    pub vrf: Option<UnitVrf>,
+    pub rejections: Option<Sender<UnitRejection>>,
+    pub fault_alarms: Option<Sender<FaultAlarm>>,
     _phantom: PhantomData<(H, D, S)>,
 }
 
@@ -841,12 +1453,38 @@ impl<
         finalization_handler: FH,
         unit_saver: US,
         unit_loader: UL,
+        stats: Stats,
+        health: Health,
+        ordering_log: Option<OrderingLog>,
+        payload_checks: Option<Sender<PayloadCheck<D>>>,
+        dag_export_requests: Option<Receiver<oneshot::Sender<String>>>,
+        compaction_requests: Option<Receiver<oneshot::Sender<CompactionReport>>>,
+        acknowledgements: Option<(usize, Sender<FinalizationAcknowledgement>)>,
+        batch_dependencies: Option<Sender<BatchDependencies>>,
+        session_summary: SummaryOutput,
+        key_schedule: KeySchedule,
+        vrf: Option<UnitVrf>,
+        rejections: Option<Sender<UnitRejection>>,
+        fault_alarms: Option<Sender<FaultAlarm>>,
     ) -> Self {
         RunwayIO {
             data_provider,
             finalization_handler,
             unit_saver: UnitSaver::new(unit_saver),
             unit_loader: UnitLoader::new(unit_loader),
+            stats,
+            health,
+            ordering_log,
+            payload_checks,
+            dag_export_requests,
+            compaction_requests,
+            acknowledgements,
+            batch_dependencies,
+            session_summary,
+            key_schedule,
+            vrf,
+            rejections,
+            fault_alarms,
             _phantom: PhantomData,
         }
     }
@@ -878,52 +1516,69 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
     let alert_config = AlertConfig {
         session_id: config.session_id,
         n_members: config.n_members,
+        limits: config.alert_limits.clone(),
+        key_schedule: runway_io.key_schedule.clone(),
     };
-    let alerter_terminator = terminator.add_offspring_connection("AlephBFT-alerter");
     let alerter_keychain = keychain.clone();
     let alert_messages_for_network = network_io.alert_messages_for_network;
     let alert_messages_from_network = network_io.alert_messages_from_network;
-    let alerter_handle = spawn_handle.spawn_essential("runway/alerter", async move {
-        alerts::run(
-            alerter_keychain,
-            alert_messages_for_network,
-            alert_messages_from_network,
-            alert_notifications_for_units,
-            alerts_from_units,
-            alert_config,
-            alerter_terminator,
-        )
-        .await;
-    });
-    let mut alerter_handle = alerter_handle.fuse();
+    let alerter_health = runway_io.health.clone();
+    let alerter_task =
+        terminator.add_offspring_task("AlephBFT-alerter", |alerter_terminator| async move {
+            alerts::run(
+                alerter_keychain,
+                alert_messages_for_network,
+                alert_messages_from_network,
+                alert_notifications_for_units,
+                alerts_from_units,
+                alert_config,
+                alerter_health,
+                alerter_terminator,
+            )
+            .await;
+        });
+    let mut alerter_handle = spawn_handle
+        .spawn_essential("runway/alerter", alerter_task)
+        .fuse();
 
-    let consensus_terminator = terminator.add_offspring_connection("AlephBFT-consensus");
     let consensus_config = config.clone();
     let consensus_spawner = spawn_handle.clone();
     let (starting_round_sender, starting_round) = oneshot::channel();
-
-    let consensus_handle = spawn_handle.spawn_essential("runway/consensus", async move {
-        consensus::run(
-            consensus_config,
-            consensus_stream,
-            consensus_sink,
-            ordered_batch_tx,
-            consensus_spawner,
-            starting_round,
-            consensus_terminator,
-        )
-        .await
-    });
-    let mut consensus_handle = consensus_handle.fuse();
+    let consensus_health = runway_io.health.clone();
+    let ordering_log = runway_io.ordering_log.clone();
+
+    let consensus_task =
+        terminator.add_offspring_task("AlephBFT-consensus", |consensus_terminator| async move {
+            consensus::run(
+                consensus_config,
+                consensus_stream,
+                consensus_sink,
+                ordered_batch_tx,
+                consensus_spawner,
+                starting_round,
+                consensus_health,
+                ordering_log,
+                consensus_terminator,
+            )
+            .await
+        });
+    let mut consensus_handle = spawn_handle
+        .spawn_essential("runway/consensus", consensus_task)
+        .fuse();
 
     let index = keychain.index();
-    let threshold = (keychain.node_count() * 2) / 3 + NodeCount(1);
+    let threshold = keychain.node_count().quorum();
     let validator = Validator::new(
         config.session_id,
         keychain.clone(),
         config.max_round,
         threshold,
-    );
+    )
+    .with_profile(config.validation)
+    .with_verification_cache(config.verification_cache_capacity)
+    .with_verification_threads(config.verification_threads)
+    .with_key_schedule(runway_io.key_schedule.clone())
+    .with_vrf(runway_io.vrf.clone());
     let (responses_for_collection, responses_from_runway) = mpsc::unbounded();
     let (unit_collections_sender, unit_collection_result) = oneshot::channel();
     let (loaded_units_tx, loaded_units_rx) = oneshot::channel();
@@ -967,8 +1622,28 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
         data_provider,
         finalization_handler,
         unit_saver,
+        stats,
+        health,
+        payload_checks,
+        dag_export_requests,
+        compaction_requests,
+        acknowledgements,
+        batch_dependencies,
+        session_summary,
+        key_schedule,
+        vrf,
+        rejections,
+        fault_alarms,
         ..
     } = runway_io;
+    let acknowledger = acknowledgements.map(|(interval, acknowledgements)| {
+        Acknowledger::new(
+            config.session_id,
+            keychain.index(),
+            interval,
+            acknowledgements,
+        )
+    });
     let (preunits_for_packer, preunits_from_runway) = mpsc::unbounded();
     let (signed_units_for_runway, signed_units_from_packer) = mpsc::unbounded();
 
@@ -977,7 +1652,8 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
             let runway_config = RunwayConfig {
                 finalization_handler,
                 unit_saver,
-                alerts_for_alerter,
+                // The alerter keeps running, but without our alerts it has nothing to do.
+                alerts_for_alerter: config.fork_alerts.then_some(alerts_for_alerter),
                 notifications_from_alerter,
                 tx_consensus,
                 rx_consensus,
@@ -989,36 +1665,56 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
                 max_round: config.max_round,
                 preunits_for_packer,
                 signed_units_from_packer,
+                memory_budget: config.resource_budget.memory,
+                memory_cap: config.resource_budget.memory_cap,
+                stats,
+                delay_config: config.delay_config.clone(),
+                payload_checks,
+                forker_data: config.forker_data,
+                request_ttl: config.request_ttl,
+                history_limit: config.history_limit,
+                dag_export_requests,
+                compaction_requests,
+                quarantine_capacity: config.quarantine_capacity,
+                acknowledger,
+                batch_dependencies,
+                rejections,
+                fault_alarms,
+                session_summary,
+                health,
+                invariant_policy: config.invariant_policy,
             };
-            let runway_terminator = terminator.add_offspring_connection("AlephBFT-runway");
             let validator = validator.clone();
             let keychain = keychain.clone();
             let runway = Runway::new(runway_config, keychain, validator);
 
-            async move { runway.run(loaded_units_rx, runway_terminator).await }
+            terminator.add_offspring_task("AlephBFT-runway", |runway_terminator| async move {
+                runway.run(loaded_units_rx, runway_terminator).await
+            })
         })
         .fuse();
     pin_mut!(runway_handle);
 
     let packer_handle = spawn_handle
         .spawn_essential("runway/packer", {
-            let packer_terminator = terminator.add_offspring_connection("AlephBFT-packer");
             let mut packer = Packer::new(
                 data_provider,
                 preunits_from_runway,
                 signed_units_for_runway,
                 keychain.clone(),
                 config.session_id,
-            );
+            )
+            .with_key_schedule(key_schedule)
+            .with_vrf(vrf);
 
-            async move {
+            terminator.add_offspring_task("AlephBFT-packer", |packer_terminator| async move {
                 match packer.run(packer_terminator).await {
                     Ok(()) => (),
                     Err(()) => {
                         debug!(target: "AlephBFT-runway", "{:?} Packer task terminated abnormally", index)
                     }
                 }
-            }
+            })
         })
         .fuse();
     pin_mut!(packer_handle);
@@ -1063,3 +1759,122 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
 
     debug!(target: "AlephBFT-runway", "{:?} Runway ended.", index);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Runway, RunwayConfig, UnitSaver};
+    use crate::{
+        stats::SummaryOutput,
+        testing::gen_config,
+        units::{ControlHash, FullUnit, PreUnit, Validator},
+        Health, InvariantPolicy, InvariantViolation, NodeCount, NodeIndex, NodeMap, SessionId,
+        Signed, Stats,
+    };
+    use aleph_bft_mock::{Data, FinalizationHandler, Hash64, Hasher64, Keychain};
+    use futures::channel::mpsc;
+
+    type TestRunway = Runway<Hasher64, Data, Vec<u8>, FinalizationHandler, Keychain>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+
+    fn runway(invariant_policy: InvariantPolicy, health: Health) -> TestRunway {
+        let config = gen_config(NodeIndex(0), N_MEMBERS);
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(0));
+        let validator = Validator::new(
+            config.session_id,
+            keychain.clone(),
+            config.max_round,
+            N_MEMBERS.quorum(),
+        );
+        let runway_config = RunwayConfig {
+            max_round: config.max_round,
+            finalization_handler: FinalizationHandler::new().0,
+            unit_saver: UnitSaver::new(Vec::new()),
+            alerts_for_alerter: None,
+            notifications_from_alerter: mpsc::unbounded().1,
+            tx_consensus: mpsc::unbounded().0,
+            rx_consensus: mpsc::unbounded().1,
+            unit_messages_from_network: mpsc::unbounded().1,
+            unit_messages_for_network: mpsc::unbounded().0,
+            responses_for_collection: mpsc::unbounded().0,
+            ordered_batch_rx: mpsc::unbounded().1,
+            resolved_requests: mpsc::unbounded().0,
+            preunits_for_packer: mpsc::unbounded().0,
+            signed_units_from_packer: mpsc::unbounded().1,
+            memory_budget: None,
+            memory_cap: None,
+            stats: Stats::new(),
+            delay_config: config.delay_config,
+            payload_checks: None,
+            forker_data: config.forker_data,
+            request_ttl: None,
+            history_limit: None,
+            dag_export_requests: None,
+            compaction_requests: None,
+            quarantine_capacity: config.quarantine_capacity,
+            acknowledger: None,
+            batch_dependencies: None,
+            rejections: None,
+            fault_alarms: None,
+            session_summary: SummaryOutput::default(),
+            health,
+            invariant_policy,
+        };
+        Runway::new(runway_config, keychain, validator)
+    }
+
+    /// Packs a unit of round 0 created by us and returns its hash.
+    async fn pack_own_unit(runway: &mut TestRunway) -> Hash64 {
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(0));
+        let preunit = PreUnit::new(
+            NodeIndex(0),
+            0,
+            ControlHash::new(&NodeMap::with_size(N_MEMBERS)),
+        );
+        let full_unit = FullUnit::new(preunit, Some(0), SessionId::from_number(0));
+        let hash = full_unit.hash();
+        runway.on_packed(Signed::sign(full_unit, &keychain).await);
+        hash
+    }
+
+    #[tokio::test]
+    async fn only_logs_by_default() {
+        let health = Health::new();
+        let mut runway = runway(InvariantPolicy::default(), health.clone());
+        runway.on_invariant_violation(InvariantViolation::UnitNotInStore);
+        assert!(!runway.exiting);
+        assert_eq!(health.report().halted, None);
+        let hash = pack_own_unit(&mut runway).await;
+        assert!(runway.store.unit_by_hash(&hash).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "internal invariant violated")]
+    fn panics_with_panic_policy() {
+        let mut runway = runway(InvariantPolicy::Panic, Health::new());
+        runway.on_invariant_violation(InvariantViolation::UnitNotInStore);
+    }
+
+    #[test]
+    fn halts_with_halt_policy() {
+        let health = Health::new();
+        let mut runway = runway(InvariantPolicy::Halt, health.clone());
+        runway.on_invariant_violation(InvariantViolation::UnitNotInStore);
+        assert!(runway.exiting);
+        assert_eq!(
+            health.report().halted,
+            Some(InvariantViolation::UnitNotInStore)
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_own_units_with_observe_policy() {
+        let health = Health::new();
+        let mut runway = runway(InvariantPolicy::Observe, health.clone());
+        runway.on_invariant_violation(InvariantViolation::UnitNotInStore);
+        assert!(!runway.exiting);
+        assert_eq!(health.report().halted, None);
+        let hash = pack_own_unit(&mut runway).await;
+        assert!(runway.store.unit_by_hash(&hash).is_none());
+    }
+}
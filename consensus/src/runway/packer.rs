@@ -1,5 +1,6 @@
 use crate::{
-    units::{FullUnit, PreUnit, SignedUnit},
+    key_rotation::KeySchedule,
+    units::{FullUnit, PreUnit, SignedUnit, UnitVrf},
     Data, DataProvider, Hasher, MultiKeychain, NodeIndex, Receiver, Sender, SessionId, Signed,
     Terminator,
 };
@@ -21,6 +22,8 @@ where
     signed_units_for_runway: Sender<SignedUnit<H, D, MK>>,
     keychain: MK,
     session_id: SessionId,
+    key_schedule: KeySchedule,
+    vrf: Option<UnitVrf>,
     _phantom: PhantomData<D>,
 }
 
@@ -44,10 +47,25 @@ where
             signed_units_for_runway,
             keychain,
             session_id,
+            key_schedule: KeySchedule::default(),
+            vrf: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Signs units with the keys of the epochs of their rounds.
+    pub fn with_key_schedule(self, key_schedule: KeySchedule) -> Self {
+        Packer {
+            key_schedule,
+            ..self
+        }
+    }
+
+    /// Proves the priorities of units with the VRF.
+    pub fn with_vrf(self, vrf: Option<UnitVrf>) -> Self {
+        Packer { vrf, ..self }
+    }
+
     fn index(&self) -> NodeIndex {
         self.keychain.index()
     }
@@ -66,8 +84,15 @@ where
             debug!(target: "AlephBFT-packer", "{:?} Received PreUnit.", self.index());
             let data = self.data_provider.get_data().await;
             debug!(target: "AlephBFT-packer", "{:?} Received data.", self.index());
-            let full_unit = FullUnit::new(preunit, data, self.session_id);
-            let signed_unit = Signed::sign(full_unit, &self.keychain).await;
+            let mut full_unit = FullUnit::new(preunit, data, self.session_id);
+            if let Some(vrf) = &self.vrf {
+                let proof = vrf.prove(self.session_id, full_unit.round()).await;
+                full_unit = full_unit.with_vrf_proof(proof);
+            }
+            let keychain = self
+                .key_schedule
+                .keychain(&self.keychain, full_unit.round());
+            let signed_unit = Signed::sign(full_unit, &keychain).await;
             if self
                 .signed_units_for_runway
                 .unbounded_send(signed_unit)
@@ -109,7 +134,7 @@ mod tests {
         pin_mut, FutureExt, StreamExt,
     };
 
-    const SESSION_ID: SessionId = 43;
+    const SESSION_ID: SessionId = SessionId::from_number(43);
     const NODE_ID: NodeIndex = NodeIndex(0);
     const N_MEMBERS: NodeCount = NodeCount(4);
 
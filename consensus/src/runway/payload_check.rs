@@ -0,0 +1,61 @@
+use crate::Data;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+
+/// A request to validate the data contained in a unit received from another node.
+///
+/// The checks are performed concurrently with everything else the unit has to wait for before
+/// being added to the Dag, e.g. fetching its parents. The unit is added to the Dag only once its
+/// data is accepted, so a unit whose data is rejected never gets ordered, and neither do any
+/// units built on top of it. Because of that the validation has to be deterministic, i.e. all
+/// honest nodes have to reach the same verdict for the same data, otherwise the protocol might
+/// stall. Dropping the check without deciding rejects the data.
+pub struct PayloadCheck<D: Data> {
+    data: D,
+    verdict: oneshot::Sender<bool>,
+}
+
+impl<D: Data> PayloadCheck<D> {
+    /// Creates a check for the data, together with a future resolving to the verdict.
+    pub(crate) fn new(data: D) -> (Self, BoxFuture<'static, bool>) {
+        let (verdict, verdict_rx) = oneshot::channel();
+        let verdict_rx = verdict_rx.map(|verdict| verdict.unwrap_or(false)).boxed();
+        (PayloadCheck { data, verdict }, verdict_rx)
+    }
+
+    /// The data to validate.
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    /// Marks the data as valid.
+    pub fn accept(self) {
+        // The unit might not be needed anymore, e.g. because the session ended.
+        let _ = self.verdict.send(true);
+    }
+
+    /// Marks the data as invalid.
+    pub fn reject(self) {
+        let _ = self.verdict.send(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PayloadCheck;
+
+    #[tokio::test]
+    async fn delivers_verdicts() {
+        let (check, verdict) = PayloadCheck::new(7u32);
+        assert_eq!(check.data(), &7);
+        check.accept();
+        assert!(verdict.await);
+
+        let (check, verdict) = PayloadCheck::new(7u32);
+        check.reject();
+        assert!(!verdict.await);
+
+        let (check, verdict) = PayloadCheck::new(7u32);
+        drop(check);
+        assert!(!verdict.await);
+    }
+}
@@ -0,0 +1,647 @@
+#[cfg(feature = "alloc_profiling")]
+use crate::{allocation_profile, AllocationProfile};
+use crate::{
+    runway::CreatorInclusion, CompactionReport, DelayConfig, NodeCount, NodeIndex, RejectionReason,
+    Round,
+};
+use futures::channel::oneshot;
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// A snapshot of statistics describing the observed performance of a running session, which the
+/// embedding application might use to adapt its behavior, e.g. the size of the provided data.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatsSnapshot {
+    /// The highest round of a unit in the local Dag.
+    pub current_round: Round,
+    /// The average time it took the local Dag to grow by one round.
+    pub average_round_duration: Option<Duration>,
+    /// The average number of units in a finalized batch.
+    pub average_units_per_batch: Option<f64>,
+    /// How long it recently took from a round appearing in the local Dag to it being finalized,
+    /// smoothed over a few batches.
+    pub finalization_latency: Option<Duration>,
+    /// The average number of bytes taken by the stored units per round.
+    pub average_bytes_per_round: Option<usize>,
+    /// The approximate number of bytes taken by the units in the store, see
+    /// [`ResourceBudget::memory_cap`].
+    ///
+    /// [`ResourceBudget::memory_cap`]: crate::ResourceBudget::memory_cap
+    pub store_bytes: usize,
+    /// How many finalized units were pruned from the store, to keep it within the cap or on
+    /// demand, see [`Compaction`].
+    ///
+    /// [`Compaction`]: crate::Compaction
+    pub pruned_units: usize,
+    /// How many bytes pruning the store reclaimed.
+    pub reclaimed_bytes: usize,
+    /// How much time pruning the store took.
+    pub compaction_time: Duration,
+    /// The fraction of units received from the network that were known already.
+    pub duplicate_rate: Option<f64>,
+    /// The nodes whose units lag far behind the Dag, so that they seem not to contribute
+    /// anymore, e.g. because they crashed or are cut off.
+    pub silent_nodes: Vec<NodeIndex>,
+    /// The number of faulty nodes observed, i.e. of the silent nodes. Once it exceeds a third
+    /// of the committee the session stalls.
+    pub effective_faults: usize,
+    /// How many units received from the network failed validation, by the reason.
+    pub rejected_units: BTreeMap<RejectionReason, usize>,
+    /// How the units of every creator contributed to the finalized batches.
+    pub inclusion: Vec<CreatorInclusion>,
+    /// The allocations made in the hot paths by all the sessions in the process.
+    #[cfg(feature = "alloc_profiling")]
+    pub allocations: AllocationProfile,
+}
+
+/// A report on a whole session, emitted once when it ends, see
+/// [`LocalIO::enable_session_summary`].
+///
+/// [`LocalIO::enable_session_summary`]: crate::LocalIO::enable_session_summary
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionSummary {
+    /// How long the session ran.
+    pub duration: Duration,
+    /// The highest round of a unit in the local Dag.
+    pub rounds: Round,
+    /// How many batches were finalized.
+    pub batches: usize,
+    /// How many units of every creator were added to the local Dag, indexed by the creators.
+    pub units_per_creator: Vec<usize>,
+    /// How many nodes were detected forking.
+    pub forks: usize,
+    /// The mean time from a round appearing in the local Dag to it being finalized.
+    pub mean_finalization_latency: Option<Duration>,
+    /// The median of that time.
+    pub p50_finalization_latency: Option<Duration>,
+    /// The 90th percentile of that time.
+    pub p90_finalization_latency: Option<Duration>,
+    /// The 99th percentile of that time.
+    pub p99_finalization_latency: Option<Duration>,
+    /// The encoded size of all the messages sent to the network.
+    pub bytes_sent: u64,
+    /// The encoded size of all the messages received from the network.
+    pub bytes_received: u64,
+}
+
+impl Display for SessionSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "duration={:?} rounds={} batches={} units_per_creator={:?} forks={}",
+            self.duration, self.rounds, self.batches, self.units_per_creator, self.forks
+        )?;
+        let latencies = [
+            ("mean", self.mean_finalization_latency),
+            ("p50", self.p50_finalization_latency),
+            ("p90", self.p90_finalization_latency),
+            ("p99", self.p99_finalization_latency),
+        ];
+        for (name, latency) in latencies {
+            if let Some(latency) = latency {
+                write!(f, " {}_finalization_latency={:?}", name, latency)?;
+            }
+        }
+        write!(
+            f,
+            " bytes_sent={} bytes_received={}",
+            self.bytes_sent, self.bytes_received
+        )
+    }
+}
+
+/// Where the [`SessionSummary`] goes when the session ends, besides the log.
+#[derive(Default)]
+pub(crate) struct SummaryOutput {
+    pub(crate) channel: Option<oneshot::Sender<SessionSummary>>,
+    pub(crate) file: Option<PathBuf>,
+}
+
+impl SummaryOutput {
+    pub(crate) fn emit(self, index: NodeIndex, summary: SessionSummary) {
+        info!(target: "AlephBFT-runway", "{:?} Session summary: {}.", index, summary);
+        if let Some(file) = &self.file {
+            if let Err(e) = std::fs::write(file, format!("{}\n", summary)) {
+                warn!(target: "AlephBFT-runway", "{:?} Could not write the session summary to {:?}: {}.", index, file, e);
+            }
+        }
+        if let Some(channel) = self.channel {
+            // The application might not be interested anymore.
+            let _ = channel.send(summary);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Traffic {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+/// A handle for querying the statistics of a session. All clones refer to the same statistics,
+/// so the application should keep a clone of the handle passed to the session.
+#[derive(Clone, Default)]
+pub struct Stats {
+    snapshot: Arc<Mutex<StatsSnapshot>>,
+    traffic: Arc<Traffic>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recent statistics.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.snapshot.lock().clone()
+    }
+
+    pub(crate) fn on_sent(&self, bytes: usize) {
+        self.traffic.sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_received(&self, bytes: usize) {
+        self.traffic
+            .received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// Gathers the raw data needed to compute the statistics and publishes them to [`Stats`].
+pub(crate) struct StatsCollector {
+    stats: Stats,
+    first_round: Option<(Round, Instant)>,
+    current_round: Option<(Round, Instant)>,
+    batches: usize,
+    ordered_units: usize,
+    received_units: usize,
+    duplicate_units: usize,
+    rejected_units: BTreeMap<RejectionReason, usize>,
+    silent_nodes: Vec<NodeIndex>,
+    pruned_units: usize,
+    reclaimed_bytes: usize,
+    compaction_time: Duration,
+    // When the rounds not finalized yet appeared in the Dag.
+    rounds_reached: VecDeque<(Round, Instant)>,
+    finalization_latency: Option<Duration>,
+    started: Instant,
+    units_per_creator: Vec<usize>,
+    forks: usize,
+    // The finalization latency of every batch, for the session summary.
+    latencies: Vec<Duration>,
+}
+
+/// The weight of the newest batch in the smoothed finalization latency.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+impl StatsCollector {
+    pub(crate) fn new(stats: Stats) -> Self {
+        StatsCollector {
+            stats,
+            first_round: None,
+            current_round: None,
+            batches: 0,
+            ordered_units: 0,
+            received_units: 0,
+            duplicate_units: 0,
+            rejected_units: BTreeMap::new(),
+            silent_nodes: Vec::new(),
+            pruned_units: 0,
+            reclaimed_bytes: 0,
+            compaction_time: Duration::ZERO,
+            rounds_reached: VecDeque::new(),
+            finalization_latency: None,
+            started: Instant::now(),
+            units_per_creator: Vec::new(),
+            forks: 0,
+            latencies: Vec::new(),
+        }
+    }
+
+    /// Registers a unit of the given creator and round added to the Dag. Returns whether the Dag
+    /// got higher.
+    pub(crate) fn on_unit_in_dag(&mut self, creator: NodeIndex, round: Round) -> bool {
+        if self.units_per_creator.len() <= creator.0 {
+            self.units_per_creator.resize(creator.0 + 1, 0);
+        }
+        self.units_per_creator[creator.0] += 1;
+        self.on_unit_in_dag_at(round, Instant::now())
+    }
+
+    fn on_unit_in_dag_at(&mut self, round: Round, now: Instant) -> bool {
+        match self.current_round {
+            Some((current, _)) if current >= round => false,
+            _ => {
+                self.first_round.get_or_insert((round, now));
+                self.current_round = Some((round, now));
+                self.rounds_reached.push_back((round, now));
+                true
+            }
+        }
+    }
+
+    /// Registers a finalized batch, the batches are finalized one per round.
+    pub(crate) fn on_ordered_batch(&mut self, n_units: usize) {
+        self.on_ordered_batch_at(n_units, Instant::now())
+    }
+
+    fn on_ordered_batch_at(&mut self, n_units: usize, now: Instant) {
+        let finalized_round = self.batches;
+        self.batches += 1;
+        self.ordered_units += n_units;
+        while let Some((round, _)) = self.rounds_reached.front() {
+            if usize::from(*round) >= finalized_round {
+                break;
+            }
+            self.rounds_reached.pop_front();
+        }
+        // If the Dag skipped the round, e.g. after loading the backup, it appeared together
+        // with the next one.
+        if let Some((_, reached)) = self.rounds_reached.front() {
+            let latency = now.saturating_duration_since(*reached);
+            self.latencies.push(latency);
+            self.finalization_latency = Some(match self.finalization_latency {
+                Some(previous) => {
+                    previous.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+                }
+                None => latency,
+            });
+        }
+    }
+
+    /// The statistics published most recently.
+    pub(crate) fn snapshot(&self) -> StatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Registers a unit received from the network, which might have been known already.
+    pub(crate) fn on_unit_received(&mut self, duplicate: bool) {
+        self.received_units += 1;
+        if duplicate {
+            self.duplicate_units += 1;
+        }
+    }
+
+    /// Registers a unit received from the network that failed validation.
+    pub(crate) fn on_unit_rejected(&mut self, reason: RejectionReason) {
+        *self.rejected_units.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Registers the nodes currently estimated as faulty.
+    pub(crate) fn on_silent_nodes(&mut self, silent_nodes: &[NodeIndex]) {
+        self.silent_nodes = silent_nodes.to_vec();
+    }
+
+    /// Registers pruning the store.
+    pub(crate) fn on_compaction(&mut self, report: &CompactionReport) {
+        self.pruned_units += report.pruned_units;
+        self.reclaimed_bytes += report.reclaimed_bytes;
+        self.compaction_time += report.duration;
+    }
+
+    /// Registers a node detected forking.
+    pub(crate) fn on_fork_detected(&mut self) {
+        self.forks += 1;
+    }
+
+    /// The report on the session so far.
+    pub(crate) fn summary(&self, n_members: NodeCount) -> SessionSummary {
+        let mut units_per_creator = self.units_per_creator.clone();
+        units_per_creator.resize(n_members.0.max(units_per_creator.len()), 0);
+        let mut latencies = self.latencies.clone();
+        latencies.sort_unstable();
+        SessionSummary {
+            duration: self.started.elapsed(),
+            rounds: self.current_round.map(|(round, _)| round).unwrap_or(0),
+            batches: self.batches,
+            units_per_creator,
+            forks: self.forks,
+            mean_finalization_latency: match latencies.len() {
+                0 => None,
+                count => Some(latencies.iter().sum::<Duration>() / count as u32),
+            },
+            p50_finalization_latency: percentile(&latencies, 50),
+            p90_finalization_latency: percentile(&latencies, 90),
+            p99_finalization_latency: percentile(&latencies, 99),
+            bytes_sent: self.stats.traffic.sent.load(Ordering::Relaxed),
+            bytes_received: self.stats.traffic.received.load(Ordering::Relaxed),
+        }
+    }
+
+    fn average_round_duration(&self) -> Option<Duration> {
+        let (first, started) = self.first_round?;
+        let (current, reached) = self.current_round?;
+        let rounds = current.checked_sub(first).filter(|rounds| *rounds > 0)?;
+        Some(reached.saturating_duration_since(started) / u32::from(rounds))
+    }
+
+    pub(crate) fn publish(&self, byte_size: usize, inclusion: &[CreatorInclusion]) {
+        let current_round = self.current_round.map(|(round, _)| round);
+        let snapshot = StatsSnapshot {
+            current_round: current_round.unwrap_or(0),
+            average_round_duration: self.average_round_duration(),
+            average_units_per_batch: match self.batches {
+                0 => None,
+                batches => Some(self.ordered_units as f64 / batches as f64),
+            },
+            finalization_latency: self.finalization_latency,
+            average_bytes_per_round: current_round
+                .map(|round| byte_size / (usize::from(round) + 1)),
+            store_bytes: byte_size,
+            pruned_units: self.pruned_units,
+            reclaimed_bytes: self.reclaimed_bytes,
+            compaction_time: self.compaction_time,
+            duplicate_rate: match self.received_units {
+                0 => None,
+                received => Some(self.duplicate_units as f64 / received as f64),
+            },
+            silent_nodes: self.silent_nodes.clone(),
+            effective_faults: self.silent_nodes.len(),
+            rejected_units: self.rejected_units.clone(),
+            inclusion: inclusion.to_vec(),
+            #[cfg(feature = "alloc_profiling")]
+            allocations: allocation_profile(),
+        };
+        *self.stats.snapshot.lock() = snapshot;
+    }
+}
+
+// The nearest-rank percentile of the sorted durations.
+fn percentile(sorted: &[Duration], percent: usize) -> Option<Duration> {
+    let rank = (sorted.len() * percent + 99) / 100;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// The minimal number of rounds needed to recommend anything.
+const MIN_ROUNDS: Round = 10;
+/// Above this duplicate rate units are rebroadcast too often.
+const HIGH_DUPLICATE_RATE: f64 = 0.5;
+/// Below this many rounds units are rebroadcast while they are still being gossiped anyway.
+const MIN_REBROADCAST_ROUNDS: u32 = 5;
+
+/// Values of the [`DelayConfig`] suggested by the observed performance of the session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ConfigRecommendation {
+    pub(crate) unit_creation_delay: Duration,
+    pub(crate) unit_rebroadcast_interval_min: Duration,
+    pub(crate) unit_rebroadcast_interval_max: Duration,
+}
+
+impl ConfigRecommendation {
+    /// Recommends values based on the statistics, or `None` if there is too little data or the
+    /// current values look fine.
+    ///
+    /// When rounds take much longer than the creation delay the session is limited by the network,
+    /// so creating units less often costs nothing, and the delay can be raised to the observed
+    /// round duration. When many received units are duplicates, or units are rebroadcast within a
+    /// few rounds, the rebroadcast interval is too short.
+    pub(crate) fn new(snapshot: &StatsSnapshot, delay_config: &DelayConfig) -> Option<Self> {
+        let round_duration = snapshot.average_round_duration?;
+        let duplicate_rate = snapshot.duplicate_rate?;
+        if snapshot.current_round < MIN_ROUNDS {
+            return None;
+        }
+        let creation_delay =
+            (delay_config.unit_creation_delay)(usize::from(snapshot.current_round));
+        let unit_creation_delay = match round_duration > 2 * creation_delay {
+            true => round_duration,
+            false => creation_delay,
+        };
+        let current_min = delay_config.unit_rebroadcast_interval_min;
+        let current_max = delay_config.unit_rebroadcast_interval_max;
+        let mut interval_min = current_min.max(round_duration * MIN_REBROADCAST_ROUNDS);
+        if duplicate_rate > HIGH_DUPLICATE_RATE {
+            interval_min = interval_min.max(2 * current_min);
+        }
+        let spread = match current_min.is_zero() {
+            true => 1.0,
+            false => current_max.as_secs_f64() / current_min.as_secs_f64(),
+        };
+        let recommendation = ConfigRecommendation {
+            unit_creation_delay,
+            unit_rebroadcast_interval_min: interval_min,
+            unit_rebroadcast_interval_max: interval_min.mul_f64(spread.max(1.0)),
+        };
+        let unchanged = unit_creation_delay == creation_delay && interval_min == current_min;
+        (!unchanged).then_some(recommendation)
+    }
+}
+
+impl Display for ConfigRecommendation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "unit_creation_delay {:?}, unit_rebroadcast_interval_min {:?}, unit_rebroadcast_interval_max {:?}",
+            self.unit_creation_delay,
+            self.unit_rebroadcast_interval_min,
+            self.unit_rebroadcast_interval_max
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigRecommendation, SessionSummary, Stats, StatsCollector, StatsSnapshot};
+    use crate::{testing::gen_config, CompactionReport, NodeCount, NodeIndex, RejectionReason};
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn starts_empty() {
+        let stats = Stats::new();
+        let collector = StatsCollector::new(stats.clone());
+        collector.publish(0, &[]);
+        assert_eq!(stats.snapshot(), StatsSnapshot::default());
+    }
+
+    #[test]
+    fn computes_averages() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        let start = Instant::now();
+        assert!(collector.on_unit_in_dag_at(0, start));
+        assert!(!collector.on_unit_in_dag_at(0, start + Duration::from_millis(50)));
+        assert!(collector.on_unit_in_dag_at(1, start + Duration::from_millis(100)));
+        assert!(collector.on_unit_in_dag_at(2, start + Duration::from_millis(200)));
+        assert!(!collector.on_unit_in_dag_at(1, start + Duration::from_millis(250)));
+        collector.on_ordered_batch(3);
+        collector.on_ordered_batch(5);
+        collector.publish(300, &[]);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.current_round, 2);
+        assert_eq!(
+            snapshot.average_round_duration,
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(snapshot.average_units_per_batch, Some(4.0));
+        assert_eq!(snapshot.average_bytes_per_round, Some(100));
+        assert_eq!(snapshot.store_bytes, 300);
+    }
+
+    #[test]
+    fn counts_rejected_units() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        collector.on_unit_rejected(RejectionReason::WrongSession);
+        collector.on_unit_rejected(RejectionReason::BadSignature);
+        collector.on_unit_rejected(RejectionReason::WrongSession);
+        collector.publish(0, &[]);
+        assert_eq!(
+            stats.snapshot().rejected_units,
+            BTreeMap::from([
+                (RejectionReason::BadSignature, 1),
+                (RejectionReason::WrongSession, 2),
+            ])
+        );
+    }
+
+    #[test]
+    fn counts_silent_nodes_as_faults() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        collector.on_silent_nodes(&[NodeIndex(1), NodeIndex(3)]);
+        collector.publish(0, &[]);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.silent_nodes, vec![NodeIndex(1), NodeIndex(3)]);
+        assert_eq!(snapshot.effective_faults, 2);
+    }
+
+    #[test]
+    fn sums_compactions() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        collector.on_compaction(&CompactionReport {
+            pruned_units: 3,
+            reclaimed_bytes: 300,
+            duration: Duration::from_millis(2),
+        });
+        collector.on_compaction(&CompactionReport {
+            pruned_units: 1,
+            reclaimed_bytes: 50,
+            duration: Duration::from_millis(1),
+        });
+        collector.publish(0, &[]);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.pruned_units, 4);
+        assert_eq!(snapshot.reclaimed_bytes, 350);
+        assert_eq!(snapshot.compaction_time, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn measures_finalization_latency() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        let start = Instant::now();
+        for round in 0..4 {
+            collector.on_unit_in_dag_at(round, start + Duration::from_millis(100) * round.into());
+        }
+        collector.on_ordered_batch_at(2, start + Duration::from_millis(300));
+        collector.publish(0, &[]);
+        assert_eq!(
+            stats.snapshot().finalization_latency,
+            Some(Duration::from_millis(300))
+        );
+
+        // Round 1 took 400ms, the average moves towards it.
+        collector.on_ordered_batch_at(2, start + Duration::from_millis(500));
+        collector.publish(0, &[]);
+        let latency = stats
+            .snapshot()
+            .finalization_latency
+            .expect("batches were finalized");
+        assert!((latency.as_secs_f64() - 0.32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn summarizes_session() {
+        let stats = Stats::new();
+        let mut collector = StatsCollector::new(stats.clone());
+        let start = Instant::now();
+        for round in 0..4 {
+            collector.on_unit_in_dag_at(round, start + Duration::from_millis(100) * round.into());
+        }
+        for round in 0..4 {
+            collector.on_unit_in_dag(NodeIndex(1), round);
+            collector.on_unit_in_dag(NodeIndex(0), round);
+        }
+        for (batch, latency) in [10, 40, 20, 30].into_iter().enumerate() {
+            let reached = start + Duration::from_millis(100) * batch as u32;
+            collector.on_ordered_batch_at(2, reached + Duration::from_millis(latency));
+        }
+        collector.on_fork_detected();
+        stats.on_sent(100);
+        stats.on_received(30);
+        stats.on_received(12);
+
+        let summary = collector.summary(NodeCount(3));
+        assert!(summary.duration < Duration::from_secs(60));
+        assert_eq!(
+            summary,
+            SessionSummary {
+                duration: summary.duration,
+                rounds: 3,
+                batches: 4,
+                units_per_creator: vec![4, 4, 0],
+                forks: 1,
+                mean_finalization_latency: Some(Duration::from_millis(25)),
+                p50_finalization_latency: Some(Duration::from_millis(20)),
+                p90_finalization_latency: Some(Duration::from_millis(40)),
+                p99_finalization_latency: Some(Duration::from_millis(40)),
+                bytes_sent: 100,
+                bytes_received: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn recommends_config_values() {
+        let delay_config = gen_config(NodeIndex(0), NodeCount(4)).delay_config;
+        let snapshot = |round_duration, duplicate_rate| StatsSnapshot {
+            current_round: 20,
+            average_round_duration: Some(Duration::from_millis(round_duration)),
+            duplicate_rate: Some(duplicate_rate),
+            ..StatsSnapshot::default()
+        };
+
+        let recommendation = ConfigRecommendation::new(&snapshot(200, 0.1), &delay_config);
+        assert_eq!(
+            recommendation,
+            Some(ConfigRecommendation {
+                unit_creation_delay: Duration::from_millis(200),
+                unit_rebroadcast_interval_min: Duration::from_millis(1000),
+                unit_rebroadcast_interval_max: Duration::from_millis(1250),
+            })
+        );
+
+        let recommendation = ConfigRecommendation::new(&snapshot(60, 0.7), &delay_config);
+        assert_eq!(
+            recommendation,
+            Some(ConfigRecommendation {
+                unit_creation_delay: Duration::from_millis(50),
+                unit_rebroadcast_interval_min: Duration::from_millis(800),
+                unit_rebroadcast_interval_max: Duration::from_millis(1000),
+            })
+        );
+
+        assert_eq!(
+            ConfigRecommendation::new(&snapshot(60, 0.1), &delay_config),
+            None
+        );
+        let mut early = snapshot(200, 0.1);
+        early.current_round = 3;
+        assert_eq!(ConfigRecommendation::new(&early, &delay_config), None);
+    }
+}
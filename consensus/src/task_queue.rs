@@ -79,6 +79,11 @@ impl<T: Eq> TaskQueue<T> {
         }
     }
 
+    /// Removes all pending tasks from the queue and returns them.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.queue.drain().map(|x| x.task)
+    }
+
     /// Returns an iterator over all pending tasks.
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.queue.iter().map(|x| &x.task)
@@ -103,4 +108,18 @@ mod tests {
         assert_eq!(Some(2), q.pop_due_task());
         assert_eq!(None, q.pop_due_task());
     }
+
+    #[test]
+    fn test_draining() {
+        let mut q = TaskQueue::new();
+        q.schedule_now(1);
+        q.schedule_in(2, Duration::from_secs(60));
+
+        let mut drained: Vec<_> = q.drain().collect();
+        drained.sort();
+
+        assert_eq!(vec![1, 2], drained);
+        assert_eq!(None, q.pop_due_task());
+        assert_eq!(0, q.iter().count());
+    }
 }
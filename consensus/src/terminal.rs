@@ -1,14 +1,14 @@
 use futures::StreamExt;
 use std::{
-    collections::{hash_map::Entry, HashMap, VecDeque},
-    fmt::{Debug, Formatter},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+    fmt::{Debug, Display, Formatter},
 };
 
 use crate::{
-    extender::ExtenderUnit,
+    cooperation::Cooperation,
     runway::{NotificationIn, NotificationOut},
     units::{ControlHash, Unit, UnitCoord},
-    Hasher, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Terminator,
+    Hasher, Health, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Subsystem, Terminator,
 };
 use codec::{Decode, Encode};
 use log::{debug, trace, warn};
@@ -20,6 +20,62 @@ pub enum UnitStatus {
     WrongControlHash,
     WaitingParentsInDag,
     InDag,
+    GrowthRuleViolated,
+    WaitingPayloadCheck,
+    InvalidPayload,
+    ParentRejected,
+}
+
+impl UnitStatus {
+    // Whether the unit will never be added to the Dag.
+    fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            UnitStatus::GrowthRuleViolated
+                | UnitStatus::InvalidPayload
+                | UnitStatus::ParentRejected
+        )
+    }
+}
+
+/// A reason for rejecting a unit whose parents do not follow the rules of growing the Dag, i.e.
+/// the parents of a unit of round r > 0 have to be units of round r-1 made by distinct creators,
+/// including the creator of the unit, and there have to be more than floor(2*N/3) of them.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub enum GrowthRuleViolation {
+    MissingOwnParent,
+    NotEnoughParents {
+        parents: NodeCount,
+        threshold: NodeCount,
+    },
+    ParentFromWrongRound {
+        parent: NodeIndex,
+        round: Round,
+    },
+    ParentFromWrongCreator {
+        parent: NodeIndex,
+        creator: NodeIndex,
+    },
+}
+
+impl Display for GrowthRuleViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use GrowthRuleViolation::*;
+        match self {
+            MissingOwnParent => write!(f, "the creator's previous unit is not a parent"),
+            NotEnoughParents { parents, threshold } => write!(
+                f,
+                "only {:?} parents while at least {:?} are required",
+                parents, threshold
+            ),
+            ParentFromWrongRound { parent, round } => {
+                write!(f, "parent {:?} is from wrong round {}", parent, round)
+            }
+            ParentFromWrongCreator { parent, creator } => {
+                write!(f, "parent {:?} is created by {:?}", parent, creator)
+            }
+        }
+    }
 }
 
 /// A Unit struct used in the Terminal. It stores a copy of a unit and apart from that some
@@ -49,14 +105,24 @@ impl<H: Hasher> Debug for TerminalUnit<H> {
     }
 }
 
-impl<H: Hasher> From<TerminalUnit<H>> for ExtenderUnit<H> {
-    fn from(u: TerminalUnit<H>) -> ExtenderUnit<H> {
-        ExtenderUnit::new(u.unit.creator(), u.unit.round(), u.unit.hash(), u.parents)
+/// A unit added to the Dag, i.e. with its parents reconstructed and matching its control hash,
+/// satisfying the growth rule and with its payload accepted. It can only be made by the terminal,
+/// and only such units are passed on to the creator and to ordering.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct ValidatedUnit<H: Hasher> {
+    unit: Unit<H>,
+    parents: NodeMap<H::Hash>,
+}
+
+impl<H: Hasher> ValidatedUnit<H> {
+    /// The unit together with the hashes of its parents.
+    pub(crate) fn into_parts(self) -> (Unit<H>, NodeMap<H::Hash>) {
+        (self.unit, self.parents)
     }
 }
 
-impl<H: Hasher> From<TerminalUnit<H>> for Unit<H> {
-    fn from(u: TerminalUnit<H>) -> Unit<H> {
+impl<H: Hasher> From<ValidatedUnit<H>> for Unit<H> {
+    fn from(u: ValidatedUnit<H>) -> Unit<H> {
         u.unit
     }
 }
@@ -82,6 +148,44 @@ impl<H: Hasher> TerminalUnit<H> {
     }
 }
 
+// Checks the growth rule for a unit, all of whose parents are present in the unit_store.
+fn check_growth_rule<H: Hasher>(
+    u: &TerminalUnit<H>,
+    unit_store: &HashMap<H::Hash, TerminalUnit<H>>,
+    threshold: NodeCount,
+) -> Result<(), GrowthRuleViolation> {
+    let round = u.unit.round();
+    if round == 0 {
+        return Ok(());
+    }
+    if u.parents.get(u.unit.creator()).is_none() {
+        return Err(GrowthRuleViolation::MissingOwnParent);
+    }
+    let parents = NodeCount(u.parents.item_count());
+    if parents < threshold {
+        return Err(GrowthRuleViolation::NotEnoughParents { parents, threshold });
+    }
+    for (parent, p_hash) in u.parents.iter() {
+        let p = match unit_store.get(p_hash) {
+            Some(p) => &p.unit,
+            None => continue,
+        };
+        if p.round() + 1 != round {
+            return Err(GrowthRuleViolation::ParentFromWrongRound {
+                parent,
+                round: p.round(),
+            });
+        }
+        if p.creator() != parent {
+            return Err(GrowthRuleViolation::ParentFromWrongCreator {
+                parent,
+                creator: p.creator(),
+            });
+        }
+    }
+    Ok(())
+}
+
 pub enum TerminalEvent<H: Hasher> {
     ParentsReconstructed(H::Hash),
     ParentsInDag(H::Hash),
@@ -112,13 +216,23 @@ type SyncClosure<X, Y> = Box<dyn Fn(X) -> Y + Sync + Send + 'static>;
 /// 5) Now we wait for all the parents of unit u to be in Dag (in order to add it to Dag). Initially, right after u gets
 ///    gets this status, we mark all the parents that are already in the Dag and set appropriate triggers for when
 ///    the remaining parents are added to Dag.
-/// 6) At the moment when all parents have been added to Dag, the unit itself is added to Dag.
+/// 6) At the moment when all parents have been added to Dag, we check whether the parents follow the growth
+///    rule (see [`GrowthRuleViolation`]). If so, the unit itself is added to Dag, otherwise it gets status
+///    GrowthRuleViolated and a notification with the reason is sent, so that evidence can be collected.
+/// 7) If the payload of the unit is being checked by the application, the unit gets status WaitingPayloadCheck
+///    until the result arrives. If the payload is valid the unit is added to Dag, otherwise it gets status
+///    InvalidPayload. The checks run concurrently with all the previous steps, so usually the result is
+///    already known at this point.
+/// 8) A rejected unit never gets to the Dag, and neither do its descendants, so all units having it as
+///    an ancestor get status ParentRejected, both the ones already waiting for it and the ones arriving later.
 ///
 /// We also refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/internals.html
 /// Section 5.3 for a discussion of this component.
 
 pub(crate) struct Terminal<H: Hasher> {
     node_id: NodeIndex,
+    // The minimal number of parents of a unit of a positive round.
+    threshold: NodeCount,
     // A channel for receiving notifications (units mainly)
     ntfct_rx: Receiver<NotificationIn<H>>,
     // A channel to push outgoing notifications
@@ -127,7 +241,7 @@ pub(crate) struct Terminal<H: Hasher> {
     // some events trigger other events and because of the Dag structure, these should be handled
     // in a FIFO order (as in BFS) and not recursively (as in DFS).
     event_queue: VecDeque<TerminalEvent<H>>,
-    post_insert: Vec<SyncClosure<TerminalUnit<H>, ()>>,
+    post_insert: Vec<SyncClosure<ValidatedUnit<H>, ()>>,
     // Here we store all the units -- the ones in Dag and the ones "hanging".
     unit_store: HashMap<H::Hash, TerminalUnit<H>>,
 
@@ -140,17 +254,28 @@ pub(crate) struct Terminal<H: Hasher> {
     // The same as above, but this time we await for a unit (with a particular hash) to be added to the Dag.
     // Once this happens, we notify all the children.
     children_hash: HashMap<H::Hash, Vec<H::Hash>>,
+    // Units whose payloads are still being checked, and ones whose payloads turned out invalid.
+    pending_payloads: HashSet<H::Hash>,
+    rejected_payloads: HashSet<H::Hash>,
+    // The numbers of units from the store that were added to the Dag and that were rejected.
+    units_in_dag: usize,
+    rejected_units: usize,
+    health: Health,
+    cooperation: Cooperation,
     exiting: bool,
 }
 
 impl<H: Hasher> Terminal<H> {
     pub(crate) fn new(
         node_id: NodeIndex,
+        n_members: NodeCount,
         ntfct_rx: Receiver<NotificationIn<H>>,
         ntfct_tx: Sender<NotificationOut<H>>,
+        health: Health,
     ) -> Self {
         Terminal {
             node_id,
+            threshold: n_members.quorum(),
             ntfct_rx,
             ntfct_tx,
             event_queue: VecDeque::new(),
@@ -159,10 +284,22 @@ impl<H: Hasher> Terminal<H> {
             unit_by_coord: HashMap::new(),
             children_coord: HashMap::new(),
             children_hash: HashMap::new(),
+            pending_payloads: HashSet::new(),
+            rejected_payloads: HashSet::new(),
+            units_in_dag: 0,
+            rejected_units: 0,
+            health,
+            cooperation: Cooperation::new(0),
             exiting: false,
         }
     }
 
+    /// Makes the terminal yield to other tasks after handling so many units in a row.
+    pub(crate) fn with_yield_interval(mut self, interval: usize) -> Self {
+        self.cooperation = Cooperation::new(interval);
+        self
+    }
+
     // Reconstruct the parent of a unit u (given by hash u_hash) at position pid as p (given by hash p_hash)
     fn reconstruct_parent(&mut self, u_hash: &H::Hash, pid: NodeIndex, p_hash: &H::Hash) {
         let u = self.unit_store.get_mut(u_hash).unwrap();
@@ -255,14 +392,19 @@ impl<H: Hasher> Terminal<H> {
             .get(u_hash)
             .expect("Unit to be added to dag must be in store")
             .clone();
-        self.post_insert.iter().for_each(|f| f(u.clone()));
+        self.units_in_dag += 1;
+        let validated = ValidatedUnit {
+            unit: u.unit,
+            parents: u.parents,
+        };
+        self.post_insert.iter().for_each(|f| f(validated.clone()));
         if let Some(children) = self.children_hash.remove(u_hash) {
             for v_hash in children {
                 self.new_parent_in_dag(&v_hash);
             }
         }
         let mut parent_hashes = Vec::new();
-        for p_hash in u.parents.into_values() {
+        for p_hash in validated.parents.into_values() {
             parent_hashes.push(p_hash);
         }
 
@@ -297,6 +439,15 @@ impl<H: Hasher> Terminal<H> {
 
     fn inspect_parents_in_dag(&mut self, u_hash: &H::Hash) {
         let u_parents = self.unit_store.get(u_hash).unwrap().parents.clone();
+        let parent_rejected = u_parents.values().any(|p_hash| {
+            self.unit_store
+                .get(p_hash)
+                .map_or(false, |p| p.status.is_rejected())
+        });
+        if parent_rejected {
+            self.reject_with_descendants(u_hash);
+            return;
+        }
         let mut n_parents_in_dag = NodeCount(0);
         for p_hash in u_parents.into_values() {
             let maybe_p = self.unit_store.get(&p_hash);
@@ -332,28 +483,129 @@ impl<H: Hasher> Terminal<H> {
     // adding u.
     fn handle_events(&mut self) {
         while let Some(event) = self.event_queue.pop_front() {
-            match event {
-                TerminalEvent::ParentsReconstructed(u_hash) => {
-                    let u = self.unit_store.get_mut(&u_hash).unwrap();
-                    if u.verify_control_hash() {
-                        self.inspect_parents_in_dag(&u_hash);
-                    } else {
-                        u.status = UnitStatus::WrongControlHash;
-                        warn!(target: "AlephBFT-terminal", "{:?} wrong control hash", self.node_id);
-                        self.on_wrong_hash_detected(u_hash);
-                    }
+            self.handle_event(event);
+        }
+    }
+
+    // The same as above, but yielding to other tasks every so often, as a single unit might
+    // trigger adding a huge number of units waiting for it to the Dag.
+    async fn handle_events_cooperatively(&mut self) {
+        while let Some(event) = self.event_queue.pop_front() {
+            self.handle_event(event);
+            self.cooperation.step().await;
+        }
+    }
+
+    fn handle_event(&mut self, event: TerminalEvent<H>) {
+        match event {
+            TerminalEvent::ParentsReconstructed(u_hash) => {
+                let u = self.unit_store.get_mut(&u_hash).unwrap();
+                if u.verify_control_hash() {
+                    self.inspect_parents_in_dag(&u_hash);
+                } else {
+                    u.status = UnitStatus::WrongControlHash;
+                    // Not rejected for good, the parents get requested by hash.
+                    self.health.on_error(Subsystem::Terminal);
+                    warn!(target: "AlephBFT-terminal", "{:?} wrong control hash", self.node_id);
+                    self.on_wrong_hash_detected(u_hash);
+                }
+            }
+            TerminalEvent::ParentsInDag(u_hash) => {
+                let u = self.unit_store.get(&u_hash).unwrap();
+                if let Err(violation) = check_growth_rule(u, &self.unit_store, self.threshold) {
+                    debug!(target: "AlephBFT-terminal", "{:?} Rejecting unit of round {:?} index {:?}: {}.", self.node_id, u.unit.round(), u.unit.creator(), violation);
+                    self.unit_store.get_mut(&u_hash).unwrap().status =
+                        UnitStatus::GrowthRuleViolated;
+                    self.on_unit_rejected();
+                    self.reject_descendants(&u_hash);
+                    let parents = self.unit_store.get(&u_hash).unwrap().parents.clone();
+                    self.send_notification(NotificationOut::GrowthRuleViolated(
+                        u_hash, parents, violation,
+                    ));
+                    return;
+                }
+                let u = self.unit_store.get_mut(&u_hash).unwrap();
+                if self.rejected_payloads.contains(&u_hash) {
+                    debug!(target: "AlephBFT-terminal", "{:?} Rejecting unit of round {:?} index {:?}: invalid payload.", self.node_id, u.unit.round(), u.unit.creator());
+                    u.status = UnitStatus::InvalidPayload;
+                    self.on_unit_rejected();
+                    self.reject_descendants(&u_hash);
+                    return;
                 }
-                TerminalEvent::ParentsInDag(u_hash) => {
-                    let u = self.unit_store.get_mut(&u_hash).unwrap();
-                    u.status = UnitStatus::InDag;
-                    trace!(target: "AlephBFT-terminal", "{:?} Adding to Dag {:?} round {:?} index {:?}.", self.node_id, u_hash, u.unit.round(), u.unit.creator());
-                    self.update_on_dag_add(&u_hash);
+                if self.pending_payloads.contains(&u_hash) {
+                    trace!(target: "AlephBFT-terminal", "{:?} Unit {:?} waiting for payload check.", self.node_id, u_hash);
+                    u.status = UnitStatus::WaitingPayloadCheck;
+                    return;
                 }
+                u.status = UnitStatus::InDag;
+                trace!(target: "AlephBFT-terminal", "{:?} Adding to Dag {:?} round {:?} index {:?}.", self.node_id, u_hash, u.unit.round(), u.unit.creator());
+                self.update_on_dag_add(&u_hash);
             }
         }
     }
 
-    pub(crate) fn register_post_insert_hook(&mut self, hook: SyncClosure<TerminalUnit<H>, ()>) {
+    fn on_payload_checked(&mut self, u_hash: H::Hash, valid: bool) {
+        if !self.pending_payloads.remove(&u_hash) {
+            return;
+        }
+        if !valid {
+            self.rejected_payloads.insert(u_hash);
+        }
+        if let Some(u) = self.unit_store.get(&u_hash) {
+            if u.status == UnitStatus::WaitingPayloadCheck {
+                self.event_queue
+                    .push_back(TerminalEvent::ParentsInDag(u_hash));
+            }
+        }
+    }
+
+    fn on_unit_rejected(&mut self) {
+        self.rejected_units += 1;
+        self.health.on_error(Subsystem::Terminal);
+    }
+
+    // Rejects the unit u (given by hash u_hash), whose parent got rejected, and all its descendants.
+    fn reject_with_descendants(&mut self, u_hash: &H::Hash) {
+        let u = self.unit_store.get_mut(u_hash).unwrap();
+        if u.status.is_rejected() {
+            return;
+        }
+        u.status = UnitStatus::ParentRejected;
+        self.on_unit_rejected();
+        self.reject_descendants(u_hash);
+    }
+
+    // Rejects all the units waiting for the rejected unit u (given by hash u_hash) to be added to
+    // the Dag, and in turn the ones waiting for them. Done iteratively, as the chains might be long.
+    fn reject_descendants(&mut self, u_hash: &H::Hash) {
+        let mut to_reject = self.children_hash.remove(u_hash).unwrap_or_default();
+        let mut n_rejected = 0;
+        while let Some(v_hash) = to_reject.pop() {
+            let v = self.unit_store.get_mut(&v_hash).unwrap();
+            if v.status.is_rejected() {
+                continue;
+            }
+            v.status = UnitStatus::ParentRejected;
+            self.on_unit_rejected();
+            n_rejected += 1;
+            to_reject.extend(self.children_hash.remove(&v_hash).unwrap_or_default());
+        }
+        if n_rejected > 0 {
+            debug!(target: "AlephBFT-terminal", "{:?} Rejecting {:?} units descending from a rejected unit.", self.node_id, n_rejected);
+        }
+    }
+
+    fn report_health(&self) {
+        self.health.on_activity(Subsystem::Terminal);
+        // The units waiting for their parents or payload checks.
+        let waiting = self
+            .unit_store
+            .len()
+            .saturating_sub(self.units_in_dag + self.rejected_units);
+        self.health.set_queue_depth(Subsystem::Terminal, waiting);
+    }
+
+    pub(crate) fn register_post_insert_hook(&mut self, hook: SyncClosure<ValidatedUnit<H>, ()>) {
         self.post_insert.push(hook);
     }
 
@@ -372,15 +624,23 @@ impl<H: Hasher> Terminal<H> {
                         Some(NotificationIn::NewUnits(units)) => {
                             for u in units {
                                 self.add_to_store(u);
-                                self.handle_events();
+                                self.handle_events_cooperatively().await;
                             }
                         },
                         Some(NotificationIn::UnitParents(u_hash, p_hashes)) => {
                             self.update_on_wrong_hash_response(u_hash, p_hashes);
-                            self.handle_events();
+                            self.handle_events_cooperatively().await;
+                        },
+                        Some(NotificationIn::PayloadsPending(u_hashes)) => {
+                            self.pending_payloads.extend(u_hashes);
+                        },
+                        Some(NotificationIn::PayloadChecked(u_hash, valid)) => {
+                            self.on_payload_checked(u_hash, valid);
+                            self.handle_events_cooperatively().await;
                         },
                         _ => {}
                     }
+                    self.report_health();
                 }
                 _ = &mut terminator.get_exit() => {
                     debug!(target: "AlephBFT-terminal", "{:?} received exit signal", self.node_id);
@@ -395,3 +655,201 @@ impl<H: Hasher> Terminal<H> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_growth_rule, GrowthRuleViolation, Terminal, TerminalUnit, UnitStatus};
+    use crate::{
+        runway::NotificationOut,
+        units::{ControlHash, PreUnit, Unit},
+        Hasher, Health, NodeCount, NodeIndex, NodeMap, Round,
+    };
+    use aleph_bft_mock::Hasher64;
+    use futures::channel::mpsc;
+    use std::collections::HashMap;
+
+    type Hash = <Hasher64 as Hasher>::Hash;
+
+    fn terminal_unit(
+        n_members: NodeCount,
+        creator: NodeIndex,
+        round: Round,
+        parents: &[TerminalUnit<Hasher64>],
+    ) -> TerminalUnit<Hasher64> {
+        let mut parent_map = NodeMap::with_size(n_members);
+        for (i, parent) in parents.iter().enumerate() {
+            parent_map.insert(NodeIndex(i), parent.unit.hash());
+        }
+        let pre_unit = PreUnit::new(creator, round, ControlHash::new(&parent_map));
+        let hash = Hasher64::hash(&[creator.0 as u8, round as u8]);
+        let mut unit = TerminalUnit::blank_from_unit(&Unit::new(pre_unit, hash));
+        unit.parents = parent_map;
+        unit
+    }
+
+    fn store(units: &[TerminalUnit<Hasher64>]) -> HashMap<Hash, TerminalUnit<Hasher64>> {
+        units.iter().map(|u| (u.unit.hash(), u.clone())).collect()
+    }
+
+    #[test]
+    fn accepts_correct_units() {
+        let n_members = NodeCount(4);
+        let threshold = NodeCount(3);
+        let round_zero: Vec<_> = (0..3)
+            .map(|i| terminal_unit(n_members, NodeIndex(i), 0, &[]))
+            .collect();
+        let unit_store = store(&round_zero);
+        for u in &round_zero {
+            assert_eq!(check_growth_rule(u, &unit_store, threshold), Ok(()));
+        }
+        let unit = terminal_unit(n_members, NodeIndex(0), 1, &round_zero);
+        assert_eq!(check_growth_rule(&unit, &unit_store, threshold), Ok(()));
+    }
+
+    #[test]
+    fn rejects_units_violating_growth_rule() {
+        let n_members = NodeCount(4);
+        let threshold = NodeCount(3);
+        let round_zero: Vec<_> = (0..3)
+            .map(|i| terminal_unit(n_members, NodeIndex(i), 0, &[]))
+            .collect();
+        let unit_store = store(&round_zero);
+
+        let unit = terminal_unit(n_members, NodeIndex(3), 1, &round_zero);
+        assert_eq!(
+            check_growth_rule(&unit, &unit_store, threshold),
+            Err(GrowthRuleViolation::MissingOwnParent)
+        );
+
+        let unit = terminal_unit(n_members, NodeIndex(0), 1, &round_zero[..2]);
+        assert_eq!(
+            check_growth_rule(&unit, &unit_store, threshold),
+            Err(GrowthRuleViolation::NotEnoughParents {
+                parents: NodeCount(2),
+                threshold,
+            })
+        );
+
+        let unit = terminal_unit(n_members, NodeIndex(0), 2, &round_zero);
+        assert_eq!(
+            check_growth_rule(&unit, &unit_store, threshold),
+            Err(GrowthRuleViolation::ParentFromWrongRound {
+                parent: NodeIndex(0),
+                round: 0,
+            })
+        );
+
+        let swapped = vec![
+            round_zero[0].clone(),
+            round_zero[2].clone(),
+            round_zero[1].clone(),
+        ];
+        let unit = terminal_unit(n_members, NodeIndex(0), 1, &swapped);
+        assert_eq!(
+            check_growth_rule(&unit, &unit_store, threshold),
+            Err(GrowthRuleViolation::ParentFromWrongCreator {
+                parent: NodeIndex(1),
+                creator: NodeIndex(2),
+            })
+        );
+    }
+
+    #[test]
+    fn waits_for_payload_checks() {
+        let n_members = NodeCount(4);
+        let (_ntfct_in_tx, ntfct_in_rx) = mpsc::unbounded();
+        let (ntfct_out_tx, mut ntfct_out_rx) = mpsc::unbounded();
+        let mut terminal = Terminal::new(
+            NodeIndex(0),
+            n_members,
+            ntfct_in_rx,
+            ntfct_out_tx,
+            Health::new(),
+        );
+        let accepted = terminal_unit(n_members, NodeIndex(1), 0, &[]).unit;
+        let rejected = terminal_unit(n_members, NodeIndex(2), 0, &[]).unit;
+        terminal
+            .pending_payloads
+            .extend([accepted.hash(), rejected.hash()]);
+        for u in [accepted.clone(), rejected.clone()] {
+            terminal.add_to_store(u);
+            terminal.handle_events();
+        }
+        let status = |terminal: &Terminal<Hasher64>, u: &Unit<Hasher64>| {
+            terminal.unit_store.get(&u.hash()).unwrap().status.clone()
+        };
+        assert_eq!(
+            status(&terminal, &accepted),
+            UnitStatus::WaitingPayloadCheck
+        );
+        assert_eq!(
+            status(&terminal, &rejected),
+            UnitStatus::WaitingPayloadCheck
+        );
+        assert!(ntfct_out_rx.try_next().is_err());
+
+        terminal.on_payload_checked(accepted.hash(), true);
+        terminal.on_payload_checked(rejected.hash(), false);
+        terminal.handle_events();
+        assert_eq!(status(&terminal, &accepted), UnitStatus::InDag);
+        assert_eq!(status(&terminal, &rejected), UnitStatus::InvalidPayload);
+        match ntfct_out_rx.try_next() {
+            Ok(Some(NotificationOut::AddedToDag(hash, _))) => assert_eq!(hash, accepted.hash()),
+            _ => panic!("the accepted unit should be added to the Dag"),
+        }
+        assert!(ntfct_out_rx.try_next().is_err());
+    }
+
+    #[test]
+    fn rejects_descendants_of_rejected_units() {
+        let n_members = NodeCount(4);
+        let (_ntfct_in_tx, ntfct_in_rx) = mpsc::unbounded();
+        let (ntfct_out_tx, _ntfct_out_rx) = mpsc::unbounded();
+        let mut terminal = Terminal::new(
+            NodeIndex(0),
+            n_members,
+            ntfct_in_rx,
+            ntfct_out_tx,
+            Health::new(),
+        );
+        let round_zero: Vec<_> = (0..3)
+            .map(|i| terminal_unit(n_members, NodeIndex(i), 0, &[]))
+            .collect();
+        let round_one: Vec<_> = (0..3)
+            .map(|i| terminal_unit(n_members, NodeIndex(i), 1, &round_zero))
+            .collect();
+        let round_two = terminal_unit(n_members, NodeIndex(0), 2, &round_one);
+        let rejected = round_zero[0].unit.clone();
+        terminal.pending_payloads.insert(rejected.hash());
+        for u in round_zero
+            .iter()
+            .chain(round_one.iter())
+            .chain([&round_two])
+        {
+            terminal.add_to_store(u.unit.clone());
+            terminal.handle_events();
+        }
+        let status = |terminal: &Terminal<Hasher64>, u: &Unit<Hasher64>| {
+            terminal.unit_store.get(&u.hash()).unwrap().status.clone()
+        };
+        assert_eq!(
+            status(&terminal, &round_two.unit),
+            UnitStatus::WaitingParentsInDag
+        );
+
+        terminal.on_payload_checked(rejected.hash(), false);
+        terminal.handle_events();
+        assert_eq!(status(&terminal, &rejected), UnitStatus::InvalidPayload);
+        for u in round_one.iter().chain([&round_two]) {
+            assert_eq!(status(&terminal, &u.unit), UnitStatus::ParentRejected);
+        }
+
+        // Units arriving after their parent got rejected are rejected right away.
+        let late = terminal_unit(n_members, NodeIndex(3), 1, &round_zero);
+        terminal.add_to_store(late.unit.clone());
+        terminal.handle_events();
+        assert_eq!(status(&terminal, &late.unit), UnitStatus::ParentRejected);
+        assert_eq!(terminal.rejected_units, 6);
+        assert!(terminal.children_hash.is_empty());
+    }
+}
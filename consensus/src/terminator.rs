@@ -1,19 +1,40 @@
 use futures::{
     channel::oneshot::{channel, Receiver, Sender},
-    future::FusedFuture,
+    future::{select, AbortHandle, Abortable, Either, FusedFuture},
+    Future, FutureExt,
 };
+use futures_timer::Delay;
 use log::{debug, warn};
-use std::fmt::{Debug, Formatter};
+use std::{
+    fmt::{Debug, Formatter},
+    time::Duration,
+};
 
 type TerminatorConnection = (Sender<()>, Receiver<()>);
 
+/// How long a component waits for its offspring to stop, unless configured otherwise.
+pub const DEFAULT_TERMINATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct OffspringConnection {
+    name: &'static str,
+    exit: Sender<()>,
+    connection: TerminatorConnection,
+    abort: Option<AbortHandle>,
+}
+
 /// Struct that holds connections to offspring and parent components/tasks
 /// and enables a clean/synchronized shutdown
+///
+/// Offspring tasks added with [`Terminator::add_offspring_task`] are additionally cancellable:
+/// if one of them does not stop within the termination timeout, e.g. because it is stuck on a
+/// channel that is never going to be ready, it is aborted, so that the shutdown completes in
+/// bounded time.
 pub struct Terminator {
     component_name: &'static str,
     parent_exit: Receiver<()>,
     parent_connection: Option<TerminatorConnection>,
-    offspring_connections: Vec<(&'static str, (Sender<()>, TerminatorConnection))>,
+    offspring_connections: Vec<OffspringConnection>,
+    termination_timeout: Duration,
 }
 
 impl Debug for Terminator {
@@ -39,6 +60,7 @@ impl Terminator {
             parent_exit,
             parent_connection,
             offspring_connections: Vec::new(),
+            termination_timeout: DEFAULT_TERMINATION_TIMEOUT,
         }
     }
 
@@ -52,8 +74,30 @@ impl Terminator {
         &mut self.parent_exit
     }
 
+    /// How long to wait for offspring tasks to stop before aborting them. Inherited by offspring
+    /// added afterwards.
+    pub fn set_termination_timeout(&mut self, timeout: Duration) {
+        self.termination_timeout = timeout;
+    }
+
     /// Add a connection to an offspring component/task
     pub fn add_offspring_connection(&mut self, name: &'static str) -> Terminator {
+        self.connect_offspring(name, None)
+    }
+
+    /// Add a connection to an offspring task created by `task` from its terminator, returning
+    /// the task to be spawned. The task gets aborted if it does not stop in time.
+    pub fn add_offspring_task<F: Future<Output = ()>>(
+        &mut self,
+        name: &'static str,
+        task: impl FnOnce(Terminator) -> F,
+    ) -> impl Future<Output = ()> {
+        let (abort, registration) = AbortHandle::new_pair();
+        let terminator = self.connect_offspring(name, Some(abort));
+        Abortable::new(task(terminator), registration).map(|_| ())
+    }
+
+    fn connect_offspring(&mut self, name: &'static str, abort: Option<AbortHandle>) -> Terminator {
         let (exit_send, exit_recv) = channel();
         let (sender, offspring_recv) = channel();
         let (offspring_sender, recv) = channel();
@@ -61,9 +105,15 @@ impl Terminator {
         let endpoint = (sender, recv);
         let offspring_endpoint = (offspring_sender, offspring_recv);
 
-        self.offspring_connections
-            .push((name, (exit_send, endpoint)));
-        Terminator::new(exit_recv, Some(offspring_endpoint), name)
+        self.offspring_connections.push(OffspringConnection {
+            name,
+            exit: exit_send,
+            connection: endpoint,
+            abort,
+        });
+        let mut offspring = Terminator::new(exit_recv, Some(offspring_endpoint), name);
+        offspring.termination_timeout = self.termination_timeout;
+        offspring
     }
 
     /// Perform a synchronized shutdown
@@ -85,19 +135,46 @@ impl Terminator {
         let mut offspring_receivers = Vec::new();
 
         // First send exits to descendants
-        for (name, (exit, connection)) in self.offspring_connections {
-            if exit.send(()).is_err() {
+        for offspring in self.offspring_connections {
+            let name = offspring.name;
+            if offspring.exit.send(()).is_err() {
                 debug!(target: self.component_name, "{} already stopped.", name);
             }
 
-            let (sender, receiver) = connection;
+            let (sender, receiver) = offspring.connection;
             offspring_senders.push((sender, name));
-            offspring_receivers.push((receiver, name));
+            offspring_receivers.push((receiver, name, offspring.abort));
         }
 
         // Make sure that all descendants recieved exit and won't be communicating with other components
-        for (receiver, name) in offspring_receivers {
-            if receiver.await.is_err() {
+        let mut deadline = Delay::new(self.termination_timeout);
+        let mut expired = false;
+        for (mut receiver, name, abort) in offspring_receivers {
+            let stopped = match expired {
+                true => (&mut receiver).now_or_never(),
+                false => match select(&mut receiver, &mut deadline).await {
+                    Either::Left((stopped, _)) => Some(stopped),
+                    Either::Right(((), _)) => {
+                        expired = true;
+                        (&mut receiver).now_or_never()
+                    }
+                },
+            };
+            let stopped = match (stopped, abort) {
+                (Some(stopped), _) => stopped,
+                (None, Some(abort)) => {
+                    warn!(
+                        target: self.component_name,
+                        "{} did not stop in time, aborting it.",
+                        name,
+                    );
+                    abort.abort();
+                    continue;
+                }
+                // Only tasks can be aborted, other offspring are waited for.
+                (None, None) => receiver.await,
+            };
+            if stopped.is_err() {
                 debug!(
                     target: self.component_name,
                     "Terminator failed to receive from {}.",
@@ -178,7 +255,8 @@ pub async fn handle_task_termination<T>(
 
 #[cfg(test)]
 mod tests {
-    use futures::{channel::oneshot, pin_mut, select, FutureExt};
+    use futures::{channel::oneshot, future::pending, pin_mut, select, FutureExt};
+    use std::time::Duration;
 
     use crate::Terminator;
 
@@ -283,4 +361,25 @@ mod tests {
         let terminator = Terminator::create_root(exit_rx, "root");
         root_component(terminator, true).await;
     }
+
+    #[tokio::test]
+    async fn aborts_stuck_offspring_task() {
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let mut terminator = Terminator::create_root(exit_rx, "root");
+        terminator.set_termination_timeout(Duration::from_millis(50));
+        let leaf_task = tokio::spawn(terminator.add_offspring_task("leaf", leaf));
+        let stuck_task = tokio::spawn(terminator.add_offspring_task(
+            "stuck",
+            |terminator| async move {
+                // Holds on to the terminator, but never listens to it.
+                let _terminator = terminator;
+                pending::<()>().await;
+            },
+        ));
+
+        exit_tx.send(()).expect("should send");
+        terminator.terminate_sync().await;
+        leaf_task.await.expect("leaf should stop");
+        stuck_task.await.expect("stuck task should be aborted");
+    }
 }
@@ -1,8 +1,9 @@
 use crate::{
     alerts::{run, Alert, AlertConfig, AlertMessage, ForkProof, ForkingNotification},
+    key_rotation::KeySchedule,
     units::{ControlHash, FullUnit, PreUnit},
-    Index, Indexed, Keychain as _, NodeCount, NodeIndex, NodeMap, Recipient, Round, Signable,
-    Signed, Terminator, UncheckedSigned,
+    AlertLimits, Health, Index, Indexed, Keychain as _, NodeCount, NodeIndex, NodeMap, Recipient,
+    Round, SessionId, Signable, Signed, Terminator, UncheckedSigned,
 };
 use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
 use aleph_bft_rmc::Message as RmcMessage;
@@ -115,7 +116,7 @@ impl TestCase {
                 )),
             ),
             Some(variant),
-            0,
+            SessionId::from_number(0),
         )
     }
 
@@ -226,8 +227,11 @@ impl TestCase {
             alerts_from_units,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SessionId::from_number(0),
+                limits: AlertLimits::default(),
+                key_schedule: KeySchedule::default(),
             },
+            Health::new(),
             Terminator::create_root(exit, "AlephBFT-alerter"),
         ));
 
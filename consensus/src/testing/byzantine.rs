@@ -34,7 +34,7 @@ impl<'a> MaliciousMember<'a> {
         session_id: SessionId,
         forking_round: Round,
     ) -> Self {
-        let threshold = (n_members * 2) / 3 + NodeCount(1);
+        let threshold = n_members.quorum();
         MaliciousMember {
             node_ix,
             n_members,
@@ -184,7 +184,7 @@ fn spawn_malicious_member(
     let (exit_tx, exit_rx) = oneshot::channel();
     let member_task = async move {
         let keychain = Keychain::new(n_members, node_index);
-        let session_id = 0u64;
+        let session_id = SessionId::from_number(0);
         let lesniak = MaliciousMember::new(
             &keychain,
             network,
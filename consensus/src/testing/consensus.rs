@@ -3,7 +3,7 @@ use crate::{
     runway::{NotificationIn, NotificationOut},
     testing::{complete_oneshot, gen_config, init_log},
     units::{ControlHash, PreUnit, Unit, UnitCoord},
-    Hasher, NodeIndex, SpawnHandle, Terminator,
+    Hasher, Health, NodeIndex, SpawnHandle, Terminator,
 };
 use aleph_bft_mock::{Hasher64, Spawner};
 use codec::Encode;
@@ -109,6 +109,9 @@ impl HonestHub {
                 // Safe to ignore in testing.
                 // Normally this is used in Member to answer parents requests.
             }
+            NotificationOut::GrowthRuleViolated(_u_hash, _parents, violation) => {
+                panic!("Honest node violated the growth rule: {}.", violation);
+            }
         }
     }
 }
@@ -176,6 +179,8 @@ async fn agree_on_first_batch() {
                 batch_tx,
                 spawner,
                 starting_round,
+                Health::new(),
+                None,
                 Terminator::create_root(exit_rx, "AlephBFT-consensus"),
             ),
         ));
@@ -218,6 +223,8 @@ async fn catches_wrong_control_hash() {
             batch_tx,
             spawner,
             starting_round,
+            Health::new(),
+            None,
             Terminator::create_root(exit_rx, "AlephBFT-consensus"),
         ),
     );
@@ -1,8 +1,8 @@
 use crate::{
-    testing::{init_log, spawn_honest_member, HonestMember},
-    NodeCount, SpawnHandle,
+    testing::{gen_config, init_log, spawn_honest_member_with_config, HonestMember},
+    Config, NodeCount, SpawnHandle,
 };
-use aleph_bft_mock::{Router, Spawner};
+use aleph_bft_mock::{assert_agreement, Router, Spawner};
 use futures::StreamExt;
 use serial_test::serial;
 
@@ -11,6 +11,23 @@ async fn honest_members_agree_on_batches(
     n_alive: NodeCount,
     n_batches: usize,
     network_reliability: f64,
+) {
+    honest_members_agree_on_batches_with_config(
+        n_members,
+        n_alive,
+        n_batches,
+        network_reliability,
+        |_| (),
+    )
+    .await
+}
+
+async fn honest_members_agree_on_batches_with_config(
+    n_members: NodeCount,
+    n_alive: NodeCount,
+    n_batches: usize,
+    network_reliability: f64,
+    configure: impl Fn(&mut Config),
 ) {
     init_log();
     let spawner = Spawner::new();
@@ -28,7 +45,11 @@ async fn honest_members_agree_on_batches(
                 exit_tx,
                 handle,
                 ..
-            } = spawn_honest_member(spawner, ix, n_members, vec![], network);
+            } = {
+                let mut config = gen_config(ix, n_members);
+                configure(&mut config);
+                spawn_honest_member_with_config(spawner, config, vec![], network)
+            };
             batch_rxs.push(finalization_rx);
             exits.push(exit_tx);
             handles.push(handle);
@@ -45,9 +66,7 @@ async fn honest_members_agree_on_batches(
         batches.push(batches_per_ix);
     }
 
-    for node_ix in n_alive.into_iterator().skip(1) {
-        assert_eq!(batches[0], batches[node_ix.0]);
-    }
+    assert_agreement(&batches);
     for exit in exits {
         let _ = exit.send(());
     }
@@ -56,6 +75,18 @@ async fn honest_members_agree_on_batches(
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn single_node_committee() {
+    honest_members_agree_on_batches(1.into(), 1.into(), 5, 1.0).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn two_node_committee() {
+    honest_members_agree_on_batches(2.into(), 2.into(), 5, 1.0).await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn small_honest_all_alive() {
@@ -74,6 +105,15 @@ async fn small_honest_one_crash_unreliable_network() {
     honest_members_agree_on_batches(4.into(), 3.into(), 5, 0.9).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn small_honest_one_crash_without_alerts() {
+    honest_members_agree_on_batches_with_config(4.into(), 3.into(), 5, 1.0, |config| {
+        config.fork_alerts = false
+    })
+    .await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn medium_honest_all_alive() {
@@ -3,7 +3,7 @@ use crate::{
     runway::NotificationOut as GenericNotificationOut,
     testing::gen_config,
     units::{FullUnit as GenericFullUnit, PreUnit as GenericPreUnit, Unit as GenericUnit},
-    NodeCount, Receiver, Round, Sender, Terminator,
+    Health, NodeCount, Receiver, Round, Sender, SessionId, Terminator,
 };
 use aleph_bft_mock::{Data, Hasher64};
 use futures::{
@@ -17,7 +17,7 @@ type FullUnit = GenericFullUnit<Hasher64, Data>;
 type NotificationOut = GenericNotificationOut<Hasher64>;
 
 fn preunit_to_unit(preunit: PreUnit) -> Unit {
-    FullUnit::new(preunit, Some(0), 0).unit()
+    FullUnit::new(preunit, Some(0), SessionId::from_number(0)).unit()
 }
 
 struct TestController {
@@ -88,6 +88,7 @@ fn setup_test(n_members: NodeCount) -> TestSetup {
         let io = IO {
             incoming_parents: parents_from_controller,
             outgoing_units: notifications_for_controller.clone(),
+            health: Health::new(),
         };
         let config = gen_config(node_ix.into(), n_members);
         let (starting_round_for_consensus, starting_round) = oneshot::channel();
@@ -3,7 +3,8 @@ use crate::{
     runway::{NotificationIn, NotificationOut},
     testing::{complete_oneshot, gen_config},
     units::{ControlHash, PreUnit, Unit},
-    NodeCount, NodeIndex, NodeMap, NodeSubset, Receiver, Round, Sender, SpawnHandle, Terminator,
+    Health, NodeCount, NodeIndex, NodeMap, NodeSubset, Receiver, Round, Sender, SpawnHandle,
+    Terminator,
 };
 use aleph_bft_mock::{Hash64, Hasher64, Spawner};
 use futures::{
@@ -139,6 +140,8 @@ async fn run_consensus_on_dag(
             batch_tx,
             spawner,
             starting_round,
+            Health::new(),
+            None,
             Terminator::create_root(exit_rx, "AlephBFT-consensus"),
         ),
     );
@@ -181,7 +184,7 @@ fn generate_random_dag(n_members: NodeCount, height: Round, seed: u64) -> Vec<Un
     // Maximum number of forks per round per forker.
     let max_variants = rng.gen_range(1..=4);
 
-    let threshold = NodeCount((2 * n_members.0) / 3 + 1);
+    let threshold = n_members.quorum();
 
     let mut dag: Vec<Vec<Vec<UnitWithParents>>> =
         vec![vec![vec![]; n_members.into()]; height.into()];
@@ -9,8 +9,10 @@ mod dag;
 mod unreliable;
 
 use crate::{
-    run_session, Config, DelayConfig, LocalIO, Network as NetworkT, NodeCount, NodeIndex,
-    SpawnHandle, TaskHandle, Terminator,
+    run_session, AlertLimits, CatchUpSources, Config, DelayConfig, DuplicateUnitPolicy,
+    ForkerDataPolicy, InvariantPolicy, LocalIO, MultiKeychain, Network as NetworkT, NodeCount,
+    NodeIndex, ParentPolicy, RequestConcurrency, ResourceBudget, SessionId, SpawnHandle,
+    TaskHandle, Terminator, TopologyHints, ValidationProfile,
 };
 use aleph_bft_mock::{
     Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, Network as MockNetwork,
@@ -55,13 +57,36 @@ pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
         parent_request_recipients: Arc::new(|_| 1),
         // 50, 50, 50, 50, ...
         newest_request_delay: Arc::new(|_| Duration::from_millis(50)),
+        clock_jump_threshold: Duration::from_millis(1000),
+        shutdown_timeout: Duration::from_secs(1),
     };
     Config {
         node_ix,
-        session_id: 0,
+        session_id: SessionId::from_number(0),
         n_members,
         delay_config,
         max_round: 5000,
+        resource_budget: ResourceBudget::default(),
+        parent_policy: ParentPolicy::default(),
+        fork_alerts: true,
+        alert_limits: AlertLimits::default(),
+        request_concurrency: RequestConcurrency::default(),
+        catch_up_sources: CatchUpSources::default(),
+        topology: TopologyHints::default(),
+        request_ttl: None,
+        history_limit: None,
+        lag_suppression: None,
+        validation: ValidationProfile::default(),
+        forker_data: ForkerDataPolicy::default(),
+        duplicate_units: DuplicateUnitPolicy::default(),
+        quarantine_capacity: 1000,
+        verification_cache_capacity: 10000,
+        verification_threads: 1,
+        invariant_policy: InvariantPolicy::default(),
+        weights: None,
+        signing_purpose: None,
+        addresses: Vec::new(),
+        yield_interval: 100,
     }
 }
 
@@ -78,10 +103,30 @@ pub fn spawn_honest_member(
     n_members: NodeCount,
     units: Vec<u8>,
     network: impl 'static + NetworkT<NetworkData>,
+) -> HonestMember {
+    let config = gen_config(node_index, n_members);
+    spawn_honest_member_with_config(spawner, config, units, network)
+}
+
+pub fn spawn_honest_member_with_config(
+    spawner: Spawner,
+    config: Config,
+    units: Vec<u8>,
+    network: impl 'static + NetworkT<NetworkData>,
+) -> HonestMember {
+    let keychain = Keychain::new(config.n_members, config.node_ix);
+    spawn_honest_member_with_keychain(spawner, config, units, network, keychain)
+}
+
+pub fn spawn_honest_member_with_keychain(
+    spawner: Spawner,
+    config: Config,
+    units: Vec<u8>,
+    network: impl 'static + NetworkT<NetworkData>,
+    keychain: impl MultiKeychain<Signature = Signature, PartialMultisignature = PartialMultisignature>,
 ) -> HonestMember {
     let data_provider = DataProvider::new();
     let (finalization_handler, finalization_rx) = FinalizationHandler::new();
-    let config = gen_config(node_index, n_members);
     let (exit_tx, exit_rx) = oneshot::channel();
     let spawner_inner = spawner;
     let unit_loader = Loader::new(units);
@@ -89,8 +134,8 @@ pub fn spawn_honest_member(
     let unit_saver: Saver = saved_state.clone().into();
     let local_io = LocalIO::new(data_provider, finalization_handler, unit_saver, unit_loader);
     let member_task = async move {
-        let keychain = Keychain::new(n_members, node_index);
-        run_session(
+        // The tests check the session through what it finalized and saved.
+        let _ = run_session(
             config,
             local_io,
             network,
@@ -98,7 +143,7 @@ pub fn spawn_honest_member(
             spawner_inner,
             Terminator::create_root(exit_rx, "AlephBFT-member"),
         )
-        .await
+        .await;
     };
     let handle = spawner.spawn_essential("member", member_task);
     HonestMember {
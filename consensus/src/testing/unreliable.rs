@@ -1,14 +1,19 @@
 use crate::{
     member::UnitMessage,
     network::NetworkDataInner,
-    testing::{init_log, spawn_honest_member, HonestMember, NetworkData},
+    testing::{
+        gen_config, init_log, spawn_honest_member, spawn_honest_member_with_keychain, HonestMember,
+        NetworkData,
+    },
     Index, NodeCount, NodeIndex, Round, Signed, SpawnHandle,
 };
-use aleph_bft_mock::{BadSigning, Keychain, NetworkHook, Router, Spawner};
+use aleph_bft_mock::{
+    assert_agreement, BadSigning, Flakiness, FlakyKeyBox, Keychain, NetworkHook, Router, Spawner,
+};
 use async_trait::async_trait;
 use futures::StreamExt;
 use parking_lot::Mutex;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 struct CorruptPacket {
     recipient: NodeIndex,
@@ -125,3 +130,68 @@ async fn request_missing_coord() {
 
     assert!(*requested.lock())
 }
+
+#[tokio::test]
+async fn flaky_signers_agree() {
+    init_log();
+
+    let n_members = NodeCount(4);
+    // The last node also makes wrong signatures, so the others get by without it.
+    let wrong_signer = NodeIndex(3);
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let spawner = Spawner::new();
+    spawner.spawn("network-hub", net_hub);
+
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut batch_rxs = Vec::new();
+    let mut keychains = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let flakiness = Flakiness {
+            max_delay: Duration::from_millis(20),
+            failure_rate: 0.3,
+            retry_delay: Duration::from_millis(5),
+            wrong_signature_rate: if ix == wrong_signer { 0.5 } else { 0.0 },
+        };
+        let keychain = FlakyKeyBox::new(Keychain::new(n_members, ix), flakiness);
+        keychains.push(keychain.clone());
+        let HonestMember {
+            finalization_rx,
+            exit_tx,
+            handle,
+            ..
+        } = spawn_honest_member_with_keychain(
+            spawner,
+            gen_config(ix, n_members),
+            vec![],
+            network,
+            keychain,
+        );
+        if ix != wrong_signer {
+            batch_rxs.push(finalization_rx);
+        }
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let n_batches = 10;
+    let mut batches = vec![];
+    for mut rx in batch_rxs.drain(..) {
+        let mut batches_per_ix = vec![];
+        for _ in 0..n_batches {
+            let batch = rx.next().await.unwrap();
+            batches_per_ix.push(batch);
+        }
+        batches.push(batches_per_ix);
+    }
+    assert_agreement(&batches);
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    assert!(keychains.iter().any(|keychain| keychain.failures() > 0));
+}
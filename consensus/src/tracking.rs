@@ -0,0 +1,219 @@
+use crate::{Data, DataProvider, FinalizationHandler, NodeIndex, Sender};
+use async_trait::async_trait;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// What happened to a data item output by a [`TrackingDataProvider`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataEvent<D: Data> {
+    /// The item was placed in a unit we created.
+    Included(D),
+    /// The unit containing the item was finalized.
+    Finalized(D),
+    /// The unit containing the item was not finalized within the timeout, e.g. because we
+    /// crashed before sending it, so the item should be provided again.
+    TimedOut(D),
+}
+
+struct Pending<D: Data> {
+    timeout: Duration,
+    // Our units are finalized in the order of creation, so are the items they contain.
+    items: VecDeque<(D, Instant)>,
+    events: Sender<DataEvent<D>>,
+}
+
+impl<D: Data> Pending<D> {
+    fn report(&self, event: DataEvent<D>) {
+        // The application might not be interested in the events anymore.
+        let _ = self.events.unbounded_send(event);
+    }
+
+    fn included(&mut self, data: D, now: Instant) {
+        self.report(DataEvent::Included(data.clone()));
+        self.items.push_back((data, now));
+    }
+
+    fn finalized(&mut self, data: D) {
+        if !self.items.iter().any(|(item, _)| item == &data) {
+            // Timed out already.
+            return;
+        }
+        while let Some((item, _)) = self.items.pop_front() {
+            if item == data {
+                self.report(DataEvent::Finalized(item));
+                return;
+            }
+            // Not finalized, yet a later item was, should not happen unless the unit backup
+            // was lost.
+            self.report(DataEvent::TimedOut(item));
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((_, included)) = self.items.front() {
+            if now.saturating_duration_since(*included) < self.timeout {
+                break;
+            }
+            let (item, _) = self.items.pop_front().expect("there is an item");
+            self.report(DataEvent::TimedOut(item));
+        }
+    }
+}
+
+/// Reports what happened to the data items output by the wrapped provider, so that the
+/// application can provide again the items that did not make it, e.g. to reliably retransmit
+/// transactions. The session should be run with the [`TrackingFinalizationHandler`] obtained
+/// from this provider. Items are reported as timed out if they are not finalized within the
+/// timeout, which is checked whenever data is requested or finalized.
+pub struct TrackingDataProvider<D: Data, DP: DataProvider<D>> {
+    data_provider: DP,
+    pending: Arc<Mutex<Pending<D>>>,
+    node_ix: NodeIndex,
+}
+
+impl<D: Data, DP: DataProvider<D>> TrackingDataProvider<D, DP> {
+    /// Wraps the provider of the node with the given index, returning the channel the events
+    /// are reported to.
+    pub fn new(
+        data_provider: DP,
+        node_ix: NodeIndex,
+        timeout: Duration,
+    ) -> (Self, UnboundedReceiver<DataEvent<D>>) {
+        let (events, events_rx) = unbounded();
+        let pending = Pending {
+            timeout,
+            items: VecDeque::new(),
+            events,
+        };
+        (
+            TrackingDataProvider {
+                data_provider,
+                pending: Arc::new(Mutex::new(pending)),
+                node_ix,
+            },
+            events_rx,
+        )
+    }
+
+    /// Wraps the finalization handler of the session.
+    pub fn finalization_handler<FH: FinalizationHandler<D>>(
+        &self,
+        finalization_handler: FH,
+    ) -> TrackingFinalizationHandler<D, FH> {
+        TrackingFinalizationHandler {
+            finalization_handler,
+            pending: self.pending.clone(),
+            node_ix: self.node_ix,
+        }
+    }
+}
+
+#[async_trait]
+impl<D: Data, DP: DataProvider<D>> DataProvider<D> for TrackingDataProvider<D, DP> {
+    async fn get_data(&mut self) -> Option<D> {
+        let data = self.data_provider.get_data().await;
+        let now = Instant::now();
+        let mut pending = self.pending.lock();
+        pending.expire(now);
+        if let Some(data) = &data {
+            pending.included(data.clone(), now);
+        }
+        data
+    }
+}
+
+/// Passes finalized data to the wrapped handler, reporting the finalization of items output by
+/// the corresponding [`TrackingDataProvider`].
+pub struct TrackingFinalizationHandler<D: Data, FH: FinalizationHandler<D>> {
+    finalization_handler: FH,
+    pending: Arc<Mutex<Pending<D>>>,
+    node_ix: NodeIndex,
+}
+
+impl<D: Data, FH: FinalizationHandler<D>> FinalizationHandler<D>
+    for TrackingFinalizationHandler<D, FH>
+{
+    fn data_finalized(&mut self, data: D) {
+        // Without the creator we cannot tell whether this is our item.
+        self.finalization_handler.data_finalized(data);
+    }
+
+    fn units_finalized(&mut self, units: Vec<(NodeIndex, Option<D>)>) {
+        {
+            let mut pending = self.pending.lock();
+            for (creator, data) in &units {
+                match data {
+                    Some(data) if *creator == self.node_ix => pending.finalized(data.clone()),
+                    _ => (),
+                }
+            }
+            pending.expire(Instant::now());
+        }
+        self.finalization_handler.units_finalized(units);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataEvent, TrackingDataProvider};
+    use crate::{DataProvider, FinalizationHandler, NodeIndex};
+    use aleph_bft_mock::DataProvider as MockDataProvider;
+    use std::time::Duration;
+
+    struct NoopHandler;
+
+    impl FinalizationHandler<u32> for NoopHandler {
+        fn data_finalized(&mut self, _data: u32) {}
+    }
+
+    #[tokio::test]
+    async fn reports_finalized_items() {
+        let (mut provider, mut events) = TrackingDataProvider::new(
+            MockDataProvider::new(),
+            NodeIndex(1),
+            Duration::from_secs(60),
+        );
+        let mut handler = provider.finalization_handler(NoopHandler);
+        let first = provider.get_data().await.expect("there is data");
+        let second = provider.get_data().await.expect("there is data");
+        let third = provider.get_data().await.expect("there is data");
+        handler.units_finalized(vec![
+            (NodeIndex(0), Some(first)),
+            (NodeIndex(1), Some(second)),
+            (NodeIndex(1), None),
+        ]);
+
+        assert_eq!(events.try_next().unwrap(), Some(DataEvent::Included(first)));
+        assert_eq!(
+            events.try_next().unwrap(),
+            Some(DataEvent::Included(second))
+        );
+        assert_eq!(events.try_next().unwrap(), Some(DataEvent::Included(third)));
+        // A later item of ours was finalized, so the first one never will be.
+        assert_eq!(events.try_next().unwrap(), Some(DataEvent::TimedOut(first)));
+        assert_eq!(
+            events.try_next().unwrap(),
+            Some(DataEvent::Finalized(second))
+        );
+        assert!(events.try_next().is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_timed_out_items() {
+        let (mut provider, mut events) =
+            TrackingDataProvider::new(MockDataProvider::new(), NodeIndex(1), Duration::ZERO);
+        let mut handler = provider.finalization_handler(NoopHandler);
+        let first = provider.get_data().await.expect("there is data");
+        handler.units_finalized(vec![]);
+        handler.units_finalized(vec![(NodeIndex(1), Some(first))]);
+
+        assert_eq!(events.try_next().unwrap(), Some(DataEvent::Included(first)));
+        assert_eq!(events.try_next().unwrap(), Some(DataEvent::TimedOut(first)));
+        assert!(events.try_next().is_err());
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     Data, Hasher, Index, Keychain, NodeCount, NodeIndex, NodeMap, NodeSubset, Round, SessionId,
     Signable, Signed, UncheckedSigned,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, EncodeLike, Error as CodecError, Input, Output};
 use derivative::Derivative;
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -11,10 +11,15 @@ mod store;
 #[cfg(test)]
 mod testing;
 mod validator;
+mod vrf;
 pub(crate) use store::*;
 #[cfg(test)]
-pub use testing::{create_units, creator_set, preunit_to_unchecked_signed_unit, preunit_to_unit};
-pub use validator::{ValidationError, Validator};
+pub use testing::{
+    create_units, creator_set, preunit_to_unchecked_signed_unit,
+    preunit_to_unchecked_signed_unit_with_vrf_proof, preunit_to_unit,
+};
+pub use validator::{RejectionReason, UnitRejection, ValidationError, Validator};
+pub(crate) use vrf::UnitVrf;
 
 /// The coordinates of a unit, i.e. creator and round. In the absence of forks this uniquely
 /// determines a unit within a session.
@@ -110,17 +115,117 @@ impl<H: Hasher> PreUnit<H> {
     }
 }
 
-#[derive(Debug, Decode, Derivative, Encode)]
+#[derive(Debug, Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
 pub struct FullUnit<H: Hasher, D: Data> {
     pre_unit: PreUnit<H>,
     data: Option<D>,
     session_id: SessionId,
-    #[codec(skip)]
+    vrf_proof: Option<Vec<u8>>,
+    // Whether the data encodes back to the bytes it was decoded from, see `Validator`.
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    canonical_data: bool,
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     hash: RwLock<Option<H::Hash>>,
 }
 
+// Units without a VRF proof are encoded as they were before proofs were added, with the data as
+// an option, so their hashes do not change. A proof is marked in the byte telling whether there
+// is data, and follows the session id.
+const DATA_FLAG: u8 = 1;
+const VRF_PROOF_FLAG: u8 = 2;
+
+impl<H: Hasher, D: Data> Encode for FullUnit<H, D> {
+    fn size_hint(&self) -> usize {
+        self.pre_unit.size_hint()
+            + self.data.size_hint()
+            + self.session_id.size_hint()
+            + self.vrf_proof.as_ref().map_or(0, Encode::size_hint)
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.pre_unit.encode_to(dest);
+        let mut flags = 0;
+        if self.data.is_some() {
+            flags |= DATA_FLAG;
+        }
+        if self.vrf_proof.is_some() {
+            flags |= VRF_PROOF_FLAG;
+        }
+        dest.push_byte(flags);
+        if let Some(data) = &self.data {
+            data.encode_to(dest);
+        }
+        self.session_id.encode_to(dest);
+        if let Some(vrf_proof) = &self.vrf_proof {
+            vrf_proof.encode_to(dest);
+        }
+    }
+}
+
+impl<H: Hasher, D: Data> EncodeLike for FullUnit<H, D> {}
+
+// Keeps the bytes read from the input, to compare them with the encoding of what was decoded.
+struct RecordingInput<'a, I: Input> {
+    input: &'a mut I,
+    read: Vec<u8>,
+}
+
+impl<'a, I: Input> Input for RecordingInput<'a, I> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, CodecError> {
+        self.input.remaining_len()
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), CodecError> {
+        self.input.read(into)?;
+        self.read.extend_from_slice(into);
+        Ok(())
+    }
+
+    fn descend_ref(&mut self) -> Result<(), CodecError> {
+        self.input.descend_ref()
+    }
+
+    fn ascend_ref(&mut self) {
+        self.input.ascend_ref()
+    }
+}
+
+impl<H: Hasher, D: Data> Decode for FullUnit<H, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let pre_unit = PreUnit::decode(input)?;
+        let flags = input.read_byte()?;
+        if flags & !(DATA_FLAG | VRF_PROOF_FLAG) != 0 {
+            return Err("invalid flags of a unit".into());
+        }
+        let (data, canonical_data) = match flags & DATA_FLAG {
+            0 => (None, true),
+            _ => {
+                let mut recording = RecordingInput {
+                    input: &mut *input,
+                    read: Vec::new(),
+                };
+                let data = D::decode(&mut recording)?;
+                let canonical_data = data.encode() == recording.read;
+                (Some(data), canonical_data)
+            }
+        };
+        let session_id = SessionId::decode(input)?;
+        let vrf_proof = match flags & VRF_PROOF_FLAG {
+            0 => None,
+            _ => Some(Vec::decode(input)?),
+        };
+        Ok(FullUnit {
+            pre_unit,
+            data,
+            session_id,
+            vrf_proof,
+            canonical_data,
+            hash: RwLock::new(None),
+        })
+    }
+}
+
 impl<H: Hasher, D: Data> Clone for FullUnit<H, D> {
     fn clone(&self) -> Self {
         let hash = self.hash.try_read().and_then(|guard| *guard);
@@ -128,6 +233,8 @@ impl<H: Hasher, D: Data> Clone for FullUnit<H, D> {
             pre_unit: self.pre_unit.clone(),
             data: self.data.clone(),
             session_id: self.session_id,
+            vrf_proof: self.vrf_proof.clone(),
+            canonical_data: self.canonical_data,
             hash: RwLock::new(hash),
         }
     }
@@ -139,9 +246,19 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
             pre_unit,
             data,
             session_id,
+            vrf_proof: None,
+            canonical_data: true,
             hash: RwLock::new(None),
         }
     }
+    /// Adds the proof of the priority of the unit, see [`UnitVrf`].
+    pub(crate) fn with_vrf_proof(self, vrf_proof: Vec<u8>) -> Self {
+        FullUnit {
+            vrf_proof: Some(vrf_proof),
+            hash: RwLock::new(None),
+            ..self
+        }
+    }
     pub(crate) fn as_pre_unit(&self) -> &PreUnit<H> {
         &self.pre_unit
     }
@@ -163,9 +280,16 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
     pub(crate) fn included_data(&self) -> Vec<D> {
         self.data.iter().cloned().collect()
     }
+    /// Whether the data was decoded from its canonical encoding, true for units not decoded.
+    pub(crate) fn has_canonical_data(&self) -> bool {
+        self.canonical_data
+    }
     pub(crate) fn session_id(&self) -> SessionId {
         self.session_id
     }
+    pub(crate) fn vrf_proof(&self) -> Option<&[u8]> {
+        self.vrf_proof.as_deref()
+    }
     pub(crate) fn hash(&self) -> H::Hash {
         let hash = *self.hash.read();
         match hash {
@@ -177,8 +301,17 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
             }
         }
     }
+    #[cfg(test)]
     pub(crate) fn unit(&self) -> Unit<H> {
-        Unit::new(self.pre_unit.clone(), self.hash())
+        Unit {
+            pre_unit: self.pre_unit.clone(),
+            hash: self.hash(),
+            priority: self.priority(),
+        }
+    }
+    // The output of the VRF, as the hash of its proof.
+    fn priority(&self) -> Option<H::Hash> {
+        self.vrf_proof.as_deref().map(H::hash)
     }
 }
 
@@ -195,19 +328,44 @@ impl<H: Hasher, D: Data> Index for FullUnit<H, D> {
     }
 }
 
+/// A unit as decoded from the network or a backup, with its signature not checked yet.
 pub(crate) type UncheckedSignedUnit<H, D, S> = UncheckedSigned<FullUnit<H, D>, S>;
 
+/// A unit with a checked signature, i.e. one that went through a [`Validator`] or that we
+/// signed ourselves.
 pub(crate) type SignedUnit<H, D, K> = Signed<FullUnit<H, D>, K>;
 
+/// A unit as seen by the consensus. Outside of tests it can only be made out of a
+/// [`SignedUnit`], so nothing unchecked reaches the terminal, which adds it to the Dag as a
+/// [`ValidatedUnit`] once its parents are known to be right.
+///
+/// [`ValidatedUnit`]: crate::terminal::ValidatedUnit
 #[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
 pub struct Unit<H: Hasher> {
     pre_unit: PreUnit<H>,
     hash: H::Hash,
+    priority: Option<H::Hash>,
+}
+
+impl<H: Hasher, D: Data, K: Keychain> From<&SignedUnit<H, D, K>> for Unit<H> {
+    fn from(su: &SignedUnit<H, D, K>) -> Self {
+        let full_unit = su.as_signable();
+        Unit {
+            pre_unit: full_unit.pre_unit.clone(),
+            hash: full_unit.hash(),
+            priority: full_unit.priority(),
+        }
+    }
 }
 
 impl<H: Hasher> Unit<H> {
+    #[cfg(test)]
     pub(crate) fn new(pre_unit: PreUnit<H>, hash: H::Hash) -> Self {
-        Unit { pre_unit, hash }
+        Unit {
+            pre_unit,
+            hash,
+            priority: None,
+        }
     }
     pub(crate) fn creator(&self) -> NodeIndex {
         self.pre_unit.creator()
@@ -221,13 +379,17 @@ impl<H: Hasher> Unit<H> {
     pub(crate) fn hash(&self) -> H::Hash {
         self.hash
     }
+    /// The output of the VRF proven by the creator for the round, if the session uses one.
+    pub(crate) fn priority(&self) -> Option<H::Hash> {
+        self.priority
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         units::{ControlHash, FullUnit as GenericFullUnit, PreUnit as GenericPreUnit},
-        Hasher, NodeIndex,
+        Hasher, NodeIndex, SessionId,
     };
     use aleph_bft_mock::{Data, Hasher64};
     use codec::{Decode, Encode};
@@ -239,12 +401,12 @@ mod tests {
     fn test_full_unit_hash_is_correct() {
         let ch = ControlHash::<Hasher64>::new(&vec![].into());
         let pre_unit = PreUnit::new(NodeIndex(5), 6, ch);
-        let full_unit = FullUnit::new(pre_unit, Some(7), 8);
+        let full_unit = FullUnit::new(pre_unit, Some(7), SessionId::from_number(8));
         let hash = full_unit.using_encoded(Hasher64::hash);
         assert_eq!(full_unit.hash(), hash);
         let ch = ControlHash::<Hasher64>::new(&vec![].into());
         let pre_unit = PreUnit::new(NodeIndex(5), 6, ch);
-        let full_unit = FullUnit::new(pre_unit, None, 8);
+        let full_unit = FullUnit::new(pre_unit, None, SessionId::from_number(8));
         let hash = full_unit.using_encoded(Hasher64::hash);
         assert_eq!(full_unit.hash(), hash);
     }
@@ -262,17 +424,48 @@ mod tests {
     fn test_full_unit_codec() {
         let ch = ControlHash::<Hasher64>::new(&vec![].into());
         let pre_unit = PreUnit::new(NodeIndex(5), 6, ch);
-        let full_unit = FullUnit::new(pre_unit, Some(7), 8);
+        let full_unit = FullUnit::new(pre_unit, Some(7), SessionId::from_number(8));
         full_unit.hash();
         let encoded = full_unit.encode();
         let decoded = FullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
         assert_eq!(decoded, full_unit);
         let ch = ControlHash::<Hasher64>::new(&vec![].into());
         let pre_unit = PreUnit::new(NodeIndex(5), 6, ch);
-        let full_unit = FullUnit::new(pre_unit, None, 8);
+        let full_unit = FullUnit::new(pre_unit, None, SessionId::from_number(8));
         full_unit.hash();
         let encoded = full_unit.encode();
         let decoded = FullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
         assert_eq!(decoded, full_unit);
     }
+
+    #[test]
+    fn full_units_with_vrf_proofs_keep_their_encoding() {
+        let ch = ControlHash::<Hasher64>::new(&vec![].into());
+        for data in [None, Some(7)] {
+            let pre_unit = PreUnit::new(NodeIndex(5), 6, ch.clone());
+            let full_unit = FullUnit::new(pre_unit.clone(), data, SessionId::from_number(8));
+            // Without a proof the encoding is the one of all the fields in order.
+            assert_eq!(
+                full_unit.encode(),
+                (&pre_unit, data, SessionId::from_number(8)).encode()
+            );
+            let with_proof = full_unit.clone().with_vrf_proof(vec![1, 2, 3]);
+            assert_ne!(with_proof.hash(), full_unit.hash());
+            let encoded = with_proof.encode();
+            let decoded =
+                FullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
+            assert_eq!(decoded, with_proof);
+            assert_eq!(decoded.vrf_proof(), Some(&[1, 2, 3][..]));
+            assert_eq!(decoded.hash(), with_proof.hash());
+        }
+        let mut encoded = FullUnit::new(
+            PreUnit::new(NodeIndex(5), 6, ch),
+            None,
+            SessionId::from_number(8),
+        )
+        .encode();
+        let flags = encoded.len() - 33;
+        encoded[flags] = 4;
+        assert!(FullUnit::decode(&mut encoded.as_slice()).is_err());
+    }
 }
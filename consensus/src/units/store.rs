@@ -1,12 +1,18 @@
 use super::*;
+use crate::alloc_profiling::{measure, HotPath};
+use codec::Encode;
 use itertools::Itertools;
 use log::{trace, warn};
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
 
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct UnitStoreStatus<'a> {
     forkers: &'a NodeSubset,
     size: usize,
+    byte_size: usize,
     height: Option<Round>,
     top_row: NodeMap<Round>,
     first_missing_rounds: NodeMap<Round>,
@@ -16,6 +22,7 @@ impl<'a> UnitStoreStatus<'a> {
     fn new(
         forkers: &'a NodeSubset,
         size: usize,
+        byte_size: usize,
         height: Option<Round>,
         top_row: NodeMap<Round>,
         first_missing_rounds: NodeMap<Round>,
@@ -23,6 +30,7 @@ impl<'a> UnitStoreStatus<'a> {
         Self {
             forkers,
             size,
+            byte_size,
             height,
             top_row,
             first_missing_rounds,
@@ -33,6 +41,7 @@ impl<'a> UnitStoreStatus<'a> {
 impl<'a> fmt::Display for UnitStoreStatus<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DAG size - {}", self.size)?;
+        write!(f, "; DAG bytes - {}", self.byte_size)?;
         if let Some(r) = self.height {
             write!(f, "; DAG height - {}", r)?;
         }
@@ -63,6 +72,14 @@ pub(crate) struct UnitStore<H: Hasher, D: Data, K: Keychain> {
     is_forker: NodeSubset,
     legit_buffer: Vec<SignedUnit<H, D, K>>,
     max_round: Round,
+    // approximate number of bytes taken by the stored units and parents
+    byte_size: usize,
+    // the highest round of a stored unit of every creator
+    top_rounds: NodeMap<Round>,
+    // the hashes of the ordered units that can still be pruned, by round
+    prunable: BTreeMap<Round, Vec<H::Hash>>,
+    // the coords of the pruned units, so that they are not requested or added again
+    pruned: HashSet<UnitCoord>,
 }
 
 impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
@@ -75,6 +92,10 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
             is_forker: NodeSubset::with_size(n_nodes),
             legit_buffer: Vec::new(),
             max_round,
+            byte_size: 0,
+            top_rounds: NodeMap::with_size(n_nodes),
+            prunable: BTreeMap::new(),
+            pruned: HashSet::new(),
         }
     }
 
@@ -101,12 +122,18 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
         UnitStoreStatus::new(
             &self.is_forker,
             self.by_coord.len(),
+            self.byte_size,
             self.by_coord.keys().map(|k| k.round).max(),
             top_row,
             first_missing_rounds,
         )
     }
 
+    /// Approximate number of bytes taken by the stored units and parents.
+    pub(crate) fn byte_size(&self) -> usize {
+        self.byte_size
+    }
+
     pub(crate) fn unit_by_coord(&self, coord: UnitCoord) -> Option<&SignedUnit<H, D, K>> {
         self.by_coord.get(&coord)
     }
@@ -119,8 +146,19 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
         self.by_hash.contains_key(hash)
     }
 
+    /// Whether the unit with the coord is in the store, or was pruned from it.
     pub(crate) fn contains_coord(&self, coord: &UnitCoord) -> bool {
-        self.by_coord.contains_key(coord)
+        self.by_coord.contains_key(coord) || self.pruned.contains(coord)
+    }
+
+    /// The number of units pruned from the store.
+    pub(crate) fn pruned_count(&self) -> usize {
+        self.pruned.len()
+    }
+
+    /// The highest round of a stored unit of the creator.
+    pub(crate) fn top_round(&self, creator: NodeIndex) -> Option<Round> {
+        self.top_rounds.get(creator).copied()
     }
 
     pub(crate) fn newest_unit(
@@ -167,6 +205,10 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
     }
 
     pub(crate) fn add_unit(&mut self, su: SignedUnit<H, D, K>, alert: bool) {
+        measure(HotPath::StoreInsertion, || self.insert_unit(su, alert))
+    }
+
+    fn insert_unit(&mut self, su: SignedUnit<H, D, K>, alert: bool) {
         let hash = su.as_signable().hash();
         let creator = su.as_signable().creator();
 
@@ -182,8 +224,17 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
             trace!(target: "AlephBFT-unit-store", "A unit ignored as a duplicate {:?}.", su.as_signable());
             return;
         }
+        if self.pruned.contains(&su.as_signable().coord()) {
+            trace!(target: "AlephBFT-unit-store", "A unit ignored as already pruned {:?}.", su.as_signable());
+            return;
+        }
+        self.byte_size += su.as_signable().encoded_size();
         self.by_hash.insert(hash, su.clone());
         self.by_coord.insert(su.as_signable().coord(), su.clone());
+        let round = su.as_signable().round();
+        if self.top_round(creator).map_or(true, |top| top < round) {
+            self.top_rounds.insert(creator, round);
+        }
 
         if alert || !self.is_forker[creator] {
             self.legit_buffer.push(su);
@@ -191,27 +242,104 @@ impl<H: Hasher, D: Data, K: Keychain> UnitStore<H, D, K> {
     }
 
     pub(crate) fn add_parents(&mut self, hash: H::Hash, parents: Vec<H::Hash>) {
-        self.parents.insert(hash, parents);
+        let parents_size = parents.len() * std::mem::size_of::<H::Hash>();
+        if self.parents.insert(hash, parents).is_none() {
+            self.byte_size += parents_size;
+        }
     }
 
     pub(crate) fn get_parents(&mut self, hash: H::Hash) -> Option<&Vec<H::Hash>> {
         self.parents.get(&hash)
     }
+
+    /// The parents of the unit, if they are known already.
+    pub(crate) fn parents(&self, hash: &H::Hash) -> Option<&Vec<H::Hash>> {
+        self.parents.get(hash)
+    }
+
+    /// Makes the ordered units prunable.
+    pub(crate) fn on_ordered(&mut self, batch: &[H::Hash]) {
+        for hash in batch {
+            if let Some(su) = self.by_hash.get(hash) {
+                let round = su.as_signable().round();
+                self.prunable.entry(round).or_default().push(*hash);
+            }
+        }
+    }
+
+    /// Prunes ordered units, those of the lowest rounds first, until the store takes at most
+    /// `cap` bytes or nothing more can be pruned. The units of forkers and the top units of all
+    /// creators are kept. Returns the number of pruned units.
+    pub(crate) fn prune_to(&mut self, cap: usize) -> usize {
+        let mut pruned = 0;
+        // Top units skipped now become prunable once their creators make newer ones.
+        let mut kept = Vec::new();
+        while self.byte_size > cap {
+            let round = match self.prunable.keys().next() {
+                Some(round) => *round,
+                None => break,
+            };
+            for hash in self.prunable.remove(&round).unwrap_or_default() {
+                if self.prune_unit(&hash) {
+                    pruned += 1;
+                } else if self.is_kept_top_unit(&hash) {
+                    kept.push((round, hash));
+                }
+            }
+        }
+        for (round, hash) in kept {
+            self.prunable.entry(round).or_default().push(hash);
+        }
+        pruned
+    }
+
+    fn is_kept_top_unit(&self, hash: &H::Hash) -> bool {
+        match self.by_hash.get(hash) {
+            Some(su) => !self.is_forker[su.as_signable().creator()],
+            None => false,
+        }
+    }
+
+    fn prune_unit(&mut self, hash: &H::Hash) -> bool {
+        let (coord, unit_size) = match self.by_hash.get(hash) {
+            Some(su) => (su.as_signable().coord(), su.as_signable().encoded_size()),
+            None => return false,
+        };
+        if self.is_forker[coord.creator()] || self.top_round(coord.creator()) == Some(coord.round())
+        {
+            return false;
+        }
+        self.by_hash.remove(hash);
+        self.by_coord.remove(&coord);
+        self.byte_size -= unit_size;
+        if let Some(parents) = self.parents.remove(hash) {
+            self.byte_size -= parents.len() * std::mem::size_of::<H::Hash>();
+        }
+        self.pruned.insert(coord);
+        self.byte_size += std::mem::size_of::<UnitCoord>();
+        true
+    }
+
+    /// All units in the store, in no particular order.
+    pub(crate) fn units(&self) -> impl Iterator<Item = &SignedUnit<H, D, K>> {
+        self.by_hash.values()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         units::{ControlHash, FullUnit, PreUnit, SignedUnit, UnitCoord, UnitStore},
-        NodeCount, NodeIndex, NodeMap, Round, Signed,
+        NodeCount, NodeIndex, NodeMap, Round, SessionId, Signed,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain};
+    use codec::Encode;
 
     async fn create_unit<'a>(
         round: Round,
         node_idx: NodeIndex,
         count: NodeCount,
-        session_id: u64,
+        session_id: SessionId,
         keychain: &Keychain,
     ) -> SignedUnit<Hasher64, Data, Keychain> {
         let preunit = PreUnit::<Hasher64>::new(
@@ -237,7 +365,14 @@ mod tests {
 
         for round in 0..4 {
             for (i, keychain) in keychains.iter().enumerate() {
-                let unit = create_unit(round, NodeIndex(i), n_nodes, 0, keychain).await;
+                let unit = create_unit(
+                    round,
+                    NodeIndex(i),
+                    n_nodes,
+                    SessionId::from_number(0),
+                    keychain,
+                )
+                .await;
                 if i == 0 {
                     forker_hashes.push(unit.as_signable().hash());
                 }
@@ -247,7 +382,14 @@ mod tests {
 
         // Forker's units
         for round in 4..7 {
-            let unit = create_unit(round, NodeIndex(0), n_nodes, 0, &keychains[0]).await;
+            let unit = create_unit(
+                round,
+                NodeIndex(0),
+                n_nodes,
+                SessionId::from_number(0),
+                &keychains[0],
+            )
+            .await;
             forker_hashes.push(unit.as_signable().hash());
             store.add_unit(unit, false);
         }
@@ -282,4 +424,108 @@ mod tests {
             assert!(store.by_hash.contains_key(hash));
         }
     }
+
+    #[tokio::test]
+    async fn byte_size_counts_units_once() {
+        let n_nodes = NodeCount(4);
+        let keychain = Keychain::new(n_nodes, NodeIndex(0));
+        let mut store = UnitStore::<Hasher64, Data, Keychain>::new(n_nodes, 100);
+        assert_eq!(store.byte_size(), 0);
+
+        let unit = create_unit(
+            0,
+            NodeIndex(0),
+            n_nodes,
+            SessionId::from_number(0),
+            &keychain,
+        )
+        .await;
+        let unit_size = unit.as_signable().encoded_size();
+        store.add_unit(unit.clone(), false);
+        store.add_unit(unit, false);
+
+        assert_eq!(store.byte_size(), unit_size);
+    }
+
+    #[tokio::test]
+    async fn tracks_top_rounds() {
+        let n_nodes = NodeCount(4);
+        let keychain = Keychain::new(n_nodes, NodeIndex(1));
+        let mut store = UnitStore::<Hasher64, Data, Keychain>::new(n_nodes, 100);
+        assert_eq!(store.top_round(NodeIndex(1)), None);
+
+        for round in [2, 5, 3] {
+            let unit = create_unit(
+                round,
+                NodeIndex(1),
+                n_nodes,
+                SessionId::from_number(0),
+                &keychain,
+            )
+            .await;
+            store.add_unit(unit, false);
+        }
+
+        assert_eq!(store.top_round(NodeIndex(1)), Some(5));
+        assert_eq!(store.top_round(NodeIndex(0)), None);
+    }
+
+    #[tokio::test]
+    async fn prunes_ordered_units_down_to_cap() {
+        let n_nodes = NodeCount(4);
+        let keychain = Keychain::new(n_nodes, NodeIndex(1));
+        let mut store = UnitStore::<Hasher64, Data, Keychain>::new(n_nodes, 100);
+        let mut units = Vec::new();
+        for round in 0..4 {
+            let unit = create_unit(
+                round,
+                NodeIndex(1),
+                n_nodes,
+                SessionId::from_number(0),
+                &keychain,
+            )
+            .await;
+            units.push(unit.clone());
+            store.add_unit(unit, false);
+        }
+        let unit_size = units[0].as_signable().encoded_size();
+        // Nothing was ordered yet.
+        assert_eq!(store.prune_to(0), 0);
+
+        let hashes: Vec<_> = units.iter().map(|unit| unit.as_signable().hash()).collect();
+        store.on_ordered(&hashes);
+        let cap = 3 * unit_size + std::mem::size_of::<UnitCoord>();
+        assert_eq!(store.prune_to(cap), 1);
+        let coord = UnitCoord::new(0, NodeIndex(1));
+        assert!(store.unit_by_coord(coord).is_none());
+        assert!(store.contains_coord(&coord));
+
+        // The top unit stays.
+        assert_eq!(store.prune_to(0), 2);
+        assert_eq!(store.pruned_count(), 3);
+        assert!(store.unit_by_hash(&hashes[3]).is_some());
+        assert_eq!(
+            store.byte_size(),
+            unit_size + 3 * std::mem::size_of::<UnitCoord>()
+        );
+
+        // Pruned units are not added again.
+        store.add_unit(units[0].clone(), false);
+        assert!(store.unit_by_coord(coord).is_none());
+
+        // The former top unit gets pruned once there is a newer one.
+        store.add_unit(
+            create_unit(
+                4,
+                NodeIndex(1),
+                n_nodes,
+                SessionId::from_number(0),
+                &keychain,
+            )
+            .await,
+            false,
+        );
+        assert_eq!(store.prune_to(0), 1);
+        assert!(store.unit_by_hash(&hashes[3]).is_none());
+    }
 }
@@ -50,3 +50,14 @@ pub async fn preunit_to_unchecked_signed_unit(
     let signed_unit = Signed::sign(full_unit, keychain).await;
     signed_unit.into()
 }
+
+pub async fn preunit_to_unchecked_signed_unit_with_vrf_proof(
+    pu: PreUnit,
+    session_id: SessionId,
+    keychain: &Keychain,
+    vrf_proof: Vec<u8>,
+) -> UncheckedSignedUnit {
+    let full_unit = FullUnit::new(pu, Some(0), session_id).with_vrf_proof(vrf_proof);
+    let signed_unit = Signed::sign(full_unit, keychain).await;
+    signed_unit.into()
+}
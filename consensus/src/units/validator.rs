@@ -1,10 +1,15 @@
 use crate::{
-    units::{FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit},
-    Data, Hasher, Keychain, NodeCount, Round, SessionId, Signature, SignatureError,
+    key_rotation::KeySchedule,
+    units::{FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, UnitVrf},
+    Data, Hasher, Keychain, NodeCount, NodeIndex, Round, SessionId, Signature, SignatureError,
+    UncheckedSigned, ValidationProfile, VerificationCache,
 };
+use codec::{Decode, Encode};
 use std::{
+    collections::BTreeMap,
     fmt::{Display, Formatter, Result as FmtResult},
     result::Result as StdResult,
+    thread,
 };
 
 /// All that can be wrong with a unit except control hash issues.
@@ -17,6 +22,9 @@ pub enum ValidationError<H: Hasher, D: Data, S: Signature> {
     RoundZeroWithParents(PreUnit<H>),
     NotEnoughParents(PreUnit<H>),
     NotDescendantOfPreviousUnit(PreUnit<H>),
+    CreatorOutOfRange(PreUnit<H>),
+    NonCanonicalData(FullUnit<H, D>),
+    WrongVrfProof(FullUnit<H, D>),
 }
 
 impl<H: Hasher, D: Data, S: Signature> Display for ValidationError<H, D, S> {
@@ -44,10 +52,127 @@ impl<H: Hasher, D: Data, S: Signature> Display for ValidationError<H, D, S> {
                 "nonzero round unit is not descendant of its creator's previous unit: {:?}",
                 pu
             ),
+            CreatorOutOfRange(pu) => write!(
+                f,
+                "unit created by {:?}, who is not a member: {:?}",
+                pu.creator(),
+                pu
+            ),
+            NonCanonicalData(fu) => write!(
+                f,
+                "unit with data that does not encode back to itself: {:?}",
+                fu
+            ),
+            WrongVrfProof(fu) => write!(
+                f,
+                "unit with a missing, unexpected or invalid VRF proof: {:?}",
+                fu
+            ),
         }
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> ValidationError<H, D, S> {
+    /// Why the unit was rejected, without the unit itself.
+    pub fn reason(&self) -> RejectionReason {
+        use ValidationError::*;
+        match self {
+            WrongSignature(_) => RejectionReason::BadSignature,
+            WrongSession(_) => RejectionReason::WrongSession,
+            RoundTooHigh(_) => RejectionReason::RoundTooHigh,
+            WrongNumberOfMembers(_)
+            | RoundZeroWithParents(_)
+            | NotEnoughParents(_)
+            | NotDescendantOfPreviousUnit(_) => RejectionReason::WrongParents,
+            CreatorOutOfRange(_) => RejectionReason::UnknownCreator,
+            NonCanonicalData(_) => RejectionReason::NonCanonicalData,
+            WrongVrfProof(_) => RejectionReason::WrongVrfProof,
+        }
+    }
+
+    /// The creator of the rejected unit, if it is to blame. Only the creator is checked before
+    /// the signature, so the creator signed every rejected unit except the ones of creators
+    /// outside the committee and the wrongly signed ones, which anybody could have made up.
+    pub fn culprit(&self) -> Option<NodeIndex> {
+        use ValidationError::*;
+        match self {
+            WrongSignature(_) | CreatorOutOfRange(_) => None,
+            WrongSession(fu) | RoundTooHigh(fu) | NonCanonicalData(fu) | WrongVrfProof(fu) => {
+                Some(fu.creator())
+            }
+            WrongNumberOfMembers(pu)
+            | RoundZeroWithParents(pu)
+            | NotEnoughParents(pu)
+            | NotDescendantOfPreviousUnit(pu) => Some(pu.creator()),
+        }
+    }
+
+    /// The report on the rejection of the unit, see [`UnitRejection`].
+    pub fn rejection(&self) -> UnitRejection {
+        UnitRejection {
+            culprit: self.culprit(),
+            reason: self.reason(),
+            evidence: None,
+        }
+    }
+}
+
+/// The kinds of [`ValidationError`], for counting rejected units in the [`StatsSnapshot`] and
+/// for scoring peers, see [`UnitRejection`].
+///
+/// [`StatsSnapshot`]: crate::StatsSnapshot
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum RejectionReason {
+    /// The unit is not signed by its creator.
+    BadSignature,
+    /// The unit belongs to another session.
+    WrongSession,
+    /// The round of the unit is above the maximal round of the session.
+    RoundTooHigh,
+    /// The parents of the unit break the rules of the Dag, e.g. there are too few of them, or
+    /// the previous unit of the creator is not among them.
+    WrongParents,
+    /// The creator of the unit is not a member of the committee.
+    UnknownCreator,
+    /// The data of the unit does not encode back to itself.
+    NonCanonicalData,
+    /// The VRF proof of the unit is missing, unexpected or invalid.
+    WrongVrfProof,
+}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use RejectionReason::*;
+        match self {
+            BadSignature => write!(f, "bad signature"),
+            WrongSession => write!(f, "wrong session"),
+            RoundTooHigh => write!(f, "round too high"),
+            WrongParents => write!(f, "wrong parents"),
+            UnknownCreator => write!(f, "unknown creator"),
+            NonCanonicalData => write!(f, "non-canonical data"),
+            WrongVrfProof => write!(f, "wrong VRF proof"),
+        }
+    }
+}
+
+/// A unit received from the network that failed validation, see
+/// [`LocalIO::enable_rejection_reports`].
+///
+/// [`LocalIO::enable_rejection_reports`]: crate::LocalIO::enable_rejection_reports
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UnitRejection {
+    /// The node that signed the invalid unit, `None` if the signature itself is wrong, as
+    /// anybody could have made such a unit up.
+    pub culprit: Option<NodeIndex>,
+    pub reason: RejectionReason,
+    /// The encoded [`GrowthRuleEvidence`] against the culprit, if the unit was rejected for
+    /// breaking the growth rule. Anybody knowing the keys of the committee can decode and check
+    /// it.
+    ///
+    /// [`GrowthRuleEvidence`]: crate::GrowthRuleEvidence
+    pub evidence: Option<Vec<u8>>,
+}
+
 impl<H: Hasher, D: Data, S: Signature> From<SignatureError<FullUnit<H, D>, S>>
     for ValidationError<H, D, S>
 {
@@ -62,11 +187,19 @@ pub struct Validator<K: Keychain> {
     keychain: K,
     max_round: Round,
     threshold: NodeCount,
+    profile: ValidationProfile,
+    cache: Option<VerificationCache>,
+    key_schedule: KeySchedule,
+    verification_threads: usize,
+    vrf: Option<UnitVrf>,
 }
 
 type Result<H, D, K> =
     StdResult<SignedUnit<H, D, K>, ValidationError<H, D, <K as Keychain>::Signature>>;
 
+type CheckResult<H, D, K> =
+    StdResult<SignedUnit<H, D, K>, SignatureError<FullUnit<H, D>, <K as Keychain>::Signature>>;
+
 impl<K: Keychain> Validator<K> {
     pub fn new(session_id: SessionId, keychain: K, max_round: Round, threshold: NodeCount) -> Self {
         Validator {
@@ -74,14 +207,156 @@ impl<K: Keychain> Validator<K> {
             keychain,
             max_round,
             threshold,
+            profile: ValidationProfile::Strict,
+            cache: None,
+            key_schedule: KeySchedule::default(),
+            verification_threads: 1,
+            vrf: None,
+        }
+    }
+
+    pub fn with_profile(self, profile: ValidationProfile) -> Self {
+        Validator { profile, ..self }
+    }
+
+    pub fn profile(&self) -> ValidationProfile {
+        self.profile
+    }
+
+    /// Remembers the signatures of at most `capacity` units, so that units received again are
+    /// not verified again. Zero disables the cache.
+    pub fn with_verification_cache(self, capacity: usize) -> Self {
+        let cache = (capacity > 0).then(|| VerificationCache::new(capacity));
+        Validator { cache, ..self }
+    }
+
+    /// Verifies the signatures of batches of units on up to `threads` threads. At most one
+    /// disables parallel verification.
+    pub fn with_verification_threads(self, threads: usize) -> Self {
+        Validator {
+            verification_threads: threads,
+            ..self
         }
     }
 
+    /// Verifies signatures of units with the keys of the epochs of their rounds.
+    pub(crate) fn with_key_schedule(self, key_schedule: KeySchedule) -> Self {
+        Validator {
+            key_schedule,
+            ..self
+        }
+    }
+
+    /// Requires every unit to prove its priority with the VRF. Without a VRF units with proofs
+    /// are rejected, as their creators could choose their priorities.
+    pub(crate) fn with_vrf(self, vrf: Option<UnitVrf>) -> Self {
+        Validator { vrf, ..self }
+    }
+
     pub fn validate_unit<H: Hasher, D: Data>(
         &self,
         uu: UncheckedSignedUnit<H, D, K::Signature>,
     ) -> Result<H, D, K> {
-        let su = uu.check(&self.keychain)?;
+        self.check_creator(&uu)?;
+        let keychain = self
+            .key_schedule
+            .keychain(&self.keychain, uu.as_signable().round());
+        let su = match &self.cache {
+            Some(cache) => uu.check_cached(&keychain, cache)?,
+            None => uu.check(&keychain)?,
+        };
+        self.validate_signed_unit(su)
+    }
+
+    /// Validates all the units, verifying their signatures in a single batch, e.g. when
+    /// catching up. The results are in the order of the units.
+    pub fn validate_units<H: Hasher, D: Data>(
+        &self,
+        uus: Vec<UncheckedSignedUnit<H, D, K::Signature>>,
+    ) -> Vec<Result<H, D, K>> {
+        // Units of different key epochs are verified in separate batches.
+        let mut batches = BTreeMap::new();
+        let mut results = Vec::new();
+        for (position, uu) in uus.into_iter().enumerate() {
+            if let Err(e) = self.check_creator(&uu) {
+                results.push((position, Err(e)));
+                continue;
+            }
+            let epoch = self.key_schedule.epoch(uu.as_signable().round());
+            batches
+                .entry(epoch)
+                .or_insert_with(Vec::new)
+                .push((position, uu));
+        }
+        for (epoch, batch) in batches {
+            let keychain = self.keychain.at_epoch(epoch);
+            let (positions, uus): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+            let checked = self.check_batch(uus, &keychain).into_iter().map(|checked| {
+                checked
+                    .map_err(ValidationError::from)
+                    .and_then(|su| self.validate_signed_unit(su))
+            });
+            results.extend(positions.into_iter().zip(checked));
+        }
+        results.sort_by_key(|(position, _)| *position);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    // Keychains need not expect verifying signatures of nodes outside the committee.
+    fn check_creator<H: Hasher, D: Data>(
+        &self,
+        uu: &UncheckedSignedUnit<H, D, K::Signature>,
+    ) -> StdResult<(), ValidationError<H, D, K::Signature>> {
+        let pre_unit = uu.as_signable().as_pre_unit();
+        match pre_unit.creator().0 < self.keychain.node_count().0 {
+            true => Ok(()),
+            false => Err(ValidationError::CreatorOutOfRange(pre_unit.clone())),
+        }
+    }
+
+    // Splits the batch into contiguous chunks verified in parallel, so concatenating their
+    // results keeps the order of the units, and thus of the units of every creator.
+    fn check_batch<H: Hasher, D: Data>(
+        &self,
+        uus: Vec<UncheckedSignedUnit<H, D, K::Signature>>,
+        keychain: &K,
+    ) -> Vec<CheckResult<H, D, K>> {
+        let cache = &self.cache;
+        let check = |uus: Vec<UncheckedSignedUnit<H, D, K::Signature>>| match cache {
+            Some(cache) => UncheckedSigned::check_batch_cached(uus, keychain, cache),
+            None => UncheckedSigned::check_batch(uus, keychain),
+        };
+        let threads = self.verification_threads.min(uus.len());
+        if threads <= 1 {
+            return check(uus);
+        }
+        let chunk_size = (uus.len() + threads - 1) / threads;
+        let mut chunks = Vec::with_capacity(threads);
+        let mut rest = uus;
+        while rest.len() > chunk_size {
+            let tail = rest.split_off(chunk_size);
+            chunks.push(rest);
+            rest = tail;
+        }
+        chunks.push(rest);
+        let check = &check;
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| scope.spawn(move || check(chunk)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("verifying signatures does not panic"))
+                .collect()
+        })
+    }
+
+    /// Validates a unit whose signature was already verified.
+    pub fn validate_signed_unit<H: Hasher, D: Data>(
+        &self,
+        su: SignedUnit<H, D, K>,
+    ) -> Result<H, D, K> {
         let full_unit = su.as_signable();
         if full_unit.session_id() != self.session_id {
             // NOTE: this implies malicious behavior as the unit's session_id
@@ -91,7 +366,43 @@ impl<K: Keychain> Validator<K> {
         if full_unit.round() > self.max_round {
             return Err(ValidationError::RoundTooHigh(full_unit.clone()));
         }
-        self.validate_unit_parents(su)
+        let proven = match (&self.vrf, full_unit.vrf_proof()) {
+            (Some(_), Some(_)) if self.profile == ValidationProfile::Light => true,
+            (Some(vrf), Some(proof)) => vrf.verify(
+                full_unit.session_id(),
+                full_unit.round(),
+                proof,
+                full_unit.creator(),
+            ),
+            (None, None) => true,
+            _ => false,
+        };
+        if !proven {
+            return Err(ValidationError::WrongVrfProof(full_unit.clone()));
+        }
+        let su = self.validate_unit_parents(su)?;
+        match self.profile {
+            ValidationProfile::Audit => self.check_invariants(su),
+            _ => Ok(su),
+        }
+    }
+
+    // Properties that honest implementations guarantee, and that the rest of the code relies
+    // on without being able to check them cheaply.
+    fn check_invariants<H: Hasher, D: Data>(&self, su: SignedUnit<H, D, K>) -> Result<H, D, K> {
+        let full_unit = su.as_signable();
+        if let Some(data) = full_unit.data() {
+            // The unit is hashed, and its signature verified, over its encoding, so data that
+            // is encoded differently than it was received, or that does not decode back to
+            // itself, changes the hash of the unit whenever it is passed on.
+            let encoded = data.encode();
+            let decodes_back =
+                D::decode(&mut &encoded[..]).map_or(false, |decoded| decoded == *data);
+            if !full_unit.has_canonical_data() || !decodes_back {
+                return Err(ValidationError::NonCanonicalData(full_unit.clone()));
+            }
+        }
+        Ok(su)
     }
 
     fn validate_unit_parents<H: Hasher, D: Data>(
@@ -125,13 +436,20 @@ impl<K: Keychain> Validator<K> {
 
 #[cfg(test)]
 mod tests {
-    use super::{ValidationError::*, Validator as GenericValidator};
+    use super::{
+        RejectionReason, UnitRejection, ValidationError::*, Validator as GenericValidator,
+    };
     use crate::{
         creation::Creator as GenericCreator,
-        units::{create_units, creator_set, preunit_to_unchecked_signed_unit, preunit_to_unit},
-        NodeCount, NodeIndex,
+        units::{
+            create_units, creator_set, preunit_to_unchecked_signed_unit,
+            preunit_to_unchecked_signed_unit_with_vrf_proof, preunit_to_unit, ControlHash,
+            FullUnit, PreUnit, UncheckedSignedUnit, UnitVrf,
+        },
+        Hasher, NodeCount, NodeIndex, NodeSubset, SessionId, Signed, ValidationProfile,
     };
-    use aleph_bft_mock::{Hasher64, Keychain};
+    use aleph_bft_mock::{Hasher64, Keychain, Signature};
+    use codec::{Decode, Encode, Error as CodecError, Input, Output};
 
     type Validator = GenericValidator<Keychain>;
     type Creator = GenericCreator<Hasher64>;
@@ -141,7 +459,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let round = 0;
         let max_round = 2;
         let creator = Creator::new(creator_id, n_members);
@@ -162,8 +480,8 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
-        let wrong_session_id = 43;
+        let session_id = SessionId::from_number(0);
+        let wrong_session_id = SessionId::from_number(43);
         let round = 0;
         let max_round = 2;
         let creator = Creator::new(creator_id, n_members);
@@ -182,13 +500,99 @@ mod tests {
         assert_eq!(full_unit, unchecked_unit.into_signable());
     }
 
+    #[tokio::test]
+    async fn reports_rejections() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let creator_id = NodeIndex(3);
+        let session_id = SessionId::from_number(0);
+        let creator = Creator::new(creator_id, n_members);
+        let keychain = Keychain::new(n_members, creator_id);
+        let validator = Validator::new(session_id, keychain, 2, threshold);
+        let (preunit, _) = creator.create_unit(0).expect("Creation should succeed.");
+
+        let unchecked_unit = preunit_to_unchecked_signed_unit(
+            preunit.clone(),
+            SessionId::from_number(43),
+            &keychain,
+        )
+        .await;
+        let error = validator
+            .validate_unit(unchecked_unit)
+            .expect_err("Validated bad unit.");
+        assert_eq!(
+            error.rejection(),
+            UnitRejection {
+                culprit: Some(creator_id),
+                reason: RejectionReason::WrongSession,
+                evidence: None,
+            }
+        );
+
+        let impostor = Keychain::new(n_members, NodeIndex(1));
+        let unchecked_unit = preunit_to_unchecked_signed_unit(preunit, session_id, &impostor).await;
+        let error = validator
+            .validate_unit(unchecked_unit)
+            .expect_err("Validated bad unit.");
+        assert_eq!(
+            error.rejection(),
+            UnitRejection {
+                culprit: None,
+                reason: RejectionReason::BadSignature,
+                evidence: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn validates_batches() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let session_id = SessionId::from_number(0);
+        let wrong_session_id = SessionId::from_number(43);
+        let max_round = 2;
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|id| Keychain::new(n_members, id))
+            .collect();
+        let validator = Validator::new(session_id, keychains[0], max_round, threshold);
+        let mut unchecked_units = Vec::new();
+        for (id, (unit_session_id, signer)) in
+            [(session_id, 0), (session_id, 2), (wrong_session_id, 2)]
+                .into_iter()
+                .enumerate()
+        {
+            let (preunit, _) = Creator::new(NodeIndex(id), n_members)
+                .create_unit(0)
+                .expect("Creation should succeed.");
+            unchecked_units.push(
+                preunit_to_unchecked_signed_unit(preunit, unit_session_id, &keychains[signer])
+                    .await,
+            );
+        }
+        let parallel_results = validator
+            .clone()
+            .with_verification_threads(2)
+            .validate_units(unchecked_units.clone());
+        let mut results = validator.validate_units(unchecked_units.clone());
+        assert_eq!(parallel_results, results);
+        assert_eq!(results.len(), 3);
+        let checked_unit = results.remove(0).expect("Unit should validate.");
+        assert_eq!(unchecked_units[0], checked_unit.into());
+        assert_eq!(results[0], Err(WrongSignature(unchecked_units[1].clone())));
+        assert_eq!(
+            results[1],
+            Err(WrongSession(unchecked_units[2].clone().into_signable()))
+        );
+    }
+
     #[tokio::test]
     async fn detects_wrong_number_of_members() {
         let n_members = NodeCount(7);
         let n_plus_one_members = NodeCount(8);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let round = 0;
         let max_round = 2;
         let creator = Creator::new(creator_id, n_members);
@@ -214,7 +618,7 @@ mod tests {
         // doing something wrong by having multiple sources for what is "threshold".
         let threshold = NodeCount(6);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let round = 1;
         let max_round = 2;
         let mut creators = creator_set(n_members);
@@ -245,7 +649,7 @@ mod tests {
         let n_members = NodeCount(7);
         let threshold = NodeCount(5);
         let creator_id = NodeIndex(0);
-        let session_id = 0;
+        let session_id = SessionId::from_number(0);
         let round = 3;
         let max_round = 2;
         let mut creators = creator_set(n_members);
@@ -272,4 +676,200 @@ mod tests {
         };
         assert_eq!(full_unit, unchecked_unit.into_signable());
     }
+
+    #[tokio::test]
+    async fn checks_vrf_proofs() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let creator_id = NodeIndex(0);
+        let session_id = SessionId::from_number(0);
+        let max_round = 2;
+        let creator = Creator::new(creator_id, n_members);
+        let keychain = Keychain::new(n_members, creator_id);
+        let vrf = UnitVrf::new(keychain);
+        let validator =
+            Validator::new(session_id, keychain, max_round, threshold).with_vrf(Some(vrf.clone()));
+        let (preunit, _) = creator.create_unit(0).expect("Creation should succeed.");
+        let proof = vrf.prove(session_id, 0).await;
+        let proven = preunit_to_unchecked_signed_unit_with_vrf_proof(
+            preunit.clone(),
+            session_id,
+            &keychain,
+            proof,
+        )
+        .await;
+        assert!(validator.validate_unit(proven.clone()).is_ok());
+        let wrongly_proven = preunit_to_unchecked_signed_unit_with_vrf_proof(
+            preunit.clone(),
+            session_id,
+            &keychain,
+            vrf.prove(session_id, 1).await,
+        )
+        .await;
+        let unproven = preunit_to_unchecked_signed_unit(preunit, session_id, &keychain).await;
+        for unchecked_unit in [wrongly_proven, unproven] {
+            match validator.validate_unit(unchecked_unit) {
+                Err(WrongVrfProof(_)) => {}
+                result => panic!("Unexpected result from validator: {:?}", result),
+            }
+        }
+        let validator = validator.with_vrf(None);
+        match validator.validate_unit(proven) {
+            Err(WrongVrfProof(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn light_profile_skips_only_vrf_proofs() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let creator_id = NodeIndex(0);
+        let session_id = SessionId::from_number(0);
+        let wrong_session_id = SessionId::from_number(43);
+        let max_round = 2;
+        let creator = Creator::new(creator_id, n_members);
+        let keychain = Keychain::new(n_members, creator_id);
+        let vrf = UnitVrf::new(keychain);
+        let validator = Validator::new(session_id, keychain, max_round, threshold)
+            .with_profile(ValidationProfile::Light)
+            .with_vrf(Some(vrf.clone()));
+        let (preunit, _) = creator.create_unit(0).expect("Creation should succeed.");
+        let wrongly_proven = preunit_to_unchecked_signed_unit_with_vrf_proof(
+            preunit.clone(),
+            session_id,
+            &keychain,
+            vrf.prove(session_id, 1).await,
+        )
+        .await;
+        let checked_unit = validator
+            .validate_unit(wrongly_proven.clone())
+            .expect("Unit should validate.");
+        assert_eq!(wrongly_proven, checked_unit.into());
+
+        let unproven =
+            preunit_to_unchecked_signed_unit(preunit.clone(), session_id, &keychain).await;
+        match validator.validate_unit(unproven) {
+            Err(WrongVrfProof(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+        let wrong_session = preunit_to_unchecked_signed_unit_with_vrf_proof(
+            preunit,
+            wrong_session_id,
+            &keychain,
+            vrf.prove(wrong_session_id, 0).await,
+        )
+        .await;
+        match validator.validate_unit(wrong_session) {
+            Err(WrongSession(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_creator_before_signature() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let session_id = SessionId::from_number(0);
+        let validator = Validator::new(
+            session_id,
+            Keychain::new(n_members, NodeIndex(0)),
+            2,
+            threshold,
+        );
+        let control_hash = ControlHash {
+            parents_mask: NodeSubset::with_size(n_members),
+            combined_hash: 0.using_encoded(Hasher64::hash),
+        };
+        let preunit = PreUnit::new(NodeIndex(7), 0, control_hash);
+        // Signed by someone else, which is not noticed.
+        let unchecked_unit = preunit_to_unchecked_signed_unit(
+            preunit,
+            session_id,
+            &Keychain::new(n_members, NodeIndex(1)),
+        )
+        .await;
+        match validator.validate_unit(unchecked_unit.clone()) {
+            Err(CreatorOutOfRange(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+        match validator.validate_units(vec![unchecked_unit]).as_slice() {
+            [Err(CreatorOutOfRange(_))] => {}
+            results => panic!("Unexpected results from validator: {:?}", results),
+        }
+    }
+
+    // Data decoding any nonzero byte as true, so it has encodings other than its own.
+    #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+    struct Lenient(bool);
+
+    impl Encode for Lenient {
+        fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+            dest.push_byte(self.0 as u8)
+        }
+    }
+
+    impl Decode for Lenient {
+        fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+            Ok(Lenient(input.read_byte()? != 0))
+        }
+    }
+
+    #[tokio::test]
+    async fn audit_profile_detects_non_canonical_data() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let creator_id = NodeIndex(0);
+        let session_id = SessionId::from_number(0);
+        let creator = Creator::new(creator_id, n_members);
+        let keychain = Keychain::new(n_members, creator_id);
+        let validator = Validator::new(session_id, keychain, 2, threshold)
+            .with_profile(ValidationProfile::Audit);
+        let (preunit, _) = creator.create_unit(0).expect("Creation should succeed.");
+        let data_position = preunit.encode().len() + 1;
+        let full_unit = FullUnit::<Hasher64, _>::new(preunit, Some(Lenient(true)), session_id);
+        let mut encoded = Signed::sign(full_unit, &keychain)
+            .await
+            .into_unchecked()
+            .encode();
+        let decode = |encoded: &[u8]| {
+            UncheckedSignedUnit::<Hasher64, Lenient, Signature>::decode(&mut &encoded[..])
+                .expect("Unit should decode.")
+        };
+        assert!(validator.validate_unit(decode(&encoded)).is_ok());
+
+        assert_eq!(encoded[data_position], 1);
+        encoded[data_position] = 2;
+        let unchecked_unit = decode(&encoded);
+        match validator.validate_unit(unchecked_unit.clone()) {
+            Err(NonCanonicalData(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+        let validator = validator.with_profile(ValidationProfile::Strict);
+        assert!(validator.validate_unit(unchecked_unit).is_ok());
+    }
+
+    #[tokio::test]
+    async fn audit_profile_performs_strict_checks() {
+        let n_members = NodeCount(7);
+        let threshold = NodeCount(5);
+        let creator_id = NodeIndex(0);
+        let session_id = SessionId::from_number(0);
+        let wrong_session_id = SessionId::from_number(43);
+        let max_round = 2;
+        let creator = Creator::new(creator_id, n_members);
+        let keychain = Keychain::new(n_members, creator_id);
+        let validator = Validator::new(session_id, keychain, max_round, threshold)
+            .with_profile(ValidationProfile::Audit);
+        let (preunit, _) = creator.create_unit(0).expect("Creation should succeed.");
+        let unchecked_unit =
+            preunit_to_unchecked_signed_unit(preunit.clone(), session_id, &keychain).await;
+        assert!(validator.validate_unit(unchecked_unit).is_ok());
+        let unchecked_unit =
+            preunit_to_unchecked_signed_unit(preunit, wrong_session_id, &keychain).await;
+        match validator.validate_unit(unchecked_unit) {
+            Err(WrongSession(_)) => {}
+            result => panic!("Unexpected result from validator: {:?}", result),
+        }
+    }
 }
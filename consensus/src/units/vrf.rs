@@ -0,0 +1,65 @@
+use crate::{NodeIndex, Round, SessionId, Vrf};
+use codec::Encode;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+const PRIORITY_DOMAIN: &[u8] = b"AlephBFT-priority";
+
+/// The VRF proving the priorities of units for head election, shared by everything creating or
+/// checking units. The priority of a unit is the output of the VRF for its session and round,
+/// so every creator has one priority per round, unknown to others before they see its unit.
+#[derive(Clone)]
+pub(crate) struct UnitVrf {
+    vrf: Arc<dyn Vrf>,
+}
+
+// VRFs are equal if they are clones of each other.
+impl PartialEq for UnitVrf {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.vrf, &other.vrf)
+    }
+}
+
+impl Eq for UnitVrf {}
+
+impl Hash for UnitVrf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.vrf) as *const ()).hash(state);
+    }
+}
+
+impl Debug for UnitVrf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("UnitVrf").finish_non_exhaustive()
+    }
+}
+
+impl UnitVrf {
+    pub(crate) fn new(vrf: impl Vrf + 'static) -> Self {
+        UnitVrf { vrf: Arc::new(vrf) }
+    }
+
+    fn input(session_id: SessionId, round: Round) -> Vec<u8> {
+        (PRIORITY_DOMAIN, session_id, round).encode()
+    }
+
+    /// The proof of our priority for units of the round.
+    pub(crate) async fn prove(&self, session_id: SessionId, round: Round) -> Vec<u8> {
+        self.vrf.prove(&Self::input(session_id, round)).await
+    }
+
+    /// Whether the proof is the one of the priority of the creator for units of the round.
+    pub(crate) fn verify(
+        &self,
+        session_id: SessionId,
+        round: Round,
+        proof: &[u8],
+        creator: NodeIndex,
+    ) -> bool {
+        self.vrf
+            .verify(&Self::input(session_id, round), proof, creator)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    Data, Index, Indexed, MultiKeychain, Multisigned, NodeIndex, PartiallyMultisigned, SessionId,
+    Signable, Signed, UncheckedSigned,
+};
+use codec::{Decode, Encode};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+// Prepended to the encoding of votes before signing, so that the signature of a vote can
+// never be mistaken for a signature of anything else.
+const VOTE_CONTEXT: &[u8] = b"AlephBFT vote";
+
+/// A vote of a committee member for a value of the application, e.g. a software upgrade or a
+/// change of parameters. The vote is bound to the session and to the round of voting, so that
+/// it cannot be replayed in other votings.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct Vote<T: Data> {
+    session_id: SessionId,
+    round: u64,
+    value: T,
+}
+
+impl<T: Data> Vote<T> {
+    pub fn new(session_id: SessionId, round: u64, value: T) -> Self {
+        Vote {
+            session_id,
+            round,
+            value,
+        }
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Data> Signable for Vote<T> {
+    type Hash = Vec<u8>;
+
+    fn hash(&self) -> Self::Hash {
+        (VOTE_CONTEXT, self).encode()
+    }
+}
+
+/// A vote signed by the member casting it, as sent over the network.
+pub type SignedVote<T, S> = UncheckedSigned<Indexed<Vote<T>>, S>;
+
+/// A vote multisigned by enough members, proving the committee agreed on its value. Others can
+/// check a received certificate using [`UncheckedSigned::check_multi`].
+pub type VoteCertificate<T, MK> = Multisigned<Vote<T>, MK>;
+
+/// The reason a vote was rejected by a [`VoteCollector`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VoteError {
+    /// The signature does not match the vote.
+    WrongSignature(NodeIndex),
+    /// The vote belongs to a different session or round.
+    WrongVoting(NodeIndex),
+    /// The member already voted for a different value.
+    DoubleVote(NodeIndex),
+}
+
+impl Display for VoteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            VoteError::WrongSignature(voter) => write!(f, "wrongly signed vote of {:?}", voter),
+            VoteError::WrongVoting(voter) => {
+                write!(f, "vote of {:?} from a different voting", voter)
+            }
+            VoteError::DoubleVote(voter) => {
+                write!(f, "{:?} already voted for a different value", voter)
+            }
+        }
+    }
+}
+
+impl Error for VoteError {}
+
+/// Collects the votes of a single round of voting, producing a [`VoteCertificate`] once the
+/// votes for a value reach the threshold of the [`MultiKeychain`], i.e. floor(2N/3)+1 votes
+/// for the usual keychains.
+pub struct VoteCollector<T: Data, MK: MultiKeychain> {
+    keychain: MK,
+    session_id: SessionId,
+    round: u64,
+    voted: HashMap<NodeIndex, T>,
+    tallies: HashMap<T, PartiallyMultisigned<Vote<T>, MK>>,
+    certified: bool,
+}
+
+impl<T: Data, MK: MultiKeychain> VoteCollector<T, MK> {
+    pub fn new(keychain: MK, session_id: SessionId, round: u64) -> Self {
+        VoteCollector {
+            keychain,
+            session_id,
+            round,
+            voted: HashMap::new(),
+            tallies: HashMap::new(),
+            certified: false,
+        }
+    }
+
+    /// Our vote for the value, to be sent to the other members and added to this collector.
+    pub async fn vote(&self, value: T) -> SignedVote<T, MK::Signature> {
+        let vote = Vote::new(self.session_id, self.round, value);
+        Signed::sign_with_index(vote, &self.keychain)
+            .await
+            .into_unchecked()
+    }
+
+    /// Adds the vote, returning the certificate if this vote completed one. The certificate is
+    /// returned only once, further votes for the certified value are accepted silently.
+    pub fn add_vote(
+        &mut self,
+        vote: SignedVote<T, MK::Signature>,
+    ) -> Result<Option<VoteCertificate<T, MK>>, VoteError> {
+        let voter = vote.index();
+        let contents = vote.as_signable_strip_index();
+        if contents.session_id != self.session_id || contents.round != self.round {
+            return Err(VoteError::WrongVoting(voter));
+        }
+        let value = contents.value.clone();
+        let signed = vote
+            .check(&self.keychain)
+            .map_err(|_| VoteError::WrongSignature(voter))?;
+        match self.voted.get(&voter) {
+            Some(voted) if voted == &value => return Ok(None),
+            Some(_) => return Err(VoteError::DoubleVote(voter)),
+            None => {
+                self.voted.insert(voter, value.clone());
+            }
+        }
+        let tally = match self.tallies.remove(&value) {
+            Some(tally) => tally.add_signature(signed, &self.keychain),
+            None => signed.into_partially_multisigned(&self.keychain),
+        };
+        let certificate = match &tally {
+            PartiallyMultisigned::Complete { multisigned } if !self.certified => {
+                self.certified = true;
+                Some(multisigned.clone())
+            }
+            _ => None,
+        };
+        self.tallies.insert(value, tally);
+        Ok(certificate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VoteCollector, VoteError};
+    use crate::{NodeCount, NodeIndex, SessionId};
+    use aleph_bft_mock::Keychain;
+
+    fn collectors(n_members: usize) -> Vec<VoteCollector<u32, Keychain>> {
+        (0..n_members)
+            .map(|i| {
+                VoteCollector::new(
+                    Keychain::new(NodeCount(n_members), NodeIndex(i)),
+                    SessionId::from_number(0),
+                    7,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn certifies_value_with_enough_votes() {
+        let mut collectors = collectors(4);
+        let mut votes = Vec::new();
+        for (i, collector) in collectors.iter().enumerate() {
+            let value = if i == 3 { 2 } else { 1 };
+            votes.push(collector.vote(value).await);
+        }
+        let collector = &mut collectors[0];
+        assert_eq!(collector.add_vote(votes[0].clone()), Ok(None));
+        assert_eq!(collector.add_vote(votes[3].clone()), Ok(None));
+        assert_eq!(collector.add_vote(votes[1].clone()), Ok(None));
+        let certificate = collector
+            .add_vote(votes[2].clone())
+            .expect("the vote is correct")
+            .expect("there are enough votes for the value");
+        assert_eq!(certificate.as_signable().value(), &1);
+        assert_eq!(certificate.as_signable().round(), 7);
+        assert_eq!(collector.add_vote(votes[2].clone()), Ok(None));
+    }
+
+    #[tokio::test]
+    async fn rejects_double_votes_and_other_votings() {
+        let mut collectors = collectors(4);
+        let first = collectors[1].vote(1).await;
+        let second = collectors[1].vote(2).await;
+        let other_round = VoteCollector::<u32, _>::new(
+            Keychain::new(NodeCount(4), NodeIndex(2)),
+            SessionId::from_number(0),
+            8,
+        )
+        .vote(1)
+        .await;
+        let collector = &mut collectors[0];
+        assert_eq!(collector.add_vote(first), Ok(None));
+        assert_eq!(
+            collector.add_vote(second),
+            Err(VoteError::DoubleVote(NodeIndex(1)))
+        );
+        assert_eq!(
+            collector.add_vote(other_round),
+            Err(VoteError::WrongVoting(NodeIndex(2)))
+        );
+    }
+}
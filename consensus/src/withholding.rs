@@ -0,0 +1,186 @@
+use crate::{NodeCount, NodeIndex, NodeMap, Round, Sender};
+use std::{collections::HashMap, time::Duration};
+
+/// How often the highest rounds we have of every creator are gossiped.
+pub(crate) const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many checks in a row have to find a node deprived of the units of a creator before it is
+/// reported, so that a node briefly missing a few units is not.
+const PATIENCE: usize = 3;
+
+/// A suspicion that a node sends its units to some nodes but not to others, see
+/// [`LocalIO::enable_withholding_detection`].
+///
+/// The suspicion is based on the highest rounds of every creator gossiped by the nodes. The
+/// reports are signed, but a faulty node can still report any rounds, so it is a hint for the
+/// operators rather than a proof of misbehavior.
+///
+/// [`LocalIO::enable_withholding_detection`]: crate::LocalIO::enable_withholding_detection
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct WithholdingSuspicion {
+    /// The node suspected of withholding its units.
+    pub creator: NodeIndex,
+    /// The node that does not get the units of the creator.
+    pub deprived: NodeIndex,
+    /// How many more rounds of the creator the deprived node lacks than it typically lacks of
+    /// other creators.
+    pub lag: Round,
+}
+
+/// Compares the highest rounds of every creator reported by the nodes, looking for a node that
+/// keeps lacking the units of one creator while keeping up with the others.
+pub(crate) struct WithholdingDetector {
+    n_members: NodeCount,
+    lag_threshold: Round,
+    reports: NodeMap<NodeMap<Round>>,
+    // The checks in a row that found the node, the second one, deprived of the units of the
+    // creator, the first one.
+    strikes: HashMap<(NodeIndex, NodeIndex), usize>,
+    suspicions: Sender<WithholdingSuspicion>,
+}
+
+impl WithholdingDetector {
+    pub(crate) fn new(
+        n_members: NodeCount,
+        lag_threshold: Round,
+        suspicions: Sender<WithholdingSuspicion>,
+    ) -> Self {
+        WithholdingDetector {
+            n_members,
+            lag_threshold: lag_threshold.max(1),
+            reports: NodeMap::with_size(n_members),
+            strikes: HashMap::new(),
+            suspicions,
+        }
+    }
+
+    /// Replaces the previous report of the node with the highest rounds of the creators it has,
+    /// raising suspicions about creators it lacks the units of.
+    pub(crate) fn on_report(&mut self, reporter: NodeIndex, top_rounds: NodeMap<Round>) {
+        if reporter.0 >= self.n_members.0 || top_rounds.size() != self.n_members {
+            return;
+        }
+        self.reports.insert(reporter, top_rounds);
+        self.check(reporter);
+    }
+
+    // The (f+1)-th highest round of the creator reported, which some correct node has, however
+    // high the faulty nodes claim to be.
+    fn best_round(&self, creator: NodeIndex) -> Round {
+        let mut rounds: Vec<_> = self
+            .reports
+            .values()
+            .filter_map(|report| report.get(creator))
+            .copied()
+            .collect();
+        rounds.sort_unstable_by(|a, b| b.cmp(a));
+        rounds
+            .get(self.n_members.max_faulty().0)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn check(&mut self, reporter: NodeIndex) {
+        let report = match self.reports.get(reporter) {
+            Some(report) => report,
+            None => return,
+        };
+        let lags: Vec<_> = (0..self.n_members.0)
+            .map(NodeIndex)
+            .filter(|creator| *creator != reporter)
+            .map(|creator| {
+                let round = report.get(creator).copied().unwrap_or(0);
+                (creator, self.best_round(creator).saturating_sub(round))
+            })
+            .collect();
+        if lags.is_empty() {
+            return;
+        }
+        // A node lagging behind everyone is slow rather than deprived.
+        let mut sorted_lags: Vec<_> = lags.iter().map(|(_, lag)| *lag).collect();
+        sorted_lags.sort_unstable();
+        let typical_lag = sorted_lags[sorted_lags.len() / 2];
+        for (creator, lag) in lags {
+            let lag = lag.saturating_sub(typical_lag);
+            if lag < self.lag_threshold {
+                self.strikes.remove(&(creator, reporter));
+                continue;
+            }
+            let strikes = self.strikes.entry((creator, reporter)).or_insert(0);
+            *strikes += 1;
+            if *strikes == PATIENCE {
+                // The application might not be interested in the suspicions anymore.
+                let _ = self.suspicions.unbounded_send(WithholdingSuspicion {
+                    creator,
+                    deprived: reporter,
+                    lag,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WithholdingDetector, WithholdingSuspicion, PATIENCE};
+    use crate::{NodeCount, NodeIndex, NodeMap, Round};
+    use futures::channel::mpsc::unbounded;
+
+    fn report(rounds: &[Round]) -> NodeMap<Round> {
+        let mut report = NodeMap::with_size(NodeCount(rounds.len()));
+        for (ix, round) in rounds.iter().enumerate() {
+            report.insert(NodeIndex(ix), *round);
+        }
+        report
+    }
+
+    #[test]
+    fn suspects_creator_withholding_from_one_node() {
+        let (suspicions_tx, mut suspicions_rx) = unbounded();
+        let mut detector = WithholdingDetector::new(NodeCount(4), 3, suspicions_tx);
+        for step in 0..PATIENCE as Round {
+            let round = 10 + step;
+            for reporter in 0..3 {
+                detector.on_report(NodeIndex(reporter), report(&[round; 4]));
+            }
+            // Node 3 gets the units of node 0 late, and of the others almost on time.
+            detector.on_report(
+                NodeIndex(3),
+                report(&[round - 5, round - 1, round - 1, round]),
+            );
+            // Node 2 is slow, but equally so for all creators.
+            detector.on_report(
+                NodeIndex(2),
+                report(&[round - 4, round - 4, round, round - 4]),
+            );
+        }
+
+        assert_eq!(
+            suspicions_rx
+                .try_next()
+                .expect("a suspicion should be raised"),
+            Some(WithholdingSuspicion {
+                creator: NodeIndex(0),
+                deprived: NodeIndex(3),
+                lag: 4,
+            })
+        );
+        assert!(suspicions_rx.try_next().is_err());
+    }
+
+    #[test]
+    fn ignores_inflated_reports() {
+        let (suspicions_tx, mut suspicions_rx) = unbounded();
+        let mut detector = WithholdingDetector::new(NodeCount(4), 3, suspicions_tx);
+        for step in 0..PATIENCE as Round {
+            let round = 10 + step;
+            // Node 3 claims to have far more units of node 0 than anyone.
+            detector.on_report(NodeIndex(3), report(&[round + 50, round, round, round]));
+            for reporter in 0..3 {
+                detector.on_report(NodeIndex(reporter), report(&[round; 4]));
+            }
+        }
+
+        assert!(suspicions_rx.try_next().is_err());
+    }
+}
@@ -0,0 +1,494 @@
+//! A [`MultiKeychain`] based on BLS signatures over BLS12-381, in which a multisignature is a
+//! single aggregated signature together with the set of its signers, so its size barely grows
+//! with the size of the committee.
+//!
+//! Aggregated signatures of a single message are only secure with public keys whose owners
+//! proved they know the secret keys, otherwise a node could choose its public key so that it
+//! cancels out the keys of others. The public keys of the committee should thus be checked with
+//! [`verify_possession`] against the proofs produced by [`prove_possession`] before they are
+//! used to create a [`Keychain`].
+
+use crate::{
+    AggregatingMultiKeychain, CompactEncoding, CompactMultisignature, Index, Keychain as KeychainT,
+    MultiKeychain, NodeCount, NodeIndex, NodeSubset,
+    PartialMultisignature as PartialMultisignatureT, PublicVerification, Vrf,
+};
+use async_trait::async_trait;
+use blst::{
+    min_pk::{
+        AggregatePublicKey, AggregateSignature, PublicKey as BlstPublicKey,
+        Signature as BlstSignature,
+    },
+    BLST_ERROR,
+};
+use codec::{Decode, Encode};
+use std::sync::Arc;
+
+pub use blst::min_pk::{PublicKey, SecretKey};
+
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const VRF_DST: &[u8] = b"BLS_VRF_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// A compressed BLS signature, of a single node or aggregated.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct Signature([u8; 96]);
+
+impl Signature {
+    fn from_blst(signature: &BlstSignature) -> Self {
+        Signature(signature.compress())
+    }
+
+    fn to_blst(&self) -> Option<BlstSignature> {
+        BlstSignature::uncompress(&self.0).ok()
+    }
+}
+
+/// The signatures of a subset of nodes aggregated into one.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct PartialMultisignature {
+    signers: NodeSubset,
+    signature: Signature,
+}
+
+impl PartialMultisignatureT for PartialMultisignature {
+    type Signature = Signature;
+
+    /// The signature is aggregated as is, and an incorrect one makes the whole aggregate
+    /// incorrect, so it has to be verified first, as [`PartiallyMultisigned`] does.
+    ///
+    /// [`PartiallyMultisigned`]: crate::PartiallyMultisigned
+    #[must_use = "consumes the original and returns the aggregated signature which should be used"]
+    fn add_signature(mut self, signature: &Self::Signature, index: NodeIndex) -> Self {
+        // Adding a signature twice would make the aggregate invalid.
+        if index.0 >= self.signers.size() || self.signers[index] {
+            return self;
+        }
+        let (ours, theirs) = match (self.signature.to_blst(), signature.to_blst()) {
+            (Some(ours), Some(theirs)) => (ours, theirs),
+            _ => return self,
+        };
+        let mut aggregate = AggregateSignature::from_signature(&ours);
+        if aggregate.add_signature(&theirs, false).is_err() {
+            return self;
+        }
+        self.signature = Signature::from_blst(&aggregate.to_signature());
+        self.signers.insert(index);
+        self
+    }
+
+    /// The signatures are aggregated, so they can only be merged if no node signed both. If the
+    /// signers overlap, the multisignature with more signers is kept. As with adding a single
+    /// signature, the other multisignature has to be verified first.
+    #[must_use = "consumes the original and returns the aggregated signature which should be used"]
+    fn merge(mut self, other: &Self) -> Self {
+        if other.signers.size() != self.signers.size() {
+            return self;
+        }
+        if other.signers.elements().any(|index| self.signers[index]) {
+            return match other.signers.len() > self.signers.len() {
+                true => other.clone(),
+                false => self,
+            };
+        }
+        let (ours, theirs) = match (self.signature.to_blst(), other.signature.to_blst()) {
+            (Some(ours), Some(theirs)) => (ours, theirs),
+            _ => return self,
+        };
+        let mut aggregate = AggregateSignature::from_signature(&ours);
+        if aggregate.add_signature(&theirs, false).is_err() {
+            return self;
+        }
+        self.signature = Signature::from_blst(&aggregate.to_signature());
+        for index in other.signers.elements() {
+            self.signers.insert(index);
+        }
+        self
+    }
+
+    fn signers(&self) -> NodeSubset {
+        self.signers.clone()
+    }
+}
+
+/// The aggregated signature is the only signature in the compact format.
+impl CompactEncoding for PartialMultisignature {
+    fn to_compact(&self) -> CompactMultisignature {
+        CompactMultisignature::new(self.signers.clone(), self.signature.0.to_vec())
+    }
+
+    fn from_compact(compact: &CompactMultisignature) -> Option<Self> {
+        Some(PartialMultisignature {
+            signers: compact.signers().clone(),
+            signature: Signature(compact.signatures().try_into().ok()?),
+        })
+    }
+}
+
+impl PublicVerification for PartialMultisignature {
+    type PublicKey = PublicKey;
+
+    fn verify_with_keys(&self, msg: &[u8], public_keys: &[Self::PublicKey]) -> bool {
+        if self.signers.size() != public_keys.len() || self.signers.is_empty() {
+            return false;
+        }
+        let signature = match self.signature.to_blst() {
+            Some(signature) => signature,
+            None => return false,
+        };
+        let public_keys: Vec<&BlstPublicKey> = self
+            .signers
+            .elements()
+            .map(|index| &public_keys[index.0])
+            .collect();
+        signature.fast_aggregate_verify(true, msg, SIGNATURE_DST, &public_keys)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+}
+
+/// Multisignatures of several messages aggregated into one signature, together with the signers
+/// of every message.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct AggregatedMultisignature {
+    signers: Vec<NodeSubset>,
+    signature: Signature,
+}
+
+/// A fresh secret key derived from the thread local cryptographically secure generator.
+pub fn generate_secret_key() -> SecretKey {
+    let seed: [u8; 32] = rand::random();
+    SecretKey::key_gen(&seed, &[]).expect("the seed is long enough")
+}
+
+/// A proof that we know the secret key of our public key, to be published together with it.
+pub fn prove_possession(secret_key: &SecretKey) -> Signature {
+    let public_key = secret_key.sk_to_pk();
+    Signature::from_blst(&secret_key.sign(&public_key.compress(), POSSESSION_DST, &[]))
+}
+
+/// Checks a proof produced by [`prove_possession`] for the public key.
+pub fn verify_possession(public_key: &PublicKey, proof: &Signature) -> bool {
+    match proof.to_blst() {
+        Some(proof) => {
+            proof.verify(
+                true,
+                &public_key.compress(),
+                POSSESSION_DST,
+                &[],
+                public_key,
+                true,
+            ) == BLST_ERROR::BLST_SUCCESS
+        }
+        None => false,
+    }
+}
+
+/// The keychains of a whole committee with freshly generated keys, e.g. for local testing. In
+/// a real deployment every node generates its own secret key and only the public keys are
+/// exchanged, together with proofs of possession.
+pub fn generate_keychains(node_count: NodeCount) -> Vec<Keychain> {
+    let secret_keys: Vec<_> = (0..node_count.0).map(|_| generate_secret_key()).collect();
+    let public_keys: Vec<_> = secret_keys.iter().map(SecretKey::sk_to_pk).collect();
+    secret_keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, secret_key)| Keychain::new(NodeIndex(index), secret_key, public_keys.clone()))
+        .collect()
+}
+
+/// The secret key of a node together with the public keys of the whole committee, ordered by
+/// the indices of the nodes. See the module description for the requirements on the keys.
+#[derive(Clone)]
+pub struct Keychain {
+    index: NodeIndex,
+    secret_key: SecretKey,
+    public_keys: Arc<Vec<PublicKey>>,
+}
+
+impl Keychain {
+    /// The `secret_key` should be the one of the public key with our index.
+    pub fn new(index: NodeIndex, secret_key: SecretKey, public_keys: Vec<PublicKey>) -> Self {
+        Keychain {
+            index,
+            secret_key,
+            public_keys: Arc::new(public_keys),
+        }
+    }
+
+    /// Whether the aggregated signature is valid for its signers, regardless of how many of
+    /// them there are.
+    pub fn verify_partial(&self, msg: &[u8], partial: &PartialMultisignature) -> bool {
+        partial.verify_with_keys(msg, &self.public_keys)
+    }
+}
+
+impl Index for Keychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+#[async_trait]
+impl KeychainT for Keychain {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        NodeCount(self.public_keys.len())
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        Signature::from_blst(&self.secret_key.sign(msg, SIGNATURE_DST, &[]))
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        match (self.public_keys.get(index.0), sgn.to_blst()) {
+            (Some(public_key), Some(signature)) => {
+                signature.verify(true, msg, SIGNATURE_DST, &[], public_key, false)
+                    == BLST_ERROR::BLST_SUCCESS
+            }
+            _ => false,
+        }
+    }
+}
+
+impl MultiKeychain for Keychain {
+    type PartialMultisignature = PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        let mut signers = NodeSubset::with_size(self.node_count());
+        if index.0 < signers.size() {
+            signers.insert(index);
+        }
+        PartialMultisignature {
+            signers,
+            signature: signature.clone(),
+        }
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        partial.signers.len() >= threshold.0 && self.verify_partial(msg, partial)
+    }
+}
+
+/// The proofs are signatures of the inputs, made with their own domain separation tag so that
+/// they are never valid signatures of anything else.
+#[async_trait]
+impl Vrf for Keychain {
+    async fn prove(&self, input: &[u8]) -> Vec<u8> {
+        Signature::from_blst(&self.secret_key.sign(input, VRF_DST, &[]))
+            .0
+            .to_vec()
+    }
+
+    fn verify(&self, input: &[u8], proof: &[u8], index: NodeIndex) -> bool {
+        let signature = match BlstSignature::uncompress(proof) {
+            Ok(signature) if proof.len() == 96 => signature,
+            _ => return false,
+        };
+        match self.public_keys.get(index.0) {
+            Some(public_key) => {
+                signature.verify(true, input, VRF_DST, &[], public_key, false)
+                    == BLST_ERROR::BLST_SUCCESS
+            }
+            None => false,
+        }
+    }
+}
+
+impl AggregatingMultiKeychain for Keychain {
+    type AggregatedMultisignature = AggregatedMultisignature;
+
+    fn aggregate(
+        &self,
+        multisignatures: &[&Self::PartialMultisignature],
+    ) -> Option<Self::AggregatedMultisignature> {
+        let signatures = multisignatures
+            .iter()
+            .map(|multisignature| multisignature.signature.to_blst())
+            .collect::<Option<Vec<_>>>()?;
+        let signatures: Vec<&BlstSignature> = signatures.iter().collect();
+        let aggregate = AggregateSignature::aggregate(&signatures, false).ok()?;
+        Some(AggregatedMultisignature {
+            signers: multisignatures
+                .iter()
+                .map(|multisignature| multisignature.signers.clone())
+                .collect(),
+            signature: Signature::from_blst(&aggregate.to_signature()),
+        })
+    }
+
+    fn verify_aggregated(
+        &self,
+        msgs: &[&[u8]],
+        aggregated: &Self::AggregatedMultisignature,
+    ) -> bool {
+        if msgs.is_empty() || msgs.len() != aggregated.signers.len() {
+            return false;
+        }
+        let quorum = self.node_count().quorum();
+        // Every message was signed by a quorum, whose keys aggregate into a single one.
+        let mut public_keys = Vec::with_capacity(msgs.len());
+        for signers in &aggregated.signers {
+            if signers.size() != self.public_keys.len() || signers.len() < quorum.0 {
+                return false;
+            }
+            let signer_keys: Vec<&BlstPublicKey> = signers
+                .elements()
+                .map(|index| &self.public_keys[index.0])
+                .collect();
+            match AggregatePublicKey::aggregate(&signer_keys, false) {
+                Ok(key) => public_keys.push(key.to_public_key()),
+                Err(_) => return false,
+            }
+        }
+        let public_keys: Vec<&BlstPublicKey> = public_keys.iter().collect();
+        match aggregated.signature.to_blst() {
+            Some(signature) => {
+                signature.aggregate_verify(true, msgs, SIGNATURE_DST, &public_keys, false)
+                    == BLST_ERROR::BLST_SUCCESS
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_keychains, generate_secret_key, prove_possession, verify_possession};
+    use crate::{
+        check_keybox_conformance, AggregatingMultiKeychain, CompactEncoding, Index, Keychain,
+        MultiKeychain, NodeCount, NodeIndex, PartialMultisignature, Vrf,
+    };
+    use codec::Encode;
+
+    #[tokio::test]
+    async fn generated_keychains_conform() {
+        let keychains = generate_keychains(NodeCount(7));
+        assert_eq!(check_keybox_conformance(&keychains).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn multisignatures_stay_small() {
+        let keychains = generate_keychains(NodeCount(10));
+        let msg = b"message";
+        let mut multisignature =
+            keychains[0].bootstrap_multi(&keychains[0].sign(msg).await, NodeIndex(0));
+        let single_size = multisignature.encode().len();
+        for keychain in &keychains[1..] {
+            // Adding the same signature twice changes nothing.
+            let signature = keychain.sign(msg).await;
+            multisignature = multisignature
+                .add_signature(&signature, keychain.index())
+                .add_signature(&signature, keychain.index());
+            assert!(keychains[0].verify_partial(msg, &multisignature));
+        }
+        assert_eq!(multisignature.signers().len(), 10);
+        assert_eq!(multisignature.encode().len(), single_size);
+        assert_eq!(
+            CompactEncoding::from_compact(&multisignature.to_compact()),
+            Some(multisignature.clone())
+        );
+        assert!(keychains[0].is_complete(msg, &multisignature));
+        assert!(!keychains[0].is_complete(b"other message", &multisignature));
+    }
+
+    #[tokio::test]
+    async fn merges_disjoint_signers() {
+        let keychains = generate_keychains(NodeCount(4));
+        let msg = b"message";
+        let partial = |index: usize, signature| {
+            keychains[index].bootstrap_multi(&signature, NodeIndex(index))
+        };
+        let mut first = partial(0, keychains[0].sign(msg).await);
+        first = first.add_signature(&keychains[1].sign(msg).await, NodeIndex(1));
+        let mut second = partial(2, keychains[2].sign(msg).await);
+        second = second.add_signature(&keychains[3].sign(msg).await, NodeIndex(3));
+
+        let overlapping = partial(1, keychains[1].sign(msg).await);
+        assert_eq!(first.clone().merge(&overlapping), first);
+        let merged = first.merge(&second);
+        assert_eq!(merged.signers().len(), 4);
+        assert!(keychains[0].is_complete(msg, &merged));
+    }
+
+    #[tokio::test]
+    async fn aggregates_multisignatures_of_different_messages() {
+        let keychains = generate_keychains(NodeCount(4));
+        let msgs: Vec<&[u8]> = vec![b"first", b"second", b"third"];
+        let mut multisignatures = Vec::new();
+        for msg in &msgs {
+            let mut multisignature =
+                keychains[0].bootstrap_multi(&keychains[0].sign(msg).await, NodeIndex(0));
+            for keychain in &keychains[1..3] {
+                multisignature =
+                    multisignature.add_signature(&keychain.sign(msg).await, keychain.index());
+            }
+            multisignatures.push(multisignature);
+        }
+        let aggregated = keychains[3]
+            .aggregate(&multisignatures.iter().collect::<Vec<_>>())
+            .expect("the multisignatures are well formed");
+        assert!(keychains[3].verify_aggregated(&msgs, &aggregated));
+        let other_msgs: Vec<&[u8]> = vec![b"first", b"second", b"other"];
+        assert!(!keychains[3].verify_aggregated(&other_msgs, &aggregated));
+        assert!(!keychains[3].verify_aggregated(&msgs[..2], &aggregated));
+
+        // Not enough signers of the last message.
+        multisignatures[2] =
+            keychains[0].bootstrap_multi(&keychains[0].sign(msgs[2]).await, NodeIndex(0));
+        let incomplete = keychains[3]
+            .aggregate(&multisignatures.iter().collect::<Vec<_>>())
+            .expect("the multisignatures are well formed");
+        assert!(!keychains[3].verify_aggregated(&msgs, &incomplete));
+    }
+
+    #[tokio::test]
+    async fn proves_vrf_outputs() {
+        let keychains = generate_keychains(NodeCount(4));
+        let input = b"input";
+        let proof = keychains[1].prove(input).await;
+        assert_eq!(keychains[1].prove(input).await, proof);
+        assert!(Vrf::verify(&keychains[0], input, &proof, NodeIndex(1)));
+        assert!(!Vrf::verify(&keychains[0], input, &proof, NodeIndex(2)));
+        assert!(!Vrf::verify(
+            &keychains[0],
+            b"other input",
+            &proof,
+            NodeIndex(1)
+        ));
+        assert!(!Vrf::verify(
+            &keychains[0],
+            input,
+            &proof[1..],
+            NodeIndex(1)
+        ));
+        // A proof is not a signature of the input.
+        let signature = keychains[1].sign(input).await;
+        assert!(!Vrf::verify(
+            &keychains[0],
+            input,
+            &signature.0,
+            NodeIndex(1)
+        ));
+    }
+
+    #[test]
+    fn checks_possession_proofs() {
+        let secret_key = generate_secret_key();
+        let proof = prove_possession(&secret_key);
+        assert!(verify_possession(&secret_key.sk_to_pk(), &proof));
+        assert!(!verify_possession(
+            &generate_secret_key().sk_to_pk(),
+            &proof
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{NodeCount, NodeSubset, PartialMultisignature, SessionId};
+use codec::{Decode, Encode};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// Partial multisignatures that can be verified knowing only the public keys of the committee,
+/// without a keychain, e.g. by external systems checking [`Certificate`]s.
+pub trait PublicVerification: PartialMultisignature {
+    /// The public key of a single node.
+    type PublicKey;
+
+    /// Whether the multisignature of the message is valid for its signers, regardless of how
+    /// many of them there are. The public keys are ordered by the indices of the nodes.
+    fn verify_with_keys(&self, msg: &[u8], public_keys: &[Self::PublicKey]) -> bool;
+}
+
+/// A self-contained proof that a quorum of the committee of a session signed a hash, made out of
+/// a complete [`Multisigned`] with [`Multisigned::into_certificate`]. It can be checked with
+/// [`verify_certificate`] knowing only the public keys of the committee.
+///
+/// Sessions with a signing purpose sign the hash prefixed with their signing domain, see
+/// [`SessionId::signing_domain`], which binds the certificate to the session. Without a purpose
+/// only the hash is signed, so it should commit to the session, as the hashes of units do.
+///
+/// [`Multisigned`]: crate::Multisigned
+/// [`Multisigned::into_certificate`]: crate::Multisigned::into_certificate
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct Certificate<H, P> {
+    session_id: SessionId,
+    signing_purpose: Option<u8>,
+    hash: H,
+    signers: NodeSubset,
+    multisignature: P,
+}
+
+impl<H: AsRef<[u8]>, P: PublicVerification> Certificate<H, P> {
+    pub(crate) fn new(
+        session_id: SessionId,
+        signing_purpose: Option<u8>,
+        hash: H,
+        multisignature: P,
+    ) -> Self {
+        Certificate {
+            session_id,
+            signing_purpose,
+            hash,
+            signers: multisignature.signers(),
+            multisignature,
+        }
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// The signing purpose of the session, if it has one.
+    pub fn signing_purpose(&self) -> Option<u8> {
+        self.signing_purpose
+    }
+
+    /// The signed hash.
+    pub fn hash(&self) -> &H {
+        &self.hash
+    }
+
+    /// The nodes that signed the hash.
+    pub fn signers(&self) -> &NodeSubset {
+        &self.signers
+    }
+
+    pub fn multisignature(&self) -> &P {
+        &self.multisignature
+    }
+}
+
+/// Why a [`Certificate`] is not valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CertificateError {
+    /// The certificate is from a different session.
+    WrongSession,
+    /// The signers are from a committee of a different size.
+    WrongCommittee,
+    /// The signers do not match the signers of the multisignature.
+    SignersMismatch,
+    /// Too few nodes signed the hash.
+    NotQuorum,
+    /// The multisignature is not valid for the hash.
+    WrongMultisignature,
+}
+
+impl Display for CertificateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CertificateError::WrongSession => {
+                write!(f, "the certificate is from a different session")
+            }
+            CertificateError::WrongCommittee => {
+                write!(f, "the certificate is from a committee of a different size")
+            }
+            CertificateError::SignersMismatch => {
+                write!(f, "the signers do not match the multisignature")
+            }
+            CertificateError::NotQuorum => write!(f, "too few nodes signed the certificate"),
+            CertificateError::WrongMultisignature => {
+                write!(f, "the multisignature is not valid for the hash")
+            }
+        }
+    }
+}
+
+impl Error for CertificateError {}
+
+/// Checks that the certificate is from the session, and that more than 2/3 of the committee with
+/// the given public keys, ordered by the indices of the nodes, signed its hash in the session.
+/// The signers are counted like in
+/// [`MultiKeychain::is_complete`], certificates of weighted committees need checking the weights
+/// of the signers on top of that.
+///
+/// [`MultiKeychain::is_complete`]: crate::MultiKeychain::is_complete
+pub fn verify_certificate<H: AsRef<[u8]>, P: PublicVerification>(
+    certificate: &Certificate<H, P>,
+    session_id: SessionId,
+    public_keys: &[P::PublicKey],
+) -> Result<(), CertificateError> {
+    if certificate.session_id != session_id {
+        return Err(CertificateError::WrongSession);
+    }
+    let node_count = NodeCount(public_keys.len());
+    if certificate.signers.size() != node_count.0 {
+        return Err(CertificateError::WrongCommittee);
+    }
+    if certificate.signers != certificate.multisignature.signers() {
+        return Err(CertificateError::SignersMismatch);
+    }
+    if certificate.signers.len() < node_count.quorum().0 {
+        return Err(CertificateError::NotQuorum);
+    }
+    let msg = [
+        &session_id.signing_domain(certificate.signing_purpose)[..],
+        certificate.hash.as_ref(),
+    ]
+    .concat();
+    if !certificate
+        .multisignature
+        .verify_with_keys(&msg, public_keys)
+    {
+        return Err(CertificateError::WrongMultisignature);
+    }
+    Ok(())
+}
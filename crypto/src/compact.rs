@@ -0,0 +1,112 @@
+use crate::{NodeCount, NodeSubset, PartialMultisignature, Signature, SignatureSet};
+use codec::{Decode, Encode};
+
+/// The standard wire format of partial multisignatures: the set of signers as a bitfield,
+/// followed by the bytes of their signatures, either aggregated into one signature or
+/// concatenated in the order of the signers. Every multisignature of the built-in keychains
+/// converts to and from it, see [`CompactEncoding`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct CompactMultisignature {
+    signers: NodeSubset,
+    signatures: Vec<u8>,
+}
+
+impl CompactMultisignature {
+    /// Joins the signers with the bytes of their signatures.
+    pub fn new(signers: NodeSubset, signatures: Vec<u8>) -> Self {
+        CompactMultisignature {
+            signers,
+            signatures,
+        }
+    }
+
+    /// The nodes whose signatures are included.
+    pub fn signers(&self) -> &NodeSubset {
+        &self.signers
+    }
+
+    /// The bytes of the signatures.
+    pub fn signatures(&self) -> &[u8] {
+        &self.signatures
+    }
+}
+
+/// Partial multisignatures that can be sent as a [`CompactMultisignature`], so that their
+/// encoding grows with the number of signers only by one bit per node besides the signatures
+/// themselves.
+pub trait CompactEncoding: PartialMultisignature {
+    /// The multisignature in the compact format.
+    fn to_compact(&self) -> CompactMultisignature;
+    /// The multisignature back from the compact format, `None` if the bytes do not match the
+    /// signers.
+    fn from_compact(compact: &CompactMultisignature) -> Option<Self>;
+}
+
+/// The signatures are concatenated, each in its own encoding, which for signatures of a fixed
+/// size, e.g. ed25519 ones, adds nothing to their bytes.
+impl<S: Signature> CompactEncoding for SignatureSet<S> {
+    fn to_compact(&self) -> CompactMultisignature {
+        let mut signatures = Vec::new();
+        for signature in self.values() {
+            signature.encode_to(&mut signatures);
+        }
+        CompactMultisignature::new(self.to_subset(), signatures)
+    }
+
+    fn from_compact(compact: &CompactMultisignature) -> Option<Self> {
+        let mut input = &compact.signatures[..];
+        let mut signature_set = SignatureSet::with_size(NodeCount(compact.signers.size()));
+        for index in compact.signers.elements() {
+            signature_set.insert(index, S::decode(&mut input).ok()?);
+        }
+        input.is_empty().then_some(signature_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompactEncoding, CompactMultisignature};
+    use crate::{NodeCount, NodeIndex, NodeSubset, PartialMultisignature, SignatureSet};
+    use codec::{Decode, Encode};
+
+    fn signature_set(node_count: usize, signers: &[usize]) -> SignatureSet<[u8; 64]> {
+        signers.iter().fold(
+            SignatureSet::with_size(NodeCount(node_count)),
+            |signature_set, signer| {
+                signature_set.add_signature(&[*signer as u8; 64], NodeIndex(*signer))
+            },
+        )
+    }
+
+    #[test]
+    fn signature_sets_survive_compact_encoding() {
+        for signers in [&[][..], &[3], &[0, 2, 5, 9]] {
+            let signature_set = signature_set(10, signers);
+            let compact = signature_set.to_compact();
+            assert_eq!(compact.signers(), &signature_set.signers());
+            assert_eq!(compact.signatures().len(), 64 * signers.len());
+            let decoded = CompactMultisignature::decode(&mut &compact.encode()[..])
+                .expect("the encoding should decode");
+            assert_eq!(
+                SignatureSet::from_compact(&decoded),
+                Some(signature_set.clone())
+            );
+            assert!(compact.encode().len() < signature_set.encode().len());
+        }
+    }
+
+    #[test]
+    fn rejects_signatures_not_matching_signers() {
+        let compact = signature_set(4, &[0, 1, 3]).to_compact();
+        let mut two_signers = NodeSubset::with_size(NodeCount(4));
+        two_signers.insert(NodeIndex(0));
+        two_signers.insert(NodeIndex(1));
+        let too_many = CompactMultisignature::new(two_signers, compact.signatures().to_vec());
+        assert_eq!(SignatureSet::<[u8; 64]>::from_compact(&too_many), None);
+        let too_few = CompactMultisignature::new(
+            compact.signers().clone(),
+            compact.signatures()[..128].to_vec(),
+        );
+        assert_eq!(SignatureSet::<[u8; 64]>::from_compact(&too_few), None);
+    }
+}
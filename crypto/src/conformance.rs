@@ -0,0 +1,345 @@
+use crate::{Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature};
+use codec::{Decode, Encode};
+use std::fmt;
+
+const CONFORMANCE_MESSAGE: &[u8] = b"AlephBFT keychain conformance";
+const OTHER_MESSAGE: &[u8] = b"AlephBFT keychain conformance, other message";
+
+/// A violation of the assumptions AlephBFT makes about a [`MultiKeychain`] implementation.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ConformanceError {
+    /// No keychains were provided.
+    NoKeychains,
+    /// The keychains do not describe a committee with one keychain per index.
+    WrongCommittee {
+        node_count: NodeCount,
+        keychains: usize,
+    },
+    /// The keychain reports a different committee size than the first one.
+    InconsistentNodeCount {
+        index: NodeIndex,
+        node_count: NodeCount,
+    },
+    /// Two keychains report the same index, or an index outside the committee.
+    WrongIndex(NodeIndex),
+    /// A correct signature by the node was rejected by the verifier.
+    CorrectSignatureRejected {
+        signer: NodeIndex,
+        verifier: NodeIndex,
+    },
+    /// A signature was accepted for a different message or as made by a different node.
+    IncorrectSignatureAccepted {
+        signer: NodeIndex,
+        verifier: NodeIndex,
+    },
+    /// A signature of the node did not survive an encode/decode round-trip.
+    SignatureCodec(NodeIndex),
+    /// A multisignature with a quorum of signatures was not considered complete.
+    QuorumIncomplete(NodeIndex),
+    /// A multisignature with fewer than a quorum of signatures was considered complete.
+    BelowQuorumComplete(NodeIndex),
+    /// A complete multisignature was considered complete for a different message.
+    WrongMessageComplete(NodeIndex),
+    /// A complete multisignature did not survive an encode/decode round-trip.
+    MultisignatureCodec(NodeIndex),
+    /// The completeness of a multisignature did not depend on the threshold it was checked with.
+    ThresholdIgnored(NodeIndex),
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ConformanceError::*;
+        match self {
+            NoKeychains => write!(f, "no keychains provided"),
+            WrongCommittee {
+                node_count,
+                keychains,
+            } => write!(
+                f,
+                "got {} keychains for a committee of {:?}",
+                keychains, node_count
+            ),
+            InconsistentNodeCount { index, node_count } => write!(
+                f,
+                "keychain {:?} reports a different committee size {:?}",
+                index, node_count
+            ),
+            WrongIndex(index) => write!(f, "index {:?} is duplicated or out of range", index),
+            CorrectSignatureRejected { signer, verifier } => write!(
+                f,
+                "keychain {:?} rejected a correct signature by {:?}",
+                verifier, signer
+            ),
+            IncorrectSignatureAccepted { signer, verifier } => write!(
+                f,
+                "keychain {:?} accepted an incorrect signature by {:?}",
+                verifier, signer
+            ),
+            SignatureCodec(index) => write!(
+                f,
+                "signature by {:?} does not survive an encode/decode round-trip",
+                index
+            ),
+            QuorumIncomplete(index) => write!(
+                f,
+                "keychain {:?} does not consider a quorum of signatures complete",
+                index
+            ),
+            BelowQuorumComplete(index) => write!(
+                f,
+                "keychain {:?} considers less than a quorum of signatures complete",
+                index
+            ),
+            WrongMessageComplete(index) => write!(
+                f,
+                "keychain {:?} considers a multisignature complete for a different message",
+                index
+            ),
+            MultisignatureCodec(index) => write!(
+                f,
+                "multisignature checked by {:?} does not survive an encode/decode round-trip",
+                index
+            ),
+            ThresholdIgnored(index) => write!(
+                f,
+                "keychain {:?} ignores the threshold when checking completeness",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConformanceError {}
+
+/// Checks whether the given keychains, one for every member of the committee, behave the way
+/// AlephBFT expects honest keychains to behave.
+///
+/// This exercises sign/verify consistency, index handling, encode/decode round-trips of
+/// signatures and multisignatures, and the completeness of multisignatures, which should be
+/// reached exactly at floor(2N/3)+1 signatures. It is meant to be called from the tests of
+/// custom [`MultiKeychain`] implementations. Signatures are only verified as made by members
+/// of the committee, keychains are free to panic for indexes outside of it.
+pub async fn check_keybox_conformance<MK: MultiKeychain>(
+    keychains: &[MK],
+) -> Result<(), ConformanceError> {
+    let first = keychains.first().ok_or(ConformanceError::NoKeychains)?;
+    let node_count = first.node_count();
+    if node_count.0 != keychains.len() {
+        return Err(ConformanceError::WrongCommittee {
+            node_count,
+            keychains: keychains.len(),
+        });
+    }
+    let mut seen = vec![false; node_count.0];
+    for keychain in keychains {
+        let index = keychain.index();
+        if keychain.node_count() != node_count {
+            return Err(ConformanceError::InconsistentNodeCount {
+                index,
+                node_count: keychain.node_count(),
+            });
+        }
+        match seen.get_mut(index.0) {
+            Some(seen) if !*seen => *seen = true,
+            _ => return Err(ConformanceError::WrongIndex(index)),
+        }
+    }
+
+    let mut signatures = Vec::with_capacity(keychains.len());
+    for keychain in keychains {
+        let signer = keychain.index();
+        let signature = keychain.sign(CONFORMANCE_MESSAGE).await;
+        match MK::Signature::decode(&mut &signature.encode()[..]) {
+            Ok(decoded) if decoded == signature => {}
+            _ => return Err(ConformanceError::SignatureCodec(signer)),
+        }
+        for verifier in keychains {
+            if !verifier.verify(CONFORMANCE_MESSAGE, &signature, signer) {
+                return Err(ConformanceError::CorrectSignatureRejected {
+                    signer,
+                    verifier: verifier.index(),
+                });
+            }
+            let wrong_index = NodeIndex((signer.0 + 1) % node_count.0);
+            if verifier.verify(OTHER_MESSAGE, &signature, signer)
+                || (wrong_index != signer
+                    && verifier.verify(CONFORMANCE_MESSAGE, &signature, wrong_index))
+            {
+                return Err(ConformanceError::IncorrectSignatureAccepted {
+                    signer,
+                    verifier: verifier.index(),
+                });
+            }
+        }
+        signatures.push((signer, signature));
+    }
+
+    let quorum = node_count.quorum();
+    for keychain in keychains {
+        let index = keychain.index();
+        let mut signatures = signatures.iter();
+        let (first_signer, first_signature) = signatures.next().expect("there is a signature");
+        let mut partial = keychain.bootstrap_multi(first_signature, *first_signer);
+        for (count, (signer, signature)) in (2..).zip(signatures) {
+            if count > quorum.0 {
+                break;
+            }
+            if keychain.is_complete(CONFORMANCE_MESSAGE, &partial) {
+                return Err(ConformanceError::BelowQuorumComplete(index));
+            }
+            partial = partial.add_signature(signature, *signer);
+        }
+        if !keychain.is_complete(CONFORMANCE_MESSAGE, &partial) {
+            return Err(ConformanceError::QuorumIncomplete(index));
+        }
+        if keychain.is_complete(OTHER_MESSAGE, &partial) {
+            return Err(ConformanceError::WrongMessageComplete(index));
+        }
+        if !keychain.is_complete_with_threshold(CONFORMANCE_MESSAGE, &partial, quorum)
+            || (quorum < node_count
+                && keychain.is_complete_with_threshold(CONFORMANCE_MESSAGE, &partial, node_count))
+        {
+            return Err(ConformanceError::ThresholdIgnored(index));
+        }
+        let decoded = MK::PartialMultisignature::decode(&mut &partial.encode()[..])
+            .map_err(|_| ConformanceError::MultisignatureCodec(index))?;
+        if !keychain.is_complete(CONFORMANCE_MESSAGE, &decoded) {
+            return Err(ConformanceError::MultisignatureCodec(index));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_keybox_conformance, ConformanceError};
+    use crate::{
+        Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature, SignatureSet,
+    };
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestKeychain {
+        count: NodeCount,
+        index: NodeIndex,
+        quorum: usize,
+        lenient: bool,
+    }
+
+    impl Index for TestKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for TestKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            self.count
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            self.lenient || (index == sgn.index && msg == sgn.msg)
+        }
+    }
+
+    impl MultiKeychain for TestKeychain {
+        type PartialMultisignature = SignatureSet<TestSignature>;
+
+        fn bootstrap_multi(
+            &self,
+            signature: &Self::Signature,
+            index: NodeIndex,
+        ) -> Self::PartialMultisignature {
+            SignatureSet::with_size(self.count).add_signature(signature, index)
+        }
+
+        fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+            self.is_complete_with_threshold(msg, partial, NodeCount(self.quorum))
+        }
+
+        fn is_complete_with_threshold(
+            &self,
+            msg: &[u8],
+            partial: &Self::PartialMultisignature,
+            threshold: NodeCount,
+        ) -> bool {
+            partial.item_count() >= threshold.0
+                && partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+        }
+    }
+
+    fn keychains(n_members: usize, quorum: usize, lenient: bool) -> Vec<TestKeychain> {
+        (0..n_members)
+            .map(|i| TestKeychain {
+                count: NodeCount(n_members),
+                index: NodeIndex(i),
+                quorum,
+                lenient,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn accepts_honest_keychains() {
+        for n_members in 1..10 {
+            let quorum = 2 * n_members / 3 + 1;
+            assert_eq!(
+                check_keybox_conformance(&keychains(n_members, quorum, false)).await,
+                Ok(())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_keychains() {
+        let mut keychains = keychains(4, 3, false);
+        keychains.pop();
+        assert_eq!(
+            check_keybox_conformance(&keychains).await,
+            Err(ConformanceError::WrongCommittee {
+                node_count: NodeCount(4),
+                keychains: 3,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_lenient_verification() {
+        assert_eq!(
+            check_keybox_conformance(&keychains(4, 3, true)).await,
+            Err(ConformanceError::IncorrectSignatureAccepted {
+                signer: NodeIndex(0),
+                verifier: NodeIndex(0),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_quorum() {
+        assert_eq!(
+            check_keybox_conformance(&keychains(7, 4, false)).await,
+            Err(ConformanceError::BelowQuorumComplete(NodeIndex(0)))
+        );
+        assert_eq!(
+            check_keybox_conformance(&keychains(7, 6, false)).await,
+            Err(ConformanceError::QuorumIncomplete(NodeIndex(0)))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{Index, KeyEpoch, Keychain, MultiKeychain, NodeCount, NodeIndex};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// A keychain signing and verifying messages prefixed with a domain tag, so that its signatures
+/// can never be mistaken for signatures made with the same keys for anything else, e.g. by
+/// another protocol. With an empty tag the messages are signed as they are.
+///
+/// Multisignatures are partial multisignatures of the wrapped keychain, complete when they are
+/// complete for it.
+#[derive(Clone)]
+pub struct DomainKeychain<K: Keychain> {
+    keychain: K,
+    domain: Arc<Vec<u8>>,
+}
+
+impl<K: Keychain> DomainKeychain<K> {
+    /// Prefixes all the messages signed and verified with the keychain with the tag.
+    pub fn new(keychain: K, domain: Vec<u8>) -> Self {
+        DomainKeychain {
+            keychain,
+            domain: Arc::new(domain),
+        }
+    }
+
+    /// The tag prefixing the messages.
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+
+    /// The wrapped keychain itself.
+    pub fn inner(&self) -> &K {
+        &self.keychain
+    }
+
+    fn tagged(&self, msg: &[u8]) -> Vec<u8> {
+        [&self.domain[..], msg].concat()
+    }
+}
+
+impl<K: Keychain> Index for DomainKeychain<K> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+#[async_trait]
+impl<K: Keychain> Keychain for DomainKeychain<K> {
+    type Signature = K::Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        self.keychain.sign(&self.tagged(msg)).await
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.keychain.verify(&self.tagged(msg), sgn, index)
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        let tagged: Vec<_> = items.iter().map(|(msg, ..)| self.tagged(msg)).collect();
+        let items: Vec<_> = items
+            .iter()
+            .zip(tagged.iter())
+            .map(|((_, sgn, index), msg)| (&msg[..], *sgn, *index))
+            .collect();
+        self.keychain.verify_batch(&items)
+    }
+
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.keychain.has_key(index)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        DomainKeychain {
+            keychain: self.keychain.at_epoch(epoch),
+            domain: self.domain.clone(),
+        }
+    }
+}
+
+impl<MK: MultiKeychain> MultiKeychain for DomainKeychain<MK> {
+    type PartialMultisignature = MK::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain.bootstrap_multi(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.keychain.is_complete(&self.tagged(msg), partial)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        self.keychain
+            .is_complete_with_threshold(&self.tagged(msg), partial, threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DomainKeychain;
+    use crate::{
+        check_keybox_conformance, Index, Keychain, MultiKeychain, NodeCount, NodeIndex,
+        PartialMultisignature, SignatureSet,
+    };
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestKeychain {
+        index: NodeIndex,
+    }
+
+    impl Index for TestKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for TestKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            NodeCount(4)
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            index == sgn.index && msg == sgn.msg
+        }
+    }
+
+    impl MultiKeychain for TestKeychain {
+        type PartialMultisignature = SignatureSet<TestSignature>;
+
+        fn bootstrap_multi(
+            &self,
+            signature: &Self::Signature,
+            index: NodeIndex,
+        ) -> Self::PartialMultisignature {
+            SignatureSet::with_size(self.node_count()).add_signature(signature, index)
+        }
+
+        fn is_complete_with_threshold(
+            &self,
+            msg: &[u8],
+            partial: &Self::PartialMultisignature,
+            threshold: NodeCount,
+        ) -> bool {
+            partial.iter().count() >= threshold.0
+                && partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+        }
+    }
+
+    fn keychains(domain: &[u8]) -> Vec<DomainKeychain<TestKeychain>> {
+        (0..4)
+            .map(|ix| {
+                DomainKeychain::new(
+                    TestKeychain {
+                        index: NodeIndex(ix),
+                    },
+                    domain.to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn separates_domains() {
+        let msg = b"message";
+        let units = keychains(b"units");
+        let votes = keychains(b"votes");
+        let signature = units[0].sign(msg).await;
+        assert_eq!(signature.msg, b"unitsmessage".to_vec());
+        assert!(units[1].verify(msg, &signature, NodeIndex(0)));
+        assert!(!votes[1].verify(msg, &signature, NodeIndex(0)));
+        assert!(!units[1].inner().verify(msg, &signature, NodeIndex(0)));
+        assert_eq!(
+            units[1].verify_batch(&[(msg, &signature, NodeIndex(0))]),
+            vec![true]
+        );
+
+        let mut partial = units[0].bootstrap_multi(&signature, NodeIndex(0));
+        for keychain in &units[1..3] {
+            partial = partial.add_signature(&keychain.sign(msg).await, keychain.index());
+        }
+        assert!(units[3].is_complete(msg, &partial));
+        assert!(!votes[3].is_complete(msg, &partial));
+    }
+
+    #[tokio::test]
+    async fn conforms() {
+        for domain in [&b""[..], &b"units"[..]] {
+            assert_eq!(check_keybox_conformance(&keychains(domain)).await, Ok(()));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    Index, KeyEpoch, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+    SignatureSet,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+
+/// One of the two signature schemes of a [`DualKeychain`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DualScheme {
+    First,
+    Second,
+}
+
+/// A signature made with one of the two schemes of a [`DualKeychain`]. The scheme is encoded as
+/// a tag in front of the signature, so that the signature is verified with the right keys.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub enum DualSignature<SA, SB> {
+    First(SA),
+    Second(SB),
+}
+
+impl<SA, SB> DualSignature<SA, SB> {
+    /// The scheme the signature was made with.
+    pub fn scheme(&self) -> DualScheme {
+        match self {
+            DualSignature::First(_) => DualScheme::First,
+            DualSignature::Second(_) => DualScheme::Second,
+        }
+    }
+}
+
+/// A keychain for migrating between two signature schemes. It signs with the preferred scheme
+/// and verifies signatures of either, so during the transition window nodes that switched
+/// already and nodes that did not yet accept each other's signatures.
+///
+/// Both keychains have to be keychains of the same node in the same committee. Multisignatures
+/// are sets of signatures, possibly of both schemes, and are complete with signatures of more
+/// than 2/3 of the nodes, whichever scheme they used.
+#[derive(Clone)]
+pub struct DualKeychain<A: Keychain, B: Keychain> {
+    first: A,
+    second: B,
+    preferred: DualScheme,
+}
+
+impl<A: Keychain, B: Keychain> DualKeychain<A, B> {
+    /// Combines the keychains, signing with the preferred one.
+    pub fn new(first: A, second: B, preferred: DualScheme) -> Self {
+        DualKeychain {
+            first,
+            second,
+            preferred,
+        }
+    }
+
+    /// Makes the keychain sign with the given scheme from now on, e.g. once enough nodes can
+    /// verify signatures of the new one.
+    pub fn prefer(&mut self, scheme: DualScheme) {
+        self.preferred = scheme;
+    }
+
+    /// The scheme the keychain signs with.
+    pub fn preferred(&self) -> DualScheme {
+        self.preferred
+    }
+}
+
+impl<A: Keychain, B: Keychain> Index for DualKeychain<A, B> {
+    fn index(&self) -> NodeIndex {
+        self.first.index()
+    }
+}
+
+#[async_trait]
+impl<A: Keychain, B: Keychain> Keychain for DualKeychain<A, B> {
+    type Signature = DualSignature<A::Signature, B::Signature>;
+
+    fn node_count(&self) -> NodeCount {
+        self.first.node_count()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        match self.preferred {
+            DualScheme::First => DualSignature::First(self.first.sign(msg).await),
+            DualScheme::Second => DualSignature::Second(self.second.sign(msg).await),
+        }
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        match sgn {
+            DualSignature::First(sgn) => self.first.verify(msg, sgn, index),
+            DualSignature::Second(sgn) => self.second.verify(msg, sgn, index),
+        }
+    }
+
+    /// Known if the key of either scheme is.
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.first.has_key(index) || self.second.has_key(index)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        DualKeychain {
+            first: self.first.at_epoch(epoch),
+            second: self.second.at_epoch(epoch),
+            preferred: self.preferred,
+        }
+    }
+}
+
+impl<A: Keychain, B: Keychain> MultiKeychain for DualKeychain<A, B> {
+    type PartialMultisignature = SignatureSet<Self::Signature>;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::with_size(self.node_count()).add_signature(signature, index)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        partial.item_count() >= threshold.0
+            && partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DualKeychain, DualScheme, DualSignature};
+    use crate::{
+        check_keybox_conformance, Index, Keychain, MultiKeychain, NodeCount, NodeIndex,
+        PartialMultisignature,
+    };
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+
+    // The signatures of both schemes are the same, but the schemes use different keys.
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+        key: u8,
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestKeychain {
+        index: NodeIndex,
+        key: u8,
+    }
+
+    impl Index for TestKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for TestKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            NodeCount(4)
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+                key: self.key,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            index == sgn.index && msg == sgn.msg && self.key == sgn.key
+        }
+    }
+
+    fn keychain(ix: usize, preferred: DualScheme) -> DualKeychain<TestKeychain, TestKeychain> {
+        let index = NodeIndex(ix);
+        DualKeychain::new(
+            TestKeychain { index, key: 0 },
+            TestKeychain { index, key: 1 },
+            preferred,
+        )
+    }
+
+    #[tokio::test]
+    async fn verifies_signatures_of_both_schemes() {
+        let msg = b"message";
+        let old = keychain(0, DualScheme::First);
+        let mut new = keychain(1, DualScheme::First);
+        new.prefer(DualScheme::Second);
+        assert_eq!(new.preferred(), DualScheme::Second);
+
+        let old_signature = old.sign(msg).await;
+        let new_signature = new.sign(msg).await;
+        assert_eq!(old_signature.scheme(), DualScheme::First);
+        assert_eq!(new_signature.scheme(), DualScheme::Second);
+        assert!(new.verify(msg, &old_signature, NodeIndex(0)));
+        assert!(old.verify(msg, &new_signature, NodeIndex(1)));
+        assert!(!old.verify(msg, &new_signature, NodeIndex(0)));
+
+        // A signature tagged with the wrong scheme does not verify.
+        let mistagged = match new_signature.clone() {
+            DualSignature::Second(signature) => DualSignature::First(signature),
+            DualSignature::First(_) => unreachable!("the second scheme is preferred"),
+        };
+        assert!(!old.verify(msg, &mistagged, NodeIndex(1)));
+        let decoded = DualSignature::decode(&mut &new_signature.encode()[..])
+            .expect("the signature should decode");
+        assert_eq!(decoded, new_signature);
+
+        // Multisignatures mix the schemes.
+        let third = keychain(2, DualScheme::Second);
+        let partial = old
+            .bootstrap_multi(&old_signature, NodeIndex(0))
+            .add_signature(&new_signature, NodeIndex(1));
+        assert!(!old.is_complete(msg, &partial));
+        let partial = partial.add_signature(&third.sign(msg).await, NodeIndex(2));
+        assert!(old.is_complete(msg, &partial));
+    }
+
+    #[tokio::test]
+    async fn conforms_with_either_preference() {
+        for preferred in [DualScheme::First, DualScheme::Second] {
+            let keychains: Vec<_> = (0..4).map(|ix| keychain(ix, preferred)).collect();
+            assert_eq!(check_keybox_conformance(&keychains).await, Ok(()));
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! A ready-to-use [`MultiKeychain`] based on ed25519 signatures, for trying AlephBFT out without
+//! implementing one. Multisignatures are sets of the signatures of single nodes.
+
+use crate::{
+    Index, Keychain as KeychainT, MultiKeychain, NodeCount, NodeIndex,
+    PartialMultisignature as PartialMultisignatureT, PublicVerification, SignatureSet,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use ed25519_dalek::{Signer, Verifier};
+use std::sync::Arc;
+
+pub use ed25519_dalek::{SigningKey as SecretKey, VerifyingKey as PublicKey};
+
+/// An ed25519 signature of a single node.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct Signature([u8; 64]);
+
+/// The signatures of a subset of nodes, complete once a quorum of them is there.
+pub type PartialMultisignature = SignatureSet<Signature>;
+
+fn verify(public_keys: &[PublicKey], msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+    match public_keys.get(index.0) {
+        Some(public_key) => public_key
+            .verify(msg, &ed25519_dalek::Signature::from_bytes(&sgn.0))
+            .is_ok(),
+        None => false,
+    }
+}
+
+impl PublicVerification for PartialMultisignature {
+    type PublicKey = PublicKey;
+
+    fn verify_with_keys(&self, msg: &[u8], public_keys: &[Self::PublicKey]) -> bool {
+        self.size().0 == public_keys.len()
+            && self
+                .iter()
+                .all(|(index, sgn)| verify(public_keys, msg, sgn, index))
+    }
+}
+
+/// A fresh secret key drawn from the thread local cryptographically secure generator.
+pub fn generate_secret_key() -> SecretKey {
+    SecretKey::from_bytes(&rand::random())
+}
+
+/// The keychains of a whole committee with freshly generated keys, e.g. for local testing. In
+/// a real deployment every node generates its own secret key and only the public keys are
+/// exchanged.
+pub fn generate_keychains(node_count: NodeCount) -> Vec<Keychain> {
+    let secret_keys: Vec<_> = (0..node_count.0).map(|_| generate_secret_key()).collect();
+    let public_keys: Vec<_> = secret_keys.iter().map(SecretKey::verifying_key).collect();
+    secret_keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, secret_key)| Keychain::new(NodeIndex(index), secret_key, public_keys.clone()))
+        .collect()
+}
+
+/// The secret key of a node together with the public keys of the whole committee, ordered by
+/// the indices of the nodes.
+#[derive(Clone)]
+pub struct Keychain {
+    index: NodeIndex,
+    secret_key: SecretKey,
+    public_keys: Arc<Vec<PublicKey>>,
+}
+
+impl Keychain {
+    /// The `secret_key` should be the one of the public key with our index.
+    pub fn new(index: NodeIndex, secret_key: SecretKey, public_keys: Vec<PublicKey>) -> Self {
+        Keychain {
+            index,
+            secret_key,
+            public_keys: Arc::new(public_keys),
+        }
+    }
+}
+
+impl Index for Keychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+#[async_trait]
+impl KeychainT for Keychain {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        NodeCount(self.public_keys.len())
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        Signature(self.secret_key.sign(msg).to_bytes())
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        verify(&self.public_keys, msg, sgn, index)
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        let public_keys: Option<Vec<_>> = items
+            .iter()
+            .map(|(_, _, index)| self.public_keys.get(index.0).copied())
+            .collect();
+        if let Some(public_keys) = public_keys {
+            let msgs: Vec<_> = items.iter().map(|(msg, _, _)| *msg).collect();
+            let signatures: Vec<_> = items
+                .iter()
+                .map(|(_, sgn, _)| ed25519_dalek::Signature::from_bytes(&sgn.0))
+                .collect();
+            if ed25519_dalek::verify_batch(&msgs, &signatures, &public_keys).is_ok() {
+                return vec![true; items.len()];
+            }
+        }
+        // Some signature is wrong, find out which.
+        items
+            .iter()
+            .map(|(msg, sgn, index)| self.verify(msg, sgn, *index))
+            .collect()
+    }
+}
+
+impl MultiKeychain for Keychain {
+    type PartialMultisignature = PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::with_size(self.node_count()).add_signature(signature, index)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        if partial.iter().count() < threshold.0 {
+            return false;
+        }
+        partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_keychains, generate_secret_key, Keychain, PartialMultisignature};
+    use crate::{
+        check_keybox_conformance, verify_certificate, Certificate, CertificateError,
+        DomainKeychain, Keychain as _, NodeCount, NodeIndex, PartiallyMultisigned, SessionId,
+        Signed,
+    };
+    use codec::{Decode, Encode};
+
+    #[tokio::test]
+    async fn generated_keychains_conform() {
+        let keychains = generate_keychains(NodeCount(7));
+        assert_eq!(check_keybox_conformance(&keychains).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn verifies_batches() {
+        let keychains = generate_keychains(NodeCount(4));
+        let msg: &[u8] = b"message";
+        let mut signatures = Vec::new();
+        for keychain in &keychains {
+            signatures.push(keychain.sign(msg).await);
+        }
+        let mut batch: Vec<_> = signatures
+            .iter()
+            .enumerate()
+            .map(|(index, signature)| (msg, signature, NodeIndex(index)))
+            .collect();
+        assert_eq!(keychains[0].verify_batch(&batch), vec![true; 4]);
+        batch[2].2 = NodeIndex(3);
+        assert_eq!(
+            keychains[0].verify_batch(&batch),
+            vec![true, true, false, true]
+        );
+    }
+
+    #[tokio::test]
+    async fn certifies_multisignatures() {
+        let keychains = generate_keychains(NodeCount(4));
+        let public_keys = keychains[0].public_keys.to_vec();
+        let msg = b"block".to_vec();
+        let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+        partial = partial.add_signature(
+            Signed::sign_with_index(msg.clone(), &keychains[1]).await,
+            &keychains[0],
+        );
+        let minority = Certificate::new(
+            SessionId::from_number(7),
+            None,
+            msg.clone(),
+            partial.clone().into_unchecked().signature(),
+        );
+        assert_eq!(
+            verify_certificate(&minority, SessionId::from_number(7), &public_keys),
+            Err(CertificateError::NotQuorum)
+        );
+
+        partial = partial.add_signature(
+            Signed::sign_with_index(msg.clone(), &keychains[2]).await,
+            &keychains[0],
+        );
+        let certificate = match partial {
+            PartiallyMultisigned::Complete { multisigned } => {
+                multisigned.into_certificate(SessionId::from_number(7), None)
+            }
+            PartiallyMultisigned::Incomplete { .. } => panic!("three of four nodes are a quorum"),
+        };
+        let certificate: Certificate<Vec<u8>, PartialMultisignature> =
+            Certificate::decode(&mut &certificate.encode()[..])
+                .expect("the certificate was just encoded");
+        assert_eq!(certificate.session_id(), SessionId::from_number(7));
+        assert_eq!(certificate.signers().len(), 3);
+        let session_id = SessionId::from_number(7);
+        assert_eq!(
+            verify_certificate(&certificate, session_id, &public_keys),
+            Ok(())
+        );
+        assert_eq!(
+            verify_certificate(&certificate, SessionId::from_number(8), &public_keys),
+            Err(CertificateError::WrongSession)
+        );
+        assert_eq!(
+            verify_certificate(&certificate, session_id, &public_keys[..3]),
+            Err(CertificateError::WrongCommittee)
+        );
+        let strangers = generate_keychains(NodeCount(4))[0].public_keys.to_vec();
+        assert_eq!(
+            verify_certificate(&certificate, session_id, &strangers),
+            Err(CertificateError::WrongMultisignature)
+        );
+    }
+
+    #[tokio::test]
+    async fn certifies_multisignatures_in_signing_domains() {
+        let session_id = SessionId::from_number(7);
+        let keychains: Vec<_> = generate_keychains(NodeCount(4))
+            .into_iter()
+            .map(|keychain| DomainKeychain::new(keychain, session_id.signing_domain(Some(3))))
+            .collect();
+        let public_keys = keychains[0].inner().public_keys.to_vec();
+        let msg = b"block".to_vec();
+        let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+        for keychain in &keychains[1..3] {
+            partial = partial.add_signature(
+                Signed::sign_with_index(msg.clone(), keychain).await,
+                &keychains[0],
+            );
+        }
+        let multisigned = match partial {
+            PartiallyMultisigned::Complete { multisigned } => multisigned,
+            PartiallyMultisigned::Incomplete { .. } => panic!("three of four nodes are a quorum"),
+        };
+        let certificate = multisigned.clone().into_certificate(session_id, Some(3));
+        assert_eq!(
+            verify_certificate(&certificate, session_id, &public_keys),
+            Ok(())
+        );
+        // Signatures in the domain of one purpose do not pass for signatures of another.
+        for purpose in [None, Some(4)] {
+            let certificate = multisigned.clone().into_certificate(session_id, purpose);
+            assert_eq!(
+                verify_certificate(&certificate, session_id, &public_keys),
+                Err(CertificateError::WrongMultisignature)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_keys_at_wrong_index() {
+        let keychains = generate_keychains(NodeCount(4));
+        let impostor = Keychain::new(
+            NodeIndex(0),
+            generate_secret_key(),
+            keychains[0].public_keys.to_vec(),
+        );
+        assert!(keychains[0].verify_own_index().await);
+        assert!(!impostor.verify_own_index().await);
+    }
+}
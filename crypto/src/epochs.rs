@@ -0,0 +1,198 @@
+use crate::{Index, Keychain, MultiKeychain, NodeCount, NodeIndex};
+use async_trait::async_trait;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The number of a set of keys of the committee, counted from zero and increased with every
+/// rotation of the keys, see [`Keychain::at_epoch`].
+pub type KeyEpoch = u64;
+
+/// A keychain whose keys are rotated while running. Every rotation starts a new key epoch with
+/// the keychain of the new keys, the keychains of earlier epochs are kept to verify messages
+/// signed before. Clones share the epochs, so a rotation is visible to all of them.
+///
+/// Unless narrowed to an epoch with [`Keychain::at_epoch`], the keychain signs and verifies with
+/// the keys of the newest epoch. Our index has to be the same in all epochs.
+#[derive(Clone)]
+pub struct EpochKeychain<K: Keychain> {
+    epochs: Arc<RwLock<Vec<K>>>,
+    epoch: Option<KeyEpoch>,
+}
+
+impl<K: Keychain> EpochKeychain<K> {
+    /// A keychain with the keys of epoch zero.
+    pub fn new(keychain: K) -> Self {
+        EpochKeychain {
+            epochs: Arc::new(RwLock::new(vec![keychain])),
+            epoch: None,
+        }
+    }
+
+    /// Starts a new epoch with the keychain, returning the number of the epoch.
+    pub fn rotate(&self, keychain: K) -> KeyEpoch {
+        let mut epochs = self.epochs.write().unwrap_or_else(PoisonError::into_inner);
+        epochs.push(keychain);
+        (epochs.len() - 1) as KeyEpoch
+    }
+
+    /// The newest epoch.
+    pub fn newest_epoch(&self) -> KeyEpoch {
+        let epochs = self.epochs.read().unwrap_or_else(PoisonError::into_inner);
+        (epochs.len() - 1) as KeyEpoch
+    }
+
+    // Calls `f` with the keychain of our epoch, `None` if the epoch did not start yet.
+    fn with_keychain<R>(&self, f: impl FnOnce(&K) -> R) -> Option<R> {
+        let epochs = self.epochs.read().unwrap_or_else(PoisonError::into_inner);
+        let keychain = match self.epoch {
+            Some(epoch) => epochs.get(epoch as usize),
+            None => epochs.last(),
+        };
+        keychain.map(f)
+    }
+
+    // The keychain of our epoch, or of the newest one if ours did not start yet, e.g. for
+    // signing, which cannot fail.
+    fn keychain(&self) -> K {
+        let epochs = self.epochs.read().unwrap_or_else(PoisonError::into_inner);
+        let newest = epochs.last().expect("there is always an epoch");
+        self.epoch
+            .and_then(|epoch| epochs.get(epoch as usize))
+            .unwrap_or(newest)
+            .clone()
+    }
+}
+
+impl<K: Keychain> Index for EpochKeychain<K> {
+    fn index(&self) -> NodeIndex {
+        self.keychain().index()
+    }
+}
+
+#[async_trait]
+impl<K: Keychain> Keychain for EpochKeychain<K> {
+    type Signature = K::Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain().node_count()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        let keychain = self.keychain();
+        keychain.sign(msg).await
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.with_keychain(|keychain| keychain.verify(msg, sgn, index))
+            .unwrap_or(false)
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        self.with_keychain(|keychain| keychain.verify_batch(items))
+            .unwrap_or_else(|| vec![false; items.len()])
+    }
+
+    /// The keys of an epoch that did not start yet are not known.
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.with_keychain(|keychain| keychain.has_key(index))
+            .unwrap_or(false)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        EpochKeychain {
+            epochs: self.epochs.clone(),
+            epoch: Some(epoch),
+        }
+    }
+}
+
+impl<K: MultiKeychain> MultiKeychain for EpochKeychain<K> {
+    type PartialMultisignature = K::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain().bootstrap_multi(signature, index)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        self.with_keychain(|keychain| keychain.is_complete_with_threshold(msg, partial, threshold))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EpochKeychain;
+    use crate::{Index, Keychain, NodeCount, NodeIndex};
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+        key: u8,
+    }
+
+    // All nodes share the key, the signatures remember which one.
+    #[derive(Clone, Debug)]
+    struct TestKeychain {
+        index: NodeIndex,
+        key: u8,
+    }
+
+    impl Index for TestKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for TestKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            NodeCount(4)
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+                key: self.key,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            index == sgn.index && msg == sgn.msg && self.key == sgn.key
+        }
+    }
+
+    #[tokio::test]
+    async fn verifies_with_keys_of_the_epoch() {
+        let index = NodeIndex(1);
+        let keychain = EpochKeychain::new(TestKeychain { index, key: 0 });
+        let old_signature = keychain.sign(b"message").await;
+        assert_eq!(keychain.rotate(TestKeychain { index, key: 1 }), 1);
+        assert_eq!(keychain.newest_epoch(), 1);
+
+        let new_signature = keychain.sign(b"message").await;
+        assert!(keychain.verify(b"message", &new_signature, index));
+        assert!(!keychain.verify(b"message", &old_signature, index));
+        let old_keychain = keychain.at_epoch(0);
+        assert!(old_keychain.verify(b"message", &old_signature, index));
+        assert!(!old_keychain.verify(b"message", &new_signature, index));
+        assert_eq!(old_keychain.sign(b"message").await, old_signature);
+
+        let future_keychain = keychain.at_epoch(2);
+        assert!(!future_keychain.has_key(index));
+        assert!(!future_keychain.verify(b"message", &new_signature, index));
+    }
+}
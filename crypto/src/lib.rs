@@ -1,11 +1,43 @@
 //! Utilities for node addressing and message signing.
 
+#[cfg(feature = "bls")]
+pub mod bls;
+mod certificate;
+mod compact;
+mod conformance;
+mod domain;
+mod dual;
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+mod epochs;
 mod node;
+mod quarantine;
+mod session;
 mod signature;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod threshold;
+mod verification;
+mod vrf;
+mod weighted;
 
+pub use certificate::{verify_certificate, Certificate, CertificateError, PublicVerification};
+pub use compact::{CompactEncoding, CompactMultisignature};
+pub use conformance::{check_keybox_conformance, ConformanceError};
+pub use domain::DomainKeychain;
+pub use dual::{DualKeychain, DualScheme, DualSignature};
+pub use epochs::{EpochKeychain, KeyEpoch};
 pub use node::{Index, NodeCount, NodeIndex, NodeMap, NodeSubset};
+pub use quarantine::Quarantine;
+pub use session::SessionId;
 pub use signature::{
-    IncompleteMultisignatureError, Indexed, Keychain, MultiKeychain, Multisigned,
-    PartialMultisignature, PartiallyMultisigned, Signable, Signature, SignatureError, SignatureSet,
-    Signed, UncheckedSigned,
+    AddSignatureError, AggregatingMultiKeychain, IncompleteMultisignatureError, Indexed, Keychain,
+    MultiKeychain, Multisigned, PartialMultisignature, PartiallyMultisigned, Signable, Signature,
+    SignatureError, SignatureSet, Signed, UncheckedSigned,
 };
+pub use threshold::ThresholdEncryption;
+pub use verification::{
+    CachingMultiKeychain, StreamingVerification, VerificationCache, VerificationStatus,
+};
+pub use vrf::Vrf;
+pub use weighted::WeightedMultiKeychain;
@@ -82,6 +82,18 @@ impl NodeCount {
     pub fn into_iterator(self) -> impl Iterator<Item = NodeIndex> {
         (0..self.0).into_iter().map(NodeIndex)
     }
+
+    /// The number of faulty nodes a committee of this size tolerates, floor((N-1)/3). Zero for
+    /// committees of up to three nodes, which then need all of them alive.
+    pub fn max_faulty(self) -> NodeCount {
+        NodeCount(self.0.saturating_sub(1) / 3)
+    }
+
+    /// The smallest number of nodes, floor(2N/3)+1, such that any two sets of that many nodes
+    /// share an honest one. All the nodes in committees of one or two.
+    pub fn quorum(self) -> NodeCount {
+        NodeCount(self.0 * 2 / 3 + 1)
+    }
 }
 
 /// A container keeping items indexed by NodeIndex.
@@ -286,8 +298,20 @@ impl fmt::Display for NodeSubset {
 #[cfg(test)]
 mod tests {
 
-    use crate::node::{NodeIndex, NodeSubset};
+    use crate::node::{NodeCount, NodeIndex, NodeSubset};
     use codec::{Decode, Encode};
+
+    #[test]
+    fn thresholds_of_tiny_committees() {
+        let thresholds: Vec<_> = (1..=7)
+            .map(|n| (NodeCount(n).quorum().0, NodeCount(n).max_faulty().0))
+            .collect();
+        assert_eq!(
+            thresholds,
+            vec![(1, 0), (2, 0), (3, 0), (3, 1), (4, 1), (5, 1), (5, 2)]
+        );
+    }
+
     #[test]
     fn decoding_node_index_works() {
         for i in 0..1000 {
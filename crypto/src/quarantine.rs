@@ -0,0 +1,202 @@
+use crate::{Index, Keychain, Signable, Signature, SignatureError, Signed, UncheckedSigned};
+use log::debug;
+use std::collections::VecDeque;
+
+/// Messages whose signatures could not be verified because the keychain did not know the keys
+/// of their signers yet, e.g. while the keys of the next session are still being distributed.
+/// Instead of being rejected for good, they wait here until the keys become available and are
+/// checked again. The quarantine is bounded, when full the oldest messages are dropped.
+pub struct Quarantine<T: Signable + Index, S: Signature> {
+    messages: VecDeque<UncheckedSigned<T, S>>,
+    capacity: usize,
+}
+
+impl<T: Signable + Index, S: Signature> Quarantine<T, S> {
+    /// A quarantine keeping at most `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Quarantine {
+            messages: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// The number of quarantined messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Quarantines the message rejected with the error, if the keychain does not know the key of
+    /// its signer yet. Otherwise the message is just wrongly signed and the error is returned.
+    pub fn insert<K: Keychain<Signature = S>>(
+        &mut self,
+        error: SignatureError<T, S>,
+        keychain: &K,
+    ) -> Result<(), SignatureError<T, S>> {
+        let signer = error.unchecked.as_signable().index();
+        if self.capacity == 0 || keychain.has_key(signer) {
+            return Err(error);
+        }
+        if self.messages.len() == self.capacity {
+            debug!(target: "AlephBFT-quarantine", "Quarantine full, dropping the oldest message.");
+            self.messages.pop_front();
+        }
+        self.messages.push_back(error.unchecked);
+        Ok(())
+    }
+
+    /// Checks again, in a single batch, all the messages whose signers the keychain knows the
+    /// keys of now. The messages still waiting for keys stay quarantined.
+    pub fn recheck<K: Keychain<Signature = S>>(
+        &mut self,
+        keychain: &K,
+    ) -> Vec<Result<Signed<T, K>, SignatureError<T, S>>> {
+        let (ready, waiting): (Vec<_>, VecDeque<_>) = self
+            .messages
+            .drain(..)
+            .partition(|message| keychain.has_key(message.as_signable().index()));
+        self.messages = waiting;
+        UncheckedSigned::check_batch(ready, keychain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quarantine;
+    use crate::{Index, Keychain, NodeCount, NodeIndex, Signable, Signed, UncheckedSigned};
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Encode, Decode)]
+    struct TestMessage {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    impl Signable for TestMessage {
+        type Hash = Vec<u8>;
+
+        fn hash(&self) -> Self::Hash {
+            self.msg.clone()
+        }
+    }
+
+    impl Index for TestMessage {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    // Knows the keys of the nodes with indices below the shared bound.
+    #[derive(Clone, Debug)]
+    struct DistributingKeychain {
+        index: NodeIndex,
+        known_keys: Arc<AtomicUsize>,
+    }
+
+    impl Index for DistributingKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for DistributingKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            NodeCount(4)
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            self.has_key(index) && index == sgn.index && msg == sgn.msg
+        }
+
+        fn has_key(&self, index: NodeIndex) -> bool {
+            index.0 < self.known_keys.load(Ordering::SeqCst)
+        }
+    }
+
+    async fn signed_message(
+        keychain: &DistributingKeychain,
+        index: NodeIndex,
+        msg: &[u8],
+    ) -> UncheckedSigned<TestMessage, TestSignature> {
+        let message = TestMessage {
+            msg: msg.to_vec(),
+            index,
+        };
+        Signed::sign(message, keychain).await.into_unchecked()
+    }
+
+    #[tokio::test]
+    async fn rechecks_once_keys_are_known() {
+        let known_keys = Arc::new(AtomicUsize::new(2));
+        let keychains: Vec<_> = (0..4)
+            .map(|index| DistributingKeychain {
+                index: NodeIndex(index),
+                known_keys: known_keys.clone(),
+            })
+            .collect();
+        let keychain = &keychains[0];
+        let mut quarantine = Quarantine::new(2);
+
+        // Signed by another node than the one whose key is known.
+        let error = signed_message(&keychains[1], NodeIndex(0), b"forged")
+            .await
+            .check(keychain)
+            .expect_err("the signature is wrong");
+        assert!(quarantine.insert(error, keychain).is_err());
+
+        for (index, msg) in [(2, b"first"), (3, b"other"), (3, b"third")] {
+            let error = signed_message(&keychains[index], NodeIndex(index), msg)
+                .await
+                .check(keychain)
+                .expect_err("the key is unknown");
+            assert!(quarantine.insert(error, keychain).is_ok());
+        }
+        // The oldest message was dropped.
+        assert_eq!(quarantine.len(), 2);
+
+        known_keys.store(3, Ordering::SeqCst);
+        assert!(quarantine.recheck(keychain).is_empty());
+        assert_eq!(quarantine.len(), 2);
+
+        known_keys.store(4, Ordering::SeqCst);
+        let checked: Vec<_> = quarantine
+            .recheck(keychain)
+            .into_iter()
+            .map(|result| {
+                result
+                    .expect("the signature is correct")
+                    .as_signable()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            checked.iter().map(|m| m.msg.clone()).collect::<Vec<_>>(),
+            vec![b"other".to_vec(), b"third".to_vec()]
+        );
+        assert!(quarantine.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use codec::{Decode, Encode};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Identifies a session for which the consensus is run. It is contained in every unit, and
+/// thus signed, as well as in every network frame, so it should be unique among all the
+/// sessions sharing keys or network infrastructure, e.g. derived from the identifier of the
+/// chain and the number of the session within it. Numbered sessions can be created using
+/// [`SessionId::from_number`].
+#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, std::hash::Hash, Encode, Decode)]
+pub struct SessionId([u8; 32]);
+
+impl SessionId {
+    /// A session identified by the given bytes.
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        SessionId(bytes)
+    }
+
+    /// A session identified only by its number, little-endian in the leading bytes.
+    pub const fn from_number(number: u64) -> Self {
+        let number = number.to_le_bytes();
+        let mut bytes = [0; 32];
+        let mut i = 0;
+        while i < number.len() {
+            bytes[i] = number[i];
+            i += 1;
+        }
+        SessionId(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The tag prefixing the messages signed in the session for the purpose, see
+    /// [`DomainKeychain`], the session id followed by the purpose, empty without a purpose.
+    ///
+    /// [`DomainKeychain`]: crate::DomainKeychain
+    pub fn signing_domain(&self, purpose: Option<u8>) -> Vec<u8> {
+        match purpose {
+            Some(purpose) => [&self.0[..], &[purpose]].concat(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl From<u64> for SessionId {
+    fn from(number: u64) -> Self {
+        SessionId::from_number(number)
+    }
+}
+
+impl From<[u8; 32]> for SessionId {
+    fn from(bytes: [u8; 32]) -> Self {
+        SessionId(bytes)
+    }
+}
+
+impl AsRef<[u8]> for SessionId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for SessionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for SessionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "SessionId({})", self)
+    }
+}
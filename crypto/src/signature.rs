@@ -1,8 +1,15 @@
-use crate::{Index, NodeCount, NodeIndex, NodeMap};
+use crate::{
+    Certificate, Index, KeyEpoch, NodeCount, NodeIndex, NodeMap, NodeSubset, PublicVerification,
+    SessionId, VerificationCache,
+};
 use async_trait::async_trait;
 use codec::{Codec, Decode, Encode};
 use log::warn;
-use std::{fmt::Debug, hash::Hash};
+use std::{
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    hash::Hash,
+};
 
 /// The type used as a signature.
 ///
@@ -29,8 +36,46 @@ pub trait Keychain: Index + Clone + Send + Sync + 'static {
     /// Verifies whether a node with `index` correctly signed the message `msg`.
     /// Should always return false for indices outside the node range.
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool;
+
+    /// Verifies several signatures at once, returning whether each of them is correct, e.g. when
+    /// catching up with thousands of units. Schemes supporting batch verification should
+    /// override this, by default every signature is verified with [`Keychain::verify`].
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        items
+            .iter()
+            .map(|(msg, sgn, index)| self.verify(msg, sgn, *index))
+            .collect()
+    }
+
+    /// Whether the public key of the node with `index` is already known. Keychains receiving
+    /// keys while running, e.g. the keys of the next session, should return false until then,
+    /// so that messages of such nodes are quarantined instead of rejected, see [`Quarantine`].
+    ///
+    /// [`Quarantine`]: crate::Quarantine
+    fn has_key(&self, index: NodeIndex) -> bool {
+        index.0 < self.node_count().0
+    }
+
+    /// The keychain with the keys of the given key epoch, for keychains whose keys are rotated
+    /// while running, see [`EpochKeychain`]. Keychains that never rotate keys have a single
+    /// epoch, so by default this is the keychain itself.
+    ///
+    /// [`EpochKeychain`]: crate::EpochKeychain
+    fn at_epoch(&self, _epoch: KeyEpoch) -> Self {
+        self.clone()
+    }
+
+    /// Checks whether our own signatures verify as signatures of the node with our
+    /// [`Index::index`], catching keychains whose index does not match the position of their
+    /// key in the committee. Called when a session starts.
+    async fn verify_own_index(&self) -> bool {
+        let signature = self.sign(OWN_INDEX_MESSAGE).await;
+        self.verify(OWN_INDEX_MESSAGE, &signature, self.index())
+    }
 }
 
+const OWN_INDEX_MESSAGE: &[u8] = b"AlephBFT own index check";
+
 /// A type to which signatures can be aggregated.
 ///
 /// Any signature can be added to multisignature.
@@ -43,6 +88,13 @@ pub trait PartialMultisignature: Signature {
     /// Adds the signature.
     #[must_use = "consumes the original and returns the aggregated signature which should be used"]
     fn add_signature(self, signature: &Self::Signature, index: NodeIndex) -> Self;
+    /// Adds the signatures of another partial multisignature of the same message, e.g. one
+    /// collected independently by another node. Signatures of nodes already present are not
+    /// added again.
+    #[must_use = "consumes the original and returns the aggregated signature which should be used"]
+    fn merge(self, other: &Self) -> Self;
+    /// The nodes whose signatures were added, whether the signatures are correct or not.
+    fn signers(&self) -> NodeSubset;
 }
 
 /// Extends Keychain with multisigning functionalities.
@@ -57,8 +109,39 @@ pub trait MultiKeychain: Keychain {
         signature: &Self::Signature,
         index: NodeIndex,
     ) -> Self::PartialMultisignature;
-    /// Checks if enough signatures have beed added.
-    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool;
+    /// Checks if enough signatures have beed added, i.e. correct signatures of more than 2/3 of
+    /// the nodes.
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.is_complete_with_threshold(msg, partial, self.node_count().quorum())
+    }
+    /// Checks if correct signatures of at least `threshold` nodes have been added, so that the
+    /// same keychain can be used for different quorum requirements, e.g. n-f signatures for
+    /// finality certificates.
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool;
+}
+
+/// Extends MultiKeychain of schemes able to aggregate complete multisignatures of different
+/// messages into a single signature, e.g. BLS, so that whoever checks many certificates at once
+/// verifies one aggregate instead of every certificate.
+pub trait AggregatingMultiKeychain: MultiKeychain {
+    type AggregatedMultisignature: Signature;
+    /// Aggregates the multisignatures, `None` if some of them is malformed.
+    fn aggregate(
+        &self,
+        multisignatures: &[&Self::PartialMultisignature],
+    ) -> Option<Self::AggregatedMultisignature>;
+    /// Checks if the aggregate consists of complete multisignatures of the messages, in the same
+    /// order.
+    fn verify_aggregated(
+        &self,
+        msgs: &[&[u8]],
+        aggregated: &Self::AggregatedMultisignature,
+    ) -> bool;
 }
 
 /// A set of signatures of a subset of nodes serving as a (partial) multisignature
@@ -72,6 +155,20 @@ impl<S: Signature> PartialMultisignature for SignatureSet<S> {
         self.insert(index, signature.clone());
         self
     }
+
+    #[must_use = "consumes the original and returns the aggregated signature which should be used"]
+    fn merge(mut self, other: &Self) -> Self {
+        for (index, signature) in other.iter() {
+            if self.get(index).is_none() {
+                self.insert(index, signature.clone());
+            }
+        }
+        self
+    }
+
+    fn signers(&self) -> NodeSubset {
+        self.to_subset()
+    }
 }
 
 /// Data which can be signed.
@@ -126,8 +223,21 @@ impl<T: Signable, S: Signature> UncheckedSigned<Indexed<T>, S> {
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
 pub struct SignatureError<T: Signable, S: Signature> {
     pub unchecked: UncheckedSigned<T, S>,
+    /// The node whose signature failed, `None` for multisignatures.
+    pub signer: Option<NodeIndex>,
 }
 
+impl<T: Signable, S: Signature> Display for SignatureError<T, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.signer {
+            Some(signer) => write!(f, "invalid signature of node {:?}", signer),
+            None => write!(f, "invalid or incomplete multisignature"),
+        }
+    }
+}
+
+impl<T: Signable, S: Signature> Error for SignatureError<T, S> where Self: Debug {}
+
 impl<T: Signable + Index, S: Signature> UncheckedSigned<T, S> {
     /// Verifies whether the signature matches the key with the index as in the signed data.
     pub fn check<K: Keychain<Signature = S>>(
@@ -136,10 +246,99 @@ impl<T: Signable + Index, S: Signature> UncheckedSigned<T, S> {
     ) -> Result<Signed<T, K>, SignatureError<T, S>> {
         let index = self.signable.index();
         if !keychain.verify(self.signable.hash().as_ref(), &self.signature, index) {
-            return Err(SignatureError { unchecked: self });
+            return Err(SignatureError {
+                unchecked: self,
+                signer: Some(index),
+            });
         }
         Ok(Signed { unchecked: self })
     }
+
+    /// Like [`UncheckedSigned::check`], but skips verifying signatures found in the cache and
+    /// caches the correct ones.
+    pub fn check_cached<K: Keychain<Signature = S>>(
+        self,
+        keychain: &K,
+        cache: &VerificationCache,
+    ) -> Result<Signed<T, K>, SignatureError<T, S>> {
+        let key = self.cache_key();
+        if cache.contains(&key) {
+            return Ok(Signed { unchecked: self });
+        }
+        let signed = self.check(keychain)?;
+        cache.insert(key);
+        Ok(signed)
+    }
+
+    fn cache_key(&self) -> Vec<u8> {
+        VerificationCache::key(
+            self.signable.hash().as_ref(),
+            self.signable.index(),
+            &self.signature,
+        )
+    }
+
+    /// Verifies the signatures of all the items at once using [`Keychain::verify_batch`],
+    /// returning the results in the same order.
+    pub fn check_batch<K: Keychain<Signature = S>>(
+        items: Vec<Self>,
+        keychain: &K,
+    ) -> Vec<Result<Signed<T, K>, SignatureError<T, S>>> {
+        let hashes: Vec<_> = items.iter().map(|item| item.signable.hash()).collect();
+        let batch: Vec<_> = items
+            .iter()
+            .zip(hashes.iter())
+            .map(|(item, hash)| (hash.as_ref(), &item.signature, item.signable.index()))
+            .collect();
+        let verified = keychain.verify_batch(&batch);
+        items
+            .into_iter()
+            .zip(verified)
+            .map(|(unchecked, correct)| match correct {
+                true => Ok(Signed { unchecked }),
+                false => Err(SignatureError {
+                    signer: Some(unchecked.signable.index()),
+                    unchecked,
+                }),
+            })
+            .collect()
+    }
+
+    /// Like [`UncheckedSigned::check_batch`], but only the signatures not found in the cache
+    /// are verified, and the correct ones are cached.
+    pub fn check_batch_cached<K: Keychain<Signature = S>>(
+        items: Vec<Self>,
+        keychain: &K,
+        cache: &VerificationCache,
+    ) -> Vec<Result<Signed<T, K>, SignatureError<T, S>>> {
+        let mut results = Vec::with_capacity(items.len());
+        let (mut positions, mut unknown, mut keys) = (Vec::new(), Vec::new(), Vec::new());
+        for (position, unchecked) in items.into_iter().enumerate() {
+            let key = unchecked.cache_key();
+            if cache.contains(&key) {
+                results.push(Some(Ok(Signed { unchecked })));
+                continue;
+            }
+            results.push(None);
+            positions.push(position);
+            unknown.push(unchecked);
+            keys.push(key);
+        }
+        for ((position, key), result) in positions
+            .into_iter()
+            .zip(keys)
+            .zip(Self::check_batch(unknown, keychain))
+        {
+            if result.is_ok() {
+                cache.insert(key);
+            }
+            results[position] = Some(result);
+        }
+        results
+            .into_iter()
+            .map(|result| result.expect("every item was checked"))
+            .collect()
+    }
 }
 
 impl<T: Signable + Index, S: Signature> Index for UncheckedSigned<T, S> {
@@ -155,7 +354,10 @@ impl<T: Signable, S: PartialMultisignature> UncheckedSigned<T, S> {
         keychain: &MK,
     ) -> Result<Multisigned<T, MK>, SignatureError<T, S>> {
         if !(keychain.is_complete(self.signable.hash().as_ref(), &self.signature)) {
-            return Err(SignatureError { unchecked: self });
+            return Err(SignatureError {
+                unchecked: self,
+                signer: None,
+            });
         }
         Ok(Multisigned { unchecked: self })
     }
@@ -178,8 +380,9 @@ impl<T: Signable, S: Signature> From<UncheckedSigned<Indexed<T>, S>> for Uncheck
 
 /// A correctly signed object of type `T`.
 ///
-/// The correctness is guaranteed by storing a (phantom) reference to the `Keychain` that verified
-/// the signature.
+/// The correctness is guaranteed by the type of the `Keychain` that verified the signature, the
+/// keychain itself is not borrowed, so signed objects can be stored and moved between tasks
+/// freely.
 #[derive(Eq, PartialEq, Hash, Debug, Decode, Encode)]
 pub struct Signed<T: Signable + Index, K: Keychain> {
     unchecked: UncheckedSigned<T, K::Signature>,
@@ -294,8 +497,8 @@ impl<T: Signable> Index for Indexed<T> {
 /// Signable data together with a complete multisignature.
 ///
 /// An instance of `Multisigned<T: Signable, MK: MultiKeychain>` consists of a data of type `T`
-/// together with a multisignature which is valid and complete according to a multikeychain
-/// reference `MK`.
+/// together with a multisignature which is valid and complete according to a multikeychain of
+/// type `MK`. Like [`Signed`], it does not borrow the multikeychain.
 #[derive(Eq, PartialEq, Hash, Debug, Decode, Encode)]
 pub struct Multisigned<T: Signable, MK: MultiKeychain> {
     unchecked: UncheckedSigned<T, MK::PartialMultisignature>,
@@ -312,6 +515,27 @@ impl<T: Signable, MK: MultiKeychain> Multisigned<T, MK> {
     }
 }
 
+impl<T: Signable, MK: MultiKeychain> Multisigned<T, MK>
+where
+    MK::PartialMultisignature: PublicVerification,
+{
+    /// A proof of the multisignature of the session for verifiers without the keychain, see
+    /// [`Certificate`]. The signing purpose is the one of the session, the keychain has to sign
+    /// in its [`SessionId::signing_domain`], as the keychain made by `Config::domain_keychain`
+    /// does.
+    pub fn into_certificate(
+        self,
+        session_id: SessionId,
+        signing_purpose: Option<u8>,
+    ) -> Certificate<T::Hash, MK::PartialMultisignature> {
+        let UncheckedSigned {
+            signable,
+            signature,
+        } = self.unchecked;
+        Certificate::new(session_id, signing_purpose, signable.hash(), signature)
+    }
+}
+
 impl<T: Signable, MK: MultiKeychain> From<Multisigned<T, MK>>
     for UncheckedSigned<T, MK::PartialMultisignature>
 {
@@ -336,7 +560,7 @@ impl<T: Signable + Clone, MK: MultiKeychain> Clone for Multisigned<T, MK> {
 /// # use std::hash::Hasher;
 /// # use aleph_bft_crypto::{MultiKeychain, PartiallyMultisigned, Signable};
 /// # trait Hash {};
-/// impl<'a, T: Hash + Signable, MK: Hash + MultiKeychain>
+/// impl<T: Hash + Signable, MK: Hash + MultiKeychain>
 ///    Hash for PartiallyMultisigned<T, MK>
 /// where MK::PartialMultisignature: Hash {}
 /// ```
@@ -353,6 +577,59 @@ pub struct IncompleteMultisignatureError<T: Signable, MK: MultiKeychain> {
     pub partial: PartiallyMultisigned<T, MK>,
 }
 
+impl<T: Signable, MK: MultiKeychain> Display for IncompleteMultisignatureError<T, MK> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "the multisignature is not complete yet")
+    }
+}
+
+impl<T: Signable, MK: MultiKeychain> Error for IncompleteMultisignatureError<T, MK> where Self: Debug
+{}
+
+/// Why a signature could not be added by [`PartiallyMultisigned::add_unchecked`]. Either way
+/// the partial multisignature is returned unchanged.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum AddSignatureError<T: Signable, MK: MultiKeychain> {
+    /// The signature is of a different object.
+    DifferentSignable {
+        partial: PartiallyMultisigned<T, MK>,
+    },
+    /// The signature is not a correct signature of the node.
+    WrongSignature {
+        partial: PartiallyMultisigned<T, MK>,
+        signer: NodeIndex,
+    },
+}
+
+impl<T: Signable, MK: MultiKeychain> AddSignatureError<T, MK> {
+    /// The partial multisignature the signature was not added to.
+    pub fn into_partial(self) -> PartiallyMultisigned<T, MK> {
+        match self {
+            AddSignatureError::DifferentSignable { partial } => partial,
+            AddSignatureError::WrongSignature { partial, .. } => partial,
+        }
+    }
+}
+
+impl<T: Signable, MK: MultiKeychain> Display for AddSignatureError<T, MK> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            AddSignatureError::DifferentSignable { .. } => {
+                write!(f, "the signature is of a different object")
+            }
+            AddSignatureError::WrongSignature { signer, .. } => {
+                write!(
+                    f,
+                    "the signature is not a correct signature of {:?}",
+                    signer
+                )
+            }
+        }
+    }
+}
+
+impl<T: Signable, MK: MultiKeychain> Error for AddSignatureError<T, MK> where Self: Debug {}
+
 /// Signable data together with a valid partial multisignature.
 ///
 /// Instances of this type keep track whether the partial multisignautre is complete or not.
@@ -400,6 +677,17 @@ impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
         }
     }
 
+    /// The nodes that already signed the object, e.g. to show the progress of collecting the
+    /// signatures or to decide whom to ask for the missing ones.
+    pub fn signers(&self) -> NodeSubset {
+        match self {
+            PartiallyMultisigned::Incomplete { unchecked } => unchecked.signature.signers(),
+            PartiallyMultisigned::Complete { multisigned } => {
+                multisigned.unchecked.signature.signers()
+            }
+        }
+    }
+
     /// Adds a signature and checks if multisignature is complete.
     #[must_use = "consumes the original and returns the aggregated signature which should be used"]
     pub fn add_signature(self, signed: Signed<Indexed<T>, MK>, keychain: &MK) -> Self {
@@ -423,14 +711,83 @@ impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
             PartiallyMultisigned::Complete { .. } => self,
         }
     }
+
+    /// Verifies the signature of the node, e.g. received from the network, before adding it as
+    /// [`PartiallyMultisigned::add_signature`] does, so that no [`Signed`] has to be created
+    /// first.
+    pub fn add_unchecked(
+        self,
+        unchecked: UncheckedSigned<T, MK::Signature>,
+        index: NodeIndex,
+        keychain: &MK,
+    ) -> Result<Self, AddSignatureError<T, MK>> {
+        if self.as_signable().hash().as_ref() != unchecked.signable.hash().as_ref() {
+            return Err(AddSignatureError::DifferentSignable { partial: self });
+        }
+        let UncheckedSigned {
+            signable,
+            signature,
+        } = unchecked;
+        let unchecked = UncheckedSigned {
+            signable: Indexed::new(signable, index),
+            signature,
+        };
+        match unchecked.check(keychain) {
+            Ok(signed) => Ok(self.add_signature(signed, keychain)),
+            Err(_) => Err(AddSignatureError::WrongSignature {
+                partial: self,
+                signer: index,
+            }),
+        }
+    }
+
+    /// Combines the signatures collected independently for the same signable, e.g. by another
+    /// node, and checks if the multisignature is complete. Returns `self` unchanged if the other
+    /// one signs something else or any of its signatures is incorrect.
+    #[must_use = "consumes the original and returns the aggregated signature which should be used"]
+    pub fn merge(self, other: PartiallyMultisigned<T, MK>, keychain: &MK) -> Self {
+        if self.as_signable().hash().as_ref() != other.as_signable().hash().as_ref() {
+            warn!(target: "AlephBFT-signed", "Tried to merge signatures of a different object");
+            return self;
+        }
+        match (self, other) {
+            (complete @ PartiallyMultisigned::Complete { .. }, _)
+            | (_, complete @ PartiallyMultisigned::Complete { .. }) => complete,
+            (
+                PartiallyMultisigned::Incomplete { mut unchecked },
+                PartiallyMultisigned::Incomplete { unchecked: other },
+            ) => {
+                // Aggregated signatures cannot be taken apart, so merging an incorrect one would
+                // make ours incorrect for good.
+                let hash = other.signable.hash();
+                if !keychain.is_complete_with_threshold(
+                    hash.as_ref(),
+                    &other.signature,
+                    NodeCount(1),
+                ) {
+                    warn!(target: "AlephBFT-signed", "Tried to merge incorrect signatures");
+                    return PartiallyMultisigned::Incomplete { unchecked };
+                }
+                unchecked.signature = unchecked.signature.merge(&other.signature);
+                if keychain.is_complete(unchecked.signable.hash().as_ref(), &unchecked.signature) {
+                    PartiallyMultisigned::Complete {
+                        multisigned: Multisigned { unchecked },
+                    }
+                } else {
+                    PartiallyMultisigned::Incomplete { unchecked }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use crate::{
-        Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
-        PartiallyMultisigned, Signable, SignatureSet, Signed,
+        AddSignatureError, Index, KeyEpoch, Keychain, MultiKeychain, NodeCount, NodeIndex,
+        PartialMultisignature, PartiallyMultisigned, Signable, SignatureSet, Signed,
+        UncheckedSigned,
     };
     use async_trait::async_trait;
     use codec::{Decode, Encode};
@@ -450,10 +807,6 @@ mod tests {
         fn new(keychain: K) -> Self {
             DefaultMultiKeychain { keychain }
         }
-
-        fn quorum(&self) -> usize {
-            2 * self.node_count().0 / 3 + 1
-        }
     }
 
     impl<K: Keychain> Index for DefaultMultiKeychain<K> {
@@ -477,6 +830,20 @@ mod tests {
         fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
             self.keychain.verify(msg, sgn, index)
         }
+
+        fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+            self.keychain.verify_batch(items)
+        }
+
+        fn has_key(&self, index: NodeIndex) -> bool {
+            self.keychain.has_key(index)
+        }
+
+        fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+            DefaultMultiKeychain {
+                keychain: self.keychain.at_epoch(epoch),
+            }
+        }
     }
 
     impl<K: Keychain> MultiKeychain for DefaultMultiKeychain<K> {
@@ -494,9 +861,14 @@ mod tests {
             )
         }
 
-        fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        fn is_complete_with_threshold(
+            &self,
+            msg: &[u8],
+            partial: &Self::PartialMultisignature,
+            threshold: NodeCount,
+        ) -> bool {
             let signature_count = partial.iter().count();
-            if signature_count < self.quorum() {
+            if signature_count < threshold.0 {
                 return false;
             }
             partial
@@ -567,6 +939,36 @@ mod tests {
         }
     }
 
+    // Claims to be a different node than the one whose key it holds.
+    #[derive(Clone, Debug)]
+    struct MisindexedKeychain {
+        keychain: TestKeychain,
+        index: NodeIndex,
+    }
+
+    impl Index for MisindexedKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for MisindexedKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            self.keychain.node_count()
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            self.keychain.sign(msg).await
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            self.keychain.verify(msg, sgn, index)
+        }
+    }
+
     type TestMultiKeychain = DefaultMultiKeychain<TestKeychain>;
 
     fn test_multi_keychain(node_count: NodeCount, index: NodeIndex) -> TestMultiKeychain {
@@ -593,6 +995,184 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn signed_objects_move_between_tasks() {
+        let node_count: NodeCount = 7.into();
+        let keychain = test_multi_keychain(node_count, 0.into());
+        let signed = Signed::sign_with_index(test_message(), &keychain).await;
+        let partial = PartiallyMultisigned::sign(test_message(), &keychain).await;
+        let (signed, partial) = tokio::spawn(async move { (signed, partial) })
+            .await
+            .expect("task should not panic");
+        assert_eq!(
+            signed.into_unchecked().as_signable_strip_index(),
+            &test_message()
+        );
+        assert_eq!(partial.as_signable(), &test_message());
+    }
+
+    #[tokio::test]
+    async fn adds_unchecked_signatures() {
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0_usize..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let mut partial = PartiallyMultisigned::sign(test_message(), &keychains[0]).await;
+        for i in 1..5 {
+            let unchecked: UncheckedSigned<TestMessage, _> =
+                Signed::sign_with_index(test_message(), &keychains[i])
+                    .await
+                    .into_unchecked()
+                    .into();
+            partial = match partial.add_unchecked(unchecked.clone(), (i + 1).into(), &keychains[0])
+            {
+                Err(AddSignatureError::WrongSignature { partial, signer }) => {
+                    assert_eq!(signer, (i + 1).into());
+                    partial
+                }
+                _ => panic!("the signature is not of the claimed node"),
+            };
+            assert!(!partial.is_complete());
+            partial = partial
+                .add_unchecked(unchecked, i.into(), &keychains[0])
+                .expect("the signature is correct");
+        }
+        assert!(partial.is_complete());
+
+        let other = TestMessage {
+            msg: "Bye".as_bytes().to_vec(),
+        };
+        let unchecked = Signed::sign_with_index(other, &keychains[5])
+            .await
+            .into_unchecked()
+            .into();
+        assert!(matches!(
+            partial.add_unchecked(unchecked, 5.into(), &keychains[0]),
+            Err(AddSignatureError::DifferentSignable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn completeness_depends_on_threshold() {
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0_usize..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let msg = test_message().hash();
+        let mut partial = keychains[0].bootstrap_multi(&keychains[0].sign(&msg).await, 0.into());
+        for keychain in &keychains[1..5] {
+            partial = partial.add_signature(&keychain.sign(&msg).await, keychain.index());
+        }
+        assert!(keychains[0].is_complete(&msg, &partial));
+        assert!(!keychains[0].is_complete_with_threshold(&msg, &partial, 6.into()));
+        assert!(keychains[0].is_complete_with_threshold(&msg, &partial, 5.into()));
+    }
+
+    #[tokio::test]
+    async fn merges_independently_collected_signatures() {
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0_usize..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let msg = test_message();
+        let mut first = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+        let mut second = PartiallyMultisigned::sign(msg.clone(), &keychains[3]).await;
+        for i in 1..3 {
+            let signed = Signed::sign_with_index(msg.clone(), &keychains[i]).await;
+            first = first.add_signature(signed, &keychains[0]);
+        }
+        for i in 2..5 {
+            let signed = Signed::sign_with_index(msg.clone(), &keychains[i]).await;
+            second = second.add_signature(signed, &keychains[0]);
+        }
+        assert!(!first.is_complete());
+        assert!(!second.is_complete());
+
+        let other = PartiallyMultisigned::sign(
+            TestMessage {
+                msg: b"other".to_vec(),
+            },
+            &keychains[5],
+        )
+        .await;
+        let unchanged = first.clone().merge(other, &keychains[0]);
+        assert_eq!(unchanged.into_unchecked(), first.clone().into_unchecked());
+
+        // Signatures of 0..5, the signature of 2 is in both.
+        let merged = first.merge(second, &keychains[0]);
+        assert!(merged.is_complete());
+        assert_eq!(
+            merged.signers().elements().collect::<Vec<_>>(),
+            (0..5).map(NodeIndex).collect::<Vec<_>>()
+        );
+        assert_eq!(merged.into_unchecked().signature().item_count(), 5);
+    }
+
+    #[tokio::test]
+    async fn does_not_merge_incorrect_signatures() {
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0_usize..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let msg = test_message();
+        let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+        for i in 1..4 {
+            let signed = Signed::sign_with_index(msg.clone(), &keychains[i]).await;
+            partial = partial.add_signature(signed, &keychains[0]);
+        }
+        let forged_signature = keychains[4].sign(b"other").await;
+        let forged: PartiallyMultisigned<TestMessage, TestMultiKeychain> =
+            PartiallyMultisigned::Incomplete {
+                unchecked: UncheckedSigned {
+                    signable: msg.clone(),
+                    signature: keychains[0].bootstrap_multi(&forged_signature, NodeIndex(4)),
+                },
+            };
+        let unchanged = partial.clone().merge(forged, &keychains[0]);
+        assert_eq!(unchanged.signers(), partial.signers());
+
+        // The correct signature of the node can still be added.
+        let signed = Signed::sign_with_index(msg, &keychains[4]).await;
+        assert!(unchanged.add_signature(signed, &keychains[0]).is_complete());
+    }
+
+    #[tokio::test]
+    async fn checks_batches() {
+        let node_count: NodeCount = 4.into();
+        let keychains: Vec<_> = (0..node_count.0)
+            .map(|i| TestKeychain::new(node_count, i.into()))
+            .collect();
+        let mut items = Vec::new();
+        for keychain in &keychains {
+            items.push(
+                Signed::sign_with_index(test_message(), keychain)
+                    .await
+                    .into_unchecked(),
+            );
+        }
+        // A signature of node 0 claiming to be of node 1.
+        let mut forged = items[0].clone();
+        forged.signable.index = 1.into();
+        items.push(forged);
+
+        let results = UncheckedSigned::check_batch(items, &keychains[0]);
+        assert_eq!(
+            results.iter().map(Result::is_ok).collect::<Vec<_>>(),
+            vec![true, true, true, true, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_own_index() {
+        let keychain = TestKeychain::new(4.into(), 2.into());
+        assert!(keychain.verify_own_index().await);
+        let misindexed = MisindexedKeychain {
+            keychain,
+            index: 1.into(),
+        };
+        assert!(!misindexed.verify_own_index().await);
+    }
+
     #[tokio::test]
     async fn test_invalid_signatures() {
         let node_count: NodeCount = 1.into();
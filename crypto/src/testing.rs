@@ -0,0 +1,137 @@
+//! Keychains for tests, enabled with the `testing` feature, so that tests of applications do not
+//! have to implement their own. Their signatures are keyed hashes anyone knowing the seed can
+//! forge, so they must not be used outside of tests.
+
+use crate::{
+    Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature, SignatureSet,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+
+/// A signature of a [`TestKeychain`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct TestSignature {
+    tag: u64,
+}
+
+/// A multisignature of a [`TestKeychain`], complete once it counts enough correct signatures.
+pub type CountingMultisignature = SignatureSet<TestSignature>;
+
+/// A deterministic keychain of a committee generated from a seed, see [`committee`]. Keychains
+/// from the same seed verify each other's signatures, keychains from different seeds do not.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct TestKeychain {
+    seed: u64,
+    node_count: NodeCount,
+    index: NodeIndex,
+}
+
+impl TestKeychain {
+    /// The keychain of the node with the index in the committee of the given size.
+    pub fn new(seed: u64, node_count: NodeCount, index: NodeIndex) -> Self {
+        TestKeychain {
+            seed,
+            node_count,
+            index,
+        }
+    }
+
+    fn key(&self, index: NodeIndex) -> u64 {
+        mix(self.seed ^ mix(index.0 as u64 + 1))
+    }
+}
+
+/// The keychains of all the nodes of a committee of the given size, generated from the seed.
+pub fn committee(seed: u64, node_count: NodeCount) -> Vec<TestKeychain> {
+    (0..node_count.0)
+        .map(|index| TestKeychain::new(seed, node_count, NodeIndex(index)))
+        .collect()
+}
+
+// The finalizer of splitmix64.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn tag(key: u64, msg: &[u8]) -> u64 {
+    let tag = msg
+        .iter()
+        .fold(key, |tag, byte| mix(tag ^ u64::from(*byte)));
+    mix(tag ^ msg.len() as u64)
+}
+
+impl Index for TestKeychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+#[async_trait]
+impl Keychain for TestKeychain {
+    type Signature = TestSignature;
+
+    fn node_count(&self) -> NodeCount {
+        self.node_count
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        TestSignature {
+            tag: tag(self.key(self.index), msg),
+        }
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        index.0 < self.node_count.0 && sgn.tag == tag(self.key(index), msg)
+    }
+}
+
+impl MultiKeychain for TestKeychain {
+    type PartialMultisignature = CountingMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::with_size(self.node_count).add_signature(signature, index)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        partial.item_count() >= threshold.0
+            && partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{committee, TestKeychain};
+    use crate::{check_keybox_conformance, Keychain, NodeCount, NodeIndex};
+
+    #[tokio::test]
+    async fn committees_conform() {
+        for n_members in [1, 4, 10] {
+            let keychains = committee(7, NodeCount(n_members));
+            assert_eq!(check_keybox_conformance(&keychains).await, Ok(()));
+        }
+    }
+
+    #[tokio::test]
+    async fn seeds_separate_committees() {
+        let keychains = committee(7, NodeCount(4));
+        let other = TestKeychain::new(8, NodeCount(4), NodeIndex(0));
+        let signature = keychains[0].sign(b"message").await;
+
+        assert_eq!(keychains[0].sign(b"message").await, signature);
+        assert!(keychains[1].verify(b"message", &signature, NodeIndex(0)));
+        assert!(!keychains[1].verify(b"message", &signature, NodeIndex(1)));
+        assert!(!keychains[1].verify(b"other message", &signature, NodeIndex(0)));
+        assert!(!other.verify(b"message", &signature, NodeIndex(0)));
+    }
+}
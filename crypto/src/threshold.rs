@@ -0,0 +1,42 @@
+use crate::{Index, NodeCount, NodeIndex, NodeMap};
+use async_trait::async_trait;
+use codec::Codec;
+use std::{fmt::Debug, hash::Hash};
+
+/// Encryption to the whole committee, such that a ciphertext can only be decrypted by combining
+/// the decryption shares of [`ThresholdEncryption::threshold`] nodes, in the same way signatures
+/// are combined into multisignatures.
+///
+/// A typical implementation holds the public key of the committee, the public key shares of all
+/// the nodes and the secret key share of our node, with the threshold greater than the number of
+/// nodes that may be malicious, i.e. floor(N/3)+1, so that they cannot decrypt anything on their
+/// own, but cannot withhold their shares from the honest nodes either.
+#[async_trait]
+pub trait ThresholdEncryption: Index + Clone + Send + Sync + 'static {
+    type Ciphertext: Debug + Clone + Codec + Send + Sync + Eq + Hash + 'static;
+    type DecryptionShare: Debug + Clone + Codec + Send + Sync + Eq + 'static;
+
+    /// Returns the total number of nodes of the committee.
+    fn node_count(&self) -> NodeCount;
+    /// The number of shares needed for decrypting.
+    fn threshold(&self) -> NodeCount;
+    /// Encrypts the plaintext to the committee.
+    fn encrypt(&self, plaintext: &[u8]) -> Self::Ciphertext;
+    /// Our share of the decryption of the ciphertext.
+    async fn decryption_share(&self, ciphertext: &Self::Ciphertext) -> Self::DecryptionShare;
+    /// Verifies whether the share is the one of the node with `index` for the ciphertext.
+    /// Should always return false for indices outside the node range.
+    fn verify_share(
+        &self,
+        ciphertext: &Self::Ciphertext,
+        share: &Self::DecryptionShare,
+        index: NodeIndex,
+    ) -> bool;
+    /// Decrypts the ciphertext with verified shares of at least a threshold of nodes, `None` if
+    /// the shares are too few or the ciphertext is malformed.
+    fn combine(
+        &self,
+        ciphertext: &Self::Ciphertext,
+        shares: &NodeMap<Self::DecryptionShare>,
+    ) -> Option<Vec<u8>>;
+}
@@ -0,0 +1,573 @@
+use crate::{
+    Index, KeyEpoch, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+    SignatureSet,
+};
+use async_trait::async_trait;
+use codec::Encode;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Remembers the most recently verified signatures of single nodes, so that a message arriving
+/// several times, e.g. rebroadcast or sent in response to a request, is only verified once, see
+/// [`UncheckedSigned::check_cached`]. At most `capacity` signatures are kept, evicting the least
+/// recently used ones. The cache is shared between clones and should only be used with a single
+/// keychain. Incorrect signatures are never cached.
+///
+/// [`UncheckedSigned::check_cached`]: crate::UncheckedSigned::check_cached
+#[derive(Clone)]
+pub struct VerificationCache {
+    entries: Arc<Mutex<LruEntries>>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct LruEntries {
+    last_used: HashMap<Vec<u8>, u64>,
+    by_last_use: BTreeMap<u64, Vec<u8>>,
+    clock: u64,
+}
+
+impl LruEntries {
+    fn touch(&mut self, key: Vec<u8>) {
+        self.clock += 1;
+        if let Some(previous) = self.last_used.insert(key.clone(), self.clock) {
+            self.by_last_use.remove(&previous);
+        }
+        self.by_last_use.insert(self.clock, key);
+    }
+}
+
+impl VerificationCache {
+    pub fn new(capacity: usize) -> Self {
+        VerificationCache {
+            entries: Arc::new(Mutex::new(LruEntries::default())),
+            capacity,
+        }
+    }
+
+    /// The number of cached signatures.
+    pub fn len(&self) -> usize {
+        self.entries.lock().last_used.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn key<S: Encode>(hash: &[u8], index: NodeIndex, signature: &S) -> Vec<u8> {
+        (hash, index, signature).encode()
+    }
+
+    /// Whether the signature with the key was verified, marking it as recently used if so.
+    pub(crate) fn contains(&self, key: &[u8]) -> bool {
+        let mut entries = self.entries.lock();
+        if !entries.last_used.contains_key(key) {
+            return false;
+        }
+        entries.touch(key.to_vec());
+        true
+    }
+
+    /// Remembers that the signature with the key is correct.
+    pub(crate) fn insert(&self, key: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock();
+        entries.touch(key);
+        while entries.last_used.len() > self.capacity {
+            let oldest = match entries.by_last_use.pop_first() {
+                Some((_, oldest)) => oldest,
+                None => break,
+            };
+            entries.last_used.remove(&oldest);
+        }
+    }
+}
+
+// Caches are equal if they are clones of each other.
+impl PartialEq for VerificationCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.entries, &other.entries)
+    }
+}
+
+impl Eq for VerificationCache {}
+
+impl Hash for VerificationCache {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.entries).hash(state);
+    }
+}
+
+impl Debug for VerificationCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("VerificationCache")
+            .field("cached signatures", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// A keychain wrapper implementing a concatenation multisignature scheme, i.e. a partial
+/// multisignature is a set of signatures, complete once it contains more than 2N/3 of them.
+///
+/// Unlike a naive implementation, it remembers which signatures were already verified correctly,
+/// so checking the same (partial) multisignature again, e.g. a stored certificate or a partial
+/// multisignature after adding a signature, only verifies signatures it has not seen before.
+/// The verified signatures are kept in a [`VerificationCache`] of 10000 signatures, unless
+/// created [`with_capacity`], shared between clones of the wrapper and the
+/// keychains it returns for other key epochs.
+///
+/// [`with_capacity`]: CachingMultiKeychain::with_capacity
+///
+/// Threshold schemes do not need this -- their [`MultiKeychain::is_complete`] should verify
+/// the aggregated signature once instead of every constituent signature.
+#[derive(Clone)]
+pub struct CachingMultiKeychain<K: Keychain> {
+    keychain: K,
+    // The key epoch of the keychain, `None` unless returned by `at_epoch`.
+    epoch: Option<KeyEpoch>,
+    verified: VerificationCache,
+}
+
+const DEFAULT_CACHE_CAPACITY: usize = 10000;
+
+impl<K: Keychain> CachingMultiKeychain<K> {
+    pub fn new(keychain: K) -> Self {
+        Self::with_capacity(keychain, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Wraps the keychain, remembering at most `capacity` verified signatures.
+    pub fn with_capacity(keychain: K, capacity: usize) -> Self {
+        CachingMultiKeychain {
+            keychain,
+            epoch: None,
+            verified: VerificationCache::new(capacity),
+        }
+    }
+
+    /// The wrapped keychain.
+    pub fn inner(&self) -> &K {
+        &self.keychain
+    }
+
+    // A signature correct with the keys of one epoch need not be correct with the ones of another.
+    fn cache_key(&self, msg: &[u8], sgn: &K::Signature, index: NodeIndex) -> Vec<u8> {
+        (self.epoch, msg, sgn, index).encode()
+    }
+
+    /// Starts verifying the (partial) multisignature in chunks, see [`StreamingVerification`].
+    pub fn verify_streaming<'a>(
+        &'a self,
+        msg: &'a [u8],
+        partial: &'a SignatureSet<K::Signature>,
+    ) -> StreamingVerification<'a, K> {
+        StreamingVerification {
+            keychain: self,
+            msg,
+            enough_signatures: partial.item_count() >= self.node_count().quorum().0,
+            pending: partial.iter().collect(),
+        }
+    }
+}
+
+/// The state of a [`StreamingVerification`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerificationStatus {
+    /// The given number of signatures still has to be verified.
+    Pending(usize),
+    /// All signatures are correct and there are enough of them.
+    Complete,
+    /// Some signature is incorrect, or there are not enough signatures.
+    Invalid,
+}
+
+/// Verification of a multisignature of a [`CachingMultiKeychain`] performed a chunk of
+/// signatures at a time, so that checking multisignatures of huge committees can be
+/// interleaved with other work, or split into parts verified in parallel.
+///
+/// Verified signatures are cached as usual, so once the verification finishes,
+/// [`MultiKeychain::is_complete`] for the same multisignature returns without verifying
+/// anything.
+pub struct StreamingVerification<'a, K: Keychain> {
+    keychain: &'a CachingMultiKeychain<K>,
+    msg: &'a [u8],
+    enough_signatures: bool,
+    pending: Vec<(NodeIndex, &'a K::Signature)>,
+}
+
+impl<'a, K: Keychain> StreamingVerification<'a, K> {
+    pub fn status(&self) -> VerificationStatus {
+        match (self.enough_signatures, self.pending.len()) {
+            (false, _) => VerificationStatus::Invalid,
+            (true, 0) => VerificationStatus::Complete,
+            (true, pending) => VerificationStatus::Pending(pending),
+        }
+    }
+
+    /// Verifies at most `chunk_size` of the remaining signatures, stopping at the first
+    /// incorrect one.
+    pub fn verify_chunk(&mut self, chunk_size: usize) -> VerificationStatus {
+        if !self.enough_signatures {
+            return VerificationStatus::Invalid;
+        }
+        for _ in 0..chunk_size {
+            let (index, sgn) = match self.pending.last() {
+                Some(signature) => *signature,
+                None => break,
+            };
+            if !self.keychain.verify(self.msg, sgn, index) {
+                self.enough_signatures = false;
+                return VerificationStatus::Invalid;
+            }
+            self.pending.pop();
+        }
+        self.status()
+    }
+
+    /// Splits the remaining signatures into at most `parts` verifications, e.g. to be run on
+    /// separate threads. The multisignature is complete if and only if all parts are.
+    pub fn split(self, parts: usize) -> Vec<Self> {
+        let part_size = (self.pending.len() + parts.max(1) - 1) / parts.max(1);
+        if part_size == 0 {
+            return vec![self];
+        }
+        self.pending
+            .chunks(part_size)
+            .map(|pending| StreamingVerification {
+                keychain: self.keychain,
+                msg: self.msg,
+                enough_signatures: self.enough_signatures,
+                pending: pending.to_vec(),
+            })
+            .collect()
+    }
+}
+
+impl<K: Keychain + Debug> Debug for CachingMultiKeychain<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CachingMultiKeychain")
+            .field("keychain", &self.keychain)
+            .field("epoch", &self.epoch)
+            .field("verified", &self.verified)
+            .finish()
+    }
+}
+
+impl<K: Keychain> Index for CachingMultiKeychain<K> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+#[async_trait]
+impl<K: Keychain> Keychain for CachingMultiKeychain<K> {
+    type Signature = K::Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        self.keychain.sign(msg).await
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        let key = self.cache_key(msg, sgn, index);
+        if self.verified.contains(&key) {
+            return true;
+        }
+        if !self.keychain.verify(msg, sgn, index) {
+            return false;
+        }
+        self.verified.insert(key);
+        true
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        let keys: Vec<_> = items
+            .iter()
+            .map(|(msg, sgn, index)| self.cache_key(msg, sgn, *index))
+            .collect();
+        let mut results: Vec<_> = keys.iter().map(|key| self.verified.contains(key)).collect();
+        let unknown: Vec<_> = (0..items.len()).filter(|i| !results[*i]).collect();
+        if unknown.is_empty() {
+            return results;
+        }
+        let batch: Vec<_> = unknown.iter().map(|i| items[*i]).collect();
+        let verified_batch = self.keychain.verify_batch(&batch);
+        for (i, correct) in unknown.into_iter().zip(verified_batch) {
+            results[i] = correct;
+        }
+        for (key, correct) in keys.into_iter().zip(&results) {
+            if *correct {
+                self.verified.insert(key);
+            }
+        }
+        results
+    }
+
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.keychain.has_key(index)
+    }
+
+    // The cache is shared, its keys include the epoch.
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        CachingMultiKeychain {
+            keychain: self.keychain.at_epoch(epoch),
+            epoch: Some(epoch),
+            verified: self.verified.clone(),
+        }
+    }
+}
+
+impl<K: Keychain> MultiKeychain for CachingMultiKeychain<K> {
+    type PartialMultisignature = SignatureSet<K::Signature>;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::with_size(self.node_count()).add_signature(signature, index)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        if partial.item_count() < threshold.0 {
+            return false;
+        }
+        partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachingMultiKeychain, VerificationCache, VerificationStatus};
+    use crate::{
+        check_keybox_conformance, Index, Keychain, MultiKeychain, NodeCount, NodeIndex,
+        PartialMultisignature, Signed, UncheckedSigned,
+    };
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    #[derive(Clone, Debug)]
+    struct CountingKeychain {
+        count: NodeCount,
+        index: NodeIndex,
+        verifications: Arc<AtomicUsize>,
+    }
+
+    impl Index for CountingKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for CountingKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            self.count
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            self.verifications.fetch_add(1, Ordering::SeqCst);
+            index == sgn.index && msg == sgn.msg
+        }
+    }
+
+    fn keychains(
+        n_members: usize,
+    ) -> (
+        Vec<CachingMultiKeychain<CountingKeychain>>,
+        Arc<AtomicUsize>,
+    ) {
+        let verifications = Arc::new(AtomicUsize::new(0));
+        let keychains = (0..n_members)
+            .map(|i| {
+                CachingMultiKeychain::new(CountingKeychain {
+                    count: NodeCount(n_members),
+                    index: NodeIndex(i),
+                    verifications: verifications.clone(),
+                })
+            })
+            .collect();
+        (keychains, verifications)
+    }
+
+    #[tokio::test]
+    async fn conforms() {
+        let (keychains, _) = keychains(7);
+        assert_eq!(check_keybox_conformance(&keychains).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn rechecking_does_not_verify_again() {
+        let msg = b"message";
+        let (keychains, verifications) = keychains(4);
+        let keychain = &keychains[0];
+        let mut partial = keychain.bootstrap_multi(&keychains[1].sign(msg).await, NodeIndex(1));
+        for other in &keychains[2..] {
+            partial = partial.add_signature(&other.sign(msg).await, other.index());
+        }
+
+        assert!(keychain.is_complete(msg, &partial));
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+        assert!(keychain.is_complete(msg, &partial));
+        assert!(keychain.clone().is_complete(msg, &partial));
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn batches_skip_cached_signatures() {
+        let msg: &[u8] = b"message";
+        let (keychains, verifications) = keychains(4);
+        let keychain = &keychains[0];
+        let first = keychains[1].sign(msg).await;
+        let second = keychains[2].sign(msg).await;
+        assert!(keychain.verify(msg, &first, NodeIndex(1)));
+        assert_eq!(verifications.load(Ordering::SeqCst), 1);
+
+        let batch = [
+            (msg, &first, NodeIndex(1)),
+            (msg, &second, NodeIndex(2)),
+            (msg, &second, NodeIndex(3)),
+        ];
+        assert_eq!(keychain.verify_batch(&batch), vec![true, true, false]);
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+        assert_eq!(keychain.verify_batch(&batch), vec![true, true, false]);
+        assert_eq!(verifications.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn verifies_in_chunks() {
+        let msg = b"message";
+        let (keychains, verifications) = keychains(7);
+        let keychain = &keychains[0];
+        let mut partial = keychain.bootstrap_multi(&keychains[1].sign(msg).await, NodeIndex(1));
+        for other in &keychains[2..6] {
+            partial = partial.add_signature(&other.sign(msg).await, other.index());
+        }
+
+        let mut verification = keychain.verify_streaming(msg, &partial);
+        assert_eq!(verification.status(), VerificationStatus::Pending(5));
+        assert_eq!(verification.verify_chunk(2), VerificationStatus::Pending(3));
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+        let parts = verification.split(2);
+        assert_eq!(parts.len(), 2);
+        let statuses: Vec<_> = parts
+            .into_iter()
+            .map(|mut part| part.verify_chunk(usize::MAX))
+            .collect();
+        assert_eq!(statuses, vec![VerificationStatus::Complete; 2]);
+        assert!(keychain.is_complete(msg, &partial));
+        assert_eq!(verifications.load(Ordering::SeqCst), 5);
+
+        let forged = partial.add_signature(&keychains[6].sign(b"other").await, NodeIndex(6));
+        let mut verification = keychain.verify_streaming(msg, &forged);
+        assert_eq!(verification.verify_chunk(10), VerificationStatus::Invalid);
+        let too_few = keychain.bootstrap_multi(&keychains[1].sign(msg).await, NodeIndex(1));
+        assert_eq!(
+            keychain.verify_streaming(msg, &too_few).status(),
+            VerificationStatus::Invalid
+        );
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_signatures() {
+        let (keychains, verifications) = keychains(4);
+        let keychain = keychains[0].inner();
+        let cache = VerificationCache::new(2);
+        let mut signed = Vec::new();
+        for msg in [b"first", b"other", b"third"] {
+            signed.push(
+                Signed::sign_with_index(msg.to_vec(), keychains[1].inner())
+                    .await
+                    .into_unchecked(),
+            );
+        }
+        let check = |i: usize| {
+            signed[i]
+                .clone()
+                .check_cached(keychain, &cache)
+                .expect("the signature is correct");
+        };
+
+        check(0);
+        check(1);
+        check(0);
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+        // Evicts the second message, the first one was used more recently.
+        check(2);
+        check(0);
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.len(), 2);
+        let results = UncheckedSigned::check_batch_cached(signed.clone(), keychain, &cache);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(verifications.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_incorrect_signatures() {
+        let (keychains, verifications) = keychains(4);
+        let signature = keychains[1].sign(b"message").await;
+        assert!(!keychains[0].verify(b"other message", &signature, NodeIndex(1)));
+        assert!(!keychains[0].verify(b"other message", &signature, NodeIndex(1)));
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn caches_signatures_per_epoch() {
+        let (keychains, verifications) = keychains(4);
+        let signature = keychains[1].sign(b"message").await;
+        let epoch_one = keychains[0].at_epoch(1);
+        assert!(keychains[0].verify(b"message", &signature, NodeIndex(1)));
+        assert!(epoch_one.verify(b"message", &signature, NodeIndex(1)));
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+        assert!(keychains[0]
+            .at_epoch(1)
+            .verify(b"message", &signature, NodeIndex(1)));
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn bounds_the_cache() {
+        let (keychains, verifications) = keychains(4);
+        let keychain = CachingMultiKeychain::with_capacity(keychains[0].inner().clone(), 1);
+        let first = keychains[1].sign(b"first").await;
+        let second = keychains[1].sign(b"second").await;
+        assert!(keychain.verify(b"first", &first, NodeIndex(1)));
+        assert!(keychain.verify(b"second", &second, NodeIndex(1)));
+        assert!(keychain.verify(b"first", &first, NodeIndex(1)));
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+    }
+}
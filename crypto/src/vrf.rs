@@ -0,0 +1,20 @@
+use crate::NodeIndex;
+use async_trait::async_trait;
+
+/// A verifiable random function: every node has a single valid proof for every input, which
+/// nobody else can compute before the node reveals it, and which everybody can check. The
+/// output of the function is the hash of the proof.
+///
+/// Signatures make such proofs only if every node has exactly one valid signature of every
+/// message, e.g. BLS signatures, see [`bls::Keychain`]. With signatures that can be randomized
+/// the creator could choose among many outputs.
+///
+/// [`bls::Keychain`]: crate::bls::Keychain
+#[async_trait]
+pub trait Vrf: Send + Sync {
+    /// The proof of our node for the input.
+    async fn prove(&self, input: &[u8]) -> Vec<u8>;
+
+    /// Whether the proof is the one of the node with the given index for the input.
+    fn verify(&self, input: &[u8], proof: &[u8], index: NodeIndex) -> bool;
+}
@@ -0,0 +1,254 @@
+use crate::{
+    Index, KeyEpoch, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// A multikeychain of a committee whose nodes carry weights, e.g. their stakes. Multisignatures
+/// are complete when their signers carry more than 2/3 of the total weight, or a custom
+/// threshold, rather than when more than 2/3 of the nodes signed them.
+///
+/// Only [`MultiKeychain::is_complete`] is weighted, [`MultiKeychain::is_complete_with_threshold`]
+/// still counts the signers, as callers passing a threshold expect. Sums of weights are `u128`,
+/// so that they cannot overflow for any weights.
+#[derive(Clone)]
+pub struct WeightedMultiKeychain<MK: MultiKeychain> {
+    keychain: MK,
+    weights: Arc<Vec<u64>>,
+    threshold: u128,
+}
+
+impl<MK: MultiKeychain> WeightedMultiKeychain<MK> {
+    /// Weighs the nodes of the keychain, the node with index `i` carries `weights[i]`, nodes
+    /// without a weight carry none.
+    pub fn new(keychain: MK, weights: Vec<u64>) -> Self {
+        let total = sum(weights.iter().copied());
+        WeightedMultiKeychain {
+            keychain,
+            weights: Arc::new(weights),
+            threshold: total * 2 / 3 + 1,
+        }
+    }
+
+    /// Makes multisignatures complete when their signers carry at least `threshold` weight.
+    pub fn with_threshold(mut self, threshold: u128) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The weight of the node.
+    pub fn weight(&self, index: NodeIndex) -> u64 {
+        self.weights.get(index.0).copied().unwrap_or(0)
+    }
+
+    /// The weight of all the nodes.
+    pub fn total_weight(&self) -> u128 {
+        sum(self.weights.iter().copied())
+    }
+
+    /// The weight the signers of a complete multisignature carry at least.
+    pub fn threshold(&self) -> u128 {
+        self.threshold
+    }
+
+    /// The weight carried by the signers of the multisignature, whether the signatures are
+    /// correct or not.
+    pub fn signed_weight(&self, partial: &MK::PartialMultisignature) -> u128 {
+        sum(partial.signers().elements().map(|index| self.weight(index)))
+    }
+
+    /// The weighted keychain itself.
+    pub fn inner(&self) -> &MK {
+        &self.keychain
+    }
+}
+
+fn sum(weights: impl Iterator<Item = u64>) -> u128 {
+    weights.map(u128::from).sum()
+}
+
+impl<MK: MultiKeychain> Index for WeightedMultiKeychain<MK> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+#[async_trait]
+impl<MK: MultiKeychain> Keychain for WeightedMultiKeychain<MK> {
+    type Signature = MK::Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        self.keychain.sign(msg).await
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.keychain.verify(msg, sgn, index)
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        self.keychain.verify_batch(items)
+    }
+
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.keychain.has_key(index)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        WeightedMultiKeychain {
+            keychain: self.keychain.at_epoch(epoch),
+            weights: self.weights.clone(),
+            threshold: self.threshold,
+        }
+    }
+}
+
+impl<MK: MultiKeychain> MultiKeychain for WeightedMultiKeychain<MK> {
+    type PartialMultisignature = MK::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain.bootstrap_multi(signature, index)
+    }
+
+    /// Checks if the signers carry enough weight and all their signatures are correct.
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        let signers = NodeCount(partial.signers().len());
+        self.signed_weight(partial) >= self.threshold
+            && self
+                .keychain
+                .is_complete_with_threshold(msg, partial, signers)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        self.keychain
+            .is_complete_with_threshold(msg, partial, threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeightedMultiKeychain;
+    use crate::{
+        Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature, SignatureSet,
+    };
+    use async_trait::async_trait;
+    use codec::{Decode, Encode};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+    struct TestSignature {
+        msg: Vec<u8>,
+        index: NodeIndex,
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestKeychain {
+        index: NodeIndex,
+    }
+
+    impl Index for TestKeychain {
+        fn index(&self) -> NodeIndex {
+            self.index
+        }
+    }
+
+    #[async_trait]
+    impl Keychain for TestKeychain {
+        type Signature = TestSignature;
+
+        fn node_count(&self) -> NodeCount {
+            NodeCount(4)
+        }
+
+        async fn sign(&self, msg: &[u8]) -> Self::Signature {
+            TestSignature {
+                msg: msg.to_vec(),
+                index: self.index,
+            }
+        }
+
+        fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+            index == sgn.index && msg == sgn.msg
+        }
+    }
+
+    impl MultiKeychain for TestKeychain {
+        type PartialMultisignature = SignatureSet<TestSignature>;
+
+        fn bootstrap_multi(
+            &self,
+            signature: &Self::Signature,
+            index: NodeIndex,
+        ) -> Self::PartialMultisignature {
+            SignatureSet::with_size(self.node_count()).add_signature(signature, index)
+        }
+
+        fn is_complete_with_threshold(
+            &self,
+            msg: &[u8],
+            partial: &Self::PartialMultisignature,
+            threshold: NodeCount,
+        ) -> bool {
+            partial.iter().count() >= threshold.0
+                && partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+        }
+    }
+
+    #[tokio::test]
+    async fn completes_with_enough_weight() {
+        let msg = b"message";
+        let keychains: Vec<_> = (0..4)
+            .map(|ix| {
+                WeightedMultiKeychain::new(
+                    TestKeychain {
+                        index: NodeIndex(ix),
+                    },
+                    vec![7, 1, 1, 1],
+                )
+            })
+            .collect();
+        assert_eq!(keychains[0].total_weight(), 10);
+        assert_eq!(keychains[0].threshold(), 7);
+
+        let signature = keychains[0].sign(msg).await;
+        let heavy = keychains[0].bootstrap_multi(&signature, NodeIndex(0));
+        assert!(keychains[1].is_complete(msg, &heavy));
+        assert!(!keychains[1].is_complete(b"other message", &heavy));
+
+        let mut light = keychains[1].bootstrap_multi(&keychains[1].sign(msg).await, NodeIndex(1));
+        for keychain in &keychains[2..] {
+            light = light.add_signature(&keychain.sign(msg).await, keychain.index());
+        }
+        assert_eq!(keychains[0].signed_weight(&light), 3);
+        assert!(!keychains[0].is_complete(msg, &light));
+        assert!(keychains[0].is_complete_with_threshold(msg, &light, NodeCount(3)));
+        assert!(keychains[0]
+            .clone()
+            .with_threshold(3)
+            .is_complete(msg, &light));
+    }
+
+    #[test]
+    fn sums_huge_weights_without_overflow() {
+        let keychain = WeightedMultiKeychain::new(
+            TestKeychain {
+                index: NodeIndex(0),
+            },
+            vec![u64::MAX; 4],
+        );
+        let max = u128::from(u64::MAX);
+        assert_eq!(keychain.total_weight(), 4 * max);
+        assert_eq!(keychain.threshold(), 8 * max / 3 + 1);
+    }
+}
@@ -10,7 +10,7 @@ use futures::{channel::oneshot, StreamExt};
 use log::{debug, error, info};
 use time::{macros::format_description, OffsetDateTime};
 
-use aleph_bft::{run_session, NodeIndex, Terminator};
+use aleph_bft::{run_session, NodeIndex, SessionId, Terminator};
 use aleph_bft_mock::{FinalizationHandler, Keychain, Loader, Saver, Spawner};
 use chain::{run_blockchain, Block, BlockNum, ChainConfig};
 use data::{Data, DataProvider, DataStore};
@@ -128,7 +128,11 @@ async fn main() {
     let member_terminator = terminator.add_offspring_connection("AlephBFT-member");
     let member_handle = tokio::spawn(async move {
         let keychain = Keychain::new(args.n_members.into(), args.my_id.into());
-        let config = aleph_bft::default_config(args.n_members.into(), args.my_id.into(), 0);
+        let config = aleph_bft::default_config(
+            args.n_members.into(),
+            args.my_id.into(),
+            SessionId::from_number(0),
+        );
         let backup_loader = Loader::new(vec![]);
         let backup_saver = Saver::new();
         let local_io = aleph_bft::LocalIO::new(
@@ -175,7 +179,10 @@ async fn main() {
     terminator.get_exit().await.expect("should receive");
     terminator.terminate_sync().await;
 
-    member_handle.await.unwrap();
+    member_handle
+        .await
+        .unwrap()
+        .expect("session should end cleanly");
     chain_handle.await.unwrap();
     network_handle.await.unwrap();
 }
@@ -0,0 +1,91 @@
+use aleph_bft_types::{
+    DataProvider as DataProviderT, FinalizationHandler as FinalizationHandlerT, NodeIndex,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::channel::mpsc::unbounded;
+use log::error;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
+type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
+
+/// A data item of a configurable size.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct Item {
+    pub creator: NodeIndex,
+    pub number: u64,
+    pub padding: Vec<u8>,
+}
+
+/// When each item was injected, shared by all the nodes, as they run in a single process.
+pub type InjectionTimes = Arc<Mutex<HashMap<(NodeIndex, u64), Instant>>>;
+
+/// Provides the items injected so far, never waiting for new ones.
+pub struct DataProvider {
+    items: Receiver<Item>,
+    // An item that did not fit into the previous unit.
+    postponed: Option<Item>,
+}
+
+impl DataProvider {
+    pub fn new() -> (Self, Sender<Item>) {
+        let (tx, rx) = unbounded();
+        (
+            Self {
+                items: rx,
+                postponed: None,
+            },
+            tx,
+        )
+    }
+}
+
+#[async_trait]
+impl DataProviderT<Item> for DataProvider {
+    async fn get_data(&mut self) -> Option<Item> {
+        self.postponed
+            .take()
+            .or_else(|| self.items.try_next().ok().flatten())
+    }
+
+    async fn get_data_items(&mut self, max_items: usize, max_bytes: usize) -> Vec<Item> {
+        let mut items = Vec::new();
+        let mut bytes = 0;
+        while items.len() < max_items {
+            let item = match self.get_data().await {
+                Some(item) => item,
+                None => break,
+            };
+            bytes += item.encoded_size();
+            if bytes > max_bytes && !items.is_empty() {
+                self.postponed = Some(item);
+                break;
+            }
+            items.push(item);
+        }
+        items
+    }
+}
+
+/// Reports every finalized item together with the time of finalization.
+#[derive(Clone)]
+pub struct FinalizationHandler {
+    node: NodeIndex,
+    tx: Sender<(NodeIndex, Item, Instant)>,
+}
+
+impl FinalizationHandlerT<Item> for FinalizationHandler {
+    fn data_finalized(&mut self, item: Item) {
+        if let Err(e) = self.tx.unbounded_send((self.node, item, Instant::now())) {
+            error!(target: "finalization-handler", "Error when sending data from FinalizationHandler {:?}.", e);
+        }
+    }
+}
+
+impl FinalizationHandler {
+    pub fn new(node: NodeIndex, tx: Sender<(NodeIndex, Item, Instant)>) -> Self {
+        Self { node, tx }
+    }
+}
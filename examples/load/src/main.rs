@@ -0,0 +1,214 @@
+mod dataio;
+
+use aleph_bft::{
+    default_config, run_session, FlatteningFinalizationHandler, LocalIO, MultiDataProvider,
+    NodeCount, NodeIndex, PayloadLimits, SessionId, Terminator,
+};
+use aleph_bft_mock::{Keychain, Loader, Router, Saver, Spawner};
+use clap::Parser;
+use dataio::{DataProvider, FinalizationHandler, InjectionTimes, Item};
+use futures::{channel::oneshot, StreamExt};
+use log::info;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::{interval, sleep, Instant};
+
+/// Runs a committee in a single process, injecting data at a fixed rate, and reports the
+/// finalization throughput and latency achieved.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Number of nodes in the committee
+    #[clap(default_value = "4", long, value_parser)]
+    nodes: usize,
+
+    /// Number of items injected per second at every node
+    #[clap(default_value = "100", long, value_parser)]
+    rate: u64,
+
+    /// Size of the payload of every item, in bytes
+    #[clap(default_value = "32", long, value_parser)]
+    payload_size: usize,
+
+    /// Maximal number of items placed in a single unit
+    #[clap(default_value = "1", long, value_parser)]
+    items_per_unit: usize,
+
+    /// Maximal number of bytes of items placed in a single unit
+    #[clap(default_value = "1000000", long, value_parser)]
+    bytes_per_unit: usize,
+
+    /// Delay between creating consecutive units, in milliseconds
+    #[clap(default_value = "500", long, value_parser)]
+    unit_creation_delay: u64,
+
+    /// Fraction of messages delivered by the network
+    #[clap(default_value = "1.0", long, value_parser)]
+    reliability: f64,
+
+    /// How long to inject data for, in seconds
+    #[clap(default_value = "30", long, value_parser)]
+    duration: u64,
+}
+
+struct Report {
+    finalized: HashMap<NodeIndex, usize>,
+    latencies: Vec<Duration>,
+}
+
+impl Report {
+    fn percentile(&self, percent: usize) -> Duration {
+        let index = (self.latencies.len() - 1) * percent / 100;
+        self.latencies[index]
+    }
+
+    fn print(mut self, n_members: usize, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let slowest = (0..n_members)
+            .map(|node| self.finalized.get(&node.into()).copied().unwrap_or(0))
+            .min()
+            .unwrap_or(0);
+        println!(
+            "Finalized items per second (slowest node): {:.1}",
+            slowest as f64 / seconds
+        );
+        if self.latencies.is_empty() {
+            println!("No items finalized, try running for longer.");
+            return;
+        }
+        self.latencies.sort();
+        println!(
+            "Latency: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            self.percentile(50),
+            self.percentile(90),
+            self.percentile(99),
+            self.percentile(100),
+        );
+    }
+}
+
+async fn inject(
+    node: NodeIndex,
+    rate: u64,
+    payload_size: usize,
+    items: futures::channel::mpsc::UnboundedSender<Item>,
+    injected: InjectionTimes,
+) {
+    let mut ticker = interval(Duration::from_secs(1) / rate.max(1) as u32);
+    for number in 0.. {
+        ticker.tick().await;
+        injected
+            .lock()
+            .insert((node, number), Instant::now().into_std());
+        let item = Item {
+            creator: node,
+            number,
+            padding: vec![0; payload_size],
+        };
+        if items.unbounded_send(item).is_err() {
+            break;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Warn)
+        .parse_default_env()
+        .init();
+
+    let Args {
+        nodes,
+        rate,
+        payload_size,
+        items_per_unit,
+        bytes_per_unit,
+        unit_creation_delay,
+        reliability,
+        duration,
+    } = Args::parse();
+    let n_members: NodeCount = nodes.into();
+    let limits = PayloadLimits {
+        max_items: items_per_unit,
+        max_bytes: bytes_per_unit,
+    };
+
+    let (router, networks) = Router::new(n_members, reliability);
+    tokio::spawn(router);
+
+    let injected = InjectionTimes::default();
+    let (finalized_tx, mut finalized_rx) = futures::channel::mpsc::unbounded();
+    let mut injectors = Vec::new();
+    let mut exits = Vec::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let id = network.index();
+        let (data_provider, items) = DataProvider::new();
+        injectors.push(tokio::spawn(inject(
+            id,
+            rate,
+            payload_size,
+            items,
+            injected.clone(),
+        )));
+        let local_io = LocalIO::new(
+            MultiDataProvider::new(data_provider, limits),
+            FlatteningFinalizationHandler::new(FinalizationHandler::new(id, finalized_tx.clone())),
+            Saver::new(),
+            Loader::new(Vec::new()),
+        );
+        let mut config = default_config(n_members, id, SessionId::from_number(0));
+        config.delay_config.unit_creation_delay =
+            Arc::new(move |_| Duration::from_millis(unit_creation_delay));
+        let (exit_tx, exit_rx) = oneshot::channel();
+        exits.push(exit_tx);
+        members.push(tokio::spawn(async move {
+            let keychain = Keychain::new(n_members, id);
+            run_session(
+                config,
+                local_io,
+                network,
+                keychain,
+                Spawner {},
+                Terminator::create_root(exit_rx, "AlephBFT-member"),
+            )
+            .await
+        }));
+    }
+    drop(finalized_tx);
+
+    info!("Injecting data for {} seconds.", duration);
+    let start = Instant::now();
+    let end = sleep(Duration::from_secs(duration));
+    futures::pin_mut!(end);
+    let mut report = Report {
+        finalized: HashMap::new(),
+        latencies: Vec::new(),
+    };
+    loop {
+        tokio::select! {
+            finalized = finalized_rx.next() => match finalized {
+                Some((node, item, finalized_at)) => {
+                    *report.finalized.entry(node).or_default() += 1;
+                    if let Some(injected_at) = injected.lock().get(&(item.creator, item.number)) {
+                        report.latencies.push(finalized_at.duration_since(*injected_at));
+                    }
+                }
+                None => break,
+            },
+            _ = &mut end => break,
+        }
+    }
+    let elapsed = start.elapsed();
+
+    for injector in injectors {
+        injector.abort();
+    }
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for member in members {
+        let _ = member.await;
+    }
+    report.print(nodes, elapsed);
+}
@@ -1,7 +1,7 @@
 mod dataio;
 mod network;
 
-use aleph_bft::{run_session, NodeIndex, Terminator};
+use aleph_bft::{run_session, NodeIndex, SessionId, Terminator};
 use aleph_bft_mock::{Keychain, Spawner};
 use clap::Parser;
 use dataio::{Data, DataProvider, FinalizationHandler};
@@ -116,7 +116,7 @@ async fn main() {
     let member_terminator = Terminator::create_root(exit_rx, "AlephBFT-member");
     let member_handle = tokio::spawn(async move {
         let keychain = Keychain::new(n_members, id);
-        let config = aleph_bft::default_config(n_members, id, 0);
+        let config = aleph_bft::default_config(n_members, id, SessionId::from_number(0));
         run_session(
             config,
             local_io,
@@ -166,5 +166,8 @@ async fn main() {
     }
 
     exit_tx.send(()).expect("should send");
-    member_handle.await.unwrap();
+    member_handle
+        .await
+        .unwrap()
+        .expect("session should end cleanly");
 }
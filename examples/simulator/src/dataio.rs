@@ -0,0 +1,80 @@
+use aleph_bft_types::{
+    DataProvider as DataProviderT, FinalizationHandler as FinalizationHandlerT, NodeIndex,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use log::error;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
+
+/// A data item identifying the unit it was created for.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct Item {
+    pub creator: NodeIndex,
+    pub number: u64,
+}
+
+/// When each item was created, shared by all the nodes, as they run in a single process.
+pub type CreationTimes = Arc<Mutex<HashMap<(NodeIndex, u64), Instant>>>;
+
+/// Provides a new item for every unit. The numbering is kept across crashes, so that items
+/// created after recovering from the backup are different from the ones created before.
+pub struct DataProvider {
+    node: NodeIndex,
+    next_number: Arc<AtomicU64>,
+    created: CreationTimes,
+}
+
+impl DataProvider {
+    pub fn new(node: NodeIndex, next_number: Arc<AtomicU64>, created: CreationTimes) -> Self {
+        DataProvider {
+            node,
+            next_number,
+            created,
+        }
+    }
+}
+
+#[async_trait]
+impl DataProviderT<Item> for DataProvider {
+    async fn get_data(&mut self) -> Option<Item> {
+        let number = self.next_number.fetch_add(1, Ordering::SeqCst);
+        self.created
+            .lock()
+            .insert((self.node, number), Instant::now());
+        Some(Item {
+            creator: self.node,
+            number,
+        })
+    }
+}
+
+/// Reports every finalized item together with the node finalizing it and the time.
+#[derive(Clone)]
+pub struct FinalizationHandler {
+    node: NodeIndex,
+    tx: Sender<(NodeIndex, Item, Instant)>,
+}
+
+impl FinalizationHandler {
+    pub fn new(node: NodeIndex, tx: Sender<(NodeIndex, Item, Instant)>) -> Self {
+        Self { node, tx }
+    }
+}
+
+impl FinalizationHandlerT<Item> for FinalizationHandler {
+    fn data_finalized(&mut self, item: Item) {
+        if let Err(e) = self.tx.unbounded_send((self.node, item, Instant::now())) {
+            error!(target: "finalization-handler", "Error when sending data from FinalizationHandler {:?}.", e);
+        }
+    }
+}
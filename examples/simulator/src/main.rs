@@ -0,0 +1,249 @@
+mod dataio;
+mod network;
+mod scenario;
+
+use aleph_bft::{
+    default_config, run_session, Error, LocalIO, NodeCount, NodeIndex, SessionId, Terminator,
+};
+use aleph_bft_mock::{
+    Hasher64, Keychain, Loader, PartialMultisignature, Saver, Signature, Spawner,
+};
+use clap::Parser;
+use dataio::{CreationTimes, DataProvider, FinalizationHandler, Item};
+use futures::{
+    channel::{mpsc::UnboundedSender, oneshot},
+    StreamExt,
+};
+use log::info;
+use network::Hub;
+use parking_lot::Mutex;
+use scenario::Scenario;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    process::exit,
+    sync::{atomic::AtomicU64, Arc},
+    time::Duration,
+};
+use tokio::{
+    task::JoinHandle,
+    time::{sleep_until, Instant},
+};
+
+type NetworkData = aleph_bft::NetworkData<Hasher64, Item, Signature, PartialMultisignature>;
+
+/// Runs a committee in a single process over a simulated network, following the latencies,
+/// crashes and partitions of a scenario file, and reports the liveness and finalization latency
+/// of the nodes.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the TOML file describing the scenario
+    #[clap(value_parser)]
+    scenario: PathBuf,
+}
+
+/// Everything a node keeps between its crash and recovery.
+struct NodeState {
+    backup: Arc<Mutex<Vec<u8>>>,
+    next_number: Arc<AtomicU64>,
+    running: Option<(oneshot::Sender<()>, JoinHandle<Result<(), Error>>)>,
+}
+
+struct Simulation {
+    scenario: Arc<Scenario>,
+    hub: Hub<NetworkData>,
+    created: CreationTimes,
+    finalized_tx: UnboundedSender<(NodeIndex, Item, std::time::Instant)>,
+    nodes: Vec<NodeState>,
+}
+
+impl Simulation {
+    fn start(&mut self, node: usize) {
+        if self.nodes[node].running.is_some() {
+            return;
+        }
+        let n_members = NodeCount(self.scenario.nodes);
+        let id = NodeIndex(node);
+        let state = &mut self.nodes[node];
+        let local_io = LocalIO::new(
+            DataProvider::new(id, state.next_number.clone(), self.created.clone()),
+            FinalizationHandler::new(id, self.finalized_tx.clone()),
+            Saver::from(state.backup.clone()),
+            Loader::new(state.backup.lock().clone()),
+        );
+        let mut config = default_config(n_members, id, SessionId::from_number(0));
+        let unit_creation_delay = self.scenario.unit_creation_delay;
+        config.delay_config.unit_creation_delay =
+            Arc::new(move |_| Duration::from_millis(unit_creation_delay));
+        let network = self.hub.connect(id);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            run_session(
+                config,
+                local_io,
+                network,
+                Keychain::new(n_members, id),
+                Spawner {},
+                Terminator::create_root(exit_rx, "AlephBFT-member"),
+            )
+            .await
+        });
+        state.running = Some((exit_tx, handle));
+    }
+
+    async fn stop(&mut self, node: usize) {
+        self.hub.disconnect(NodeIndex(node));
+        if let Some((exit, handle)) = self.nodes[node].running.take() {
+            let _ = exit.send(());
+            let _ = handle.await;
+        }
+    }
+}
+
+enum Event {
+    Crash(usize),
+    Recover(usize),
+}
+
+#[derive(Default)]
+struct Report {
+    finalized: HashMap<NodeIndex, Vec<Duration>>,
+    latencies: Vec<Duration>,
+}
+
+impl Report {
+    fn percentile(&self, percent: usize) -> Duration {
+        let index = (self.latencies.len() - 1) * percent / 100;
+        self.latencies[index]
+    }
+
+    fn print(mut self, scenario: &Scenario) {
+        println!("node  finalized  longest stall");
+        for node in 0..scenario.nodes {
+            let times = self
+                .finalized
+                .get(&NodeIndex(node))
+                .cloned()
+                .unwrap_or_default();
+            // The longest time without finalizing anything, including the start and the end.
+            let mut previous = Duration::ZERO;
+            let mut stall = Duration::ZERO;
+            for time in times.iter().copied().chain([scenario.duration()]) {
+                stall = stall.max(time.saturating_sub(previous));
+                previous = time;
+            }
+            println!("{:>4}  {:>9}  {:?}", node, times.len(), stall);
+        }
+        if self.latencies.is_empty() {
+            println!("No items finalized, try running for longer.");
+            return;
+        }
+        self.latencies.sort();
+        println!(
+            "Latency: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            self.percentile(50),
+            self.percentile(90),
+            self.percentile(99),
+            self.percentile(100),
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Warn)
+        .parse_default_env()
+        .init();
+
+    let Args { scenario } = Args::parse();
+    let scenario: Scenario = match fs::read_to_string(&scenario)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("Could not read the scenario {}: {}", scenario.display(), e);
+            exit(1);
+        }
+    };
+    if let Err(e) = scenario.validate() {
+        eprintln!("Invalid scenario: {}", e);
+        exit(1);
+    }
+    let scenario = Arc::new(scenario);
+
+    let start = Instant::now();
+    let (finalized_tx, mut finalized_rx) = futures::channel::mpsc::unbounded();
+    let mut simulation = Simulation {
+        scenario: scenario.clone(),
+        hub: Hub::new(scenario.clone(), start.into_std()),
+        created: CreationTimes::default(),
+        finalized_tx,
+        nodes: (0..scenario.nodes)
+            .map(|_| NodeState {
+                backup: Arc::new(Mutex::new(Vec::new())),
+                next_number: Arc::new(AtomicU64::new(0)),
+                running: None,
+            })
+            .collect(),
+    };
+    for node in 0..scenario.nodes {
+        simulation.start(node);
+    }
+
+    let mut events: Vec<_> = scenario
+        .crash_schedule()
+        .into_iter()
+        .flat_map(|(node, at, recover)| {
+            let recovery = recover.map(|recover| (recover, Event::Recover(node)));
+            [(at, Event::Crash(node))].into_iter().chain(recovery)
+        })
+        .collect();
+    events.sort_by_key(|(at, _)| *at);
+    let mut events = events.into_iter().peekable();
+
+    info!("Running the scenario for {:?}.", scenario.duration());
+    let end = start + scenario.duration();
+    let mut report = Report::default();
+    loop {
+        let next_event = match events.peek() {
+            Some((at, _)) if start + *at < end => start + *at,
+            _ => end,
+        };
+        tokio::select! {
+            finalized = finalized_rx.next() => {
+                if let Some((node, item, finalized_at)) = finalized {
+                    report
+                        .finalized
+                        .entry(node)
+                        .or_default()
+                        .push(finalized_at.duration_since(start.into_std()));
+                    if let Some(created_at) =
+                        simulation.created.lock().get(&(item.creator, item.number))
+                    {
+                        report.latencies.push(finalized_at.duration_since(*created_at));
+                    }
+                }
+            }
+            _ = sleep_until(next_event) => match events.next() {
+                Some((_, Event::Crash(node))) if next_event < end => {
+                    info!("Crashing node {}.", node);
+                    simulation.stop(node).await;
+                }
+                Some((_, Event::Recover(node))) if next_event < end => {
+                    info!("Recovering node {}.", node);
+                    simulation.start(node);
+                }
+                _ => break,
+            },
+        }
+    }
+
+    for node in 0..scenario.nodes {
+        simulation.stop(node).await;
+    }
+    report.print(&scenario);
+}
@@ -0,0 +1,84 @@
+use crate::scenario::Scenario;
+use aleph_bft_mock::Network;
+use aleph_bft_types::NodeIndex;
+use futures::{channel::mpsc::unbounded, StreamExt};
+use parking_lot::Mutex;
+use rand::Rng;
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
+
+/// Passes messages between the simulated nodes, delaying them by the latency of the scenario
+/// and dropping the ones sent across a partition or to a crashed node.
+pub struct Hub<D> {
+    scenario: Arc<Scenario>,
+    start: Instant,
+    inboxes: Arc<Mutex<Vec<Option<Sender<(D, NodeIndex)>>>>>,
+}
+
+impl<D> Clone for Hub<D> {
+    fn clone(&self) -> Self {
+        Hub {
+            scenario: self.scenario.clone(),
+            start: self.start,
+            inboxes: self.inboxes.clone(),
+        }
+    }
+}
+
+impl<D: Clone + Debug + Send + 'static> Hub<D> {
+    pub fn new(scenario: Arc<Scenario>, start: Instant) -> Self {
+        let inboxes = (0..scenario.nodes).map(|_| None).collect();
+        Hub {
+            scenario,
+            start,
+            inboxes: Arc::new(Mutex::new(inboxes)),
+        }
+    }
+
+    /// The network of the node, replacing the previous one if the node was connected before.
+    pub fn connect(&self, node: NodeIndex) -> Network<D> {
+        let (inbox, inbox_rx) = unbounded();
+        let (outbox, mut outbox_rx) = unbounded();
+        self.inboxes.lock()[node.0] = Some(inbox);
+        let hub = self.clone();
+        tokio::spawn(async move {
+            while let Some((data, recipient)) = outbox_rx.next().await {
+                hub.route(node, recipient, data);
+            }
+        });
+        let peers = (0..self.scenario.nodes).map(NodeIndex).collect();
+        Network::new(inbox_rx, outbox, peers, node)
+    }
+
+    /// Drops all messages to the node from now on, until it connects again.
+    pub fn disconnect(&self, node: NodeIndex) {
+        self.inboxes.lock()[node.0] = None;
+    }
+
+    fn route(&self, sender: NodeIndex, recipient: NodeIndex, data: D) {
+        if !self
+            .scenario
+            .connected(sender.0, recipient.0, self.start.elapsed())
+        {
+            return;
+        }
+        let jitter = match self.scenario.latency.jitter {
+            0 => 0,
+            jitter => rand::thread_rng().gen_range(0..=jitter),
+        };
+        let delay = self.scenario.latency(sender.0, recipient.0) + Duration::from_millis(jitter);
+        let inboxes = self.inboxes.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Some(inbox) = &inboxes.lock()[recipient.0] {
+                // The node might have crashed in the meantime.
+                let _ = inbox.unbounded_send((data, sender));
+            }
+        });
+    }
+}
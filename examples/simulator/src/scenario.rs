@@ -0,0 +1,149 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A simulated run of a committee, read from a TOML file. Times are in seconds since the start
+/// of the run, latencies in milliseconds.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Number of nodes in the committee.
+    pub nodes: usize,
+    /// How long to run the committee for.
+    pub duration: f64,
+    /// Delay between creating consecutive units.
+    #[serde(default = "default_unit_creation_delay")]
+    pub unit_creation_delay: u64,
+    #[serde(default)]
+    pub latency: Latency,
+    #[serde(default)]
+    pub crashes: Vec<Crash>,
+    #[serde(default)]
+    pub partitions: Vec<Partition>,
+}
+
+fn default_unit_creation_delay() -> u64 {
+    200
+}
+
+/// The latency of messages between nodes.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Latency {
+    /// Latency of links without an override.
+    #[serde(default)]
+    pub default: u64,
+    /// Random latency added to every message, between zero and this.
+    #[serde(default)]
+    pub jitter: u64,
+    /// Overrides of the default for messages sent from one node to another.
+    #[serde(default)]
+    pub links: Vec<Link>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Link {
+    pub from: usize,
+    pub to: usize,
+    pub latency: u64,
+}
+
+/// A node stopping at `at`, and starting again from its backup at `recover`, if given.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Crash {
+    pub node: usize,
+    pub at: f64,
+    pub recover: Option<f64>,
+}
+
+/// Between `start` and `end` messages only pass between nodes of the same group, nodes not in
+/// any group are cut off from everyone.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Partition {
+    pub start: f64,
+    pub end: f64,
+    pub groups: Vec<Vec<usize>>,
+}
+
+fn seconds(time: f64) -> Duration {
+    Duration::from_secs_f64(time.max(0.0))
+}
+
+impl Scenario {
+    /// Checks that the scenario refers only to existing nodes and sensible times.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.nodes == 0 {
+            return Err("there has to be at least one node".into());
+        }
+        if self.duration.is_nan() || self.duration <= 0.0 {
+            return Err("the duration has to be positive".into());
+        }
+        let check_node = |node: usize| {
+            if node < self.nodes {
+                Ok(())
+            } else {
+                Err(format!("node {} does not exist", node))
+            }
+        };
+        for link in &self.latency.links {
+            check_node(link.from)?;
+            check_node(link.to)?;
+        }
+        for crash in &self.crashes {
+            check_node(crash.node)?;
+            if crash.recover.map_or(false, |recover| recover < crash.at) {
+                return Err(format!("node {} recovers before crashing", crash.node));
+            }
+        }
+        for partition in &self.partitions {
+            if partition.end < partition.start {
+                return Err("a partition ends before it starts".into());
+            }
+            for node in partition.groups.iter().flatten() {
+                check_node(*node)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn duration(&self) -> Duration {
+        seconds(self.duration)
+    }
+
+    /// The latency of a message from one node to another, without the jitter.
+    pub fn latency(&self, from: usize, to: usize) -> Duration {
+        let millis = self
+            .latency
+            .links
+            .iter()
+            .find(|link| link.from == from && link.to == to)
+            .map_or(self.latency.default, |link| link.latency);
+        Duration::from_millis(millis)
+    }
+
+    /// Whether a message sent at the given time can pass from one node to another.
+    pub fn connected(&self, from: usize, to: usize, at: Duration) -> bool {
+        self.partitions
+            .iter()
+            .filter(|partition| seconds(partition.start) <= at && at < seconds(partition.end))
+            .all(|partition| {
+                partition
+                    .groups
+                    .iter()
+                    .any(|group| group.contains(&from) && group.contains(&to))
+            })
+    }
+
+    /// The crashes, as (node, crash time, recovery time) ordered by the crash time.
+    pub fn crash_schedule(&self) -> Vec<(usize, Duration, Option<Duration>)> {
+        let mut schedule: Vec<_> = self
+            .crashes
+            .iter()
+            .map(|crash| (crash.node, seconds(crash.at), crash.recover.map(seconds)))
+            .collect();
+        schedule.sort_by_key(|(_, at, _)| *at);
+        schedule
+    }
+}
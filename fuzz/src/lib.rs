@@ -1,6 +1,8 @@
 use aleph_bft::{
-    run_session, Config, DelayConfig, LocalIO, Network as NetworkT, NetworkData, NodeCount,
-    NodeIndex, Recipient, SpawnHandle, TaskHandle, Terminator,
+    run_session, AlertLimits, CatchUpSources, Config, DelayConfig, DuplicateUnitPolicy,
+    ForkerDataPolicy, InvariantPolicy, LocalIO, Network as NetworkT, NetworkData, NodeCount,
+    NodeIndex, ParentPolicy, Recipient, RequestConcurrency, ResourceBudget, SessionId, SpawnHandle,
+    TaskHandle, Terminator, TopologyHints, ValidationProfile,
 };
 use aleph_bft_mock::{
     Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, NetworkHook,
@@ -203,13 +205,36 @@ pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
         parent_request_recipients: Arc::new(|_| 1),
         // 50, 50, 50, 50, ...
         newest_request_delay: Arc::new(|_| Duration::from_millis(50)),
+        clock_jump_threshold: Duration::from_millis(1000),
+        shutdown_timeout: Duration::from_secs(1),
     };
     Config {
         node_ix,
-        session_id: 0,
+        session_id: SessionId::from_number(0),
         n_members,
         delay_config,
         max_round: 5000,
+        resource_budget: ResourceBudget::default(),
+        parent_policy: ParentPolicy::default(),
+        fork_alerts: true,
+        alert_limits: AlertLimits::default(),
+        request_concurrency: RequestConcurrency::default(),
+        catch_up_sources: CatchUpSources::default(),
+        topology: TopologyHints::default(),
+        request_ttl: None,
+        history_limit: None,
+        lag_suppression: None,
+        validation: ValidationProfile::default(),
+        forker_data: ForkerDataPolicy::default(),
+        duplicate_units: DuplicateUnitPolicy::default(),
+        quarantine_capacity: 1000,
+        verification_cache_capacity: 10000,
+        verification_threads: 1,
+        invariant_policy: InvariantPolicy::default(),
+        weights: None,
+        signing_purpose: None,
+        addresses: Vec::new(),
+        yield_interval: 100,
     }
 }
 
@@ -227,7 +252,8 @@ pub fn spawn_honest_member_with_config(
     let (exit_tx, exit_rx) = oneshot::channel();
     let spawner_inner = spawner.clone();
     let member_task = async move {
-        run_session(
+        // Fuzzed input might make the session stop with an error.
+        let _ = run_session(
             config,
             local_io,
             network,
@@ -235,7 +261,7 @@ pub fn spawn_honest_member_with_config(
             spawner_inner.clone(),
             Terminator::create_root(exit_rx, "AlephBFT-member"),
         )
-        .await
+        .await;
     };
     spawner.spawn("member", member_task);
     (exit_tx, finalization_rx)
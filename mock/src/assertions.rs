@@ -0,0 +1,64 @@
+//! Checks of the guarantees of AlephBFT on the output of a simulated committee, panicking with a
+//! description of the violation, for reuse in integration tests.
+
+use aleph_bft_types::Round;
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
+
+/// The outputs of honest nodes, e.g. their batches or finalized data items, agree -- every output
+/// is a prefix of the longest one, as nodes might be at different points of the session.
+pub fn assert_agreement<T: PartialEq + Debug>(outputs: &[Vec<T>]) {
+    let longest = match outputs.iter().max_by_key(|output| output.len()) {
+        Some(longest) => longest,
+        None => return,
+    };
+    for (node, output) in outputs.iter().enumerate() {
+        if let Some(position) = output
+            .iter()
+            .zip(longest.iter())
+            .position(|(ours, theirs)| ours != theirs)
+        {
+            panic!(
+                "Output of node {} diverges at position {}: {:?} instead of {:?}.",
+                node, position, output[position], longest[position]
+            );
+        }
+    }
+}
+
+/// No item is output twice, e.g. no data item is finalized in two batches. Meaningful only if the
+/// proposed items are unique, as for the mock data provider.
+pub fn assert_no_duplicates<T: Hash + Eq + Debug>(output: &[T]) {
+    let mut seen = HashSet::new();
+    for (position, item) in output.iter().enumerate() {
+        if !seen.insert(item) {
+            panic!("Item {:?} output again at position {}.", item, position);
+        }
+    }
+}
+
+/// Every proposed item, given together with the round of the unit it was placed in, appears in
+/// one of the batches at most `rounds` after that round. The nth batch is the one with the head
+/// of round n, so an item can be checked once the batches reach `round + rounds`, items proposed
+/// later are skipped.
+pub fn assert_included_within<D: PartialEq + Debug>(
+    batches: &[Vec<D>],
+    proposed: &[(Round, D)],
+    rounds: Round,
+) {
+    for (round, item) in proposed {
+        let deadline = *round as usize + rounds as usize;
+        if deadline >= batches.len() {
+            continue;
+        }
+        if !batches[..=deadline]
+            .iter()
+            .flatten()
+            .any(|data| data == item)
+        {
+            panic!(
+                "Item {:?} proposed in round {} is not included within {} rounds.",
+                item, round, rounds
+            );
+        }
+    }
+}
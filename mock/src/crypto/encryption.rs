@@ -0,0 +1,66 @@
+use crate::crypto::Keychain;
+use aleph_bft_types::{
+    Index, Keychain as KeychainT, NodeCount, NodeIndex, NodeMap, ThresholdEncryption,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+
+// The bytes are only disguised, anyone can decrypt them.
+const MASK: u8 = 0x5a;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode, Decode)]
+pub struct Ciphertext(Vec<u8>);
+
+#[derive(Clone, Eq, PartialEq, Debug, Encode, Decode)]
+pub struct DecryptionShare {
+    ciphertext: Ciphertext,
+    index: NodeIndex,
+}
+
+#[async_trait]
+impl ThresholdEncryption for Keychain {
+    type Ciphertext = Ciphertext;
+    type DecryptionShare = DecryptionShare;
+
+    fn node_count(&self) -> NodeCount {
+        KeychainT::node_count(self)
+    }
+
+    fn threshold(&self) -> NodeCount {
+        KeychainT::node_count(self) / 3 + NodeCount(1)
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Self::Ciphertext {
+        Ciphertext(plaintext.iter().map(|byte| byte ^ MASK).collect())
+    }
+
+    async fn decryption_share(&self, ciphertext: &Self::Ciphertext) -> Self::DecryptionShare {
+        DecryptionShare {
+            ciphertext: ciphertext.clone(),
+            index: self.index(),
+        }
+    }
+
+    fn verify_share(
+        &self,
+        ciphertext: &Self::Ciphertext,
+        share: &Self::DecryptionShare,
+        index: NodeIndex,
+    ) -> bool {
+        index.0 < KeychainT::node_count(self).0
+            && share.index == index
+            && &share.ciphertext == ciphertext
+    }
+
+    fn combine(
+        &self,
+        ciphertext: &Self::Ciphertext,
+        shares: &NodeMap<Self::DecryptionShare>,
+    ) -> Option<Vec<u8>> {
+        let enough = shares.item_count() >= self.threshold().0
+            && shares
+                .iter()
+                .all(|(index, share)| self.verify_share(ciphertext, share, index));
+        enough.then(|| ciphertext.0.iter().map(|byte| byte ^ MASK).collect())
+    }
+}
@@ -1,9 +1,10 @@
 use crate::crypto::{PartialMultisignature, Signature};
 use aleph_bft_types::{
     Index, Keychain as KeychainT, MultiKeychain as MultiKeychainT, NodeCount, NodeIndex,
-    PartialMultisignature as PartialMultisignatureT, SignatureSet,
+    PartialMultisignature as PartialMultisignatureT, SignatureSet, Vrf,
 };
 use async_trait::async_trait;
+use codec::Encode;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct Keychain {
@@ -21,10 +22,6 @@ impl Keychain {
             .map(|i| Self::new(node_count, i.into()))
             .collect()
     }
-
-    fn quorum(&self) -> usize {
-        2 * self.count.0 / 3 + 1
-    }
 }
 
 impl Index for Keychain {
@@ -50,6 +47,17 @@ impl KeychainT for Keychain {
     }
 }
 
+#[async_trait]
+impl Vrf for Keychain {
+    async fn prove(&self, input: &[u8]) -> Vec<u8> {
+        (input, self.index).encode()
+    }
+
+    fn verify(&self, input: &[u8], proof: &[u8], index: NodeIndex) -> bool {
+        proof == (input, index).encode()
+    }
+}
+
 impl MultiKeychainT for Keychain {
     type PartialMultisignature = PartialMultisignature;
 
@@ -61,9 +69,14 @@ impl MultiKeychainT for Keychain {
         SignatureSet::add_signature(SignatureSet::with_size(self.node_count()), signature, index)
     }
 
-    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
         let signature_count = partial.iter().count();
-        if signature_count < self.quorum() {
+        if signature_count < threshold.0 {
             return false;
         }
         partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
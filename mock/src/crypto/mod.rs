@@ -1,9 +1,11 @@
+mod encryption;
 mod keychain;
 mod signable;
 mod signature;
 mod wrappers;
 
+pub use encryption::{Ciphertext, DecryptionShare};
 pub use keychain::Keychain;
 pub use signable::Signable;
 pub use signature::{PartialMultisignature, Signature};
-pub use wrappers::BadSigning;
+pub use wrappers::{BadSigning, Flakiness, FlakyKeyBox};
@@ -1,10 +1,19 @@
 use crate::crypto::{PartialMultisignature, Signature};
 use aleph_bft_types::{
-    Index, Keychain as KeychainT, MultiKeychain as MultiKeychainT, NodeCount, NodeIndex,
+    Index, KeyEpoch, Keychain as KeychainT, MultiKeychain as MultiKeychainT, NodeCount, NodeIndex,
 };
 use async_trait::async_trait;
 use codec::{Decode, Encode};
-use std::fmt::Debug;
+use futures_timer::Delay;
+use rand::Rng;
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 pub trait MK:
     KeychainT<Signature = Signature> + MultiKeychainT<PartialMultisignature = PartialMultisignature>
@@ -52,6 +61,14 @@ impl<T: MK> KeychainT for BadSigning<T> {
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
         self.0.verify(msg, sgn, index)
     }
+
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.0.has_key(index)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        Self(self.0.at_epoch(epoch))
+    }
 }
 
 impl<T: MK> MultiKeychainT for BadSigning<T> {
@@ -68,4 +85,159 @@ impl<T: MK> MultiKeychainT for BadSigning<T> {
     fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
         self.0.is_complete(msg, partial)
     }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        self.0.is_complete_with_threshold(msg, partial, threshold)
+    }
+}
+
+/// How a [`FlakyKeyBox`] misbehaves. All the rates are probabilities, the failure rate has to be
+/// below one, otherwise signing never succeeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flakiness {
+    /// Signing takes a random time up to this long.
+    pub max_delay: Duration,
+    /// How likely an attempt to sign fails, e.g. because a remote signer is unavailable, after
+    /// which signing is retried.
+    pub failure_rate: f64,
+    /// How long to wait before retrying a failed attempt.
+    pub retry_delay: Duration,
+    /// How likely a signature is wrong, i.e. a signature of a different message.
+    pub wrong_signature_rate: f64,
+}
+
+impl Default for Flakiness {
+    fn default() -> Self {
+        Flakiness {
+            max_delay: Duration::ZERO,
+            failure_rate: 0.0,
+            retry_delay: Duration::from_millis(10),
+            wrong_signature_rate: 0.0,
+        }
+    }
+}
+
+const WRONG_SIGNATURE_PREFIX: &[u8] = b"FLAKY";
+
+fn happens(probability: f64) -> bool {
+    probability > 0.0 && rand::thread_rng().gen_bool(probability)
+}
+
+/// Keychain wrapper injecting delays, transient failures and wrong signatures into signing, for
+/// testing how signers misbehaving in realistic ways are dealt with. Verification is left to the
+/// wrapped keychain. Clones share the counters of injected faults.
+#[derive(Clone, Debug)]
+pub struct FlakyKeyBox<K: KeychainT> {
+    keychain: K,
+    flakiness: Flakiness,
+    failures: Arc<AtomicUsize>,
+    wrong_signatures: Arc<AtomicUsize>,
+}
+
+impl<K: KeychainT> FlakyKeyBox<K> {
+    pub fn new(keychain: K, flakiness: Flakiness) -> Self {
+        FlakyKeyBox {
+            keychain,
+            flakiness,
+            failures: Arc::new(AtomicUsize::new(0)),
+            wrong_signatures: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// How many attempts to sign failed so far.
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// How many wrong signatures were made so far.
+    pub fn wrong_signatures(&self) -> usize {
+        self.wrong_signatures.load(Ordering::Relaxed)
+    }
+}
+
+impl<K: KeychainT> Index for FlakyKeyBox<K> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+#[async_trait]
+impl<K: KeychainT> KeychainT for FlakyKeyBox<K> {
+    type Signature = K::Signature;
+
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        let max_delay = self.flakiness.max_delay.as_millis() as u64;
+        if max_delay > 0 {
+            let delay = rand::thread_rng().gen_range(0..=max_delay);
+            Delay::new(Duration::from_millis(delay)).await;
+        }
+        while happens(self.flakiness.failure_rate) {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+            Delay::new(self.flakiness.retry_delay).await;
+        }
+        if happens(self.flakiness.wrong_signature_rate) {
+            self.wrong_signatures.fetch_add(1, Ordering::Relaxed);
+            return self
+                .keychain
+                .sign(&[WRONG_SIGNATURE_PREFIX, msg].concat())
+                .await;
+        }
+        self.keychain.sign(msg).await
+    }
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.keychain.verify(msg, sgn, index)
+    }
+
+    fn verify_batch(&self, items: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        self.keychain.verify_batch(items)
+    }
+
+    fn has_key(&self, index: NodeIndex) -> bool {
+        self.keychain.has_key(index)
+    }
+
+    fn at_epoch(&self, epoch: KeyEpoch) -> Self {
+        FlakyKeyBox {
+            keychain: self.keychain.at_epoch(epoch),
+            flakiness: self.flakiness.clone(),
+            failures: self.failures.clone(),
+            wrong_signatures: self.wrong_signatures.clone(),
+        }
+    }
+}
+
+impl<K: MultiKeychainT> MultiKeychainT for FlakyKeyBox<K> {
+    type PartialMultisignature = K::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain.bootstrap_multi(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.keychain.is_complete(msg, partial)
+    }
+
+    fn is_complete_with_threshold(
+        &self,
+        msg: &[u8],
+        partial: &Self::PartialMultisignature,
+        threshold: NodeCount,
+    ) -> bool {
+        self.keychain
+            .is_complete_with_threshold(msg, partial, threshold)
+    }
 }
@@ -1,12 +1,17 @@
 //! Mock implementations of required traits. Do NOT use outside of testing!
 
+mod assertions;
 mod crypto;
 mod dataio;
 mod hasher;
 mod network;
 mod spawner;
 
-pub use crypto::{BadSigning, Keychain, PartialMultisignature, Signable, Signature};
+pub use assertions::{assert_agreement, assert_included_within, assert_no_duplicates};
+pub use crypto::{
+    BadSigning, Ciphertext, DecryptionShare, Flakiness, FlakyKeyBox, Keychain,
+    PartialMultisignature, Signable, Signature,
+};
 pub use dataio::{Data, DataProvider, FinalizationHandler, Loader, Saver, StalledDataProvider};
 pub use hasher::{Hash64, Hasher64};
 pub use network::{
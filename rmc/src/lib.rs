@@ -8,7 +8,7 @@ use codec::{Decode, Encode};
 use core::fmt::Debug;
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
-    FutureExt, StreamExt,
+    pin_mut, FutureExt, StreamExt,
 };
 use futures_timer::Delay;
 use log::{debug, warn};
@@ -324,6 +324,40 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
             }
         }
     }
+
+    /// Starts rmc for every hash received from `hashes` and sends every collected multisigned
+    /// hash, ours or of other nodes, to `multisigned_hashes`. Ends when either channel is closed,
+    /// after which this node stops helping others complete their multicasts, so `hashes` should
+    /// be kept open for as long as the multicasts matter.
+    pub async fn run(
+        mut self,
+        mut hashes: UnboundedReceiver<H>,
+        multisigned_hashes: UnboundedSender<Multisigned<H, MK>>,
+    ) {
+        loop {
+            let event = {
+                let multisigned_hash = self.next_multisigned_hash().fuse();
+                pin_mut!(multisigned_hash);
+                futures::select! {
+                    hash = hashes.next() => Err(hash),
+                    multisigned_hash = multisigned_hash => Ok(multisigned_hash),
+                }
+            };
+            match event {
+                Err(Some(hash)) => self.start_rmc(hash).await,
+                Err(None) => {
+                    debug!(target: "AlephBFT-rmc", "Hashes channel closed, ending");
+                    return;
+                }
+                Ok(multisigned_hash) => {
+                    if multisigned_hashes.unbounded_send(multisigned_hash).is_err() {
+                        debug!(target: "AlephBFT-rmc", "Multisigned hashes receiver dropped, ending");
+                        return;
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -468,6 +502,54 @@ mod tests {
         }
     }
 
+    /// Run every rmc on channels and send the hash to each of them.
+    #[tokio::test]
+    async fn runs_on_channels() {
+        let node_count = NodeCount(7);
+        let keychains = Keychain::new_vec(node_count);
+        let TestData { mut network, rmcs } = TestData::new(node_count, &keychains, |_, _| true);
+
+        let hash: Signable = "73".into();
+        let mut hashes_txs = Vec::new();
+        let mut multisigned_rxs = Vec::new();
+        let runs: Vec<_> = rmcs
+            .into_iter()
+            .map(|rmc| {
+                let (hashes_tx, hashes_rx) = unbounded();
+                let (multisigned_tx, multisigned_rx) = unbounded();
+                hashes_tx
+                    .unbounded_send(hash.clone())
+                    .expect("Channel should be open");
+                hashes_txs.push(hashes_tx);
+                multisigned_rxs.push(multisigned_rx);
+                rmc.run(hashes_rx, multisigned_tx)
+            })
+            .collect();
+        let collect = async {
+            let mut multisigned_hashes = Vec::new();
+            for rx in multisigned_rxs.iter_mut() {
+                multisigned_hashes.push(rx.next().await.expect("Rmc should be running"));
+            }
+            multisigned_hashes
+        };
+
+        tokio::select! {
+            multisigned_hashes = collect => {
+                assert_eq!(multisigned_hashes.len(), node_count.0);
+                for multisigned in multisigned_hashes {
+                    assert_eq!(multisigned.as_signable(), &hash);
+                }
+            }
+            _ = future::join_all(runs) => {
+                panic!("rmc ended unexpectedly");
+            }
+            _ = network.run() => {
+                panic!("network ended unexpectedly");
+            }
+        }
+        drop(hashes_txs);
+    }
+
     /// Each message is delivered with 20% probability
     #[tokio::test]
     async fn faulty_network() {
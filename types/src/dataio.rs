@@ -1,3 +1,4 @@
+use aleph_bft_crypto::NodeIndex;
 use async_trait::async_trait;
 
 /// The source of data items that consensus should order.
@@ -10,6 +11,15 @@ use async_trait::async_trait;
 pub trait DataProvider<Data>: Sync + Send + 'static {
     /// Outputs a new data item to be ordered
     async fn get_data(&mut self) -> Option<Data>;
+
+    /// Outputs at most `max_items` new data items to be ordered together, taking at most
+    /// `max_bytes` when encoded, unless they consist of a single item. Used for packing several
+    /// items into a single unit, see `MultiDataProvider` in the consensus crate.
+    /// By default this returns the single item output by [`DataProvider::get_data`], if any.
+    async fn get_data_items(&mut self, max_items: usize, max_bytes: usize) -> Vec<Data> {
+        let _ = (max_items, max_bytes);
+        self.get_data().await.into_iter().collect()
+    }
 }
 
 /// The source of finalization of the units that consensus produces.
@@ -20,4 +30,20 @@ pub trait FinalizationHandler<Data>: Sync + Send + 'static {
     /// Data, provided by [DataProvider::get_data], has been finalized.
     /// The calls to this function follow the order of finalization.
     fn data_finalized(&mut self, data: Data);
+
+    /// A batch of data, i.e. the data of all units ordered at once, has been finalized. Batches
+    /// might be empty, if none of their units contained data.
+    /// By default every piece of data is passed to [`FinalizationHandler::data_finalized`].
+    fn batch_finalized(&mut self, batch: Vec<Data>) {
+        for data in batch {
+            self.data_finalized(data);
+        }
+    }
+
+    /// The units of a batch have been finalized, given in order together with their creators.
+    /// Unlike in [`FinalizationHandler::batch_finalized`] units without data are included.
+    /// By default the data is passed to [`FinalizationHandler::batch_finalized`].
+    fn units_finalized(&mut self, units: Vec<(NodeIndex, Option<Data>)>) {
+        self.batch_finalized(units.into_iter().filter_map(|(_, data)| data).collect());
+    }
 }
@@ -4,13 +4,24 @@ mod dataio;
 mod network;
 mod tasks;
 
+#[cfg(feature = "bls")]
+pub use aleph_bft_crypto::bls;
+#[cfg(feature = "ed25519")]
+pub use aleph_bft_crypto::ed25519;
+#[cfg(feature = "testing")]
+pub use aleph_bft_crypto::testing;
 pub use aleph_bft_crypto::{
-    IncompleteMultisignatureError, Index, Indexed, Keychain, MultiKeychain, Multisigned, NodeCount,
-    NodeIndex, NodeMap, NodeSubset, PartialMultisignature, PartiallyMultisigned, Signable,
-    Signature, SignatureError, SignatureSet, Signed, UncheckedSigned,
+    check_keybox_conformance, verify_certificate, AddSignatureError, AggregatingMultiKeychain,
+    CachingMultiKeychain, Certificate, CertificateError, CompactEncoding, CompactMultisignature,
+    ConformanceError, DomainKeychain, DualKeychain, DualScheme, DualSignature, EpochKeychain,
+    IncompleteMultisignatureError, Index, Indexed, KeyEpoch, Keychain, MultiKeychain, Multisigned,
+    NodeCount, NodeIndex, NodeMap, NodeSubset, PartialMultisignature, PartiallyMultisigned,
+    PublicVerification, Quarantine, SessionId, Signable, Signature, SignatureError, SignatureSet,
+    Signed, StreamingVerification, ThresholdEncryption, UncheckedSigned, VerificationCache,
+    VerificationStatus, Vrf, WeightedMultiKeychain,
 };
 pub use dataio::{DataProvider, FinalizationHandler};
-pub use network::{Network, Recipient};
+pub use network::{AddressedRawNetwork, Network, RawNetwork, Recipient};
 pub use tasks::{SpawnHandle, TaskHandle};
 
 use codec::Codec;
@@ -23,14 +34,52 @@ impl<T> Data for T where T: Eq + Clone + Send + Sync + Debug + StdHash + Codec +
 
 /// A hasher, used for creating identifiers for blocks or units.
 pub trait Hasher: Eq + Clone + Send + Sync + Debug + 'static {
-    /// A hash, as an identifier for a block or unit.
+    /// A hash, as an identifier for a block or unit. The same fixed-size hash identifies units,
+    /// makes up control hashes and is signed in place of messages, and is used as a key of maps
+    /// without being hashed again, so it should be a cryptographic hash.
     type Hash: AsRef<[u8]> + Eq + Ord + Copy + Clone + Send + Sync + Debug + StdHash + Codec;
 
     fn hash(s: &[u8]) -> Self::Hash;
 }
 
-/// The number of a session for which the consensus is run.
-pub type SessionId = u64;
+#[doc(hidden)]
+pub mod __private {
+    pub use codec::Encode;
+}
+
+/// Implements [`Signable`] for a type by its encoding, so signing it takes one line. Given a
+/// [`Hasher`], the hash of the encoding is signed instead of the whole encoding:
+/// ```ignore
+/// #[derive(Encode)]
+/// struct Transfer {
+///     amount: u64,
+/// }
+///
+/// signable_by_encoding!(Transfer);
+/// // Or, to sign hashes:
+/// signable_by_encoding!(Transfer, Blake2b);
+/// ```
+#[macro_export]
+macro_rules! signable_by_encoding {
+    ($signable:ty) => {
+        impl $crate::Signable for $signable {
+            type Hash = ::std::vec::Vec<u8>;
+
+            fn hash(&self) -> Self::Hash {
+                $crate::__private::Encode::encode(self)
+            }
+        }
+    };
+    ($signable:ty, $hasher:ty) => {
+        impl $crate::Signable for $signable {
+            type Hash = <$hasher as $crate::Hasher>::Hash;
+
+            fn hash(&self) -> Self::Hash {
+                <$hasher as $crate::Hasher>::hash(&$crate::__private::Encode::encode(self))
+            }
+        }
+    };
+}
 
 /// An asynchronous round of the protocol.
 pub type Round = u16;
@@ -41,3 +41,38 @@ pub trait Network<D>: Send {
     /// Receive a message from the network.
     async fn next_event(&mut self) -> Option<D>;
 }
+
+/// RawNetwork represents a lower-level interface for sending and receiving opaque byte messages.
+///
+/// It is meant for integrators that already have a byte-oriented transport (message queues,
+/// custom UDP, etc.). Every call to [`RawNetwork::send`] corresponds to exactly one frame that
+/// should be delivered as a single item by [`RawNetwork::next_event`] on the receiving side.
+/// Encoding, versioning and session-tagging of the frames is handled by AlephBFT, the implementation
+/// only has to move the bytes around. The same remarks about rate control and reliability as for
+/// [`Network`] apply.
+#[async_trait::async_trait]
+pub trait RawNetwork: Send {
+    /// Send a frame to a single node or everyone, depending on the value of the recipient
+    /// argument.
+    ///
+    /// Note on the implementation: this function should be implemented in a non-blocking manner.
+    fn send(&self, frame: Vec<u8>, recipient: Recipient);
+    /// Receive a frame from the network.
+    async fn next_event(&mut self) -> Option<Vec<u8>>;
+}
+
+/// AddressedRawNetwork is a [`RawNetwork`] that also reports the transport address every frame
+/// arrived from, e.g. a socket address or a peer id.
+///
+/// It is meant for transports that know where their traffic comes from, so that it can be
+/// filtered by address before any decoding takes place.
+#[async_trait::async_trait]
+pub trait AddressedRawNetwork<A>: Send {
+    /// Send a frame to a single node or everyone, depending on the value of the recipient
+    /// argument.
+    ///
+    /// Note on the implementation: this function should be implemented in a non-blocking manner.
+    fn send(&self, frame: Vec<u8>, recipient: Recipient);
+    /// Receive a frame from the network, together with the address of its sender.
+    async fn next_event(&mut self) -> Option<(A, Vec<u8>)>;
+}